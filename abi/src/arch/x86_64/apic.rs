@@ -177,12 +177,24 @@ pub const LAPIC_TIMER_DIV_16: u32 = 0x3;
 /// Fixed delivery mode (bits 8-10 = 000).
 pub const LAPIC_ICR_DELIVERY_FIXED: u32 = 0 << 8;
 
+/// INIT delivery mode (bits 8-10 = 101).
+pub const LAPIC_ICR_DELIVERY_INIT: u32 = 5 << 8;
+
+/// Start-up (SIPI) delivery mode (bits 8-10 = 110).
+pub const LAPIC_ICR_DELIVERY_STARTUP: u32 = 6 << 8;
+
 /// Physical destination mode (bit 11 = 0).
 pub const LAPIC_ICR_DEST_PHYSICAL: u32 = 0 << 11;
 
 /// Assert interrupt level (bit 14 = 1).
 pub const LAPIC_ICR_LEVEL_ASSERT: u32 = 1 << 14;
 
+/// Deassert interrupt level (bit 14 = 0), for the INIT level de-assert.
+pub const LAPIC_ICR_LEVEL_DEASSERT: u32 = 0 << 14;
+
+/// Level-triggered (bit 15 = 1).
+pub const LAPIC_ICR_TRIGGER_LEVEL: u32 = 1 << 15;
+
 /// Edge-triggered (bit 15 = 0).
 pub const LAPIC_ICR_TRIGGER_EDGE: u32 = 0 << 15;
 
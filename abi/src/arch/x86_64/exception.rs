@@ -12,6 +12,13 @@ pub fn exception_is_critical(vector: u8) -> bool {
     )
 }
 
+/// NMI and machine check: hardware reporting trouble the kernel cannot
+/// repair. Either can fire again while being handled, so they get a
+/// minimal log-and-halt path instead of the full fatal dump.
+pub fn is_nonrecoverable_exception(vector: u8) -> bool {
+    matches!(vector, EXCEPTION_MACHINE_CHECK | EXCEPTION_NMI)
+}
+
 pub fn get_exception_name(vector: u8) -> &'static str {
     match vector {
         0 => "Divide Error",
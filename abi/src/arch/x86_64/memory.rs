@@ -78,6 +78,13 @@ pub const PROCESS_HEAP_START_VA: u64 = 0x0000_0000_0100_0000;
 /// Process heap maximum virtual address.
 pub const PROCESS_HEAP_MAX_VA: u64 = 0x0000_0000_4000_0000;
 
+/// Start of the window for anonymous `mmap` regions, above the heap limit.
+pub const PROCESS_MMAP_START_VA: u64 = 0x0000_0010_0000_0000;
+
+/// End (exclusive) of the anonymous `mmap` window, where shared memory
+/// mappings begin.
+pub const PROCESS_MMAP_END_VA: u64 = 0x0000_7000_0000_0000;
+
 /// Process stack top virtual address.
 pub const PROCESS_STACK_TOP_VA: u64 = 0x0000_7FFF_FF00_0000;
 
@@ -107,6 +107,52 @@ pub const PCI_COMMAND_SPECIAL_CYCLES: u16 = 0x0008;
 /// Disable interrupts (bit 10).
 pub const PCI_COMMAND_INTERRUPT_DISABLE: u16 = 0x0400;
 
+// =============================================================================
+// Capabilities
+// =============================================================================
+
+/// Status register bit: the capability list at `PCI_CAP_PTR_OFFSET` is valid.
+pub const PCI_STATUS_CAP_LIST: u16 = 0x0010;
+
+/// Offset of the first capability pointer (8-bit).
+pub const PCI_CAP_PTR_OFFSET: u8 = 0x34;
+
+/// Capability ID: Message Signalled Interrupts.
+pub const PCI_CAP_ID_MSI: u8 = 0x05;
+
+/// Capability ID: MSI-X.
+pub const PCI_CAP_ID_MSIX: u8 = 0x11;
+
+/// MSI message control: MSI enable (bit 0).
+pub const PCI_MSI_CTRL_ENABLE: u16 = 0x0001;
+
+/// MSI message control: multiple message enable field (bits 6:4).
+pub const PCI_MSI_CTRL_MULTI_ENABLE_MASK: u16 = 0x0070;
+
+/// MSI message control: 64-bit message address capable (bit 7).
+pub const PCI_MSI_CTRL_64BIT: u16 = 0x0080;
+
+/// MSI-X message control: table size minus one (bits 10:0).
+pub const PCI_MSIX_CTRL_TABLE_SIZE_MASK: u16 = 0x07FF;
+
+/// MSI-X message control: mask every vector (bit 14).
+pub const PCI_MSIX_CTRL_FUNCTION_MASK: u16 = 0x4000;
+
+/// MSI-X message control: MSI-X enable (bit 15).
+pub const PCI_MSIX_CTRL_ENABLE: u16 = 0x8000;
+
+/// MSI-X table/PBA register: BAR indicator (bits 2:0); the rest is the offset.
+pub const PCI_MSIX_BIR_MASK: u32 = 0x7;
+
+/// Size of one MSI-X table entry in bytes.
+pub const PCI_MSIX_ENTRY_SIZE: usize = 16;
+
+/// MSI-X entry vector control: vector masked (bit 0).
+pub const PCI_MSIX_ENTRY_CTRL_MASKED: u32 = 0x1;
+
+/// Base of the x86 MSI address window; the destination APIC ID goes in 19:12.
+pub const PCI_MSI_ADDRESS_BASE: u64 = 0xFEE0_0000;
+
 // =============================================================================
 // Device Classes
 // =============================================================================
@@ -0,0 +1,106 @@
+//! Window composition into an arbitrary pixel buffer
+//!
+//! The compositor copies each window's pixels onto its output, converting
+//! formats and skipping color-keyed pixels. Keeping the routine independent
+//! of where the output lives lets the same code render to the framebuffer,
+//! a screenshot or thumbnail buffer, or an in-memory buffer under test.
+
+use crate::draw::pixel_ops;
+use crate::pixel::PixelFormat;
+use crate::surface::SurfaceBlendMode;
+
+/// Output buffer windows are composited onto.
+pub struct CompositeTarget<'a> {
+    pub data: &'a mut [u8],
+    pub width: u32,
+    pub height: u32,
+    /// Bytes per row; at least `width * bytes_per_pixel`
+    pub pitch: usize,
+    pub format: PixelFormat,
+}
+
+/// One window's pixels and where they go on the target.
+pub struct CompositeSource<'a> {
+    /// Tightly packed rows of `width` pixels
+    pub data: &'a [u8],
+    pub format: PixelFormat,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Source pixels of this color are left out
+    pub color_key: Option<u32>,
+    pub blend_mode: SurfaceBlendMode,
+}
+
+impl CompositeSource<'_> {
+    /// Whether every drawn pixel fully hides what is beneath it, so the
+    /// covered area can be culled from windows lower in the stack.
+    #[inline]
+    pub fn is_opaque(&self) -> bool {
+        self.color_key.is_none()
+            && (self.blend_mode == SurfaceBlendMode::Opaque || !self.format.has_alpha())
+    }
+}
+
+impl CompositeTarget<'_> {
+    /// Copy `src` onto the target, clipped to the target's bounds.
+    ///
+    /// Rows that would run past either buffer are skipped rather than
+    /// truncated, so a short source buffer never reads out of bounds.
+    pub fn blit(&mut self, src: &CompositeSource<'_>) {
+        let x0 = src.x.max(0);
+        let y0 = src.y.max(0);
+        let x1 = src
+            .x
+            .saturating_add(src.width as i32)
+            .min(self.width as i32);
+        let y1 = src
+            .y
+            .saturating_add(src.height as i32)
+            .min(self.height as i32);
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        let src_bpp = src.format.bytes_per_pixel() as usize;
+        let dst_bpp = self.format.bytes_per_pixel() as usize;
+        let src_pitch = src.width as usize * src_bpp;
+        let src_x = (x0 - src.x) as usize;
+        let src_y = (y0 - src.y) as usize;
+        let pixels = (x1 - x0) as usize;
+        // Opaque sources keep the straight row copy
+        let blend = src.blend_mode == SurfaceBlendMode::AlphaBlend && src.format.has_alpha();
+
+        for row in 0..(y1 - y0) as usize {
+            let src_off = (src_y + row) * src_pitch + src_x * src_bpp;
+            let dst_off = (y0 as usize + row) * self.pitch + x0 as usize * dst_bpp;
+            let src_end = src_off + pixels * src_bpp;
+            let dst_end = dst_off + pixels * dst_bpp;
+            if src_end > src.data.len() || dst_end > self.data.len() {
+                continue;
+            }
+            let (dst_row, src_row) = (
+                &mut self.data[dst_off..dst_end],
+                &src.data[src_off..src_end],
+            );
+            if blend {
+                pixel_ops::blend_row_keyed(
+                    dst_row,
+                    self.format,
+                    src_row,
+                    src.format,
+                    src.color_key,
+                );
+            } else {
+                pixel_ops::convert_row_keyed(
+                    dst_row,
+                    self.format,
+                    src_row,
+                    src.format,
+                    src.color_key,
+                );
+            }
+        }
+    }
+}
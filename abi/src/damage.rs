@@ -65,6 +65,13 @@ impl DamageRect {
         self.union(other).area()
     }
 
+    /// Area that merging with another rect would invalidate beyond the two
+    /// rects themselves (combined area minus the sum of both areas)
+    #[inline]
+    pub fn wasted_area(&self, other: &Self) -> i32 {
+        self.combined_area(other) - self.area() - other.area()
+    }
+
     /// Clip this rect to buffer bounds
     #[inline]
     pub fn clip(&self, width: i32, height: i32) -> Self {
@@ -81,6 +88,31 @@ impl DamageRect {
     pub fn intersects(&self, other: &Self) -> bool {
         self.x0 <= other.x1 && self.x1 >= other.x0 && self.y0 <= other.y1 && self.y1 >= other.y0
     }
+
+    /// Check if this rect overlaps another or shares an edge with it.
+    ///
+    /// Rects that only meet at a corner do not count, since their union
+    /// would cover two empty quadrants.
+    #[inline]
+    pub fn touches(&self, other: &Self) -> bool {
+        let x_overlap = self.x0 <= other.x1 && self.x1 >= other.x0;
+        let y_overlap = self.y0 <= other.y1 && self.y1 >= other.y0;
+        let x_abut = self.x0 <= other.x1.saturating_add(1) && self.x1.saturating_add(1) >= other.x0;
+        let y_abut = self.y0 <= other.y1.saturating_add(1) && self.y1.saturating_add(1) >= other.y0;
+        (x_overlap && y_abut) || (x_abut && y_overlap)
+    }
+}
+
+/// Heuristic used to pick which pair of regions to merge when a tracker is
+/// at capacity.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Merge the pair whose bounding box is smallest
+    #[default]
+    SmallestArea,
+    /// Merge the pair whose bounding box adds the least area beyond the two
+    /// rects, favouring neighbouring rects over distant ones
+    LeastWaste,
 }
 
 /// Generic damage tracker with configurable capacity.
@@ -93,6 +125,7 @@ pub struct DamageTracker<const N: usize = MAX_DAMAGE_REGIONS> {
     count: u8,
     /// Set when damage exceeds capacity - means entire surface is dirty
     full_damage: bool,
+    strategy: MergeStrategy,
 }
 
 impl<const N: usize> Default for DamageTracker<N> {
@@ -104,17 +137,37 @@ impl<const N: usize> Default for DamageTracker<N> {
 impl<const N: usize> DamageTracker<N> {
     /// Create an empty damage tracker
     pub const fn new() -> Self {
+        Self::with_strategy(MergeStrategy::SmallestArea)
+    }
+
+    /// Create an empty damage tracker using the given merge heuristic
+    pub const fn with_strategy(strategy: MergeStrategy) -> Self {
         Self {
             regions: [DamageRect::invalid(); N],
             count: 0,
             full_damage: false,
+            strategy,
         }
     }
 
+    /// Get the merge heuristic used when at capacity
+    #[inline]
+    pub fn merge_strategy(&self) -> MergeStrategy {
+        self.strategy
+    }
+
+    /// Change the merge heuristic used when at capacity
+    #[inline]
+    pub fn set_merge_strategy(&mut self, strategy: MergeStrategy) {
+        self.strategy = strategy;
+    }
+
     /// Add a damage region.
     ///
-    /// When at capacity, uses `merge_smallest_pair()` to make room.
-    /// This is the default strategy suitable for most use cases.
+    /// A rect that overlaps or shares an edge with an existing region is
+    /// unioned into it, and any regions the grown rect now touches are folded
+    /// in as well. Otherwise, when at capacity, uses `merge_best_pair()` to
+    /// make room according to the tracker's `MergeStrategy`.
     pub fn add(&mut self, rect: DamageRect) {
         if !rect.is_valid() {
             return;
@@ -124,8 +177,16 @@ impl<const N: usize> DamageTracker<N> {
             return;
         }
 
+        for i in 0..(self.count as usize) {
+            if self.regions[i].touches(&rect) {
+                self.regions[i] = self.regions[i].union(&rect);
+                self.merge_all(DamageRect::touches);
+                return;
+            }
+        }
+
         if (self.count as usize) >= N {
-            self.merge_smallest_pair();
+            self.merge_best_pair();
         }
 
         if (self.count as usize) < N {
@@ -155,7 +216,7 @@ impl<const N: usize> DamageTracker<N> {
         for i in 0..(self.count as usize) {
             if self.regions[i].intersects(&rect) {
                 self.regions[i] = self.regions[i].union(&rect);
-                self.merge_all_overlapping();
+                self.merge_all(DamageRect::intersects);
                 return;
             }
         }
@@ -176,8 +237,8 @@ impl<const N: usize> DamageTracker<N> {
         self.add(DamageRect { x0, y0, x1, y1 });
     }
 
-    /// Merge the pair of regions with smallest combined area
-    fn merge_smallest_pair(&mut self) {
+    /// Merge the pair of regions that scores lowest under the merge strategy
+    fn merge_best_pair(&mut self) {
         if self.count < 2 {
             return;
         }
@@ -185,13 +246,16 @@ impl<const N: usize> DamageTracker<N> {
         let count = self.count as usize;
         let mut best_i = 0;
         let mut best_j = 1;
-        let mut best_area = i32::MAX;
+        let mut best_cost = i32::MAX;
 
         for i in 0..count {
             for j in (i + 1)..count {
-                let combined = self.regions[i].combined_area(&self.regions[j]);
-                if combined < best_area {
-                    best_area = combined;
+                let cost = match self.strategy {
+                    MergeStrategy::SmallestArea => self.regions[i].combined_area(&self.regions[j]),
+                    MergeStrategy::LeastWaste => self.regions[i].wasted_area(&self.regions[j]),
+                };
+                if cost < best_cost {
+                    best_cost = cost;
                     best_i = i;
                     best_j = j;
                 }
@@ -205,8 +269,8 @@ impl<const N: usize> DamageTracker<N> {
         self.count -= 1;
     }
 
-    /// Merge all overlapping regions to reduce count
-    fn merge_all_overlapping(&mut self) {
+    /// Merge every pair of regions matching `mergeable` to reduce count
+    fn merge_all(&mut self, mergeable: fn(&DamageRect, &DamageRect) -> bool) {
         if self.count <= 1 {
             return;
         }
@@ -215,7 +279,7 @@ impl<const N: usize> DamageTracker<N> {
         while i < self.count as usize {
             let mut j = i + 1;
             while j < self.count as usize {
-                if self.regions[i].intersects(&self.regions[j]) {
+                if mergeable(&self.regions[i], &self.regions[j]) {
                     self.regions[i] = self.regions[i].union(&self.regions[j]);
                     // Remove region j by swapping with last
                     self.count -= 1;
@@ -248,6 +312,15 @@ impl<const N: usize> DamageTracker<N> {
         &self.regions[..self.count as usize]
     }
 
+    /// Sum of the areas of all damage regions.
+    ///
+    /// Regions left overlapping by capacity merges are counted once each, so
+    /// this can overestimate the damaged pixels but never underestimates
+    /// them. `full_damage` is not reflected; check `is_full_damage()` first.
+    pub fn total_area(&self) -> u64 {
+        self.regions().iter().map(|r| r.area() as u64).sum()
+    }
+
     /// Get the bounding box of all damage
     pub fn bounding_box(&self) -> DamageRect {
         if self.count == 0 {
@@ -51,6 +51,19 @@ impl DisplayInfo {
         self.pitch as usize * self.height as usize
     }
 
+    /// Bytes needed for a BGRA8888 capture of the display with rows `stride`
+    /// bytes apart, or `None` if the stride is shorter than one row.
+    #[inline]
+    pub fn capture_size(&self, stride: usize) -> Option<usize> {
+        let row = self.width as usize * 4;
+        if stride < row || self.height == 0 {
+            return None;
+        }
+        stride
+            .checked_mul(self.height as usize - 1)?
+            .checked_add(row)
+    }
+
     /// Check if dimensions are valid (non-zero, reasonable bounds).
     #[inline]
     pub fn is_valid(&self) -> bool {
@@ -158,6 +158,7 @@ pub trait DrawTarget {
 /// duplicated between kernel GraphicsContext and userland DrawBuffer.
 pub mod pixel_ops {
     use super::PixelBuffer;
+    use crate::pixel::PixelFormat;
 
     /// Calculate byte offset for a pixel coordinate.
     #[inline]
@@ -188,8 +189,8 @@ pub mod pixel_ops {
 
         let x0 = x.max(0);
         let y0 = y.max(0);
-        let x1 = (x + w - 1).min(buf_w as i32 - 1);
-        let y1 = (y + h - 1).min(buf_h as i32 - 1);
+        let x1 = x.saturating_add(w - 1).min(buf_w as i32 - 1);
+        let y1 = y.saturating_add(h - 1).min(buf_h as i32 - 1);
 
         if x0 > x1 || y0 > y1 {
             None
@@ -198,6 +199,175 @@ pub mod pixel_ops {
         }
     }
 
+    /// A copy of `width` x `height` pixels from (src_x, src_y) in one buffer
+    /// to (dst_x, dst_y) in another.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct BlitRect {
+        pub src_x: i32,
+        pub src_y: i32,
+        pub dst_x: i32,
+        pub dst_y: i32,
+        pub width: i32,
+        pub height: i32,
+    }
+
+    /// Clip one axis of a blit; offsets stay paired between the buffers.
+    fn clip_blit_axis(
+        src: i32,
+        dst: i32,
+        len: i32,
+        src_max: u32,
+        dst_max: u32,
+    ) -> Option<(i32, i32, i32)> {
+        let (src, dst, len) = (src as i64, dst as i64, len as i64);
+        let skip = 0.max(-src).max(-dst);
+        let len = (len - skip)
+            .min(src_max as i64 - (src + skip))
+            .min(dst_max as i64 - (dst + skip));
+        if len <= 0 {
+            return None;
+        }
+        Some(((src + skip) as i32, (dst + skip) as i32, len as i32))
+    }
+
+    /// Shrink a blit so it lies inside both a `src_w` x `src_h` source and a
+    /// `dst_w` x `dst_h` destination. Trimming an edge on one side trims the
+    /// same pixels on the other. Returns None if nothing is left to copy.
+    pub fn clip_blit(
+        rect: BlitRect,
+        src_w: u32,
+        src_h: u32,
+        dst_w: u32,
+        dst_h: u32,
+    ) -> Option<BlitRect> {
+        let (src_x, dst_x, width) =
+            clip_blit_axis(rect.src_x, rect.dst_x, rect.width, src_w, dst_w)?;
+        let (src_y, dst_y, height) =
+            clip_blit_axis(rect.src_y, rect.dst_y, rect.height, src_h, dst_h)?;
+        Some(BlitRect {
+            src_x,
+            src_y,
+            dst_x,
+            dst_y,
+            width,
+            height,
+        })
+    }
+
+    /// Mask of the color bits compared against a color key (alpha/X ignored).
+    pub const COLOR_KEY_MASK: u32 = 0x00FF_FFFF;
+
+    /// Raw color bits of a pixel as compared against a color key: the low
+    /// three bytes, or all 16 bits of a 2-byte pixel.
+    #[inline]
+    fn key_bits(px: &[u8]) -> u32 {
+        match *px {
+            [b0, b1] => u16::from_le_bytes([b0, b1]) as u32,
+            [b0, b1, b2, ..] => u32::from_le_bytes([b0, b1, b2, 0]),
+            _ => u32::MAX,
+        }
+    }
+
+    /// Copy a row of pixels, skipping source pixels that match `key`.
+    ///
+    /// Keyed pixels leave the destination untouched so whatever is already
+    /// there shows through. With no key this is a plain copy. Copies
+    /// `min(dst.len(), src.len())` bytes, rounded down to whole pixels.
+    /// For 2-byte pixels the key is matched against the raw 16 bits; other
+    /// depths below 3 bytes are never keyed.
+    #[inline]
+    pub fn copy_row_keyed(dst: &mut [u8], src: &[u8], bytes_pp: usize, key: Option<u32>) {
+        let len = dst.len().min(src.len());
+        let Some(key) = key.filter(|_| bytes_pp >= 2) else {
+            dst[..len].copy_from_slice(&src[..len]);
+            return;
+        };
+        let key = key & COLOR_KEY_MASK;
+        for (d, s) in dst[..len]
+            .chunks_exact_mut(bytes_pp)
+            .zip(src[..len].chunks_exact(bytes_pp))
+        {
+            if key_bits(s) != key {
+                d.copy_from_slice(s);
+            }
+        }
+    }
+
+    /// Copy a row of pixels between buffers of different formats, converting
+    /// each pixel. Keyed pixels are matched against the source's raw RGB bits,
+    /// as in `copy_row_keyed`. Falls back to `copy_row_keyed` when the formats
+    /// are identical.
+    #[inline]
+    pub fn convert_row_keyed(
+        dst: &mut [u8],
+        dst_fmt: PixelFormat,
+        src: &[u8],
+        src_fmt: PixelFormat,
+        key: Option<u32>,
+    ) {
+        let dst_bpp = dst_fmt.bytes_per_pixel() as usize;
+        let src_bpp = src_fmt.bytes_per_pixel() as usize;
+        if dst_fmt == src_fmt {
+            let len = dst.len().min(src.len());
+            copy_row_keyed(&mut dst[..len], &src[..len], src_bpp, key);
+            return;
+        }
+        let key = key.map(|k| k & COLOR_KEY_MASK);
+        for (d, s) in dst.chunks_exact_mut(dst_bpp).zip(src.chunks_exact(src_bpp)) {
+            if key.is_some_and(|k| key_bits(s) == k) {
+                continue;
+            }
+            dst_fmt.encode_pixel(src_fmt.decode_pixel(s), d);
+        }
+    }
+
+    /// Blend one 0xRRGGBBAA color over another: each channel becomes
+    /// `src * a + dst * (1 - a)` with `a` the source alpha.
+    #[inline]
+    pub fn blend_rgba(src: u32, dst: u32) -> u32 {
+        let a = src & 0xFF;
+        if a == 0xFF {
+            return src;
+        }
+        if a == 0 {
+            return dst;
+        }
+        let inv = 0xFF - a;
+        let mix = |shift: u32| {
+            let s = (src >> shift) & 0xFF;
+            let d = (dst >> shift) & 0xFF;
+            (s * a + d * inv + 127) / 255
+        };
+        let out_a = a + ((dst & 0xFF) * inv + 127) / 255;
+        (mix(24) << 24) | (mix(16) << 16) | (mix(8) << 8) | out_a
+    }
+
+    /// Blend a row of source pixels over the destination by source alpha,
+    /// converting between formats. Keyed pixels are skipped as in
+    /// `convert_row_keyed`; fully transparent ones leave `dst` untouched.
+    pub fn blend_row_keyed(
+        dst: &mut [u8],
+        dst_fmt: PixelFormat,
+        src: &[u8],
+        src_fmt: PixelFormat,
+        key: Option<u32>,
+    ) {
+        let dst_bpp = dst_fmt.bytes_per_pixel() as usize;
+        let src_bpp = src_fmt.bytes_per_pixel() as usize;
+        let key = key.map(|k| k & COLOR_KEY_MASK);
+        for (d, s) in dst.chunks_exact_mut(dst_bpp).zip(src.chunks_exact(src_bpp)) {
+            if key.is_some_and(|k| u32::from_le_bytes([s[0], s[1], s[2], 0]) == k) {
+                continue;
+            }
+            let color = src_fmt.decode_pixel(s);
+            match color & 0xFF {
+                0 => {}
+                0xFF => dst_fmt.encode_pixel(color, d),
+                _ => dst_fmt.encode_pixel(blend_rgba(color, dst_fmt.decode_pixel(d)), d),
+            }
+        }
+    }
+
     /// Generic draw_pixel implementation for PixelBuffer types.
     #[inline]
     pub fn draw_pixel_impl<P: PixelBuffer + ?Sized>(buf: &mut P, x: i32, y: i32, color: u32) {
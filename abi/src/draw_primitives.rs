@@ -4,7 +4,7 @@
 //! This replaces duplicate code in video/src/graphics.rs and
 //! userland/src/gfx/primitives.rs.
 
-use crate::draw::DrawTarget;
+use crate::draw::{DrawTarget, pixel_ops};
 
 /// Draw a line using Bresenham's algorithm
 pub fn line<T: DrawTarget>(target: &mut T, x0: i32, y0: i32, x1: i32, y1: i32, color: u32) {
@@ -17,8 +17,17 @@ pub fn line<T: DrawTarget>(target: &mut T, x0: i32, y0: i32, x1: i32, y1: i32, c
         return;
     }
 
-    let dx = (x1 - x0).abs();
-    let dy = -(y1 - y0).abs();
+    line_points(x0, y0, x1, y1, |x, y| target.draw_pixel(x, y, raw));
+}
+
+/// Visit every point of the Bresenham line from (x0, y0) to (x1, y1).
+///
+/// Both endpoints are included and points are visited in order from the
+/// start. No clipping is done; the error term is kept in `i64` so distant
+/// endpoints cannot overflow.
+pub fn line_points<F: FnMut(i32, i32)>(x0: i32, y0: i32, x1: i32, y1: i32, mut plot: F) {
+    let dx = (x1 as i64 - x0 as i64).abs();
+    let dy = -(y1 as i64 - y0 as i64).abs();
     let sx = if x0 < x1 { 1 } else { -1 };
     let sy = if y0 < y1 { 1 } else { -1 };
     let mut err = dx + dy;
@@ -26,7 +35,7 @@ pub fn line<T: DrawTarget>(target: &mut T, x0: i32, y0: i32, x1: i32, y1: i32, c
     let mut y = y0;
 
     loop {
-        target.draw_pixel(x, y, raw);
+        plot(x, y);
         if x == x1 && y == y1 {
             break;
         }
@@ -42,6 +51,137 @@ pub fn line<T: DrawTarget>(target: &mut T, x0: i32, y0: i32, x1: i32, y1: i32, c
     }
 }
 
+/// Sub-pixel steps per pixel while flattening curves.
+const CURVE_SUBPIXEL: i64 = 16;
+/// How far, in sub-pixel units, a flattened segment may stray from the true
+/// curve: a quarter pixel keeps curves smooth without needless segments.
+const CURVE_TOLERANCE: i64 = CURVE_SUBPIXEL / 4;
+/// Subdivision depth limit; 2^10 segments is plenty for any on-screen curve.
+const CURVE_MAX_DEPTH: u32 = 10;
+
+type CurvePoint = (i64, i64);
+
+fn to_subpixel((x, y): (i32, i32)) -> CurvePoint {
+    (x as i64 * CURVE_SUBPIXEL, y as i64 * CURVE_SUBPIXEL)
+}
+
+fn to_pixel((x, y): CurvePoint) -> (i32, i32) {
+    let round = |v: i64| (v + CURVE_SUBPIXEL / 2).div_euclid(CURVE_SUBPIXEL) as i32;
+    (round(x), round(y))
+}
+
+fn midpoint(a: CurvePoint, b: CurvePoint) -> CurvePoint {
+    ((a.0 + b.0) / 2, (a.1 + b.1) / 2)
+}
+
+/// Whether the chord p0-p3 is within `CURVE_TOLERANCE` of the cubic, using
+/// the bound on the control points' deviation from the chord.
+fn cubic_is_flat(p: &[CurvePoint; 4]) -> bool {
+    let ux = 3 * p[1].0 - 2 * p[0].0 - p[3].0;
+    let uy = 3 * p[1].1 - 2 * p[0].1 - p[3].1;
+    let vx = 3 * p[2].0 - p[0].0 - 2 * p[3].0;
+    let vy = 3 * p[2].1 - p[0].1 - 2 * p[3].1;
+    (ux * ux).max(vx * vx) + (uy * uy).max(vy * vy) <= 16 * CURVE_TOLERANCE * CURVE_TOLERANCE
+}
+
+/// Split at t = 1/2 until each piece is flat, emitting piece end points.
+fn flatten_cubic<F: FnMut(CurvePoint)>(p: [CurvePoint; 4], depth: u32, emit: &mut F) {
+    if depth == 0 || cubic_is_flat(&p) {
+        emit(p[3]);
+        return;
+    }
+    let p01 = midpoint(p[0], p[1]);
+    let p12 = midpoint(p[1], p[2]);
+    let p23 = midpoint(p[2], p[3]);
+    let p012 = midpoint(p01, p12);
+    let p123 = midpoint(p12, p23);
+    let mid = midpoint(p012, p123);
+    flatten_cubic([p[0], p01, p012, mid], depth - 1, emit);
+    flatten_cubic([mid, p123, p23, p[3]], depth - 1, emit);
+}
+
+/// Plot `start`, then the flattened cubic's vertices as whole pixels.
+fn visit_flattened<F: FnMut(i32, i32)>(start: (i32, i32), control: [CurvePoint; 4], mut plot: F) {
+    let mut last = start;
+    plot(start.0, start.1);
+    flatten_cubic(control, CURVE_MAX_DEPTH, &mut |point| {
+        let (x, y) = to_pixel(point);
+        if (x, y) != last {
+            plot(x, y);
+            last = (x, y);
+        }
+    });
+}
+
+/// Visit the vertices of the cubic Bezier p0..p3 flattened into line
+/// segments, in order from `p0` to `p3`.
+///
+/// Subdivision is adaptive: straight stretches become one segment, tight
+/// bends are split until every segment is within a quarter pixel of the
+/// curve. Both end points are visited exactly; consecutive duplicates
+/// after rounding are skipped.
+pub fn cubic_bezier_points<F: FnMut(i32, i32)>(
+    p0: (i32, i32),
+    p1: (i32, i32),
+    p2: (i32, i32),
+    p3: (i32, i32),
+    plot: F,
+) {
+    let control = [
+        to_subpixel(p0),
+        to_subpixel(p1),
+        to_subpixel(p2),
+        to_subpixel(p3),
+    ];
+    visit_flattened(p0, control, plot);
+}
+
+/// Quadratic counterpart of `cubic_bezier_points`, with control point `p1`.
+pub fn quad_bezier_points<F: FnMut(i32, i32)>(
+    p0: (i32, i32),
+    p1: (i32, i32),
+    p2: (i32, i32),
+    plot: F,
+) {
+    // Degree elevation: the same curve as a cubic, in sub-pixel precision
+    // so the thirds do not round to whole pixels.
+    let (a, b, c) = (to_subpixel(p0), to_subpixel(p1), to_subpixel(p2));
+    let c1 = ((a.0 + 2 * b.0) / 3, (a.1 + 2 * b.1) / 3);
+    let c2 = ((2 * b.0 + c.0) / 3, (2 * b.1 + c.1) / 3);
+    visit_flattened(p0, [a, c1, c2, c], plot);
+}
+
+/// Stroke a cubic Bezier curve as connected lines.
+pub fn cubic_bezier<T: DrawTarget>(
+    target: &mut T,
+    p0: (i32, i32),
+    p1: (i32, i32),
+    p2: (i32, i32),
+    p3: (i32, i32),
+    color: u32,
+) {
+    let mut prev = p0;
+    cubic_bezier_points(p0, p1, p2, p3, |x, y| {
+        line(target, prev.0, prev.1, x, y, color);
+        prev = (x, y);
+    });
+}
+
+/// Stroke a quadratic Bezier curve as connected lines.
+pub fn quad_bezier<T: DrawTarget>(
+    target: &mut T,
+    p0: (i32, i32),
+    p1: (i32, i32),
+    p2: (i32, i32),
+    color: u32,
+) {
+    let mut prev = p0;
+    quad_bezier_points(p0, p1, p2, |x, y| {
+        line(target, prev.0, prev.1, x, y, color);
+        prev = (x, y);
+    });
+}
+
 /// Draw a rectangle outline
 pub fn rect<T: DrawTarget>(target: &mut T, x: i32, y: i32, w: i32, h: i32, color: u32) {
     if w <= 0 || h <= 0 {
@@ -60,6 +200,78 @@ pub fn fill_rect<T: DrawTarget>(target: &mut T, x: i32, y: i32, w: i32, h: i32,
     target.fill_rect(x, y, w, h, raw);
 }
 
+/// Interpolate each 8-bit channel `step / steps` of the way from `from` to
+/// `to`, rounding to nearest. `steps == 0` yields `from`.
+pub fn lerp_color(from: u32, to: u32, step: u32, steps: u32) -> u32 {
+    if steps == 0 {
+        return from;
+    }
+    let step = step.min(steps);
+    // u64 keeps `delta * step` from overflowing for huge step counts
+    let (step, steps) = (step as u64, steps as u64);
+    let mix = |shift: u32| {
+        let a = ((from >> shift) & 0xFF) as u64;
+        let b = ((to >> shift) & 0xFF) as u64;
+        let v = if b >= a {
+            a + ((b - a) * step + steps / 2) / steps
+        } else {
+            a - ((a - b) * step + steps / 2) / steps
+        };
+        (v as u32) << shift
+    };
+    mix(24) | mix(16) | mix(8) | mix(0)
+}
+
+/// Fill a rectangle with a vertical gradient from `top` (first row) to
+/// `bottom` (last row).
+///
+/// Row colors are spread over the whole rectangle before clipping, so a
+/// partly visible gradient shows the same colors it would unclipped. Each
+/// visible row is written as one span.
+pub fn fill_gradient<T: DrawTarget>(
+    target: &mut T,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+    top: u32,
+    bottom: u32,
+) {
+    let Some((x0, y0, x1, y1)) = pixel_ops::clip_rect(x, y, w, h, target.width(), target.height())
+    else {
+        return;
+    };
+    let fmt = target.pixel_format();
+    let steps = (h - 1) as u32;
+    for row in y0..=y1 {
+        let color = lerp_color(top, bottom, (row - y) as u32, steps);
+        target.fill_rect(x0, row, x1 - x0 + 1, 1, fmt.convert_color(color));
+    }
+}
+
+/// Fill a rectangle with a horizontal gradient from `left` (first column)
+/// to `right` (last column). Clipping works as in `fill_gradient`.
+pub fn fill_gradient_horizontal<T: DrawTarget>(
+    target: &mut T,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+    left: u32,
+    right: u32,
+) {
+    let Some((x0, y0, x1, y1)) = pixel_ops::clip_rect(x, y, w, h, target.width(), target.height())
+    else {
+        return;
+    };
+    let fmt = target.pixel_format();
+    let steps = (w - 1) as u32;
+    for col in x0..=x1 {
+        let color = lerp_color(left, right, (col - x) as u32, steps);
+        target.draw_vline(col, y0, y1, fmt.convert_color(color));
+    }
+}
+
 /// Draw a circle outline using the midpoint algorithm
 pub fn circle<T: DrawTarget>(target: &mut T, cx: i32, cy: i32, radius: i32, color: u32) {
     if radius <= 0 {
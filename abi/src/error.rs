@@ -69,6 +69,10 @@ pub enum CompositorError {
     BufferNotFound = -9,
     /// Invalid buffer token
     InvalidToken = -10,
+    /// No framebuffer to present to (headless)
+    NoFramebuffer = -11,
+    /// Queued operations must be drained first; retry later
+    Busy = -12,
 }
 
 impl_kernel_error!(CompositorError, fallback: InvalidArgument, variants: {
@@ -83,6 +87,8 @@ impl_kernel_error!(CompositorError, fallback: InvalidArgument, variants: {
     -8 => PermissionDenied,
     -9 => BufferNotFound,
     -10 => InvalidToken,
+    -11 => NoFramebuffer,
+    -12 => Busy,
 });
 
 /// Shared memory operation errors
@@ -118,3 +124,25 @@ impl_kernel_error!(ShmError, fallback: InvalidToken, variants: {
     -6 => MappingLimitReached,
     -7 => InvalidSize,
 });
+
+/// Syscall registry errors
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyscallError {
+    /// Operation succeeded
+    #[default]
+    Success = 0,
+    /// Syscall number is outside the dispatch table
+    InvalidNumber = -1,
+    /// A handler is already installed for this number
+    AlreadyRegistered = -2,
+    /// No dynamically registered handler for this number
+    NotRegistered = -3,
+}
+
+impl_kernel_error!(SyscallError, fallback: InvalidNumber, variants: {
+    0 => Success,
+    -1 => InvalidNumber,
+    -2 => AlreadyRegistered,
+    -3 => NotRegistered,
+});
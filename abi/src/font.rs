@@ -10,6 +10,9 @@ pub const FONT_CHAR_WIDTH: i32 = 8;
 /// Font character height in pixels
 pub const FONT_CHAR_HEIGHT: i32 = 16;
 
+/// Rows from the top of a glyph cell to the baseline; descenders sit below
+pub const FONT_BASELINE: i32 = 12;
+
 /// First printable ASCII character
 pub const FONT_FIRST_CHAR: u8 = 32;
 
@@ -19,6 +22,12 @@ pub const FONT_LAST_CHAR: u8 = 126;
 /// Number of characters in the font
 pub const FONT_CHAR_COUNT: usize = (FONT_LAST_CHAR - FONT_FIRST_CHAR + 1) as usize;
 
+/// First printable Latin-1 supplement character (no-break space)
+pub const FONT_LATIN1_FIRST: u8 = 0xA0;
+
+/// Number of Latin-1 supplement characters in the font (0xA0-0xFF)
+pub const FONT_LATIN1_COUNT: usize = (u8::MAX - FONT_LATIN1_FIRST) as usize + 1;
+
 /// 8x16 bitmap font data (95 glyphs, 16 bytes each)
 #[allow(clippy::unreadable_literal)]
 pub static FONT_DATA: [[u8; FONT_CHAR_HEIGHT as usize]; FONT_CHAR_COUNT] = [
@@ -499,11 +508,747 @@ pub static FONT_DATA: [[u8; FONT_CHAR_HEIGHT as usize]; FONT_CHAR_COUNT] = [
     ],
 ];
 
-/// Get the glyph data for a character.
+/// 8x16 bitmap font data for the printable Latin-1 supplement (96 glyphs,
+/// 0xA0-0xFF). Accented letters reuse the ASCII letter shapes.
+#[allow(clippy::unreadable_literal)]
+pub static FONT_LATIN1_DATA: [[u8; FONT_CHAR_HEIGHT as usize]; FONT_LATIN1_COUNT] = [
+    // No-break space (160)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ¡ (161)
+    [
+        0x00, 0x00, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x3C, 0x3C, 0x3C, 0x18, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ¢ (162)
+    [
+        0x00, 0x00, 0x18, 0x18, 0x7C, 0xC6, 0xC0, 0xC0, 0xC0, 0xC6, 0x7C, 0x18, 0x18, 0x00, 0x00,
+        0x00,
+    ],
+    // £ (163)
+    [
+        0x00, 0x00, 0x38, 0x6C, 0x64, 0x60, 0xF0, 0x60, 0x60, 0x60, 0xE6, 0xFC, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ¤ (164)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x66, 0x3C, 0x66, 0x66, 0x3C, 0x66, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ¥ (165)
+    [
+        0x00, 0x00, 0x66, 0x66, 0x3C, 0x18, 0x7E, 0x18, 0x7E, 0x18, 0x18, 0x18, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ¦ (166)
+    [
+        0x00, 0x00, 0x18, 0x18, 0x18, 0x18, 0x00, 0x00, 0x18, 0x18, 0x18, 0x18, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // § (167)
+    [
+        0x00, 0x7C, 0xC6, 0x60, 0x38, 0x6C, 0xC6, 0x6C, 0x38, 0x0C, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ¨ (168)
+    [
+        0x00, 0x00, 0x66, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // © (169)
+    [
+        0x00, 0x00, 0x7C, 0x82, 0x9A, 0xA2, 0xA2, 0xA2, 0x9A, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ª (170)
+    [
+        0x00, 0x00, 0x3C, 0x6C, 0x6C, 0x3E, 0x00, 0x7E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // « (171)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x36, 0x6C, 0xD8, 0x6C, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ¬ (172)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFE, 0x06, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Soft hyphen (173)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFE, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ® (174)
+    [
+        0x00, 0x00, 0x7C, 0x82, 0xB2, 0xAA, 0xB2, 0xAA, 0xAA, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ¯ (175)
+    [
+        0x00, 0x00, 0x7E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ° (176)
+    [
+        0x00, 0x00, 0x38, 0x6C, 0x6C, 0x38, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ± (177)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x7E, 0x18, 0x18, 0x00, 0x7E, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ² (178)
+    [
+        0x00, 0x00, 0x70, 0xD8, 0x30, 0x60, 0xF8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ³ (179)
+    [
+        0x00, 0x00, 0xF0, 0x18, 0x70, 0x18, 0xF0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ´ (180)
+    [
+        0x00, 0x00, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // µ (181)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x7C, 0x60, 0xC0, 0x00,
+        0x00,
+    ],
+    // ¶ (182)
+    [
+        0x00, 0x00, 0x7F, 0xDB, 0xDB, 0xDB, 0x7B, 0x1B, 0x1B, 0x1B, 0x1B, 0x1B, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // · (183)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ¸ (184)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x0C, 0x38,
+        0x00,
+    ],
+    // ¹ (185)
+    [
+        0x00, 0x00, 0x30, 0x70, 0x30, 0x30, 0x78, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // º (186)
+    [
+        0x00, 0x00, 0x38, 0x6C, 0x6C, 0x38, 0x00, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // » (187)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0xD8, 0x6C, 0x36, 0x6C, 0xD8, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ¼ (188)
+    [
+        0x00, 0xC0, 0xC0, 0xC2, 0xC6, 0xCC, 0x18, 0x30, 0x66, 0xCE, 0x9E, 0x3E, 0x06, 0x06, 0x00,
+        0x00,
+    ],
+    // ½ (189)
+    [
+        0x00, 0xC0, 0xC0, 0xC2, 0xC6, 0xCC, 0x18, 0x30, 0x60, 0xDC, 0x86, 0x0C, 0x18, 0x3E, 0x00,
+        0x00,
+    ],
+    // ¾ (190)
+    [
+        0x00, 0xE0, 0x30, 0x62, 0x36, 0xEC, 0x18, 0x30, 0x66, 0xCE, 0x9E, 0x3E, 0x06, 0x06, 0x00,
+        0x00,
+    ],
+    // ¿ (191)
+    [
+        0x00, 0x00, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x0C, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // À (192)
+    [
+        0x30, 0x18, 0x00, 0x10, 0x38, 0x6C, 0xC6, 0xFE, 0xC6, 0xC6, 0xC6, 0xC6, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Á (193)
+    [
+        0x0C, 0x18, 0x00, 0x10, 0x38, 0x6C, 0xC6, 0xFE, 0xC6, 0xC6, 0xC6, 0xC6, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Â (194)
+    [
+        0x18, 0x66, 0x00, 0x10, 0x38, 0x6C, 0xC6, 0xFE, 0xC6, 0xC6, 0xC6, 0xC6, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ã (195)
+    [
+        0x76, 0xDC, 0x00, 0x10, 0x38, 0x6C, 0xC6, 0xFE, 0xC6, 0xC6, 0xC6, 0xC6, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ä (196)
+    [
+        0x00, 0x66, 0x00, 0x10, 0x38, 0x6C, 0xC6, 0xFE, 0xC6, 0xC6, 0xC6, 0xC6, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Å (197)
+    [
+        0x38, 0x6C, 0x38, 0x10, 0x38, 0x6C, 0xC6, 0xFE, 0xC6, 0xC6, 0xC6, 0xC6, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Æ (198)
+    [
+        0x00, 0x00, 0x3E, 0x6C, 0xCC, 0xCC, 0xFE, 0xCC, 0xCC, 0xCC, 0xCC, 0xCE, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ç (199)
+    [
+        0x00, 0x00, 0x3C, 0x66, 0xC2, 0xC0, 0xC0, 0xC0, 0xC0, 0xC2, 0x66, 0x3C, 0x18, 0x0C, 0x38,
+        0x00,
+    ],
+    // È (200)
+    [
+        0x30, 0x18, 0x00, 0xFE, 0x66, 0x68, 0x78, 0x68, 0x60, 0x62, 0x66, 0xFE, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // É (201)
+    [
+        0x0C, 0x18, 0x00, 0xFE, 0x66, 0x68, 0x78, 0x68, 0x60, 0x62, 0x66, 0xFE, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ê (202)
+    [
+        0x18, 0x66, 0x00, 0xFE, 0x66, 0x68, 0x78, 0x68, 0x60, 0x62, 0x66, 0xFE, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ë (203)
+    [
+        0x00, 0x66, 0x00, 0xFE, 0x66, 0x68, 0x78, 0x68, 0x60, 0x62, 0x66, 0xFE, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ì (204)
+    [
+        0x30, 0x18, 0x00, 0x3C, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Í (205)
+    [
+        0x0C, 0x18, 0x00, 0x3C, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Î (206)
+    [
+        0x18, 0x66, 0x00, 0x3C, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ï (207)
+    [
+        0x00, 0x66, 0x00, 0x3C, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ð (208)
+    [
+        0x00, 0x00, 0xF8, 0x6C, 0x66, 0x66, 0xF6, 0x66, 0x66, 0x66, 0x6C, 0xF8, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ñ (209)
+    [
+        0x76, 0xDC, 0x00, 0xC6, 0xE6, 0xF6, 0xFE, 0xDE, 0xCE, 0xC6, 0xC6, 0xC6, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ò (210)
+    [
+        0x30, 0x18, 0x00, 0x7C, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ó (211)
+    [
+        0x0C, 0x18, 0x00, 0x7C, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ô (212)
+    [
+        0x18, 0x66, 0x00, 0x7C, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Õ (213)
+    [
+        0x76, 0xDC, 0x00, 0x7C, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ö (214)
+    [
+        0x00, 0x66, 0x00, 0x7C, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // × (215)
+    [
+        0x00, 0x00, 0x00, 0x00, 0xC6, 0x6C, 0x38, 0x38, 0x6C, 0xC6, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ø (216)
+    [
+        0x00, 0x00, 0x7E, 0xC6, 0xC6, 0xCE, 0xD6, 0xD6, 0xE6, 0xC6, 0xC6, 0xFC, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ù (217)
+    [
+        0x30, 0x18, 0x00, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ú (218)
+    [
+        0x0C, 0x18, 0x00, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Û (219)
+    [
+        0x18, 0x66, 0x00, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ü (220)
+    [
+        0x00, 0x66, 0x00, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Ý (221)
+    [
+        0x0C, 0x18, 0x00, 0x66, 0x66, 0x66, 0x3C, 0x18, 0x18, 0x18, 0x18, 0x3C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // Þ (222)
+    [
+        0x00, 0x00, 0xF0, 0x60, 0x7C, 0x66, 0x66, 0x66, 0x7C, 0x60, 0x60, 0xF0, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ß (223)
+    [
+        0x00, 0x00, 0x78, 0xCC, 0xCC, 0xCC, 0xD8, 0xCC, 0xC6, 0xC6, 0xC6, 0xCC, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // à (224)
+    [
+        0x00, 0x00, 0x30, 0x18, 0x00, 0x78, 0x0C, 0x7C, 0xCC, 0xCC, 0xCC, 0x76, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // á (225)
+    [
+        0x00, 0x00, 0x0C, 0x18, 0x00, 0x78, 0x0C, 0x7C, 0xCC, 0xCC, 0xCC, 0x76, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // â (226)
+    [
+        0x00, 0x00, 0x18, 0x66, 0x00, 0x78, 0x0C, 0x7C, 0xCC, 0xCC, 0xCC, 0x76, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ã (227)
+    [
+        0x00, 0x00, 0x76, 0xDC, 0x00, 0x78, 0x0C, 0x7C, 0xCC, 0xCC, 0xCC, 0x76, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ä (228)
+    [
+        0x00, 0x00, 0x00, 0x66, 0x00, 0x78, 0x0C, 0x7C, 0xCC, 0xCC, 0xCC, 0x76, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // å (229)
+    [
+        0x00, 0x00, 0x38, 0x6C, 0x38, 0x78, 0x0C, 0x7C, 0xCC, 0xCC, 0xCC, 0x76, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // æ (230)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0xCC, 0x76, 0x36, 0x7E, 0xD8, 0xD8, 0x6E, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ç (231)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x7C, 0xC6, 0xC0, 0xC0, 0xC0, 0xC6, 0x7C, 0x18, 0x0C, 0x38,
+        0x00,
+    ],
+    // è (232)
+    [
+        0x00, 0x00, 0x30, 0x18, 0x00, 0x7C, 0xC6, 0xFE, 0xC0, 0xC0, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // é (233)
+    [
+        0x00, 0x00, 0x0C, 0x18, 0x00, 0x7C, 0xC6, 0xFE, 0xC0, 0xC0, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ê (234)
+    [
+        0x00, 0x00, 0x18, 0x66, 0x00, 0x7C, 0xC6, 0xFE, 0xC0, 0xC0, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ë (235)
+    [
+        0x00, 0x00, 0x00, 0x66, 0x00, 0x7C, 0xC6, 0xFE, 0xC0, 0xC0, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ì (236)
+    [
+        0x00, 0x00, 0x30, 0x18, 0x00, 0x38, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // í (237)
+    [
+        0x00, 0x00, 0x0C, 0x18, 0x00, 0x38, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // î (238)
+    [
+        0x00, 0x00, 0x18, 0x66, 0x00, 0x38, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ï (239)
+    [
+        0x00, 0x00, 0x00, 0x66, 0x00, 0x38, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ð (240)
+    [
+        0x00, 0x00, 0x36, 0x1C, 0x36, 0x06, 0x7E, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ñ (241)
+    [
+        0x00, 0x00, 0x76, 0xDC, 0x00, 0xDC, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ò (242)
+    [
+        0x00, 0x00, 0x30, 0x18, 0x00, 0x7C, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ó (243)
+    [
+        0x00, 0x00, 0x0C, 0x18, 0x00, 0x7C, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ô (244)
+    [
+        0x00, 0x00, 0x18, 0x66, 0x00, 0x7C, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // õ (245)
+    [
+        0x00, 0x00, 0x76, 0xDC, 0x00, 0x7C, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ö (246)
+    [
+        0x00, 0x00, 0x00, 0x66, 0x00, 0x7C, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7C, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ÷ (247)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x7E, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ø (248)
+    [
+        0x00, 0x00, 0x00, 0x00, 0x02, 0x7C, 0xCE, 0xCE, 0xD6, 0xE6, 0xE6, 0x7C, 0x80, 0x00, 0x00,
+        0x00,
+    ],
+    // ù (249)
+    [
+        0x00, 0x00, 0x30, 0x18, 0x00, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0x76, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ú (250)
+    [
+        0x00, 0x00, 0x0C, 0x18, 0x00, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0x76, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // û (251)
+    [
+        0x00, 0x00, 0x18, 0x66, 0x00, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0x76, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ü (252)
+    [
+        0x00, 0x00, 0x00, 0x66, 0x00, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0x76, 0x00, 0x00, 0x00,
+        0x00,
+    ],
+    // ý (253)
+    [
+        0x00, 0x00, 0x0C, 0x18, 0x00, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7E, 0x06, 0x0C, 0xF8, 0x00,
+        0x00,
+    ],
+    // þ (254)
+    [
+        0x00, 0x00, 0xE0, 0x60, 0x60, 0x7C, 0x66, 0x66, 0x66, 0x66, 0x7C, 0x60, 0x60, 0xF0, 0x00,
+        0x00,
+    ],
+    // ÿ (255)
+    [
+        0x00, 0x00, 0x00, 0x66, 0x00, 0xC6, 0xC6, 0xC6, 0xC6, 0xC6, 0x7E, 0x06, 0x0C, 0xF8, 0x00,
+        0x00,
+    ],
+];
+
+/// Box-drawing and block-element glyphs, sorted by codepoint: the light and
+/// double line sets (U+2500-U+256C) plus full, half and shaded blocks.
+#[allow(clippy::unreadable_literal)]
+pub static BOX_GLYPHS: [(char, [u8; FONT_CHAR_HEIGHT as usize]); 30] = [
+    // ─ (U+2500)
+    (
+        '\u{2500}',
+        [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ],
+    ),
+    // │ (U+2502)
+    (
+        '\u{2502}',
+        [
+            0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+            0x18, 0x18,
+        ],
+    ),
+    // ┌ (U+250C)
+    (
+        '\u{250C}',
+        [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+            0x18, 0x18,
+        ],
+    ),
+    // ┐ (U+2510)
+    (
+        '\u{2510}',
+        [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF8, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+            0x18, 0x18,
+        ],
+    ),
+    // └ (U+2514)
+    (
+        '\u{2514}',
+        [
+            0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ],
+    ),
+    // ┘ (U+2518)
+    (
+        '\u{2518}',
+        [
+            0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0xF8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ],
+    ),
+    // ├ (U+251C)
+    (
+        '\u{251C}',
+        [
+            0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1F, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+            0x18, 0x18,
+        ],
+    ),
+    // ┤ (U+2524)
+    (
+        '\u{2524}',
+        [
+            0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0xF8, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+            0x18, 0x18,
+        ],
+    ),
+    // ┬ (U+252C)
+    (
+        '\u{252C}',
+        [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+            0x18, 0x18,
+        ],
+    ),
+    // ┴ (U+2534)
+    (
+        '\u{2534}',
+        [
+            0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ],
+    ),
+    // ┼ (U+253C)
+    (
+        '\u{253C}',
+        [
+            0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+            0x18, 0x18,
+        ],
+    ),
+    // ═ (U+2550)
+    (
+        '\u{2550}',
+        [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ],
+    ),
+    // ║ (U+2551)
+    (
+        '\u{2551}',
+        [
+            0x36, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36,
+            0x36, 0x36,
+        ],
+    ),
+    // ╔ (U+2554)
+    (
+        '\u{2554}',
+        [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x3F, 0x30, 0x37, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36,
+            0x36, 0x36,
+        ],
+    ),
+    // ╗ (U+2557)
+    (
+        '\u{2557}',
+        [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0xFE, 0x06, 0xF6, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36,
+            0x36, 0x36,
+        ],
+    ),
+    // ╚ (U+255A)
+    (
+        '\u{255A}',
+        [
+            0x36, 0x36, 0x36, 0x36, 0x36, 0x37, 0x30, 0x3F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ],
+    ),
+    // ╝ (U+255D)
+    (
+        '\u{255D}',
+        [
+            0x36, 0x36, 0x36, 0x36, 0x36, 0xF6, 0x06, 0xFE, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ],
+    ),
+    // ╠ (U+2560)
+    (
+        '\u{2560}',
+        [
+            0x36, 0x36, 0x36, 0x36, 0x36, 0x37, 0x30, 0x37, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36,
+            0x36, 0x36,
+        ],
+    ),
+    // ╣ (U+2563)
+    (
+        '\u{2563}',
+        [
+            0x36, 0x36, 0x36, 0x36, 0x36, 0xF6, 0x06, 0xF6, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36,
+            0x36, 0x36,
+        ],
+    ),
+    // ╦ (U+2566)
+    (
+        '\u{2566}',
+        [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x00, 0xF7, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36,
+            0x36, 0x36,
+        ],
+    ),
+    // ╩ (U+2569)
+    (
+        '\u{2569}',
+        [
+            0x36, 0x36, 0x36, 0x36, 0x36, 0xF7, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ],
+    ),
+    // ╬ (U+256C)
+    (
+        '\u{256C}',
+        [
+            0x36, 0x36, 0x36, 0x36, 0x36, 0xF7, 0x00, 0xF7, 0x36, 0x36, 0x36, 0x36, 0x36, 0x36,
+            0x36, 0x36,
+        ],
+    ),
+    // ▀ (U+2580)
+    (
+        '\u{2580}',
+        [
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ],
+    ),
+    // ▄ (U+2584)
+    (
+        '\u{2584}',
+        [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0xFF, 0xFF,
+        ],
+    ),
+    // █ (U+2588)
+    (
+        '\u{2588}',
+        [
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0xFF, 0xFF,
+        ],
+    ),
+    // ▌ (U+258C)
+    (
+        '\u{258C}',
+        [
+            0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0,
+            0xF0, 0xF0,
+        ],
+    ),
+    // ▐ (U+2590)
+    (
+        '\u{2590}',
+        [
+            0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F,
+            0x0F, 0x0F,
+        ],
+    ),
+    // ░ (U+2591)
+    (
+        '\u{2591}',
+        [
+            0x22, 0x88, 0x22, 0x88, 0x22, 0x88, 0x22, 0x88, 0x22, 0x88, 0x22, 0x88, 0x22, 0x88,
+            0x22, 0x88,
+        ],
+    ),
+    // ▒ (U+2592)
+    (
+        '\u{2592}',
+        [
+            0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA,
+            0x55, 0xAA,
+        ],
+    ),
+    // ▓ (U+2593)
+    (
+        '\u{2593}',
+        [
+            0xDD, 0x77, 0xDD, 0x77, 0xDD, 0x77, 0xDD, 0x77, 0xDD, 0x77, 0xDD, 0x77, 0xDD, 0x77,
+            0xDD, 0x77,
+        ],
+    ),
+];
+
+/// Get the glyph data for a Latin-1 character.
 ///
-/// Returns `None` if the character is outside the printable ASCII range (32-126).
+/// Returns `None` outside printable ASCII (32-126) and the printable
+/// Latin-1 supplement (160-255).
 #[inline]
 pub fn get_glyph(ch: u8) -> Option<&'static [u8; FONT_CHAR_HEIGHT as usize]> {
+    if ch >= FONT_LATIN1_FIRST {
+        return Some(&FONT_LATIN1_DATA[(ch - FONT_LATIN1_FIRST) as usize]);
+    }
     if ch < FONT_FIRST_CHAR || ch > FONT_LAST_CHAR {
         return None;
     }
@@ -516,3 +1261,22 @@ pub fn get_glyph(ch: u8) -> Option<&'static [u8; FONT_CHAR_HEIGHT as usize]> {
 pub fn get_glyph_or_space(ch: u8) -> &'static [u8; FONT_CHAR_HEIGHT as usize] {
     get_glyph(ch).unwrap_or(&FONT_DATA[0]) // Index 0 is space
 }
+
+/// Get the glyph data for any character: Latin-1 via `get_glyph`, plus the
+/// box-drawing and block glyphs in `BOX_GLYPHS`.
+#[inline]
+pub fn get_glyph_char(ch: char) -> Option<&'static [u8; FONT_CHAR_HEIGHT as usize]> {
+    if let Ok(byte) = u8::try_from(ch) {
+        return get_glyph(byte);
+    }
+    BOX_GLYPHS
+        .binary_search_by_key(&ch, |&(c, _)| c)
+        .ok()
+        .map(|idx| &BOX_GLYPHS[idx].1)
+}
+
+/// `get_glyph_char`, returning the space glyph for characters without one.
+#[inline]
+pub fn get_glyph_char_or_space(ch: char) -> &'static [u8; FONT_CHAR_HEIGHT as usize] {
+    get_glyph_char(ch).unwrap_or(&FONT_DATA[0])
+}
@@ -4,54 +4,349 @@
 //! to any DrawTarget implementation.
 
 use crate::draw::DrawTarget;
-use crate::font::{FONT_CHAR_HEIGHT, FONT_CHAR_WIDTH, get_glyph_or_space};
+use crate::font::{
+    FONT_BASELINE, FONT_CHAR_HEIGHT, FONT_CHAR_WIDTH, get_glyph_char_or_space, get_glyph_or_space,
+};
+
+/// Glyph size; larger sizes scale the 8x16 font by a whole factor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FontSize {
+    #[default]
+    Normal,
+    Large,
+}
+
+impl FontSize {
+    #[inline]
+    pub const fn scale(self) -> i32 {
+        match self {
+            FontSize::Normal => 1,
+            FontSize::Large => 2,
+        }
+    }
+
+    #[inline]
+    pub const fn glyph_width(self) -> i32 {
+        FONT_CHAR_WIDTH * self.scale()
+    }
+
+    #[inline]
+    pub const fn glyph_height(self) -> i32 {
+        FONT_CHAR_HEIGHT * self.scale()
+    }
+}
+
+/// Distance from the top of a glyph of `size` down to its baseline.
+#[inline]
+pub const fn baseline_offset(size: FontSize) -> i32 {
+    FONT_BASELINE * size.scale()
+}
 
 pub fn draw_char<T: DrawTarget>(target: &mut T, x: i32, y: i32, ch: u8, fg: u32, bg: u32) {
+    draw_glyph(target, x, y, get_glyph_or_space(ch), 1, fg, bg);
+}
+
+/// Draw one glyph scaled to `size`, with its cell's top-left at (x, y).
+pub fn draw_char_sized<T: DrawTarget>(
+    target: &mut T,
+    x: i32,
+    y: i32,
+    ch: u8,
+    size: FontSize,
+    fg: u32,
+    bg: u32,
+) {
+    draw_char_scaled(target, x, y, ch, size.scale(), fg, bg);
+}
+
+/// Draw one glyph with each font pixel as a `scale`x`scale` block, its
+/// cell's top-left at (x, y). Scales below 2 draw at normal size.
+pub fn draw_char_scaled<T: DrawTarget>(
+    target: &mut T,
+    x: i32,
+    y: i32,
+    ch: u8,
+    scale: i32,
+    fg: u32,
+    bg: u32,
+) {
+    draw_glyph(target, x, y, get_glyph_or_space(ch), scale, fg, bg);
+}
+
+fn draw_glyph<T: DrawTarget>(
+    target: &mut T,
+    x: i32,
+    y: i32,
+    glyph: &[u8; FONT_CHAR_HEIGHT as usize],
+    scale: i32,
+    fg: u32,
+    bg: u32,
+) {
     let fmt = target.pixel_format();
     let fg_raw = fmt.convert_color(fg);
     let bg_raw = fmt.convert_color(bg);
-    let glyph = get_glyph_or_space(ch);
+
+    if scale <= 1 {
+        for (row_idx, &row_bits) in glyph.iter().enumerate() {
+            let py = y + row_idx as i32;
+            for col in 0..FONT_CHAR_WIDTH {
+                let px = x + col;
+                let is_fg = (row_bits & (0x80 >> col)) != 0;
+                if is_fg {
+                    target.draw_pixel(px, py, fg_raw);
+                } else if bg != 0 {
+                    target.draw_pixel(px, py, bg_raw);
+                }
+            }
+        }
+        return;
+    }
 
     for (row_idx, &row_bits) in glyph.iter().enumerate() {
-        let py = y + row_idx as i32;
         for col in 0..FONT_CHAR_WIDTH {
-            let px = x + col;
             let is_fg = (row_bits & (0x80 >> col)) != 0;
-            if is_fg {
-                target.draw_pixel(px, py, fg_raw);
-            } else if bg != 0 {
-                target.draw_pixel(px, py, bg_raw);
+            if !is_fg && bg == 0 {
+                continue;
+            }
+            let color = if is_fg { fg_raw } else { bg_raw };
+            let px = x + col * scale;
+            let py = y + row_idx as i32 * scale;
+            for dy in 0..scale {
+                target.draw_hline(px, px + scale - 1, py + dy, color);
             }
         }
     }
 }
 
-pub fn draw_string<T: DrawTarget>(target: &mut T, x: i32, y: i32, text: &[u8], fg: u32, bg: u32) {
+/// A span of text drawn at one size and color.
+#[derive(Clone, Copy)]
+pub struct TextRun<'a> {
+    pub text: &'a [u8],
+    pub size: FontSize,
+    pub fg: u32,
+    pub bg: u32,
+}
+
+/// Where `layout_runs` put a glyph: the top-left of its cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlyphPlacement {
+    pub ch: u8,
+    pub x: i32,
+    pub y: i32,
+    pub size: FontSize,
+    /// Index of the run the glyph came from
+    pub run: usize,
+}
+
+/// Position within a run list. A NUL byte ends its run.
+#[derive(Clone, Copy)]
+struct RunCursor {
+    run: usize,
+    byte: usize,
+}
+
+impl RunCursor {
+    /// Current byte and its run's size, skipping over exhausted runs.
+    fn peek(&mut self, runs: &[TextRun<'_>]) -> Option<(u8, FontSize)> {
+        while let Some(r) = runs.get(self.run) {
+            match r.text.get(self.byte) {
+                Some(&ch) if ch != 0 => return Some((ch, r.size)),
+                _ => {
+                    self.run += 1;
+                    self.byte = 0;
+                }
+            }
+        }
+        None
+    }
+
+    fn advance(&mut self) {
+        self.byte += 1;
+    }
+
+    fn at(&self, other: &RunCursor) -> bool {
+        self.run == other.run && self.byte == other.byte
+    }
+}
+
+#[inline]
+fn tab_stop(cx: i32, x: i32, size: FontSize) -> i32 {
+    let tab_width = 4 * size.glyph_width();
+    ((cx - x + tab_width) / tab_width) * tab_width + x
+}
+
+/// One laid-out line: the glyphs in `start..end`, then resume at `next`.
+struct LineSpan {
+    start: RunCursor,
+    end: RunCursor,
+    next: RunCursor,
+    /// Largest baseline offset on the line
+    ascent: i32,
+    /// Tallest glyph on the line
+    height: i32,
+    last: bool,
+}
+
+/// Find where the line starting at `start` ends, wrapping before a glyph
+/// that would cross `right`.
+fn scan_line(runs: &[TextRun<'_>], start: RunCursor, x: i32, right: i32) -> LineSpan {
+    let mut cur = start;
+    let mut cx = x;
+    let mut ascent = 0;
+    let mut height = 0;
+    loop {
+        let Some((ch, size)) = cur.peek(runs) else {
+            return LineSpan {
+                start,
+                end: cur,
+                next: cur,
+                ascent,
+                height,
+                last: true,
+            };
+        };
+        match ch {
+            b'\n' => {
+                if height == 0 {
+                    // Blank line: as tall as the text it was typed in
+                    ascent = baseline_offset(size);
+                    height = size.glyph_height();
+                }
+                let end = cur;
+                cur.advance();
+                return LineSpan {
+                    start,
+                    end,
+                    next: cur,
+                    ascent,
+                    height,
+                    last: false,
+                };
+            }
+            b'\r' => cx = x,
+            b'\t' => cx = tab_stop(cx, x, size),
+            _ => {
+                let w = size.glyph_width();
+                if cx > x && cx + w > right {
+                    return LineSpan {
+                        start,
+                        end: cur,
+                        next: cur,
+                        ascent,
+                        height,
+                        last: false,
+                    };
+                }
+                ascent = ascent.max(baseline_offset(size));
+                height = height.max(size.glyph_height());
+                cx += w;
+            }
+        }
+        cur.advance();
+    }
+}
+
+/// Lay out mixed-size text starting at (x, y), wrapping at `right` and
+/// stopping once a line would start at or below `bottom`.
+///
+/// Every glyph on a line shares one baseline, set by the line's largest
+/// glyph, and each line advances by the height of its tallest glyph.
+/// Returns the y just below the last line.
+pub fn layout_runs(
+    runs: &[TextRun<'_>],
+    x: i32,
+    y: i32,
+    right: i32,
+    bottom: i32,
+    mut place: impl FnMut(GlyphPlacement),
+) -> i32 {
+    let mut top = y;
+    let mut cur = RunCursor { run: 0, byte: 0 };
+    while top < bottom {
+        let line = scan_line(runs, cur, x, right);
+        let baseline = top + line.ascent;
+        let mut pos = line.start;
+        let mut cx = x;
+        while !pos.at(&line.end) {
+            let Some((ch, size)) = pos.peek(runs) else {
+                break;
+            };
+            if pos.at(&line.end) {
+                break;
+            }
+            match ch {
+                b'\r' => cx = x,
+                b'\t' => cx = tab_stop(cx, x, size),
+                _ => {
+                    place(GlyphPlacement {
+                        ch,
+                        x: cx,
+                        y: baseline - baseline_offset(size),
+                        size,
+                        run: pos.run,
+                    });
+                    cx += size.glyph_width();
+                }
+            }
+            pos.advance();
+        }
+        top += line.height;
+        if line.last {
+            break;
+        }
+        cur = line.next;
+    }
+    top
+}
+
+/// Draw runs of mixed-size text with aligned baselines. See `layout_runs`.
+pub fn draw_runs<T: DrawTarget>(target: &mut T, x: i32, y: i32, runs: &[TextRun<'_>]) {
+    let right = target.width() as i32;
+    let bottom = target.height() as i32;
+    layout_runs(runs, x, y, right, bottom, |g| {
+        let run = &runs[g.run];
+        draw_char_sized(target, g.x, g.y, g.ch, g.size, run.fg, run.bg);
+    });
+}
+
+/// Draw `text` with glyphs scaled by `scale`, wrapping at the target's
+/// right edge. A NUL character ends the text.
+fn draw_text<T: DrawTarget>(
+    target: &mut T,
+    x: i32,
+    y: i32,
+    text: impl IntoIterator<Item = char>,
+    scale: i32,
+    fg: u32,
+    bg: u32,
+) {
     let w = target.width() as i32;
     let h = target.height() as i32;
+    let glyph_w = FONT_CHAR_WIDTH * scale;
+    let glyph_h = FONT_CHAR_HEIGHT * scale;
     let mut cx = x;
     let mut cy = y;
 
-    for &ch in text {
+    for ch in text {
         match ch {
-            0 => break,
-            b'\n' => {
+            '\0' => break,
+            '\n' => {
                 cx = x;
-                cy += FONT_CHAR_HEIGHT;
+                cy += glyph_h;
             }
-            b'\r' => {
+            '\r' => {
                 cx = x;
             }
-            b'\t' => {
-                let tab_width = 4 * FONT_CHAR_WIDTH;
+            '\t' => {
+                let tab_width = 4 * glyph_w;
                 cx = ((cx - x + tab_width) / tab_width) * tab_width + x;
             }
             _ => {
-                draw_char(target, cx, cy, ch, fg, bg);
-                cx += FONT_CHAR_WIDTH;
-                if cx + FONT_CHAR_WIDTH > w {
+                draw_glyph(target, cx, cy, get_glyph_char_or_space(ch), scale, fg, bg);
+                cx += glyph_w;
+                if cx + glyph_w > w {
                     cx = x;
-                    cy += FONT_CHAR_HEIGHT;
+                    cy += glyph_h;
                 }
             }
         }
@@ -61,17 +356,52 @@ pub fn draw_string<T: DrawTarget>(target: &mut T, x: i32, y: i32, text: &[u8], f
     }
 }
 
+/// Draw Latin-1 `text`: each byte is its own character.
+pub fn draw_string<T: DrawTarget>(target: &mut T, x: i32, y: i32, text: &[u8], fg: u32, bg: u32) {
+    draw_text(target, x, y, text.iter().map(|&b| char::from(b)), 1, fg, bg);
+}
+
+/// `draw_string` with every glyph scaled by a whole factor; lines, tabs and
+/// wrapping advance by the scaled cell size.
+pub fn draw_string_scaled<T: DrawTarget>(
+    target: &mut T,
+    x: i32,
+    y: i32,
+    text: &[u8],
+    scale: i32,
+    fg: u32,
+    bg: u32,
+) {
+    let text = text.iter().map(|&b| char::from(b));
+    draw_text(target, x, y, text, scale.max(1), fg, bg);
+}
+
+/// Draw UTF-8 `text`, including the box-drawing characters the font has.
 #[inline]
 pub fn draw_str<T: DrawTarget>(target: &mut T, x: i32, y: i32, text: &str, fg: u32, bg: u32) {
-    draw_string(target, x, y, text.as_bytes(), fg, bg);
+    draw_text(target, x, y, text.chars(), 1, fg, bg);
 }
 
-pub fn string_width(text: &[u8]) -> i32 {
+/// `draw_str` with every glyph scaled by a whole factor.
+#[inline]
+pub fn draw_str_scaled<T: DrawTarget>(
+    target: &mut T,
+    x: i32,
+    y: i32,
+    text: &str,
+    scale: i32,
+    fg: u32,
+    bg: u32,
+) {
+    draw_text(target, x, y, text.chars(), scale.max(1), fg, bg);
+}
+
+fn text_width(text: impl IntoIterator<Item = char>) -> i32 {
     let mut width = 0i32;
-    for &ch in text {
+    for ch in text {
         match ch {
-            0 | b'\n' => break,
-            b'\t' => {
+            '\0' | '\n' => break,
+            '\t' => {
                 let tab_width = 4 * FONT_CHAR_WIDTH;
                 width = ((width + tab_width - 1) / tab_width) * tab_width;
             }
@@ -81,6 +411,10 @@ pub fn string_width(text: &[u8]) -> i32 {
     width
 }
 
+pub fn string_width(text: &[u8]) -> i32 {
+    text_width(text.iter().map(|&b| char::from(b)))
+}
+
 pub fn string_lines(text: &[u8]) -> i32 {
     let mut lines = 1i32;
     for &ch in text {
@@ -95,7 +429,7 @@ pub fn string_lines(text: &[u8]) -> i32 {
 }
 
 pub fn str_width(text: &str) -> i32 {
-    string_width(text.as_bytes())
+    text_width(text.chars())
 }
 
 pub fn str_lines(text: &str) -> i32 {
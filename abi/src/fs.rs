@@ -17,6 +17,11 @@ pub const USER_FS_OPEN_WRITE: u32 = 0x2;
 pub const USER_FS_OPEN_CREAT: u32 = 0x4;
 pub const USER_FS_OPEN_APPEND: u32 = 0x8;
 
+/// Seek origins for the fs_lseek syscall
+pub const SEEK_SET: i32 = 0;
+pub const SEEK_CUR: i32 = 1;
+pub const SEEK_END: i32 = 2;
+
 /// Filesystem directory entry information.
 ///
 /// Returned by the fs_list syscall for each entry in a directory.
@@ -68,6 +73,36 @@ impl Default for UserFsEntry {
     }
 }
 
+/// Fixed header of each record written by the fs_getdents syscall.
+///
+/// The header is followed by `name_len` name bytes and a NUL; the next
+/// record starts `reclen` bytes after this one, always 8-byte aligned.
+#[repr(C)]
+#[derive(Default, Copy, Clone)]
+pub struct UserDirent {
+    /// Inode number of the entry
+    pub inode: u64,
+    /// Total record length, header and padding included
+    pub reclen: u16,
+    /// Entry type (0 = file, 1 = directory)
+    pub type_: u8,
+    /// Name length in bytes, excluding the NUL
+    pub name_len: u8,
+    pub _pad: [u8; 4],
+}
+
+impl UserDirent {
+    /// Record length for an entry with a `name_len`-byte name.
+    pub const fn reclen(name_len: usize) -> usize {
+        (size_of::<Self>() + name_len + 1).next_multiple_of(8)
+    }
+
+    /// Check if this is a directory
+    pub fn is_directory(&self) -> bool {
+        self.type_ == FS_TYPE_DIRECTORY
+    }
+}
+
 /// Filesystem stat information.
 ///
 /// Returned by the fs_stat syscall.
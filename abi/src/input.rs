@@ -6,6 +6,17 @@ pub const MAX_INPUT_TASKS: usize = 32;
 /// Maximum events per task queue
 pub const MAX_EVENTS_PER_TASK: usize = 64;
 
+/// What a full input queue does with a newly routed event
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputOverflowPolicy {
+    /// Discard the oldest queued event so the freshest ones survive
+    #[default]
+    DropOldest = 0,
+    /// Keep the queued events and discard the new one
+    DropNewest = 1,
+}
+
 /// Focus type for input_set_focus syscall
 pub const INPUT_FOCUS_KEYBOARD: u32 = 0;
 pub const INPUT_FOCUS_POINTER: u32 = 1;
@@ -29,6 +40,8 @@ pub enum InputEventType {
     PointerEnter = 5,
     /// Pointer left surface
     PointerLeave = 6,
+    /// The surface was resized; its buffers must be mapped again
+    SurfaceResize = 7,
 }
 
 impl InputEventType {
@@ -43,6 +56,7 @@ impl InputEventType {
             4 => Some(Self::PointerButtonRelease),
             5 => Some(Self::PointerEnter),
             6 => Some(Self::PointerLeave),
+            7 => Some(Self::SurfaceResize),
             _ => None,
         }
     }
@@ -56,15 +70,43 @@ impl InputEventType {
     /// Returns true if this is a pointer event
     #[inline]
     pub fn is_pointer_event(self) -> bool {
-        !self.is_key_event()
+        matches!(
+            self,
+            Self::PointerMotion
+                | Self::PointerButtonPress
+                | Self::PointerButtonRelease
+                | Self::PointerEnter
+                | Self::PointerLeave
+        )
     }
 }
 
+pub const MOD_SHIFT: u8 = 0x01;
+pub const MOD_CTRL: u8 = 0x02;
+pub const MOD_ALT: u8 = 0x04;
+pub const MOD_CAPS_LOCK: u8 = 0x08;
+/// Right Alt, which selects the layout's AltGr column.
+pub const MOD_ALTGR: u8 = 0x10;
+
+/// One raw keyboard transition. `scancode` is the set-1 byte, break bit
+/// included; `extended` is set when it followed an 0xE0 prefix. `modifiers`
+/// holds the `MOD_*` bits in effect once the key is applied.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub scancode: u8,
+    pub ascii: u8,
+    pub modifiers: u8,
+    pub pressed: bool,
+    pub extended: bool,
+}
+
 /// Input event data (union-like structure)
 ///
 /// For key events: data0 contains scancode in low 16 bits, ASCII in high 16 bits
 /// For pointer motion: data0 is x coordinate, data1 is y coordinate
 /// For pointer button: data0 contains button code
+/// For surface resize: data0 is the new width, data1 the new height
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default)]
 pub struct InputEventData {
@@ -158,6 +200,19 @@ impl InputEvent {
         }
     }
 
+    /// Create a surface resize event
+    pub fn surface_resize(width: u32, height: u32, timestamp_ms: u64) -> Self {
+        Self {
+            event_type: InputEventType::SurfaceResize,
+            _padding: [0; 3],
+            timestamp_ms,
+            data: InputEventData {
+                data0: width,
+                data1: height,
+            },
+        }
+    }
+
     /// Extract scancode from key event
     #[inline]
     pub fn key_scancode(&self) -> u8 {
@@ -187,4 +242,10 @@ impl InputEvent {
     pub fn pointer_button_code(&self) -> u8 {
         (self.data.data0 & 0xFF) as u8
     }
+
+    /// Extract (width, height) from surface resize event
+    #[inline]
+    pub fn resize_dimensions(&self) -> (u32, u32) {
+        (self.data.data0, self.data.data1)
+    }
 }
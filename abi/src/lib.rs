@@ -14,6 +14,7 @@
 pub mod addr;
 pub mod arch;
 pub mod boot;
+pub mod composite;
 pub mod damage;
 pub mod display;
 pub mod draw;
@@ -24,7 +25,10 @@ pub mod font;
 pub mod font_render;
 pub mod fs;
 pub mod input;
+pub mod line_history;
+pub mod overlay;
 pub mod pixel;
+pub mod sched_traits;
 pub mod shm;
 pub mod surface;
 pub mod syscall;
@@ -33,9 +37,10 @@ pub mod video_traits;
 pub mod window;
 
 pub use addr::*;
+pub use composite::{CompositeSource, CompositeTarget};
 pub use damage::{
     DamageRect, DamageTracker, InternalDamageTracker, MAX_DAMAGE_REGIONS,
-    MAX_INTERNAL_DAMAGE_REGIONS,
+    MAX_INTERNAL_DAMAGE_REGIONS, MergeStrategy,
 };
 pub use display::{DisplayInfo, FramebufferData};
 pub use draw::{DamageTracking, DrawTarget, PixelBuffer, pixel_ops};
@@ -43,6 +48,7 @@ pub use error::*;
 pub use fate::FateResult;
 pub use fs::*;
 pub use input::*;
+pub use overlay::{LayerTarget, MAX_OVERLAY_PIXELS, OverlayLayer};
 pub use pixel::*;
 pub use shm::*;
 pub use surface::*;
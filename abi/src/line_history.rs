@@ -0,0 +1,108 @@
+//! Command-line history ring
+//!
+//! Keeps the most recent input lines for up/down-arrow recall. The ring is
+//! fixed-size: once full, each new line overwrites the oldest one. Browsing
+//! walks backwards from the newest entry; stepping forward past the newest
+//! returns to the (empty) line being edited.
+
+/// Number of lines remembered
+pub const LINE_HISTORY_ENTRIES: usize = 32;
+/// Longest line stored; longer input is truncated
+pub const LINE_HISTORY_LINE_MAX: usize = 256;
+
+pub struct LineHistory {
+    lines: [[u8; LINE_HISTORY_LINE_MAX]; LINE_HISTORY_ENTRIES],
+    lens: [u16; LINE_HISTORY_ENTRIES],
+    /// Slot the next line is written to
+    head: usize,
+    count: usize,
+    /// Browse position: 0 = not browsing, n = n-th most recent line
+    cursor: usize,
+}
+
+impl Default for LineHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineHistory {
+    pub const fn new() -> Self {
+        Self {
+            lines: [[0; LINE_HISTORY_LINE_MAX]; LINE_HISTORY_ENTRIES],
+            lens: [0; LINE_HISTORY_ENTRIES],
+            head: 0,
+            count: 0,
+            cursor: 0,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The `n`-th most recent line (1 = newest).
+    fn entry(&self, n: usize) -> &[u8] {
+        let slot = (self.head + LINE_HISTORY_ENTRIES - n) % LINE_HISTORY_ENTRIES;
+        &self.lines[slot][..self.lens[slot] as usize]
+    }
+
+    /// Record a submitted line and stop browsing.
+    ///
+    /// Blank lines and repeats of the newest entry are not stored. Returns
+    /// true if the line was added.
+    pub fn push(&mut self, line: &[u8]) -> bool {
+        self.cursor = 0;
+        if line.iter().all(|&b| b == b' ' || b == b'\t') {
+            return false;
+        }
+        let line = &line[..line.len().min(LINE_HISTORY_LINE_MAX)];
+        if self.count > 0 && self.entry(1) == line {
+            return false;
+        }
+        let slot = self.head;
+        self.lines[slot][..line.len()].copy_from_slice(line);
+        self.lens[slot] = line.len() as u16;
+        self.head = (self.head + 1) % LINE_HISTORY_ENTRIES;
+        self.count = (self.count + 1).min(LINE_HISTORY_ENTRIES);
+        true
+    }
+
+    /// Step to the next older line (up arrow).
+    ///
+    /// Stays on the oldest line once reached; `None` only if empty.
+    pub fn older(&mut self) -> Option<&[u8]> {
+        if self.count == 0 {
+            return None;
+        }
+        if self.cursor < self.count {
+            self.cursor += 1;
+        }
+        Some(self.entry(self.cursor))
+    }
+
+    /// Step to the next newer line (down arrow).
+    ///
+    /// Returns `None` when moving past the newest line, i.e. back to an
+    /// empty input line.
+    pub fn newer(&mut self) -> Option<&[u8]> {
+        if self.cursor <= 1 {
+            self.cursor = 0;
+            return None;
+        }
+        self.cursor -= 1;
+        Some(self.entry(self.cursor))
+    }
+
+    /// Stop browsing without recording anything.
+    #[inline]
+    pub fn reset_cursor(&mut self) {
+        self.cursor = 0;
+    }
+}
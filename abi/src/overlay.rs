@@ -0,0 +1,221 @@
+//! Overlay layer for cursors and tooltips
+//!
+//! The overlay is a small pixel block composited after every window so it
+//! always sits on top. It keeps a save-under copy of the pixels it covers,
+//! letting it move or disappear without the windows below being redrawn.
+
+use crate::damage::DamageRect;
+
+/// Maximum number of pixels an overlay can hold (e.g. 32x32)
+pub const MAX_OVERLAY_PIXELS: usize = 32 * 32;
+
+/// Raw pixel buffer an overlay composites onto (3 or 4 bytes per pixel).
+pub struct LayerTarget<'a> {
+    pub data: &'a mut [u8],
+    pub width: u32,
+    pub height: u32,
+    pub pitch: usize,
+    pub bytes_pp: usize,
+}
+
+impl LayerTarget<'_> {
+    #[inline]
+    fn offset(&self, x: i32, y: i32) -> Option<usize> {
+        if !(3..=4).contains(&self.bytes_pp) {
+            return None;
+        }
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+        let off = (y as usize) * self.pitch + (x as usize) * self.bytes_pp;
+        if off + self.bytes_pp <= self.data.len() {
+            Some(off)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn read(&self, off: usize) -> u32 {
+        let mut bytes = [0u8; 4];
+        bytes[..self.bytes_pp].copy_from_slice(&self.data[off..off + self.bytes_pp]);
+        u32::from_le_bytes(bytes)
+    }
+
+    #[inline]
+    fn write(&mut self, off: usize, pixel: u32) {
+        let bytes = pixel.to_le_bytes();
+        self.data[off..off + self.bytes_pp].copy_from_slice(&bytes[..self.bytes_pp]);
+    }
+}
+
+/// Topmost compositor layer with save-under.
+///
+/// Pixels are stored pre-converted to the target's format. Pixels equal to
+/// the optional color key are transparent and leave the target untouched.
+pub struct OverlayLayer {
+    content: [u32; MAX_OVERLAY_PIXELS],
+    saved: [u32; MAX_OVERLAY_PIXELS],
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    key: Option<u32>,
+    active: bool,
+    /// `saved` holds the pixels currently covered in the target
+    shown: bool,
+}
+
+impl Default for OverlayLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OverlayLayer {
+    /// Create an empty, inactive overlay
+    pub const fn new() -> Self {
+        Self {
+            content: [0; MAX_OVERLAY_PIXELS],
+            saved: [0; MAX_OVERLAY_PIXELS],
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+            key: None,
+            active: false,
+            shown: false,
+        }
+    }
+
+    /// Check whether an overlay is set
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Get the overlay rectangle as (x, y, width, height), if set
+    #[inline]
+    pub fn bounds(&self) -> Option<(i32, i32, u32, u32)> {
+        self.active
+            .then_some((self.x, self.y, self.width, self.height))
+    }
+
+    /// Area the overlay covers, for damage
+    pub fn damage_rect(&self) -> Option<DamageRect> {
+        let (x, y, w, h) = self.bounds()?;
+        Some(DamageRect {
+            x0: x,
+            y0: y,
+            x1: x + w as i32 - 1,
+            y1: y + h as i32 - 1,
+        })
+    }
+
+    /// Set the transparent color key (pixel value in target format)
+    #[inline]
+    pub fn set_color_key(&mut self, key: Option<u32>) {
+        self.key = key;
+    }
+
+    /// Replace the overlay with `pixels` (row-major, `w * h` entries) at
+    /// (x, y), restoring whatever the previous overlay covered first.
+    ///
+    /// Returns false, leaving the overlay unchanged, if the size is zero,
+    /// exceeds `MAX_OVERLAY_PIXELS`, or `pixels` is too short.
+    pub fn set_overlay(
+        &mut self,
+        target: &mut LayerTarget<'_>,
+        pixels: &[u32],
+        x: i32,
+        y: i32,
+        w: u32,
+        h: u32,
+    ) -> bool {
+        let count = (w as usize) * (h as usize);
+        if count == 0 || count > MAX_OVERLAY_PIXELS || pixels.len() < count {
+            return false;
+        }
+        self.restore(target);
+        self.content[..count].copy_from_slice(&pixels[..count]);
+        self.x = x;
+        self.y = y;
+        self.width = w;
+        self.height = h;
+        self.active = true;
+        self.draw(target);
+        true
+    }
+
+    /// Move the overlay, restoring the pixels it uncovers
+    pub fn move_overlay(&mut self, target: &mut LayerTarget<'_>, x: i32, y: i32) {
+        self.restore(target);
+        self.x = x;
+        self.y = y;
+        self.draw(target);
+    }
+
+    /// Move the overlay and return the spots to repair on screen: the one
+    /// it left and the one it now covers
+    pub fn move_and_damage(
+        &mut self,
+        target: &mut LayerTarget<'_>,
+        x: i32,
+        y: i32,
+    ) -> [Option<DamageRect>; 2] {
+        let old = self.damage_rect();
+        self.move_overlay(target, x, y);
+        [old, self.damage_rect()]
+    }
+
+    /// Remove the overlay, restoring the pixels underneath
+    pub fn clear_overlay(&mut self, target: &mut LayerTarget<'_>) {
+        self.restore(target);
+        self.active = false;
+    }
+
+    /// Draw the overlay at (x, y) onto a frame that was just repainted.
+    ///
+    /// The previous save-under is stale at that point, so it is dropped
+    /// rather than restored.
+    pub fn composite_at(&mut self, target: &mut LayerTarget<'_>, x: i32, y: i32) {
+        self.shown = false;
+        self.x = x;
+        self.y = y;
+        self.draw(target);
+    }
+
+    fn restore(&mut self, target: &mut LayerTarget<'_>) {
+        if !self.shown {
+            return;
+        }
+        for row in 0..self.height as i32 {
+            for col in 0..self.width as i32 {
+                if let Some(off) = target.offset(self.x + col, self.y + row) {
+                    let idx = (row as usize) * (self.width as usize) + col as usize;
+                    target.write(off, self.saved[idx]);
+                }
+            }
+        }
+        self.shown = false;
+    }
+
+    fn draw(&mut self, target: &mut LayerTarget<'_>) {
+        if !self.active {
+            return;
+        }
+        for row in 0..self.height as i32 {
+            for col in 0..self.width as i32 {
+                if let Some(off) = target.offset(self.x + col, self.y + row) {
+                    let idx = (row as usize) * (self.width as usize) + col as usize;
+                    self.saved[idx] = target.read(off);
+                    let pixel = self.content[idx];
+                    if self.key != Some(pixel) {
+                        target.write(off, pixel);
+                    }
+                }
+            }
+        }
+        self.shown = true;
+    }
+}
@@ -96,6 +96,36 @@ impl PixelFormat {
         }
     }
 
+    /// Decode one pixel stored in this format into 0xRRGGBBAA, the input
+    /// layout of `convert_color`. Formats without alpha decode as opaque.
+    ///
+    /// `bytes` must hold at least `bytes_per_pixel()` bytes.
+    #[inline]
+    pub fn decode_pixel(self, bytes: &[u8]) -> u32 {
+        let v = match self.bytes_per_pixel() {
+            3 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]),
+            _ => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        };
+        let (r, g, b, a) = match self {
+            Self::Argb8888 => (v >> 16, v >> 8, v, v >> 24),
+            Self::Xrgb8888 | Self::Rgb888 => (v >> 16, v >> 8, v, 0xFF),
+            Self::Bgr888 => (v, v >> 8, v >> 16, 0xFF),
+            Self::Rgba8888 => (v >> 24, v >> 16, v >> 8, v),
+            Self::Bgra8888 => (v >> 8, v >> 16, v >> 24, v),
+        };
+        ((r & 0xFF) << 24) | ((g & 0xFF) << 16) | ((b & 0xFF) << 8) | (a & 0xFF)
+    }
+
+    /// Store a 0xRRGGBBAA color into `out` using this format's memory layout.
+    ///
+    /// `out` must hold at least `bytes_per_pixel()` bytes.
+    #[inline]
+    pub fn encode_pixel(self, rgba: u32, out: &mut [u8]) {
+        let bytes = self.convert_color(rgba).to_le_bytes();
+        let n = self.bytes_per_pixel() as usize;
+        out[..n].copy_from_slice(&bytes[..n]);
+    }
+
     /// Get a bitmap of all supported formats
     ///
     /// Returns a u32 where bit N is set if format with value N is supported.
@@ -125,6 +155,8 @@ pub enum DrawPixelFormat {
     Rgba,
     /// BGRA with alpha
     Bgra,
+    /// 16-bit 5-6-5 RGB, red in the top bits
+    Rgb565,
 }
 
 impl DrawPixelFormat {
@@ -132,7 +164,8 @@ impl DrawPixelFormat {
     #[inline]
     pub fn from_bpp(bpp: u8) -> Self {
         match bpp {
-            16 | 24 => Self::Rgb,
+            16 => Self::Rgb565,
+            24 => Self::Rgb,
             32 => Self::Rgba,
             _ => Self::Rgb,
         }
@@ -162,7 +195,34 @@ impl DrawPixelFormat {
                     | ((color & 0x0000FF) << 16)
                     | (color & 0xFF000000)
             }
+            Self::Rgb565 => {
+                let r = (color >> 19) & 0x1F;
+                let g = (color >> 10) & 0x3F;
+                let b = (color >> 3) & 0x1F;
+                (r << 11) | (g << 5) | b
+            }
             _ => color,
         }
     }
+
+    /// Convert a raw pixel value in this format back to 0xAARRGGBB.
+    ///
+    /// Inverse of `convert_color`. 5-6-5 channels are widened by repeating
+    /// their top bits, so full intensity stays 0xFF; alpha reads as opaque.
+    #[inline]
+    pub fn decode_color(self, raw: u32) -> u32 {
+        match self {
+            Self::Rgb565 => {
+                let r = (raw >> 11) & 0x1F;
+                let g = (raw >> 5) & 0x3F;
+                let b = raw & 0x1F;
+                let r = (r << 3) | (r >> 2);
+                let g = (g << 2) | (g >> 4);
+                let b = (b << 3) | (b >> 2);
+                0xFF00_0000 | (r << 16) | (g << 8) | b
+            }
+            // The byte swaps are their own inverse
+            _ => self.convert_color(raw),
+        }
+    }
 }
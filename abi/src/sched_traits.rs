@@ -0,0 +1,91 @@
+//! Pluggable scheduling policies.
+//!
+//! The scheduler owns the ready queues; a policy only decides which of the
+//! offered candidates runs next. Candidates arrive in queue order: highest
+//! priority first, FIFO within a priority level. Keeping the decision pure
+//! lets policies be tested without a running scheduler.
+
+/// Snapshot of a runnable task offered to a policy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadyTask {
+    pub task_id: u32,
+    /// Lower values are more important (0 = highest)
+    pub priority: u8,
+    /// Ticks consumed so far
+    pub total_runtime: u64,
+    /// Timestamp the task last ran at (0 if it never ran)
+    pub last_run_timestamp: u64,
+    /// Time slice length in ticks
+    pub time_slice: u64,
+}
+
+impl ReadyTask {
+    /// Virtual deadline: the end of the slice the task is owed since it last ran.
+    #[inline]
+    pub fn deadline(&self) -> u64 {
+        self.last_run_timestamp.saturating_add(self.time_slice)
+    }
+}
+
+/// Next-task selection strategy.
+pub trait SchedPolicy: Sync {
+    /// Short name for diagnostics
+    fn name(&self) -> &'static str;
+
+    /// Pick the index into `ready` of the task to run next.
+    ///
+    /// `ready` is never empty. Returning `None` or an out-of-range index makes
+    /// the scheduler fall back to the first candidate.
+    fn pick_next(&self, ready: &[ReadyTask]) -> Option<usize>;
+}
+
+/// Strict priority, FIFO within a level (the default).
+pub struct RoundRobin;
+
+impl SchedPolicy for RoundRobin {
+    fn name(&self) -> &'static str {
+        "round-robin"
+    }
+
+    fn pick_next(&self, _ready: &[ReadyTask]) -> Option<usize> {
+        Some(0)
+    }
+}
+
+/// Earliest virtual deadline first within the highest ready priority.
+pub struct EarliestDeadline;
+
+impl SchedPolicy for EarliestDeadline {
+    fn name(&self) -> &'static str {
+        "edf"
+    }
+
+    fn pick_next(&self, ready: &[ReadyTask]) -> Option<usize> {
+        let top = ready.first()?.priority;
+        ready
+            .iter()
+            .enumerate()
+            .take_while(|(_, t)| t.priority == top)
+            .min_by_key(|(_, t)| t.deadline())
+            .map(|(i, _)| i)
+    }
+}
+
+/// Least accumulated runtime first within the highest ready priority.
+pub struct FairRoundRobin;
+
+impl SchedPolicy for FairRoundRobin {
+    fn name(&self) -> &'static str {
+        "fair-rr"
+    }
+
+    fn pick_next(&self, ready: &[ReadyTask]) -> Option<usize> {
+        let top = ready.first()?.priority;
+        ready
+            .iter()
+            .enumerate()
+            .take_while(|(_, t)| t.priority == top)
+            .min_by_key(|(_, t)| t.total_runtime)
+            .map(|(i, _)| i)
+    }
+}
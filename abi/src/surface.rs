@@ -8,6 +8,29 @@ pub const WINDOW_STATE_MAXIMIZED: u8 = 2;
 /// Maximum number of child subsurfaces per surface
 pub const MAX_CHILDREN: usize = 8;
 
+/// How a surface's pixels are combined with what lies beneath it.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SurfaceBlendMode {
+    /// Pixels replace the destination; any alpha channel is ignored
+    #[default]
+    Opaque = 0,
+    /// Pixels are blended over the destination by their alpha channel
+    AlphaBlend = 1,
+}
+
+impl SurfaceBlendMode {
+    /// Convert from raw u8 value
+    #[inline]
+    pub fn from_u8(val: u8) -> Option<Self> {
+        match val {
+            0 => Some(Self::Opaque),
+            1 => Some(Self::AlphaBlend),
+            _ => None,
+        }
+    }
+}
+
 /// Role of a surface in the compositor hierarchy.
 ///
 /// Corresponds to Wayland's xdg_toplevel, xdg_popup, and wl_subsurface roles.
@@ -26,6 +26,20 @@ pub const SYSCALL_WRITE: u64 = 2;
 pub const SYSCALL_READ: u64 = 3;
 pub const SYSCALL_ROULETTE: u64 = 4;
 pub const SYSCALL_SLEEP_MS: u64 = 5;
+/// Sleep for at least arg0 nanoseconds, rounded up to whole timer ticks.
+/// The task is blocked and takes no CPU until its deadline has passed.
+///
+/// # Returns
+/// * 0 after the sleep
+/// * On error: -1
+pub const SYSCALL_NANOSLEEP: u64 = 97;
+/// Read the wall-clock time (arg0: pointer to a `UserTimeval`). Seconds come
+/// from the RTC; microseconds are interpolated from the TSC.
+///
+/// # Returns
+/// * 0 on success
+/// * On error: -1 for a bad pointer
+pub const SYSCALL_GETTIMEOFDAY: u64 = 98;
 pub const SYSCALL_FB_INFO: u64 = 6;
 
 // =============================================================================
@@ -49,15 +63,57 @@ pub const SYSCALL_FS_MKDIR: u64 = 19;
 pub const SYSCALL_FS_UNLINK: u64 = 20;
 pub const SYSCALL_FS_LIST: u64 = 21;
 
+/// Read directory entries from an open directory descriptor (arg0: fd,
+/// arg1: buffer, arg2: buffer length) as packed `UserDirent` records.
+/// Later calls continue where the previous one stopped.
+///
+/// # Returns
+/// * Bytes written on success, 0 at the end of the directory
+/// * On error: -1, also when the next entry does not fit the buffer
+pub const SYSCALL_FS_GETDENTS: u64 = 93;
+
+/// Move the offset of an open file (arg0: fd, arg1: signed offset,
+/// arg2: `SEEK_SET`, `SEEK_CUR` or `SEEK_END`). Seeking past the end is
+/// allowed; reads there return 0.
+///
+/// # Returns
+/// * The new offset on success
+/// * On error: -1, with the offset unchanged, also when it would go negative
+pub const SYSCALL_FS_LSEEK: u64 = 94;
+
+/// Create an anonymous pipe (arg0: pointer to `[i32; 2]`). The read end is
+/// stored in element 0 and the write end in element 1. Reads of an empty
+/// pipe block while a write end is open and return 0 after that. Writes to
+/// a full pipe block. Writes with no read end left fail with `-EPIPE`.
+///
+/// # Returns
+/// * 0 on success
+/// * On error: -1
+pub const SYSCALL_FS_PIPE: u64 = 95;
+
 // =============================================================================
 // System
 // =============================================================================
 
 pub const SYSCALL_SYS_INFO: u64 = 22;
+/// List the PCI devices found at boot (arg0: array of `PciDeviceInfo`, arg1:
+/// its length). Only tasks spawned with `TASK_FLAG_SYSTEM` may call it.
+///
+/// # Returns
+/// * Number of devices written, at most arg1
+/// * On error: -1 for a bad buffer or an unprivileged caller
+pub const SYSCALL_PCI_ENUMERATE: u64 = 99;
 pub const SYSCALL_HALT: u64 = 23;
 pub const SYSCALL_READ_CHAR: u64 = 25;
 pub const SYSCALL_TTY_SET_FOCUS: u64 = 28;
 pub const SYSCALL_GET_TIME_MS: u64 = 39;
+/// Set the TTY line mode (arg0: nonzero for canonical, 0 for raw). Only the
+/// task holding TTY focus may change it.
+///
+/// # Returns
+/// * 0 on success
+/// * -1 if another task holds focus
+pub const SYSCALL_TTY_SET_MODE: u64 = 109;
 
 // =============================================================================
 // Window management
@@ -79,6 +135,13 @@ pub const SYSCALL_INPUT_SET_FOCUS: u64 = 62;
 pub const SYSCALL_INPUT_SET_FOCUS_WITH_OFFSET: u64 = 65;
 pub const SYSCALL_INPUT_GET_POINTER_POS: u64 = 66;
 pub const SYSCALL_INPUT_GET_BUTTON_STATE: u64 = 67;
+/// Select the keyboard layout by name (arg0: name, arg1: its length), one of
+/// "us", "uk" or "de". Only tasks spawned with `TASK_FLAG_SYSTEM` may call it.
+///
+/// # Returns
+/// * 0 on success
+/// * -1 for an unknown layout, a bad buffer or an unprivileged caller
+pub const SYSCALL_KEYBOARD_SET_LAYOUT: u64 = 103;
 
 // =============================================================================
 // Surface / Compositor
@@ -95,6 +158,54 @@ pub const SYSCALL_SURFACE_SET_ROLE: u64 = 57;
 pub const SYSCALL_SURFACE_SET_PARENT: u64 = 58;
 pub const SYSCALL_SURFACE_SET_REL_POS: u64 = 59;
 pub const SYSCALL_SURFACE_SET_TITLE: u64 = 63;
+pub const SYSCALL_SURFACE_SET_COLOR_KEY: u64 = 84;
+/// Returns 1 if a frame including the caller's surface was presented since
+/// the last call, 0 otherwise. Reading clears the flag.
+pub const SYSCALL_SURFACE_FRAME_DONE: u64 = 86;
+/// Attach a back buffer (token, width, height) to the caller's surface;
+/// token 0 detaches it.
+pub const SYSCALL_SURFACE_ATTACH_BACK: u64 = 87;
+/// Commit by swapping the front and back buffers instead of copying.
+pub const SYSCALL_SURFACE_COMMIT_SWAP: u64 = 88;
+/// Attach a spare buffer (token, width, height) to the caller's surface,
+/// making it triple buffered. Token 0 detaches the spare and returns its
+/// token, which is the only buffer safe to free; this fails with `Busy`
+/// while commits are still queued.
+pub const SYSCALL_SURFACE_ATTACH_SPARE: u64 = 100;
+/// Resize the caller's surface (width, height). Buffers are reallocated;
+/// old mappings keep the old pixels until unmapped, and the buffers must be
+/// mapped again after the `SurfaceResize` input event arrives.
+pub const SYSCALL_SURFACE_RESIZE: u64 = 101;
+/// Copy the screen into a user buffer (ptr, len, stride) as BGRA8888.
+/// Stride 0 packs rows tightly; the buffer must hold the whole frame.
+pub const SYSCALL_COMPOSITOR_CAPTURE: u64 = 102;
+/// Copy up to `max` visible windows' `WindowInfo` into a user buffer and
+/// return the count. Open to any task; buffer tokens and damage are zeroed.
+pub const SYSCALL_LIST_WINDOWS: u64 = 89;
+/// Set the caller's surface blend mode (`SurfaceBlendMode` value).
+pub const SYSCALL_SURFACE_SET_BLEND_MODE: u64 = 90;
+/// Open a damage batch on the caller's surface: damage and commits are held
+/// until `SURFACE_END_FRAME` and composited once.
+pub const SYSCALL_SURFACE_BEGIN_FRAME: u64 = 104;
+/// Close the open damage batch and commit it; ignored when no batch is
+/// open.
+pub const SYSCALL_SURFACE_END_FRAME: u64 = 105;
+/// Downscale a window (task_id, max_w, max_h, ptr, len) into a user buffer,
+/// tightly packed in the window's pixel format. Bounds are capped at
+/// `THUMBNAIL_MAX_DIM`. Returns `width << 16 | height`. Compositor only.
+pub const SYSCALL_SURFACE_THUMBNAIL: u64 = 106;
+/// Largest thumbnail edge `SURFACE_THUMBNAIL` accepts.
+pub const THUMBNAIL_MAX_DIM: u32 = 256;
+/// Token of the buffer the caller should draw its next frame into, or 0 if
+/// its surface has no back buffer. Changes after every swap commit.
+pub const SYSCALL_SURFACE_BACK_BUFFER: u64 = 107;
+/// Pop the oldest raw keyboard event into arg0 (a `KeyEvent`). Only tasks
+/// spawned with `TASK_FLAG_SYSTEM` may call it.
+///
+/// # Returns
+/// * 1 if an event was written, 0 if none was queued
+/// * -1 for a bad buffer or an unprivileged caller
+pub const SYSCALL_KEYBOARD_POLL_EVENT: u64 = 108;
 
 // =============================================================================
 // Shared memory
@@ -124,6 +235,9 @@ pub const SYSCALL_SPAWN_TASK: u64 = 64;
 
 /// Execute an ELF binary from the filesystem, replacing the current process.
 ///
+/// Files starting with `#!` are run through the named interpreter, with the
+/// script path inserted into argv.
+///
 /// # Arguments (via registers)
 /// * rdi (arg0): Pointer to null-terminated path string
 /// * rsi (arg1): Pointer to null-terminated argv array (or NULL)
@@ -135,6 +249,7 @@ pub const SYSCALL_SPAWN_TASK: u64 = 64;
 /// * -ENOEXEC: Not a valid ELF executable
 /// * -ENOMEM: Insufficient memory
 /// * -EFAULT: Invalid pointer
+/// * -ELOOP: `#!` interpreters nested more than four deep
 pub const SYSCALL_EXEC: u64 = 70;
 
 // =============================================================================
@@ -143,6 +258,28 @@ pub const SYSCALL_EXEC: u64 = 70;
 
 pub const SYSCALL_BRK: u64 = 71;
 
+/// Move the program break by a signed increment.
+///
+/// # Returns
+/// * The previous break on success
+/// * On error: -1, with the break unchanged
+pub const SYSCALL_SBRK: u64 = 85;
+
+/// Map anonymous zeroed memory (arg0: length, a nonzero page multiple).
+///
+/// # Returns
+/// * The page-aligned base address on success
+/// * On error: -1
+pub const SYSCALL_MMAP: u64 = 91;
+
+/// Unmap memory from `SYSCALL_MMAP` (arg0: address, arg1: length, both
+/// page-aligned).
+///
+/// # Returns
+/// * 0 on success
+/// * On error: -1
+pub const SYSCALL_MUNMAP: u64 = 92;
+
 // =============================================================================
 // Process management
 // =============================================================================
@@ -155,15 +292,43 @@ pub const SYSCALL_BRK: u64 = 71;
 /// * On error: negative error code
 pub const SYSCALL_FORK: u64 = 72;
 
+/// Wait for a child task to exit (arg0: child task ID, arg1: pointer to an
+/// `i32` for the exit code or null, arg2: options, see `WNOHANG`). A child's
+/// exit code is kept until it is collected once.
+///
+/// # Returns
+/// * The child's task ID once it has exited and been reaped
+/// * 0 with `WNOHANG` while the child is still running
+/// * On error: -1 if the ID is not an unreaped child of the caller
+pub const SYSCALL_WAITPID: u64 = 96;
+
+/// `SYSCALL_WAITPID` option: return 0 instead of blocking.
+pub const WNOHANG: u64 = 1;
+
 // =============================================================================
 // SMP / CPU Affinity
 // =============================================================================
 
 pub const SYSCALL_GET_CPU_COUNT: u64 = 80;
 pub const SYSCALL_GET_CURRENT_CPU: u64 = 81;
+/// Pin a task to a set of CPUs (arg0: task ID or 0 for the caller, arg1:
+/// mask with bit n allowing CPU n). An empty mask is rejected with -1.
 pub const SYSCALL_SET_CPU_AFFINITY: u64 = 82;
 pub const SYSCALL_GET_CPU_AFFINITY: u64 = 83;
 
+// =============================================================================
+// Error codes
+// =============================================================================
+
+/// Returned (negated) in rax when no handler is installed for the syscall number.
+pub const ENOSYS: u64 = 38;
+
+/// Returned (negated) in rax by writes to a pipe whose read ends are all closed.
+pub const EPIPE: u64 = 32;
+
+/// Highest syscall number (exclusive) the dispatch table can hold.
+pub const SYSCALL_TABLE_SIZE: usize = 128;
+
 // =============================================================================
 // Syscall data structures
 // =============================================================================
@@ -183,3 +348,13 @@ pub struct UserSysInfo {
     pub ready_tasks: u32,
     pub schedule_calls: u32,
 }
+
+/// Wall-clock time returned by SYSCALL_GETTIMEOFDAY
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UserTimeval {
+    /// Seconds since 1970-01-01 00:00:00 UTC
+    pub tv_sec: i64,
+    /// Microseconds into the current second (0..1_000_000)
+    pub tv_usec: i64,
+}
@@ -95,6 +95,17 @@ impl TaskStatus {
     }
 }
 
+/// Reason a task state transition was refused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateError {
+    /// The state machine does not allow moving from `from` to `to`
+    Illegal { from: TaskStatus, to: TaskStatus },
+    /// Another CPU changed the state between the check and the update
+    Raced { observed: TaskStatus },
+    /// The task is already in the requested state; nothing changed
+    Unchanged { state: TaskStatus },
+}
+
 // =============================================================================
 // BlockReason - Why a task is blocked
 // =============================================================================
@@ -153,6 +164,9 @@ pub const TASK_PRIORITY_NORMAL: u8 = 1;
 pub const TASK_PRIORITY_LOW: u8 = 2;
 pub const TASK_PRIORITY_IDLE: u8 = 3;
 
+/// `Task::cpu_affinity` value that places no restriction on the CPU.
+pub const TASK_AFFINITY_ANY: u32 = 0;
+
 // =============================================================================
 // Task Flag Constants
 // =============================================================================
@@ -409,6 +423,9 @@ pub enum TaskExitReason {
     Kernel = 3,
 }
 
+/// Exit code reported for a task that was terminated without exiting itself.
+pub const TASK_EXIT_CODE_KILLED: i32 = -9;
+
 /// Specific fault that caused task termination.
 #[repr(u16)]
 #[derive(Clone, Copy, PartialEq, Eq, Default)]
@@ -456,23 +473,34 @@ pub struct Task {
     pub fpu_state: FpuState,
     pub time_slice: u64,
     pub time_slice_remaining: u64,
+    /// TSC cycles spent running, charged to the task when it is switched out.
     pub total_runtime: u64,
     pub creation_time: u64,
     pub yield_count: u32,
+    /// Timestamp the current run began at, or 0 while the task is not running.
     pub last_run_timestamp: u64,
     pub waiting_on_task_id: u32,
+    /// Task allowed to reap this one with waitpid, or `INVALID_TASK_ID`.
+    pub parent_task_id: u32,
     pub user_started: u8,
     pub context_from_user: u8,
     pub exit_reason: TaskExitReason,
     pub fault_reason: TaskFaultReason,
-    pub exit_code: u32,
+    pub exit_code: i32,
     pub fate_token: u32,
     pub fate_value: u32,
     pub fate_pending: u8,
+    /// CPUs the task may run on: bit n allows CPU n, `TASK_AFFINITY_ANY`
+    /// allows every CPU.
     pub cpu_affinity: u32,
     pub last_cpu: u8,
     pub migration_count: u32,
     pub switch_ctx: SwitchContext,
+    /// Ready-queue level the task is queued at: `priority`, or higher while
+    /// an aging boost is in effect. `priority` itself is never changed.
+    pub effective_priority: u8,
+    /// Ticks spent waiting in a ready queue since the task was last queued.
+    pub ready_wait_ticks: u32,
     pub next_ready: *mut Task,
 }
 
@@ -504,6 +532,7 @@ impl Task {
             yield_count: 0,
             last_run_timestamp: 0,
             waiting_on_task_id: INVALID_TASK_ID,
+            parent_task_id: INVALID_TASK_ID,
             user_started: 0,
             context_from_user: 0,
             exit_reason: TaskExitReason::None,
@@ -512,14 +541,23 @@ impl Task {
             fate_token: 0,
             fate_value: 0,
             fate_pending: 0,
-            cpu_affinity: 0,
+            cpu_affinity: TASK_AFFINITY_ANY,
             last_cpu: 0,
             migration_count: 0,
             switch_ctx: SwitchContext::zero(),
+            effective_priority: TASK_PRIORITY_NORMAL,
+            ready_wait_ticks: 0,
             next_ready: ptr::null_mut(),
         }
     }
 
+    /// Whether the affinity mask lets the task run on `cpu_id`.
+    #[inline]
+    pub fn allows_cpu(&self, cpu_id: usize) -> bool {
+        self.cpu_affinity == TASK_AFFINITY_ANY
+            || (cpu_id < u32::BITS as usize && self.cpu_affinity & (1 << cpu_id) != 0)
+    }
+
     #[inline]
     pub fn state(&self) -> u8 {
         self.state_atomic.load(Ordering::Acquire)
@@ -540,23 +578,31 @@ impl Task {
         self.set_state(status.as_u8());
     }
 
+    /// Move to `target` if the state machine allows it. This is not
+    /// idempotent: asking for the current state is refused with `Unchanged`,
+    /// so callers such as wakers only act (and enqueue) after a real state
+    /// change. Terminated -> Terminated is the one exception and succeeds.
     #[inline]
-    pub fn try_transition_to(&self, target: TaskStatus) -> bool {
+    pub fn transition(&self, target: TaskStatus) -> Result<(), StateError> {
         let current = self.state();
-        let current_status = TaskStatus::from_u8(current);
-        if current_status.can_transition_to(target) {
-            match self.state_atomic.compare_exchange(
-                current,
-                target.as_u8(),
-                Ordering::AcqRel,
-                Ordering::Acquire,
-            ) {
-                Ok(_) => true,
-                Err(_) => false,
-            }
-        } else {
-            false
+        let from = TaskStatus::from_u8(current);
+        if from == target && !from.can_transition_to(target) {
+            return Err(StateError::Unchanged { state: from });
+        }
+        if !from.can_transition_to(target) {
+            return Err(StateError::Illegal { from, to: target });
         }
+        self.state_atomic
+            .compare_exchange(current, target.as_u8(), Ordering::AcqRel, Ordering::Acquire)
+            .map(|_| ())
+            .map_err(|observed| StateError::Raced {
+                observed: TaskStatus::from_u8(observed),
+            })
+    }
+
+    #[inline]
+    pub fn try_transition_to(&self, target: TaskStatus) -> bool {
+        self.transition(target).is_ok()
     }
 
     #[inline]
@@ -589,6 +635,21 @@ impl Task {
         }
     }
 
+    /// Undo a block that never took effect: the task is still on its CPU,
+    /// so it goes straight back to Running. Fails once a waker has moved it
+    /// to Ready, in which case the waker has also queued it.
+    #[inline]
+    pub fn cancel_block(&self) -> bool {
+        self.state_atomic
+            .compare_exchange(
+                TaskStatus::Blocked.as_u8(),
+                TaskStatus::Running.as_u8(),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+    }
+
     #[inline]
     pub fn block_atomic(&self) -> bool {
         self.try_transition_to(TaskStatus::Blocked)
@@ -645,6 +706,7 @@ impl Task {
         self.yield_count = other.yield_count;
         self.last_run_timestamp = other.last_run_timestamp;
         self.waiting_on_task_id = other.waiting_on_task_id;
+        self.parent_task_id = other.parent_task_id;
         self.user_started = other.user_started;
         self.context_from_user = other.context_from_user;
         self.exit_reason = other.exit_reason;
@@ -657,6 +719,8 @@ impl Task {
         self.last_cpu = other.last_cpu;
         self.migration_count = other.migration_count;
         self.switch_ctx = other.switch_ctx;
+        self.effective_priority = other.effective_priority;
+        self.ready_wait_ticks = other.ready_wait_ticks;
         self.next_ready = other.next_ready;
     }
 }
@@ -670,9 +734,11 @@ impl Task {
 #[derive(Clone, Copy)]
 pub struct TaskExitRecord {
     pub task_id: u32,
+    /// Parent at exit time; `INVALID_TASK_ID` once nobody can reap it.
+    pub parent_task_id: u32,
     pub exit_reason: TaskExitReason,
     pub fault_reason: TaskFaultReason,
-    pub exit_code: u32,
+    pub exit_code: i32,
 }
 
 impl TaskExitRecord {
@@ -680,6 +746,7 @@ impl TaskExitRecord {
     pub const fn empty() -> Self {
         Self {
             task_id: INVALID_TASK_ID,
+            parent_task_id: INVALID_TASK_ID,
             exit_reason: TaskExitReason::None,
             fault_reason: TaskFaultReason::None,
             exit_code: 0,
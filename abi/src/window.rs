@@ -1,5 +1,8 @@
 //! Window and damage region types
 
+use crate::pixel::PixelFormat;
+use crate::surface::SurfaceBlendMode;
+
 pub use crate::damage::{
     MAX_DAMAGE_REGIONS as MAX_WINDOW_DAMAGE_REGIONS, MAX_INTERNAL_DAMAGE_REGIONS,
 };
@@ -7,6 +10,42 @@ pub use crate::damage::{
 /// Maximum buffer age before it's considered invalid (for damage accumulation)
 pub const MAX_BUFFER_AGE: u8 = 8;
 
+/// `WindowInfo::flags` bit: `color_key` is valid and matching pixels are transparent
+pub const WINDOW_FLAG_COLOR_KEY: u8 = 1 << 0;
+
+/// `WindowInfo::flags` bit: pixels are alpha-blended over what is beneath
+pub const WINDOW_FLAG_ALPHA_BLEND: u8 = 1 << 1;
+
+/// `WindowInfo::format` value for surfaces laid out like the display
+pub const WINDOW_FORMAT_NATIVE: u8 = 0xFF;
+
+/// Size of a window title buffer, including the NUL terminator
+pub const WINDOW_TITLE_LEN: usize = 32;
+
+/// Copy a title into a fixed buffer, stopping at the first NUL and keeping at
+/// most `WINDOW_TITLE_LEN - 1` bytes so the result is always NUL-terminated.
+#[inline]
+pub fn copy_window_title(dst: &mut [u8; WINDOW_TITLE_LEN], src: &[u8]) {
+    let len = src
+        .iter()
+        .position(|&b| b == 0)
+        .unwrap_or(src.len())
+        .min(WINDOW_TITLE_LEN - 1);
+    dst[..len].copy_from_slice(&src[..len]);
+    dst[len..].fill(0);
+}
+
+/// The text of a client-supplied title, without trailing NUL padding.
+///
+/// Returns `None` if a NUL appears inside the text: storing it would
+/// silently cut the title short at that point.
+#[inline]
+pub fn window_title_text(src: &[u8]) -> Option<&[u8]> {
+    let end = src.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    let text = &src[..end];
+    (!text.contains(&0)).then_some(text)
+}
+
 /// Per-window damage region in surface-local coordinates
 #[repr(C)]
 #[derive(Copy, Clone, Default, Debug)]
@@ -50,14 +89,18 @@ pub struct WindowInfo {
     pub state: u8,
     /// Number of damage regions (u8::MAX means full damage)
     pub damage_count: u8,
-    /// Padding for alignment
-    pub _padding: [u8; 2],
+    /// Window flags (WINDOW_FLAG_*)
+    pub flags: u8,
+    /// Surface pixel format (`PixelFormat` value, or WINDOW_FORMAT_NATIVE)
+    pub format: u8,
     /// Shared memory token for this surface (0 if not using shared memory)
     pub shm_token: u32,
+    /// Transparent color key (RGB bits only), valid if WINDOW_FLAG_COLOR_KEY is set
+    pub color_key: u32,
     /// Individual damage regions
     pub damage_regions: [WindowDamageRect; MAX_WINDOW_DAMAGE_REGIONS],
     /// Window title as UTF-8 bytes (null-terminated)
-    pub title: [u8; 32],
+    pub title: [u8; WINDOW_TITLE_LEN],
 }
 
 impl WindowInfo {
@@ -84,6 +127,44 @@ impl WindowInfo {
         core::str::from_utf8(&self.title[..len]).unwrap_or("<invalid>")
     }
 
+    /// Set the title from a possibly unterminated byte slice, truncating to
+    /// fit and always leaving a NUL terminator
+    #[inline]
+    pub fn set_title(&mut self, src: &[u8]) {
+        copy_window_title(&mut self.title, src);
+    }
+
+    /// Resolve the surface's pixel format given the display's `native` one.
+    /// Returns `None` if the surface reports a format this ABI doesn't know.
+    #[inline]
+    pub fn pixel_format(&self, native: PixelFormat) -> Option<PixelFormat> {
+        if self.format == WINDOW_FORMAT_NATIVE {
+            Some(native)
+        } else {
+            PixelFormat::from_u32(self.format as u32)
+        }
+    }
+
+    /// Get the color key, if the surface has one set
+    #[inline]
+    pub fn color_key(&self) -> Option<u32> {
+        if self.flags & WINDOW_FLAG_COLOR_KEY != 0 {
+            Some(self.color_key)
+        } else {
+            None
+        }
+    }
+
+    /// How the surface is combined with the windows beneath it
+    #[inline]
+    pub fn blend_mode(&self) -> SurfaceBlendMode {
+        if self.flags & WINDOW_FLAG_ALPHA_BLEND != 0 {
+            SurfaceBlendMode::AlphaBlend
+        } else {
+            SurfaceBlendMode::Opaque
+        }
+    }
+
     /// Get the window bounds as a damage rect
     #[inline]
     pub fn bounds(&self) -> WindowDamageRect {
@@ -117,10 +198,12 @@ impl Default for WindowInfo {
             height: 0,
             state: 0,
             damage_count: 0,
-            _padding: [0; 2],
+            flags: 0,
+            format: WINDOW_FORMAT_NATIVE,
             shm_token: 0,
+            color_key: 0,
             damage_regions: [WindowDamageRect::default(); MAX_WINDOW_DAMAGE_REGIONS],
-            title: [0; 32],
+            title: [0; WINDOW_TITLE_LEN],
         }
     }
 }
@@ -1,7 +1,7 @@
 use core::ffi::{CStr, c_char};
 
 use slopos_lib::klog::{self, KlogLevel};
-use slopos_lib::{klog_debug, klog_info};
+use slopos_lib::{clock, klog_debug_mod, klog_info, tsc};
 use slopos_tests::{
     TestRunSummary, TestSuiteResult, tests_register_suite, tests_register_system_suites,
     tests_request_shutdown, tests_reset_registry, tests_run_all,
@@ -15,17 +15,21 @@ use crate::limine_protocol;
 use crate::smp::smp_init;
 use slopos_drivers::{
     apic::{apic_detect, apic_init, send_ipi_all_excluding_self},
+    hpet,
     interrupts::config_from_cmdline,
     ioapic::init,
     pci::{pci_get_primary_gpu, pci_init, pci_probe_drivers},
     pic::pic_quiesce_disable,
-    pit::{pit_init, pit_poll_delay_ms},
+    pit::{pit_get_frequency, pit_init, pit_poll_delay_ms},
+    tty,
     virtio_blk::virtio_blk_register_driver,
     xe,
 };
 use slopos_mm::tlb;
 
 const PIT_DEFAULT_FREQUENCY_HZ: u32 = 100;
+/// Length of the TSC calibration window when an HPET is available.
+const HPET_CALIBRATION_MS: u32 = 10;
 
 fn serial_note(msg: &str) {
     slopos_drivers::serial::write_line(msg);
@@ -56,38 +60,61 @@ fn boot_video_backend() -> video::VideoBackend {
     }
 }
 
+fn boot_console_serial_requested() -> bool {
+    cmdline_contains(boot_get_cmdline(), "console=serial")
+}
+
 fn boot_step_debug_subsystem_fn() {
-    klog_debug!("Debug/logging subsystem initialized.");
+    if cmdline_contains(boot_get_cmdline(), "syscall_trace") {
+        slopos_core::syscall::syscall_trace_enable();
+        klog_info!("Syscall tracing enabled from cmdline.");
+    }
+    klog_debug_mod!("boot", "Debug/logging subsystem initialized.");
 }
 
 fn boot_step_gdt_setup_fn() {
-    klog_debug!("GDT/TSS already initialized via PCR in early boot.");
+    klog_debug_mod!("boot", "GDT/TSS already initialized via PCR in early boot.");
 }
 
 fn boot_step_idt_setup_fn() {
-    klog_debug!("Initializing IDT...");
+    klog_debug_mod!("boot", "Initializing IDT...");
     serial_note("boot: idt setup start");
     idt_init();
     ist_stacks_init();
     idt_load();
     serial_note("boot: idt setup done");
-    klog_debug!("IDT initialized and loaded.");
+    klog_debug_mod!("boot", "IDT initialized and loaded.");
 }
 
 fn boot_step_irq_setup_fn() {
-    klog_debug!("Configuring IRQ dispatcher...");
+    klog_debug_mod!("boot", "Configuring IRQ dispatcher...");
     slopos_drivers::irq::init();
-    klog_debug!("IRQ dispatcher ready.");
+    klog_debug_mod!("boot", "IRQ dispatcher ready.");
 }
 
 fn boot_step_timer_setup_fn() {
-    klog_debug!("Initializing programmable interval timer...");
+    klog_debug_mod!("boot", "Initializing programmable interval timer...");
+    clock::clock_init();
     pit_init(PIT_DEFAULT_FREQUENCY_HZ);
-    klog_debug!("Programmable interval timer configured.");
+    clock::clock_set_tick_source(slopos_core::irq::get_timer_ticks, pit_get_frequency());
+    klog_debug_mod!("boot", "Programmable interval timer configured.");
 
     let ticks_before = slopos_core::irq::get_timer_ticks();
+    let tsc_before = tsc::rdtsc();
     pit_poll_delay_ms(100);
+    let tsc_after = tsc::rdtsc();
     let ticks_after = slopos_core::irq::get_timer_ticks();
+    // Prefer the HPET as the reference; it overrides the CPUID value. The
+    // coarse PIT window above only fills in when neither is available.
+    hpet::hpet_init();
+    let tsc_hz = match hpet::hpet_measure_tsc(HPET_CALIBRATION_MS) {
+        Some((cycles, elapsed_ns)) => clock::clock_calibrate_tsc(cycles, elapsed_ns),
+        None if clock::clock_tsc_hz() == 0 => {
+            clock::clock_calibrate_tsc(tsc_after.wrapping_sub(tsc_before), 100_000_000)
+        }
+        None => clock::clock_tsc_hz(),
+    };
+    klog_info!("BOOT: TSC frequency {} Hz", tsc_hz);
     klog_info!(
         "BOOT: PIT ticks after 100ms poll: {} -> {}",
         ticks_before,
@@ -104,6 +131,16 @@ fn boot_step_timer_setup_fn() {
         );
     }
     let backend = boot_video_backend();
+    let serial_requested = boot_console_serial_requested();
+    let console = tty::tty_select_console(
+        serial_requested,
+        boot_fb.is_some() || backend == video::VideoBackend::Xe,
+    );
+    tty::tty_set_console(console);
+    if serial_requested {
+        // Only the TTY moves; the display still comes up for other clients
+        klog_info!("BOOT: console=serial requested; TTY on COM1");
+    }
     if backend == video::VideoBackend::Xe {
         klog_info!("BOOT: deferring video init until PCI for GPU backend");
         return;
@@ -116,12 +153,12 @@ fn boot_step_timer_setup_fn() {
 }
 
 fn boot_step_apic_setup_fn() {
-    klog_debug!("Detecting Local APIC...");
+    klog_debug_mod!("boot", "Detecting Local APIC...");
     if apic_detect() == 0 {
         panic!("SlopOS requires a Local APIC - legacy PIC is gone");
     }
 
-    klog_debug!("Initializing Local APIC...");
+    klog_debug_mod!("boot", "Initializing Local APIC...");
     if apic_init() != 0 {
         panic!("Local APIC initialization failed");
     }
@@ -131,24 +168,27 @@ fn boot_step_apic_setup_fn() {
     tlb::register_ipi_sender(send_ipi_all_excluding_self);
     tlb::init();
 
-    klog_debug!("Local APIC initialized (legacy PIC path removed).");
+    klog_debug_mod!("boot", "Local APIC initialized (legacy PIC path removed).");
 }
 
 fn boot_step_smp_setup_fn() {
-    klog_debug!("Discovering CPUs and starting APs...");
+    klog_debug_mod!("boot", "Discovering CPUs and starting APs...");
     smp_init();
 }
 
 fn boot_step_ioapic_setup_fn() {
-    klog_debug!("Discovering IOAPIC controllers via ACPI MADT...");
+    klog_debug_mod!("boot", "Discovering IOAPIC controllers via ACPI MADT...");
     if init() != 0 {
         panic!("IOAPIC discovery failed - SlopOS cannot operate without it");
     }
-    klog_debug!("IOAPIC: discovery complete, ready for redirection programming.");
+    klog_debug_mod!(
+        "boot",
+        "IOAPIC: discovery complete, ready for redirection programming."
+    );
 }
 
 fn boot_step_pci_init_fn() {
-    klog_debug!("Enumerating PCI devices...");
+    klog_debug_mod!("boot", "Enumerating PCI devices...");
     virtio_blk_register_driver();
     pci_init();
     pci_probe_drivers();
@@ -156,17 +196,19 @@ fn boot_step_pci_init_fn() {
         xe::xe_probe();
     }
 
-    klog_debug!("PCI subsystem initialized.");
+    klog_debug_mod!("boot", "PCI subsystem initialized.");
     let gpu = pci_get_primary_gpu();
     if gpu.present != 0 {
-        klog_debug!(
+        klog_debug_mod!(
+            "boot",
             "PCI: Primary GPU detected (bus {}, device {}, function {})",
             gpu.device.bus,
             gpu.device.device,
             gpu.device.function
         );
         if gpu.mmio_region.is_mapped() {
-            klog_debug!(
+            klog_debug_mod!(
+                "boot",
                 "PCI: GPU MMIO virtual base {:#x}, size {:#x}",
                 gpu.mmio_region.virt_base(),
                 gpu.mmio_size
@@ -175,7 +217,10 @@ fn boot_step_pci_init_fn() {
             klog_info!("PCI: WARNING GPU MMIO mapping unavailable");
         }
     } else {
-        klog_debug!("PCI: No GPU-class device discovered during enumeration");
+        klog_debug_mod!(
+            "boot",
+            "PCI: No GPU-class device discovered during enumeration"
+        );
     }
 
     let backend = boot_video_backend();
@@ -206,10 +251,18 @@ use crate::gdt_tests::{
     test_tss_rsp0_value_valid,
 };
 
+use crate::smp_tests::{
+    test_madt_parses_lapic_and_x2apic, test_madt_stops_at_malformed_entry,
+    test_sipi_vector_encoding,
+};
+
 use crate::shutdown_tests::{
     test_acpi_pm1a_ports_defined, test_apic_availability_queryable, test_apic_enabled_queryable,
     test_com1_lsr_offset, test_com1_port_defined, test_double_scheduler_shutdown,
-    test_kernel_page_directory_available, test_ps2_command_port_defined, test_qemu_debug_exit_port,
+    test_kernel_page_directory_available, test_klog_dump_ring_replays_logged_lines,
+    test_klog_history_records_whole_lines, test_klog_history_truncates_oldest,
+    test_klog_module_level_override, test_klog_panic_flush_drains_deferred,
+    test_klog_panic_flush_uart_timeout, test_ps2_command_port_defined, test_qemu_debug_exit_port,
     test_rapid_shutdown_cycles, test_scheduler_reinit_after_shutdown,
     test_scheduler_shutdown_clears_state, test_scheduler_shutdown_disables,
     test_scheduler_shutdown_idempotent, test_serial_flush_terminates, test_shutdown_e2e_full_flow,
@@ -286,6 +339,12 @@ define_test_suite!(
         test_com1_port_defined,
         test_com1_lsr_offset,
         test_serial_flush_terminates,
+        test_klog_panic_flush_drains_deferred,
+        test_klog_panic_flush_uart_timeout,
+        test_klog_history_truncates_oldest,
+        test_klog_dump_ring_replays_logged_lines,
+        test_klog_history_records_whole_lines,
+        test_klog_module_level_override,
         test_shutdown_sequence_ordering,
         test_shutdown_from_clean_state,
         test_shutdown_partial_init,
@@ -298,8 +357,23 @@ define_test_suite!(
     ]
 );
 
+define_test_suite!(
+    smp,
+    SUITE_SCHEDULER,
+    [
+        test_madt_parses_lapic_and_x2apic,
+        test_madt_stops_at_malformed_entry,
+        test_sipi_vector_encoding,
+    ]
+);
+
 fn register_boot_test_suites() {
-    register_test_suites!(tests_register_suite, GDT_SUITE_DESC, SHUTDOWN_SUITE_DESC,);
+    register_test_suites!(
+        tests_register_suite,
+        GDT_SUITE_DESC,
+        SHUTDOWN_SUITE_DESC,
+        SMP_SUITE_DESC,
+    );
 }
 
 fn boot_step_interrupt_tests_fn() -> i32 {
@@ -319,7 +393,7 @@ fn boot_step_interrupt_tests_fn() -> i32 {
     }
 
     if !test_config.enabled {
-        klog_debug!("INTERRUPT_TEST: Harness disabled");
+        klog_debug_mod!("boot", "INTERRUPT_TEST: Harness disabled");
         return 0;
     }
 
@@ -359,7 +433,7 @@ fn boot_step_interrupt_tests_fn() -> i32 {
     let rc = tests_run_all(&test_config, &mut summary);
 
     if test_config.shutdown {
-        klog_debug!("TESTS: Auto shutdown enabled after harness");
+        klog_debug_mod!("boot", "TESTS: Auto shutdown enabled after harness");
         tests_request_shutdown(summary.failed as i32);
     }
 
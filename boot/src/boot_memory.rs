@@ -1,5 +1,5 @@
 use slopos_lib::klog::{self, KlogLevel};
-use slopos_lib::{klog_debug, klog_info};
+use slopos_lib::{klog_debug_mod, klog_info};
 
 use crate::boot_init_step;
 use crate::early_init::{boot_get_hhdm_offset, boot_get_memmap};
@@ -19,14 +19,14 @@ fn boot_step_memory_init() -> i32 {
     let boot_fb = crate::limine_protocol::boot_info().framebuffer;
     let framebuffer = boot_fb.as_ref().map(|bf| (bf.address as u64, &bf.info));
 
-    klog_debug!("Initializing memory management from Limine data...");
+    klog_debug_mod!("boot", "Initializing memory management from Limine data...");
     let rc = init_memory_system(memmap, hhdm, hhdm_available, framebuffer);
     if rc != 0 {
         klog_info!("ERROR: Memory system initialization failed");
         return -1;
     }
 
-    klog_debug!("Memory management initialized.");
+    klog_debug_mod!("boot", "Memory management initialized.");
     0
 }
 
@@ -37,14 +37,14 @@ fn boot_step_memory_verify() {
     }
 
     if klog::is_enabled_level(KlogLevel::Debug) {
-        klog_debug!("Stack pointer read successfully!");
+        klog_debug_mod!("boot", "Stack pointer read successfully!");
         klog_info!("Current Stack Pointer: 0x{:x}", stack_ptr);
 
         let current_ip = boot_step_memory_verify as *const () as usize as u64;
         klog_info!("Kernel Code Address: 0x{:x}", current_ip);
 
         if current_ip >= KERNEL_VIRTUAL_BASE {
-            klog_debug!("Running in higher-half virtual memory - CORRECT");
+            klog_debug_mod!("boot", "Running in higher-half virtual memory - CORRECT");
         } else {
             klog_info!("WARNING: Not running in higher-half virtual memory");
         }
@@ -1,8 +1,13 @@
-use slopos_lib::{klog_debug, klog_info};
+use slopos_lib::{klog_debug_mod, klog_info};
 
 use crate::early_init::{boot_init_priority, boot_mark_initialized};
-use slopos_core::{boot_step_idle_task, boot_step_scheduler_init, boot_step_task_manager_init};
+use slopos_core::syscall::fs::pipe_wake_waiters;
+use slopos_core::{
+    boot_step_idle_task, boot_step_scheduler_init, boot_step_task_manager_init,
+    boot_step_work_queue,
+};
 use slopos_drivers::virtio_blk;
+use slopos_fs::pipe::register_pipe_wake_hook;
 use slopos_fs::{
     ext2_vfs_init_with_callbacks, ext2_vfs_is_initialized, vfs_init_builtin_filesystems,
 };
@@ -20,7 +25,13 @@ fn boot_step_idle_task_wrapper() -> i32 {
     boot_step_idle_task()
 }
 
+fn boot_step_work_queue_wrapper() -> i32 {
+    boot_step_work_queue()
+}
+
 fn boot_step_fs_init() -> i32 {
+    register_pipe_wake_hook(pipe_wake_waiters);
+
     if virtio_blk::virtio_blk_is_ready() {
         if ext2_vfs_init_with_callbacks(
             virtio_blk::virtio_blk_read,
@@ -73,6 +84,14 @@ crate::boot_init_step_with_flags!(
     boot_init_priority(50)
 );
 
+crate::boot_init_step_with_flags!(
+    BOOT_STEP_WORK_QUEUE,
+    services,
+    b"work queue\0",
+    boot_step_work_queue_wrapper,
+    boot_init_priority(52)
+);
+
 crate::boot_init_step_with_flags!(
     BOOT_STEP_FS_INIT,
     services,
@@ -92,7 +111,7 @@ fn boot_step_framebuffer_demo_fn() {
         return;
     }
 
-    klog_debug!("Graphics demo: framebuffer validation complete");
+    klog_debug_mod!("boot", "Graphics demo: framebuffer validation complete");
 }
 
 crate::boot_init_step_with_flags_unit!(
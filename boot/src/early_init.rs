@@ -8,7 +8,7 @@ use core::sync::atomic::{AtomicUsize, Ordering};
 use slopos_core::wl_currency;
 use slopos_drivers::serial;
 use slopos_lib::klog::{self, KlogLevel};
-use slopos_lib::{klog_debug, klog_info, klog_newline, klog_set_level};
+use slopos_lib::{klog_debug_mod, klog_info, klog_newline, klog_set_level};
 use slopos_video::splash;
 
 use crate::limine_protocol;
@@ -188,7 +188,7 @@ fn boot_info(msg: &'static [u8]) {
 }
 
 fn boot_debug(msg: &'static [u8]) {
-    klog_debug!("{}", bytes_to_str(msg));
+    klog_debug_mod!("boot", "{}", bytes_to_str(msg));
 }
 
 fn boot_init_report_phase(level: KlogLevel, prefix: &[u8], value: Option<&[u8]>) {
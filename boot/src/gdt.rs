@@ -3,7 +3,7 @@
 use core::arch::asm;
 
 use slopos_abi::arch::x86_64::msr::Msr;
-use slopos_lib::{MAX_CPUS, get_current_cpu, klog_debug};
+use slopos_lib::{MAX_CPUS, get_current_cpu, klog_debug_mod};
 
 const GDT_CODE_SELECTOR: u16 = 0x08;
 const GDT_DATA_SELECTOR: u16 = 0x10;
@@ -234,11 +234,19 @@ pub fn gdt_init_for_cpu(cpu_id: usize) {
     }
 
     if slopos_lib::pcr::is_pcr_initialized() {
-        klog_debug!("GDT: Skipped - using PCR-based GDT for CPU {}", cpu_id);
+        klog_debug_mod!(
+            "boot",
+            "GDT: Skipped - using PCR-based GDT for CPU {}",
+            cpu_id
+        );
         return;
     }
 
-    klog_debug!("GDT: Initializing descriptor tables for CPU {}", cpu_id);
+    klog_debug_mod!(
+        "boot",
+        "GDT: Initializing descriptor tables for CPU {}",
+        cpu_id
+    );
 
     unsafe {
         PER_CPU_GDT[cpu_id].entries = [
@@ -276,7 +284,11 @@ pub fn gdt_init_for_cpu(cpu_id: usize) {
         load_tss();
     }
 
-    klog_debug!("GDT: Initialized with TSS loaded for CPU {}", cpu_id);
+    klog_debug_mod!(
+        "boot",
+        "GDT: Initialized with TSS loaded for CPU {}",
+        cpu_id
+    );
 }
 pub fn gdt_set_kernel_rsp0(rsp0: u64) {
     let cpu_id = get_current_cpu();
@@ -351,12 +363,12 @@ fn rdmsr(msr: Msr) -> u64 {
 const EFER_SCE: u64 = 1 << 0;
 
 pub fn syscall_msr_init() {
-    klog_debug!("SYSCALL: Initializing MSRs for fast syscall path");
+    klog_debug_mod!("boot", "SYSCALL: Initializing MSRs for fast syscall path");
 
     let efer = rdmsr(Msr::EFER);
     if (efer & EFER_SCE) == 0 {
         wrmsr(Msr::EFER, efer | EFER_SCE);
-        klog_debug!("SYSCALL: Enabled SCE bit in EFER");
+        klog_debug_mod!("boot", "SYSCALL: Enabled SCE bit in EFER");
     }
 
     let star_value: u64 =
@@ -370,7 +382,8 @@ pub fn syscall_msr_init() {
     wrmsr(Msr::LSTAR, lstar_value);
     wrmsr(Msr::SFMASK, sfmask_value);
 
-    klog_debug!(
+    klog_debug_mod!(
+        "boot",
         "SYSCALL: STAR=0x{:016x} LSTAR=0x{:016x} SFMASK=0x{:016x}",
         star_value,
         lstar_value,
@@ -382,7 +395,7 @@ pub fn syscall_msr_init() {
 
 fn syscall_gs_base_init() {
     if slopos_lib::pcr::is_pcr_initialized() {
-        klog_debug!("SYSCALL: Skipped GS_BASE init - using PCR");
+        klog_debug_mod!("boot", "SYSCALL: Skipped GS_BASE init - using PCR");
         return;
     }
     let cpu_id = get_current_cpu();
@@ -400,7 +413,8 @@ fn syscall_gs_base_init_for_cpu(cpu_id: usize) {
             SYSCALL_CPU_DATA_PTR = cpu_data_ptr;
         }
         wrmsr(Msr::KERNEL_GS_BASE, cpu_data_ptr);
-        klog_debug!(
+        klog_debug_mod!(
+            "boot",
             "SYSCALL: CPU {} KERNEL_GS_BASE=0x{:016x}",
             cpu_id,
             cpu_data_ptr
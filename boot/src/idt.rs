@@ -6,7 +6,7 @@ use core::ffi::{CStr, c_char, c_void};
 
 use slopos_lib::cpu;
 use slopos_lib::string::cstr_to_str;
-use slopos_lib::{klog_debug, klog_info};
+use slopos_lib::{klog_debug_mod, klog_info};
 
 use crate::ist_stacks;
 use crate::panic::set_panic_cpu_state;
@@ -74,10 +74,12 @@ pub enum ExceptionMode {
 }
 
 use slopos_abi::addr::{PhysAddr, VirtAddr};
+use slopos_abi::arch::x86_64::exception::is_nonrecoverable_exception;
 use slopos_core::irq::irq_dispatch;
 use slopos_core::syscall::syscall_handle;
 use slopos_drivers::apic::send_eoi;
-use slopos_lib::kdiag_dump_interrupt_frame;
+use slopos_lib::{StateFlag, report_nonrecoverable};
+use slopos_lib::{dump_fatal_exception, kdiag_dump_interrupt_frame};
 use slopos_mm::cow;
 use slopos_mm::demand;
 use slopos_mm::hhdm::PhysAddrHhdm;
@@ -135,7 +137,7 @@ unsafe extern "C" {
     fn irq15();
 }
 pub fn idt_init() {
-    klog_debug!("IDT: init start");
+    klog_debug_mod!("boot", "IDT: init start");
     unsafe {
         core::ptr::write_bytes(
             IDT.as_mut_ptr() as *mut u8,
@@ -206,10 +208,15 @@ pub fn idt_init() {
 
     initialize_handler_tables();
 
-    klog_debug!("IDT: Configured 256 interrupt vectors");
+    klog_debug_mod!("boot", "IDT: Configured 256 interrupt vectors");
     let base = unsafe { IDT_POINTER.base };
     let limit = unsafe { IDT_POINTER.limit };
-    klog_debug!("IDT: init prepared base=0x{:x} limit=0x{:x}", base, limit);
+    klog_debug_mod!(
+        "boot",
+        "IDT: init prepared base=0x{:x} limit=0x{:x}",
+        base,
+        limit
+    );
 }
 pub fn idt_set_gate_priv(vector: u8, handler: u64, selector: u16, typ: u8, dpl: u8) {
     unsafe {
@@ -252,7 +259,11 @@ pub fn idt_install_exception_handler(vector: u8, handler: ExceptionHandler) {
     }
     unsafe {
         OVERRIDE_HANDLERS[vector as usize] = Some(handler);
-        klog_debug!("IDT: Registered override handler for exception {}", vector);
+        klog_debug_mod!(
+            "boot",
+            "IDT: Registered override handler for exception {}",
+            vector
+        );
     }
 }
 pub fn idt_set_ist(vector: u8, ist_index: u8) {
@@ -294,7 +305,8 @@ fn handle_tlb_shootdown_ipi() {
     if let Some(cpu_idx) = tlb::cpu_index_from_apic_id(apic_id) {
         tlb::handle_shootdown_ipi(cpu_idx);
     } else {
-        klog_debug!(
+        klog_debug_mod!(
+            "boot",
             "TLB: Missing CPU index for APIC 0x{:x}; cannot ack shootdown",
             apic_id
         );
@@ -309,6 +321,11 @@ pub fn common_exception_handler_impl(frame: *mut slopos_lib::InterruptFrame) {
 
     ist_stacks::ist_record_usage(vector, frame as u64);
 
+    // Before any logging that formats or takes locks
+    if is_nonrecoverable_exception(vector) {
+        halt_nonrecoverable(vector, frame);
+    }
+
     if vector == SYSCALL_VECTOR {
         syscall_handle(frame);
         return;
@@ -343,7 +360,8 @@ pub fn common_exception_handler_impl(frame: *mut slopos_lib::InterruptFrame) {
     }
 
     let cr2 = cpu::read_cr2();
-    klog_debug!(
+    klog_debug_mod!(
+        "boot",
         "EXCEPTION: vec={} rip=0x{:x} err=0x{:x} cs=0x{:x} ss=0x{:x} cr2=0x{:x}",
         vector,
         frame_ref.rip,
@@ -510,12 +528,12 @@ fn terminate_user_task(
 
 fn exception_default_panic(frame: *mut slopos_lib::InterruptFrame) {
     klog_info!("FATAL: Unhandled exception");
-    kdiag_dump_interrupt_frame(frame);
+    dump_fatal(frame);
     panic_with_frame("Unhandled exception", frame);
 }
 pub fn exception_divide_error(frame: *mut slopos_lib::InterruptFrame) {
     klog_info!("FATAL: Divide by zero error");
-    kdiag_dump_interrupt_frame(frame);
+    dump_fatal(frame);
     panic_with_frame("Divide by zero error", frame);
 }
 pub fn exception_debug(frame: *mut slopos_lib::InterruptFrame) {
@@ -523,9 +541,7 @@ pub fn exception_debug(frame: *mut slopos_lib::InterruptFrame) {
     kdiag_dump_interrupt_frame(frame);
 }
 pub fn exception_nmi(frame: *mut slopos_lib::InterruptFrame) {
-    klog_info!("FATAL: Non-maskable interrupt");
-    kdiag_dump_interrupt_frame(frame);
-    panic_with_frame("Non-maskable interrupt", frame);
+    halt_nonrecoverable(EXCEPTION_NMI, frame);
 }
 pub fn exception_breakpoint(frame: *mut slopos_lib::InterruptFrame) {
     klog_info!("DEBUG: Breakpoint exception");
@@ -549,7 +565,7 @@ pub fn exception_invalid_opcode(frame: *mut slopos_lib::InterruptFrame) {
         return;
     }
     klog_info!("FATAL: Invalid opcode");
-    kdiag_dump_interrupt_frame(frame);
+    dump_fatal(frame);
     panic_with_frame("Invalid opcode", frame);
 }
 pub fn exception_device_not_available(frame: *mut slopos_lib::InterruptFrame) {
@@ -566,22 +582,22 @@ pub fn exception_device_not_available(frame: *mut slopos_lib::InterruptFrame) {
 }
 pub fn exception_double_fault(frame: *mut slopos_lib::InterruptFrame) {
     klog_info!("FATAL: Double fault");
-    kdiag_dump_interrupt_frame(frame);
+    dump_fatal(frame);
     panic_with_frame("Double fault", frame);
 }
 pub fn exception_invalid_tss(frame: *mut slopos_lib::InterruptFrame) {
     klog_info!("FATAL: Invalid TSS");
-    kdiag_dump_interrupt_frame(frame);
+    dump_fatal(frame);
     panic_with_frame("Invalid TSS", frame);
 }
 pub fn exception_segment_not_present(frame: *mut slopos_lib::InterruptFrame) {
     klog_info!("FATAL: Segment not present");
-    kdiag_dump_interrupt_frame(frame);
+    dump_fatal(frame);
     panic_with_frame("Segment not present", frame);
 }
 pub fn exception_stack_fault(frame: *mut slopos_lib::InterruptFrame) {
     klog_info!("FATAL: Stack segment fault");
-    kdiag_dump_interrupt_frame(frame);
+    dump_fatal(frame);
     panic_with_frame("Stack segment fault", frame);
 }
 pub fn exception_general_protection(frame: *mut slopos_lib::InterruptFrame) {
@@ -594,7 +610,7 @@ pub fn exception_general_protection(frame: *mut slopos_lib::InterruptFrame) {
         return;
     }
     klog_info!("FATAL: General protection fault");
-    kdiag_dump_interrupt_frame(frame);
+    dump_fatal(frame);
     panic_with_frame("General protection fault", frame);
 }
 fn try_handle_page_fault(frame: *mut slopos_lib::InterruptFrame) -> bool {
@@ -767,7 +783,7 @@ pub fn exception_page_fault(frame: *mut slopos_lib::InterruptFrame) {
         return;
     }
 
-    kdiag_dump_interrupt_frame(frame);
+    dump_fatal(frame);
     panic_with_frame("Page fault", frame);
 }
 pub fn exception_fpu_error(frame: *mut slopos_lib::InterruptFrame) {
@@ -779,15 +795,34 @@ pub fn exception_alignment_check(frame: *mut slopos_lib::InterruptFrame) {
     kdiag_dump_interrupt_frame(frame);
 }
 pub fn exception_machine_check(frame: *mut slopos_lib::InterruptFrame) {
-    klog_info!("FATAL: Machine check");
-    kdiag_dump_interrupt_frame(frame);
-    panic_with_frame("Machine check", frame);
+    halt_nonrecoverable(EXCEPTION_MACHINE_CHECK, frame);
+}
+
+/// Set by the first NMI/#MC to be reported; later ones just halt.
+static NONRECOVERABLE_REPORTED: StateFlag = StateFlag::new();
+
+/// Log one line for an NMI or machine check and halt this CPU.
+///
+/// Both can fire again while being handled, so this skips the full fatal
+/// dump and panic path: no `core::fmt`, no stack reads, no locks.
+fn halt_nonrecoverable(vector: u8, frame: *mut slopos_lib::InterruptFrame) -> ! {
+    cpu::disable_interrupts();
+    if NONRECOVERABLE_REPORTED.enter() {
+        report_nonrecoverable(vector, unsafe { &*frame });
+    }
+    cpu::halt_loop();
 }
 pub fn exception_simd_fp_exception(frame: *mut slopos_lib::InterruptFrame) {
     klog_info!("ERROR: SIMD floating-point exception");
     kdiag_dump_interrupt_frame(frame);
 }
 
+/// Full register, cause, and stack dump ahead of an unrecoverable panic.
+fn dump_fatal(frame: *mut slopos_lib::InterruptFrame) {
+    let frame_ref = unsafe { &*frame };
+    dump_fatal_exception(frame_ref.vector as u8, frame_ref);
+}
+
 fn panic_with_frame(message: &str, frame: *mut slopos_lib::InterruptFrame) {
     let frame_ref = unsafe { &*frame };
     set_panic_cpu_state(frame_ref.rip, frame_ref.rsp);
@@ -59,7 +59,7 @@ use slopos_abi::arch::x86_64::idt::{
     EXCEPTION_DOUBLE_FAULT, EXCEPTION_GENERAL_PROTECTION, EXCEPTION_PAGE_FAULT,
     EXCEPTION_STACK_FAULT, IRQ_BASE_VECTOR,
 };
-use slopos_lib::{klog_debug, klog_info};
+use slopos_lib::{klog_debug_mod, klog_info};
 use slopos_mm::hhdm::PhysAddrHhdm;
 use slopos_mm::mm_constants::{
     EXCEPTION_STACK_GUARD_SIZE, EXCEPTION_STACK_PAGES, EXCEPTION_STACK_REGION_BASE,
@@ -394,7 +394,8 @@ fn map_stack_pages(stack: &IstStackConfig) {
 /// - GDT/TSS is initialized
 /// - IDT is initialized (but before interrupts are enabled)
 pub fn ist_stacks_init() {
-    klog_debug!(
+    klog_debug_mod!(
+        "boot",
         "IST: Initializing {} dedicated interrupt stacks",
         IST_STACK_COUNT
     );
@@ -412,7 +413,8 @@ pub fn ist_stacks_init() {
         // Configure IDT to use this IST for the vector
         idt_set_ist(stack.vector, stack.ist_index);
 
-        klog_debug!(
+        klog_debug_mod!(
+            "boot",
             "IST: {} [{}] vec={} IST{} @ 0x{:x}-0x{:x}",
             stack.name_str(),
             stack.category.name(),
@@ -17,6 +17,7 @@ pub mod limine_protocol;
 pub mod panic;
 pub mod shutdown_tests;
 pub mod smp;
+pub mod smp_tests;
 pub mod safe_stack {
     pub use crate::ist_stacks::{safe_stack_guard_fault, safe_stack_init, safe_stack_record_usage};
 }
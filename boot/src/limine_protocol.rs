@@ -14,7 +14,7 @@ use limine::{
 };
 
 use slopos_abi::DisplayInfo;
-use slopos_lib::{klog_debug, klog_info};
+use slopos_lib::{klog_debug_mod, klog_info};
 
 pub use slopos_abi::boot::{
     BootFramebuffer, BootInfo, LimineMemmapEntry, LimineMemmapResponse, MemoryRegion,
@@ -217,19 +217,20 @@ pub fn init_limine_protocol() -> i32 {
     if let Some(resp) = BOOTLOADER_INFO_REQUEST.get_response() {
         let name = resp.name();
         let version = resp.version();
-        klog_debug!("Bootloader: {} version {}", name, version);
+        klog_debug_mod!("boot", "Bootloader: {} version {}", name, version);
     }
 
     if let Some(hhdm) = HHDM_REQUEST.get_response() {
         info.hhdm_offset = hhdm.offset();
         info.flags.hhdm_available = true;
-        klog_debug!("HHDM offset: 0x{:x}", hhdm.offset());
+        klog_debug_mod!("boot", "HHDM offset: 0x{:x}", hhdm.offset());
     }
 
     if let Some(ka) = KERNEL_ADDRESS_REQUEST.get_response() {
         info.kernel_phys_base = ka.physical_base();
         info.kernel_virt_base = ka.virtual_base();
-        klog_debug!(
+        klog_debug_mod!(
+            "boot",
             "Kernel phys base: 0x{:x} virt base: 0x{:x}",
             ka.physical_base(),
             ka.virtual_base()
@@ -243,7 +244,7 @@ pub fn init_limine_protocol() -> i32 {
         info.flags.rsdp_available = rsdp_ptr != 0;
 
         if rsdp_ptr != 0 {
-            klog_debug!("ACPI RSDP pointer: 0x{:x}", rsdp_ptr);
+            klog_debug_mod!("boot", "ACPI RSDP pointer: 0x{:x}", rsdp_ptr);
         } else {
             klog_info!("ACPI: Limine returned null RSDP pointer");
         }
@@ -260,9 +261,9 @@ pub fn init_limine_protocol() -> i32 {
 
             if let Some(cmd) = info.cmdline {
                 if !cmd.is_empty() {
-                    klog_debug!("Kernel cmdline: {}", cmd);
+                    klog_debug_mod!("boot", "Kernel cmdline: {}", cmd);
                 } else {
-                    klog_debug!("Kernel cmdline: <empty>");
+                    klog_debug_mod!("boot", "Kernel cmdline: <empty>");
                 }
             }
         }
@@ -285,7 +286,8 @@ pub fn init_limine_protocol() -> i32 {
         info.memmap_entry_count = entries.len() as u64;
         info.flags.memmap_available = true;
 
-        klog_debug!(
+        klog_debug_mod!(
+            "boot",
             "Memory map: {} entries, total {} MB, available {} MB",
             entries.len(),
             total / (1024 * 1024),
@@ -302,13 +304,15 @@ pub fn init_limine_protocol() -> i32 {
             info.framebuffer = Some(BootFramebuffer::new(fb.addr(), display_info));
             info.flags.framebuffer_available = true;
 
-            klog_debug!(
+            klog_debug_mod!(
+                "boot",
                 "Framebuffer: {}x{} @ {} bpp",
                 fb.width(),
                 fb.height(),
                 fb.bpp()
             );
-            klog_debug!(
+            klog_debug_mod!(
+                "boot",
                 "Framebuffer addr: 0x{:x} pitch: {}",
                 fb.addr() as u64,
                 fb.pitch()
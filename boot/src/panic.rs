@@ -5,9 +5,10 @@ use core::sync::atomic::{AtomicU64, Ordering};
 
 use slopos_drivers::keyboard::poll_wait_enter;
 use slopos_drivers::serial;
+use slopos_lib::klog::klog_dump_ring;
 use slopos_lib::panic_recovery;
 use slopos_lib::stacktrace::{self, StacktraceEntry};
-use slopos_lib::{StateFlag, cpu};
+use slopos_lib::{StateFlag, cpu, klog_panic_flush};
 use slopos_mm::memory_init::is_memory_system_initialized;
 use slopos_video::panic_screen;
 
@@ -65,8 +66,11 @@ fn panic_dump_backtrace() {
         let mut line = MessageBuffer::new();
         let _ = write!(
             line,
-            "  #{} rbp=0x{:016x} rip=0x{:016x}",
-            i, entry.frame_pointer, entry.return_address
+            "  #{} rbp=0x{:016x} rip=0x{:016x} {}",
+            i,
+            entry.frame_pointer,
+            entry.return_address,
+            stacktrace::Symbolized::return_to(entry.return_address)
         );
         panic_serial_write(line.as_str());
     }
@@ -111,6 +115,7 @@ pub fn panic_handler_impl(info: &PanicInfo) -> ! {
 
     if !PANIC_IN_PROGRESS.enter() {
         panic_serial_write("\n!!! RECURSIVE PANIC DETECTED - HALTING !!!\n");
+        klog_panic_flush();
         cpu::halt_loop();
     }
 
@@ -171,9 +176,16 @@ pub fn panic_handler_impl(info: &PanicInfo) -> ! {
 
     panic_dump_backtrace();
 
+    panic_serial_write("Log history (oldest first):");
+    klog_dump_ring(&mut serial::serial_putc_com1);
+
     panic_serial_write("===================");
     panic_serial_write("Kernel panic: unrecoverable error");
 
+    // Nothing below may log synchronously before a halt; push out whatever
+    // was deferred and wait for the UART FIFO so the report is not cut off.
+    klog_panic_flush();
+
     if panic_screen::display_panic_screen(
         Some(message_str),
         display_rip,
@@ -183,7 +195,7 @@ pub fn panic_handler_impl(info: &PanicInfo) -> ! {
         cr4,
     ) {
         panic_serial_write("Press ENTER to shutdown...");
-        poll_wait_enter();
+        poll_wait_enter(&mut panic_screen::panic_screen_scroll_log);
     } else {
         panic_serial_write("System halted.");
     }
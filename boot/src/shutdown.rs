@@ -5,7 +5,7 @@ use slopos_lib::ports::{
     ACPI_PM1A_CNT, ACPI_PM1A_CNT_BOCHS, ACPI_PM1A_CNT_VBOX, COM1, PS2_COMMAND,
 };
 use slopos_lib::string::cstr_to_str;
-use slopos_lib::{StateFlag, cpu, klog_info};
+use slopos_lib::{StateFlag, cpu, klog_drain_deferred, klog_info};
 
 static SHUTDOWN_IN_PROGRESS: StateFlag = StateFlag::new();
 static INTERRUPTS_QUIESCED: StateFlag = StateFlag::new();
@@ -15,10 +15,11 @@ use slopos_core::{scheduler_shutdown, task_shutdown_all};
 use slopos_drivers::apic::apic_is_available;
 use slopos_drivers::apic::{apic_disable, apic_send_eoi, apic_send_ipi_halt_all, apic_timer_stop};
 use slopos_drivers::pit::pit_poll_delay_ms;
-use slopos_mm::page_alloc::{page_allocator_paint_all, pcp_drain_all};
+use slopos_mm::page_alloc::{page_alloc_log_meminfo, page_allocator_paint_all, pcp_drain_all};
 use slopos_mm::paging::{paging_get_kernel_directory, switch_page_directory};
 
 fn serial_flush() {
+    klog_drain_deferred();
     let lsr_port = COM1.offset(5);
     for _ in 0..1024 {
         let lsr = unsafe { lsr_port.read() };
@@ -86,6 +87,7 @@ pub fn kernel_shutdown(reason: *const c_char) -> ! {
     }
 
     pcp_drain_all();
+    page_alloc_log_meminfo();
 
     scheduler_shutdown();
 
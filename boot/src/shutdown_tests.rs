@@ -21,8 +21,15 @@ use slopos_core::scheduler::task::{
     task_find_by_id, task_shutdown_all,
 };
 use slopos_drivers::apic::{apic_is_available, apic_is_enabled};
+use slopos_lib::klog::{
+    KLOG_LINE_MAX, KlogHistory, KlogLevel, UartLineStatus, klog_clear_module_level, klog_defer,
+    klog_deferred_pending, klog_drain_deferred, klog_dump_ring, klog_get_level,
+    klog_get_module_level, klog_module_enabled, klog_panic_flush_with, klog_set_level,
+    klog_set_module_level,
+};
 use slopos_lib::ports::{
     ACPI_PM1A_CNT, ACPI_PM1A_CNT_BOCHS, ACPI_PM1A_CNT_VBOX, COM1, PS2_COMMAND, QEMU_DEBUG_EXIT,
+    UART_LSR_TX_EMPTY, UART_LSR_TX_IDLE,
 };
 use slopos_lib::{StateFlag, klog_info, testing::TestResult};
 use slopos_mm::paging::paging_get_kernel_directory;
@@ -518,6 +525,205 @@ pub fn test_serial_flush_terminates() -> TestResult {
     TestResult::Pass
 }
 
+/// Mock UART that reports a busy transmitter for a fixed number of polls.
+struct MockUart {
+    busy_polls: u32,
+    polls: u32,
+}
+
+impl UartLineStatus for MockUart {
+    fn line_status(&mut self) -> u8 {
+        self.polls += 1;
+        if self.polls > self.busy_polls {
+            UART_LSR_TX_EMPTY | UART_LSR_TX_IDLE
+        } else {
+            // FIFO empty but the shift register is still sending
+            UART_LSR_TX_EMPTY
+        }
+    }
+}
+
+/// Test: klog_panic_flush empties the deferred ring and waits for the UART
+pub fn test_klog_panic_flush_drains_deferred() -> TestResult {
+    // Start from an empty ring so only our bytes are counted
+    klog_drain_deferred();
+
+    klog_defer(KlogLevel::Error, format_args!("deferred one"));
+    klog_defer(KlogLevel::Error, format_args!("deferred {}", 2));
+    let queued = klog_deferred_pending();
+    if queued != "deferred one\ndeferred 2\n".len() {
+        klog_info!("SHUTDOWN_TEST: expected deferred bytes, got {}", queued);
+        return TestResult::Fail;
+    }
+
+    let mut captured = [0u8; 64];
+    let mut len = 0;
+    let mut uart = MockUart {
+        busy_polls: 3,
+        polls: 0,
+    };
+    let report = klog_panic_flush_with(
+        &mut |b| {
+            if len < captured.len() {
+                captured[len] = b;
+                len += 1;
+            }
+        },
+        &mut uart,
+        16,
+    );
+
+    if report.drained != queued || !report.uart_drained {
+        klog_info!("SHUTDOWN_TEST: panic flush report {:?}", report);
+        return TestResult::Fail;
+    }
+    if &captured[..len] != b"deferred one\ndeferred 2\n" {
+        klog_info!("SHUTDOWN_TEST: deferred bytes reordered or lost");
+        return TestResult::Fail;
+    }
+    if klog_deferred_pending() != 0 || uart.polls != 4 {
+        klog_info!("SHUTDOWN_TEST: ring not empty or UART not polled until idle");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: a UART that never drains is reported instead of hanging the panic path
+pub fn test_klog_panic_flush_uart_timeout() -> TestResult {
+    let mut uart = MockUart {
+        busy_polls: u32::MAX,
+        polls: 0,
+    };
+    let report = klog_panic_flush_with(&mut |_| {}, &mut uart, 32);
+
+    if report.uart_drained || uart.polls != 32 {
+        klog_info!(
+            "SHUTDOWN_TEST: stuck UART not bounded ({} polls)",
+            uart.polls
+        );
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: the history replays lines in order and drops the cut-off oldest line
+pub fn test_klog_history_truncates_oldest() -> TestResult {
+    let mut history = KlogHistory::<32>::new();
+    let mut out = [0u8; 64];
+    let mut len = 0;
+
+    history.record(b"first\nsecond\nthird\n");
+    history.dump(&mut |b| {
+        out[len] = b;
+        len += 1;
+    });
+    if &out[..len] != b"first\nsecond\nthird\n" {
+        klog_info!("SHUTDOWN_TEST: history replay out of order");
+        return TestResult::Fail;
+    }
+
+    // 39 bytes into 32: "first\ns" is overwritten, "econd" is a partial line
+    history.record(b"fourth line\n");
+    history.record(b"fifth!!\n");
+    len = 0;
+    let count = history.dump(&mut |b| {
+        out[len] = b;
+        len += 1;
+    });
+    if count != len || &out[..len] != b"third\nfourth line\nfifth!!\n" {
+        klog_info!("SHUTDOWN_TEST: wrapped history kept {} bytes", len);
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: lines logged through klog come back from the global ring, in order
+pub fn test_klog_dump_ring_replays_logged_lines() -> TestResult {
+    klog_info!("KLOG_RING_TEST a");
+    klog_info!("KLOG_RING_TEST b");
+    klog_info!("KLOG_RING_TEST c");
+
+    const EXPECTED: &[u8] = b"KLOG_RING_TEST a\nKLOG_RING_TEST b\nKLOG_RING_TEST c\n";
+    let mut tail = [0u8; EXPECTED.len()];
+    let mut seen = 0usize;
+    let dumped = klog_dump_ring(&mut |b| {
+        tail.copy_within(1.., 0);
+        tail[EXPECTED.len() - 1] = b;
+        seen += 1;
+    });
+
+    if dumped != seen || seen < EXPECTED.len() || tail != EXPECTED {
+        klog_info!("SHUTDOWN_TEST: klog ring did not end with the logged lines");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: a formatted line lands in the history whole, cut at KLOG_LINE_MAX
+pub fn test_klog_history_records_whole_lines() -> TestResult {
+    klog_info!("KLOG_LINE_TEST {}-{}-{:x<300}", 1, 2, "");
+
+    // Preceding newline, then the line capped at KLOG_LINE_MAX, then its own.
+    const LEN: usize = KLOG_LINE_MAX + 2;
+    let mut tail = [0u8; LEN];
+    klog_dump_ring(&mut |b| {
+        tail.copy_within(1.., 0);
+        tail[LEN - 1] = b;
+    });
+
+    let line = &tail[1..LEN - 1];
+    if tail[0] != b'\n'
+        || tail[LEN - 1] != b'\n'
+        || !line.starts_with(b"KLOG_LINE_TEST 1-2-x")
+        || line.iter().skip(19).any(|&b| b != b'x')
+    {
+        klog_info!("SHUTDOWN_TEST: long formatted line not recorded as one capped line");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: a module override lets its debug lines through a Warn global level
+pub fn test_klog_module_level_override() -> TestResult {
+    let saved = klog_get_level();
+    klog_set_level(KlogLevel::Warn);
+    let known = klog_set_module_level("compositor", KlogLevel::Debug);
+
+    let compositor = klog_module_enabled("compositor", KlogLevel::Debug);
+    let scheduler = klog_module_enabled("scheduler", KlogLevel::Debug);
+    let unknown_tag = klog_set_module_level("no-such-module", KlogLevel::Trace);
+    let unknown_warn = klog_module_enabled("no-such-module", KlogLevel::Warn);
+
+    slopos_lib::klog_debug_mod!("compositor", "KLOG_MOD_TEST kept");
+    slopos_lib::klog_debug_mod!("scheduler", "KLOG_MOD_TEST dropped");
+
+    const EXPECTED: &[u8] = b"KLOG_MOD_TEST kept\n";
+    let mut tail = [0u8; EXPECTED.len()];
+    klog_dump_ring(&mut |b| {
+        tail.copy_within(1.., 0);
+        tail[EXPECTED.len() - 1] = b;
+    });
+
+    klog_clear_module_level("compositor");
+    let cleared = klog_get_module_level("compositor").is_none();
+    klog_set_level(saved);
+
+    if !known || unknown_tag || !compositor || scheduler || !unknown_warn || !cleared {
+        klog_info!(
+            "SHUTDOWN_TEST: module filter compositor={} scheduler={} cleared={}",
+            compositor,
+            scheduler,
+            cleared
+        );
+        return TestResult::Fail;
+    }
+    if tail != EXPECTED {
+        klog_info!("SHUTDOWN_TEST: compositor debug line missing or scheduler line logged");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
 // =============================================================================
 // SHUTDOWN SEQUENCE TESTS
 // Test the ordering and coordination of shutdown steps
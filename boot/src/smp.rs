@@ -5,6 +5,7 @@ use limine::mp::{Cpu as MpCpu, ResponseFlags as MpResponseFlags};
 use slopos_core::wl_currency::{award_loss, award_win};
 use slopos_core::{init_scheduler_for_ap, scheduler_run_ap};
 use slopos_drivers::apic;
+use slopos_drivers::pit::pit_poll_delay_ms;
 use slopos_lib::{cpu, init_bsp, init_percpu_for_cpu, is_cpu_online, klog_info, pcr};
 use slopos_mm::tlb;
 
@@ -16,6 +17,96 @@ static NEXT_CPU_ID: AtomicUsize = AtomicUsize::new(1);
 
 const AP_STARTED_MAGIC: u64 = 0x4150_5354_4152_5444;
 
+/// MADT entry types describing processors
+const MADT_ENTRY_LOCAL_APIC: u8 = 0;
+const MADT_ENTRY_LOCAL_X2APIC: u8 = 9;
+/// Processor is enabled, or can be brought online later
+const MADT_LAPIC_ENABLED: u32 = 1 << 0;
+const MADT_LAPIC_ONLINE_CAPABLE: u32 = 1 << 1;
+
+/// SIPI targets must be page-aligned and below 1 MiB (real mode).
+const SIPI_MAX_ENTRY: u64 = 0x10_0000;
+/// INIT-to-SIPI and SIPI-to-SIPI delays from the MP specification
+const AP_INIT_DELAY_MS: u32 = 10;
+const AP_SIPI_DELAY_MS: u32 = 1;
+
+#[inline]
+fn read_le32(bytes: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([bytes[off], bytes[off + 1], bytes[off + 2], bytes[off + 3]])
+}
+
+/// Collect APIC IDs of usable processors from MADT entries (the bytes after
+/// the fixed MADT header).
+///
+/// Both local APIC and local x2APIC entries are read; disabled processors
+/// are skipped. Parsing stops at a truncated or zero-length entry. Returns
+/// the number of IDs written to `out`.
+pub fn madt_apic_ids(entries: &[u8], out: &mut [u32]) -> usize {
+    let mut count = 0;
+    let mut off = 0;
+    while off + 2 <= entries.len() {
+        let len = entries[off + 1] as usize;
+        if len < 2 || off + len > entries.len() {
+            break;
+        }
+        let entry = &entries[off..off + len];
+        let parsed = match entry[0] {
+            MADT_ENTRY_LOCAL_APIC if len >= 8 => Some((entry[3] as u32, read_le32(entry, 4))),
+            MADT_ENTRY_LOCAL_X2APIC if len >= 16 => {
+                Some((read_le32(entry, 4), read_le32(entry, 8)))
+            }
+            _ => None,
+        };
+        let usable = parsed
+            .filter(|&(_, flags)| flags & (MADT_LAPIC_ENABLED | MADT_LAPIC_ONLINE_CAPABLE) != 0);
+        if let Some((apic_id, _)) = usable {
+            if count == out.len() {
+                break;
+            }
+            out[count] = apic_id;
+            count += 1;
+        }
+        off += len;
+    }
+    count
+}
+
+/// SIPI vector for a real-mode entry point: its page number below 1 MiB.
+/// Returns None for misaligned or out-of-range addresses.
+pub fn sipi_vector(entry_phys: u64) -> Option<u8> {
+    if entry_phys & 0xFFF != 0 || entry_phys >= SIPI_MAX_ENTRY {
+        return None;
+    }
+    Some((entry_phys >> 12) as u8)
+}
+
+/// Start an application processor with the INIT-SIPI-SIPI sequence.
+///
+/// The AP begins in real mode at `trampoline_phys`, which must hold a
+/// trampoline that switches to long mode and parks the core. This only
+/// delivers the IPIs; returns false if they could not be sent. No such
+/// trampoline ships yet, see `plans/KNOWN_ISSUES.md`.
+pub fn ap_startup(apic_id: u32, trampoline_phys: u64) -> bool {
+    let Some(vector) = sipi_vector(trampoline_phys) else {
+        klog_info!(
+            "MP: trampoline 0x{:x} is not SIPI-addressable",
+            trampoline_phys
+        );
+        return false;
+    };
+    if !apic::send_init_ipi(apic_id) {
+        return false;
+    }
+    pit_poll_delay_ms(AP_INIT_DELAY_MS);
+    for _ in 0..2 {
+        if !apic::send_startup_ipi(apic_id, vector) {
+            return false;
+        }
+        pit_poll_delay_ms(AP_SIPI_DELAY_MS);
+    }
+    true
+}
+
 unsafe extern "C" fn ap_entry(cpu_info: &MpCpu) -> ! {
     cpu::disable_interrupts();
 
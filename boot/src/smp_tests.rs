@@ -0,0 +1,83 @@
+//! SMP bring-up tests - MADT processor parsing and SIPI encoding.
+
+use core::ffi::c_int;
+
+use slopos_lib::klog_info;
+
+use crate::smp::{madt_apic_ids, sipi_vector};
+
+pub fn test_madt_parses_lapic_and_x2apic() -> c_int {
+    #[rustfmt::skip]
+    let entries: [u8; 8 + 12 + 8 + 16 + 8] = [
+        // Local APIC: acpi 0, apic 0, enabled
+        0, 8, 0, 0, 0x01, 0, 0, 0,
+        // I/O APIC: not a processor
+        1, 12, 0, 0, 0x00, 0x00, 0xC0, 0xFE, 0, 0, 0, 0,
+        // Local APIC: acpi 1, apic 2, online-capable only
+        0, 8, 1, 2, 0x02, 0, 0, 0,
+        // Local x2APIC: apic 0x1234, enabled
+        9, 16, 0, 0, 0x34, 0x12, 0, 0, 0x01, 0, 0, 0, 3, 0, 0, 0,
+        // Local APIC: acpi 4, apic 7, disabled
+        0, 8, 4, 7, 0x00, 0, 0, 0,
+    ];
+    let mut ids = [0u32; 8];
+    let n = madt_apic_ids(&entries, &mut ids);
+    if n != 3 || ids[..3] != [0, 2, 0x1234] {
+        klog_info!("SMP_TEST: BUG - parsed {} ids {:?}", n, &ids[..n]);
+        return -1;
+    }
+
+    // Output capacity bounds the result
+    let mut one = [0u32; 1];
+    if madt_apic_ids(&entries, &mut one) != 1 || one[0] != 0 {
+        klog_info!("SMP_TEST: BUG - output capacity not respected");
+        return -1;
+    }
+    0
+}
+
+pub fn test_madt_stops_at_malformed_entry() -> c_int {
+    // Second entry claims 8 bytes but only 5 remain
+    let truncated: [u8; 13] = [0, 8, 0, 1, 1, 0, 0, 0, 0, 8, 1, 2, 1];
+    let mut ids = [0u32; 4];
+    if madt_apic_ids(&truncated, &mut ids) != 1 || ids[0] != 1 {
+        klog_info!("SMP_TEST: BUG - truncated MADT entry accepted");
+        return -1;
+    }
+    let zero_len: [u8; 10] = [0, 0, 0, 3, 1, 0, 0, 0, 0, 0];
+    if madt_apic_ids(&zero_len, &mut ids) != 0 {
+        klog_info!("SMP_TEST: BUG - zero-length MADT entry not rejected");
+        return -1;
+    }
+    if madt_apic_ids(&[], &mut ids) != 0 {
+        klog_info!("SMP_TEST: BUG - empty MADT produced ids");
+        return -1;
+    }
+    0
+}
+
+pub fn test_sipi_vector_encoding() -> c_int {
+    let valid = [
+        (0x8000u64, 0x08u8),
+        (0x0, 0x00),
+        (0x9F000, 0x9F),
+        (0xFF000, 0xFF),
+    ];
+    for (entry, vector) in valid {
+        if sipi_vector(entry) != Some(vector) {
+            klog_info!(
+                "SMP_TEST: BUG - entry 0x{:x} did not encode to 0x{:x}",
+                entry,
+                vector
+            );
+            return -1;
+        }
+    }
+    for entry in [0x8001u64, 0x8800, 0xFFF, 0x10_0000, 0xFFFF_FFFF_8000_0000] {
+        if sipi_vector(entry).is_some() {
+            klog_info!("SMP_TEST: BUG - entry 0x{:x} should be rejected", entry);
+            return -1;
+        }
+    }
+    0
+}
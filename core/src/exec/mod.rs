@@ -5,13 +5,15 @@ pub mod tests;
 use alloc::vec::Vec;
 
 use slopos_abi::addr::VirtAddr;
-use slopos_fs::vfs::ops::vfs_open;
+use slopos_fs::vfs::ops::{VfsHandle, vfs_open};
 use slopos_lib::klog_info;
-use slopos_mm::elf::{ElfError, ElfValidator};
+use slopos_mm::elf::{ElfError, ElfValidator, MAX_LOAD_SEGMENTS};
 use slopos_mm::hhdm::PhysAddrHhdm;
-use slopos_mm::mm_constants::{PAGE_SIZE_4KB, PROCESS_CODE_START_VA};
+use slopos_mm::mm_constants::{PAGE_SIZE_4KB, PROCESS_CODE_START_VA, USER_SPACE_END_VA};
 use slopos_mm::process_vm::process_vm_get_page_dir;
 
+use crate::platform;
+
 extern crate alloc;
 
 pub const EXEC_MAX_PATH: usize = 256;
@@ -19,6 +21,19 @@ pub const EXEC_MAX_ARG_STRLEN: usize = 4096;
 pub const EXEC_MAX_ARGS: usize = 32;
 pub const EXEC_MAX_ENVS: usize = 32;
 pub const EXEC_MAX_ELF_SIZE: usize = 16 * 1024 * 1024;
+/// Longest `#!` line accepted, counting the `#!` but not the newline.
+pub const EXEC_MAX_SHEBANG_LINE: usize = 255;
+/// Interpreter hops allowed before exec gives up on a `#!` chain.
+pub const EXEC_MAX_SHEBANG_DEPTH: u32 = 4;
+
+// Auxiliary vector entry types (System V AMD64 ABI)
+pub const AT_NULL: u64 = 0;
+pub const AT_PHDR: u64 = 3;
+pub const AT_PHENT: u64 = 4;
+pub const AT_PHNUM: u64 = 5;
+pub const AT_PAGESZ: u64 = 6;
+pub const AT_ENTRY: u64 = 9;
+pub const AT_RANDOM: u64 = 25;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(i32)]
@@ -30,6 +45,7 @@ pub enum ExecError {
     NameTooLong = -36,
     IoError = -5,
     TooManyArgs = -7,
+    Loop = -40,
 }
 
 impl From<ElfError> for ExecError {
@@ -38,6 +54,61 @@ impl From<ElfError> for ExecError {
     }
 }
 
+/// Interpreter named on a script's `#!` line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shebang<'a> {
+    pub interpreter: &'a [u8],
+    /// Everything after the interpreter, passed as one argument
+    pub arg: Option<&'a [u8]>,
+}
+
+/// Parse a leading `#!` line.
+///
+/// Returns `Ok(None)` if `data` does not start with `#!`. The line may be
+/// at most `EXEC_MAX_SHEBANG_LINE` bytes and must name an interpreter.
+pub fn parse_shebang(data: &[u8]) -> Result<Option<Shebang<'_>>, ExecError> {
+    let Some(rest) = data.strip_prefix(b"#!") else {
+        return Ok(None);
+    };
+    let line_len = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+    if line_len + 2 > EXEC_MAX_SHEBANG_LINE {
+        return Err(ExecError::NoExec);
+    }
+
+    let line = rest[..line_len].trim_ascii();
+    let (interpreter, arg) = match line.iter().position(|&b| b == b' ' || b == b'\t') {
+        Some(split) => (&line[..split], Some(line[split..].trim_ascii())),
+        None => (line, None),
+    };
+    if interpreter.is_empty() {
+        return Err(ExecError::NoExec);
+    }
+    Ok(Some(Shebang { interpreter, arg }))
+}
+
+/// Argument vector for running `script` through its interpreter: the
+/// interpreter, its optional argument, the script path, then the caller's
+/// arguments after argv[0].
+pub fn shebang_argv<'a>(
+    shebang: &Shebang<'a>,
+    script: &'a [u8],
+    argv: Option<&[&'a [u8]]>,
+) -> Result<Vec<&'a [u8]>, ExecError> {
+    let tail = argv.map_or(&[][..], |a| a.get(1..).unwrap_or(&[]));
+    let count = 2 + shebang.arg.is_some() as usize + tail.len();
+    if count > EXEC_MAX_ARGS {
+        return Err(ExecError::TooManyArgs);
+    }
+
+    let mut out: Vec<&[u8]> = Vec::new();
+    out.try_reserve(count).map_err(|_| ExecError::NoMem)?;
+    out.push(shebang.interpreter);
+    out.extend(shebang.arg);
+    out.push(script);
+    out.extend_from_slice(tail);
+    Ok(out)
+}
+
 pub fn do_exec(
     process_id: u32,
     path: &[u8],
@@ -45,50 +116,92 @@ pub fn do_exec(
     envp: Option<&[&[u8]]>,
     entry_out: &mut u64,
     stack_ptr_out: &mut u64,
+) -> Result<(), ExecError> {
+    exec_file(process_id, path, argv, envp, entry_out, stack_ptr_out, 0)
+}
+
+/// Load `path`, following `#!` lines; `depth` counts interpreter hops so far.
+fn exec_file(
+    process_id: u32,
+    path: &[u8],
+    argv: Option<&[&[u8]]>,
+    envp: Option<&[&[u8]]>,
+    entry_out: &mut u64,
+    stack_ptr_out: &mut u64,
+    depth: u32,
 ) -> Result<(), ExecError> {
     if path.is_empty() || path.len() > EXEC_MAX_PATH {
         return Err(ExecError::NameTooLong);
     }
 
+    // Reject bad argument vectors before the old image is torn down
+    validate_exec_args(argv, envp)?;
+
     let handle = vfs_open(path, false).map_err(|e| match e {
         slopos_fs::VfsError::NotFound => ExecError::NoEntry,
         slopos_fs::VfsError::IsDirectory => ExecError::NoExec,
         slopos_fs::VfsError::PermissionDenied => ExecError::NoExec,
         _ => ExecError::IoError,
     })?;
+    if handle.file_type == slopos_fs::FileType::Directory {
+        return Err(ExecError::NoExec);
+    }
 
     let file_size = handle.size().map_err(|_| ExecError::IoError)? as usize;
     if file_size == 0 || file_size > EXEC_MAX_ELF_SIZE {
         return Err(ExecError::NoExec);
     }
 
-    let mut elf_data: Vec<u8> = Vec::new();
-    elf_data
-        .try_reserve(file_size)
-        .map_err(|_| ExecError::NoMem)?;
-    elf_data.resize(file_size, 0);
+    // Copy the file out before loading: loading in place through
+    // `with_backing` would map and fill the image under the filesystem's
+    // lock, with interrupts and preemption off for the whole load.
+    let copied = read_file_copy(&handle, file_size)?;
+    let script = load_image(process_id, &copied, argv, envp, entry_out, stack_ptr_out)?;
 
-    let mut offset = 0u64;
-    while (offset as usize) < file_size {
-        let remaining = file_size - offset as usize;
-        let chunk_size = remaining.min(4096);
-        let read = handle
-            .read(
-                offset,
-                &mut elf_data[offset as usize..offset as usize + chunk_size],
-            )
-            .map_err(|_| ExecError::IoError)?;
-        if read == 0 {
-            break;
-        }
-        offset += read as u64;
+    // Scripts are re-run through their interpreter once the file is released
+    let Some(line) = script else {
+        return Ok(());
+    };
+    let shebang = parse_shebang(&line)?.ok_or(ExecError::NoExec)?;
+    if depth >= EXEC_MAX_SHEBANG_DEPTH {
+        return Err(ExecError::Loop);
     }
+    let script_argv = shebang_argv(&shebang, path, argv)?;
+    exec_file(
+        process_id,
+        shebang.interpreter,
+        Some(&script_argv),
+        envp,
+        entry_out,
+        stack_ptr_out,
+        depth + 1,
+    )
+}
 
-    if (offset as usize) < file_size {
-        elf_data.truncate(offset as usize);
+/// Load an ELF image from `elf_data` into `process_id`.
+///
+/// If the file is a `#!` script nothing is loaded and a copy of its first
+/// line is returned for the caller to follow.
+fn load_image(
+    process_id: u32,
+    elf_data: &[u8],
+    argv: Option<&[&[u8]]>,
+    envp: Option<&[&[u8]]>,
+    entry_out: &mut u64,
+    stack_ptr_out: &mut u64,
+) -> Result<Option<Vec<u8>>, ExecError> {
+    if parse_shebang(elf_data)?.is_some() {
+        let line_len = elf_data
+            .iter()
+            .position(|&b| b == b'\n')
+            .unwrap_or(elf_data.len());
+        let mut line = Vec::new();
+        line.try_reserve(line_len).map_err(|_| ExecError::NoMem)?;
+        line.extend_from_slice(&elf_data[..line_len]);
+        return Ok(Some(line));
     }
 
-    let validator = ElfValidator::new(&elf_data)
+    let validator = ElfValidator::new(elf_data)
         .map_err(|_| ExecError::NoExec)?
         .with_load_base(PROCESS_CODE_START_VA);
 
@@ -110,24 +223,29 @@ pub fn do_exec(
     let min_vaddr = segments.iter().map(|s| s.original_vaddr).min().unwrap_or(0);
     let _needs_reloc = min_vaddr >= 0xFFFF_FFFF_8000_0000 || min_vaddr != PROCESS_CODE_START_VA;
 
-    clear_user_code_region(page_dir, PROCESS_CODE_START_VA);
-
-    for segment in segments.iter() {
-        let user_start =
-            translate_address(segment.original_vaddr, min_vaddr, PROCESS_CODE_START_VA);
-        let user_end = translate_address(
-            segment.original_vaddr + segment.mem_size,
+    // Translate everything up front so a malformed ELF is rejected before
+    // the old image is torn down or anything is mapped.
+    let mut user_ranges = [(0u64, 0u64); MAX_LOAD_SEGMENTS];
+    for (range, segment) in user_ranges.iter_mut().zip(segments.iter()) {
+        *range = translate_segment(
+            segment.original_vaddr,
+            segment.mem_size,
             min_vaddr,
             PROCESS_CODE_START_VA,
-        );
+        )?;
+    }
+    let user_entry = translate_address(header.e_entry, min_vaddr, PROCESS_CODE_START_VA)?;
+
+    clear_user_code_region(page_dir, PROCESS_CODE_START_VA);
 
-        map_segment(page_dir, &elf_data, segment, user_start, user_end)?;
+    for (segment, &(user_start, user_end)) in segments.iter().zip(user_ranges.iter()) {
+        map_segment(page_dir, elf_data, segment, user_start, user_end)?;
     }
 
-    let user_entry = translate_address(header.e_entry, min_vaddr, PROCESS_CODE_START_VA);
     *entry_out = user_entry;
 
-    let stack_top = setup_user_stack(process_id, argv, envp)?;
+    let aux = aux_info(header, segments, &user_ranges[..segments.len()], user_entry);
+    let stack_top = setup_user_stack(process_id, argv, envp, &aux)?;
     *stack_ptr_out = stack_top;
 
     klog_info!(
@@ -137,22 +255,41 @@ pub fn do_exec(
         stack_top
     );
 
-    Ok(())
+    Ok(None)
 }
 
-pub fn translate_address(addr: u64, min_vaddr: u64, code_base: u64) -> u64 {
+/// Relocate an ELF virtual address into the user code region.
+///
+/// Fails with `NoExec` if the relocation would overflow or underflow.
+pub fn translate_address(addr: u64, min_vaddr: u64, code_base: u64) -> Result<u64, ExecError> {
     const KERNEL_BASE: u64 = 0xFFFF_FFFF_8000_0000;
-    if addr >= KERNEL_BASE {
-        let offset = addr.wrapping_sub(KERNEL_BASE);
-        code_base.wrapping_add(offset)
+    let translated = if addr >= KERNEL_BASE {
+        code_base.checked_add(addr - KERNEL_BASE)
     } else if min_vaddr >= KERNEL_BASE {
-        let offset = addr.wrapping_sub(min_vaddr);
-        code_base.wrapping_add(offset)
+        addr.checked_sub(min_vaddr)
+            .and_then(|offset| code_base.checked_add(offset))
     } else if min_vaddr < code_base {
-        addr.wrapping_add(code_base.wrapping_sub(min_vaddr))
+        addr.checked_add(code_base - min_vaddr)
     } else {
-        addr
+        Some(addr)
+    };
+    translated.ok_or(ExecError::NoExec)
+}
+
+/// Translate a segment's `[vaddr, vaddr + mem_size)` into user addresses.
+pub fn translate_segment(
+    vaddr: u64,
+    mem_size: u64,
+    min_vaddr: u64,
+    code_base: u64,
+) -> Result<(u64, u64), ExecError> {
+    let end = vaddr.checked_add(mem_size).ok_or(ExecError::NoExec)?;
+    let user_start = translate_address(vaddr, min_vaddr, code_base)?;
+    let user_end = translate_address(end, min_vaddr, code_base)?;
+    if user_end < user_start {
+        return Err(ExecError::NoExec);
     }
+    Ok((user_start, user_end))
 }
 
 fn clear_user_code_region(page_dir: *mut slopos_mm::paging::ProcessPageDir, code_base: u64) {
@@ -164,6 +301,35 @@ fn clear_user_code_region(page_dir: *mut slopos_mm::paging::ProcessPageDir, code
     );
 }
 
+fn read_file_copy(handle: &VfsHandle, file_size: usize) -> Result<Vec<u8>, ExecError> {
+    let mut elf_data: Vec<u8> = Vec::new();
+    elf_data
+        .try_reserve(file_size)
+        .map_err(|_| ExecError::NoMem)?;
+    elf_data.resize(file_size, 0);
+
+    let mut offset = 0u64;
+    while (offset as usize) < file_size {
+        let remaining = file_size - offset as usize;
+        let chunk_size = remaining.min(4096);
+        let read = handle
+            .read(
+                offset,
+                &mut elf_data[offset as usize..offset as usize + chunk_size],
+            )
+            .map_err(|_| ExecError::IoError)?;
+        if read == 0 {
+            break;
+        }
+        offset += read as u64;
+    }
+
+    if (offset as usize) < file_size {
+        elf_data.truncate(offset as usize);
+    }
+    Ok(elf_data)
+}
+
 fn map_segment(
     page_dir: *mut slopos_mm::paging::ProcessPageDir,
     elf_data: &[u8],
@@ -174,7 +340,9 @@ fn map_segment(
     use slopos_lib::align_down;
     use slopos_mm::elf::PF_W;
     use slopos_mm::mm_constants::PageFlags;
-    use slopos_mm::page_alloc::{ALLOC_FLAG_ZERO, alloc_page_frame, free_page_frame};
+    use slopos_mm::page_alloc::{
+        ALLOC_FLAG_ZERO, ALLOC_FLAG_ZERO_ON_FREE, alloc_page_frame, free_page_frame,
+    };
     use slopos_mm::paging::{map_page_4kb_in_dir, virt_to_phys_in_dir};
 
     let map_flags = if (segment.flags & PF_W) != 0 {
@@ -192,7 +360,7 @@ fn map_segment(
         let phys = if !existing_phys.is_null() {
             existing_phys
         } else {
-            let new_phys = alloc_page_frame(ALLOC_FLAG_ZERO);
+            let new_phys = alloc_page_frame(ALLOC_FLAG_ZERO | ALLOC_FLAG_ZERO_ON_FREE);
             if new_phys.is_null() {
                 return Err(ExecError::NoMem);
             }
@@ -260,91 +428,187 @@ fn copy_segment_page_data(
     }
 }
 
-fn setup_user_stack(
-    process_id: u32,
-    argv: Option<&[&[u8]]>,
-    envp: Option<&[&[u8]]>,
-) -> Result<u64, ExecError> {
-    let layout = unsafe { &*slopos_mm::memory_layout::mm_get_process_layout() };
-    let stack_top = layout.stack_top;
+/// Check argv/envp before anything is copied onto the user stack.
+///
+/// The strings must already be kernel copies: each slice has to lie in the
+/// kernel half of the address space, must not wrap, and must be no longer
+/// than `EXEC_MAX_ARG_STRLEN`. An empty string is always valid, whatever
+/// its (possibly dangling) pointer.
+pub fn validate_exec_args(argv: Option<&[&[u8]]>, envp: Option<&[&[u8]]>) -> Result<(), ExecError> {
+    if argv.map_or(0, |a| a.len()) > EXEC_MAX_ARGS || envp.map_or(0, |e| e.len()) > EXEC_MAX_ENVS {
+        return Err(ExecError::TooManyArgs);
+    }
 
-    let page_dir = process_vm_get_page_dir(process_id);
-    if page_dir.is_null() {
-        return Err(ExecError::NoMem);
+    let strings = argv.into_iter().chain(envp).flat_map(|v| v.iter());
+    for s in strings {
+        if s.is_empty() {
+            continue;
+        }
+        if s.len() > EXEC_MAX_ARG_STRLEN {
+            return Err(ExecError::Fault);
+        }
+        let start = s.as_ptr() as u64;
+        if start < USER_SPACE_END_VA || start.checked_add(s.len() as u64).is_none() {
+            return Err(ExecError::Fault);
+        }
+    }
+    Ok(())
+}
+
+/// Values handed to the new image through the auxiliary vector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuxInfo {
+    /// Translated entry point
+    pub entry: u64,
+    /// User address of the program headers, if a loaded segment covers them
+    pub phdr: Option<u64>,
+    pub phent: u64,
+    pub phnum: u64,
+}
+
+/// Work out `AuxInfo` for an image whose segments were loaded at `user_ranges`.
+fn aux_info(
+    header: &slopos_mm::elf::Elf64Header,
+    segments: &[slopos_mm::elf::ValidatedSegment],
+    user_ranges: &[(u64, u64)],
+    entry: u64,
+) -> AuxInfo {
+    let table_end = header
+        .e_phoff
+        .saturating_add(header.phdr_table_size() as u64);
+    let phdr = segments
+        .iter()
+        .zip(user_ranges)
+        .find(|(seg, _)| {
+            header.e_phoff >= seg.file_offset
+                && table_end <= seg.file_offset.saturating_add(seg.file_size)
+        })
+        .map(|(seg, &(user_start, _))| user_start + (header.e_phoff - seg.file_offset));
+    AuxInfo {
+        entry,
+        phdr,
+        phent: header.e_phentsize as u64,
+        phnum: header.e_phnum as u64,
     }
+}
 
-    let argc = argv.map(|a| a.len()).unwrap_or(0);
-    let envc = envp.map(|e| e.len()).unwrap_or(0);
+/// Sixteen bytes for `AT_RANDOM` from the kernel RNG. User code seeds
+/// stack canaries from them, so they must not be guessable.
+fn aux_random_bytes() -> [u8; 16] {
+    let mut out = [0u8; 16];
+    for chunk in out.chunks_exact_mut(8) {
+        chunk.copy_from_slice(&platform::rng_next().to_le_bytes());
+    }
+    out
+}
+
+/// Initial process stack ready to be copied to `sp`.
+pub struct StackImage {
+    /// Value for RSP at `_start`; 16-byte aligned and pointing at argc
+    pub sp: u64,
+    /// Bytes from `sp` up to the top of the used stack area
+    pub bytes: Vec<u8>,
+}
 
-    if argc > EXEC_MAX_ARGS || envc > EXEC_MAX_ENVS {
+/// Lay out argc, argv, envp, the auxiliary vector, the strings and the
+/// `AT_RANDOM` bytes below `stack_top` in the System V order.
+pub fn build_stack_image(
+    stack_top: u64,
+    argv: Option<&[&[u8]]>,
+    envp: Option<&[&[u8]]>,
+    aux: &AuxInfo,
+    random: &[u8; 16],
+) -> Result<StackImage, ExecError> {
+    let argv = argv.unwrap_or(&[]);
+    let envp = envp.unwrap_or(&[]);
+    if argv.len() > EXEC_MAX_ARGS || envp.len() > EXEC_MAX_ENVS {
         return Err(ExecError::TooManyArgs);
     }
 
-    let mut sp = stack_top;
-    sp = sp.wrapping_sub(128);
-    sp &= !0xF;
+    let top = stack_top.wrapping_sub(128) & !0xF;
 
+    // Strings first, from the top down, so their addresses are known
     let mut string_ptrs: Vec<u64> = Vec::new();
     string_ptrs
-        .try_reserve(argc + envc + 2)
+        .try_reserve(argv.len() + envp.len())
         .map_err(|_| ExecError::NoMem)?;
-
-    if let Some(args) = argv {
-        for arg in args.iter() {
-            let len = arg.len() + 1;
-            sp = sp.wrapping_sub(len as u64);
-            sp &= !0x7;
-            write_to_user_stack(page_dir, sp, arg)?;
-            write_byte_to_user_stack(page_dir, sp + arg.len() as u64, 0)?;
-            string_ptrs.push(sp);
-        }
+    let mut cursor = top;
+    for s in argv.iter().chain(envp.iter()) {
+        cursor = cursor.wrapping_sub(s.len() as u64 + 1) & !0x7;
+        string_ptrs.push(cursor);
     }
+    let random_addr = cursor.wrapping_sub(16) & !0xF;
 
-    let argv_start = string_ptrs.len();
-
-    if let Some(envs) = envp {
-        for env in envs.iter() {
-            let len = env.len() + 1;
-            sp = sp.wrapping_sub(len as u64);
-            sp &= !0x7;
-            write_to_user_stack(page_dir, sp, env)?;
-            write_byte_to_user_stack(page_dir, sp + env.len() as u64, 0)?;
-            string_ptrs.push(sp);
-        }
+    let mut auxv = [(AT_NULL, 0u64); 7];
+    let mut aux_len = 0;
+    let mut push_aux = |tag: u64, value: u64| {
+        auxv[aux_len] = (tag, value);
+        aux_len += 1;
+    };
+    push_aux(AT_PAGESZ, PAGE_SIZE_4KB);
+    push_aux(AT_ENTRY, aux.entry);
+    if let Some(phdr) = aux.phdr {
+        push_aux(AT_PHDR, phdr);
     }
-
-    sp &= !0xF;
-
-    let aux_size = 2 * 8;
-    sp = sp.wrapping_sub(aux_size);
-    write_u64_to_user_stack(page_dir, sp, 0)?;
-    write_u64_to_user_stack(page_dir, sp + 8, 0)?;
-
-    sp = sp.wrapping_sub(8);
-    write_u64_to_user_stack(page_dir, sp, 0)?;
-
-    for i in (argv_start..string_ptrs.len()).rev() {
-        sp = sp.wrapping_sub(8);
-        write_u64_to_user_stack(page_dir, sp, string_ptrs[i])?;
+    push_aux(AT_PHENT, aux.phent);
+    push_aux(AT_PHNUM, aux.phnum);
+    push_aux(AT_RANDOM, random_addr);
+    push_aux(AT_NULL, 0);
+
+    // argc, argv[] + NULL, envp[] + NULL, auxv pairs
+    let words = 1 + (argv.len() + 1) + (envp.len() + 1) + 2 * aux_len;
+    let sp = random_addr.wrapping_sub(words as u64 * 8) & !0xF;
+
+    let len = (top - sp) as usize;
+    let mut bytes: Vec<u8> = Vec::new();
+    bytes.try_reserve(len).map_err(|_| ExecError::NoMem)?;
+    bytes.resize(len, 0);
+
+    let mut put = |addr: u64, data: &[u8]| {
+        let off = (addr - sp) as usize;
+        bytes[off..off + data.len()].copy_from_slice(data);
+    };
+    for (s, &addr) in argv.iter().chain(envp.iter()).zip(string_ptrs.iter()) {
+        // The terminating NUL is already there from the zero fill
+        put(addr, s);
     }
+    put(random_addr, random);
 
-    sp = sp.wrapping_sub(8);
-    write_u64_to_user_stack(page_dir, sp, 0)?;
-
-    for i in (0..argv_start).rev() {
-        sp = sp.wrapping_sub(8);
-        write_u64_to_user_stack(page_dir, sp, string_ptrs[i])?;
+    let (argv_ptrs, envp_ptrs) = string_ptrs.split_at(argv.len());
+    let mut word_addr = sp;
+    let mut put_word = |value: u64| {
+        put(word_addr, &value.to_le_bytes());
+        word_addr += 8;
+    };
+    put_word(argv.len() as u64);
+    argv_ptrs.iter().for_each(|&p| put_word(p));
+    put_word(0);
+    envp_ptrs.iter().for_each(|&p| put_word(p));
+    put_word(0);
+    for &(tag, value) in &auxv[..aux_len] {
+        put_word(tag);
+        put_word(value);
     }
 
-    sp = sp.wrapping_sub(8);
-    write_u64_to_user_stack(page_dir, sp, argc as u64)?;
+    Ok(StackImage { sp, bytes })
+}
+
+fn setup_user_stack(
+    process_id: u32,
+    argv: Option<&[&[u8]]>,
+    envp: Option<&[&[u8]]>,
+    aux: &AuxInfo,
+) -> Result<u64, ExecError> {
+    let layout = unsafe { &*slopos_mm::memory_layout::mm_get_process_layout() };
 
-    sp &= !0xF;
-    if ((stack_top - sp) / 8) % 2 != 0 {
-        sp = sp.wrapping_sub(8);
+    let page_dir = process_vm_get_page_dir(process_id);
+    if page_dir.is_null() {
+        return Err(ExecError::NoMem);
     }
 
-    Ok(sp)
+    let image = build_stack_image(layout.stack_top, argv, envp, aux, &aux_random_bytes())?;
+    write_to_user_stack(page_dir, image.sp, &image.bytes)?;
+    Ok(image.sp)
 }
 
 fn write_to_user_stack(
@@ -373,20 +637,3 @@ fn write_to_user_stack(
     }
     Ok(())
 }
-
-fn write_byte_to_user_stack(
-    page_dir: *mut slopos_mm::paging::ProcessPageDir,
-    addr: u64,
-    byte: u8,
-) -> Result<(), ExecError> {
-    write_to_user_stack(page_dir, addr, &[byte])
-}
-
-fn write_u64_to_user_stack(
-    page_dir: *mut slopos_mm::paging::ProcessPageDir,
-    addr: u64,
-    value: u64,
-) -> Result<(), ExecError> {
-    let bytes = value.to_le_bytes();
-    write_to_user_stack(page_dir, addr, &bytes)
-}
@@ -2,12 +2,21 @@
 
 use core::ffi::c_int;
 
+use slopos_fs::vfs::ops::{vfs_mkdir, vfs_open};
 use slopos_lib::klog_info;
 use slopos_mm::elf::{ELF_MAGIC, ElfValidator};
-use slopos_mm::mm_constants::PROCESS_CODE_START_VA;
+use slopos_mm::mm_constants::{INVALID_PROCESS_ID, PROCESS_CODE_START_VA};
 use slopos_mm::process_vm;
 
-use super::{EXEC_MAX_ELF_SIZE, EXEC_MAX_PATH};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{
+    AT_ENTRY, AT_NULL, AT_PAGESZ, AT_PHDR, AT_PHENT, AT_PHNUM, AT_RANDOM, AuxInfo,
+    EXEC_MAX_ARG_STRLEN, EXEC_MAX_ARGS, EXEC_MAX_ELF_SIZE, EXEC_MAX_PATH, EXEC_MAX_SHEBANG_LINE,
+    ExecError, Shebang, StackImage, build_stack_image, do_exec, parse_shebang, shebang_argv,
+    validate_exec_args,
+};
 
 const MINIMAL_ELF_SIZE: usize = 64;
 
@@ -257,7 +266,18 @@ pub fn test_translate_address_kernel_to_user() -> c_int {
     let min_vaddr = 0xFFFF_FFFF_8000_0000u64;
     let code_base = PROCESS_CODE_START_VA;
 
-    let translated = translate_address(kernel_addr, min_vaddr, code_base);
+    let Ok(translated) = translate_address(kernel_addr, min_vaddr, code_base) else {
+        klog_info!("EXEC_TEST: BUG - valid kernel address rejected");
+        return -1;
+    };
+
+    if translated != code_base + 0x1000 {
+        klog_info!(
+            "EXEC_TEST: BUG - kernel address translated to {:#x}",
+            translated
+        );
+        return -1;
+    }
 
     if translated >= 0xFFFF_8000_0000_0000 {
         klog_info!("EXEC_TEST: BUG - translate_address didn't move kernel addr to user space");
@@ -279,7 +299,9 @@ pub fn test_translate_address_user_passthrough() -> c_int {
     let min_vaddr = 0x0000_0040_0000_0000u64;
     let code_base = PROCESS_CODE_START_VA;
 
-    let translated = translate_address(user_addr, min_vaddr, code_base);
+    let Ok(translated) = translate_address(user_addr, min_vaddr, code_base) else {
+        return -1;
+    };
 
     if translated >= 0xFFFF_8000_0000_0000 {
         klog_info!("EXEC_TEST: BUG - user address translated to kernel space");
@@ -289,6 +311,38 @@ pub fn test_translate_address_user_passthrough() -> c_int {
     0
 }
 
+pub fn test_translate_address_overflow_rejected() -> c_int {
+    use super::{translate_address, translate_segment};
+
+    let code_base = PROCESS_CODE_START_VA;
+    let min_vaddr = 0xFFFF_FFFF_8000_0000u64;
+
+    // Segment end wraps past u64::MAX
+    let near_max = u64::MAX - 0xFFF;
+    if translate_segment(near_max, 0x2000, min_vaddr, code_base) != Err(ExecError::NoExec) {
+        klog_info!("EXEC_TEST: BUG - wrapping segment end accepted");
+        return -1;
+    }
+
+    // Relocation onto a code base that cannot hold the offset
+    if translate_address(u64::MAX, min_vaddr, u64::MAX - 0x10) != Err(ExecError::NoExec) {
+        klog_info!("EXEC_TEST: BUG - overflowing relocation accepted");
+        return -1;
+    }
+
+    // Address below a kernel-half min_vaddr would underflow the offset
+    if translate_address(0x1000, 0xFFFF_FFFF_9000_0000, code_base) != Err(ExecError::NoExec) {
+        klog_info!("EXEC_TEST: BUG - underflowing relocation accepted");
+        return -1;
+    }
+
+    // A well-formed kernel-base segment still translates
+    match translate_segment(min_vaddr, 0x2000, min_vaddr, code_base) {
+        Ok((start, end)) if start == code_base && end == code_base + 0x2000 => 0,
+        _ => -1,
+    }
+}
+
 pub fn test_process_vm_null_page_dir() -> c_int {
     let pid = 9999; // Invalid process ID
     let page_dir = process_vm::process_vm_get_page_dir(pid);
@@ -347,3 +401,303 @@ pub fn test_exec_max_size_boundary() -> c_int {
     }
     0
 }
+
+pub fn test_exec_args_well_formed() -> c_int {
+    let argv: [&[u8]; 3] = [b"/bin/sh", b"-c", b"echo slop"];
+    let envp: [&[u8]; 1] = [b"HOME=/"];
+
+    if validate_exec_args(Some(&argv), Some(&envp)).is_err() {
+        klog_info!("EXEC_TEST: well-formed argv/envp rejected");
+        return -1;
+    }
+    if validate_exec_args(None, None).is_err() {
+        klog_info!("EXEC_TEST: empty argv/envp rejected");
+        return -1;
+    }
+    // An empty Vec's slice points at a dangling, non-kernel address.
+    let dangling: Vec<u8> = Vec::new();
+    let argv: [&[u8]; 2] = [b"prog", &dangling];
+    let envp: [&[u8]; 1] = [&dangling];
+    if validate_exec_args(Some(&argv), Some(&envp)).is_err() {
+        klog_info!("EXEC_TEST: BUG - empty argument string rejected");
+        return -1;
+    }
+    0
+}
+
+pub fn test_exec_args_too_long() -> c_int {
+    let huge = vec![b'A'; EXEC_MAX_ARG_STRLEN + 1];
+    let argv: [&[u8]; 2] = [b"/bin/sh", &huge];
+
+    match validate_exec_args(Some(&argv), None) {
+        Err(ExecError::Fault) => {}
+        other => {
+            klog_info!("EXEC_TEST: BUG - oversized arg not rejected: {:?}", other);
+            return -1;
+        }
+    }
+
+    let envp: [&[u8]; 1] = [&huge];
+    if validate_exec_args(None, Some(&envp)) != Err(ExecError::Fault) {
+        klog_info!("EXEC_TEST: BUG - oversized env not rejected");
+        return -1;
+    }
+    0
+}
+
+pub fn test_exec_args_too_many() -> c_int {
+    let argv: [&[u8]; EXEC_MAX_ARGS + 1] = [b"x"; EXEC_MAX_ARGS + 1];
+    if validate_exec_args(Some(&argv), None) != Err(ExecError::TooManyArgs) {
+        klog_info!("EXEC_TEST: BUG - argv over EXEC_MAX_ARGS accepted");
+        return -1;
+    }
+    0
+}
+
+/// Read the u64 at user address `addr` out of a stack image.
+fn stack_word(image: &StackImage, addr: u64) -> Option<u64> {
+    let off = addr.checked_sub(image.sp)? as usize;
+    let bytes = image.bytes.get(off..off + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Read the NUL-terminated string at user address `addr` out of a stack image.
+fn stack_str(image: &StackImage, addr: u64) -> Option<&[u8]> {
+    let off = addr.checked_sub(image.sp)? as usize;
+    let rest = image.bytes.get(off..)?;
+    let len = rest.iter().position(|&b| b == 0)?;
+    Some(&rest[..len])
+}
+
+/// Walk a NULL-terminated pointer array starting at `addr` and check each
+/// entry points at the expected string. Returns the address after the NULL.
+fn check_string_array(image: &StackImage, mut addr: u64, expected: &[&[u8]]) -> Option<u64> {
+    for s in expected {
+        let ptr = stack_word(image, addr)?;
+        if stack_str(image, ptr)? != *s {
+            return None;
+        }
+        addr += 8;
+    }
+    if stack_word(image, addr)? != 0 {
+        return None;
+    }
+    Some(addr + 8)
+}
+
+pub fn test_exec_stack_auxv() -> c_int {
+    const STACK_TOP: u64 = 0x0000_7FFF_FF00_0000;
+    let aux = AuxInfo {
+        entry: PROCESS_CODE_START_VA + 0x1234,
+        phdr: Some(PROCESS_CODE_START_VA + 0x40),
+        phent: 56,
+        phnum: 4,
+    };
+    let random: [u8; 16] = core::array::from_fn(|i| i as u8 + 1);
+    let all_args: [&[u8]; 4] = [b"/bin/prog", b"-v", b"a", b"longer argument"];
+    let envp: [&[u8]; 1] = [b"HOME=/"];
+
+    // Every argc parity must still leave RSP aligned with argc at [rsp]
+    for argc in 0..=all_args.len() {
+        let argv = &all_args[..argc];
+        let Ok(image) = build_stack_image(STACK_TOP, Some(argv), Some(&envp), &aux, &random) else {
+            klog_info!("EXEC_TEST: stack image build failed for argc={}", argc);
+            return -1;
+        };
+        if image.sp % 16 != 0 || image.sp >= STACK_TOP {
+            klog_info!("EXEC_TEST: BUG - initial rsp {:#x} misaligned", image.sp);
+            return -1;
+        }
+        if stack_word(&image, image.sp) != Some(argc as u64) {
+            klog_info!("EXEC_TEST: BUG - argc not at rsp for argc={}", argc);
+            return -1;
+        }
+        let Some(envp_addr) = check_string_array(&image, image.sp + 8, argv) else {
+            klog_info!("EXEC_TEST: BUG - argv does not decode");
+            return -1;
+        };
+        let Some(mut auxv_addr) = check_string_array(&image, envp_addr, &envp) else {
+            klog_info!("EXEC_TEST: BUG - envp does not decode");
+            return -1;
+        };
+
+        let mut seen = [None; 6];
+        let tags = [AT_PAGESZ, AT_ENTRY, AT_PHDR, AT_PHENT, AT_PHNUM, AT_RANDOM];
+        let terminated = loop {
+            let (Some(tag), Some(value)) = (
+                stack_word(&image, auxv_addr),
+                stack_word(&image, auxv_addr + 8),
+            ) else {
+                break false;
+            };
+            if tag == AT_NULL {
+                break true;
+            }
+            if let Some(i) = tags.iter().position(|&t| t == tag) {
+                seen[i] = Some(value);
+            }
+            auxv_addr += 16;
+        };
+        if !terminated {
+            klog_info!("EXEC_TEST: BUG - auxv not terminated by AT_NULL");
+            return -1;
+        }
+        let expected = [
+            Some(4096),
+            Some(aux.entry),
+            aux.phdr,
+            Some(aux.phent),
+            Some(aux.phnum),
+        ];
+        if seen[..5] != expected {
+            klog_info!("EXEC_TEST: BUG - auxv values wrong: {:?}", &seen[..5]);
+            return -1;
+        }
+        let Some(random_addr) = seen[5] else {
+            klog_info!("EXEC_TEST: BUG - AT_RANDOM missing");
+            return -1;
+        };
+        let off = (random_addr - image.sp) as usize;
+        if random_addr % 16 != 0 || image.bytes.get(off..off + 16) != Some(&random[..]) {
+            klog_info!("EXEC_TEST: BUG - AT_RANDOM does not point at the random bytes");
+            return -1;
+        }
+    }
+
+    // Without a mapped program header table AT_PHDR is left out
+    let aux = AuxInfo { phdr: None, ..aux };
+    let Ok(image) = build_stack_image(STACK_TOP, None, None, &aux, &random) else {
+        return -1;
+    };
+    let mut addr = image.sp + 3 * 8;
+    while let Some(tag) = stack_word(&image, addr) {
+        if tag == AT_PHDR {
+            klog_info!("EXEC_TEST: BUG - AT_PHDR emitted without a mapped table");
+            return -1;
+        }
+        if tag == AT_NULL {
+            return 0;
+        }
+        addr += 16;
+    }
+    klog_info!("EXEC_TEST: BUG - auxv not terminated without AT_PHDR");
+    -1
+}
+
+type ShebangCase = (&'static [u8], Result<Option<Shebang<'static>>, ExecError>);
+
+pub fn test_exec_shebang_parse() -> c_int {
+    let sb = |interpreter: &'static [u8], arg: Option<&'static [u8]>| {
+        Ok(Some(Shebang { interpreter, arg }))
+    };
+    let cases: [ShebangCase; 9] = [
+        (b"#!/bin/sh\necho hi\n", sb(b"/bin/sh", None)),
+        (b"#!/bin/sh", sb(b"/bin/sh", None)),
+        (
+            b"#! /bin/env  slop -x \r\n",
+            sb(b"/bin/env", Some(b"slop -x")),
+        ),
+        (b"#!/bin/sh\t-e\n", sb(b"/bin/sh", Some(b"-e"))),
+        (b"#!\n/bin/sh\n", Err(ExecError::NoExec)),
+        (b"#!   \n", Err(ExecError::NoExec)),
+        (b"\x7fELF", Ok(None)),
+        (b"#/bin/sh\n", Ok(None)),
+        (b"", Ok(None)),
+    ];
+    for (i, (data, expected)) in cases.iter().enumerate() {
+        let got = parse_shebang(data);
+        if got != *expected {
+            klog_info!("EXEC_TEST: shebang case {} parsed as {:?}", i, got);
+            return -1;
+        }
+    }
+
+    let mut line = vec![b'a'; EXEC_MAX_SHEBANG_LINE + 1];
+    line[..3].copy_from_slice(b"#!/");
+    if parse_shebang(&line[..EXEC_MAX_SHEBANG_LINE]).is_err() {
+        klog_info!("EXEC_TEST: BUG - shebang line at the cap rejected");
+        return -1;
+    }
+    if parse_shebang(&line) != Err(ExecError::NoExec) {
+        klog_info!("EXEC_TEST: BUG - overlong shebang line accepted");
+        return -1;
+    }
+    0
+}
+
+pub fn test_exec_shebang_argv() -> c_int {
+    let with_arg = Shebang {
+        interpreter: b"/bin/sh",
+        arg: Some(b"-e"),
+    };
+    let argv: [&[u8]; 3] = [b"run.sh", b"one", b"two"];
+    let expected: [&[u8]; 5] = [b"/bin/sh", b"-e", b"/tmp/run.sh", b"one", b"two"];
+    match shebang_argv(&with_arg, b"/tmp/run.sh", Some(&argv)) {
+        Ok(v) if v[..] == expected[..] => {}
+        other => {
+            klog_info!("EXEC_TEST: BUG - script argv wrong: {:?}", other);
+            return -1;
+        }
+    }
+
+    let bare = Shebang {
+        interpreter: b"/bin/sh",
+        arg: None,
+    };
+    let expected: [&[u8]; 2] = [b"/bin/sh", b"/tmp/run.sh"];
+    if shebang_argv(&bare, b"/tmp/run.sh", None).as_deref() != Ok(&expected[..]) {
+        klog_info!("EXEC_TEST: BUG - script argv without caller args wrong");
+        return -1;
+    }
+
+    let full: [&[u8]; EXEC_MAX_ARGS] = [b"x"; EXEC_MAX_ARGS];
+    if shebang_argv(&with_arg, b"/tmp/run.sh", Some(&full)) != Err(ExecError::TooManyArgs) {
+        klog_info!("EXEC_TEST: BUG - script argv overflow not rejected");
+        return -1;
+    }
+    0
+}
+
+/// Write `content` to `path` in the VFS.
+fn write_exec_file(path: &[u8], content: &[u8]) -> bool {
+    let Ok(handle) = vfs_open(path, true) else {
+        return false;
+    };
+    handle.write(0, content) == Ok(content.len())
+}
+
+pub fn test_exec_shebang_chain() -> c_int {
+    let _ = vfs_mkdir(b"/exec_test");
+    let files: [(&[u8], &[u8]); 4] = [
+        (b"/exec_test/loop.sh", b"#!/exec_test/loop.sh\n"),
+        (b"/exec_test/text", b"plain text, neither ELF nor script\n"),
+        (b"/exec_test/hop.sh", b"#!/exec_test/text -x\n"),
+        (b"/exec_test/missing.sh", b"#!/exec_test/nowhere\n"),
+    ];
+    for (path, content) in files {
+        if !write_exec_file(path, content) {
+            klog_info!("EXEC_TEST: could not create test scripts");
+            return -1;
+        }
+    }
+
+    // The interpreter is resolved before any process state is touched, so
+    // an invalid process id is fine here.
+    let exec = |path: &[u8]| {
+        let (mut entry, mut sp) = (0, 0);
+        do_exec(INVALID_PROCESS_ID, path, None, None, &mut entry, &mut sp)
+    };
+    let results = [
+        (exec(b"/exec_test/loop.sh"), ExecError::Loop),
+        (exec(b"/exec_test/hop.sh"), ExecError::NoExec),
+        (exec(b"/exec_test/text"), ExecError::NoExec),
+        (exec(b"/exec_test/missing.sh"), ExecError::NoEntry),
+    ];
+    for (i, (got, expected)) in results.iter().enumerate() {
+        if *got != Err(*expected) {
+            klog_info!("EXEC_TEST: BUG - chain case {} returned {:?}", i, got);
+            return -1;
+        }
+    }
+    0
+}
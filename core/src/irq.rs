@@ -18,7 +18,10 @@ use slopos_abi::arch::x86_64::memory::{
 use slopos_lib::InitFlag;
 use slopos_lib::IrqMutex;
 use slopos_lib::string::cstr_to_str;
-use slopos_lib::{InterruptFrame, cpu, kdiag_dump_interrupt_frame, klog_debug, klog_info, tsc};
+use slopos_lib::{
+    InterruptFrame, KlogLevel, cpu, kdiag_dump_interrupt_frame, klog_debug, klog_defer, klog_info,
+    tsc,
+};
 
 use crate::platform;
 use crate::scheduler::scheduler::scheduler_handle_post_irq;
@@ -179,10 +182,11 @@ pub fn unmask_irq_line(irq: u8) {
     }
 }
 
-/// Log an unhandled IRQ (only once per line).
+/// Log an unhandled IRQ (only once per line). Runs in interrupt context, so
+/// the line is deferred rather than spun out on the UART.
 fn log_unhandled_irq(irq: u8, vector: u8) {
     if irq as usize >= IRQ_LINES {
-        klog_info!("IRQ: Spurious vector {} received", vector);
+        klog_defer!(KlogLevel::Info, "IRQ: Spurious vector {} received", vector);
         return;
     }
 
@@ -198,7 +202,8 @@ fn log_unhandled_irq(irq: u8, vector: u8) {
     if already_reported {
         return;
     }
-    klog_info!(
+    klog_defer!(
+        KlogLevel::Info,
         "IRQ: Unhandled IRQ {} (vector {}) - masking line",
         irq,
         vector
@@ -341,7 +346,7 @@ pub fn disable_line(irq: u8) {
 /// Main IRQ dispatch function - called from IDT handler.
 pub fn irq_dispatch(frame: *mut InterruptFrame) {
     if frame.is_null() {
-        klog_info!("IRQ: Received null frame");
+        klog_defer!(KlogLevel::Info, "IRQ: Received null frame");
         return;
     }
 
@@ -351,7 +356,10 @@ pub fn irq_dispatch(frame: *mut InterruptFrame) {
     let expected_rip = frame_ref.rip;
 
     if !IRQ_SYSTEM_INIT.is_set_relaxed() {
-        klog_info!("IRQ: Dispatch received before initialization");
+        klog_defer!(
+            KlogLevel::Info,
+            "IRQ: Dispatch received before initialization"
+        );
         if vector >= IRQ_BASE_VECTOR {
             acknowledge_irq();
         }
@@ -359,7 +367,7 @@ pub fn irq_dispatch(frame: *mut InterruptFrame) {
     }
 
     if vector < IRQ_BASE_VECTOR {
-        klog_info!("IRQ: Received non-IRQ vector {}", vector);
+        klog_defer!(KlogLevel::Info, "IRQ: Received non-IRQ vector {}", vector);
         return;
     }
 
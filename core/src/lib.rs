@@ -26,6 +26,7 @@ pub use scheduler::sched_tests;
 pub use scheduler::scheduler as sched;
 pub use scheduler::task;
 pub use scheduler::test_tasks;
+pub use scheduler::work_queue;
 pub use scheduler::work_steal;
 
 pub use scheduler::fate_api::*;
@@ -36,4 +37,5 @@ pub use scheduler::sched_tests::*;
 pub use scheduler::scheduler::*;
 pub use scheduler::task::*;
 pub use scheduler::test_tasks::*;
+pub use scheduler::work_queue::*;
 pub use scheduler::work_steal::*;
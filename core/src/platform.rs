@@ -1,5 +1,6 @@
 use core::ffi::{c_char, c_int, c_void};
 
+use slopos_abi::arch::x86_64::pci::PciDeviceInfo;
 use slopos_lib::ServiceCell;
 
 #[repr(C)]
@@ -16,6 +17,13 @@ pub struct PlatformServices {
 
     pub rng_next: fn() -> u64,
 
+    /// Wall-clock (seconds, microseconds) since the Unix epoch.
+    pub wall_clock_now: fn() -> (u64, u32),
+
+    /// Copy the boot-time PCI device list into a caller buffer; returns
+    /// entries written.
+    pub pci_enumerate: fn(&mut [PciDeviceInfo]) -> u32,
+
     pub gdt_set_kernel_rsp0: fn(u64),
 
     pub kernel_shutdown: fn(*const c_char) -> !,
@@ -55,6 +63,16 @@ pub fn timer_frequency() -> u32 {
     (platform().timer_get_frequency)()
 }
 
+#[inline(always)]
+pub fn wall_clock_now() -> (u64, u32) {
+    (platform().wall_clock_now)()
+}
+
+#[inline(always)]
+pub fn pci_enumerate(out: &mut [PciDeviceInfo]) -> u32 {
+    (platform().pci_enumerate)(out)
+}
+
 #[inline(always)]
 pub fn get_time_ms() -> u64 {
     let ticks = timer_ticks();
@@ -1,3 +1,4 @@
+use alloc::boxed::Box;
 use core::ffi::{c_char, c_int, c_void};
 
 use slopos_lib::klog_info;
@@ -40,6 +41,32 @@ pub fn kthread_spawn_ex(
 
     id
 }
+fn kthread_closure_trampoline<F: FnOnce() + Send + 'static>(arg: *mut c_void) {
+    let f = unsafe { Box::from_raw(arg as *mut F) };
+    f();
+}
+
+/// Spawn a kernel thread running `f`. The closure is boxed and handed to the
+/// task as its argument; the trampoline reclaims the box when the thread runs,
+/// and it is dropped here if the task cannot be created.
+pub fn kthread_spawn_closure<F>(name: *const c_char, priority: u8, f: F) -> KthreadId
+where
+    F: FnOnce() + Send + 'static,
+{
+    let arg = Box::into_raw(Box::new(f)) as *mut c_void;
+    let id = kthread_spawn_ex(
+        name,
+        Some(kthread_closure_trampoline::<F>),
+        arg,
+        priority,
+        0,
+    );
+    if id == INVALID_TASK_ID {
+        drop(unsafe { Box::from_raw(arg as *mut F) });
+    }
+    id
+}
+
 pub fn kthread_yield() {
     scheduler::r#yield();
 }
@@ -3,7 +3,7 @@
 use core::sync::atomic::{AtomicU64, Ordering};
 
 use slopos_abi::task::Task;
-use slopos_lib::{get_cpu_count, klog_debug};
+use slopos_lib::{get_cpu_count, klog_debug_mod};
 
 use super::per_cpu::{get_cpu_scheduler, with_cpu_scheduler};
 use super::work_steal::{calculate_load_imbalance, find_least_loaded_cpu, find_most_loaded_cpu};
@@ -93,7 +93,8 @@ fn migrate_task_between_cpus(from_cpu: usize, to_cpu: usize) -> bool {
         sched.enqueue_local(task);
     });
 
-    klog_debug!(
+    klog_debug_mod!(
+        "scheduler",
         "LOAD_BALANCE: Migrated task from CPU {} to CPU {}",
         from_cpu,
         to_cpu
@@ -125,7 +126,8 @@ pub fn trigger_migration(task: *mut Task) -> bool {
                 sched.enqueue_local(task);
             });
 
-            klog_debug!(
+            klog_debug_mod!(
+                "scheduler",
                 "LOAD_BALANCE: Migrated task to CPU {} due to affinity",
                 cpu_id
             );
@@ -12,4 +12,5 @@ pub mod switch_context;
 pub mod task;
 pub mod task_lock;
 pub mod test_tasks;
+pub mod work_queue;
 pub mod work_steal;
@@ -7,13 +7,17 @@ use core::ffi::c_void;
 use core::ptr;
 use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU64, Ordering};
 
+use slopos_abi::sched_traits::{ReadyTask, SchedPolicy};
 use slopos_abi::task::{
-    INVALID_TASK_ID, TASK_FLAG_KERNEL_MODE, TASK_PRIORITY_IDLE, TASK_STATE_READY, Task, TaskContext,
+    INVALID_TASK_ID, TASK_FLAG_KERNEL_MODE, TASK_PRIORITY_HIGH, TASK_PRIORITY_IDLE,
+    TASK_STATE_READY, Task, TaskContext,
 };
-use slopos_lib::{InitFlag, MAX_CPUS, klog_debug, klog_info};
+use slopos_lib::{InitFlag, MAX_CPUS, klog_debug_mod, klog_info};
 use spin::Mutex;
 
 const NUM_PRIORITY_LEVELS: usize = 4;
+/// Ready tasks offered to a scheduling policy per decision.
+const POLICY_MAX_CANDIDATES: usize = 32;
 
 #[derive(Default)]
 struct ReadyQueue {
@@ -150,6 +154,47 @@ impl ReadyQueue {
     }
 }
 
+impl ReadyList for ReadyQueue {
+    fn first(&self) -> *mut Task {
+        self.head
+    }
+
+    fn pop_first(&mut self) -> *mut Task {
+        self.dequeue()
+    }
+
+    fn unlink(&mut self, task: *mut Task) -> i32 {
+        self.remove(task)
+    }
+}
+
+/// A run queue linked through `Task::next_ready`, shared by the per-CPU and
+/// global schedulers.
+pub(super) trait ReadyList {
+    fn first(&self) -> *mut Task;
+    fn pop_first(&mut self) -> *mut Task;
+    fn unlink(&mut self, task: *mut Task) -> i32;
+
+    /// Unlink the first task whose affinity allows `cpu_id`.
+    fn dequeue_allowed(&mut self, cpu_id: usize) -> *mut Task {
+        let head = self.first();
+        if !head.is_null() && unsafe { (*head).allows_cpu(cpu_id) } {
+            return self.pop_first();
+        }
+        let mut cursor = head;
+        while !cursor.is_null() {
+            if unsafe { (*cursor).allows_cpu(cpu_id) } {
+                if self.unlink(cursor) != 0 {
+                    return ptr::null_mut();
+                }
+                return cursor;
+            }
+            cursor = unsafe { (*cursor).next_ready };
+        }
+        ptr::null_mut()
+    }
+}
+
 const EMPTY_QUEUE: ReadyQueue = ReadyQueue::new();
 
 #[repr(C, align(64))]
@@ -257,17 +302,56 @@ impl PerCpuScheduler {
             );
             return -1;
         }
-        let priority = unsafe { (*task).priority as usize };
-        let idx = priority.min(NUM_PRIORITY_LEVELS - 1);
+        let queued = unsafe { (*task).effective_priority as usize };
+        let priority = unsafe { (*task).priority };
+        let idx = (priority as usize).min(NUM_PRIORITY_LEVELS - 1);
 
+        let _guard = self.queue_lock.lock();
+        if self.ready_queues[queued.min(NUM_PRIORITY_LEVELS - 1)].contains(task) {
+            return 0;
+        }
+        // A fresh trip through the queue starts at the base priority again,
+        // so any aging boost ends once the task has run.
         unsafe {
             (*task).last_cpu = self.cpu_id as u8;
+            (*task).effective_priority = priority;
+            (*task).ready_wait_ticks = 0;
         }
-
-        let _guard = self.queue_lock.lock();
         self.ready_queues[idx].enqueue(task)
     }
 
+    /// Charge one tick of waiting to every queued task below the top level
+    /// and move those that have waited `threshold` ticks up to it.
+    ///
+    /// Idle-priority tasks are never boosted. Runs from the timer tick, so a
+    /// tick that interrupted a queue operation on this CPU skips aging rather
+    /// than spinning on the lock. Returns the number boosted.
+    pub fn age_ready_tasks(&mut self, threshold: u32) -> u32 {
+        let Some(_guard) = self.queue_lock.try_lock() else {
+            return 0;
+        };
+        let top = TASK_PRIORITY_HIGH as usize;
+        let mut boosted = 0;
+        for level in top + 1..TASK_PRIORITY_IDLE as usize {
+            let mut cursor = self.ready_queues[level].head;
+            while !cursor.is_null() {
+                let task = cursor;
+                cursor = unsafe { (*task).next_ready };
+                let waited = unsafe {
+                    (*task).ready_wait_ticks = (*task).ready_wait_ticks.saturating_add(1);
+                    (*task).ready_wait_ticks
+                };
+                if waited < threshold || self.ready_queues[level].remove(task) != 0 {
+                    continue;
+                }
+                unsafe { (*task).effective_priority = TASK_PRIORITY_HIGH };
+                self.ready_queues[top].enqueue(task);
+                boosted += 1;
+            }
+        }
+        boosted
+    }
+
     pub fn dequeue_highest_priority(&mut self) -> *mut Task {
         // Sanity check: ensure self pointer is in valid kernel space
         let self_addr = self as *const _ as usize;
@@ -279,8 +363,9 @@ impl PerCpuScheduler {
             return ptr::null_mut();
         }
         let _guard = self.queue_lock.lock();
+        let cpu_id = self.cpu_id;
         for queue in self.ready_queues.iter_mut() {
-            let task = queue.dequeue();
+            let task = queue.dequeue_allowed(cpu_id);
             if !task.is_null() {
                 return task;
             }
@@ -288,18 +373,71 @@ impl PerCpuScheduler {
         ptr::null_mut()
     }
 
+    /// Dequeue the task chosen by `policy` among the first
+    /// `POLICY_MAX_CANDIDATES` ready tasks (priority order, FIFO per level)
+    /// whose affinity allows this CPU.
+    pub fn dequeue_with_policy(&mut self, policy: &dyn SchedPolicy) -> *mut Task {
+        let _guard = self.queue_lock.lock();
+
+        let mut ready = [ReadyTask::default(); POLICY_MAX_CANDIDATES];
+        let mut tasks: [*mut Task; POLICY_MAX_CANDIDATES] =
+            [ptr::null_mut(); POLICY_MAX_CANDIDATES];
+        let mut levels = [0usize; POLICY_MAX_CANDIDATES];
+        let mut count = 0;
+
+        'collect: for (level, queue) in self.ready_queues.iter().enumerate() {
+            let mut cursor = queue.head;
+            while !cursor.is_null() {
+                if count == POLICY_MAX_CANDIDATES {
+                    break 'collect;
+                }
+                let task = unsafe { &*cursor };
+                if !task.allows_cpu(self.cpu_id) {
+                    cursor = task.next_ready;
+                    continue;
+                }
+                ready[count] = ReadyTask {
+                    task_id: task.task_id,
+                    priority: task.effective_priority,
+                    total_runtime: task.total_runtime,
+                    last_run_timestamp: task.last_run_timestamp,
+                    time_slice: task.time_slice,
+                };
+                tasks[count] = cursor;
+                levels[count] = level;
+                count += 1;
+                cursor = task.next_ready;
+            }
+        }
+
+        if count == 0 {
+            return ptr::null_mut();
+        }
+
+        let idx = policy
+            .pick_next(&ready[..count])
+            .filter(|&i| i < count)
+            .unwrap_or(0);
+        let task = tasks[idx];
+        if self.ready_queues[levels[idx]].remove(task) != 0 {
+            return ptr::null_mut();
+        }
+        task
+    }
+
     pub fn remove_task(&mut self, task: *mut Task) -> i32 {
         if task.is_null() {
             return -1;
         }
-        let priority = unsafe { (*task).priority as usize };
+        let priority = unsafe { (*task).effective_priority as usize };
         let idx = priority.min(NUM_PRIORITY_LEVELS - 1);
         let _guard = self.queue_lock.lock();
         self.ready_queues[idx].remove(task)
     }
 
+    /// Sum of the per-queue counters. Reads them without the queue lock, so
+    /// it is safe from interrupt context; the total may be momentarily stale.
     pub fn total_ready_count(&self) -> u32 {
-        let _guard = self.queue_lock.lock();
         self.ready_queues.iter().map(|q| q.len()).sum()
     }
 
@@ -361,7 +499,11 @@ pub fn init_percpu_scheduler(cpu_id: usize) {
     unsafe {
         CPU_SCHEDULERS[cpu_id].init(cpu_id);
     }
-    klog_debug!("SCHED: Per-CPU scheduler initialized for CPU {}", cpu_id);
+    klog_debug_mod!(
+        "scheduler",
+        "SCHED: Per-CPU scheduler initialized for CPU {}",
+        cpu_id
+    );
 }
 
 pub fn init_all_percpu_schedulers() {
@@ -470,12 +612,37 @@ pub fn select_target_cpu(task: *mut Task) -> usize {
         }
     }
 
-    find_least_loaded_cpu(affinity)
+    find_least_loaded_cpu(affinity).unwrap_or(0)
+}
+
+/// Move a ready `task` off the queue of a CPU its affinity no longer allows
+/// onto the least loaded CPU it does. It stays put when no allowed CPU is
+/// up. Returns true if the task moved.
+pub fn migrate_disallowed_task(task: *mut Task) -> bool {
+    if task.is_null() {
+        return false;
+    }
+    let (affinity, last_cpu) = unsafe { ((*task).cpu_affinity, (*task).last_cpu as usize) };
+    if unsafe { (*task).allows_cpu(last_cpu) } {
+        return false;
+    }
+    let Some(target) = find_least_loaded_cpu(affinity) else {
+        return false;
+    };
+    if with_cpu_scheduler(last_cpu, |sched| sched.remove_task(task)) != Some(0) {
+        return false;
+    }
+    if enqueue_task_on_cpu(target, task) != 0 {
+        // Never lose a ready task; it will wait for its old CPU instead
+        enqueue_task_on_cpu(last_cpu, task);
+        return false;
+    }
+    true
 }
 
-fn find_least_loaded_cpu(affinity: u32) -> usize {
+fn find_least_loaded_cpu(affinity: u32) -> Option<usize> {
     let cpu_count = slopos_lib::get_cpu_count();
-    let mut best_cpu = 0usize;
+    let mut best_cpu = None;
     let mut min_load = u32::MAX;
 
     for cpu_id in 0..cpu_count {
@@ -490,7 +657,7 @@ fn find_least_loaded_cpu(affinity: u32) -> usize {
         if let Some(load) = with_cpu_scheduler(cpu_id, |sched| sched.total_ready_count()) {
             if load < min_load {
                 min_load = load;
-                best_cpu = cpu_id;
+                best_cpu = Some(cpu_id);
             }
         }
     }
@@ -583,7 +750,12 @@ pub fn create_ap_idle_task(cpu_id: usize) -> *mut Task {
         sched.set_idle_task(idle_task);
     });
 
-    klog_debug!("SCHED: Created idle task {} for CPU {}", task_id, cpu_id);
+    klog_debug_mod!(
+        "scheduler",
+        "SCHED: Created idle task {} for CPU {}",
+        task_id,
+        cpu_id
+    );
 
     idle_task
 }
@@ -9,20 +9,34 @@
 
 use core::ffi::{c_char, c_void};
 use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
-use slopos_lib::klog_info;
 use slopos_lib::testing::TestResult;
+use slopos_lib::{cpu, kdiag_timestamp, klog_info};
 
-use super::per_cpu::{pause_all_aps, resume_all_aps_if_not_nested};
+use super::kthread::kthread_spawn_closure;
+use super::per_cpu::{
+    enqueue_task_on_cpu, pause_all_aps, resume_all_aps_if_not_nested, with_cpu_scheduler,
+};
 use super::scheduler::{
-    self, get_scheduler_stats, init_scheduler, schedule, schedule_task, scheduler_is_enabled,
-    scheduler_shutdown, scheduler_timer_tick, unschedule_task,
+    self, SCHED_AGING_THRESHOLD_TICKS, SchedDryRun, WaitError, dequeue_next_ready,
+    get_scheduler_stats, init_scheduler, schedule, schedule_task, scheduler_has_other_ready,
+    scheduler_is_enabled, scheduler_policy_name, scheduler_reset_policy, scheduler_set_policy,
+    scheduler_shutdown, scheduler_timer_tick, sleep_task_until, sleep_ticks_for_ns, task_wait,
+    task_waitpid, unschedule_task,
 };
 use super::task::{
-    INVALID_TASK_ID, MAX_TASKS, TASK_FLAG_KERNEL_MODE, TASK_PRIORITY_HIGH, TASK_PRIORITY_IDLE,
-    TASK_PRIORITY_LOW, TASK_PRIORITY_NORMAL, TASK_STATE_BLOCKED, TASK_STATE_READY,
-    TASK_STATE_RUNNING, Task, init_task_manager, task_create, task_find_by_id, task_get_info,
-    task_set_state, task_shutdown_all, task_terminate,
+    INVALID_TASK_ID, MAX_TASKS, StateError, TASK_AFFINITY_ANY, TASK_EXIT_CODE_KILLED,
+    TASK_FLAG_KERNEL_MODE, TASK_PRIORITY_HIGH, TASK_PRIORITY_IDLE, TASK_PRIORITY_LOW,
+    TASK_PRIORITY_NORMAL, TASK_STATE_BLOCKED, TASK_STATE_READY, TASK_STATE_RUNNING, Task,
+    TaskStatus, init_task_manager, task_create, task_find_by_id, task_get_cpu_time, task_get_info,
+    task_record_context_switch, task_set_affinity, task_set_exit_code, task_set_parent,
+    task_set_state, task_shutdown_all, task_terminate, task_transition,
+};
+use super::work_queue::WorkQueue;
+use crate::{irq, platform};
+use slopos_abi::sched_traits::{
+    EarliestDeadline, FairRoundRobin, ReadyTask, RoundRobin, SchedPolicy,
 };
 
 // =============================================================================
@@ -193,6 +207,397 @@ pub fn test_state_transition_invalid_terminated_to_running() -> TestResult {
     TestResult::Pass
 }
 
+fn create_transition_task() -> *mut Task {
+    let task_id = task_create(
+        b"Transition\0".as_ptr() as *const c_char,
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_NORMAL,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    if task_id == INVALID_TASK_ID {
+        return ptr::null_mut();
+    }
+    task_find_by_id(task_id)
+}
+
+/// Test: task_transition accepts a legal RUNNING -> READY move
+pub fn test_transition_running_to_ready() -> TestResult {
+    let _fixture = SchedFixture::new();
+
+    let task = create_transition_task();
+    if task.is_null() {
+        return TestResult::Fail;
+    }
+    let task = unsafe { &*task };
+
+    if task_transition(task, TaskStatus::Running).is_err()
+        || task_transition(task, TaskStatus::Ready).is_err()
+    {
+        klog_info!("SCHED_TEST: legal RUNNING->READY transition rejected");
+        return TestResult::Fail;
+    }
+    if task.status() != TaskStatus::Ready {
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: task_transition rejects TERMINATED -> RUNNING and leaves the state alone
+pub fn test_transition_terminated_to_running_rejected() -> TestResult {
+    let _fixture = SchedFixture::new();
+
+    let task = create_transition_task();
+    if task.is_null() {
+        return TestResult::Fail;
+    }
+    let task = unsafe { &*task };
+
+    if task_transition(task, TaskStatus::Terminated).is_err() {
+        return TestResult::Fail;
+    }
+    let result = task_transition(task, TaskStatus::Running);
+    if result
+        != Err(StateError::Illegal {
+            from: TaskStatus::Terminated,
+            to: TaskStatus::Running,
+        })
+    {
+        klog_info!(
+            "SCHED_TEST: BUG - TERMINATED->RUNNING returned {:?}",
+            result
+        );
+        return TestResult::Fail;
+    }
+    if task_transition(task, TaskStatus::Ready).is_ok() {
+        klog_info!("SCHED_TEST: BUG - terminated task could be re-queued");
+        return TestResult::Fail;
+    }
+    if task.status() != TaskStatus::Terminated {
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: transitioning to the current state is refused, so a second waker
+/// cannot mistake an already-Ready task for one it just woke
+pub fn test_transition_same_state_rejected() -> TestResult {
+    let _fixture = SchedFixture::new();
+
+    let task = create_transition_task();
+    if task.is_null() {
+        return TestResult::Fail;
+    }
+    let task = unsafe { &*task };
+
+    for status in [TaskStatus::Running, TaskStatus::Blocked, TaskStatus::Ready] {
+        if task_transition(task, status).is_err() {
+            klog_info!("SCHED_TEST: legal transition to {:?} rejected", status);
+            return TestResult::Fail;
+        }
+        let again = task_transition(task, status);
+        if again != Err(StateError::Unchanged { state: status }) {
+            klog_info!(
+                "SCHED_TEST: BUG - repeated transition to {:?} returned {:?}",
+                status,
+                again
+            );
+            return TestResult::Fail;
+        }
+        if task.status() != status {
+            return TestResult::Fail;
+        }
+    }
+    if task.mark_ready() {
+        klog_info!("SCHED_TEST: BUG - mark_ready succeeded on a Ready task");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+// =============================================================================
+// Scheduling Policy Tests
+// =============================================================================
+
+static POLICY_TARGET_ID: AtomicU32 = AtomicU32::new(INVALID_TASK_ID);
+
+/// Trivial policy: always pick the task whose id is in `POLICY_TARGET_ID`.
+struct PickTaskId;
+
+impl SchedPolicy for PickTaskId {
+    fn name(&self) -> &'static str {
+        "pick-task-id"
+    }
+
+    fn pick_next(&self, ready: &[ReadyTask]) -> Option<usize> {
+        let target = POLICY_TARGET_ID.load(Ordering::SeqCst);
+        ready.iter().position(|t| t.task_id == target)
+    }
+}
+
+fn create_policy_tasks(ids: &mut [u32; 3]) -> bool {
+    let cpu = slopos_lib::get_current_cpu();
+    for id in ids.iter_mut() {
+        *id = task_create(
+            c"PolicyTask".as_ptr(),
+            dummy_task_fn,
+            ptr::null_mut(),
+            TASK_PRIORITY_NORMAL,
+            TASK_FLAG_KERNEL_MODE,
+        );
+        let task = task_find_by_id(*id);
+        if task.is_null() || enqueue_task_on_cpu(cpu, task) != 0 {
+            return false;
+        }
+    }
+    true
+}
+
+/// Test: the scheduler dequeues whatever task the registered policy picks
+pub fn test_sched_policy_custom_pick_honored() -> TestResult {
+    let _fixture = SchedFixture::new();
+    let cpu = slopos_lib::get_current_cpu();
+
+    let mut ids = [INVALID_TASK_ID; 3];
+    if !create_policy_tasks(&mut ids) {
+        return TestResult::Fail;
+    }
+
+    // Ask for the last-queued task; FIFO would pick ids[0]
+    POLICY_TARGET_ID.store(ids[2], Ordering::SeqCst);
+    scheduler_set_policy(&PickTaskId);
+    let picked = dequeue_next_ready(cpu);
+    let name = scheduler_policy_name();
+
+    // Unknown target: the policy declines and the scheduler falls back to FIFO
+    POLICY_TARGET_ID.store(INVALID_TASK_ID, Ordering::SeqCst);
+    let fallback = dequeue_next_ready(cpu);
+    scheduler_reset_policy();
+
+    if picked.is_null() || unsafe { (*picked).task_id } != ids[2] {
+        klog_info!("SCHED_TEST: custom policy choice ignored");
+        return TestResult::Fail;
+    }
+    if fallback.is_null() || unsafe { (*fallback).task_id } != ids[0] {
+        klog_info!("SCHED_TEST: policy fallback did not take queue head");
+        return TestResult::Fail;
+    }
+    if name != "pick-task-id" || scheduler_policy_name() != RoundRobin.name() {
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: set_policy returns the previous policy and RoundRobin keeps FIFO order
+pub fn test_sched_policy_round_robin_default() -> TestResult {
+    let _fixture = SchedFixture::new();
+    let cpu = slopos_lib::get_current_cpu();
+
+    let mut ids = [INVALID_TASK_ID; 3];
+    if !create_policy_tasks(&mut ids) {
+        return TestResult::Fail;
+    }
+
+    let prev = scheduler_set_policy(&RoundRobin);
+    let first = dequeue_next_ready(cpu);
+    scheduler_reset_policy();
+    let second = dequeue_next_ready(cpu);
+
+    if prev.name() != RoundRobin.name() {
+        return TestResult::Fail;
+    }
+    if first.is_null() || second.is_null() {
+        return TestResult::Fail;
+    }
+    if unsafe { ((*first).task_id, (*second).task_id) } != (ids[0], ids[1]) {
+        klog_info!("SCHED_TEST: round-robin order broken");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: EDF and fair-RR choose within the top priority only
+pub fn test_sched_policy_edf_and_fair_pick() -> TestResult {
+    let task = |task_id, priority, total_runtime, last_run_timestamp| ReadyTask {
+        task_id,
+        priority,
+        total_runtime,
+        last_run_timestamp,
+        time_slice: 10,
+    };
+    let ready = [
+        task(1, 0, 50, 300),
+        task(2, 0, 10, 200),
+        task(3, 0, 30, 100),
+        // Lower priority: never chosen while priority 0 work is ready
+        task(4, 1, 0, 0),
+    ];
+
+    if EarliestDeadline.pick_next(&ready) != Some(2) {
+        return TestResult::Fail;
+    }
+    if FairRoundRobin.pick_next(&ready) != Some(1) {
+        return TestResult::Fail;
+    }
+    if RoundRobin.pick_next(&ready) != Some(0) {
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+// =============================================================================
+// Work Queue Tests
+// =============================================================================
+
+static WORK_ORDER: [AtomicU32; 8] = [const { AtomicU32::new(0) }; 8];
+static WORK_RAN: AtomicU32 = AtomicU32::new(0);
+
+fn work_record(tag: u32) {
+    let idx = WORK_RAN.fetch_add(1, Ordering::SeqCst) as usize;
+    if idx < WORK_ORDER.len() {
+        WORK_ORDER[idx].store(tag, Ordering::SeqCst);
+    }
+}
+
+fn work_a() {
+    work_record(1);
+}
+fn work_b() {
+    work_record(2);
+}
+fn work_c() {
+    work_record(3);
+}
+fn work_d() {
+    work_record(4);
+}
+fn work_e() {
+    work_record(5);
+}
+
+fn work_reset() {
+    WORK_RAN.store(0, Ordering::SeqCst);
+    for slot in &WORK_ORDER {
+        slot.store(0, Ordering::SeqCst);
+    }
+}
+
+fn work_order_is(expected: &[u32]) -> bool {
+    WORK_RAN.load(Ordering::SeqCst) as usize == expected.len()
+        && expected
+            .iter()
+            .zip(WORK_ORDER.iter())
+            .all(|(want, got)| got.load(Ordering::SeqCst) == *want)
+}
+
+/// Test: queued work items drain in FIFO order
+pub fn test_work_queue_fifo_drain() -> TestResult {
+    work_reset();
+    let queue: WorkQueue<8> = WorkQueue::new();
+
+    for work in [work_a, work_b, work_c, work_d] {
+        if !queue.enqueue(work) {
+            return TestResult::Fail;
+        }
+    }
+    if queue.run_pending() != 4 || !queue.is_empty() {
+        klog_info!("SCHED_TEST: work queue did not drain all items");
+        return TestResult::Fail;
+    }
+    if !work_order_is(&[1, 2, 3, 4]) {
+        klog_info!("SCHED_TEST: work items ran out of order");
+        return TestResult::Fail;
+    }
+    if queue.dequeue().is_some() || queue.dropped() != 0 {
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: enqueueing past capacity counts drops and leaves the queue usable
+pub fn test_work_queue_overflow_counts_drops() -> TestResult {
+    work_reset();
+    let queue: WorkQueue<4> = WorkQueue::new();
+
+    let accepted = [work_a, work_b, work_c, work_d, work_e, work_e]
+        .into_iter()
+        .filter(|work| queue.enqueue(*work))
+        .count();
+    if accepted != 4 || queue.dropped() != 2 {
+        klog_info!(
+            "SCHED_TEST: overflow accepted={} dropped={}",
+            accepted,
+            queue.dropped()
+        );
+        return TestResult::Fail;
+    }
+
+    // Drain partway, then refill across the wrap point
+    if queue.dequeue().is_none() {
+        return TestResult::Fail;
+    }
+    if !queue.enqueue(work_e) || queue.enqueue(work_e) {
+        return TestResult::Fail;
+    }
+    if queue.dropped() != 3 {
+        return TestResult::Fail;
+    }
+
+    work_reset();
+    if queue.run_pending() != 4 || !work_order_is(&[2, 3, 4, 5]) {
+        klog_info!("SCHED_TEST: queue corrupted after overflow");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: a block cancelled before any waker ran returns to RUNNING, while a
+/// block a waker already undid stays READY for the queue it was put on
+pub fn test_cancel_block_only_before_wake() -> TestResult {
+    let _fixture = SchedFixture::new();
+
+    let task = create_transition_task();
+    if task.is_null() {
+        return TestResult::Fail;
+    }
+    let task = unsafe { &*task };
+
+    if task_transition(task, TaskStatus::Running).is_err()
+        || task_transition(task, TaskStatus::Blocked).is_err()
+    {
+        return TestResult::Fail;
+    }
+    if !task.cancel_block() || task.status() != TaskStatus::Running {
+        klog_info!("SCHED_TEST: un-woken block was not cancelled");
+        return TestResult::Fail;
+    }
+
+    if task_transition(task, TaskStatus::Blocked).is_err()
+        || task_transition(task, TaskStatus::Ready).is_err()
+    {
+        return TestResult::Fail;
+    }
+    if task.cancel_block() || task.status() != TaskStatus::Ready {
+        klog_info!("SCHED_TEST: BUG - cancel_block overrode a wakeup");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: blocking with the wake condition already true does not sleep
+pub fn test_block_unless_pending_returns() -> TestResult {
+    let current = scheduler::scheduler_get_current_task();
+    if current.is_null() || unsafe { (*current).status() } != TaskStatus::Running {
+        return TestResult::Skipped;
+    }
+    scheduler::block_current_task_unless(|| true);
+    if unsafe { (*current).status() } != TaskStatus::Running {
+        klog_info!("SCHED_TEST: pending wakeup left the caller blocked");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
 /// Test: INVALID state transition BLOCKED -> RUNNING (should go through READY first)
 pub fn test_state_transition_invalid_blocked_to_running() -> TestResult {
     let _fixture = SchedFixture::new();
@@ -927,3 +1332,637 @@ pub fn test_interleaved_operations() -> TestResult {
 
     TestResult::Pass
 }
+
+// =============================================================================
+// CLOSURE KTHREAD TESTS
+// =============================================================================
+
+static CLOSURE_RAN: AtomicBool = AtomicBool::new(false);
+static CLOSURE_DROPS: AtomicU32 = AtomicU32::new(0);
+
+struct DropCounter;
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        CLOSURE_DROPS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Test: a spawned closure runs when its task entry is invoked
+pub fn test_kthread_closure_runs() -> TestResult {
+    let _fixture = SchedFixture::new();
+    CLOSURE_RAN.store(false, Ordering::SeqCst);
+
+    let value = 0x5105u32;
+    let task_id = kthread_spawn_closure(
+        b"ClosureTask\0".as_ptr() as *const c_char,
+        TASK_PRIORITY_NORMAL,
+        move || {
+            if value == 0x5105 {
+                CLOSURE_RAN.store(true, Ordering::SeqCst);
+            }
+        },
+    );
+    if task_id == INVALID_TASK_ID {
+        klog_info!("SCHED_TEST: kthread_spawn_closure failed");
+        return TestResult::Fail;
+    }
+
+    let task = task_find_by_id(task_id);
+    if task.is_null() {
+        klog_info!("SCHED_TEST: Spawned closure task not found");
+        return TestResult::Fail;
+    }
+
+    // The scheduler is not running here; call the entry the way the
+    // context-switch trampoline would.
+    let (entry, arg) = unsafe { ((*task).entry_point, (*task).entry_arg) };
+    let entry: fn(*mut c_void) = unsafe { core::mem::transmute(entry as usize) };
+    entry(arg);
+    task_terminate(task_id);
+
+    if !CLOSURE_RAN.load(Ordering::SeqCst) {
+        klog_info!("SCHED_TEST: Closure did not run");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: a failed spawn drops the boxed closure instead of leaking it
+pub fn test_kthread_closure_failure_frees_box() -> TestResult {
+    let _fixture = SchedFixture::new();
+
+    for _ in 0..MAX_TASKS {
+        let _ = task_create(
+            b"Filler\0".as_ptr() as *const c_char,
+            dummy_task_fn,
+            ptr::null_mut(),
+            TASK_PRIORITY_NORMAL,
+            TASK_FLAG_KERNEL_MODE,
+        );
+    }
+
+    CLOSURE_DROPS.store(0, Ordering::SeqCst);
+    let guard = DropCounter;
+    let task_id = kthread_spawn_closure(
+        b"Overflow\0".as_ptr() as *const c_char,
+        TASK_PRIORITY_NORMAL,
+        move || {
+            let _guard = guard;
+        },
+    );
+
+    if task_id != INVALID_TASK_ID {
+        klog_info!("SCHED_TEST: Spawn succeeded with a full task table");
+        return TestResult::Fail;
+    }
+    if CLOSURE_DROPS.load(Ordering::SeqCst) != 1 {
+        klog_info!("SCHED_TEST: BUG - closure leaked on failed spawn");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+// =============================================================================
+// EXIT CODE PROPAGATION TESTS
+// =============================================================================
+
+/// Test: a task exiting with a code has it returned once by task_wait
+pub fn test_exit_code_retrievable_via_wait() -> TestResult {
+    let _fixture = SchedFixture::new();
+
+    let task_id = task_create(
+        b"Exiter\0".as_ptr() as *const c_char,
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_NORMAL,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    if task_id == INVALID_TASK_ID {
+        return TestResult::Fail;
+    }
+
+    if task_set_exit_code(task_id, 42) != 0 || task_terminate(task_id) != 0 {
+        klog_info!("SCHED_TEST: Failed to exit task with code");
+        return TestResult::Fail;
+    }
+
+    if task_wait(task_id) != Some(42) {
+        klog_info!("SCHED_TEST: BUG - exit code not propagated to wait");
+        return TestResult::Fail;
+    }
+    if task_wait(task_id).is_some() {
+        klog_info!("SCHED_TEST: BUG - exit record not reaped by wait");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: waitpid reaps a child's exit code once and only for its parent
+pub fn test_waitpid_reaps_child_once() -> TestResult {
+    let _fixture = SchedFixture::new();
+
+    let spawn = |name: &[u8]| {
+        task_create(
+            name.as_ptr() as *const c_char,
+            dummy_task_fn,
+            ptr::null_mut(),
+            TASK_PRIORITY_NORMAL,
+            TASK_FLAG_KERNEL_MODE,
+        )
+    };
+    let parent = spawn(b"Parent\0");
+    let child = spawn(b"Child\0");
+    if parent == INVALID_TASK_ID || child == INVALID_TASK_ID {
+        return TestResult::Fail;
+    }
+    if task_set_parent(child, parent) != 0 {
+        return TestResult::Fail;
+    }
+
+    if task_waitpid(parent, child, true) != Err(WaitError::StillRunning) {
+        klog_info!("SCHED_TEST: BUG - WNOHANG on a running child did not return at once");
+        return TestResult::Fail;
+    }
+    if task_waitpid(child, parent, true) != Err(WaitError::NotChild)
+        || task_waitpid(parent, child.wrapping_add(1000), true) != Err(WaitError::NotChild)
+    {
+        klog_info!("SCHED_TEST: BUG - waitpid accepted a task that is not a child");
+        return TestResult::Fail;
+    }
+
+    if task_set_exit_code(child, 7) != 0 || task_terminate(child) != 0 {
+        return TestResult::Fail;
+    }
+    if task_waitpid(child.wrapping_add(1000), child, false) != Err(WaitError::NotChild) {
+        klog_info!("SCHED_TEST: BUG - a stranger reaped the child");
+        return TestResult::Fail;
+    }
+    if task_waitpid(parent, child, false) != Ok(7) {
+        klog_info!("SCHED_TEST: BUG - waitpid did not return the exit code");
+        return TestResult::Fail;
+    }
+    if task_waitpid(parent, child, false) != Err(WaitError::NotChild) {
+        klog_info!("SCHED_TEST: BUG - exit code was reaped twice");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: a sleeping task is not runnable again until its deadline tick
+pub fn test_sleep_wakes_after_deadline() -> TestResult {
+    let _fixture = SchedFixture::new();
+
+    if sleep_ticks_for_ns(0, 100) != 0
+        || sleep_ticks_for_ns(1, 100) != 1
+        || sleep_ticks_for_ns(10_000_000, 100) != 1
+        || sleep_ticks_for_ns(10_000_001, 100) != 2
+    {
+        klog_info!("SCHED_TEST: BUG - sleep durations not rounded up to whole ticks");
+        return TestResult::Fail;
+    }
+
+    let task_id = task_create(
+        b"Sleeper\0".as_ptr() as *const c_char,
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_NORMAL,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    let task = task_find_by_id(task_id);
+    if task.is_null() {
+        return TestResult::Fail;
+    }
+
+    // Tick the way the timer IRQ does; the real timer must not tick underneath.
+    const SLEEP_TICKS: u64 = 70;
+    let flags = cpu::save_flags_cli();
+    let start = platform::timer_ticks();
+    let mut woke_after = None;
+    if sleep_task_until(task, start + SLEEP_TICKS) == 0 {
+        for elapsed in 1..=SLEEP_TICKS + 1 {
+            irq::increment_timer_ticks();
+            scheduler_timer_tick();
+            if !unsafe { (*task).is_blocked() } {
+                woke_after = Some(elapsed);
+                break;
+            }
+        }
+    }
+    cpu::restore_flags(flags);
+
+    if woke_after != Some(SLEEP_TICKS) {
+        klog_info!(
+            "SCHED_TEST: BUG - sleeper woke after {:?} ticks",
+            woke_after
+        );
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: a low-priority task behind constant high-priority work is aged to
+/// the top level and runs within the threshold window
+pub fn test_aging_prevents_low_priority_starvation() -> TestResult {
+    let _fixture = SchedFixture::new();
+    let cpu = slopos_lib::get_current_cpu();
+
+    let low_id = task_create(
+        c"AgingLow".as_ptr(),
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_LOW,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    let high_id = task_create(
+        c"AgingHigh".as_ptr(),
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_HIGH,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    let low = task_find_by_id(low_id);
+    let high = task_find_by_id(high_id);
+    if low.is_null() || high.is_null() {
+        return TestResult::Fail;
+    }
+
+    // Tick by hand: the high task runs every tick and is requeued at once,
+    // so without aging the low task would never be picked.
+    let flags = cpu::save_flags_cli();
+    let mut ran_after = None;
+    if enqueue_task_on_cpu(cpu, low) == 0 && enqueue_task_on_cpu(cpu, high) == 0 {
+        for tick in 1..=SCHED_AGING_THRESHOLD_TICKS + 2 {
+            with_cpu_scheduler(cpu, |s| s.age_ready_tasks(SCHED_AGING_THRESHOLD_TICKS));
+            let picked = dequeue_next_ready(cpu);
+            if picked == low {
+                ran_after = Some(tick);
+                break;
+            }
+            if picked != high || enqueue_task_on_cpu(cpu, high) != 0 {
+                break;
+            }
+        }
+    }
+    let boosted_level = unsafe { (*low).effective_priority };
+    let requeued = enqueue_task_on_cpu(cpu, low) == 0;
+    let decayed_level = unsafe { (*low).effective_priority };
+    cpu::restore_flags(flags);
+
+    if ran_after.is_none_or(|tick| tick > SCHED_AGING_THRESHOLD_TICKS + 1) {
+        klog_info!(
+            "SCHED_TEST: BUG - low-priority task starved, ran after {:?} ticks",
+            ran_after
+        );
+        return TestResult::Fail;
+    }
+    if unsafe { (*low).priority } != TASK_PRIORITY_LOW || boosted_level != TASK_PRIORITY_HIGH {
+        klog_info!("SCHED_TEST: BUG - aging changed the base priority or missed the boost");
+        return TestResult::Fail;
+    }
+    if !requeued || decayed_level != TASK_PRIORITY_LOW {
+        klog_info!("SCHED_TEST: BUG - aging boost outlived the run");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: equal-priority tasks take turns, and a lone ready task keeps
+/// being picked
+pub fn test_equal_priority_round_robin() -> TestResult {
+    let _fixture = SchedFixture::new();
+    let cpu = slopos_lib::get_current_cpu();
+
+    let mut ids = [INVALID_TASK_ID; 3];
+    let names = [c"RoundA", c"RoundB", c"RoundC"];
+    for (id, name) in ids.iter_mut().zip(names) {
+        *id = task_create(
+            name.as_ptr(),
+            dummy_task_fn,
+            ptr::null_mut(),
+            TASK_PRIORITY_NORMAL,
+            TASK_FLAG_KERNEL_MODE,
+        );
+        if task_find_by_id(*id).is_null() {
+            return TestResult::Fail;
+        }
+    }
+    let tasks = ids.map(task_find_by_id);
+    let current_id = || {
+        let task = scheduler::scheduler_get_current_task();
+        if task.is_null() {
+            INVALID_TASK_ID
+        } else {
+            unsafe { (*task).task_id }
+        }
+    };
+
+    // RoundA is running and the others are ready; every schedule() preempts
+    // whichever task holds the CPU.
+    let flags = cpu::save_flags_cli();
+    let dry_run = SchedDryRun::new(tasks[0]);
+    let queued = tasks[1..]
+        .iter()
+        .all(|&task| enqueue_task_on_cpu(cpu, task) == 0);
+    let mut picked = [INVALID_TASK_ID; 6];
+    for slot in picked.iter_mut() {
+        schedule();
+        *slot = current_id();
+    }
+    // Leave RoundA alone on the CPU; it must keep being picked.
+    for &task in &tasks[1..] {
+        with_cpu_scheduler(cpu, |s| s.remove_task(task));
+    }
+    let mut lone_runs = 0;
+    if current_id() == ids[0] {
+        for _ in 0..3 {
+            schedule();
+            if current_id() != ids[0] {
+                break;
+            }
+            lone_runs += 1;
+        }
+    }
+    drop(dry_run);
+    cpu::restore_flags(flags);
+
+    if !queued {
+        return TestResult::Fail;
+    }
+    let expected = [ids[1], ids[2], ids[0], ids[1], ids[2], ids[0]];
+    if picked != expected {
+        klog_info!(
+            "SCHED_TEST: BUG - equal-priority picks {:?}, expected {:?}",
+            picked,
+            expected
+        );
+        return TestResult::Fail;
+    }
+    if lone_runs != 3 {
+        klog_info!("SCHED_TEST: BUG - lone ready task was passed over");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: a yield hands the CPU to a ready equal-priority sibling, and with
+/// nobody else ready the yielder keeps running
+pub fn test_yield_hands_off_to_sibling() -> TestResult {
+    let _fixture = SchedFixture::new();
+    let cpu = slopos_lib::get_current_cpu();
+
+    let yielder_id = task_create(
+        c"Yielder".as_ptr(),
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_NORMAL,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    let sibling_id = task_create(
+        c"Sibling".as_ptr(),
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_NORMAL,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    let yielder = task_find_by_id(yielder_id);
+    let sibling = task_find_by_id(sibling_id);
+    if yielder.is_null() || sibling.is_null() {
+        return TestResult::Fail;
+    }
+
+    // The yielder is running. Alone it keeps the CPU; once the sibling is
+    // ready, yielding hands the CPU over and the next yield hands it back.
+    let flags = cpu::save_flags_cli();
+    let dry_run = SchedDryRun::new(yielder);
+    let alone = scheduler_has_other_ready();
+    scheduler::r#yield();
+    let kept = scheduler::scheduler_get_current_task();
+    let queued = enqueue_task_on_cpu(cpu, sibling) == 0;
+    let contended = scheduler_has_other_ready();
+    scheduler::r#yield();
+    let handed_to = scheduler::scheduler_get_current_task();
+    scheduler::r#yield();
+    let back_to = scheduler::scheduler_get_current_task();
+    let requeued = with_cpu_scheduler(cpu, |s| s.remove_task(sibling)) == Some(0);
+    drop(dry_run);
+    cpu::restore_flags(flags);
+
+    if alone || kept != yielder || !queued || !contended {
+        klog_info!("SCHED_TEST: BUG - yield readiness check wrong");
+        return TestResult::Fail;
+    }
+    if handed_to != sibling || back_to != yielder || !requeued {
+        klog_info!("SCHED_TEST: BUG - yield did not hand off to the sibling first");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: a task whose affinity excludes this CPU, with no allowed CPU up to
+/// move it to, is skipped until the bit is restored; an empty mask is
+/// rejected
+pub fn test_affinity_excludes_cpu() -> TestResult {
+    let _fixture = SchedFixture::new();
+    let cpu = slopos_lib::get_current_cpu();
+    let offline = slopos_lib::get_cpu_count();
+    if offline >= u32::BITS as usize {
+        return TestResult::Skipped;
+    }
+
+    let task_id = task_create(
+        c"Pinned".as_ptr(),
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_NORMAL,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    let task = task_find_by_id(task_id);
+    if task.is_null() || unsafe { (*task).cpu_affinity } != TASK_AFFINITY_ANY {
+        return TestResult::Fail;
+    }
+    if task_set_affinity(task_id, 0) == 0 {
+        klog_info!("SCHED_TEST: BUG - empty affinity mask accepted");
+        return TestResult::Fail;
+    }
+
+    let elsewhere = 1u32 << offline;
+    let flags = cpu::save_flags_cli();
+    let queued = enqueue_task_on_cpu(cpu, task) == 0;
+    let pinned_away = task_set_affinity(task_id, elsewhere) == 0;
+    let skipped = dequeue_next_ready(cpu);
+    let restored = task_set_affinity(task_id, elsewhere | (1 << cpu)) == 0;
+    let picked = dequeue_next_ready(cpu);
+    cpu::restore_flags(flags);
+
+    if !queued || !pinned_away || !restored {
+        return TestResult::Fail;
+    }
+    if !skipped.is_null() {
+        klog_info!("SCHED_TEST: BUG - task picked on a CPU its affinity excludes");
+        return TestResult::Fail;
+    }
+    if picked != task {
+        klog_info!("SCHED_TEST: BUG - task not picked after its CPU bit was restored");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: narrowing the affinity of a queued task moves it to a CPU the new
+/// mask allows
+pub fn test_affinity_migrates_queued_task() -> TestResult {
+    let _fixture = SchedFixture::new();
+    let cpu = slopos_lib::get_current_cpu();
+    let Some(other) = (0..slopos_lib::get_cpu_count().min(u32::BITS as usize))
+        .find(|&c| c != cpu && with_cpu_scheduler(c, |_| ()).is_some())
+    else {
+        return TestResult::Skipped;
+    };
+
+    let task_id = task_create(
+        c"Migrated".as_ptr(),
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_NORMAL,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    let task = task_find_by_id(task_id);
+    if task.is_null() {
+        return TestResult::Fail;
+    }
+
+    let flags = cpu::save_flags_cli();
+    let queued = enqueue_task_on_cpu(cpu, task) == 0;
+    let pinned = task_set_affinity(task_id, 1 << other) == 0;
+    let left_behind = dequeue_next_ready(cpu);
+    let moved_to = unsafe { (*task).last_cpu as usize };
+    let on_other = with_cpu_scheduler(other, |sched| sched.remove_task(task)) == Some(0);
+    cpu::restore_flags(flags);
+
+    if !queued || !pinned {
+        return TestResult::Fail;
+    }
+    if !left_behind.is_null() || moved_to != other || !on_other {
+        klog_info!("SCHED_TEST: BUG - queued task not moved to an allowed CPU");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: a running task that drops its own CPU from its affinity is
+/// requeued by schedule() on a CPU it allows, not stranded on this one
+pub fn test_affinity_running_task_leaves_cpu() -> TestResult {
+    let _fixture = SchedFixture::new();
+    let cpu = slopos_lib::get_current_cpu();
+    let Some(other) = (0..slopos_lib::get_cpu_count().min(u32::BITS as usize))
+        .find(|&c| c != cpu && with_cpu_scheduler(c, |_| ()).is_some())
+    else {
+        return TestResult::Skipped;
+    };
+
+    let task_id = task_create(
+        c"SelfPinned".as_ptr(),
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_NORMAL,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    let task = task_find_by_id(task_id);
+    if task.is_null() {
+        return TestResult::Fail;
+    }
+
+    let flags = cpu::save_flags_cli();
+    let dry_run = SchedDryRun::new(task);
+    let pinned = task_set_affinity(task_id, 1 << other) == 0;
+    schedule();
+    let stranded = dequeue_next_ready(cpu);
+    let on_other = with_cpu_scheduler(other, |sched| sched.remove_task(task)) == Some(0);
+    drop(dry_run);
+    cpu::restore_flags(flags);
+
+    if !pinned {
+        return TestResult::Fail;
+    }
+    if !stranded.is_null() || !on_other {
+        klog_info!("SCHED_TEST: BUG - running task requeued on a CPU it excluded");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: CPU time grows while a task is running and is kept once it is
+/// switched out
+pub fn test_task_cpu_time_accumulates() -> TestResult {
+    let _fixture = SchedFixture::new();
+
+    let task_id = task_create(
+        c"CpuTime".as_ptr(),
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_NORMAL,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    let task = task_find_by_id(task_id);
+    if task.is_null() || task_get_cpu_time(task_id) != 0 {
+        return TestResult::Fail;
+    }
+
+    // Switch the task in and sample it across a few timer ticks.
+    task_record_context_switch(ptr::null_mut(), task, kdiag_timestamp());
+    let mut prev = 0;
+    for _ in 0..3 {
+        let start = platform::timer_ticks();
+        for _ in 0..10_000_000 {
+            if platform::timer_ticks() != start {
+                break;
+            }
+            core::hint::spin_loop();
+        }
+        let now = task_get_cpu_time(task_id);
+        if now <= prev {
+            klog_info!("SCHED_TEST: BUG - CPU time {} after {}", now, prev);
+            task_record_context_switch(task, ptr::null_mut(), kdiag_timestamp());
+            return TestResult::Fail;
+        }
+        prev = now;
+    }
+    task_record_context_switch(task, ptr::null_mut(), kdiag_timestamp());
+
+    let charged = task_get_cpu_time(task_id);
+    for _ in 0..1000 {
+        core::hint::spin_loop();
+    }
+    if charged < prev || task_get_cpu_time(task_id) != charged {
+        klog_info!("SCHED_TEST: BUG - CPU time lost or still growing after switch-out");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: a force-terminated task reports the killed sentinel
+pub fn test_terminated_task_reports_killed() -> TestResult {
+    let _fixture = SchedFixture::new();
+
+    let task_id = task_create(
+        b"Victim\0".as_ptr() as *const c_char,
+        dummy_task_fn,
+        ptr::null_mut(),
+        TASK_PRIORITY_NORMAL,
+        TASK_FLAG_KERNEL_MODE,
+    );
+    if task_id == INVALID_TASK_ID {
+        return TestResult::Fail;
+    }
+
+    task_terminate(task_id);
+
+    match task_wait(task_id) {
+        Some(code) if code == TASK_EXIT_CODE_KILLED => TestResult::Pass,
+        other => {
+            klog_info!("SCHED_TEST: Expected killed sentinel, got {:?}", other);
+            TestResult::Fail
+        }
+    }
+}
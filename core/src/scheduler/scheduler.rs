@@ -1,7 +1,8 @@
 use core::ffi::{c_int, c_void};
 use core::ptr;
-use core::sync::atomic::Ordering;
+use core::sync::atomic::{AtomicBool, Ordering};
 
+use slopos_abi::sched_traits::{RoundRobin, SchedPolicy};
 use slopos_lib::IrqMutex;
 use slopos_lib::preempt::PreemptGuard;
 use spin::Once;
@@ -12,16 +13,20 @@ use slopos_lib::klog_info;
 use crate::platform;
 use crate::wl_currency;
 
-use super::per_cpu;
+use super::per_cpu::{self, ReadyList};
 use super::task::{
-    INVALID_TASK_ID, TASK_FLAG_KERNEL_MODE, TASK_FLAG_NO_PREEMPT, TASK_FLAG_USER_MODE,
-    TASK_PRIORITY_IDLE, TASK_STATE_BLOCKED, TASK_STATE_READY, TASK_STATE_RUNNING, Task,
-    TaskContext, task_get_info, task_is_blocked, task_is_invalid, task_is_ready, task_is_running,
-    task_is_terminated, task_record_context_switch, task_record_yield, task_set_current,
-    task_set_state,
+    BlockReason, ChildStatus, INVALID_TASK_ID, MAX_TASKS, TASK_FLAG_KERNEL_MODE,
+    TASK_FLAG_NO_PREEMPT, TASK_FLAG_USER_MODE, TASK_PRIORITY_IDLE, TASK_STATE_BLOCKED,
+    TASK_STATE_READY, TASK_STATE_RUNNING, Task, TaskContext, TaskStatus, task_find_by_id,
+    task_get_info, task_is_blocked, task_is_invalid, task_is_ready, task_is_running,
+    task_is_terminated, task_reap_child, task_reap_exit_record, task_record_context_switch,
+    task_record_yield, task_set_current, task_set_state, task_set_state_with_reason,
 };
 
 const SCHED_DEFAULT_TIME_SLICE: u32 = 10;
+/// Ticks a ready task may wait behind higher-priority work before it is
+/// boosted to the top level for one run.
+pub const SCHED_AGING_THRESHOLD_TICKS: u32 = 50;
 const SCHED_POLICY_COOPERATIVE: u8 = 2;
 const SCHEDULER_PREEMPTION_DEFAULT: u8 = 1;
 
@@ -38,6 +43,120 @@ struct ReadyQueue {
 // Access serialized through SchedulerInner mutex.
 unsafe impl Send for ReadyQueue {}
 
+/// Buckets in the sleep timer wheel. A sleeper is filed under
+/// `deadline % SLEEP_WHEEL_SLOTS`, so a tick only inspects one bucket.
+const SLEEP_WHEEL_SLOTS: usize = 64;
+
+const _: () = assert!(MAX_TASKS <= u32::BITS as usize);
+
+#[derive(Clone, Copy)]
+struct Sleeper {
+    task: *mut Task,
+    task_id: u32,
+    deadline: u64,
+}
+
+/// Hashed timer wheel of sleeping tasks. Each bucket is a bitmask over
+/// `sleepers`; an entry due more than one turn ahead stays in its bucket
+/// until the turn it expires in.
+struct SleepWheel {
+    buckets: [u32; SLEEP_WHEEL_SLOTS],
+    sleepers: [Option<Sleeper>; MAX_TASKS],
+    /// Last tick whose bucket has been processed.
+    last_tick: u64,
+}
+
+impl SleepWheel {
+    const fn new() -> Self {
+        Self {
+            buckets: [0; SLEEP_WHEEL_SLOTS],
+            sleepers: [None; MAX_TASKS],
+            last_tick: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    fn bucket_for(&self, deadline: u64) -> usize {
+        // Anything already due goes in the next bucket to be processed.
+        (deadline.max(self.last_tick + 1) % SLEEP_WHEEL_SLOTS as u64) as usize
+    }
+
+    fn insert(&mut self, task: *mut Task, deadline: u64) -> bool {
+        self.remove(task);
+        let Some(idx) = self.sleepers.iter().position(Option::is_none) else {
+            return false;
+        };
+        self.sleepers[idx] = Some(Sleeper {
+            task,
+            task_id: unsafe { (*task).task_id },
+            deadline,
+        });
+        let bucket = self.bucket_for(deadline);
+        self.buckets[bucket] |= 1 << idx;
+        true
+    }
+
+    fn remove(&mut self, task: *mut Task) {
+        for (idx, slot) in self.sleepers.iter_mut().enumerate() {
+            if slot.is_some_and(|s| s.task == task) {
+                *slot = None;
+                for bucket in self.buckets.iter_mut() {
+                    *bucket &= !(1 << idx);
+                }
+            }
+        }
+    }
+
+    /// Collect the tasks whose deadline is at or before `now` into `out` and
+    /// return how many there are. Ticks skipped since the last call are
+    /// caught up on.
+    fn expire(&mut self, now: u64, out: &mut [*mut Task; MAX_TASKS]) -> usize {
+        if now <= self.last_tick {
+            return 0;
+        }
+        let first = self.last_tick + 1;
+        let span = (now - self.last_tick).min(SLEEP_WHEEL_SLOTS as u64);
+        self.last_tick = now;
+
+        let mut count = 0;
+        for tick in first..first + span {
+            let bucket = (tick % SLEEP_WHEEL_SLOTS as u64) as usize;
+            let mut pending = self.buckets[bucket];
+            while pending != 0 {
+                let idx = pending.trailing_zeros() as usize;
+                pending &= pending - 1;
+                let Some(sleeper) = self.sleepers[idx] else {
+                    self.buckets[bucket] &= !(1 << idx);
+                    continue;
+                };
+                if sleeper.deadline > now {
+                    continue;
+                }
+                let task = unsafe { &*sleeper.task };
+                let stale = task.task_id != sleeper.task_id
+                    || task_is_invalid(sleeper.task)
+                    || task_is_terminated(sleeper.task);
+                if !stale && !task_is_blocked(sleeper.task) {
+                    // Still on its way to blocking; look again next tick.
+                    self.buckets[bucket] &= !(1 << idx);
+                    self.buckets[self.bucket_for(now + 1)] |= 1 << idx;
+                    continue;
+                }
+                self.buckets[bucket] &= !(1 << idx);
+                self.sleepers[idx] = None;
+                if !stale && task.block_reason == BlockReason::Sleep {
+                    out[count] = sleeper.task;
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+}
+
 struct SchedulerInner {
     ready_queues: [ReadyQueue; NUM_PRIORITY_LEVELS],
     current_task: *mut Task,
@@ -53,6 +172,7 @@ struct SchedulerInner {
     total_preemptions: u64,
     schedule_calls: u32,
     preemption_enabled: u8,
+    sleepers: SleepWheel,
 }
 
 // SAFETY: SchedulerInner contains raw pointers to Task in static storage.
@@ -108,6 +228,7 @@ impl SchedulerInner {
             total_preemptions: 0,
             schedule_calls: 0,
             preemption_enabled: SCHEDULER_PREEMPTION_DEFAULT,
+            sleepers: SleepWheel::new(),
         }
     }
 
@@ -124,9 +245,9 @@ impl SchedulerInner {
         self.ready_queues[idx].enqueue(task)
     }
 
-    fn dequeue_highest_priority(&mut self) -> *mut Task {
+    fn dequeue_highest_priority(&mut self, cpu_id: usize) -> *mut Task {
         for queue in self.ready_queues.iter_mut() {
-            let task = queue.dequeue();
+            let task = queue.dequeue_allowed(cpu_id);
             if !task.is_null() {
                 return task;
             }
@@ -152,6 +273,8 @@ impl SchedulerInner {
 
 static SCHEDULER: Once<IrqMutex<SchedulerInner>> = Once::new();
 static IDLE_WAKEUP_CB: Once<IrqMutex<Option<fn() -> c_int>>> = Once::new();
+/// Registered next-task policy; `None` is the built-in round-robin fast path.
+static SCHED_POLICY: IrqMutex<Option<&'static dyn SchedPolicy>> = IrqMutex::new(None);
 
 #[inline]
 fn with_scheduler<R>(f: impl FnOnce(&mut SchedulerInner) -> R) -> R {
@@ -266,6 +389,20 @@ impl ReadyQueue {
     }
 }
 
+impl ReadyList for ReadyQueue {
+    fn first(&self) -> *mut Task {
+        self.head
+    }
+
+    fn pop_first(&mut self) -> *mut Task {
+        self.dequeue()
+    }
+
+    fn unlink(&mut self, task: *mut Task) -> c_int {
+        self.remove(task)
+    }
+}
+
 fn get_default_time_slice(sched: &SchedulerInner) -> u64 {
     if sched.time_slice != 0 {
         sched.time_slice as u64
@@ -356,14 +493,120 @@ pub fn unschedule_task(task: *mut Task) -> c_int {
     })
 }
 
-fn select_next_task(sched: &mut SchedulerInner) -> *mut Task {
+/// Select how the next task is picked from the ready queues.
+///
+/// Returns the previously active policy. `RoundRobin` is the default.
+pub fn scheduler_set_policy(policy: &'static dyn SchedPolicy) -> &'static dyn SchedPolicy {
+    let prev = SCHED_POLICY.lock().replace(policy);
+    prev.unwrap_or(&RoundRobin)
+}
+
+/// Return to the default round-robin policy.
+pub fn scheduler_reset_policy() {
+    *SCHED_POLICY.lock() = None;
+}
+
+/// Name of the active scheduling policy.
+pub fn scheduler_policy_name() -> &'static str {
+    SCHED_POLICY.lock().map_or(RoundRobin.name(), |p| p.name())
+}
+
+/// Dequeue the next task from `cpu_id`'s ready queues using the active policy.
+pub(crate) fn dequeue_next_ready(cpu_id: usize) -> *mut Task {
+    let policy = *SCHED_POLICY.lock();
+    per_cpu::with_cpu_scheduler(cpu_id, |local| match policy {
+        Some(policy) => local.dequeue_with_policy(policy),
+        None => local.dequeue_highest_priority(),
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Queue a task that was just running behind the ready tasks of its own
+/// priority on this CPU, so equal-priority siblings take turns with it.
+/// A task that dropped this CPU from its affinity while running goes to a
+/// CPU it allows instead; queued here it would never be picked again.
+pub(crate) fn requeue_local(task: *mut Task) -> c_int {
     let cpu_id = slopos_lib::get_current_cpu();
+    if !unsafe { (*task).allows_cpu(cpu_id) } {
+        let target = per_cpu::select_target_cpu(task);
+        if target != cpu_id && per_cpu::enqueue_task_on_cpu(target, task) == 0 {
+            if slopos_lib::is_cpu_online(target) {
+                send_reschedule_ipi(target);
+            }
+            return 0;
+        }
+    }
+    per_cpu::with_cpu_scheduler(cpu_id, |local| local.enqueue_local(task)).unwrap_or(-1)
+}
+
+/// Set while a `SchedDryRun` is alive: `schedule()` picks and does its
+/// bookkeeping as usual but never switches stacks.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Runs the scheduler with a given task as the one running on this CPU,
+/// without ever switching to another task. Tests use it to drive
+/// `schedule()`, yields and timer ticks and read who would run next from
+/// `scheduler_get_current_task`. Keep interrupts off while it is alive.
+pub(crate) struct SchedDryRun {
+    prev_current: *mut Task,
+    prev_local: *mut Task,
+}
 
-    let mut next = per_cpu::with_cpu_scheduler(cpu_id, |local| local.dequeue_highest_priority())
+impl SchedDryRun {
+    pub(crate) fn new(current: *mut Task) -> Self {
+        let cpu_id = slopos_lib::get_current_cpu();
+        DRY_RUN.store(true, Ordering::SeqCst);
+        let prev_local = per_cpu::with_cpu_scheduler(cpu_id, |local| {
+            let prev = local.current_task();
+            local.set_current_task(current);
+            prev
+        })
         .unwrap_or(ptr::null_mut());
+        task_set_current(current);
+        let prev_current = with_scheduler(|sched| {
+            sched.enabled = 1;
+            sched.preemption_enabled = 1;
+            reset_task_quantum(sched, current);
+            core::mem::replace(&mut sched.current_task, current)
+        });
+        Self {
+            prev_current,
+            prev_local,
+        }
+    }
+}
+
+impl Drop for SchedDryRun {
+    fn drop(&mut self) {
+        with_scheduler(|sched| {
+            sched.enabled = 0;
+            sched.current_task = self.prev_current;
+        });
+        per_cpu::with_cpu_scheduler(slopos_lib::get_current_cpu(), |local| {
+            local.set_current_task(self.prev_local)
+        });
+        PreemptGuard::clear_reschedule_pending();
+        DRY_RUN.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Ready tasks waiting on this CPU's queues or the global fallback queue.
+/// Safe from interrupt context: the per-CPU queues are not locked.
+fn ready_count_lockfree(sched: &SchedulerInner) -> u32 {
+    let local = per_cpu::with_cpu_scheduler(slopos_lib::get_current_cpu(), |local| {
+        local.total_ready_count()
+    })
+    .unwrap_or(0);
+    sched.total_ready_count() + local
+}
+
+fn select_next_task(sched: &mut SchedulerInner) -> *mut Task {
+    let cpu_id = slopos_lib::get_current_cpu();
+
+    let mut next = dequeue_next_ready(cpu_id);
 
     if next.is_null() {
-        next = sched.dequeue_highest_priority();
+        next = sched.dequeue_highest_priority(cpu_id);
     }
 
     if next.is_null() && !sched.idle_task.is_null() && !task_is_terminated(sched.idle_task) {
@@ -498,7 +741,7 @@ pub fn schedule() {
             if task_is_running(current) {
                 if task_set_state(unsafe { (*current).task_id }, TASK_STATE_READY) != 0 {
                     klog_info!("schedule: failed to mark task ready");
-                } else if sched.enqueue_task(current) != 0 {
+                } else if requeue_local(current) != 0 && sched.enqueue_task(current) != 0 {
                     klog_info!("schedule: ready queue full when re-queuing task");
                     task_set_state(unsafe { (*current).task_id }, TASK_STATE_RUNNING);
                     reset_task_quantum(sched, current);
@@ -535,6 +778,10 @@ pub fn schedule() {
         }
     });
 
+    if DRY_RUN.load(Ordering::Relaxed) {
+        PreemptGuard::clear_reschedule_pending();
+        return;
+    }
     match result {
         ScheduleResult::Disabled | ScheduleResult::NoTask => {
             drop(preempt_guard);
@@ -552,13 +799,25 @@ pub fn schedule() {
     }
 }
 
+/// Whether a task other than the running one is ready on this CPU.
+pub fn scheduler_has_other_ready() -> bool {
+    try_with_scheduler(|sched| ready_count_lockfree(sched) > 0).unwrap_or(false)
+}
+
+/// Give up the CPU. The caller is requeued behind the ready tasks of its
+/// own priority, so an equal-priority sibling runs first; with nothing else
+/// ready a running caller just continues.
 pub fn r#yield() {
-    with_scheduler(|sched| {
+    let current = with_scheduler(|sched| {
         sched.total_yields += 1;
         if !sched.current_task.is_null() {
             task_record_yield(sched.current_task);
         }
+        sched.current_task
     });
+    if !current.is_null() && task_is_running(current) && !scheduler_has_other_ready() {
+        return;
+    }
     schedule();
 }
 
@@ -567,6 +826,17 @@ pub fn yield_() {
 }
 
 pub fn block_current_task() {
+    block_current_task_unless(|| false);
+}
+
+/// Block the current task unless `wake_pending` reports that the event it
+/// sleeps on has already happened.
+///
+/// The check runs after the task is marked Blocked. A waker that publishes
+/// its event and then calls `unblock_task` either finds the task Blocked and
+/// wakes it, or published early enough for `wake_pending` to see it, so the
+/// wakeup cannot fall between the caller's own check and the block.
+pub fn block_current_task_unless(wake_pending: impl Fn() -> bool) {
     let current = scheduler_get_current_task();
     if current.is_null() {
         return;
@@ -574,10 +844,24 @@ pub fn block_current_task() {
     if task_is_blocked(current) {
         return;
     }
+    // No preemption until the check is done: being switched out while
+    // Blocked with the event already published would never be undone.
+    let preempt = PreemptGuard::new();
     if task_set_state(unsafe { (*current).task_id }, TASK_STATE_BLOCKED) != 0 {
         return;
     }
+    core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+    if wake_pending() {
+        if unsafe { (*current).cancel_block() } {
+            return;
+        }
+        // A waker got there first: the task is Ready and already queued.
+        drop(preempt);
+        schedule();
+        return;
+    }
     unschedule_task(current);
+    drop(preempt);
     schedule();
 }
 
@@ -596,11 +880,55 @@ pub fn task_wait_for(task_id: u32) -> c_int {
         return 0;
     }
     unsafe { (*current).waiting_on_task_id = task_id };
-    block_current_task();
+    // Exit releases only waiters it finds Blocked, so the target's state is
+    // re-checked after we are Blocked: either exit sees us and wakes us, or
+    // we see it gone. Look it up by id, since its slot may be recycled.
+    block_current_task_unless(|| {
+        let target = task_find_by_id(task_id);
+        target.is_null() || task_is_terminated(target) || task_is_invalid(target)
+    });
     unsafe { (*current).waiting_on_task_id = INVALID_TASK_ID };
     0
 }
 
+/// Wait for `task_id` to exit and reap its exit code. Returns `None` if the
+/// task is unknown, was already reaped, or the wait could not be performed.
+pub fn task_wait(task_id: u32) -> Option<i32> {
+    if let Some(rec) = task_reap_exit_record(task_id) {
+        return Some(rec.exit_code);
+    }
+    if task_wait_for(task_id) != 0 {
+        return None;
+    }
+    task_reap_exit_record(task_id).map(|rec| rec.exit_code)
+}
+
+/// Why `task_waitpid` returned without an exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitError {
+    /// `child_id` is not an unreaped child of the waiter.
+    NotChild,
+    /// `nohang` was set and the child is still running.
+    StillRunning,
+}
+
+/// Wait for `child_id`, a child of `parent_id`, to exit and reap its exit
+/// code. With `nohang` the call returns `StillRunning` instead of blocking.
+pub fn task_waitpid(parent_id: u32, child_id: u32, nohang: bool) -> Result<i32, WaitError> {
+    loop {
+        match task_reap_child(parent_id, child_id) {
+            ChildStatus::Exited(code) => return Ok(code),
+            ChildStatus::NotChild => return Err(WaitError::NotChild),
+            ChildStatus::Running if nohang => return Err(WaitError::StillRunning),
+            ChildStatus::Running => {
+                if task_wait_for(child_id) != 0 {
+                    return Err(WaitError::NotChild);
+                }
+            }
+        }
+    }
+}
+
 pub fn unblock_task(task: *mut Task) -> c_int {
     if task.is_null() {
         return -1;
@@ -715,12 +1043,14 @@ pub fn init_scheduler() -> c_int {
         sched.total_ticks = 0;
         sched.total_preemptions = 0;
         sched.preemption_enabled = SCHEDULER_PREEMPTION_DEFAULT;
+        sched.sleepers.clear();
     });
     user_copy::register_current_task_provider(current_task_process_id);
 
     per_cpu::init_all_percpu_schedulers();
 
     slopos_lib::preempt::register_reschedule_callback(deferred_reschedule_callback);
+    slopos_fs::vfs::vfs_register_yield_callback(yield_);
 
     slopos_lib::panic_recovery::register_panic_cleanup(sched_panic_cleanup);
 
@@ -830,6 +1160,7 @@ pub fn scheduler_shutdown() {
         sched.init_queues();
         sched.current_task = ptr::null_mut();
         sched.idle_task = ptr::null_mut();
+        sched.sleepers.clear();
     });
 }
 
@@ -893,7 +1224,74 @@ pub fn scheduler_is_preemption_enabled() -> c_int {
     try_with_scheduler(|sched| sched.preemption_enabled as c_int).unwrap_or(0)
 }
 
+/// Timer ticks covering `ns` nanoseconds at `freq_hz`, rounded up so that
+/// any nonzero sleep lasts at least one tick.
+pub fn sleep_ticks_for_ns(ns: u64, freq_hz: u32) -> u64 {
+    if ns == 0 {
+        return 0;
+    }
+    let ticks = (ns as u128 * freq_hz as u128).div_ceil(1_000_000_000);
+    ticks.clamp(1, u64::MAX as u128) as u64
+}
+
+/// Block the current task until at least `ticks` timer ticks have passed.
+/// Returns -1 when there is no task to put to sleep.
+pub fn task_sleep_ticks(ticks: u64) -> c_int {
+    let current = scheduler_get_current_task();
+    if current.is_null() {
+        return -1;
+    }
+    let deadline = platform::timer_ticks().saturating_add(ticks.max(1));
+
+    while platform::timer_ticks() < deadline {
+        if sleep_task_until(current, deadline) != 0 {
+            return -1;
+        }
+        schedule();
+        // Woken early by someone else: drop the stale entry and go again.
+        with_scheduler(|sched| sched.sleepers.remove(current));
+    }
+    0
+}
+
+/// File `task` in the sleep wheel and block it until the tick count reaches
+/// `deadline`. The caller switches away if `task` is the current task.
+pub fn sleep_task_until(task: *mut Task, deadline: u64) -> c_int {
+    if task.is_null() {
+        return -1;
+    }
+    if !with_scheduler(|sched| sched.sleepers.insert(task, deadline)) {
+        return -1;
+    }
+    let task_id = unsafe { (*task).task_id };
+    if task_set_state_with_reason(task_id, TaskStatus::Blocked, BlockReason::Sleep) != 0 {
+        with_scheduler(|sched| sched.sleepers.remove(task));
+        return -1;
+    }
+    unschedule_task(task);
+    0
+}
+
+/// Make every sleeper whose deadline is at or before `now` runnable again.
+/// Returns how many tasks were woken.
+pub fn scheduler_wake_sleepers(now: u64) -> usize {
+    let mut woken = [ptr::null_mut(); MAX_TASKS];
+    let count = try_with_scheduler(|sched| sched.sleepers.expire(now, &mut woken)).unwrap_or(0);
+    for &task in &woken[..count] {
+        unsafe { (*task).block_reason = BlockReason::None };
+        unblock_task(task);
+    }
+    count
+}
+
 pub fn scheduler_timer_tick() {
+    if platform::is_platform_initialized() && scheduler_wake_sleepers(platform::timer_ticks()) > 0 {
+        scheduler_request_reschedule_from_interrupt();
+    }
+    per_cpu::with_cpu_scheduler(slopos_lib::get_current_cpu(), |local| {
+        local.age_ready_tasks(SCHED_AGING_THRESHOLD_TICKS)
+    });
+
     // If preemption is disabled via PreemptGuard, just mark pending
     if PreemptGuard::is_active() {
         PreemptGuard::set_reschedule_pending();
@@ -911,7 +1309,7 @@ pub fn scheduler_timer_tick() {
             return;
         }
         if current == sched.idle_task {
-            if sched.total_ready_count() > 0 {
+            if ready_count_lockfree(sched) > 0 {
                 PreemptGuard::set_reschedule_pending();
             }
             return;
@@ -927,7 +1325,8 @@ pub fn scheduler_timer_tick() {
                 return;
             }
         }
-        if sched.total_ready_count() == 0 {
+        // A task with no ready siblings keeps the CPU for another quantum.
+        if ready_count_lockfree(sched) == 0 {
             reset_task_quantum(sched, current);
             return;
         }
@@ -937,8 +1336,9 @@ pub fn scheduler_timer_tick() {
 }
 
 pub fn scheduler_request_reschedule_from_interrupt() {
-    let should_set =
-        try_with_scheduler(|sched| sched.enabled != 0 && sched.preemption_enabled != 0);
+    let should_set = try_with_scheduler(|sched| {
+        sched.enabled != 0 && sched.preemption_enabled != 0 && ready_count_lockfree(sched) > 0
+    });
     if should_set == Some(true) && !PreemptGuard::is_active() {
         PreemptGuard::set_reschedule_pending();
     }
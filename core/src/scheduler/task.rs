@@ -7,18 +7,18 @@ use slopos_lib::IrqMutex;
 use slopos_lib::cpu;
 use slopos_lib::kdiag_timestamp;
 use slopos_lib::string::cstr_to_str;
-use slopos_lib::{klog_debug, klog_info};
+use slopos_lib::{klog_debug_mod, klog_info};
 
 use super::scheduler;
 
 pub use slopos_abi::task::{
-    BlockReason, FpuState, INVALID_PROCESS_ID, INVALID_TASK_ID, IdtEntry, MAX_TASKS,
-    TASK_FLAG_COMPOSITOR, TASK_FLAG_DISPLAY_EXCLUSIVE, TASK_FLAG_KERNEL_MODE, TASK_FLAG_NO_PREEMPT,
-    TASK_FLAG_SYSTEM, TASK_FLAG_USER_MODE, TASK_KERNEL_STACK_SIZE, TASK_NAME_MAX_LEN,
-    TASK_PRIORITY_HIGH, TASK_PRIORITY_IDLE, TASK_PRIORITY_LOW, TASK_PRIORITY_NORMAL,
-    TASK_STACK_SIZE, TASK_STATE_BLOCKED, TASK_STATE_INVALID, TASK_STATE_READY, TASK_STATE_RUNNING,
-    TASK_STATE_TERMINATED, Task, TaskContext, TaskExitReason, TaskExitRecord, TaskFaultReason,
-    TaskStatus,
+    BlockReason, FpuState, INVALID_PROCESS_ID, INVALID_TASK_ID, IdtEntry, MAX_TASKS, StateError,
+    TASK_AFFINITY_ANY, TASK_EXIT_CODE_KILLED, TASK_FLAG_COMPOSITOR, TASK_FLAG_DISPLAY_EXCLUSIVE,
+    TASK_FLAG_KERNEL_MODE, TASK_FLAG_NO_PREEMPT, TASK_FLAG_SYSTEM, TASK_FLAG_USER_MODE,
+    TASK_KERNEL_STACK_SIZE, TASK_NAME_MAX_LEN, TASK_PRIORITY_HIGH, TASK_PRIORITY_IDLE,
+    TASK_PRIORITY_LOW, TASK_PRIORITY_NORMAL, TASK_STACK_SIZE, TASK_STATE_BLOCKED,
+    TASK_STATE_INVALID, TASK_STATE_READY, TASK_STATE_RUNNING, TASK_STATE_TERMINATED, Task,
+    TaskContext, TaskExitReason, TaskExitRecord, TaskFaultReason, TaskStatus,
 };
 
 use slopos_mm::mm_constants::PROCESS_CODE_START_VA;
@@ -119,6 +119,9 @@ pub fn task_find_by_id(task_id: u32) -> *mut Task {
 }
 
 fn release_task_dependents(completed_task_id: u32) {
+    // Pairs with the fence in block_current_task_unless: a waiter that is
+    // not Blocked yet will see this task Terminated when it re-checks.
+    core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
     let dependents: [Option<*mut Task>; MAX_TASKS] = with_task_manager(|mgr| {
         let mut result = [None; MAX_TASKS];
         let mut idx = 0;
@@ -158,6 +161,23 @@ fn release_task_dependents(completed_task_id: u32) {
     }
 }
 
+/// Detach the children of an exiting task. Their unreaped exit records can
+/// no longer be waited for, so their slots become the first to recycle.
+fn orphan_children(parent_id: u32) {
+    with_task_manager(|mgr| {
+        for task in mgr.tasks.iter_mut() {
+            if task.parent_task_id == parent_id {
+                task.parent_task_id = INVALID_TASK_ID;
+            }
+        }
+        for rec in mgr.exit_records.iter_mut() {
+            if rec.parent_task_id == parent_id {
+                rec.parent_task_id = INVALID_TASK_ID;
+            }
+        }
+    });
+}
+
 fn user_entry_is_allowed(addr: u64) -> bool {
     // Allow entry points in embedded user_text section (for legacy compatibility)
     let (start_ptr, end_ptr) = symbols::user_text_bounds();
@@ -181,16 +201,45 @@ fn task_slot_index_inner(mgr: &TaskManagerInner, task: *const Task) -> Option<us
     if idx < MAX_TASKS { Some(idx) } else { None }
 }
 
+/// Pick a free task slot, preferring ones whose exit record has already been
+/// reaped so that an unclaimed exit code survives as long as possible. A
+/// record that a live parent could still reap is only given up last.
+fn find_free_slot_inner(mgr: &mut TaskManagerInner) -> *mut Task {
+    let mut orphaned: *mut Task = ptr::null_mut();
+    let mut fallback: *mut Task = ptr::null_mut();
+    for (t, rec) in mgr.tasks.iter_mut().zip(mgr.exit_records.iter()) {
+        if t.state() != TASK_STATE_INVALID {
+            continue;
+        }
+        if rec.task_id == INVALID_TASK_ID {
+            return t as *mut Task;
+        }
+        if rec.parent_task_id == INVALID_TASK_ID {
+            if orphaned.is_null() {
+                orphaned = t as *mut Task;
+            }
+        } else if fallback.is_null() {
+            fallback = t as *mut Task;
+        }
+    }
+    if orphaned.is_null() {
+        fallback
+    } else {
+        orphaned
+    }
+}
+
 fn record_task_exit(
     task: *const Task,
     exit_reason: TaskExitReason,
     fault_reason: TaskFaultReason,
-    exit_code: u32,
+    exit_code: i32,
 ) {
     with_task_manager(|mgr| {
         if let Some(idx) = task_slot_index_inner(mgr, task) {
             mgr.exit_records[idx] = TaskExitRecord {
                 task_id: unsafe { (*task).task_id },
+                parent_task_id: unsafe { (*task).parent_task_id },
                 exit_reason,
                 fault_reason,
                 exit_code,
@@ -286,7 +335,8 @@ pub fn init_task_manager() -> c_int {
                 if task.task_id > max_task_id {
                     max_task_id = task.task_id;
                 }
-                klog_debug!(
+                klog_debug_mod!(
+                    "scheduler",
                     "init_task_manager: preserving idle task {} ('{}')",
                     task.task_id,
                     unsafe { cstr_to_str(task.name.as_ptr() as *const c_char) }
@@ -331,16 +381,7 @@ pub fn task_create(
             return (ptr::null_mut(), INVALID_TASK_ID);
         }
 
-        let task = {
-            let mut found = ptr::null_mut();
-            for t in mgr.tasks.iter_mut() {
-                if t.state() == TASK_STATE_INVALID {
-                    found = t as *mut Task;
-                    break;
-                }
-            }
-            found
-        };
+        let task = find_free_slot_inner(mgr);
         if task.is_null() {
             klog_info!("task_create: No free task slots");
             return (ptr::null_mut(), INVALID_TASK_ID);
@@ -411,6 +452,8 @@ pub fn task_create(
     unsafe { copy_name(&mut task_ref.name, name) };
     task_ref.set_state(TASK_STATE_READY);
     task_ref.priority = priority;
+    task_ref.effective_priority = priority;
+    task_ref.ready_wait_ticks = 0;
     task_ref.flags = flags;
     task_ref.process_id = process_id;
     task_ref.stack_base = stack_base;
@@ -459,6 +502,12 @@ pub fn task_create(
     task_ref.yield_count = 0;
     task_ref.last_run_timestamp = 0;
     task_ref.waiting_on_task_id = INVALID_TASK_ID;
+    let creator = scheduler::scheduler_get_current_task();
+    task_ref.parent_task_id = if creator.is_null() {
+        INVALID_TASK_ID
+    } else {
+        unsafe { (*creator).task_id }
+    };
     task_ref.user_started = 0;
     task_ref.context_from_user = 0;
     task_ref.exit_reason = TaskExitReason::None;
@@ -485,7 +534,8 @@ pub fn task_create(
         mgr.tasks_created = mgr.tasks_created.saturating_add(1);
     });
 
-    klog_debug!(
+    klog_debug_mod!(
+        "scheduler",
         "Created task '{}' with ID {}",
         unsafe { cstr_to_str(task_ref.name.as_ptr() as *const c_char) },
         task_id
@@ -531,6 +581,7 @@ pub fn task_terminate(task_id: u32) -> c_int {
         (*task_ptr).last_run_timestamp = 0;
         if (*task_ptr).exit_reason == TaskExitReason::None {
             (*task_ptr).exit_reason = TaskExitReason::Kernel;
+            (*task_ptr).exit_code = TASK_EXIT_CODE_KILLED;
         }
         record_task_exit(
             task_ptr,
@@ -544,6 +595,8 @@ pub fn task_terminate(task_id: u32) -> c_int {
         (*task_ptr).fate_pending = 0;
     }
 
+    orphan_children(resolved_id);
+
     let was_paused = crate::per_cpu::pause_all_aps();
     release_task_dependents(resolved_id);
     crate::per_cpu::resume_all_aps_if_not_nested(was_paused);
@@ -659,6 +712,121 @@ pub fn task_get_exit_record(task_id: u32, record_out: *mut TaskExitRecord) -> c_
     })
 }
 
+/// Remove and return the exit record for `task_id`, freeing its slot's
+/// record for reuse. Returns `None` if the task has not exited or was
+/// already reaped.
+pub fn task_reap_exit_record(task_id: u32) -> Option<TaskExitRecord> {
+    if task_id == INVALID_TASK_ID {
+        return None;
+    }
+    with_task_manager(|mgr| {
+        for rec in mgr.exit_records.iter_mut() {
+            if rec.task_id == task_id {
+                let taken = *rec;
+                *rec = TaskExitRecord::empty();
+                return Some(taken);
+            }
+        }
+        None
+    })
+}
+
+/// Mark a task as exiting normally with `code`. The code is captured into the
+/// exit record when the task is subsequently terminated.
+pub fn task_set_exit_code(task_id: u32, code: i32) -> c_int {
+    let task = task_find_by_id(task_id);
+    if task.is_null() {
+        return -1;
+    }
+    unsafe {
+        (*task).exit_reason = TaskExitReason::Normal;
+        (*task).fault_reason = TaskFaultReason::None;
+        (*task).exit_code = code;
+    }
+    0
+}
+
+/// Make `parent_id` the task allowed to reap `task_id`.
+pub fn task_set_parent(task_id: u32, parent_id: u32) -> c_int {
+    let task = task_find_by_id(task_id);
+    if task.is_null() || unsafe { (*task).state() } == TASK_STATE_INVALID {
+        return -1;
+    }
+    unsafe { (*task).parent_task_id = parent_id };
+    0
+}
+
+/// Restrict `task_id` to the CPUs in `mask` (bit n allows CPU n).
+///
+/// An empty mask would leave the task nowhere to run and is rejected. A
+/// task already queued on a CPU the mask excludes moves to an allowed CPU's
+/// queue; if none of those is up it stays put and that CPU skips it.
+pub fn task_set_affinity(task_id: u32, mask: u32) -> c_int {
+    if mask == 0 {
+        return -1;
+    }
+    let task = task_find_by_id(task_id);
+    if task.is_null() || unsafe { (*task).state() } == TASK_STATE_INVALID {
+        return -1;
+    }
+    unsafe { (*task).cpu_affinity = mask };
+    crate::per_cpu::migrate_disallowed_task(task);
+    0
+}
+
+/// Where a child stands from its parent's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildStatus {
+    /// The child exited; its record has been reaped and is returned.
+    Exited(i32),
+    /// The child is still alive.
+    Running,
+    /// `child_id` is not an unreaped child of the parent.
+    NotChild,
+}
+
+/// Reap `child_id` if it has exited, checking it belongs to `parent_id`.
+/// The lookup and the reap happen under one lock so only one waiter can
+/// collect a given exit code.
+pub fn task_reap_child(parent_id: u32, child_id: u32) -> ChildStatus {
+    if parent_id == INVALID_TASK_ID || child_id == INVALID_TASK_ID {
+        return ChildStatus::NotChild;
+    }
+    with_task_manager(|mgr| {
+        for rec in mgr.exit_records.iter_mut() {
+            if rec.task_id == child_id && rec.parent_task_id == parent_id {
+                let code = rec.exit_code;
+                *rec = TaskExitRecord::empty();
+                return ChildStatus::Exited(code);
+            }
+        }
+        let running = mgr.tasks.iter().any(|t| {
+            t.task_id == child_id
+                && t.parent_task_id == parent_id
+                && t.state() != TASK_STATE_INVALID
+                && t.state() != TASK_STATE_TERMINATED
+        });
+        if running {
+            ChildStatus::Running
+        } else {
+            ChildStatus::NotChild
+        }
+    })
+}
+
+/// Exit the current task with `code`. Never returns.
+pub fn task_exit(code: i32) -> ! {
+    let current = scheduler::scheduler_get_current_task();
+    if !current.is_null() {
+        unsafe {
+            (*current).exit_reason = TaskExitReason::Normal;
+            (*current).fault_reason = TaskFaultReason::None;
+            (*current).exit_code = code;
+        }
+    }
+    scheduler::scheduler_task_exit_impl()
+}
+
 pub fn task_set_state(task_id: u32, new_state: u8) -> c_int {
     let task = task_find_by_id(task_id);
     if task.is_null() {
@@ -670,14 +838,29 @@ pub fn task_set_state(task_id: u32, new_state: u8) -> c_int {
         return -1;
     }
 
-    let new_status = TaskStatus::from_u8(new_state);
-    if task_ref.try_transition_to(new_status) {
+    if task_transition(task_ref, TaskStatus::from_u8(new_state)).is_ok() {
         0
     } else {
         -1
     }
 }
 
+/// Validated state change for `task`. Illegal moves, such as re-queuing a
+/// terminated task, are logged and rejected rather than applied. Requesting
+/// the current state returns `StateError::Unchanged` (except for Terminated).
+pub fn task_transition(task: &Task, to: TaskStatus) -> Result<(), StateError> {
+    let result = task.transition(to);
+    if let Err(StateError::Illegal { from, to }) = result {
+        klog_info!(
+            "task_transition: task {} illegal move {:?} -> {:?}",
+            task.task_id,
+            from,
+            to
+        );
+    }
+    result
+}
+
 pub fn task_set_state_with_reason(
     task_id: u32,
     new_status: TaskStatus,
@@ -760,6 +943,21 @@ pub fn task_record_context_switch(from: *mut Task, to: *mut Task, timestamp: u64
     }
 }
 
+/// CPU time consumed by `task_id` in TSC cycles, including the slice it is
+/// running right now. Unknown tasks report 0. The idle task accrues idle
+/// time like any other task.
+pub fn task_get_cpu_time(task_id: u32) -> u64 {
+    let task = task_find_by_id(task_id);
+    if task.is_null() {
+        return 0;
+    }
+    let (total, started) = unsafe { ((*task).total_runtime, (*task).last_run_timestamp) };
+    if started == 0 {
+        return total;
+    }
+    total.saturating_add(kdiag_timestamp().saturating_sub(started))
+}
+
 pub fn task_record_yield(task: *mut Task) {
     with_task_manager(|mgr| {
         mgr.total_yields += 1;
@@ -899,13 +1097,7 @@ pub fn task_fork(parent_task: *mut Task) -> u32 {
             return (ptr::null_mut(), INVALID_TASK_ID);
         }
 
-        let mut slot: *mut Task = ptr::null_mut();
-        for t in mgr.tasks.iter_mut() {
-            if t.state() == TASK_STATE_INVALID {
-                slot = t as *mut Task;
-                break;
-            }
-        }
+        let slot = find_free_slot_inner(mgr);
 
         if slot.is_null() {
             return (ptr::null_mut(), INVALID_TASK_ID);
@@ -954,6 +1146,7 @@ pub fn task_fork(parent_task: *mut Task) -> u32 {
     child.yield_count = 0;
     child.last_run_timestamp = 0;
     child.waiting_on_task_id = INVALID_TASK_ID;
+    child.parent_task_id = parent.task_id;
     child.exit_reason = TaskExitReason::None;
     child.fault_reason = TaskFaultReason::None;
     child.exit_code = 0;
@@ -0,0 +1,194 @@
+//! Bounded work queue for deferring heavy work out of interrupt context.
+//!
+//! Producers (IRQ handlers, any CPU) push with a lock-free CAS on the head
+//! index; a single kernel worker thread pops and runs the items in FIFO
+//! order. Each slot carries a sequence number (Vyukov bounded queue) so a
+//! producer only publishes into a slot the consumer has finished with.
+//! When the ring is full the item is dropped and counted, never blocking the
+//! interrupted context.
+
+use core::ffi::c_int;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+
+use slopos_lib::klog_info;
+
+use super::kthread::kthread_spawn_ex;
+use super::scheduler::{block_current_task_unless, unblock_task};
+use super::task::{INVALID_TASK_ID, TASK_PRIORITY_HIGH, TASK_STATE_BLOCKED, task_find_by_id};
+
+/// Deferred work item; runs in the worker thread's context.
+pub type WorkFn = fn();
+
+/// Capacity of the global kernel work queue.
+pub const WORK_QUEUE_CAPACITY: usize = 64;
+
+struct WorkSlot {
+    seq: AtomicUsize,
+    work: AtomicPtr<()>,
+}
+
+impl WorkSlot {
+    const fn new(seq: usize) -> Self {
+        Self {
+            seq: AtomicUsize::new(seq),
+            work: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+}
+
+pub struct WorkQueue<const N: usize> {
+    slots: [WorkSlot; N],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    dropped: AtomicU64,
+}
+
+impl<const N: usize> Default for WorkQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> WorkQueue<N> {
+    pub const fn new() -> Self {
+        let mut slots = [const { WorkSlot::new(0) }; N];
+        let mut i = 0;
+        while i < N {
+            slots[i] = WorkSlot::new(i);
+            i += 1;
+        }
+        Self {
+            slots,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Push `work`; safe from interrupt context on any CPU.
+    ///
+    /// Returns false and bumps the drop counter if the queue is full.
+    pub fn enqueue(&self, work: WorkFn) -> bool {
+        let mut pos = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[pos % N];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq as isize - pos as isize;
+            if diff == 0 {
+                match self.head.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        slot.work.store(work as *mut (), Ordering::Relaxed);
+                        slot.seq.store(pos.wrapping_add(1), Ordering::Release);
+                        return true;
+                    }
+                    Err(actual) => pos = actual,
+                }
+            } else if diff < 0 {
+                // Slot still holds an item from the previous lap: full
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return false;
+            } else {
+                pos = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Pop the oldest item. Only the worker (single consumer) may call this.
+    pub fn dequeue(&self) -> Option<WorkFn> {
+        let pos = self.tail.load(Ordering::Relaxed);
+        let slot = &self.slots[pos % N];
+        let seq = slot.seq.load(Ordering::Acquire);
+        if seq != pos.wrapping_add(1) {
+            return None;
+        }
+        let raw = slot.work.swap(ptr::null_mut(), Ordering::Relaxed);
+        self.tail.store(pos.wrapping_add(1), Ordering::Relaxed);
+        slot.seq.store(pos.wrapping_add(N), Ordering::Release);
+        Some(unsafe { core::mem::transmute::<*mut (), WorkFn>(raw) })
+    }
+
+    /// Run every queued item in FIFO order, returning how many ran.
+    pub fn run_pending(&self) -> usize {
+        let mut ran = 0;
+        while let Some(work) = self.dequeue() {
+            work();
+            ran += 1;
+        }
+        ran
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let pos = self.tail.load(Ordering::Relaxed);
+        self.slots[pos % N].seq.load(Ordering::Acquire) != pos.wrapping_add(1)
+    }
+
+    /// Number of items rejected because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+static KERNEL_WORK_QUEUE: WorkQueue<WORK_QUEUE_CAPACITY> = WorkQueue::new();
+static WORKER_TASK_ID: AtomicU32 = AtomicU32::new(INVALID_TASK_ID);
+
+/// Defer `work` to the kernel worker thread. Callable from IRQ handlers.
+pub fn work_queue_enqueue(work: WorkFn) -> bool {
+    if !KERNEL_WORK_QUEUE.enqueue(work) {
+        return false;
+    }
+    // Pairs with the fence in block_current_task_unless: either the worker
+    // sees the item or we see it Blocked.
+    core::sync::atomic::fence(Ordering::SeqCst);
+    let worker_id = WORKER_TASK_ID.load(Ordering::Acquire);
+    if worker_id != INVALID_TASK_ID {
+        let worker = task_find_by_id(worker_id);
+        if !worker.is_null() && unsafe { (*worker).state() } == TASK_STATE_BLOCKED {
+            unblock_task(worker);
+        }
+    }
+    true
+}
+
+/// Number of work items dropped because the global queue was full.
+pub fn work_queue_dropped() -> u64 {
+    KERNEL_WORK_QUEUE.dropped()
+}
+
+fn work_queue_worker(_arg: *mut core::ffi::c_void) {
+    loop {
+        KERNEL_WORK_QUEUE.run_pending();
+        // Re-checked once the worker is Blocked, so a producer on any CPU
+        // that enqueued after run_pending either wakes it or is seen here.
+        block_current_task_unless(|| !KERNEL_WORK_QUEUE.is_empty());
+    }
+}
+
+/// Spawn the kernel worker thread that drains the global work queue.
+pub fn boot_step_work_queue() -> c_int {
+    if WORKER_TASK_ID.load(Ordering::Acquire) != INVALID_TASK_ID {
+        return 0;
+    }
+    let id = kthread_spawn_ex(
+        c"kworker".as_ptr(),
+        Some(work_queue_worker),
+        ptr::null_mut(),
+        TASK_PRIORITY_HIGH,
+        0,
+    );
+    if id == INVALID_TASK_ID {
+        klog_info!("work_queue: failed to spawn worker thread");
+        return -1;
+    }
+    WORKER_TASK_ID.store(id, Ordering::Release);
+    0
+}
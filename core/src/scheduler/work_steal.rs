@@ -1,7 +1,7 @@
 //! Work Stealing for SMP Load Balancing
 
 use slopos_abi::task::Task;
-use slopos_lib::{get_cpu_count, get_current_cpu, klog_debug};
+use slopos_lib::{get_cpu_count, get_current_cpu, klog_debug_mod};
 
 use super::per_cpu::{get_cpu_scheduler, with_cpu_scheduler, with_local_scheduler};
 
@@ -25,7 +25,12 @@ pub fn try_work_steal() -> bool {
             with_local_scheduler(|sched| {
                 sched.enqueue_local(task);
             });
-            klog_debug!("WORK_STEAL: CPU {} stole task from CPU {}", cpu_id, victim);
+            klog_debug_mod!(
+                "scheduler",
+                "WORK_STEAL: CPU {} stole task from CPU {}",
+                cpu_id,
+                victim
+            );
             return true;
         }
     }
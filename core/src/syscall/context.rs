@@ -1,6 +1,6 @@
 use crate::syscall::common::{SyscallDisposition, syscall_return_err, syscall_return_ok};
 use slopos_abi::task::{
-    INVALID_PROCESS_ID, TASK_FLAG_COMPOSITOR, TASK_FLAG_DISPLAY_EXCLUSIVE, Task,
+    INVALID_PROCESS_ID, TASK_FLAG_COMPOSITOR, TASK_FLAG_DISPLAY_EXCLUSIVE, TASK_FLAG_SYSTEM, Task,
 };
 use slopos_lib::InterruptFrame;
 
@@ -14,6 +14,21 @@ pub struct SyscallArgs {
     pub arg5: u64,
 }
 
+impl SyscallArgs {
+    /// Read the syscall argument registers out of a trap frame.
+    #[inline]
+    pub fn from_frame(frame: &InterruptFrame) -> Self {
+        Self {
+            arg0: frame.rdi,
+            arg1: frame.rsi,
+            arg2: frame.rdx,
+            arg3: frame.r10,
+            arg4: frame.r8,
+            arg5: frame.r9,
+        }
+    }
+}
+
 pub struct SyscallContext {
     task_ptr: *mut Task,
     frame_ptr: *mut InterruptFrame,
@@ -26,17 +41,7 @@ impl SyscallContext {
             return None;
         }
 
-        let args = unsafe {
-            let f = &*frame;
-            SyscallArgs {
-                arg0: f.rdi,
-                arg1: f.rsi,
-                arg2: f.rdx,
-                arg3: f.r10,
-                arg4: f.r8,
-                arg5: f.r9,
-            }
-        };
+        let args = SyscallArgs::from_frame(unsafe { &*frame });
 
         Some(Self {
             task_ptr: task,
@@ -86,6 +91,11 @@ impl SyscallContext {
         self.has_flag(TASK_FLAG_DISPLAY_EXCLUSIVE)
     }
 
+    #[inline]
+    pub fn is_system(&self) -> bool {
+        self.has_flag(TASK_FLAG_SYSTEM)
+    }
+
     #[inline]
     pub fn args(&self) -> &SyscallArgs {
         &self.args
@@ -120,6 +130,12 @@ impl SyscallContext {
         syscall_return_err(self.frame_ptr, u64::MAX)
     }
 
+    /// Fail with a specific negative error code instead of the generic -1.
+    #[inline]
+    pub fn err_code(&self, code: i32) -> SyscallDisposition {
+        syscall_return_err(self.frame_ptr, code as i64 as u64)
+    }
+
     #[inline]
     pub fn require_task(&self) -> Result<(), SyscallDisposition> {
         if self.task_ptr.is_null() {
@@ -160,6 +176,15 @@ impl SyscallContext {
         }
     }
 
+    #[inline]
+    pub fn require_system(&self) -> Result<(), SyscallDisposition> {
+        if !self.is_system() {
+            Err(self.err())
+        } else {
+            Ok(())
+        }
+    }
+
     #[inline]
     pub fn check_result(&self, result: i32) -> Result<(), SyscallDisposition> {
         if result != 0 { Err(self.err()) } else { Ok(()) }
@@ -1,12 +1,87 @@
+use core::fmt;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
 use slopos_lib::klog_info;
 
 use crate::scheduler_get_current_task;
-use crate::syscall::handlers::syscall_lookup;
+use crate::syscall::common::SyscallDisposition;
+use crate::syscall::context::SyscallArgs;
+use crate::syscall::handlers::{syscall_name, syscall_resolve};
 
 use slopos_abi::arch::GDT_USER_DATA_SELECTOR;
+use slopos_abi::syscall::ENOSYS;
 use slopos_abi::task::{TASK_FLAG_NO_PREEMPT, TASK_FLAG_USER_MODE, Task, TaskContext};
 use slopos_lib::InterruptFrame;
 
+/// Destination for syscall trace lines. Defaults to klog when unset.
+pub type SyscallTraceSink = fn(fmt::Arguments<'_>);
+
+static SYSCALL_TRACE_ENABLED: AtomicBool = AtomicBool::new(false);
+static SYSCALL_TRACE_SINK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+pub fn syscall_trace_enable() {
+    SYSCALL_TRACE_ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn syscall_trace_disable() {
+    SYSCALL_TRACE_ENABLED.store(false, Ordering::Relaxed);
+}
+
+#[inline(always)]
+pub fn syscall_trace_enabled() -> bool {
+    SYSCALL_TRACE_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Redirect trace output, returning the previous sink. `None` restores klog.
+pub fn syscall_trace_set_sink(sink: Option<SyscallTraceSink>) -> Option<SyscallTraceSink> {
+    let raw = sink.map_or(ptr::null_mut(), |f| f as *mut ());
+    let prev = SYSCALL_TRACE_SINK.swap(raw, Ordering::AcqRel);
+    if prev.is_null() {
+        None
+    } else {
+        Some(unsafe { core::mem::transmute::<*mut (), SyscallTraceSink>(prev) })
+    }
+}
+
+fn syscall_trace_emit(
+    sysno: u64,
+    args: &SyscallArgs,
+    frame: *mut InterruptFrame,
+    disposition: SyscallDisposition,
+) {
+    let name = syscall_name(sysno).unwrap_or("?");
+    let ret = if frame.is_null() {
+        0
+    } else {
+        unsafe { (*frame).rax as i64 }
+    };
+    let line = format_args!(
+        "SYSCALL_TRACE: {}({}) [{:#x}, {:#x}, {:#x}, {:#x}, {:#x}, {:#x}] -> {}{}",
+        name,
+        sysno,
+        args.arg0,
+        args.arg1,
+        args.arg2,
+        args.arg3,
+        args.arg4,
+        args.arg5,
+        ret,
+        if disposition == SyscallDisposition::NoReturn {
+            " (noreturn)"
+        } else {
+            ""
+        },
+    );
+    let sink = SYSCALL_TRACE_SINK.load(Ordering::Acquire);
+    if sink.is_null() {
+        klog_info!("{}", line);
+    } else {
+        let sink: SyscallTraceSink = unsafe { core::mem::transmute(sink) };
+        sink(line);
+    }
+}
+
 fn save_user_context(frame: *mut InterruptFrame, task: *mut Task) {
     if frame.is_null() || task.is_null() {
         return;
@@ -68,26 +143,41 @@ pub fn syscall_handle(frame: *mut InterruptFrame) {
     let original_provider = slopos_mm::user_copy::set_syscall_process_id(pid);
 
     let sysno = unsafe { (*frame).rax };
-    let entry = syscall_lookup(sysno);
-    if entry.is_null() {
-        klog_info!("SYSCALL: Unknown syscall {}", sysno);
-        unsafe {
-            (*frame).rax = u64::MAX;
-        }
-        unsafe {
-            (*task).flags &= !TASK_FLAG_NO_PREEMPT;
-        }
-        slopos_mm::user_copy::restore_task_provider(original_provider);
-        return;
-    }
-
-    let handler = unsafe { (*entry).handler };
-    if let Some(func) = handler {
-        func(task, frame);
-    }
+    syscall_dispatch(sysno, task, frame);
 
     unsafe {
         (*task).flags &= !TASK_FLAG_NO_PREEMPT;
     }
     slopos_mm::user_copy::restore_task_provider(original_provider);
 }
+
+/// Route `sysno` to its handler. Unknown numbers get `-ENOSYS` in rax.
+pub fn syscall_dispatch(
+    sysno: u64,
+    task: *mut Task,
+    frame: *mut InterruptFrame,
+) -> SyscallDisposition {
+    let trace_args = if syscall_trace_enabled() && !frame.is_null() {
+        Some(SyscallArgs::from_frame(unsafe { &*frame }))
+    } else {
+        None
+    };
+
+    let disposition = match syscall_resolve(sysno) {
+        Some(func) => func(task, frame),
+        None => {
+            klog_info!("SYSCALL: Unknown syscall {}", sysno);
+            if !frame.is_null() {
+                unsafe {
+                    (*frame).rax = ENOSYS.wrapping_neg();
+                }
+            }
+            SyscallDisposition::Ok
+        }
+    };
+
+    if let Some(args) = trace_args {
+        syscall_trace_emit(sysno, &args, frame, disposition);
+    }
+    disposition
+}
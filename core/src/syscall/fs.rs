@@ -2,23 +2,103 @@
 
 use core::ffi::{c_char, c_int, c_void};
 use core::mem;
+use core::sync::atomic::{Ordering, fence};
 
+use slopos_abi::task::INVALID_TASK_ID;
 use slopos_abi::{USER_FS_MAX_ENTRIES, UserFsEntry, UserFsList, UserFsStat};
+use slopos_lib::IrqMutex;
 
 use crate::syscall::common::{
     USER_IO_MAX_BYTES, USER_PATH_MAX, syscall_bounded_from_user, syscall_copy_to_user_bounded,
     syscall_copy_user_str_to_cstr,
 };
 
+use slopos_abi::syscall::EPIPE;
+
+use crate::{
+    block_current_task_unless, scheduler_get_current_task, task_find_by_id, unblock_task, yield_,
+};
+
 use slopos_fs::fileio::{
-    file_close_fd, file_list_path, file_mkdir_path, file_open_for_process, file_read_fd,
-    file_stat_path, file_unlink_path, file_write_fd,
+    FILEIO_BROKEN_PIPE, FILEIO_WOULD_BLOCK, file_close_fd, file_getdents_fd, file_list_path,
+    file_mkdir_path, file_open_for_process, file_pipe_end, file_pipe_for_process, file_read_fd,
+    file_seek_fd, file_stat_path, file_unlink_path, file_write_fd,
 };
+use slopos_fs::pipe::{MAX_PIPES, pipe_ready};
 
 use slopos_mm::kernel_heap::{kfree, kmalloc};
 use slopos_mm::user_copy::{copy_bytes_to_user, copy_from_user, copy_to_user};
 use slopos_mm::user_ptr::{UserBytes, UserPtr};
 
+/// Tasks per pipe that can sleep on it; any further waiter yields and polls.
+const PIPE_MAX_WAITERS: usize = 8;
+
+/// Ids of the tasks blocked on each pipe, `INVALID_TASK_ID` in free slots.
+static PIPE_WAITERS: IrqMutex<[[u32; PIPE_MAX_WAITERS]; MAX_PIPES]> =
+    IrqMutex::new([[INVALID_TASK_ID; PIPE_MAX_WAITERS]; MAX_PIPES]);
+
+/// Replace the first `from` in pipe `id`'s waiter list with `to`.
+fn pipe_waiters_swap(id: usize, from: u32, to: u32) -> bool {
+    let mut waiters = PIPE_WAITERS.lock();
+    let slot = waiters
+        .get_mut(id)
+        .and_then(|list| list.iter_mut().find(|slot| **slot == from));
+    match slot {
+        Some(slot) => {
+            *slot = to;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Sleep until the pipe behind `fd` may have become readable or writable.
+/// Falls back to a yield when the task cannot be queued on the pipe.
+fn pipe_wait(process_id: u32, fd: c_int) {
+    let current = scheduler_get_current_task();
+    let Some((id, end)) = file_pipe_end(process_id, fd) else {
+        yield_();
+        return;
+    };
+    if current.is_null() {
+        yield_();
+        return;
+    }
+    let task_id = unsafe { (*current).task_id };
+    if !pipe_waiters_swap(id, INVALID_TASK_ID, task_id) {
+        yield_();
+        return;
+    }
+    // Re-checked once Blocked: the other end either finds us queued and
+    // wakes us, or already left the pipe ready.
+    block_current_task_unless(|| pipe_ready(id, end));
+    pipe_waiters_swap(id, task_id, INVALID_TASK_ID);
+}
+
+/// Pipe wake hook: release every task sleeping on pipe `id`. Registered
+/// with `slopos_fs::pipe::register_pipe_wake_hook` at boot.
+pub fn pipe_wake_waiters(id: usize) {
+    // Pairs with the fence in block_current_task_unless: a waiter not yet
+    // Blocked sees the pipe ready when it re-checks.
+    fence(Ordering::SeqCst);
+    let Some(waiters) = PIPE_WAITERS
+        .lock()
+        .get_mut(id)
+        .map(|list| mem::replace(list, [INVALID_TASK_ID; PIPE_MAX_WAITERS]))
+    else {
+        return;
+    };
+    for task_id in waiters
+        .into_iter()
+        .filter(|&waiter| waiter != INVALID_TASK_ID)
+    {
+        let task = task_find_by_id(task_id);
+        if !task.is_null() {
+            unblock_task(task);
+        }
+    }
+}
+
 define_syscall!(syscall_fs_open(ctx, args, pid) requires process_id {
     let mut path = [0i8; USER_PATH_MAX];
     check_result!(ctx, syscall_copy_user_str_to_cstr(&mut path, args.arg0));
@@ -36,7 +116,34 @@ define_syscall!(syscall_fs_read(ctx, args, pid) requires process_id {
     let mut tmp = [0u8; USER_IO_MAX_BYTES];
     let capped_len = args.arg2_usize().min(USER_IO_MAX_BYTES);
 
-    let bytes = file_read_fd(pid, args.arg0 as c_int, tmp.as_mut_ptr() as *mut c_char, capped_len);
+    // Pipes report an empty ring instead of blocking; wait for the writer here.
+    let bytes = loop {
+        let rc = file_read_fd(pid, args.arg0 as c_int, tmp.as_mut_ptr() as *mut c_char, capped_len);
+        if rc != FILEIO_WOULD_BLOCK {
+            break rc;
+        }
+        pipe_wait(pid, args.arg0 as c_int);
+    };
+    if bytes < 0 {
+        return ctx.err();
+    }
+
+    try_or_err!(ctx, syscall_copy_to_user_bounded(args.arg1, &tmp[..bytes as usize]));
+    ctx.ok(bytes as u64)
+});
+
+define_syscall!(syscall_fs_lseek(ctx, args, pid) requires process_id {
+    let pos = file_seek_fd(pid, args.arg0 as c_int, args.arg1 as i64, args.arg2 as c_int);
+    ctx.from_rc_value(pos)
+});
+
+define_syscall!(syscall_fs_getdents(ctx, args, pid) requires process_id {
+    require_nonzero!(ctx, args.arg1);
+
+    let mut tmp = [0u8; USER_IO_MAX_BYTES];
+    let capped_len = args.arg2_usize().min(USER_IO_MAX_BYTES);
+
+    let bytes = file_getdents_fd(pid, args.arg0 as c_int, &mut tmp[..capped_len]);
     if bytes < 0 {
         return ctx.err();
     }
@@ -51,10 +158,31 @@ define_syscall!(syscall_fs_write(ctx, args, pid) requires process_id {
     let mut tmp = [0u8; USER_IO_MAX_BYTES];
     let write_len = try_or_err!(ctx, syscall_bounded_from_user(&mut tmp, args.arg1, args.arg2, USER_IO_MAX_BYTES));
 
-    let bytes = file_write_fd(pid, args.arg0 as c_int, tmp.as_ptr() as *const c_char, write_len);
+    let bytes = loop {
+        let rc = file_write_fd(pid, args.arg0 as c_int, tmp.as_ptr() as *const c_char, write_len);
+        if rc != FILEIO_WOULD_BLOCK {
+            break rc;
+        }
+        pipe_wait(pid, args.arg0 as c_int);
+    };
+    if bytes == FILEIO_BROKEN_PIPE {
+        return ctx.err_code(-(EPIPE as i32));
+    }
     ctx.from_rc_value(bytes as i64)
 });
 
+define_syscall!(syscall_fs_pipe(ctx, args, pid) requires process_id {
+    let fds_ptr = try_or_err!(ctx, UserPtr::<[c_int; 2]>::try_new(args.arg0));
+    let mut fds = [-1; 2];
+    check_result!(ctx, file_pipe_for_process(pid, &mut fds));
+    if copy_to_user(fds_ptr, &fds).is_err() {
+        file_close_fd(pid, fds[0]);
+        file_close_fd(pid, fds[1]);
+        return ctx.err();
+    }
+    ctx.ok(0)
+});
+
 define_syscall!(syscall_fs_stat(ctx, args) {
     require_nonzero!(ctx, args.arg0);
     require_nonzero!(ctx, args.arg1);
@@ -1,38 +1,47 @@
 use core::ffi::c_char;
 use core::ptr;
 
+use alloc::vec;
+
+use slopos_abi::CompositorError;
 use slopos_abi::DisplayInfo;
 use slopos_abi::InputEvent;
-use slopos_abi::WindowInfo;
+use slopos_abi::arch::x86_64::pci::{PCI_MAX_DEVICES, PciDeviceInfo};
+use slopos_abi::error::SyscallError;
 use slopos_abi::fate::FateResult;
 use slopos_abi::syscall::*;
+use slopos_abi::{WINDOW_TITLE_LEN, WindowInfo};
 
 use crate::exec;
 
 use crate::platform;
+use crate::scheduler::scheduler::WaitError;
 use crate::syscall::common::{
-    SyscallDisposition, SyscallEntry, USER_IO_MAX_BYTES, syscall_bounded_from_user,
+    SyscallDisposition, SyscallEntry, SyscallHandler, USER_IO_MAX_BYTES, syscall_bounded_from_user,
     syscall_copy_to_user_bounded, syscall_copy_user_str, syscall_return_err,
 };
 use crate::syscall::context::SyscallContext;
 use crate::syscall::fs::{
-    syscall_fs_close, syscall_fs_list, syscall_fs_mkdir, syscall_fs_open, syscall_fs_read,
-    syscall_fs_stat, syscall_fs_unlink, syscall_fs_write,
+    syscall_fs_close, syscall_fs_getdents, syscall_fs_list, syscall_fs_lseek, syscall_fs_mkdir,
+    syscall_fs_open, syscall_fs_pipe, syscall_fs_read, syscall_fs_stat, syscall_fs_unlink,
+    syscall_fs_write,
 };
 use crate::syscall_services::{fate as fate_svc, input, tty, video};
 use crate::{
     clear_scheduler_current_task, fate_apply_outcome, fate_set_pending, fate_spin,
     fate_take_pending, get_scheduler_stats, get_task_stats, schedule,
-    scheduler_is_preemption_enabled, task_terminate, yield_,
+    scheduler_is_preemption_enabled, sleep_ticks_for_ns, task_sleep_ticks, task_terminate,
+    task_waitpid, yield_,
 };
 
 use slopos_abi::task::{Task, TaskExitReason, TaskFaultReason};
 use slopos_lib::InterruptFrame;
-use slopos_lib::klog_debug;
+use slopos_lib::klog_debug_mod;
+use slopos_lib::string::cstr_to_str;
 use slopos_mm::page_alloc::get_page_allocator_stats;
 use slopos_mm::paging;
-use slopos_mm::user_copy::copy_to_user;
-use slopos_mm::user_ptr::UserPtr;
+use slopos_mm::user_copy::{copy_bytes_to_user, copy_to_user};
+use slopos_mm::user_ptr::{UserBytes, UserPtr, UserPtrError, UserSlice};
 
 pub fn syscall_yield(task: *mut Task, frame: *mut InterruptFrame) -> SyscallDisposition {
     let Some(ctx) = SyscallContext::new(task, frame) else {
@@ -77,22 +86,46 @@ define_syscall!(syscall_sleep_ms(ctx, args) {
     ctx.ok(0)
 });
 
+define_syscall!(syscall_nanosleep(ctx, args) {
+    let ticks = sleep_ticks_for_ns(args.arg0, platform::timer_frequency());
+    if ticks == 0 {
+        return ctx.ok(0);
+    }
+    ctx.from_zero_success(task_sleep_ticks(ticks))
+});
+
+define_syscall!(syscall_gettimeofday(ctx, args) {
+    let ptr = try_or_err!(ctx, UserPtr::<UserTimeval>::try_new(args.arg0));
+    let (secs, usecs) = platform::wall_clock_now();
+    let tv = UserTimeval {
+        tv_sec: secs as i64,
+        tv_usec: usecs as i64,
+    };
+    try_or_err!(ctx, copy_to_user(ptr, &tv));
+    ctx.ok(0)
+});
+
 pub fn syscall_exit(task: *mut Task, frame: *mut InterruptFrame) -> SyscallDisposition {
     let ctx = SyscallContext::new(task, frame);
     let task_id = ctx.as_ref().and_then(|c| c.task_id()).unwrap_or(u32::MAX);
-    klog_debug!("SYSCALL_EXIT: task {} entering exit", task_id);
+    klog_debug_mod!("syscall", "SYSCALL_EXIT: task {} entering exit", task_id);
     if let Some(ref c) = ctx {
         if let Some(t) = c.task_mut() {
             t.exit_reason = TaskExitReason::Normal;
             t.fault_reason = TaskFaultReason::None;
-            t.exit_code = 0;
+            t.exit_code = c.args().arg0_i32();
         }
     }
-    klog_debug!("SYSCALL_EXIT: task {} calling task_terminate", task_id);
+    klog_debug_mod!(
+        "syscall",
+        "SYSCALL_EXIT: task {} calling task_terminate",
+        task_id
+    );
     task_terminate(task_id);
     clear_scheduler_current_task();
     schedule();
-    klog_debug!(
+    klog_debug_mod!(
+        "syscall",
         "SYSCALL_EXIT: task {} schedule returned (should not happen)",
         task_id
     );
@@ -103,6 +136,18 @@ define_syscall!(syscall_surface_commit(ctx, args, task_id) requires task_id {
     ctx.from_result(video::surface_commit(task_id))
 });
 
+define_syscall!(syscall_surface_commit_swap(ctx, args, task_id) requires task_id {
+    ctx.from_result(video::surface_commit_swap(task_id))
+});
+
+define_syscall!(syscall_surface_begin_frame(ctx, args, task_id) requires task_id {
+    ctx.from_result(video::surface_begin_frame(task_id))
+});
+
+define_syscall!(syscall_surface_end_frame(ctx, args, task_id) requires task_id {
+    ctx.from_result(video::surface_end_frame(task_id))
+});
+
 define_syscall!(syscall_surface_frame(ctx, args, task_id) requires task_id {
     ctx.from_result(video::surface_request_frame_callback(task_id))
 });
@@ -112,6 +157,14 @@ define_syscall!(syscall_poll_frame_done(ctx, args, task_id) requires task_id {
     ctx.ok(timestamp)
 });
 
+define_syscall!(syscall_surface_frame_done(ctx, args, task_id) requires task_id {
+    ctx.ok(video::surface_frame_done(task_id) as u64)
+});
+
+define_syscall!(syscall_surface_back_buffer(ctx, args, task_id) requires task_id {
+    ctx.ok(video::surface_back_buffer(task_id) as u64)
+});
+
 define_syscall!(syscall_buffer_age(ctx, args, task_id) requires task_id {
     let age = video::surface_get_buffer_age(task_id);
     ctx.ok(age as u64)
@@ -162,14 +215,47 @@ define_syscall!(syscall_surface_attach(ctx, args, task_id, process_id) requires
     let token = args.arg0_u32();
     let width = args.arg1_u32();
     let height = args.arg2_u32();
-    let result = slopos_mm::shared_memory::surface_attach(process_id, token, width, height);
-    check_result!(ctx, result);
+    if let Err(e) = slopos_mm::shared_memory::surface_attach(process_id, token, width, height) {
+        return ctx.err_code(e.as_c_int());
+    }
     if video::register_surface(task_id, width, height, token).is_err() {
         return ctx.err();
     }
     ctx.ok(0)
 });
 
+define_syscall!(syscall_surface_attach_back(ctx, args, task_id, process_id) requires task_and_process {
+    let token = args.arg0_u32();
+    if token != 0 {
+        let width = args.arg1_u32();
+        let height = args.arg2_u32();
+        if let Err(e) = slopos_mm::shared_memory::surface_attach(process_id, token, width, height) {
+            return ctx.err_code(e.as_c_int());
+        }
+    }
+    ctx.from_result(video::surface_attach_back_buffer(task_id, token))
+});
+
+define_syscall!(syscall_surface_attach_spare(ctx, args, task_id, process_id) requires task_and_process {
+    let token = args.arg0_u32();
+    if token == 0 {
+        return match video::surface_detach_spare_buffer(task_id) {
+            Ok(detached) => ctx.ok(detached as u64),
+            Err(e) => ctx.err_code(e.as_c_int()),
+        };
+    }
+    let width = args.arg1_u32();
+    let height = args.arg2_u32();
+    if let Err(e) = slopos_mm::shared_memory::surface_attach(process_id, token, width, height) {
+        return ctx.err_code(e.as_c_int());
+    }
+    ctx.from_result(video::surface_attach_spare_buffer(task_id, token))
+});
+
+define_syscall!(syscall_surface_resize(ctx, args, task_id) requires task_id {
+    ctx.from_result(video::surface_resize(task_id, args.arg0_u32(), args.arg1_u32()))
+});
+
 define_syscall!(syscall_shm_create_with_format(ctx, args, task_id) requires task_id {
     let size = args.arg0;
     let format_val = args.arg1_u32();
@@ -194,16 +280,26 @@ define_syscall!(syscall_surface_set_rel_pos(ctx, args, task_id) requires task_id
 });
 
 define_syscall!(syscall_surface_set_title(ctx, args, task_id) requires task_id {
-    let title_ptr = args.arg0_const_ptr::<u8>();
-    let title_len = args.arg1_usize();
+    // Anything past the longest storable title is dropped without reading it
+    let mut title = [0u8; WINDOW_TITLE_LEN - 1];
+    let len = try_or_err!(
+        ctx,
+        syscall_bounded_from_user(&mut title, args.arg0, args.arg1, WINDOW_TITLE_LEN - 1)
+    );
+    ctx.from_result(video::surface_set_title(task_id, &title[..len]))
+});
 
-    if title_ptr.is_null() || title_len == 0 {
-        return ctx.err();
-    }
+define_syscall!(syscall_surface_set_color_key(ctx, args, task_id) requires task_id {
+    let key = if args.arg0_u32() != 0 {
+        Some(args.arg1_u32())
+    } else {
+        None
+    };
+    ctx.from_result(video::surface_set_color_key(task_id, key))
+});
 
-    let copy_len = title_len.min(31);
-    let title_slice = unsafe { core::slice::from_raw_parts(title_ptr, copy_len) };
-    ctx.from_result(video::surface_set_title(task_id, title_slice))
+define_syscall!(syscall_surface_set_blend_mode(ctx, args, task_id) requires task_id {
+    ctx.from_result(video::surface_set_blend_mode(task_id, args.arg0 as u8))
 });
 
 define_syscall!(syscall_input_poll(ctx, args, task_id) requires task_id {
@@ -284,6 +380,11 @@ define_syscall!(syscall_tty_set_focus(ctx, args) requires compositor {
     ctx.from_bool_value(tty::tty_set_focus(target) == 0, tty::tty_get_focus() as u64)
 });
 
+define_syscall!(syscall_tty_set_mode(ctx, args, task_id) requires task_id {
+    let canonical = args.arg0 != 0;
+    ctx.from_zero_success(tty::tty_set_mode(task_id, canonical))
+});
+
 define_syscall!(syscall_enumerate_windows(ctx, args) requires compositor {
     let out_buffer = args.arg0_ptr::<WindowInfo>();
     let max_count = args.arg1_u32();
@@ -292,6 +393,148 @@ define_syscall!(syscall_enumerate_windows(ctx, args) requires compositor {
     ctx.ok(video::surface_enumerate_windows(out_buffer, max_count) as u64)
 });
 
+/// Windows staged in kernel memory per `surface_list_windows` call
+const LIST_WINDOWS_BATCH: usize = 4;
+
+/// Copy up to `max_count` visible windows into the user array at `user_buf`.
+///
+/// The array's bounds are checked up front, but entries are copied out one
+/// at a time: if a copy faults (say another thread unmapped the buffer),
+/// the entries before it are already written and the error is returned.
+/// Windows are fetched in small batches, so a window opened or closed
+/// mid-copy can be missed or reported twice; callers re-list on the next
+/// refresh.
+pub fn list_windows_to_user(user_buf: u64, max_count: u32) -> Result<u32, UserPtrError> {
+    if max_count == 0 {
+        return Ok(0);
+    }
+    let dst = UserSlice::<WindowInfo>::try_new(user_buf, max_count as usize)?;
+    let entry_size = core::mem::size_of::<WindowInfo>() as u64;
+    let mut staged = [WindowInfo::default(); LIST_WINDOWS_BATCH];
+    let mut copied = 0u32;
+    while copied < max_count {
+        let want = ((max_count - copied) as usize).min(LIST_WINDOWS_BATCH);
+        let got = video::surface_list_windows(copied, &mut staged[..want]);
+        for info in &staged[..got as usize] {
+            let addr = dst.base().as_u64() + copied as u64 * entry_size;
+            copy_to_user(UserPtr::try_new(addr)?, info)?;
+            copied += 1;
+        }
+        if (got as usize) < want {
+            break;
+        }
+    }
+    Ok(copied)
+}
+
+/// Copy up to `max_count` devices from the boot-time PCI enumeration to
+/// `user_buf`.
+fn pci_devices_to_user(user_buf: u64, max_count: u32) -> Result<u32, UserPtrError> {
+    let max = (max_count as usize).min(PCI_MAX_DEVICES);
+    if max == 0 {
+        return Ok(0);
+    }
+    let dst = UserSlice::<PciDeviceInfo>::try_new(user_buf, max)?;
+    let entry_size = core::mem::size_of::<PciDeviceInfo>() as u64;
+    let mut staged = vec![PciDeviceInfo::zeroed(); max];
+    let count = platform::pci_enumerate(&mut staged).min(max as u32);
+    for (i, info) in staged[..count as usize].iter().enumerate() {
+        let addr = dst.base().as_u64() + i as u64 * entry_size;
+        copy_to_user(UserPtr::try_new(addr)?, info)?;
+    }
+    Ok(count)
+}
+
+/// Screen rows staged in kernel memory per capture batch
+const CAPTURE_BATCH_ROWS: usize = 16;
+
+/// Copy the screen to `user_buf` as BGRA8888, rows `stride` bytes apart
+/// (0 packs them tightly). Padding between rows is left untouched.
+///
+/// `len` must cover the whole frame. Rows are staged a batch at a time, so
+/// a capture racing the compositor may mix two frames.
+pub fn capture_to_user(user_buf: u64, len: u64, stride: u64) -> Result<(), CompositorError> {
+    let info = video::get_display_info().ok_or(CompositorError::NoFramebuffer)?;
+    let row_bytes = info.width as usize * 4;
+    let stride = match stride {
+        0 => row_bytes,
+        s => s as usize,
+    };
+    let needed = info
+        .capture_size(stride)
+        .ok_or(CompositorError::InvalidArgument)?;
+    if (len as usize) < needed {
+        return Err(CompositorError::InvalidArgument);
+    }
+    UserBytes::try_new(user_buf, needed).map_err(|_| CompositorError::InvalidArgument)?;
+
+    let mut staged = vec![0u8; row_bytes * CAPTURE_BATCH_ROWS];
+    let mut row = 0u32;
+    while row < info.height {
+        let got = video::compositor_capture_rows(row, &mut staged, row_bytes)?;
+        if got == 0 {
+            break;
+        }
+        for (i, line) in staged
+            .chunks_exact(row_bytes)
+            .take(got as usize)
+            .enumerate()
+        {
+            let addr = user_buf + (row as u64 + i as u64) * stride as u64;
+            let dst = UserBytes::try_new(addr, row_bytes)
+                .map_err(|_| CompositorError::InvalidArgument)?;
+            copy_bytes_to_user(dst, line).map_err(|_| CompositorError::InvalidArgument)?;
+        }
+        row += got;
+    }
+    Ok(())
+}
+
+/// Stage a window thumbnail in kernel memory and copy it out in one go.
+/// Returns the thumbnail size packed as `width << 16 | height`.
+pub fn thumbnail_to_user(
+    task_id: u32,
+    max_w: u32,
+    max_h: u32,
+    user_buf: u64,
+    len: u64,
+) -> Result<u64, CompositorError> {
+    if max_w == 0 || max_h == 0 || max_w > THUMBNAIL_MAX_DIM || max_h > THUMBNAIL_MAX_DIM {
+        return Err(CompositorError::InvalidArgument);
+    }
+    let staged_len = (max_w as usize * max_h as usize * 4).min(len as usize);
+    let dst =
+        UserBytes::try_new(user_buf, staged_len).map_err(|_| CompositorError::InvalidArgument)?;
+
+    let mut staged = vec![0u8; staged_len];
+    let (w, h) = video::surface_generate_thumbnail(task_id, max_w, max_h, &mut staged)
+        .ok_or(CompositorError::InvalidArgument)?;
+    copy_bytes_to_user(dst, &staged).map_err(|_| CompositorError::InvalidArgument)?;
+    Ok(((w as u64) << 16) | h as u64)
+}
+
+define_syscall!(syscall_surface_thumbnail(ctx, args) requires compositor {
+    let packed = try_or_err!(
+        ctx,
+        thumbnail_to_user(args.arg0_u32(), args.arg1_u32(), args.arg2_u32(), args.arg3, args.arg4)
+    );
+    ctx.ok(packed)
+});
+
+define_syscall!(syscall_compositor_capture(ctx, args) {
+    ctx.from_result(capture_to_user(args.arg0, args.arg1, args.arg2))
+});
+
+define_syscall!(syscall_pci_enumerate(ctx, args) requires system {
+    let count = try_or_err!(ctx, pci_devices_to_user(args.arg0, args.arg1_u32()));
+    ctx.ok(count as u64)
+});
+
+define_syscall!(syscall_list_windows(ctx, args) {
+    let count = try_or_err!(ctx, list_windows_to_user(args.arg0, args.arg1_u32()));
+    ctx.ok(count as u64)
+});
+
 define_syscall!(syscall_set_window_position(ctx, args) requires compositor {
     let target_task_id = args.arg0_u32();
     let x = args.arg1_i32();
@@ -312,14 +555,7 @@ define_syscall!(syscall_raise_window(ctx, args) requires compositor {
 
 define_syscall!(syscall_fb_flip(ctx, args) requires compositor {
     let token = args.arg0_u32();
-    let phys_addr = slopos_mm::shared_memory::shm_get_phys_addr(token);
-    let size = slopos_mm::shared_memory::shm_get_size(token);
-    if phys_addr.is_null() || size == 0 {
-        return ctx.err();
-    }
-    some_or_err!(ctx, video::get_display_info());
-    check_result!(ctx, video::fb_flip_from_shm(phys_addr, size));
-    ctx.ok(0)
+    ctx.from_result(video::fb_flip(token))
 });
 
 define_syscall!(syscall_drain_queue(ctx, args) requires compositor {
@@ -561,6 +797,27 @@ define_syscall!(syscall_brk(ctx, args, process_id) requires process_id {
     ctx.ok(result)
 });
 
+define_syscall!(syscall_sbrk(ctx, args, process_id) requires process_id {
+    let increment = args.arg0 as i64;
+    match slopos_mm::process_vm::process_vm_sbrk(process_id, increment) {
+        Some(old_brk) => ctx.ok(old_brk),
+        None => ctx.err(),
+    }
+});
+
+define_syscall!(syscall_mmap(ctx, args, process_id) requires process_id {
+    match slopos_mm::process_vm::process_vm_mmap(process_id, args.arg0) {
+        Some(addr) => ctx.ok(addr),
+        None => ctx.err(),
+    }
+});
+
+define_syscall!(syscall_munmap(ctx, args, process_id) requires process_id {
+    ctx.from_zero_success(slopos_mm::process_vm::process_vm_munmap(
+        process_id, args.arg0, args.arg1,
+    ))
+});
+
 define_syscall!(syscall_get_cpu_count(ctx, args) {
     let _ = args;
     ctx.ok(slopos_lib::get_cpu_count() as u64)
@@ -576,13 +833,10 @@ define_syscall!(syscall_set_cpu_affinity(ctx, args, task_id) requires task_id {
     let new_affinity = args.arg1_u32();
     let resolved_task_id = if target_or_zero == 0 { task_id } else { target_or_zero };
 
-    let task_ptr = crate::scheduler::task::task_find_by_id(resolved_task_id);
-    if task_ptr.is_null() {
-        return ctx.err();
-    }
-
-    unsafe { (*task_ptr).cpu_affinity = new_affinity; }
-    ctx.ok(0)
+    ctx.from_zero_success(crate::scheduler::task::task_set_affinity(
+        resolved_task_id,
+        new_affinity,
+    ))
 });
 
 define_syscall!(syscall_get_cpu_affinity(ctx, args, task_id) requires task_id {
@@ -597,6 +851,26 @@ define_syscall!(syscall_get_cpu_affinity(ctx, args, task_id) requires task_id {
     ctx.ok(unsafe { (*task_ptr).cpu_affinity } as u64)
 });
 
+define_syscall!(syscall_waitpid(ctx, args, task_id) requires task_id {
+    let child_id = args.arg0_u32();
+    // Check the status pointer up front so a bad one cannot eat the code.
+    let status_ptr = if args.arg1 == 0 {
+        None
+    } else {
+        Some(try_or_err!(ctx, UserPtr::<i32>::try_new(args.arg1)))
+    };
+    match task_waitpid(task_id, child_id, args.arg2 & WNOHANG != 0) {
+        Ok(code) => {
+            if let Some(ptr) = status_ptr {
+                try_or_err!(ctx, copy_to_user(ptr, &code));
+            }
+            ctx.ok(child_id as u64)
+        }
+        Err(WaitError::StillRunning) => ctx.ok(0),
+        Err(WaitError::NotChild) => ctx.err(),
+    }
+});
+
 pub fn syscall_fork(task: *mut Task, frame: *mut InterruptFrame) -> SyscallDisposition {
     let Some(ctx) = SyscallContext::new(task, frame) else {
         return syscall_return_err(frame, u64::MAX);
@@ -609,11 +883,11 @@ pub fn syscall_fork(task: *mut Task, frame: *mut InterruptFrame) -> SyscallDispo
     )
 }
 
-static SYSCALL_TABLE: [SyscallEntry; 128] = {
-    let mut table: [SyscallEntry; 128] = [SyscallEntry {
+static SYSCALL_TABLE: [SyscallEntry; SYSCALL_TABLE_SIZE] = {
+    let mut table: [SyscallEntry; SYSCALL_TABLE_SIZE] = [SyscallEntry {
         handler: None,
         name: core::ptr::null(),
-    }; 128];
+    }; SYSCALL_TABLE_SIZE];
     table[SYSCALL_YIELD as usize] = SyscallEntry {
         handler: Some(syscall_yield),
         name: b"yield\0".as_ptr() as *const c_char,
@@ -638,6 +912,10 @@ static SYSCALL_TABLE: [SyscallEntry; 128] = {
         handler: Some(syscall_tty_set_focus),
         name: b"tty_set_focus\0".as_ptr() as *const c_char,
     };
+    table[SYSCALL_TTY_SET_MODE as usize] = SyscallEntry {
+        handler: Some(syscall_tty_set_mode),
+        name: c"tty_set_mode".as_ptr(),
+    };
     table[SYSCALL_ROULETTE as usize] = SyscallEntry {
         handler: Some(syscall_roulette_spin),
         name: b"roulette\0".as_ptr() as *const c_char,
@@ -646,6 +924,14 @@ static SYSCALL_TABLE: [SyscallEntry; 128] = {
         handler: Some(syscall_sleep_ms),
         name: b"sleep_ms\0".as_ptr() as *const c_char,
     };
+    table[SYSCALL_NANOSLEEP as usize] = SyscallEntry {
+        handler: Some(syscall_nanosleep),
+        name: c"nanosleep".as_ptr(),
+    };
+    table[SYSCALL_GETTIMEOFDAY as usize] = SyscallEntry {
+        handler: Some(syscall_gettimeofday),
+        name: c"gettimeofday".as_ptr(),
+    };
     table[SYSCALL_FB_INFO as usize] = SyscallEntry {
         handler: Some(syscall_fb_info),
         name: b"fb_info\0".as_ptr() as *const c_char,
@@ -694,10 +980,26 @@ static SYSCALL_TABLE: [SyscallEntry; 128] = {
         handler: Some(syscall_fs_list),
         name: b"fs_list\0".as_ptr() as *const c_char,
     };
+    table[SYSCALL_FS_GETDENTS as usize] = SyscallEntry {
+        handler: Some(syscall_fs_getdents),
+        name: c"fs_getdents".as_ptr(),
+    };
+    table[SYSCALL_FS_LSEEK as usize] = SyscallEntry {
+        handler: Some(syscall_fs_lseek),
+        name: c"fs_lseek".as_ptr(),
+    };
+    table[SYSCALL_FS_PIPE as usize] = SyscallEntry {
+        handler: Some(syscall_fs_pipe),
+        name: c"fs_pipe".as_ptr(),
+    };
     table[SYSCALL_SYS_INFO as usize] = SyscallEntry {
         handler: Some(syscall_sys_info),
         name: b"sys_info\0".as_ptr() as *const c_char,
     };
+    table[SYSCALL_PCI_ENUMERATE as usize] = SyscallEntry {
+        handler: Some(syscall_pci_enumerate),
+        name: c"pci_enumerate".as_ptr(),
+    };
     table[SYSCALL_HALT as usize] = SyscallEntry {
         handler: Some(syscall_halt),
         name: b"halt\0".as_ptr() as *const c_char,
@@ -778,6 +1080,50 @@ static SYSCALL_TABLE: [SyscallEntry; 128] = {
         handler: Some(syscall_mark_frames_done),
         name: b"mark_frames_done\0".as_ptr() as *const c_char,
     };
+    table[SYSCALL_SURFACE_FRAME_DONE as usize] = SyscallEntry {
+        handler: Some(syscall_surface_frame_done),
+        name: c"surface_frame_done".as_ptr(),
+    };
+    table[SYSCALL_SURFACE_ATTACH_BACK as usize] = SyscallEntry {
+        handler: Some(syscall_surface_attach_back),
+        name: c"surface_attach_back".as_ptr(),
+    };
+    table[SYSCALL_SURFACE_COMMIT_SWAP as usize] = SyscallEntry {
+        handler: Some(syscall_surface_commit_swap),
+        name: c"surface_commit_swap".as_ptr(),
+    };
+    table[SYSCALL_SURFACE_BEGIN_FRAME as usize] = SyscallEntry {
+        handler: Some(syscall_surface_begin_frame),
+        name: c"surface_begin_frame".as_ptr(),
+    };
+    table[SYSCALL_SURFACE_END_FRAME as usize] = SyscallEntry {
+        handler: Some(syscall_surface_end_frame),
+        name: c"surface_end_frame".as_ptr(),
+    };
+    table[SYSCALL_SURFACE_ATTACH_SPARE as usize] = SyscallEntry {
+        handler: Some(syscall_surface_attach_spare),
+        name: c"surface_attach_spare".as_ptr(),
+    };
+    table[SYSCALL_SURFACE_RESIZE as usize] = SyscallEntry {
+        handler: Some(syscall_surface_resize),
+        name: c"surface_resize".as_ptr(),
+    };
+    table[SYSCALL_COMPOSITOR_CAPTURE as usize] = SyscallEntry {
+        handler: Some(syscall_compositor_capture),
+        name: c"compositor_capture".as_ptr(),
+    };
+    table[SYSCALL_SURFACE_THUMBNAIL as usize] = SyscallEntry {
+        handler: Some(syscall_surface_thumbnail),
+        name: c"surface_thumbnail".as_ptr(),
+    };
+    table[SYSCALL_SURFACE_BACK_BUFFER as usize] = SyscallEntry {
+        handler: Some(syscall_surface_back_buffer),
+        name: c"surface_back_buffer".as_ptr(),
+    };
+    table[SYSCALL_LIST_WINDOWS as usize] = SyscallEntry {
+        handler: Some(syscall_list_windows),
+        name: c"list_windows".as_ptr(),
+    };
     table[SYSCALL_SHM_GET_FORMATS as usize] = SyscallEntry {
         handler: Some(syscall_shm_get_formats),
         name: b"shm_get_formats\0".as_ptr() as *const c_char,
@@ -810,6 +1156,14 @@ static SYSCALL_TABLE: [SyscallEntry; 128] = {
         handler: Some(syscall_surface_set_title),
         name: b"surface_set_title\0".as_ptr() as *const c_char,
     };
+    table[SYSCALL_SURFACE_SET_COLOR_KEY as usize] = SyscallEntry {
+        handler: Some(syscall_surface_set_color_key),
+        name: c"surface_set_color_key".as_ptr(),
+    };
+    table[SYSCALL_SURFACE_SET_BLEND_MODE as usize] = SyscallEntry {
+        handler: Some(syscall_surface_set_blend_mode),
+        name: c"surface_set_blend_mode".as_ptr(),
+    };
     table[SYSCALL_INPUT_POLL as usize] = SyscallEntry {
         handler: Some(syscall_input_poll),
         name: b"input_poll\0".as_ptr() as *const c_char,
@@ -850,10 +1204,26 @@ static SYSCALL_TABLE: [SyscallEntry; 128] = {
         handler: Some(syscall_brk),
         name: b"brk\0".as_ptr() as *const c_char,
     };
+    table[SYSCALL_SBRK as usize] = SyscallEntry {
+        handler: Some(syscall_sbrk),
+        name: c"sbrk".as_ptr(),
+    };
+    table[SYSCALL_MMAP as usize] = SyscallEntry {
+        handler: Some(syscall_mmap),
+        name: c"mmap".as_ptr(),
+    };
+    table[SYSCALL_MUNMAP as usize] = SyscallEntry {
+        handler: Some(syscall_munmap),
+        name: c"munmap".as_ptr(),
+    };
     table[SYSCALL_FORK as usize] = SyscallEntry {
         handler: Some(syscall_fork),
         name: b"fork\0".as_ptr() as *const c_char,
     };
+    table[SYSCALL_WAITPID as usize] = SyscallEntry {
+        handler: Some(syscall_waitpid),
+        name: c"waitpid".as_ptr(),
+    };
     table[SYSCALL_GET_CPU_COUNT as usize] = SyscallEntry {
         handler: Some(syscall_get_cpu_count),
         name: b"get_cpu_count\0".as_ptr() as *const c_char,
@@ -884,3 +1254,60 @@ pub fn syscall_lookup(sysno: u64) -> *const SyscallEntry {
         entry as *const SyscallEntry
     }
 }
+
+/// Handlers installed at runtime by subsystems that live outside `core`.
+/// Consulted only for slots the static table leaves empty.
+static SYSCALL_REGISTRY: slopos_lib::IrqMutex<[Option<SyscallHandler>; SYSCALL_TABLE_SIZE]> =
+    slopos_lib::IrqMutex::new([None; SYSCALL_TABLE_SIZE]);
+
+pub fn register_syscall(sysno: u64, handler: SyscallHandler) -> Result<(), SyscallError> {
+    if (sysno as usize) >= SYSCALL_TABLE_SIZE {
+        return Err(SyscallError::InvalidNumber);
+    }
+    if !syscall_lookup(sysno).is_null() {
+        return Err(SyscallError::AlreadyRegistered);
+    }
+    let mut registry = SYSCALL_REGISTRY.lock();
+    let slot = &mut registry[sysno as usize];
+    if slot.is_some() {
+        return Err(SyscallError::AlreadyRegistered);
+    }
+    *slot = Some(handler);
+    Ok(())
+}
+
+pub fn unregister_syscall(sysno: u64) -> Result<(), SyscallError> {
+    if (sysno as usize) >= SYSCALL_TABLE_SIZE {
+        return Err(SyscallError::InvalidNumber);
+    }
+    match SYSCALL_REGISTRY.lock()[sysno as usize].take() {
+        Some(_) => Ok(()),
+        None => Err(SyscallError::NotRegistered),
+    }
+}
+
+/// Name of a built-in syscall, or `None` for unknown and runtime-registered
+/// numbers.
+pub fn syscall_name(sysno: u64) -> Option<&'static str> {
+    let entry = syscall_lookup(sysno);
+    if entry.is_null() {
+        return None;
+    }
+    let name = unsafe { (*entry).name };
+    if name.is_null() {
+        return None;
+    }
+    Some(unsafe { cstr_to_str(name) })
+}
+
+/// Resolve a syscall number to its handler, static table first.
+pub fn syscall_resolve(sysno: u64) -> Option<SyscallHandler> {
+    let entry = syscall_lookup(sysno);
+    if !entry.is_null() {
+        return unsafe { (*entry).handler };
+    }
+    if (sysno as usize) >= SYSCALL_TABLE_SIZE {
+        return None;
+    }
+    SYSCALL_REGISTRY.lock()[sysno as usize]
+}
@@ -116,6 +116,24 @@ macro_rules! define_syscall {
             $body
         }
     };
+
+    ($name:ident($ctx:ident, $args:ident) requires system $body:block) => {
+        pub fn $name(
+            task: *mut slopos_abi::task::Task,
+            frame: *mut slopos_lib::InterruptFrame,
+        ) -> $crate::syscall::common::SyscallDisposition {
+            #[allow(unused_variables)]
+            let Some($ctx) = $crate::syscall::context::SyscallContext::new(task, frame) else {
+                return $crate::syscall::common::syscall_return_err(frame, u64::MAX);
+            };
+            if let Err(disp) = $ctx.require_system() {
+                return disp;
+            }
+            #[allow(unused_variables)]
+            let $args = $ctx.args();
+            $body
+        }
+    };
 }
 
 #[macro_export]
@@ -7,5 +7,8 @@ pub mod fs;
 pub mod handlers;
 pub mod tests;
 
-pub use dispatch::syscall_handle;
-pub use handlers::register_spawn_task_callback;
+pub use dispatch::{
+    syscall_dispatch, syscall_handle, syscall_trace_disable, syscall_trace_enable,
+    syscall_trace_enabled, syscall_trace_set_sink,
+};
+pub use handlers::{register_spawn_task_callback, register_syscall, unregister_syscall};
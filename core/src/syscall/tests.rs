@@ -10,20 +10,28 @@
 //! IMPORTANT: Some of these tests are EXPECTED to fail initially.
 //! That's the point - they find real bugs in untested code paths.
 
+use alloc::string::String;
 use core::ffi::{c_char, c_void};
+use core::fmt::{self, Write};
 use core::ptr;
 
 use slopos_abi::task::{
     INVALID_TASK_ID, TASK_FLAG_KERNEL_MODE, TASK_STATE_BLOCKED, TASK_STATE_READY,
     TASK_STATE_TERMINATED, Task,
 };
-use slopos_lib::{InterruptFrame, klog_info, testing::TestResult};
+use slopos_lib::{InterruptFrame, IrqMutex, klog_info, testing::TestResult};
 
 use crate::scheduler::scheduler::{init_scheduler, scheduler_shutdown};
 use crate::scheduler::task::{
     init_task_manager, task_create, task_find_by_id, task_shutdown_all, task_terminate,
 };
-use crate::syscall::handlers::syscall_lookup;
+use crate::syscall::common::SyscallDisposition;
+use crate::syscall::dispatch::{
+    syscall_dispatch, syscall_trace_disable, syscall_trace_enable, syscall_trace_set_sink,
+};
+use crate::syscall::handlers::{register_syscall, syscall_lookup, unregister_syscall};
+use slopos_abi::error::SyscallError;
+use slopos_abi::syscall::{ENOSYS, SYSCALL_EXIT, SYSCALL_GET_CPU_COUNT};
 
 // =============================================================================
 // TEST HELPERS
@@ -135,6 +143,167 @@ pub fn test_syscall_lookup_valid() -> TestResult {
     TestResult::Pass
 }
 
+// =============================================================================
+// SYSCALL REGISTRY TESTS
+// =============================================================================
+
+/// Top slot of the table; no static handler lives there.
+const TEST_REGISTRY_SYSNO: u64 = 127;
+const TEST_REGISTRY_MAGIC: u64 = 0x5107_0B05;
+
+fn registry_test_handler(_task: *mut Task, frame: *mut InterruptFrame) -> SyscallDisposition {
+    unsafe {
+        (*frame).rax = TEST_REGISTRY_MAGIC;
+    }
+    SyscallDisposition::Ok
+}
+
+fn zeroed_frame() -> InterruptFrame {
+    unsafe { core::mem::zeroed() }
+}
+
+/// Test: a registered handler is reached through dispatch
+pub fn test_syscall_register_and_dispatch() -> TestResult {
+    if register_syscall(TEST_REGISTRY_SYSNO, registry_test_handler).is_err() {
+        klog_info!("SYSCALL_TEST: register_syscall rejected a free slot");
+        return TestResult::Fail;
+    }
+
+    let mut frame = zeroed_frame();
+    syscall_dispatch(TEST_REGISTRY_SYSNO, ptr::null_mut(), &mut frame);
+    let _ = unregister_syscall(TEST_REGISTRY_SYSNO);
+
+    if frame.rax != TEST_REGISTRY_MAGIC {
+        klog_info!(
+            "SYSCALL_TEST: registered handler not invoked (rax={:#x})",
+            frame.rax
+        );
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: duplicate registration is rejected, for both dynamic and static slots
+pub fn test_syscall_register_duplicate() -> TestResult {
+    if register_syscall(TEST_REGISTRY_SYSNO, registry_test_handler).is_err() {
+        klog_info!("SYSCALL_TEST: register_syscall rejected a free slot");
+        return TestResult::Fail;
+    }
+    let dup = register_syscall(TEST_REGISTRY_SYSNO, registry_test_handler);
+    let _ = unregister_syscall(TEST_REGISTRY_SYSNO);
+    if dup != Err(SyscallError::AlreadyRegistered) {
+        klog_info!("SYSCALL_TEST: BUG - duplicate registration accepted");
+        return TestResult::Fail;
+    }
+
+    if register_syscall(SYSCALL_EXIT, registry_test_handler) != Err(SyscallError::AlreadyRegistered)
+    {
+        klog_info!("SYSCALL_TEST: BUG - static SYSCALL_EXIT slot was overridable");
+        return TestResult::Fail;
+    }
+
+    if register_syscall(128, registry_test_handler) != Err(SyscallError::InvalidNumber) {
+        klog_info!("SYSCALL_TEST: BUG - out-of-range syscall number accepted");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: an unregistered number dispatches to -ENOSYS
+pub fn test_syscall_unregistered_enosys() -> TestResult {
+    let mut frame = zeroed_frame();
+    syscall_dispatch(TEST_REGISTRY_SYSNO, ptr::null_mut(), &mut frame);
+    if frame.rax != ENOSYS.wrapping_neg() {
+        klog_info!("SYSCALL_TEST: expected -ENOSYS, got {:#x}", frame.rax);
+        return TestResult::Fail;
+    }
+
+    let mut frame = zeroed_frame();
+    syscall_dispatch(u64::MAX, ptr::null_mut(), &mut frame);
+    if frame.rax != ENOSYS.wrapping_neg() {
+        klog_info!(
+            "SYSCALL_TEST: expected -ENOSYS for u64::MAX, got {:#x}",
+            frame.rax
+        );
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+// =============================================================================
+// SYSCALL TRACE TESTS
+// =============================================================================
+
+static TRACE_CAPTURE: IrqMutex<String> = IrqMutex::new(String::new());
+
+fn capture_trace_sink(args: fmt::Arguments<'_>) {
+    let mut buf = TRACE_CAPTURE.lock();
+    let _ = buf.write_fmt(args);
+    buf.push('\n');
+}
+
+/// Installs the capturing sink and restores klog tracing state on drop.
+struct TraceCapture;
+
+impl TraceCapture {
+    fn new() -> Self {
+        TRACE_CAPTURE.lock().clear();
+        syscall_trace_set_sink(Some(capture_trace_sink));
+        Self
+    }
+
+    fn take(&self) -> String {
+        core::mem::take(&mut *TRACE_CAPTURE.lock())
+    }
+}
+
+impl Drop for TraceCapture {
+    fn drop(&mut self) {
+        syscall_trace_disable();
+        syscall_trace_set_sink(None);
+    }
+}
+
+/// Test: dispatch emits nothing while tracing is disabled
+pub fn test_syscall_trace_disabled_silent() -> TestResult {
+    let capture = TraceCapture::new();
+    syscall_trace_disable();
+
+    let mut frame = zeroed_frame();
+    syscall_dispatch(SYSCALL_GET_CPU_COUNT, ptr::null_mut(), &mut frame);
+
+    let out = capture.take();
+    if !out.is_empty() {
+        klog_info!("SYSCALL_TEST: BUG - trace emitted while disabled: {}", out);
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+/// Test: an enabled trace names the syscall and reports its return value
+pub fn test_syscall_trace_enabled_logs_call() -> TestResult {
+    let capture = TraceCapture::new();
+    syscall_trace_enable();
+
+    let mut frame = zeroed_frame();
+    frame.rdi = 0xABCD;
+    syscall_dispatch(SYSCALL_GET_CPU_COUNT, ptr::null_mut(), &mut frame);
+    syscall_trace_disable();
+
+    let out = capture.take();
+    let mut expected_ret = String::new();
+    let _ = write!(expected_ret, "-> {}", frame.rax as i64);
+    if !out.contains("get_cpu_count") || !out.contains(expected_ret.as_str()) {
+        klog_info!("SYSCALL_TEST: unexpected trace line: {}", out);
+        return TestResult::Fail;
+    }
+    if !out.contains("0xabcd") {
+        klog_info!("SYSCALL_TEST: trace missing arguments: {}", out);
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
 // =============================================================================
 // FORK EDGE CASE TESTS
 // =============================================================================
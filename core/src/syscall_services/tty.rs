@@ -4,6 +4,7 @@ slopos_lib::define_service! {
         read_char_blocking(buf: *mut u8) -> i32;
         set_focus(target: u32) -> i32;
         get_focus() -> u32;
+        set_mode(task_id: u32, canonical: bool) -> i32;
     }
 }
 
@@ -26,3 +27,8 @@ pub fn tty_set_focus(target: u32) -> i32 {
 pub fn tty_get_focus() -> u32 {
     get_focus()
 }
+
+#[inline(always)]
+pub fn tty_set_mode(task_id: u32, canonical: bool) -> i32 {
+    set_mode(task_id, canonical)
+}
@@ -2,7 +2,7 @@ use core::sync::atomic::{AtomicU64, Ordering};
 
 use spin::Once;
 
-use slopos_lib::{InitFlag, cpu, klog_debug, klog_info};
+use slopos_lib::{InitFlag, cpu, klog_debug_mod, klog_info};
 
 use slopos_abi::addr::PhysAddr;
 use slopos_abi::arch::x86_64::cpuid::{CPUID_FEAT_ECX_X2APIC, CPUID_FEAT_EDX_APIC};
@@ -28,11 +28,11 @@ static APIC_BASE_PHYSICAL: AtomicU64 = AtomicU64::new(0);
 static APIC_REGS: Once<MmioRegion> = Once::new();
 
 pub fn detect() -> bool {
-    klog_debug!("APIC: Detecting Local APIC availability...");
+    klog_debug_mod!("drivers", "APIC: Detecting Local APIC availability...");
 
     let (_, _, ecx, edx) = cpu::cpuid(1);
     if edx & CPUID_FEAT_EDX_APIC == 0 {
-        klog_debug!("APIC: Local APIC is not available");
+        klog_debug_mod!("drivers", "APIC: Local APIC is not available");
         APIC_AVAILABLE.reset();
         return false;
     }
@@ -68,12 +68,19 @@ pub fn detect() -> bool {
             } else {
                 ""
             };
-            klog_debug!(
+            klog_debug_mod!(
+                "drivers",
                 "APIC: Physical base: 0x{:x}, Virtual base (HHDM): 0x{:x}",
                 apic_phys,
                 virt
             );
-            klog_debug!("APIC: MSR flags:{}{}{}", bsp_flag, x2apic_flag, enable_flag);
+            klog_debug_mod!(
+                "drivers",
+                "APIC: MSR flags:{}{}{}",
+                bsp_flag,
+                x2apic_flag,
+                enable_flag
+            );
             true
         }
         None => {
@@ -90,7 +97,7 @@ pub fn init() -> i32 {
         return -1;
     }
 
-    klog_debug!("APIC: Initializing Local APIC");
+    klog_debug_mod!("drivers", "APIC: Initializing Local APIC");
 
     slopos_lib::register_lapic_id_fn(get_id);
     slopos_lib::register_send_ipi_to_cpu_fn(send_ipi_to_cpu);
@@ -99,7 +106,7 @@ pub fn init() -> i32 {
     if apic_base_msr & APIC_BASE_GLOBAL_ENABLE == 0 {
         apic_base_msr |= APIC_BASE_GLOBAL_ENABLE;
         cpu::write_msr(MSR_APIC_BASE, apic_base_msr);
-        klog_debug!("APIC: Enabled APIC globally via MSR");
+        klog_debug_mod!("drivers", "APIC: Enabled APIC globally via MSR");
     }
 
     enable();
@@ -119,10 +126,15 @@ pub fn init() -> i32 {
 
     let apic_id = get_id();
     let apic_version = get_version();
-    klog_debug!("APIC: ID: 0x{:x}, Version: 0x{:x}", apic_id, apic_version);
+    klog_debug_mod!(
+        "drivers",
+        "APIC: ID: 0x{:x}, Version: 0x{:x}",
+        apic_id,
+        apic_version
+    );
 
     APIC_ENABLED.mark_set();
-    klog_debug!("APIC: Initialization complete");
+    klog_debug_mod!("drivers", "APIC: Initialization complete");
     0
 }
 
@@ -155,7 +167,7 @@ pub fn enable() {
     spurious |= 0xFF;
     write_register(LAPIC_SPURIOUS, spurious);
     APIC_ENABLED.mark_set();
-    klog_debug!("APIC: Local APIC enabled");
+    klog_debug_mod!("drivers", "APIC: Local APIC enabled");
 }
 
 pub fn disable() {
@@ -166,7 +178,7 @@ pub fn disable() {
     spurious &= !LAPIC_SPURIOUS_ENABLE;
     write_register(LAPIC_SPURIOUS, spurious);
     APIC_ENABLED.reset();
-    klog_debug!("APIC: Local APIC disabled");
+    klog_debug_mod!("drivers", "APIC: Local APIC disabled");
 }
 
 pub fn send_eoi() {
@@ -194,7 +206,8 @@ pub fn timer_init(vector: u32, frequency: u32) {
     if !is_enabled() {
         return;
     }
-    klog_debug!(
+    klog_debug_mod!(
+        "drivers",
         "APIC: Initializing timer with vector 0x{:x} and frequency {}",
         vector,
         frequency
@@ -207,7 +220,7 @@ pub fn timer_init(vector: u32, frequency: u32) {
 
     let initial_count = 1_000_000u32.saturating_div(frequency.max(1));
     timer_start(initial_count);
-    klog_debug!("APIC: Timer initialized");
+    klog_debug_mod!("drivers", "APIC: Timer initialized");
 }
 
 pub fn timer_start(initial_count: u32) {
@@ -276,7 +289,7 @@ pub fn send_ipi_halt_all() {
         timeout -= 1;
     }
 
-    klog_debug!("APIC: Sent shutdown IPI to all processors");
+    klog_debug_mod!("drivers", "APIC: Sent shutdown IPI to all processors");
 }
 
 pub fn get_base_address() -> u64 {
@@ -460,6 +473,48 @@ pub fn send_ipi_all_excluding_self(vector: u8) {
     }
 }
 
+/// Wait for the previous IPI to leave the ICR. Returns false on timeout.
+fn icr_wait_idle() -> bool {
+    let mut timeout = 10000;
+    while (read_register(LAPIC_ICR_LOW) & LAPIC_ICR_DELIVERY_STATUS) != 0 && timeout > 0 {
+        cpu::pause();
+        timeout -= 1;
+    }
+    timeout > 0
+}
+
+fn send_icr(target_apic_id: u32, icr_low: u32) -> bool {
+    if !icr_wait_idle() {
+        return false;
+    }
+    write_register(LAPIC_ICR_HIGH, target_apic_id << 24);
+    write_register(LAPIC_ICR_LOW, icr_low);
+    icr_wait_idle()
+}
+
+/// Send INIT (assert, then de-assert) to put a CPU into wait-for-SIPI.
+pub fn send_init_ipi(target_apic_id: u32) -> bool {
+    if !is_available() || !is_enabled() {
+        return false;
+    }
+    let init = LAPIC_ICR_DELIVERY_INIT | LAPIC_ICR_DEST_PHYSICAL | LAPIC_ICR_TRIGGER_LEVEL;
+    send_icr(target_apic_id, init | LAPIC_ICR_LEVEL_ASSERT)
+        && send_icr(target_apic_id, init | LAPIC_ICR_LEVEL_DEASSERT)
+}
+
+/// Send a start-up IPI; the CPU begins real-mode execution at `vector << 12`.
+pub fn send_startup_ipi(target_apic_id: u32, vector: u8) -> bool {
+    if !is_available() || !is_enabled() {
+        return false;
+    }
+    let icr_low = (vector as u32)
+        | LAPIC_ICR_DELIVERY_STARTUP
+        | LAPIC_ICR_DEST_PHYSICAL
+        | LAPIC_ICR_LEVEL_ASSERT
+        | LAPIC_ICR_TRIGGER_EDGE;
+    send_icr(target_apic_id, icr_low)
+}
+
 pub fn send_ipi_to_cpu(target_apic_id: u32, vector: u8) {
     if !is_available() || !is_enabled() {
         return;
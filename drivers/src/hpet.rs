@@ -0,0 +1,188 @@
+//! High Precision Event Timer.
+//!
+//! The HPET main counter ticks at a fixed rate, reported in femtoseconds per
+//! tick by the capabilities register. It is much finer than the PIT and is
+//! used as the reference when calibrating the TSC. The register block is
+//! located through the ACPI `HPET` table only: probing a guessed address on
+//! a machine without one can hit an unrelated device. Without the table, or
+//! when no valid timer answers, callers keep using the PIT.
+//!
+//! The main counter may be 32 bits wide. Its upper half then reads as zero,
+//! so counter values are masked to the implemented width and intervals are
+//! measured modulo that width.
+
+use core::hint::spin_loop;
+use core::ptr::read_unaligned;
+
+use spin::Once;
+
+use slopos_abi::addr::PhysAddr;
+use slopos_lib::{klog_info, tsc};
+use slopos_mm::mmio::MmioRegion;
+
+use crate::ioapic::acpi_lookup_table;
+
+pub const FS_PER_SEC: u64 = 1_000_000_000_000_000;
+const FS_PER_NS: u64 = 1_000_000;
+
+/// Longest counter period the specification allows (100 ns).
+const HPET_MAX_PERIOD_FS: u64 = 100_000_000;
+
+const HPET_REGION_SIZE: usize = 0x400;
+const HPET_REG_CAPABILITIES: usize = 0x000;
+const HPET_REG_CONFIG: usize = 0x010;
+const HPET_REG_MAIN_COUNTER: usize = 0x0F0;
+const HPET_CONFIG_ENABLE: u64 = 1 << 0;
+/// Capabilities bit set when the main counter is 64 bits wide.
+const HPET_CAP_COUNT_SIZE_64: u64 = 1 << 13;
+
+/// Offset of the base address within the ACPI `HPET` table: the 36-byte
+/// SDT header, the event timer block ID, then the address field of a
+/// generic address structure.
+const HPET_ACPI_ADDRESS_OFFSET: usize = 44;
+
+struct Hpet {
+    regs: MmioRegion,
+    period_fs: u64,
+    counter_mask: u64,
+}
+
+static HPET: Once<Hpet> = Once::new();
+
+/// Counter period in the capabilities register, or None if it is out of
+/// spec (an absent device reads as all zeroes or all ones).
+pub fn hpet_period_from_caps(caps: u64) -> Option<u64> {
+    let period_fs = caps >> 32;
+    (period_fs != 0 && period_fs <= HPET_MAX_PERIOD_FS).then_some(period_fs)
+}
+
+/// Mask covering the implemented width of the main counter.
+pub fn hpet_counter_mask_from_caps(caps: u64) -> u64 {
+    if caps & HPET_CAP_COUNT_SIZE_64 != 0 {
+        u64::MAX
+    } else {
+        u32::MAX as u64
+    }
+}
+
+/// Ticks from `start` to `end` on a counter of `mask` width, correct across
+/// one wrap of the counter.
+pub fn hpet_ticks_between(start: u64, end: u64, mask: u64) -> u64 {
+    end.wrapping_sub(start) & mask
+}
+
+/// TSC frequency implied by `cycles` elapsing over `hpet_ticks` counter
+/// ticks of `period_fs` each. Returns 0 for an empty interval.
+pub fn tsc_hz_from_hpet(cycles: u64, hpet_ticks: u64, period_fs: u64) -> u64 {
+    let elapsed_fs = hpet_ticks as u128 * period_fs as u128;
+    if elapsed_fs == 0 {
+        return 0;
+    }
+    let hz = cycles as u128 * FS_PER_SEC as u128 / elapsed_fs;
+    hz.min(u64::MAX as u128) as u64
+}
+
+fn hpet_acpi_base() -> Option<u64> {
+    let table = acpi_lookup_table(b"HPET");
+    if table.is_null() {
+        return None;
+    }
+    let length = unsafe { read_unaligned(table.add(4) as *const u32) } as usize;
+    if length < HPET_ACPI_ADDRESS_OFFSET + 8 {
+        return None;
+    }
+    let base = unsafe { read_unaligned(table.add(HPET_ACPI_ADDRESS_OFFSET) as *const u64) };
+    (base != 0).then_some(base)
+}
+
+/// Find, map and start the main counter. Returns false if there is no
+/// usable HPET.
+pub fn hpet_init() -> bool {
+    if HPET.is_completed() {
+        return true;
+    }
+
+    let Some(base) = hpet_acpi_base() else {
+        klog_info!("HPET: no ACPI table, staying on the PIT");
+        return false;
+    };
+    let Some(regs) = MmioRegion::map(PhysAddr::new(base), HPET_REGION_SIZE) else {
+        klog_info!("HPET: failed to map registers at 0x{:x}", base);
+        return false;
+    };
+    let caps = regs.read_u64(HPET_REG_CAPABILITIES);
+    let Some(period_fs) = hpet_period_from_caps(caps) else {
+        klog_info!("HPET: no timer at 0x{:x}, staying on the PIT", base);
+        return false;
+    };
+    let counter_mask = hpet_counter_mask_from_caps(caps);
+
+    let config = regs.read_u64(HPET_REG_CONFIG);
+    regs.write_u64(HPET_REG_CONFIG, config | HPET_CONFIG_ENABLE);
+    HPET.call_once(|| Hpet {
+        regs,
+        period_fs,
+        counter_mask,
+    });
+
+    klog_info!(
+        "HPET: base 0x{:x}, {} Hz {}-bit counter",
+        base,
+        FS_PER_SEC / period_fs,
+        counter_mask.count_ones()
+    );
+    true
+}
+
+pub fn hpet_is_available() -> bool {
+    HPET.is_completed()
+}
+
+/// Current main counter value, masked to the counter's width, or 0 without
+/// an HPET.
+pub fn hpet_now() -> u64 {
+    HPET.get().map_or(0, |hpet| {
+        hpet.regs.read_u64(HPET_REG_MAIN_COUNTER) & hpet.counter_mask
+    })
+}
+
+/// Mask covering the main counter's width, or 0 without an HPET.
+pub fn hpet_counter_mask() -> u64 {
+    HPET.get().map_or(0, |hpet| hpet.counter_mask)
+}
+
+/// Femtoseconds per main counter tick, or 0 without an HPET.
+pub fn hpet_period_fs() -> u64 {
+    HPET.get().map_or(0, |hpet| hpet.period_fs)
+}
+
+/// Busy-wait `interval_ms` on the HPET and count the TSC cycles that pass.
+/// Returns (cycles, elapsed nanoseconds), or None without an HPET or when
+/// the interval is longer than half a counter wrap.
+pub fn hpet_measure_tsc(interval_ms: u32) -> Option<(u64, u64)> {
+    let period_fs = hpet_period_fs();
+    if period_fs == 0 {
+        return None;
+    }
+    let mask = hpet_counter_mask();
+    let wait_ticks = interval_ms as u64 * (FS_PER_SEC / 1_000) / period_fs;
+    if wait_ticks > mask / 2 {
+        return None;
+    }
+
+    let start = hpet_now();
+    let tsc_start = tsc::rdtsc();
+    // Keep the HPET read that ended the wait; re-reading after the closing
+    // rdtsc would skew the interval by one MMIO access.
+    let elapsed = loop {
+        let elapsed = hpet_ticks_between(start, hpet_now(), mask);
+        if elapsed >= wait_ticks {
+            break elapsed;
+        }
+        spin_loop();
+    };
+    let tsc_end = tsc::rdtsc();
+
+    let elapsed_ns = (elapsed as u128 * period_fs as u128 / FS_PER_NS as u128) as u64;
+    Some((tsc_end.wrapping_sub(tsc_start), elapsed_ns))
+}
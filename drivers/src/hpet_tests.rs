@@ -0,0 +1,88 @@
+//! HPET tests - period validation and TSC calibration against the counter.
+
+use slopos_lib::clock::{NS_PER_SEC, clock_tsc_hz};
+use slopos_lib::testing::TestResult;
+use slopos_lib::{assert_eq_test, assert_test, klog_info};
+
+use crate::hpet::{
+    hpet_counter_mask_from_caps, hpet_is_available, hpet_measure_tsc, hpet_period_from_caps,
+    hpet_ticks_between, tsc_hz_from_hpet,
+};
+
+/// Plausible TSC range for any CPU this kernel runs on, emulated or not.
+const TSC_MHZ_MIN: u64 = 50;
+const TSC_MHZ_MAX: u64 = 20_000;
+
+pub fn test_hpet_period_validation() -> TestResult {
+    assert_eq_test!(hpet_period_from_caps(0), None, "absent device reads zero");
+    assert_eq_test!(
+        hpet_period_from_caps(u64::MAX),
+        None,
+        "floating bus reads ones"
+    );
+    assert_eq_test!(
+        hpet_period_from_caps(10_000_000u64 << 32 | 0x8086_A201),
+        Some(10_000_000)
+    );
+    assert_eq_test!(hpet_period_from_caps(100_000_001u64 << 32), None);
+    TestResult::Pass
+}
+
+pub fn test_hpet_counter_wrap() -> TestResult {
+    let narrow = hpet_counter_mask_from_caps(10_000_000u64 << 32);
+    let wide = hpet_counter_mask_from_caps(10_000_000u64 << 32 | 1 << 13);
+    assert_eq_test!(narrow, 0xFFFF_FFFF, "COUNT_SIZE_CAP clear is 32-bit");
+    assert_eq_test!(wide, u64::MAX);
+    assert_eq_test!(
+        hpet_ticks_between(0xFFFF_FFF0, 0x10, narrow),
+        0x20,
+        "32-bit counter wraps to zero"
+    );
+    assert_eq_test!(hpet_ticks_between(0x10, 0x30, narrow), 0x20);
+    assert_eq_test!(hpet_ticks_between(u64::MAX - 0xF, 0x10, wide), 0x20);
+    TestResult::Pass
+}
+
+pub fn test_hpet_tsc_hz_conversion() -> TestResult {
+    // 100 MHz counter: 1_000_000 ticks is 10 ms.
+    assert_eq_test!(
+        tsc_hz_from_hpet(30_000_000, 1_000_000, 10_000_000),
+        3_000_000_000
+    );
+    // 14.318 MHz legacy rate, 69_841_279 fs per tick.
+    let hz = tsc_hz_from_hpet(24_000_000, 143_182, 69_841_279);
+    assert_test!(
+        hz.abs_diff(2_400_000_000) < 1_000_000,
+        "2.4 GHz within 1 MHz"
+    );
+    assert_eq_test!(tsc_hz_from_hpet(1_000, 0, 10_000_000), 0, "empty interval");
+    TestResult::Pass
+}
+
+pub fn test_hpet_tsc_calibration_plausible() -> TestResult {
+    if !hpet_is_available() {
+        return TestResult::Skipped;
+    }
+    let Some((cycles, elapsed_ns)) = hpet_measure_tsc(10) else {
+        return TestResult::Fail;
+    };
+    assert_test!(elapsed_ns >= 9_000_000, "waited out the interval");
+
+    let hz = (cycles as u128 * NS_PER_SEC as u128 / elapsed_ns as u128) as u64;
+    let mhz = hz / 1_000_000;
+    klog_info!("HPET_TEST: TSC measured at {} MHz", mhz);
+    assert_test!(
+        (TSC_MHZ_MIN..=TSC_MHZ_MAX).contains(&mhz),
+        "TSC frequency is plausible"
+    );
+
+    // Against the boot-time figure, allowing for emulator jitter.
+    let boot_hz = clock_tsc_hz();
+    if boot_hz != 0 {
+        assert_test!(
+            hz.abs_diff(boot_hz) <= boot_hz / 4,
+            "agrees with boot calibration within 25%"
+        );
+    }
+    TestResult::Pass
+}
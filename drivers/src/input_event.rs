@@ -23,7 +23,8 @@ pub fn get_timestamp_ms() -> u64 {
 
 // Re-export ABI types and constants for consumers
 pub use slopos_abi::{
-    InputEvent, InputEventData, InputEventType, MAX_EVENTS_PER_TASK, MAX_INPUT_TASKS,
+    InputEvent, InputEventData, InputEventType, InputOverflowPolicy, MAX_EVENTS_PER_TASK,
+    MAX_INPUT_TASKS,
 };
 
 /// Extension trait for InputEvent construction methods
@@ -144,6 +145,8 @@ struct TaskEventQueue {
     tail: usize,
     /// Number of events in queue
     count: usize,
+    /// What happens to new events once the queue is full
+    overflow: InputOverflowPolicy,
 }
 
 impl TaskEventQueue {
@@ -160,18 +163,26 @@ impl TaskEventQueue {
             head: 0,
             tail: 0,
             count: 0,
+            overflow: InputOverflowPolicy::DropOldest,
         }
     }
 
-    fn push(&mut self, event: InputEvent) {
+    /// Queue an event. Returns false if the queue was full and the policy
+    /// discarded the new event.
+    fn push(&mut self, event: InputEvent) -> bool {
         if self.count >= MAX_EVENTS_PER_TASK {
-            // Drop oldest event
-            self.tail = (self.tail + 1) % MAX_EVENTS_PER_TASK;
-            self.count -= 1;
+            match self.overflow {
+                InputOverflowPolicy::DropOldest => {
+                    self.tail = (self.tail + 1) % MAX_EVENTS_PER_TASK;
+                    self.count -= 1;
+                }
+                InputOverflowPolicy::DropNewest => return false,
+            }
         }
         self.events[self.head] = event;
         self.head = (self.head + 1) % MAX_EVENTS_PER_TASK;
         self.count += 1;
+        true
     }
 
     fn pop(&mut self) -> Option<InputEvent> {
@@ -254,6 +265,7 @@ impl InputManager {
             if !queue.active {
                 queue.task_id = task_id;
                 queue.active = true;
+                queue.overflow = InputOverflowPolicy::default();
                 queue.clear();
                 return Some(i);
             }
@@ -371,7 +383,7 @@ pub fn input_route_key_event(scancode: u8, ascii: u8, pressed: bool, timestamp_m
         } else {
             InputEventType::KeyRelease
         };
-        mgr.queues[idx].push(InputEvent::key(event_type, scancode, ascii, timestamp_ms));
+        let _ = mgr.queues[idx].push(InputEvent::key(event_type, scancode, ascii, timestamp_ms));
     }
 }
 
@@ -391,7 +403,7 @@ pub fn input_route_pointer_motion(x: i32, y: i32, timestamp_ms: u64) {
     let local_y = y - mgr.window_offset_y;
 
     if let Some(idx) = mgr.find_or_create_queue(focus) {
-        mgr.queues[idx].push(InputEvent::pointer_motion(local_x, local_y, timestamp_ms));
+        let _ = mgr.queues[idx].push(InputEvent::pointer_motion(local_x, local_y, timestamp_ms));
     }
 }
 
@@ -411,7 +423,32 @@ pub fn input_route_pointer_button(button: u8, pressed: bool, timestamp_ms: u64)
     }
 
     if let Some(idx) = mgr.find_or_create_queue(focus) {
-        mgr.queues[idx].push(InputEvent::pointer_button(pressed, button, timestamp_ms));
+        let _ = mgr.queues[idx].push(InputEvent::pointer_button(pressed, button, timestamp_ms));
+    }
+}
+
+/// Queue an event for a task directly, bypassing focus.
+///
+/// Returns false if no queue slot is free or the task's queue is full and
+/// set to `DropNewest`.
+pub fn input_push(task_id: u32, event: InputEvent) -> bool {
+    let mut mgr = INPUT_MANAGER.lock();
+    match mgr.find_or_create_queue(task_id) {
+        Some(idx) => mgr.queues[idx].push(event),
+        None => false,
+    }
+}
+
+/// Choose what a task's queue does when full (default `DropOldest`).
+/// Returns false if no queue slot is free.
+pub fn input_set_overflow_policy(task_id: u32, policy: InputOverflowPolicy) -> bool {
+    let mut mgr = INPUT_MANAGER.lock();
+    match mgr.find_or_create_queue(task_id) {
+        Some(idx) => {
+            mgr.queues[idx].overflow = policy;
+            true
+        }
+        None => false,
     }
 }
 
@@ -3,7 +3,7 @@ use core::mem;
 use core::ptr::read_unaligned;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
-use slopos_lib::{InitFlag, StateFlag, klog_debug, klog_info};
+use slopos_lib::{InitFlag, StateFlag, klog_debug_mod, klog_info};
 
 use slopos_abi::addr::PhysAddr;
 use slopos_abi::arch::x86_64::ioapic::*;
@@ -304,6 +304,20 @@ fn acpi_find_table(rsdp: *const AcpiRsdp, signature: &[u8; 4]) -> *const AcpiSdt
     core::ptr::null()
 }
 
+/// Look up a checksummed ACPI table by signature through the boot RSDP.
+/// Returns a pointer to the table header, or null when ACPI is unavailable
+/// or the table is absent.
+pub(crate) fn acpi_lookup_table(signature: &[u8; 4]) -> *const u8 {
+    if !hhdm::is_available() || !platform::is_rsdp_available() {
+        return core::ptr::null();
+    }
+    let rsdp = platform::get_rsdp_address() as *const AcpiRsdp;
+    if !acpi_validate_rsdp(rsdp) {
+        return core::ptr::null();
+    }
+    acpi_find_table(rsdp, signature) as *const u8
+}
+
 fn ioapic_find_controller(gsi: u32) -> Option<*mut IoapicController> {
     unsafe {
         let base_ptr = IOAPIC_TABLE.ptr();
@@ -342,7 +356,8 @@ fn ioapic_log_controller(ctrl: &IoapicController) {
 }
 
 fn ioapic_log_iso(iso: &IoapicIso) {
-    klog_debug!(
+    klog_debug_mod!(
+        "drivers",
         "IOAPIC: ISO bus {}, IRQ {} -> GSI {}, flags 0x{:x}",
         iso.bus_source,
         iso.irq_source,
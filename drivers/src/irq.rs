@@ -9,16 +9,20 @@ use slopos_core::irq::{
     self, LEGACY_IRQ_COM1, LEGACY_IRQ_KEYBOARD, LEGACY_IRQ_MOUSE, LEGACY_IRQ_TIMER,
 };
 use slopos_core::sched::scheduler_timer_tick;
-use slopos_lib::{InterruptFrame, cpu, klog_debug, klog_info};
+use slopos_core::scheduler_request_reschedule_from_interrupt;
+use slopos_lib::ports::COM1;
+use slopos_lib::{InterruptFrame, cpu, klog_debug_mod, klog_info};
 
-use crate::{apic, ioapic, ps2};
+use crate::tty::tty_notify_input_ready;
+use crate::{apic, ioapic, ps2, serial};
 
 extern "C" fn timer_irq_handler(_irq: u8, _frame: *mut InterruptFrame, _ctx: *mut c_void) {
     irq::increment_timer_ticks();
     let tick = irq::get_timer_ticks();
     if tick <= 3 {
-        klog_debug!("IRQ: Timer tick #{}", tick);
+        klog_debug_mod!("drivers", "IRQ: Timer tick #{}", tick);
     }
+    ps2::keyboard::handle_timer_tick(tick);
     scheduler_timer_tick();
 }
 
@@ -39,6 +43,14 @@ extern "C" fn mouse_irq_handler(_irq: u8, _frame: *mut InterruptFrame, _ctx: *mu
     ps2::mouse::handle_irq(data);
 }
 
+extern "C" fn serial_irq_handler(_irq: u8, _frame: *mut InterruptFrame, _ctx: *mut c_void) {
+    serial::serial_poll_receive(COM1.address());
+    if serial::serial_buffer_pending(COM1.address()) != 0 {
+        tty_notify_input_ready();
+        scheduler_request_reschedule_from_interrupt();
+    }
+}
+
 fn program_ioapic_route(irq_line: u8) {
     if irq_line as usize >= irq::IRQ_LINES {
         return;
@@ -130,6 +142,13 @@ pub fn init() {
         core::ptr::null_mut(),
         core::ptr::null(),
     );
+    let _ = irq::register_handler(
+        LEGACY_IRQ_COM1,
+        Some(serial_irq_handler),
+        core::ptr::null_mut(),
+        core::ptr::null(),
+    );
+    serial::serial_enable_rx_interrupt();
 
     cpu::enable_interrupts();
 }
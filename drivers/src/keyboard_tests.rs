@@ -0,0 +1,312 @@
+//! PS/2 keyboard tests - modifier tracking and key event decoding.
+
+use core::ptr;
+
+use slopos_abi::syscall::{SYSCALL_KEYBOARD_POLL_EVENT, SYSCALL_KEYBOARD_SET_LAYOUT};
+use slopos_core::syscall::handlers::{syscall_name, syscall_resolve};
+use slopos_lib::testing::TestResult;
+use slopos_lib::{assert_eq_test, assert_test};
+
+use crate::keyboard::{
+    AutoRepeat, KeyDecoder, KeyEvent, MOD_ALTGR, MOD_CAPS_LOCK, MOD_CTRL, MOD_SHIFT, RepeatTiming,
+    getchar, handle_scancode, keyboard_layout, keyboard_poll_event, keyboard_set_layout,
+    keyboard_set_layout_by_name,
+};
+use crate::ps2::layout::{LAYOUT_DE, LAYOUT_UK, LAYOUT_US, layout_by_name};
+
+const SC_LSHIFT: u8 = 0x2A;
+const SC_CTRL: u8 = 0x1D;
+const SC_CAPS: u8 = 0x3A;
+const SC_A: u8 = 0x1E;
+const SC_1: u8 = 0x02;
+const SC_Y_US: u8 = 0x15;
+const SC_Z_US: u8 = 0x2C;
+const SC_RALT: u8 = 0x38;
+const BREAK: u8 = 0x80;
+
+/// Press and release `make`, returning the press event.
+fn tap(decoder: &mut KeyDecoder, make: u8) -> Option<KeyEvent> {
+    let press = decoder.feed(make);
+    decoder.feed(make | BREAK);
+    press
+}
+
+pub fn test_keyboard_shift_uppercases_letter() -> TestResult {
+    let mut decoder = KeyDecoder::new();
+    let shift = decoder.feed(SC_LSHIFT);
+    assert_test!(shift.is_some_and(|e| e.pressed && e.modifiers == MOD_SHIFT));
+
+    assert_eq_test!(
+        decoder.feed(SC_A),
+        Some(KeyEvent {
+            scancode: SC_A,
+            ascii: b'A',
+            modifiers: MOD_SHIFT,
+            pressed: true,
+            extended: false,
+        })
+    );
+    assert_eq_test!(
+        decoder.feed(SC_A | BREAK),
+        Some(KeyEvent {
+            scancode: SC_A | BREAK,
+            ascii: b'A',
+            modifiers: MOD_SHIFT,
+            pressed: false,
+            extended: false,
+        })
+    );
+
+    let release = decoder.feed(SC_LSHIFT | BREAK);
+    assert_test!(release.is_some_and(|e| !e.pressed && e.modifiers == 0));
+    assert_eq_test!(tap(&mut decoder, SC_A).map(|e| e.ascii), Some(b'a'));
+    TestResult::Pass
+}
+
+pub fn test_keyboard_caps_lock_letters_only() -> TestResult {
+    let mut decoder = KeyDecoder::new();
+    tap(&mut decoder, SC_CAPS);
+    let a = tap(&mut decoder, SC_A);
+    assert_test!(a.is_some_and(|e| e.ascii == b'A' && e.modifiers == MOD_CAPS_LOCK));
+    assert_eq_test!(
+        tap(&mut decoder, SC_1).map(|e| e.ascii),
+        Some(b'1'),
+        "caps lock leaves digits alone"
+    );
+
+    decoder.feed(SC_LSHIFT);
+    assert_eq_test!(
+        tap(&mut decoder, SC_A).map(|e| e.ascii),
+        Some(b'a'),
+        "shift cancels caps lock"
+    );
+    assert_eq_test!(tap(&mut decoder, SC_1).map(|e| e.ascii), Some(b'!'));
+    decoder.feed(SC_LSHIFT | BREAK);
+
+    tap(&mut decoder, SC_CAPS);
+    assert_eq_test!(tap(&mut decoder, SC_A).map(|e| e.modifiers), Some(0));
+    TestResult::Pass
+}
+
+pub fn test_keyboard_ctrl_and_prefix() -> TestResult {
+    let mut decoder = KeyDecoder::new();
+    assert_eq_test!(decoder.feed(0xE0), None, "prefix is not a key");
+    let ctrl = decoder.feed(SC_CTRL);
+    assert_test!(
+        ctrl.is_some_and(|e| e.extended),
+        "key after 0xE0 is extended"
+    );
+    let ctrl_a = tap(&mut decoder, SC_A);
+    assert_test!(ctrl_a.is_some_and(|e| e.ascii == 0x01 && e.modifiers == MOD_CTRL));
+    assert_test!(ctrl_a.is_some_and(|e| !e.extended), "prefix covers one key");
+    decoder.feed(SC_CTRL);
+    decoder.feed(SC_CTRL | BREAK);
+    let still_ctrl = tap(&mut decoder, SC_A);
+    assert_test!(
+        still_ctrl.is_some_and(|e| e.modifiers == MOD_CTRL),
+        "releasing left Ctrl keeps right Ctrl held"
+    );
+    decoder.feed(0xE0);
+    decoder.feed(SC_CTRL | BREAK);
+    let plain = tap(&mut decoder, SC_A);
+    assert_test!(plain.is_some_and(|e| e.ascii == b'a' && e.modifiers == 0));
+    TestResult::Pass
+}
+
+const TIMING: RepeatTiming = RepeatTiming {
+    delay_ticks: 50,
+    interval_ticks: 5,
+};
+
+fn feed_repeat(decoder: &mut KeyDecoder, repeat: &mut AutoRepeat, scancode: u8, now: u64) -> bool {
+    decoder
+        .feed(scancode)
+        .is_some_and(|event| repeat.key(&event, now, TIMING))
+}
+
+fn count_repeats(repeat: &mut AutoRepeat, from: u64, to: u64) -> u32 {
+    let decoder = KeyDecoder::new();
+    (from..=to)
+        .filter(|&tick| repeat.poll(tick, &decoder).is_some())
+        .count() as u32
+}
+
+pub fn test_keyboard_repeat_after_delay() -> TestResult {
+    let mut decoder = KeyDecoder::new();
+    let mut repeat = AutoRepeat::new();
+    let Some(press) = decoder.feed(SC_A) else {
+        return TestResult::Fail;
+    };
+    assert_test!(repeat.key(&press, 100, TIMING));
+
+    assert_eq_test!(
+        count_repeats(&mut repeat, 100, 149),
+        0,
+        "still in the delay"
+    );
+    // Delay ends at 150; repeats at 150, 155, ..., 170.
+    assert_eq_test!(count_repeats(&mut repeat, 150, 172), 5);
+    assert_eq_test!(
+        repeat.poll(175, &decoder),
+        Some(press),
+        "repeats carry the key"
+    );
+
+    // The keyboard's own typematic make is swallowed while we repeat.
+    assert_test!(!repeat.key(&press, 176, TIMING));
+
+    let Some(release) = decoder.feed(SC_A | BREAK) else {
+        return TestResult::Fail;
+    };
+    assert_test!(repeat.key(&release, 177, TIMING));
+    assert_eq_test!(count_repeats(&mut repeat, 177, 300), 0, "release cancels");
+    TestResult::Pass
+}
+
+pub fn test_keyboard_repeat_cancel_and_modifiers() -> TestResult {
+    let d = &mut KeyDecoder::new();
+    let r = &mut AutoRepeat::new();
+
+    // Pure modifiers never repeat.
+    assert_test!(feed_repeat(d, r, SC_LSHIFT, 0));
+    assert_test!(feed_repeat(d, r, SC_CTRL, 0));
+    feed_repeat(d, r, SC_CTRL | BREAK, 0);
+    feed_repeat(d, r, SC_LSHIFT | BREAK, 0);
+    assert_eq_test!(count_repeats(r, 0, 200), 0);
+
+    // A second key takes over; releasing the first one no longer matters.
+    feed_repeat(d, r, SC_A, 200);
+    feed_repeat(d, r, SC_1, 220);
+    feed_repeat(d, r, SC_A | BREAK, 230);
+    let Some(event) = r.poll(270, d) else {
+        return TestResult::Fail;
+    };
+    assert_eq_test!(event.ascii, b'1');
+    TestResult::Pass
+}
+
+pub fn test_keyboard_repeat_resamples_modifiers() -> TestResult {
+    let d = &mut KeyDecoder::new();
+    let r = &mut AutoRepeat::new();
+    feed_repeat(d, r, SC_LSHIFT, 0);
+    feed_repeat(d, r, SC_A, 0);
+    let Some(shifted) = r.poll(50, d) else {
+        return TestResult::Fail;
+    };
+    assert_eq_test!(shifted.ascii, b'A');
+    assert_eq_test!(shifted.modifiers, MOD_SHIFT);
+
+    // Letting go of Shift does not cancel the repeat, but ends the capitals.
+    assert_test!(feed_repeat(d, r, SC_LSHIFT | BREAK, 52));
+    let Some(plain) = r.poll(55, d) else {
+        return TestResult::Fail;
+    };
+    assert_eq_test!(plain.ascii, b'a', "repeat follows the released Shift");
+    assert_eq_test!(plain.modifiers, 0);
+    assert_eq_test!(plain.scancode, SC_A);
+    TestResult::Pass
+}
+
+pub fn test_keyboard_repeat_timing_from_hz() -> TestResult {
+    assert_eq_test!(
+        RepeatTiming::from_hz(100),
+        RepeatTiming {
+            delay_ticks: 50,
+            interval_ticks: 5,
+        }
+    );
+    assert_eq_test!(RepeatTiming::from_hz(1).interval_ticks, 1, "never zero");
+    TestResult::Pass
+}
+
+fn ascii(decoder: &mut KeyDecoder, make: u8) -> u8 {
+    tap(decoder, make).map_or(0, |e| e.ascii)
+}
+
+pub fn test_keyboard_layout_de() -> TestResult {
+    let d = &mut KeyDecoder::with_layout(&LAYOUT_DE);
+    assert_eq_test!(ascii(d, SC_Y_US), b'z', "Y and Z swap places");
+    assert_eq_test!(ascii(d, SC_Z_US), b'y');
+    assert_eq_test!(ascii(d, 0x35), b'-');
+    assert_eq_test!(ascii(d, 0x1B), b'+');
+    assert_eq_test!(ascii(d, 0x27), 0, "umlauts are not ASCII");
+
+    d.feed(SC_LSHIFT);
+    assert_eq_test!(ascii(d, 0x08), b'/', "shift-7");
+    assert_eq_test!(ascii(d, 0x03), b'"', "shift-2");
+    assert_eq_test!(ascii(d, SC_Y_US), b'Z');
+    d.feed(SC_LSHIFT | BREAK);
+
+    d.feed(0xE0);
+    let altgr = d.feed(SC_RALT);
+    assert_test!(altgr.is_some_and(|e| e.modifiers == MOD_ALTGR));
+    assert_eq_test!(ascii(d, 0x10), b'@', "AltGr-Q");
+    assert_eq_test!(ascii(d, 0x56), b'|');
+    assert_eq_test!(ascii(d, SC_A), b'a', "no AltGr entry falls back");
+    d.feed(0xE0);
+    d.feed(SC_RALT | BREAK);
+    assert_eq_test!(ascii(d, 0x10), b'q');
+    TestResult::Pass
+}
+
+pub fn test_keyboard_layout_us_and_uk() -> TestResult {
+    let us = &mut KeyDecoder::new();
+    assert_eq_test!(ascii(us, SC_Y_US), b'y', "US is the default");
+    us.feed(0xE0);
+    us.feed(SC_RALT);
+    assert_eq_test!(ascii(us, 0x10), b'q', "US has no AltGr column");
+
+    let uk = &mut KeyDecoder::with_layout(&LAYOUT_UK);
+    assert_eq_test!(ascii(uk, 0x2B), b'#');
+    uk.feed(SC_LSHIFT);
+    assert_eq_test!(ascii(uk, 0x03), b'"');
+    assert_eq_test!(ascii(uk, 0x28), b'@');
+    TestResult::Pass
+}
+
+pub fn test_keyboard_set_layout() -> TestResult {
+    let previous = keyboard_layout();
+    assert_test!(layout_by_name("de").is_some_and(|l| ptr::eq(l, &LAYOUT_DE)));
+    assert_test!(layout_by_name("fr").is_none());
+
+    keyboard_set_layout(&LAYOUT_DE);
+    let selected = keyboard_layout().name;
+    keyboard_set_layout(previous);
+    assert_eq_test!(selected, "de");
+    assert_test!(ptr::eq(keyboard_layout(), previous));
+    assert_test!(ptr::eq(KeyDecoder::new().layout(), &LAYOUT_US));
+    TestResult::Pass
+}
+
+pub fn test_keyboard_layout_syscall_registered() -> TestResult {
+    // Installed by the driver at init, not by core's static table
+    assert_test!(syscall_resolve(SYSCALL_KEYBOARD_SET_LAYOUT).is_some());
+    assert_eq_test!(syscall_name(SYSCALL_KEYBOARD_SET_LAYOUT), None);
+    assert_test!(syscall_resolve(SYSCALL_KEYBOARD_POLL_EVENT).is_some());
+    assert_eq_test!(syscall_name(SYSCALL_KEYBOARD_POLL_EVENT), None);
+    TestResult::Pass
+}
+
+pub fn test_keyboard_irq_path_altgr() -> TestResult {
+    let previous = keyboard_layout();
+    assert_test!(!keyboard_set_layout_by_name(b"fr"));
+    assert_test!(keyboard_set_layout_by_name(b"de"));
+    while keyboard_poll_event().is_some() {}
+
+    for scancode in [0xE0, SC_RALT, 0x10, 0x10 | BREAK, 0xE0, SC_RALT | BREAK] {
+        handle_scancode(scancode);
+    }
+    keyboard_set_layout(previous);
+    while getchar() != 0 {}
+
+    let altgr = keyboard_poll_event();
+    assert_test!(
+        altgr.is_some_and(|e| e.extended && e.modifiers == MOD_ALTGR),
+        "the E0 prefix reaches the decoder"
+    );
+    assert_test!(keyboard_poll_event().is_some_and(|e| e.pressed && e.ascii == b'@'));
+    assert_test!(keyboard_poll_event().is_some_and(|e| !e.pressed));
+    assert_test!(keyboard_poll_event().is_some_and(|e| !e.pressed && e.modifiers == 0));
+    assert_eq_test!(keyboard_poll_event(), None);
+    TestResult::Pass
+}
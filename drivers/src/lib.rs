@@ -3,21 +3,32 @@
 
 pub mod apic;
 pub mod fate;
+pub mod hpet;
+pub mod hpet_tests;
 pub mod input_event;
 pub mod interrupt_test;
 pub mod interrupts;
 pub mod ioapic;
 pub mod ioapic_tests;
 pub mod irq;
+pub mod keyboard_tests;
+pub mod mouse_tests;
 pub mod pci;
+pub mod pci_msi;
+pub mod pci_msi_tests;
+pub mod pci_tests;
 pub mod pic;
 pub mod pit;
 pub mod platform_init;
 pub mod ps2;
 pub mod random;
+pub mod random_tests;
+pub mod rtc;
+pub mod rtc_tests;
 pub mod serial;
 pub mod syscall_services_init;
 pub mod tty;
+pub mod tty_tests;
 pub mod virtio;
 pub mod virtio_blk;
 pub mod xe;
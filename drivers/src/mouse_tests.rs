@@ -0,0 +1,100 @@
+//! PS/2 mouse tests - packet decoding, resync and the event queue.
+
+use slopos_lib::assert_eq_test;
+use slopos_lib::testing::TestResult;
+
+use crate::mouse::{self, MouseEvent, PacketDecoder, mouse_poll_event};
+
+fn feed_all(decoder: &mut PacketDecoder, bytes: &[u8]) -> Option<MouseEvent> {
+    let mut last = None;
+    for &byte in bytes {
+        last = decoder.feed(byte);
+    }
+    last
+}
+
+pub fn test_mouse_decode_positive_motion() -> TestResult {
+    let mut decoder = PacketDecoder::new();
+    assert_eq_test!(decoder.feed(0x09), None);
+    assert_eq_test!(decoder.feed(0x05), None);
+    // Device Y points up; the event's points down.
+    assert_eq_test!(
+        decoder.feed(0x03),
+        Some(MouseEvent {
+            dx: 5,
+            dy: -3,
+            buttons: mouse::BUTTON_LEFT,
+        })
+    );
+    TestResult::Pass
+}
+
+pub fn test_mouse_decode_negative_motion() -> TestResult {
+    let mut decoder = PacketDecoder::new();
+    // Sync, both sign bits and the right button.
+    let event = feed_all(&mut decoder, &[0x3A, 0xFB, 0xFE]);
+    assert_eq_test!(
+        event,
+        Some(MouseEvent {
+            dx: -5,
+            dy: 2,
+            buttons: mouse::BUTTON_RIGHT,
+        })
+    );
+    let event = feed_all(&mut decoder, &[0x1C, 0x00, 0x00]);
+    assert_eq_test!(
+        event,
+        Some(MouseEvent {
+            dx: -256,
+            dy: 0,
+            buttons: mouse::BUTTON_MIDDLE,
+        })
+    );
+    TestResult::Pass
+}
+
+pub fn test_mouse_decoder_resyncs() -> TestResult {
+    let mut decoder = PacketDecoder::new();
+    // Tail of a packet whose first byte was lost: neither byte has bit 3.
+    assert_eq_test!(feed_all(&mut decoder, &[0x05, 0x03]), None);
+    assert_eq_test!(
+        feed_all(&mut decoder, &[0x08, 0x01, 0x01]),
+        Some(MouseEvent {
+            dx: 1,
+            dy: -1,
+            buttons: 0,
+        })
+    );
+
+    // An overflowed packet is consumed whole and yields nothing.
+    assert_eq_test!(feed_all(&mut decoder, &[0x48, 0xFF, 0x00]), None);
+    assert_eq_test!(
+        feed_all(&mut decoder, &[0x0F, 0x00, 0x00]),
+        Some(MouseEvent {
+            dx: 0,
+            dy: 0,
+            buttons: 0x07,
+        })
+    );
+    TestResult::Pass
+}
+
+pub fn test_mouse_poll_event_drains_queue() -> TestResult {
+    while mouse_poll_event().is_some() {}
+
+    // Motionless and with the current buttons, so nothing is routed on.
+    let buttons = mouse::get_buttons();
+    for byte in [0x08 | buttons, 0, 0] {
+        mouse::handle_irq(byte);
+    }
+    assert_eq_test!(
+        mouse_poll_event(),
+        Some(MouseEvent {
+            dx: 0,
+            dy: 0,
+            buttons,
+        })
+    );
+    assert_eq_test!(mouse_poll_event(), None);
+    TestResult::Pass
+}
@@ -5,7 +5,7 @@ use core::sync::atomic::{AtomicUsize, Ordering};
 use slopos_abi::PhysAddr;
 use slopos_lib::ports::{PCI_CONFIG_ADDRESS, PCI_CONFIG_DATA};
 use slopos_lib::string::cstr_to_str;
-use slopos_lib::{InitFlag, IrqMutex, klog_info};
+use slopos_lib::{InitFlag, IrqMutex, klog_info_mod};
 use slopos_mm::mmio::MmioRegion;
 
 pub use slopos_abi::arch::x86_64::pci::{PciBarInfo, PciDeviceInfo, *};
@@ -20,7 +20,6 @@ const PCI_HEADER_TYPE: u8 = PCI_HEADER_TYPE_OFFSET;
 const PCI_INTERRUPT_LINE: u8 = PCI_INTERRUPT_LINE_OFFSET;
 const PCI_INTERRUPT_PIN: u8 = PCI_INTERRUPT_PIN_OFFSET;
 const PCI_BAR0: u8 = PCI_BAR0_OFFSET;
-const PCI_SECONDARY_BUS: u8 = 0x19;
 
 #[repr(C)]
 #[derive(Clone, Copy, Default)]
@@ -55,7 +54,6 @@ pub struct PciDriver {
 unsafe impl Sync for PciDriver {}
 
 struct PciEnumState {
-    bus_visited: [u8; PCI_MAX_BUSES],
     devices: [PciDeviceInfo; PCI_MAX_DEVICES],
     device_count: usize,
     primary_gpu: PciGpuInfo,
@@ -64,7 +62,6 @@ struct PciEnumState {
 impl PciEnumState {
     const fn new() -> Self {
         Self {
-            bus_visited: [0; PCI_MAX_BUSES],
             devices: [PciDeviceInfo::zeroed(); PCI_MAX_DEVICES],
             device_count: 0,
             primary_gpu: PciGpuInfo::zeroed(),
@@ -147,30 +144,61 @@ pub fn pci_config_write8(bus: u8, device: u8, function: u8, offset: u8, value: u
     pci_config_write32(bus, device, function, offset, new_dword);
 }
 
-fn pci_read_vendor_id(bus: u8, device: u8, function: u8) -> u16 {
-    pci_config_read16(bus, device, function, PCI_VENDOR_ID)
-}
+/// Config-space access addressed by bus, device and function.
+///
+/// Enumeration goes through this so it can run against a mocked bus.
+pub trait PciConfigAccess {
+    fn read32(&self, bus: u8, device: u8, function: u8, offset: u8) -> u32;
+    fn write32(&mut self, bus: u8, device: u8, function: u8, offset: u8, value: u32);
 
-fn pci_read_header_type(bus: u8, device: u8, function: u8) -> u8 {
-    pci_config_read8(bus, device, function, PCI_HEADER_TYPE)
-}
+    fn read16(&self, bus: u8, device: u8, function: u8, offset: u8) -> u16 {
+        let value = self.read32(bus, device, function, offset);
+        ((value >> ((offset & 0x2) * 8)) & 0xFFFF) as u16
+    }
 
-fn pci_is_multifunction(bus: u8, device: u8) -> bool {
-    (pci_read_header_type(bus, device, 0) & 0x80) != 0
+    fn read8(&self, bus: u8, device: u8, function: u8, offset: u8) -> u8 {
+        let value = self.read32(bus, device, function, offset);
+        ((value >> ((offset & 0x3) * 8)) & 0xFF) as u8
+    }
 }
 
-fn pci_get_secondary_bus(bus: u8, device: u8, function: u8) -> u8 {
-    pci_config_read8(bus, device, function, PCI_SECONDARY_BUS)
+/// Configuration mechanism #1 through the 0xCF8/0xCFC port pair.
+pub struct PortConfigAccess;
+
+impl PciConfigAccess for PortConfigAccess {
+    fn read32(&self, bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+        pci_config_read32(bus, device, function, offset)
+    }
+
+    fn write32(&mut self, bus: u8, device: u8, function: u8, offset: u8, value: u32) {
+        pci_config_write32(bus, device, function, offset, value);
+    }
 }
 
-fn pci_probe_bar(bus: u8, device: u8, function: u8, bar_idx: u8) -> PciBarInfo {
+fn pci_probe_bar(
+    cfg: &mut impl PciConfigAccess,
+    bus: u8,
+    device: u8,
+    function: u8,
+    bar_idx: u8,
+) -> PciBarInfo {
     let bar_offset = PCI_BAR0 + bar_idx * 4;
-    let original = pci_config_read32(bus, device, function, bar_offset);
+    let original = cfg.read32(bus, device, function, bar_offset);
     let is_io = (original & 1) != 0;
 
-    pci_config_write32(bus, device, function, bar_offset, 0xFFFF_FFFF);
-    let size_mask = pci_config_read32(bus, device, function, bar_offset);
-    pci_config_write32(bus, device, function, bar_offset, original);
+    // Turn decode off while the BAR holds the all-ones sizing pattern, so
+    // the device never answers at a bogus address.
+    let command = cfg.read32(bus, device, function, PCI_COMMAND_OFFSET);
+    let decode = (PCI_COMMAND_IO_SPACE | PCI_COMMAND_MEMORY_SPACE) as u32;
+    if command & decode != 0 {
+        cfg.write32(bus, device, function, PCI_COMMAND_OFFSET, command & !decode);
+    }
+    cfg.write32(bus, device, function, bar_offset, 0xFFFF_FFFF);
+    let size_mask = cfg.read32(bus, device, function, bar_offset);
+    cfg.write32(bus, device, function, bar_offset, original);
+    if command & decode != 0 {
+        cfg.write32(bus, device, function, PCI_COMMAND_OFFSET, command);
+    }
 
     if size_mask == 0 || size_mask == 0xFFFF_FFFF {
         return PciBarInfo::zeroed();
@@ -178,7 +206,7 @@ fn pci_probe_bar(bus: u8, device: u8, function: u8, bar_idx: u8) -> PciBarInfo {
 
     if is_io {
         let base = (original & !0x3) as u64;
-        let size = (!((size_mask as u64) | 0xFFFF_FFFF_FFFF_0003) + 1) as u64;
+        let size = !((size_mask as u64) | 0xFFFF_FFFF_FFFF_0003) + 1;
         PciBarInfo {
             base,
             size,
@@ -191,12 +219,12 @@ fn pci_probe_bar(bus: u8, device: u8, function: u8, bar_idx: u8) -> PciBarInfo {
         let is_prefetchable = ((original >> 3) & 1) != 0;
         let base_low = (original & !0xF) as u64;
         let base_high = if is_64bit && bar_idx < 5 {
-            pci_config_read32(bus, device, function, bar_offset + 4) as u64
+            cfg.read32(bus, device, function, bar_offset + 4) as u64
         } else {
             0
         };
         let base = base_low | (base_high << 32);
-        let size = (!((size_mask as u64) | 0xF) + 1) as u64;
+        let size = !((size_mask as u64) | 0xF) + 1;
         PciBarInfo {
             base,
             size,
@@ -207,27 +235,26 @@ fn pci_probe_bar(bus: u8, device: u8, function: u8, bar_idx: u8) -> PciBarInfo {
     }
 }
 
-fn pci_probe_device(state: &mut PciEnumState, bus: u8, device: u8, function: u8) {
-    let vendor = pci_read_vendor_id(bus, device, function);
-    if vendor == 0xFFFF {
-        return;
+/// Read the identity, interrupt routing and BARs of one function, or None
+/// if nothing answers at that address.
+fn pci_read_device_info(
+    cfg: &mut impl PciConfigAccess,
+    bus: u8,
+    device: u8,
+    function: u8,
+) -> Option<PciDeviceInfo> {
+    let vendor_id = cfg.read16(bus, device, function, PCI_VENDOR_ID);
+    if vendor_id == PCI_VENDOR_ID_INVALID {
+        return None;
     }
-
-    let device_id = pci_config_read16(bus, device, function, PCI_DEVICE_ID);
-    let class = pci_config_read8(bus, device, function, PCI_CLASS);
-    let subclass = pci_config_read8(bus, device, function, PCI_SUBCLASS);
-    let prog_if = pci_config_read8(bus, device, function, PCI_PROG_IF);
-    let revision = pci_config_read8(bus, device, function, PCI_REVISION);
-    let header_type = pci_read_header_type(bus, device, function) & 0x7F;
-    let interrupt_line = pci_config_read8(bus, device, function, PCI_INTERRUPT_LINE);
-    let interrupt_pin = pci_config_read8(bus, device, function, PCI_INTERRUPT_PIN);
+    let header_type = cfg.read8(bus, device, function, PCI_HEADER_TYPE) & 0x7F;
 
     let mut bars = [PciBarInfo::zeroed(); PCI_MAX_BARS];
     let mut bar_count = 0u8;
     if header_type == 0 {
         let mut bar_idx = 0u8;
         while bar_idx < 6 {
-            let bar = pci_probe_bar(bus, device, function, bar_idx);
+            let bar = pci_probe_bar(cfg, bus, device, function, bar_idx);
             bars[bar_idx as usize] = bar;
             if bar.base != 0 || bar.size != 0 {
                 bar_count = bar_idx + 1;
@@ -239,7 +266,28 @@ fn pci_probe_device(state: &mut PciEnumState, bus: u8, device: u8, function: u8)
         }
     }
 
-    let info = PciDeviceInfo {
+    Some(PciDeviceInfo {
+        bus,
+        device,
+        function,
+        vendor_id,
+        device_id: cfg.read16(bus, device, function, PCI_DEVICE_ID),
+        class_code: cfg.read8(bus, device, function, PCI_CLASS),
+        subclass: cfg.read8(bus, device, function, PCI_SUBCLASS),
+        prog_if: cfg.read8(bus, device, function, PCI_PROG_IF),
+        revision: cfg.read8(bus, device, function, PCI_REVISION),
+        header_type,
+        irq_line: cfg.read8(bus, device, function, PCI_INTERRUPT_LINE),
+        irq_pin: cfg.read8(bus, device, function, PCI_INTERRUPT_PIN),
+        bar_count,
+        bars,
+    })
+}
+
+/// Log a device found at boot and pick the first display-class function
+/// with a memory BAR as the primary GPU.
+fn pci_report_device(state: &mut PciEnumState, info: PciDeviceInfo) {
+    let PciDeviceInfo {
         bus,
         device,
         function,
@@ -249,19 +297,12 @@ fn pci_probe_device(state: &mut PciEnumState, bus: u8, device: u8, function: u8)
         subclass,
         prog_if,
         revision,
-        header_type,
-        irq_line: interrupt_line,
-        irq_pin: interrupt_pin,
-        bar_count,
         bars,
-    };
+        ..
+    } = info;
 
-    if state.device_count < PCI_MAX_DEVICES {
-        state.devices[state.device_count] = info;
-        state.device_count += 1;
-    }
-
-    klog_info!(
+    klog_info_mod!(
+        "pci",
         "PCI: [Bus {} Dev {} Func {}] VID=0x{:04x} DID=0x{:04x} Class=0x{:02x}:{:02x} ProgIF=0x{:02x} Rev=0x{:02x}",
         bus,
         device,
@@ -277,7 +318,13 @@ fn pci_probe_device(state: &mut PciEnumState, bus: u8, device: u8, function: u8)
     for (i, bar) in bars.iter().enumerate() {
         if bar.base != 0 || bar.size != 0 {
             if bar.is_io != 0 {
-                klog_info!("    BAR{}: IO base=0x{:x} size={}", i, bar.base, bar.size);
+                klog_info_mod!(
+                    "pci",
+                    "    BAR{}: IO base=0x{:x} size={}",
+                    i,
+                    bar.base,
+                    bar.size
+                );
             } else {
                 let pf = if bar.prefetchable != 0 {
                     "prefetch"
@@ -285,7 +332,8 @@ fn pci_probe_device(state: &mut PciEnumState, bus: u8, device: u8, function: u8)
                     "non-prefetch"
                 };
                 let bits = if bar.is_64bit != 0 { "64bit" } else { "32bit" };
-                klog_info!(
+                klog_info_mod!(
+                    "pci",
                     "    BAR{}: MMIO base=0x{:x} size=0x{:x} {} {}",
                     i,
                     bar.base,
@@ -309,7 +357,8 @@ fn pci_probe_device(state: &mut PciEnumState, bus: u8, device: u8, function: u8)
                     let phys = PhysAddr::new(bar.base);
                     state.primary_gpu.mmio_region =
                         MmioRegion::map(phys, bar.size as usize).unwrap_or_else(MmioRegion::empty);
-                    klog_info!(
+                    klog_info_mod!(
+                        "pci",
                         "PCI: Selected display-class GPU candidate at MMIO phys=0x{:x} size=0x{:x} virt=0x{:x}",
                         bar.base,
                         bar.size,
@@ -320,35 +369,6 @@ fn pci_probe_device(state: &mut PciEnumState, bus: u8, device: u8, function: u8)
             }
         }
     }
-
-    if header_type == 1 {
-        let secondary = pci_get_secondary_bus(bus, device, function);
-        pci_scan_bus_inner(state, secondary);
-    }
-}
-
-fn pci_scan_bus_inner(state: &mut PciEnumState, bus: u8) {
-    if state.bus_visited[bus as usize] != 0 {
-        return;
-    }
-    state.bus_visited[bus as usize] = 1;
-
-    for device in 0..32u8 {
-        let vendor = pci_read_vendor_id(bus, device, 0);
-        if vendor == 0xFFFF {
-            continue;
-        }
-
-        pci_probe_device(state, bus, device, 0);
-
-        if pci_is_multifunction(bus, device) {
-            for function in 1..8u8 {
-                if pci_read_vendor_id(bus, device, function) != 0xFFFF {
-                    pci_probe_device(state, bus, device, function);
-                }
-            }
-        }
-    }
 }
 
 pub fn pci_init() {
@@ -356,27 +376,24 @@ pub fn pci_init() {
         return;
     }
 
-    klog_info!("PCI: Initializing PCI subsystem");
+    klog_info_mod!("pci", "PCI: Initializing PCI subsystem");
 
-    let mut state = ENUM_STATE.lock();
-    state.device_count = 0;
-    state.bus_visited = [0; PCI_MAX_BUSES];
+    let mut guard = ENUM_STATE.lock();
+    let state = &mut *guard;
     state.primary_gpu = PciGpuInfo::zeroed();
-
-    pci_scan_bus_inner(&mut state, 0);
-
-    let header_type = pci_read_header_type(0, 0, 0);
-    if (header_type & 0x80) != 0 {
-        for function in 1..8u8 {
-            if pci_read_vendor_id(0, 0, function) != 0xFFFF {
-                pci_scan_bus_inner(&mut state, function);
-            }
-        }
+    state.device_count = pci_scan_all(&mut PortConfigAccess, &mut state.devices);
+    for index in 0..state.device_count {
+        let info = state.devices[index];
+        pci_report_device(state, info);
     }
 
     let count = state.device_count;
     DEVICE_COUNT_CACHE.store(count, Ordering::Release);
-    klog_info!("PCI: Enumeration complete. Devices discovered: {}", count);
+    klog_info_mod!(
+        "pci",
+        "PCI: Enumeration complete. Devices discovered: {}",
+        count
+    );
 }
 
 pub fn pci_get_device_count() -> usize {
@@ -392,6 +409,51 @@ pub fn pci_get_device(index: usize) -> Option<PciDeviceInfo> {
     }
 }
 
+/// Brute-force scan of every bus, device and function slot. BARs are
+/// sized along the way, so this only runs once at boot from `pci_init`.
+///
+/// Each bus number is visited once in order rather than by following
+/// bridges, so a bridge pointing back at an earlier bus cannot make the scan
+/// loop. Functions 1-7 are only probed when function 0 sets the
+/// multi-function bit. Returns the number of entries written to `out`.
+pub fn pci_scan_all(cfg: &mut impl PciConfigAccess, out: &mut [PciDeviceInfo]) -> usize {
+    let mut count = 0;
+    for bus in 0..PCI_MAX_BUSES {
+        let bus = bus as u8;
+        for device in 0..PCI_MAX_DEVICES_PER_BUS as u8 {
+            if cfg.read16(bus, device, 0, PCI_VENDOR_ID) == PCI_VENDOR_ID_INVALID {
+                continue;
+            }
+            let header_type = cfg.read8(bus, device, 0, PCI_HEADER_TYPE);
+            let functions = if header_type & PCI_HEADER_TYPE_MULTI_FUNCTION != 0 {
+                PCI_MAX_FUNCTIONS as u8
+            } else {
+                1
+            };
+            for function in 0..functions {
+                if count == out.len() {
+                    return count;
+                }
+                if let Some(info) = pci_read_device_info(cfg, bus, device, function) {
+                    out[count] = info;
+                    count += 1;
+                }
+            }
+        }
+    }
+    count
+}
+
+/// Copy as many devices from the boot-time enumeration as fit in `out`, for
+/// diagnostics such as `lspci`. The hardware is not touched, so this is
+/// safe while drivers own the devices. Returns the number of entries written.
+pub fn pci_enumerate_devices(out: &mut [PciDeviceInfo]) -> u32 {
+    let state = ENUM_STATE.lock();
+    let count = state.device_count.min(out.len());
+    out[..count].copy_from_slice(&state.devices[..count]);
+    count as u32
+}
+
 pub fn pci_get_primary_gpu() -> PciGpuInfo {
     ENUM_STATE.lock().primary_gpu
 }
@@ -403,7 +465,7 @@ pub fn pci_register_driver(driver: &'static PciDriver) -> c_int {
         return -1;
     }
     let name = cstr_or_placeholder(driver.name);
-    klog_info!("PCI: Registered driver {}", name);
+    klog_info_mod!("pci", "PCI: Registered driver {}", name);
     registry.drivers[idx] = driver;
     registry.count = idx + 1;
     0
@@ -0,0 +1,241 @@
+//! MSI and MSI-X interrupt setup for PCI devices.
+//!
+//! A message-signalled interrupt is a memory write the device performs into
+//! the local APIC window: the address selects the destination CPU and the
+//! data word carries the IDT vector. Devices advertise MSI and MSI-X in their
+//! capability list; when neither is present the legacy INTx line is used.
+//!
+//! Capability parsing and MSI programming go through `PciConfigSpace`, so
+//! they run the same against real hardware and a synthetic config space.
+
+use slopos_abi::addr::PhysAddr;
+use slopos_lib::klog_info;
+use slopos_mm::mmio::MmioRegion;
+
+use crate::apic;
+use crate::pci::{
+    PCI_CAP_ID_MSI, PCI_CAP_ID_MSIX, PCI_CAP_PTR_OFFSET, PCI_COMMAND_INTERRUPT_DISABLE,
+    PCI_COMMAND_OFFSET, PCI_MSI_ADDRESS_BASE, PCI_MSI_CTRL_64BIT, PCI_MSI_CTRL_ENABLE,
+    PCI_MSI_CTRL_MULTI_ENABLE_MASK, PCI_MSIX_BIR_MASK, PCI_MSIX_CTRL_ENABLE,
+    PCI_MSIX_CTRL_FUNCTION_MASK, PCI_MSIX_CTRL_TABLE_SIZE_MASK, PCI_MSIX_ENTRY_CTRL_MASKED,
+    PCI_MSIX_ENTRY_SIZE, PCI_STATUS_CAP_LIST, PCI_STATUS_OFFSET, PciDeviceInfo, pci_config_read8,
+    pci_config_read16, pci_config_read32, pci_config_write16, pci_config_write32,
+};
+
+/// Bound on capability list entries, so a looping list cannot hang the walk.
+const PCI_CAP_MAX_ENTRIES: u32 = 48;
+
+/// Byte-addressed access to one function's configuration space.
+pub trait PciConfigSpace {
+    fn read8(&self, offset: u8) -> u8;
+    fn read16(&self, offset: u8) -> u16;
+    fn read32(&self, offset: u8) -> u32;
+    fn write16(&mut self, offset: u8, value: u16);
+    fn write32(&mut self, offset: u8, value: u32);
+}
+
+/// Configuration space of a function on the bus, through the legacy ports.
+#[derive(Clone, Copy)]
+pub struct PciFunctionConfig {
+    bus: u8,
+    device: u8,
+    function: u8,
+}
+
+impl PciFunctionConfig {
+    pub fn of(info: &PciDeviceInfo) -> Self {
+        Self {
+            bus: info.bus,
+            device: info.device,
+            function: info.function,
+        }
+    }
+}
+
+impl PciConfigSpace for PciFunctionConfig {
+    fn read8(&self, offset: u8) -> u8 {
+        pci_config_read8(self.bus, self.device, self.function, offset)
+    }
+
+    fn read16(&self, offset: u8) -> u16 {
+        pci_config_read16(self.bus, self.device, self.function, offset)
+    }
+
+    fn read32(&self, offset: u8) -> u32 {
+        pci_config_read32(self.bus, self.device, self.function, offset)
+    }
+
+    fn write16(&mut self, offset: u8, value: u16) {
+        pci_config_write16(self.bus, self.device, self.function, offset, value);
+    }
+
+    fn write32(&mut self, offset: u8, value: u32) {
+        pci_config_write32(self.bus, self.device, self.function, offset, value);
+    }
+}
+
+/// Offset of the first capability with ID `cap_id`, if the device has one.
+pub fn pci_find_capability(cfg: &impl PciConfigSpace, cap_id: u8) -> Option<u8> {
+    if cfg.read16(PCI_STATUS_OFFSET) & PCI_STATUS_CAP_LIST == 0 {
+        return None;
+    }
+    // The bottom two bits of every pointer are reserved.
+    let mut ptr = cfg.read8(PCI_CAP_PTR_OFFSET) & !0x3;
+    for _ in 0..PCI_CAP_MAX_ENTRIES {
+        if ptr == 0 {
+            return None;
+        }
+        if cfg.read8(ptr) == cap_id {
+            return Some(ptr);
+        }
+        ptr = cfg.read8(ptr + 1) & !0x3;
+    }
+    None
+}
+
+/// Address/data pair a device writes to raise an interrupt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MsiMessage {
+    pub address: u64,
+    pub data: u32,
+}
+
+impl MsiMessage {
+    /// Fixed delivery, edge triggered, `vector` on the CPU with `apic_id`.
+    pub fn new(apic_id: u8, vector: u8) -> Self {
+        Self {
+            address: PCI_MSI_ADDRESS_BASE | ((apic_id as u64) << 12),
+            data: vector as u32,
+        }
+    }
+}
+
+/// How a device ended up signalling interrupts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PciIrqMode {
+    Msi,
+    MsiX,
+    /// Neither capability is present; the device keeps its INTx line.
+    Legacy(u8),
+}
+
+/// Program and enable the MSI capability at `cap` for a single vector.
+/// Returns false, leaving the device untouched, when the capability's
+/// registers would run past the end of config space.
+pub fn msi_program(cfg: &mut impl PciConfigSpace, cap: u8, msg: MsiMessage) -> bool {
+    let control = cfg.read16(cap + 2);
+    let is_64bit = control & PCI_MSI_CTRL_64BIT != 0;
+    let data_offset = cap.checked_add(if is_64bit { 12 } else { 8 });
+    // The data register is 16 bits wide, so its second byte must fit too.
+    let Some(data_offset) = data_offset.filter(|&offset| offset < u8::MAX) else {
+        return false;
+    };
+    cfg.write16(cap + 2, control & !PCI_MSI_CTRL_ENABLE);
+
+    cfg.write32(cap + 4, msg.address as u32);
+    if is_64bit {
+        cfg.write32(cap + 8, (msg.address >> 32) as u32);
+    }
+    cfg.write16(data_offset, msg.data as u16);
+
+    // One message only: leave the multiple message enable field at zero.
+    let control = (control & !PCI_MSI_CTRL_MULTI_ENABLE_MASK) | PCI_MSI_CTRL_ENABLE;
+    cfg.write16(cap + 2, control);
+    true
+}
+
+/// A mapped MSI-X vector table.
+pub struct MsixTable {
+    region: MmioRegion,
+    entries: u16,
+}
+
+impl MsixTable {
+    pub fn entries(&self) -> u16 {
+        self.entries
+    }
+
+    /// Point entry `index` at `msg` and unmask it. Returns false when the
+    /// index is past the end of the table.
+    pub fn program_entry(&self, index: u16, msg: MsiMessage) -> bool {
+        if index >= self.entries {
+            return false;
+        }
+        let base = index as usize * PCI_MSIX_ENTRY_SIZE;
+        self.region.write_u32(base + 12, PCI_MSIX_ENTRY_CTRL_MASKED);
+        self.region.write_u32(base, msg.address as u32);
+        self.region.write_u32(base + 4, (msg.address >> 32) as u32);
+        self.region.write_u32(base + 8, msg.data);
+        self.region.write_u32(base + 12, 0);
+        true
+    }
+}
+
+/// Map the MSI-X table described by the capability at `cap`.
+pub fn msix_map_table(info: &PciDeviceInfo, cap: u8) -> Option<MsixTable> {
+    let cfg = PciFunctionConfig::of(info);
+    // The table offset is a dword at cap + 4 and must sit inside config space.
+    let table_offset = cap.checked_add(4).filter(|&offset| offset <= u8::MAX - 3)?;
+    let entries = (cfg.read16(cap + 2) & PCI_MSIX_CTRL_TABLE_SIZE_MASK) + 1;
+    let table = cfg.read32(table_offset);
+    let bar = info.bars.get((table & PCI_MSIX_BIR_MASK) as usize)?;
+    if bar.base == 0 || bar.is_io != 0 {
+        return None;
+    }
+    let phys = PhysAddr::new(bar.base + (table & !PCI_MSIX_BIR_MASK) as u64);
+    let region = MmioRegion::map(phys, entries as usize * PCI_MSIX_ENTRY_SIZE)?;
+    Some(MsixTable { region, entries })
+}
+
+/// Turn on MSI-X delivery for the capability at `cap`.
+pub fn msix_enable(cfg: &mut impl PciConfigSpace, cap: u8) {
+    let control = cfg.read16(cap + 2);
+    cfg.write16(
+        cap + 2,
+        (control | PCI_MSIX_CTRL_ENABLE) & !PCI_MSIX_CTRL_FUNCTION_MASK,
+    );
+}
+
+fn disable_intx(cfg: &mut impl PciConfigSpace) {
+    let command = cfg.read16(PCI_COMMAND_OFFSET);
+    cfg.write16(PCI_COMMAND_OFFSET, command | PCI_COMMAND_INTERRUPT_DISABLE);
+}
+
+/// Route the device's interrupt to `vector` on this CPU, preferring MSI,
+/// then MSI-X entry 0, then the legacy INTx line.
+pub fn pci_enable_msi(info: &PciDeviceInfo, vector: u8) -> PciIrqMode {
+    let mut cfg = PciFunctionConfig::of(info);
+    let msg = MsiMessage::new(apic::get_id() as u8, vector);
+
+    if let Some(cap) = pci_find_capability(&cfg, PCI_CAP_ID_MSI) {
+        if msi_program(&mut cfg, cap, msg) {
+            disable_intx(&mut cfg);
+            return PciIrqMode::Msi;
+        }
+        klog_info!(
+            "PCI: [Bus {} Dev {} Func {}] MSI capability at {:#x} overruns config space",
+            info.bus,
+            info.device,
+            info.function,
+            cap
+        );
+    }
+
+    if let Some(cap) = pci_find_capability(&cfg, PCI_CAP_ID_MSIX) {
+        match msix_map_table(info, cap) {
+            Some(table) if table.program_entry(0, msg) => {
+                msix_enable(&mut cfg, cap);
+                disable_intx(&mut cfg);
+                return PciIrqMode::MsiX;
+            }
+            _ => klog_info!(
+                "PCI: [Bus {} Dev {} Func {}] MSI-X table unusable, using INTx",
+                info.bus,
+                info.device,
+                info.function
+            ),
+        }
+    }
+
+    PciIrqMode::Legacy(info.irq_line)
+}
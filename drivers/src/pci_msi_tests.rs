@@ -0,0 +1,133 @@
+//! PCI MSI tests - capability walk and message programming on a synthetic
+//! config space.
+
+use slopos_lib::testing::TestResult;
+use slopos_lib::{assert_eq_test, assert_test};
+
+use crate::pci::{
+    PCI_CAP_ID_MSI, PCI_CAP_ID_MSIX, PCI_CAP_PTR_OFFSET, PCI_MSI_CTRL_64BIT, PCI_MSI_CTRL_ENABLE,
+    PCI_MSI_CTRL_MULTI_ENABLE_MASK, PCI_STATUS_CAP_LIST, PCI_STATUS_OFFSET,
+};
+use crate::pci_msi::{MsiMessage, PciConfigSpace, msi_program, pci_find_capability};
+
+/// Vendor-specific capability ID, used as a filler entry in the list.
+const PCI_CAP_ID_VENDOR: u8 = 0x09;
+
+struct FakeConfig {
+    bytes: [u8; 256],
+}
+
+impl FakeConfig {
+    fn new() -> Self {
+        Self { bytes: [0; 256] }
+    }
+
+    /// Vendor capability at 0x40 chained to an MSI capability at 0x50.
+    fn with_msi(control: u16) -> Self {
+        let mut cfg = Self::new();
+        cfg.write16(PCI_STATUS_OFFSET, PCI_STATUS_CAP_LIST);
+        cfg.bytes[PCI_CAP_PTR_OFFSET as usize] = 0x40;
+        cfg.bytes[0x40] = PCI_CAP_ID_VENDOR;
+        cfg.bytes[0x41] = 0x50;
+        cfg.bytes[0x50] = PCI_CAP_ID_MSI;
+        cfg.bytes[0x51] = 0x00;
+        cfg.write16(0x52, control);
+        cfg
+    }
+}
+
+impl PciConfigSpace for FakeConfig {
+    fn read8(&self, offset: u8) -> u8 {
+        self.bytes[offset as usize]
+    }
+
+    fn read16(&self, offset: u8) -> u16 {
+        let o = offset as usize;
+        u16::from_le_bytes([self.bytes[o], self.bytes[o + 1]])
+    }
+
+    fn read32(&self, offset: u8) -> u32 {
+        let o = offset as usize;
+        u32::from_le_bytes(self.bytes[o..o + 4].try_into().unwrap())
+    }
+
+    fn write16(&mut self, offset: u8, value: u16) {
+        let o = offset as usize;
+        self.bytes[o..o + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn write32(&mut self, offset: u8, value: u32) {
+        let o = offset as usize;
+        self.bytes[o..o + 4].copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+pub fn test_msi_capability_found_in_chain() -> TestResult {
+    let cfg = FakeConfig::with_msi(0);
+    assert_eq_test!(pci_find_capability(&cfg, PCI_CAP_ID_MSI), Some(0x50));
+    assert_eq_test!(pci_find_capability(&cfg, PCI_CAP_ID_VENDOR), Some(0x40));
+    assert_eq_test!(pci_find_capability(&cfg, PCI_CAP_ID_MSIX), None);
+    TestResult::Pass
+}
+
+pub fn test_msi_no_capability_list() -> TestResult {
+    let mut cfg = FakeConfig::with_msi(0);
+    cfg.write16(PCI_STATUS_OFFSET, 0);
+    assert_eq_test!(
+        pci_find_capability(&cfg, PCI_CAP_ID_MSI),
+        None,
+        "status without CAP_LIST hides the list"
+    );
+    TestResult::Pass
+}
+
+pub fn test_msi_capability_loop_terminates() -> TestResult {
+    let mut cfg = FakeConfig::with_msi(0);
+    cfg.bytes[0x51] = 0x40;
+    assert_eq_test!(pci_find_capability(&cfg, PCI_CAP_ID_MSIX), None);
+    TestResult::Pass
+}
+
+pub fn test_msi_message_targets_apic() -> TestResult {
+    let msg = MsiMessage::new(1, 0x41);
+    assert_eq_test!(msg.address, 0xFEE0_1000);
+    assert_eq_test!(msg.data, 0x41);
+    TestResult::Pass
+}
+
+pub fn test_msi_program_32bit() -> TestResult {
+    let mut cfg = FakeConfig::with_msi(PCI_MSI_CTRL_MULTI_ENABLE_MASK);
+    assert_test!(msi_program(&mut cfg, 0x50, MsiMessage::new(2, 0x30)));
+    assert_eq_test!(cfg.read32(0x54), 0xFEE0_2000);
+    assert_eq_test!(cfg.read16(0x58), 0x30);
+    let control = cfg.read16(0x52);
+    assert_test!(control & PCI_MSI_CTRL_ENABLE != 0, "MSI enabled");
+    assert_eq_test!(control & PCI_MSI_CTRL_MULTI_ENABLE_MASK, 0);
+    TestResult::Pass
+}
+
+pub fn test_msi_program_64bit() -> TestResult {
+    let mut cfg = FakeConfig::with_msi(PCI_MSI_CTRL_64BIT);
+    assert_test!(msi_program(&mut cfg, 0x50, MsiMessage::new(0, 0x42)));
+    assert_eq_test!(cfg.read32(0x54), 0xFEE0_0000);
+    assert_eq_test!(cfg.read32(0x58), 0, "upper address dword");
+    assert_eq_test!(cfg.read16(0x5C), 0x42);
+    assert_test!(cfg.read16(0x52) & PCI_MSI_CTRL_ENABLE != 0, "MSI enabled");
+    TestResult::Pass
+}
+
+pub fn test_msi_program_rejects_capability_at_end() -> TestResult {
+    let mut cfg = FakeConfig::new();
+    cfg.bytes[0xF4] = PCI_CAP_ID_MSI;
+    cfg.write16(0xF6, PCI_MSI_CTRL_64BIT);
+    let before = cfg.bytes;
+    assert_test!(
+        !msi_program(&mut cfg, 0xF4, MsiMessage::new(0, 0x42)),
+        "64-bit data register would wrap past 0xFF"
+    );
+    assert_test!(cfg.bytes == before, "nothing written on rejection");
+    cfg.write16(0xF6, 0);
+    assert_test!(msi_program(&mut cfg, 0xF4, MsiMessage::new(0, 0x42)));
+    assert_eq_test!(cfg.read16(0xFC), 0x42, "32-bit layout still fits");
+    TestResult::Pass
+}
@@ -0,0 +1,149 @@
+//! PCI enumeration tests - brute-force bus scan against a mocked config space.
+
+use slopos_lib::testing::TestResult;
+use slopos_lib::{assert_eq_test, assert_test};
+
+use crate::pci::{PCI_HEADER_TYPE_MULTI_FUNCTION, PciConfigAccess, PciDeviceInfo, pci_scan_all};
+
+/// One answering function: (bus, device, function, vendor, device id,
+/// class, subclass, header type).
+type MockFunction = (u8, u8, u8, u16, u16, u8, u8, u8);
+
+struct MockBus {
+    functions: &'static [MockFunction],
+}
+
+impl MockBus {
+    fn find(&self, bus: u8, device: u8, function: u8) -> Option<&MockFunction> {
+        self.functions
+            .iter()
+            .find(|f| f.0 == bus && f.1 == device && f.2 == function)
+    }
+}
+
+impl PciConfigAccess for MockBus {
+    fn read32(&self, bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+        let Some(&(_, _, _, vendor, device_id, class, subclass, header)) =
+            self.find(bus, device, function)
+        else {
+            return 0xFFFF_FFFF;
+        };
+        match offset & 0xFC {
+            0x00 => ((device_id as u32) << 16) | vendor as u32,
+            0x08 => ((class as u32) << 24) | ((subclass as u32) << 16),
+            0x0C => (header as u32) << 16,
+            _ => 0,
+        }
+    }
+
+    fn write32(&mut self, _bus: u8, _device: u8, _function: u8, _offset: u8, _value: u32) {}
+}
+
+/// Two devices on bus 0 with slot 1 between them left empty.
+static TWO_DEVICES: &[MockFunction] = &[
+    (0, 0, 0, 0x8086, 0x1237, 0x06, 0x00, 0x00),
+    (0, 2, 0, 0x1234, 0x1111, 0x03, 0x00, 0x00),
+];
+
+pub fn test_pci_scan_reports_present_devices() -> TestResult {
+    let mut bus = MockBus {
+        functions: TWO_DEVICES,
+    };
+    let mut out = [PciDeviceInfo::zeroed(); 8];
+    let count = pci_scan_all(&mut bus, &mut out);
+    assert_eq_test!(count, 2, "empty slots are skipped");
+    assert_eq_test!(out[0].vendor_id, 0x8086);
+    assert_eq_test!(out[0].class_code, 0x06);
+    assert_eq_test!(out[1].device, 2);
+    assert_eq_test!(out[1].device_id, 0x1111);
+    assert_eq_test!(out[1].class_code, 0x03);
+    assert_eq_test!(out[1].bar_count, 0);
+    TestResult::Pass
+}
+
+pub fn test_pci_scan_multifunction() -> TestResult {
+    const MULTI: u8 = PCI_HEADER_TYPE_MULTI_FUNCTION;
+    static FUNCTIONS: &[MockFunction] = &[
+        (0, 1, 0, 0x8086, 0x7000, 0x06, 0x01, MULTI),
+        (0, 1, 3, 0x8086, 0x7113, 0x06, 0x80, 0x00),
+        // Single-function device that also answers on function 1.
+        (0, 4, 0, 0x1AF4, 0x1001, 0x01, 0x00, 0x00),
+        (0, 4, 1, 0x1AF4, 0x1001, 0x01, 0x00, 0x00),
+    ];
+    let mut bus = MockBus {
+        functions: FUNCTIONS,
+    };
+    let mut out = [PciDeviceInfo::zeroed(); 8];
+    let count = pci_scan_all(&mut bus, &mut out);
+    assert_eq_test!(count, 3);
+    assert_eq_test!(out[1].function, 3);
+    assert_eq_test!(out[0].header_type, 0, "multi-function bit stripped");
+    assert_test!(out[2].device == 4 && out[2].function == 0);
+    TestResult::Pass
+}
+
+pub fn test_pci_scan_stops_at_capacity() -> TestResult {
+    let mut bus = MockBus {
+        functions: TWO_DEVICES,
+    };
+    let mut out = [PciDeviceInfo::zeroed(); 1];
+    assert_eq_test!(pci_scan_all(&mut bus, &mut out), 1);
+    assert_eq_test!(out[0].vendor_id, 0x8086);
+    TestResult::Pass
+}
+
+/// One device with a 4 KiB memory BAR0 that records whether it was sized
+/// while still decoding.
+struct SizingBus {
+    command: u32,
+    bar0: u32,
+    sized_while_decoding: bool,
+}
+
+const SIZING_BAR_BASE: u32 = 0xFEB0_0000;
+const SIZING_BAR_SIZE: u32 = 0x1000;
+
+impl PciConfigAccess for SizingBus {
+    fn read32(&self, bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+        if (bus, device, function) != (0, 0, 0) {
+            return 0xFFFF_FFFF;
+        }
+        match offset & 0xFC {
+            0x00 => 0x1111_1234,
+            0x04 => self.command,
+            0x08 => 0x0300_0000,
+            0x10 if self.bar0 == 0xFFFF_FFFF => !(SIZING_BAR_SIZE - 1),
+            0x10 => self.bar0,
+            _ => 0,
+        }
+    }
+
+    fn write32(&mut self, _bus: u8, _device: u8, _function: u8, offset: u8, value: u32) {
+        match offset {
+            0x04 => self.command = value,
+            0x10 => {
+                if value == 0xFFFF_FFFF && self.command & 0x3 != 0 {
+                    self.sized_while_decoding = true;
+                }
+                self.bar0 = value;
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn test_pci_bar_sizing_disables_decode() -> TestResult {
+    let mut bus = SizingBus {
+        command: 0x0007,
+        bar0: SIZING_BAR_BASE,
+        sized_while_decoding: false,
+    };
+    let mut out = [PciDeviceInfo::zeroed(); 1];
+    assert_eq_test!(pci_scan_all(&mut bus, &mut out), 1);
+    assert_test!(!bus.sized_while_decoding, "decode off during sizing");
+    assert_eq_test!(bus.command, 0x0007, "command register restored");
+    assert_eq_test!(bus.bar0, SIZING_BAR_BASE, "BAR restored");
+    assert_eq_test!(out[0].bars[0].base, SIZING_BAR_BASE as u64);
+    assert_eq_test!(out[0].bars[0].size, SIZING_BAR_SIZE as u64);
+    TestResult::Pass
+}
@@ -6,7 +6,7 @@ use slopos_lib::ports::{
     PIT_COMMAND_BINARY, PIT_COMMAND_CHANNEL0, PIT_COMMAND_MODE_SQUARE, PIT_DEFAULT_FREQUENCY_HZ,
     PIT_IRQ_LINE,
 };
-use slopos_lib::{cpu, klog_debug, klog_info};
+use slopos_lib::{cpu, klog_debug_mod, klog_info};
 
 static CURRENT_FREQUENCY_HZ: AtomicU32 = AtomicU32::new(0);
 static CURRENT_RELOAD_DIVISOR: AtomicU32 = AtomicU32::new(0);
@@ -53,7 +53,7 @@ pub fn pit_set_frequency(frequency_hz: u32) {
     pit_io_wait();
 
     let freq = CURRENT_FREQUENCY_HZ.load(Ordering::SeqCst);
-    klog_debug!("PIT: frequency set to {} Hz\n", freq);
+    klog_debug_mod!("drivers", "PIT: frequency set to {} Hz\n", freq);
 }
 
 pub fn pit_init(frequency_hz: u32) {
@@ -1,6 +1,6 @@
 use core::ffi::{c_char, c_int, c_void};
 
-use crate::{apic, ioapic, pit, random, serial};
+use crate::{apic, ioapic, pci, pit, random, rtc, serial, tty};
 use slopos_core::irq;
 use slopos_core::platform::{PlatformServices, register_platform};
 
@@ -89,13 +89,17 @@ static PLATFORM_SERVICES: PlatformServices = PlatformServices {
     timer_sleep_ms: |ms| pit::pit_sleep_ms(ms),
     timer_enable_irq: || pit::pit_enable_irq(),
     timer_disable_irq: || pit::pit_disable_irq(),
-    console_putc: |c| serial::serial_putc_com1(c),
+    console_putc: |c| {
+        serial::serial_putc_com1(c);
+        tty::tty_console_write(&[c]);
+    },
     console_puts: |s| {
-        for &c in s {
-            serial::serial_putc_com1(c);
-        }
+        serial::serial_write_com1(s);
+        tty::tty_console_write(s);
     },
     rng_next: || random::random_next(),
+    wall_clock_now: || rtc::rtc_gettimeofday(),
+    pci_enumerate: |out| pci::pci_enumerate_devices(out),
     gdt_set_kernel_rsp0: gdt_set_kernel_rsp0_impl,
     kernel_shutdown: kernel_shutdown_impl,
     kernel_reboot: kernel_reboot_impl,
@@ -1,19 +1,32 @@
-use slopos_lib::{IrqMutex, RingBuffer, klog_debug};
+use slopos_lib::{IrqMutex, RingBuffer, klog_debug_mod};
 
 use crate::input_event::{self, get_timestamp_ms};
+use crate::pit::pit_get_frequency;
 use crate::ps2;
+use crate::ps2::layout::{KeyboardLayout, LAYOUT_US, layout_by_name};
 use crate::tty::tty_notify_input_ready;
-use slopos_core::scheduler_request_reschedule_from_interrupt;
+use slopos_core::{irq, scheduler_request_reschedule_from_interrupt};
 
 const BUFFER_SIZE: usize = 256;
 type Buffer = RingBuffer<u8, BUFFER_SIZE>;
 
+const EVENT_QUEUE_SIZE: usize = 64;
+
+/// How long a key must be held before it starts repeating.
+pub const REPEAT_DELAY_MS: u64 = 500;
+/// Time between repeats once a held key is repeating (about 20 per second).
+pub const REPEAT_INTERVAL_MS: u64 = 50;
+
+pub use slopos_abi::input::{KeyEvent, MOD_ALT, MOD_ALTGR, MOD_CAPS_LOCK, MOD_CTRL, MOD_SHIFT};
+
 #[derive(Clone, Copy)]
 struct ModifierState {
     shift_left: bool,
     shift_right: bool,
     ctrl_left: bool,
+    ctrl_right: bool,
     alt_left: bool,
+    alt_right: bool,
     caps_lock: bool,
 }
 
@@ -23,7 +36,9 @@ impl ModifierState {
             shift_left: false,
             shift_right: false,
             ctrl_left: false,
+            ctrl_right: false,
             alt_left: false,
+            alt_right: false,
             caps_lock: false,
         }
     }
@@ -31,10 +46,180 @@ impl ModifierState {
     fn is_shift(&self) -> bool {
         self.shift_left || self.shift_right
     }
+
+    fn is_ctrl(&self) -> bool {
+        self.ctrl_left || self.ctrl_right
+    }
+
+    fn bits(&self) -> u8 {
+        let mut bits = 0;
+        if self.is_shift() {
+            bits |= MOD_SHIFT;
+        }
+        if self.is_ctrl() {
+            bits |= MOD_CTRL;
+        }
+        if self.alt_left {
+            bits |= MOD_ALT;
+        }
+        if self.caps_lock {
+            bits |= MOD_CAPS_LOCK;
+        }
+        if self.alt_right {
+            bits |= MOD_ALTGR;
+        }
+        bits
+    }
 }
 
-struct KeyboardState {
+/// Turns scancodes into key events, tracking modifiers and Caps Lock
+/// across make and break codes and translating through a layout.
+pub struct KeyDecoder {
     modifiers: ModifierState,
+    layout: &'static KeyboardLayout,
+    extended: bool,
+}
+
+impl Default for KeyDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyDecoder {
+    pub const fn new() -> Self {
+        Self::with_layout(&LAYOUT_US)
+    }
+
+    pub const fn with_layout(layout: &'static KeyboardLayout) -> Self {
+        Self {
+            modifiers: ModifierState::new(),
+            layout,
+            extended: false,
+        }
+    }
+
+    pub fn layout(&self) -> &'static KeyboardLayout {
+        self.layout
+    }
+
+    pub fn set_layout(&mut self, layout: &'static KeyboardLayout) {
+        self.layout = layout;
+    }
+
+    /// Decode one scancode byte. The 0xE0 prefix yields nothing on its own.
+    pub fn feed(&mut self, scancode: u8) -> Option<KeyEvent> {
+        if scancode == 0xE0 {
+            self.extended = true;
+            return None;
+        }
+        let extended = core::mem::take(&mut self.extended);
+        let make_code = get_make_code(scancode);
+        let pressed = !is_break_code(scancode);
+        let ascii = translate_scancode(scancode, &self.modifiers, self.layout);
+        if is_modifier(make_code) {
+            handle_modifier(&mut self.modifiers, make_code, extended, pressed);
+        }
+        Some(KeyEvent {
+            scancode,
+            ascii,
+            modifiers: self.modifiers.bits(),
+            pressed,
+            extended,
+        })
+    }
+
+    /// `event` translated again under the modifiers held now, so a repeat
+    /// follows Shift, Ctrl or AltGr changing while its key stays down.
+    pub fn resample(&self, event: &KeyEvent) -> KeyEvent {
+        KeyEvent {
+            ascii: translate_scancode(event.scancode, &self.modifiers, self.layout),
+            modifiers: self.modifiers.bits(),
+            ..*event
+        }
+    }
+}
+
+/// Auto-repeat delays converted to timer ticks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RepeatTiming {
+    pub delay_ticks: u64,
+    pub interval_ticks: u64,
+}
+
+impl RepeatTiming {
+    /// Round each delay up to whole ticks of a `tick_hz` timer.
+    pub fn from_hz(tick_hz: u32) -> Self {
+        let to_ticks = |ms: u64| (ms * tick_hz as u64).div_ceil(1000).max(1);
+        Self {
+            delay_ticks: to_ticks(REPEAT_DELAY_MS),
+            interval_ticks: to_ticks(REPEAT_INTERVAL_MS),
+        }
+    }
+}
+
+/// Software auto-repeat for the most recently pressed printable key.
+pub struct AutoRepeat {
+    held: Option<KeyEvent>,
+    next_tick: u64,
+    interval_ticks: u64,
+}
+
+impl Default for AutoRepeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AutoRepeat {
+    pub const fn new() -> Self {
+        Self {
+            held: None,
+            next_tick: 0,
+            interval_ticks: 1,
+        }
+    }
+
+    /// Track a decoded key at tick `now`. Pressing a printable key arms the
+    /// repeat; releasing it or pressing any other key cancels it. Returns
+    /// false for the keyboard's own typematic make of the held key, which
+    /// the caller should drop so the key does not repeat twice.
+    pub fn key(&mut self, event: &KeyEvent, now: u64, timing: RepeatTiming) -> bool {
+        let held = self.held.map(|h| (h.scancode, h.extended));
+        if !event.pressed {
+            if held == Some((event.scancode & !0x80, event.extended)) {
+                self.held = None;
+            }
+            return true;
+        }
+        if held == Some((event.scancode, event.extended)) {
+            return false;
+        }
+        self.held = None;
+        if event.ascii == b' ' || event.ascii.is_ascii_graphic() {
+            self.held = Some(*event);
+            self.next_tick = now + timing.delay_ticks;
+            self.interval_ticks = timing.interval_ticks;
+        }
+        true
+    }
+
+    /// The held key's next repeat if it is due at tick `now`, translated
+    /// under `decoder`'s current modifiers rather than those at the press.
+    pub fn poll(&mut self, now: u64, decoder: &KeyDecoder) -> Option<KeyEvent> {
+        let held = self.held?;
+        if now < self.next_tick {
+            return None;
+        }
+        self.next_tick += self.interval_ticks;
+        Some(decoder.resample(&held))
+    }
+}
+
+struct KeyboardState {
+    decoder: KeyDecoder,
+    repeat: AutoRepeat,
+    events: RingBuffer<KeyEvent, EVENT_QUEUE_SIZE>,
     char_buffer: Buffer,
     scancode_buffer: Buffer,
     extended_code: bool,
@@ -43,7 +228,15 @@ struct KeyboardState {
 impl KeyboardState {
     const fn new() -> Self {
         Self {
-            modifiers: ModifierState::new(),
+            decoder: KeyDecoder::new(),
+            repeat: AutoRepeat::new(),
+            events: RingBuffer::new_overwrite_with(KeyEvent {
+                scancode: 0,
+                ascii: 0,
+                modifiers: 0,
+                pressed: false,
+                extended: false,
+            }),
             char_buffer: Buffer::new_with(0),
             scancode_buffer: Buffer::new_with(0),
             extended_code: false,
@@ -51,7 +244,9 @@ impl KeyboardState {
     }
 
     fn reset(&mut self) {
-        self.modifiers = ModifierState::new();
+        self.decoder = KeyDecoder::with_layout(self.decoder.layout());
+        self.repeat = AutoRepeat::new();
+        self.events.reset();
         self.char_buffer = Buffer::new_with(0);
         self.scancode_buffer = Buffer::new_with(0);
         self.extended_code = false;
@@ -62,28 +257,8 @@ static STATE: IrqMutex<KeyboardState> = IrqMutex::new(KeyboardState::new());
 
 const KEY_PAGE_UP: u8 = 0x80;
 const KEY_PAGE_DOWN: u8 = 0x81;
-
-const SCANCODE_LETTERS: [u8; 0x80] = [
-    0x00, 0x00, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x30, 0x2D, 0x3D, 0x00, 0x09,
-    0x71, 0x77, 0x65, 0x72, 0x74, 0x79, 0x75, 0x69, 0x6F, 0x70, 0x5B, 0x5D, 0x00, 0x00, 0x61, 0x73,
-    0x64, 0x66, 0x67, 0x68, 0x6A, 0x6B, 0x6C, 0x3B, 0x27, 0x60, 0x00, 0x5C, 0x7A, 0x78, 0x63, 0x76,
-    0x62, 0x6E, 0x6D, 0x2C, 0x2E, 0x2F, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-];
-
-const SCANCODE_SHIFTED: [u8; 0x80] = [
-    0x00, 0x00, 0x21, 0x40, 0x23, 0x24, 0x25, 0x5E, 0x26, 0x2A, 0x28, 0x29, 0x5F, 0x2B, 0x00, 0x00,
-    0x51, 0x57, 0x45, 0x52, 0x54, 0x59, 0x55, 0x49, 0x4F, 0x50, 0x7B, 0x7D, 0x00, 0x00, 0x41, 0x53,
-    0x44, 0x46, 0x47, 0x48, 0x4A, 0x4B, 0x4C, 0x3A, 0x22, 0x7E, 0x00, 0x7C, 0x5A, 0x58, 0x43, 0x56,
-    0x42, 0x4E, 0x4D, 0x3C, 0x3E, 0x3F, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-];
+const KEY_ARROW_UP: u8 = 0x82;
+const KEY_ARROW_DOWN: u8 = 0x83;
 
 #[inline(always)]
 fn is_break_code(scancode: u8) -> bool {
@@ -95,33 +270,38 @@ fn get_make_code(scancode: u8) -> u8 {
     scancode & 0x7F
 }
 
-fn translate_letter(make_code: u8, modifiers: &ModifierState) -> u8 {
+/// Caps Lock only flips letters; Shift also selects the symbol row. AltGr
+/// wins where the layout defines a character for it.
+fn translate_letter(make_code: u8, modifiers: &ModifierState, layout: &KeyboardLayout) -> u8 {
     let shift = modifiers.is_shift();
     let caps = modifiers.caps_lock;
 
-    if shift && (make_code as usize) < SCANCODE_SHIFTED.len() {
-        let shifted = SCANCODE_SHIFTED[make_code as usize];
-        if shifted != 0 {
-            return shifted;
+    if modifiers.alt_right {
+        let altgr = layout.lookup(make_code, false, true);
+        if altgr != 0 {
+            return altgr;
         }
     }
 
-    if (make_code as usize) < SCANCODE_LETTERS.len() {
-        let base_char = SCANCODE_LETTERS[make_code as usize];
-        if base_char != 0 {
-            if (b'a'..=b'z').contains(&base_char) {
-                let should_uppercase = shift ^ caps;
-                if should_uppercase {
-                    return base_char - 0x20;
-                }
-            }
-            return base_char;
+    let base_char = layout.lookup(make_code, false, false);
+    if base_char.is_ascii_lowercase() {
+        return if shift ^ caps {
+            base_char.to_ascii_uppercase()
+        } else {
+            base_char
+        };
+    }
+
+    if shift {
+        let shifted = layout.lookup(make_code, true, false);
+        if shifted != 0 {
+            return shifted;
         }
     }
-    0
+    base_char
 }
 
-fn translate_scancode(scancode: u8, modifiers: &ModifierState) -> u8 {
+fn translate_scancode(scancode: u8, modifiers: &ModifierState, layout: &KeyboardLayout) -> u8 {
     let make_code = get_make_code(scancode);
     match make_code {
         0x1C => b'\n',
@@ -129,15 +309,28 @@ fn translate_scancode(scancode: u8, modifiers: &ModifierState) -> u8 {
         0x39 => b' ',
         0x0F => b'\t',
         0x01 => 0x1B,
-        _ => translate_letter(make_code, modifiers),
+        _ => {
+            let c = translate_letter(make_code, modifiers, layout);
+            if modifiers.is_ctrl() && c.is_ascii_alphabetic() {
+                c & 0x1F
+            } else {
+                c
+            }
+        }
     }
 }
 
-fn handle_modifier(modifiers: &mut ModifierState, make_code: u8, is_press: bool) {
+fn is_modifier(make_code: u8) -> bool {
+    matches!(make_code, 0x2A | 0x36 | 0x1D | 0x38 | 0x3A)
+}
+
+fn handle_modifier(modifiers: &mut ModifierState, make_code: u8, extended: bool, is_press: bool) {
     match make_code {
         0x2A => modifiers.shift_left = is_press,
         0x36 => modifiers.shift_right = is_press,
+        0x1D if extended => modifiers.ctrl_right = is_press,
         0x1D => modifiers.ctrl_left = is_press,
+        0x38 if extended => modifiers.alt_right = is_press,
         0x38 => modifiers.alt_left = is_press,
         0x3A => {
             if is_press {
@@ -152,11 +345,32 @@ pub fn init() {
     STATE.lock().reset();
 }
 
+/// Switch the layout used to translate subsequent key presses. US is the
+/// default until this is called.
+pub fn keyboard_set_layout(layout: &'static KeyboardLayout) {
+    STATE.lock().decoder.set_layout(layout);
+}
+
+/// Switch to the compiled-in layout called `name`; false if there is none.
+pub fn keyboard_set_layout_by_name(name: &[u8]) -> bool {
+    let Some(layout) = core::str::from_utf8(name).ok().and_then(layout_by_name) else {
+        return false;
+    };
+    keyboard_set_layout(layout);
+    true
+}
+
+pub fn keyboard_layout() -> &'static KeyboardLayout {
+    STATE.lock().decoder.layout()
+}
+
 pub fn handle_scancode(scancode: u8) {
-    klog_debug!("[KBD] Scancode: 0x{:02x}\n", scancode);
+    klog_debug_mod!("drivers", "[KBD] Scancode: 0x{:02x}\n", scancode);
 
     let mut state = STATE.lock();
 
+    // The decoder sees every byte so it can tell right Alt from left Alt.
+    let decoded = state.decoder.feed(scancode);
     if scancode == 0xE0 {
         state.extended_code = true;
         return;
@@ -165,7 +379,8 @@ pub fn handle_scancode(scancode: u8) {
     let is_press = !is_break_code(scancode);
     let make_code = get_make_code(scancode);
 
-    klog_debug!(
+    klog_debug_mod!(
+        "drivers",
         "[KBD] Make code: 0x{:02x} is_press: {}",
         make_code,
         is_press as u32
@@ -173,15 +388,24 @@ pub fn handle_scancode(scancode: u8) {
 
     state.scancode_buffer.push_overwrite(scancode);
 
-    let ascii = translate_scancode(scancode, &state.modifiers);
+    let Some(event) = decoded else {
+        return;
+    };
+    let timing = RepeatTiming::from_hz(pit_get_frequency());
+    if !state.repeat.key(&event, irq::get_timer_ticks(), timing) {
+        state.extended_code = false;
+        return;
+    }
+    // The queue drops its oldest event when nobody drains it.
+    state.events.push(event);
+    let ascii = event.ascii;
     let timestamp_ms = get_timestamp_ms();
 
     drop(state);
     input_event::input_route_key_event(make_code, ascii, is_press, timestamp_ms);
     let mut state = STATE.lock();
 
-    if matches!(make_code, 0x2A | 0x36 | 0x1D | 0x38 | 0x3A) {
-        handle_modifier(&mut state.modifiers, make_code, is_press);
+    if is_modifier(make_code) {
         return;
     }
 
@@ -193,6 +417,8 @@ pub fn handle_scancode(scancode: u8) {
         let extended_key = match make_code {
             0x49 => KEY_PAGE_UP,
             0x51 => KEY_PAGE_DOWN,
+            0x48 => KEY_ARROW_UP,
+            0x50 => KEY_ARROW_DOWN,
             _ => 0,
         };
         if extended_key != 0 {
@@ -208,17 +434,38 @@ pub fn handle_scancode(scancode: u8) {
         return;
     }
 
-    klog_debug!("[KBD] ASCII: 0x{:02x}\n", ascii);
+    klog_debug_mod!("drivers", "[KBD] ASCII: 0x{:02x}\n", ascii);
 
     if ascii != 0 {
         state.char_buffer.push_overwrite(ascii);
-        klog_debug!("[KBD] Adding to buffer");
+        klog_debug_mod!("drivers", "[KBD] Adding to buffer");
         drop(state);
         tty_notify_input_ready();
         scheduler_request_reschedule_from_interrupt();
     }
 }
 
+/// Re-emit the held key when its repeat is due. Runs from the timer IRQ.
+pub fn handle_timer_tick(now: u64) {
+    let mut guard = STATE.lock();
+    let state = &mut *guard;
+    let Some(event) = state.repeat.poll(now, &state.decoder) else {
+        return;
+    };
+    state.events.push(event);
+    state.char_buffer.push_overwrite(event.ascii);
+    drop(guard);
+
+    input_event::input_route_key_event(
+        get_make_code(event.scancode),
+        event.ascii,
+        true,
+        get_timestamp_ms(),
+    );
+    tty_notify_input_ready();
+    scheduler_request_reschedule_from_interrupt();
+}
+
 pub fn getchar() -> u8 {
     STATE.lock().char_buffer.try_pop().unwrap_or(0)
 }
@@ -231,20 +478,40 @@ pub fn has_input() -> i32 {
     }
 }
 
+/// Pop the oldest queued key event, if any.
+pub fn keyboard_poll_event() -> Option<KeyEvent> {
+    STATE.lock().events.try_pop()
+}
+
 pub fn get_scancode() -> u8 {
     STATE.lock().scancode_buffer.try_pop().unwrap_or(0)
 }
 
-pub fn poll_wait_enter() {
+/// Spin on the controller until Enter is pressed. Page Up and Page Down
+/// call `on_page` with 1 and -1 so the caller can page through output.
+pub fn poll_wait_enter(on_page: &mut dyn FnMut(i32)) {
     use slopos_lib::cpu;
     const ENTER_MAKE_CODE: u8 = 0x1C;
+    const PAGE_UP_MAKE_CODE: u8 = 0x49;
+    const PAGE_DOWN_MAKE_CODE: u8 = 0x51;
 
+    let mut extended = false;
     loop {
         if ps2::has_data() {
             let scancode = ps2::read_data_nowait();
             if scancode == ENTER_MAKE_CODE {
                 break;
             }
+            match scancode {
+                0xE0 => {
+                    extended = true;
+                    continue;
+                }
+                PAGE_UP_MAKE_CODE if extended => on_page(1),
+                PAGE_DOWN_MAKE_CODE if extended => on_page(-1),
+                _ => {}
+            }
+            extended = false;
         }
         cpu::pause();
     }
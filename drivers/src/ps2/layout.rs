@@ -0,0 +1,107 @@
+//! Compiled-in keyboard layouts.
+//!
+//! Each layout maps set-1 make codes to the character produced on its own,
+//! with Shift, and with AltGr (right Alt). A zero entry means the key
+//! produces nothing in that column; characters outside ASCII, such as the
+//! German umlauts or the pound sign, are left as zero since the console
+//! only carries bytes.
+
+pub struct KeyboardLayout {
+    pub name: &'static str,
+    pub base: [u8; 0x80],
+    pub shifted: [u8; 0x80],
+    pub altgr: [u8; 0x80],
+}
+
+impl KeyboardLayout {
+    /// Character for `make_code` in the selected column, or 0.
+    pub fn lookup(&self, make_code: u8, shift: bool, altgr: bool) -> u8 {
+        let index = make_code as usize;
+        if index >= self.base.len() {
+            return 0;
+        }
+        if altgr {
+            return self.altgr[index];
+        }
+        if shift {
+            self.shifted[index]
+        } else {
+            self.base[index]
+        }
+    }
+}
+
+/// Build a table where `row[i]` is the character for make code `i`, then
+/// fill in keys outside the main block, such as the ISO key at 0x56.
+const fn keymap(row: &[u8], extra: &[(u8, u8)]) -> [u8; 0x80] {
+    let mut table = [0u8; 0x80];
+    let mut i = 0;
+    while i < row.len() {
+        table[i] = row[i];
+        i += 1;
+    }
+    let mut i = 0;
+    while i < extra.len() {
+        table[extra[i].0 as usize] = extra[i].1;
+        i += 1;
+    }
+    table
+}
+
+pub static LAYOUT_US: KeyboardLayout = KeyboardLayout {
+    name: "us",
+    base: keymap(
+        b"\x00\x001234567890-=\0\tqwertyuiop[]\0\0asdfghjkl;'`\0\\zxcvbnm,./\0\0\0 ",
+        &[],
+    ),
+    shifted: keymap(
+        b"\x00\x00!@#$%^&*()_+\0\0QWERTYUIOP{}\0\0ASDFGHJKL:\"~\0|ZXCVBNM<>?\0\0\0 ",
+        &[],
+    ),
+    altgr: keymap(b"", &[]),
+};
+
+pub static LAYOUT_UK: KeyboardLayout = KeyboardLayout {
+    name: "uk",
+    base: keymap(
+        b"\x00\x001234567890-=\0\tqwertyuiop[]\0\0asdfghjkl;'`\0#zxcvbnm,./\0\0\0 ",
+        &[(0x56, b'\\')],
+    ),
+    shifted: keymap(
+        b"\x00\x00!\"\0$%^&*()_+\0\0QWERTYUIOP{}\0\0ASDFGHJKL:@\0\0~ZXCVBNM<>?\0\0\0 ",
+        &[(0x56, b'|')],
+    ),
+    altgr: keymap(b"", &[]),
+};
+
+pub static LAYOUT_DE: KeyboardLayout = KeyboardLayout {
+    name: "de",
+    base: keymap(
+        b"\x00\x001234567890\0\0\0\tqwertzuiop\0+\0\0asdfghjkl\0\0^\0#yxcvbnm,.-\0\0\0 ",
+        &[(0x56, b'<')],
+    ),
+    shifted: keymap(
+        b"\x00\x00!\"\0$%&/()=?`\0\0QWERTZUIOP\0*\0\0ASDFGHJKL\0\0\0\0'YXCVBNM;:_\0\0\0 ",
+        &[(0x56, b'>')],
+    ),
+    altgr: keymap(
+        b"",
+        &[
+            (0x08, b'{'),
+            (0x09, b'['),
+            (0x0A, b']'),
+            (0x0B, b'}'),
+            (0x0C, b'\\'),
+            (0x10, b'@'),
+            (0x1B, b'~'),
+            (0x56, b'|'),
+        ],
+    ),
+};
+
+static LAYOUTS: [&KeyboardLayout; 3] = [&LAYOUT_US, &LAYOUT_UK, &LAYOUT_DE];
+
+/// Look a compiled-in layout up by name ("us", "uk" or "de").
+pub fn layout_by_name(name: &str) -> Option<&'static KeyboardLayout> {
+    LAYOUTS.iter().copied().find(|layout| layout.name == name)
+}
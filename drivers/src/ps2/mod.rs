@@ -34,6 +34,7 @@
 //! | 7   | PARE | Parity error |
 
 pub mod keyboard;
+pub mod layout;
 pub mod mouse;
 
 use slopos_lib::cpu;
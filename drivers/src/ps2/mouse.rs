@@ -1,4 +1,4 @@
-use slopos_lib::{IrqMutex, klog_debug, klog_info};
+use slopos_lib::{IrqMutex, RingBuffer, klog_debug_mod, klog_info};
 
 use crate::input_event::{self, get_timestamp_ms};
 use crate::ps2;
@@ -7,12 +7,92 @@ pub const BUTTON_LEFT: u8 = 0x01;
 pub const BUTTON_RIGHT: u8 = 0x02;
 pub const BUTTON_MIDDLE: u8 = 0x04;
 
+const PACKET_BUTTONS: u8 = 0x07;
+/// Always set in the first byte of a packet; a byte without it cannot start one.
+const PACKET_SYNC: u8 = 0x08;
+const PACKET_X_SIGN: u8 = 0x10;
+const PACKET_Y_SIGN: u8 = 0x20;
+const PACKET_OVERFLOW: u8 = 0xC0;
+
+const EVENT_QUEUE_SIZE: usize = 64;
+
+/// One decoded movement packet. `dy` grows downwards, like screen rows.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MouseEvent {
+    pub dx: i16,
+    pub dy: i16,
+    pub buttons: u8,
+}
+
+/// Reassembles the 3-byte packets the mouse streams one byte per IRQ.
+#[derive(Default)]
+pub struct PacketDecoder {
+    packet: [u8; 3],
+    len: u8,
+}
+
+impl PacketDecoder {
+    pub const fn new() -> Self {
+        Self {
+            packet: [0; 3],
+            len: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    /// Take the next byte from the device, returning the event it completes.
+    ///
+    /// A byte that arrives where a packet should start but lacks the sync
+    /// bit is dropped, so a lost byte costs one packet instead of leaving
+    /// every later packet shifted.
+    pub fn feed(&mut self, byte: u8) -> Option<MouseEvent> {
+        if self.len == 0 && byte & PACKET_SYNC == 0 {
+            klog_debug_mod!(
+                "drivers",
+                "[MOUSE] Dropping out-of-sync byte: 0x{:02x}",
+                byte
+            );
+            return None;
+        }
+        self.packet[self.len as usize] = byte;
+        self.len += 1;
+        if (self.len as usize) < self.packet.len() {
+            return None;
+        }
+        self.len = 0;
+
+        let [flags, dx, dy] = self.packet;
+        if flags & PACKET_OVERFLOW != 0 {
+            klog_debug_mod!("drivers", "[MOUSE] Invalid packet flags: 0x{:02x}", flags);
+            return None;
+        }
+        Some(MouseEvent {
+            dx: delta(dx, flags & PACKET_X_SIGN != 0),
+            dy: -delta(dy, flags & PACKET_Y_SIGN != 0),
+            buttons: flags & PACKET_BUTTONS,
+        })
+    }
+}
+
+/// Widen a 9-bit two's complement movement whose sign bit lives in byte 0.
+fn delta(raw: u8, negative: bool) -> i16 {
+    if negative {
+        raw as i16 - 256
+    } else {
+        raw as i16
+    }
+}
+
 struct MouseState {
     x: i32,
     y: i32,
     buttons: u8,
-    packet_byte: u8,
-    packet: [u8; 3],
+    decoder: PacketDecoder,
+    events: RingBuffer<MouseEvent, EVENT_QUEUE_SIZE>,
     max_x: i32,
     max_y: i32,
 }
@@ -23,8 +103,12 @@ impl MouseState {
             x: 0,
             y: 0,
             buttons: 0,
-            packet_byte: 0,
-            packet: [0; 3],
+            decoder: PacketDecoder::new(),
+            events: RingBuffer::new_overwrite_with(MouseEvent {
+                dx: 0,
+                dy: 0,
+                buttons: 0,
+            }),
             max_x: 1920,
             max_y: 1080,
         }
@@ -37,7 +121,7 @@ pub fn init() {
     klog_info!("Initializing PS/2 mouse...");
 
     let mut config = ps2::read_config();
-    klog_debug!("PS/2 controller status: 0x{:02x}", config);
+    klog_debug_mod!("drivers", "PS/2 controller status: 0x{:02x}", config);
 
     ps2::write_command(ps2::CMD_ENABLE_AUX);
 
@@ -60,7 +144,8 @@ pub fn init() {
         let mut state = STATE.lock();
         state.x = state.max_x / 2;
         state.y = state.max_y / 2;
-        state.packet_byte = 0;
+        state.decoder.reset();
+        state.events.reset();
         (state.x, state.y)
     };
 
@@ -79,38 +164,15 @@ pub fn set_bounds(width: i32, height: i32) {
 
 pub fn handle_irq(data: u8) {
     let mut state = STATE.lock();
-    let byte_num = state.packet_byte;
-
-    state.packet[byte_num as usize] = data;
-    state.packet_byte = (byte_num + 1) % 3;
-
-    if state.packet_byte != 0 {
-        return;
-    }
-
-    let packet_flags = state.packet[0];
-    let dx_raw = state.packet[1];
-    let dy_raw = state.packet[2];
-
-    if packet_flags & 0xC0 != 0 {
-        klog_debug!("[MOUSE] Invalid packet flags: 0x{:02x}", packet_flags);
+    let Some(event) = state.decoder.feed(data) else {
         return;
-    }
+    };
+    // The queue drops its oldest event when nobody drains it.
+    state.events.push(event);
 
+    let MouseEvent { dx, dy, buttons } = event;
     let old_buttons = state.buttons;
-    state.buttons = packet_flags & 0x07;
-
-    let mut dx = dx_raw as i16;
-    if packet_flags & 0x10 != 0 {
-        dx = (dx as i16) - 256;
-    }
-
-    let mut dy = dy_raw as i16;
-    if packet_flags & 0x20 != 0 {
-        dy = (dy as i16) - 256;
-    }
-
-    dy = -dy;
+    state.buttons = buttons;
 
     state.x += dx as i32;
     state.y += dy as i32;
@@ -147,3 +209,8 @@ pub fn get_position() -> (i32, i32) {
 pub fn get_buttons() -> u8 {
     STATE.lock().buttons
 }
+
+/// Pop the oldest queued movement event, if any.
+pub fn mouse_poll_event() -> Option<MouseEvent> {
+    STATE.lock().events.try_pop()
+}
@@ -1,8 +1,22 @@
-use slopos_lib::tsc;
+use core::arch::asm;
+
+use slopos_lib::{cpu, tsc};
 use spin::{Mutex, Once};
 
 const DEFAULT_LFSR_SEED: u64 = 0xACE1u64;
 
+/// CPUID leaf 1, ECX bit 30: RDRAND instruction support.
+const CPUID_FEAT_ECX_RDRAND: u32 = 1 << 30;
+/// CPUID leaf 7, subleaf 0, EBX bit 18: RDSEED instruction support.
+const CPUID_FEAT_EBX_RDSEED: u32 = 1 << 18;
+
+/// Attempts before giving up on RDRAND, as recommended by Intel's DRNG guide.
+const RDRAND_RETRIES: u32 = 10;
+/// RDSEED drains faster than RDRAND refills, so it gets more attempts.
+const RDSEED_RETRIES: u32 = 100;
+/// Software draws between reseeds of the LFSR from RDSEED.
+const RESEED_INTERVAL: u32 = 1024;
+
 #[derive(Clone, Copy)]
 pub struct Lfsr64 {
     state: u64,
@@ -29,10 +43,176 @@ impl Lfsr64 {
     }
 }
 
-static RNG: Once<Mutex<Lfsr64>> = Once::new();
+#[derive(Clone, Copy)]
+struct HwRngFeatures {
+    rdrand: bool,
+    rdseed: bool,
+}
+
+static HW_FEATURES: Once<HwRngFeatures> = Once::new();
 
+fn hw_features() -> HwRngFeatures {
+    *HW_FEATURES.call_once(|| {
+        let (_, _, ecx, _) = cpu::cpuid(1);
+        let (max_leaf, _, _, _) = cpu::cpuid(0);
+        let rdseed = if max_leaf >= 7 {
+            let (_, ebx, _, _) = cpu::cpuid(7);
+            (ebx & CPUID_FEAT_EBX_RDSEED) != 0
+        } else {
+            false
+        };
+        HwRngFeatures {
+            rdrand: (ecx & CPUID_FEAT_ECX_RDRAND) != 0,
+            rdseed,
+        }
+    })
+}
+
+/// True when the CPU has RDRAND, so `random_next` is hardware backed.
+pub fn random_hw_available() -> bool {
+    hw_features().rdrand
+}
+
+pub(crate) fn rdrand64() -> Option<u64> {
+    if !hw_features().rdrand {
+        return None;
+    }
+    for _ in 0..RDRAND_RETRIES {
+        let value: u64;
+        let ok: u8;
+        unsafe {
+            asm!(
+                "rdrand {value}",
+                "setc {ok}",
+                value = out(reg) value,
+                ok = out(reg_byte) ok,
+                options(nomem, nostack)
+            );
+        }
+        if ok != 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn rdseed64() -> Option<u64> {
+    if !hw_features().rdseed {
+        return None;
+    }
+    for _ in 0..RDSEED_RETRIES {
+        let value: u64;
+        let ok: u8;
+        unsafe {
+            asm!(
+                "rdseed {value}",
+                "setc {ok}",
+                value = out(reg) value,
+                ok = out(reg_byte) ok,
+                options(nomem, nostack)
+            );
+        }
+        if ok != 0 {
+            return Some(value);
+        }
+        cpu::pause();
+    }
+    None
+}
+
+/// Software fallback, reseeded from RDSEED every `RESEED_INTERVAL` draws.
+struct SoftRng {
+    lfsr: Lfsr64,
+    draws: u32,
+}
+
+impl SoftRng {
+    fn new() -> Self {
+        let lfsr = match rdseed64() {
+            Some(seed) => Lfsr64::with_seed(seed),
+            None => Lfsr64::from_tsc(),
+        };
+        Self { lfsr, draws: 0 }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.draws += 1;
+        if self.draws >= RESEED_INTERVAL {
+            self.draws = 0;
+            if let Some(seed) = rdseed64() {
+                self.lfsr = Lfsr64::with_seed(self.lfsr.next() ^ seed);
+            }
+        }
+        self.lfsr.next()
+    }
+}
+
+static RNG: Once<Mutex<SoftRng>> = Once::new();
+
+/// Next 64 random bits: RDRAND when available, the software PRNG otherwise.
 pub fn random_next() -> u64 {
-    RNG.call_once(|| Mutex::new(Lfsr64::from_tsc()));
+    if let Some(value) = rdrand64() {
+        return value;
+    }
+    RNG.call_once(|| Mutex::new(SoftRng::new()));
     let rng = RNG.get().expect("RNG missing");
     rng.lock().next()
 }
+
+/// Fill `out` with bytes from `random_next`.
+pub fn random_bytes(out: &mut [u8]) {
+    for chunk in out.chunks_mut(8) {
+        let bytes = random_next().to_le_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
+
+/// Bits that are set, in parts per thousand (500 for a perfectly balanced sample).
+pub fn monobit(bytes: &[u8]) -> u32 {
+    if bytes.is_empty() {
+        return 0;
+    }
+    let ones: u64 = bytes.iter().map(|b| b.count_ones() as u64).sum();
+    let bits = bytes.len() as u64 * 8;
+    ((ones * 1000) / bits) as u32
+}
+
+/// Length of the longest run of identical bits, scanning each byte MSB first.
+pub fn longest_run(bytes: &[u8]) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut last = None;
+    for byte in bytes {
+        for shift in (0..8).rev() {
+            let bit = (byte >> shift) & 1;
+            if last == Some(bit) {
+                current += 1;
+            } else {
+                current = 1;
+                last = Some(bit);
+            }
+            longest = longest.max(current);
+        }
+    }
+    longest
+}
+
+/// Accepted monobit band for a sample of at least `SANITY_MIN_BYTES`.
+pub const SANITY_MONOBIT_RANGE: core::ops::RangeInclusive<u32> = 450..=550;
+/// Longest bit run tolerated before the sample is considered degenerate.
+pub const SANITY_MAX_RUN: usize = 32;
+/// Smallest sample `entropy_looks_sane` will judge.
+pub const SANITY_MIN_BYTES: usize = 64;
+
+/// Flag obviously broken output: constant bytes, strong bias, or long runs.
+///
+/// This is a smoke test, not a statistical certification.
+pub fn entropy_looks_sane(bytes: &[u8]) -> bool {
+    if bytes.len() < SANITY_MIN_BYTES {
+        return false;
+    }
+    if bytes.iter().all(|&b| b == bytes[0]) {
+        return false;
+    }
+    SANITY_MONOBIT_RANGE.contains(&monobit(bytes)) && longest_run(bytes) <= SANITY_MAX_RUN
+}
@@ -0,0 +1,121 @@
+//! Random tests - statistics helpers and output quality smoke checks.
+
+use slopos_lib::testing::TestResult;
+use slopos_lib::{assert_eq_test, assert_test, klog_info};
+
+use crate::random::{
+    Lfsr64, entropy_looks_sane, longest_run, monobit, random_bytes, random_hw_available,
+    random_next, rdrand64,
+};
+
+const SAMPLE_BYTES: usize = 512;
+
+fn fill_from(mut next: impl FnMut() -> u64, out: &mut [u8]) {
+    for chunk in out.chunks_mut(8) {
+        let bytes = next().to_le_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
+
+pub fn test_random_stats_all_zero() -> TestResult {
+    let bytes = [0u8; 16];
+    assert_eq_test!(monobit(&bytes), 0);
+    assert_eq_test!(longest_run(&bytes), 128);
+    assert_test!(
+        !entropy_looks_sane(&[0u8; 128]),
+        "all zeros must be flagged"
+    );
+    TestResult::Pass
+}
+
+pub fn test_random_stats_all_one() -> TestResult {
+    let bytes = [0xFFu8; 16];
+    assert_eq_test!(monobit(&bytes), 1000);
+    assert_eq_test!(longest_run(&bytes), 128);
+    assert_test!(
+        !entropy_looks_sane(&[0xFFu8; 128]),
+        "all ones must be flagged"
+    );
+    TestResult::Pass
+}
+
+pub fn test_random_stats_balanced() -> TestResult {
+    // 0b01010101 alternates every bit; 0x0F alternates every nibble
+    assert_eq_test!(monobit(&[0x55u8; 16]), 500);
+    assert_eq_test!(longest_run(&[0x55u8; 16]), 1);
+    assert_eq_test!(monobit(&[0x0Fu8; 16]), 500);
+    assert_eq_test!(longest_run(&[0x0Fu8; 16]), 4);
+    // Runs carry across byte boundaries: 0x01 0x80 -> ...0001 1000...
+    assert_eq_test!(longest_run(&[0x01, 0x80]), 7);
+    assert_eq_test!(monobit(&[]), 0);
+    assert_eq_test!(longest_run(&[]), 0);
+    TestResult::Pass
+}
+
+pub fn test_random_constant_pattern_flagged() -> TestResult {
+    // Balanced but constant output is still broken
+    assert_test!(
+        !entropy_looks_sane(&[0xA5u8; 128]),
+        "constant bytes flagged"
+    );
+    // Strong bias: 7 of 8 bits set
+    let mut biased = [0xFEu8; 128];
+    for b in biased.iter_mut().skip(1).step_by(2) {
+        *b = 0xEF;
+    }
+    assert_test!(!entropy_looks_sane(&biased), "bias flagged");
+    TestResult::Pass
+}
+
+pub fn test_random_lfsr_output_sane() -> TestResult {
+    let mut bytes = [0u8; SAMPLE_BYTES];
+    let mut rng = Lfsr64::with_seed(0x1234_5678_9ABC_DEF0);
+    fill_from(|| rng.next(), &mut bytes);
+    assert_test!(entropy_looks_sane(&bytes), "seeded LFSR output degenerate");
+    TestResult::Pass
+}
+
+pub fn test_random_bytes_kib_varies() -> TestResult {
+    let mut bytes = [0u8; 1024];
+    random_bytes(&mut bytes);
+    assert_test!(
+        bytes.iter().any(|&b| b != 0),
+        "1 KiB of random bytes all zero"
+    );
+    assert_test!(
+        bytes.iter().any(|&b| b != bytes[0]),
+        "1 KiB of random bytes constant"
+    );
+    // An unaligned tail must still be filled
+    let mut tail = [0u8; 13];
+    random_bytes(&mut tail);
+    assert_test!(tail.iter().any(|&b| b != 0), "odd-length fill left zeros");
+    TestResult::Pass
+}
+
+pub fn test_random_hw_rdrand_delivers() -> TestResult {
+    let (first, second) = (rdrand64(), rdrand64());
+    assert_eq_test!(
+        first.is_some() && second.is_some(),
+        random_hw_available(),
+        "RDRAND yields values exactly when reported available"
+    );
+    assert_test!(first.is_none() || first != second, "RDRAND stuck");
+    // Either way random_next keeps moving
+    assert_test!(random_next() != random_next(), "random_next stuck");
+    TestResult::Pass
+}
+
+pub fn test_random_next_output_sane() -> TestResult {
+    let mut bytes = [0u8; SAMPLE_BYTES];
+    fill_from(random_next, &mut bytes);
+    if !entropy_looks_sane(&bytes) {
+        klog_info!(
+            "RANDOM_TEST: random_next degenerate: monobit={} longest_run={}",
+            monobit(&bytes),
+            longest_run(&bytes)
+        );
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
@@ -0,0 +1,283 @@
+//! CMOS real-time clock.
+//!
+//! The RTC keeps wall-clock time across reboots. Its registers are read
+//! through the CMOS index/data port pair; depending on status register B the
+//! values are BCD or binary and the hour is 12- or 24-hour. A read can race
+//! the once-per-second update, so registers are sampled outside the
+//! update-in-progress window and re-read until two samples agree.
+//!
+//! Reading the RTC is slow and only has one-second resolution, so the wall
+//! clock anchors on a single RTC read and interpolates from the TSC after
+//! that. Re-anchoring would throw away the fraction of a second the TSC has
+//! already counted and make the clock stall until the RTC caught up.
+
+use core::ptr::read_unaligned;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use spin::Once;
+
+use slopos_lib::clock::{clock_tsc_hz, cycles_to_ns};
+use slopos_lib::ports::{CMOS_ADDRESS, CMOS_DATA};
+use slopos_lib::{IrqMutex, tsc};
+
+use crate::ioapic::acpi_lookup_table;
+
+const RTC_REG_SECONDS: u8 = 0x00;
+const RTC_REG_MINUTES: u8 = 0x02;
+const RTC_REG_HOURS: u8 = 0x04;
+const RTC_REG_DAY: u8 = 0x07;
+const RTC_REG_MONTH: u8 = 0x08;
+const RTC_REG_YEAR: u8 = 0x09;
+const RTC_REG_STATUS_A: u8 = 0x0A;
+const RTC_REG_STATUS_B: u8 = 0x0B;
+/// Century register at the usual location, used when there is no FADT to
+/// say where it is.
+const RTC_REG_CENTURY: u8 = 0x32;
+/// Offset of the century field in the ACPI `FACP` table (FADT): the CMOS
+/// index of the century register, or 0 when the RTC has none.
+const FADT_CENTURY_OFFSET: usize = 108;
+
+/// Status A: an update cycle is in progress, registers are unstable
+const RTC_STATUS_A_UIP: u8 = 0x80;
+/// Status B: hour register is 24-hour
+pub const RTC_STATUS_B_24H: u8 = 0x02;
+/// Status B: registers are binary rather than BCD
+pub const RTC_STATUS_B_BINARY: u8 = 0x04;
+/// 12-hour mode: PM flag in the hour register
+const RTC_HOUR_PM: u8 = 0x80;
+
+/// Setting bit 7 of the CMOS index keeps NMIs masked during the access
+const CMOS_NMI_DISABLE: u8 = 0x80;
+
+/// Assumed century when the century register is absent or implausible.
+const RTC_CENTURY_BASE: u16 = 2000;
+/// Century register values accepted as real (1970..=9999).
+const RTC_CENTURY_VALID: core::ops::RangeInclusive<u8> = 19..=99;
+
+/// Bound on spins waiting for an update cycle (~244us on real hardware)
+const RTC_UIP_MAX_SPINS: u32 = 100_000;
+/// Bound on re-reads while looking for two identical samples
+const RTC_MAX_READ_ATTEMPTS: u32 = 8;
+
+/// Serializes index/data sequences on the CMOS ports.
+static CMOS_LOCK: IrqMutex<()> = IrqMutex::new(());
+
+/// CMOS index of the century register, 0 when there is none.
+static CENTURY_REG: Once<u8> = Once::new();
+
+/// The RTC reading the wall clock counts from and the TSC value at it.
+#[derive(Clone, Copy)]
+struct WallAnchor {
+    unix_secs: u64,
+    tsc: u64,
+}
+
+static WALL_ANCHOR: IrqMutex<Option<WallAnchor>> = IrqMutex::new(None);
+/// Last wall time handed out in microseconds, so the clock never steps back
+static LAST_WALL_US: AtomicU64 = AtomicU64::new(0);
+
+/// Calendar date and time as reported by the RTC.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RtcTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+/// Raw RTC time registers, before BCD and 12-hour decoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RtcRegisters {
+    pub seconds: u8,
+    pub minutes: u8,
+    pub hours: u8,
+    pub day: u8,
+    pub month: u8,
+    pub year: u8,
+    /// Raw century register, or 0 when the firmware has none.
+    pub century: u8,
+}
+
+/// Convert a packed BCD byte to binary (0x59 -> 59).
+///
+/// Nibbles above 9 are not valid BCD; they are clamped to 9 so a corrupt
+/// register still decodes to something in 0..=99.
+#[inline]
+pub fn bcd_to_bin(v: u8) -> u8 {
+    let tens = (v >> 4).min(9);
+    let ones = (v & 0x0F).min(9);
+    tens * 10 + ones
+}
+
+impl RtcTime {
+    /// Decode raw registers using the format bits from status register B.
+    pub fn from_registers(regs: &RtcRegisters, status_b: u8) -> Self {
+        let binary = status_b & RTC_STATUS_B_BINARY != 0;
+        let decode = |v: u8| if binary { v } else { bcd_to_bin(v) };
+
+        let pm = status_b & RTC_STATUS_B_24H == 0 && regs.hours & RTC_HOUR_PM != 0;
+        let mut hour = decode(regs.hours & !RTC_HOUR_PM);
+        if status_b & RTC_STATUS_B_24H == 0 {
+            // 12-hour clock: 12 AM is midnight, 12 PM is noon
+            hour %= 12;
+            if pm {
+                hour += 12;
+            }
+        }
+
+        // A missing century register reads as 0 or 0xFF; fall back then.
+        let century = decode(regs.century);
+        let century_base = if regs.century != 0 && RTC_CENTURY_VALID.contains(&century) {
+            century as u16 * 100
+        } else {
+            RTC_CENTURY_BASE
+        };
+
+        Self {
+            year: century_base + decode(regs.year) as u16,
+            month: decode(regs.month),
+            day: decode(regs.day),
+            hour,
+            minute: decode(regs.minutes),
+            second: decode(regs.seconds),
+        }
+    }
+
+    /// Seconds since the Unix epoch. Dates before 1970 clamp to 0.
+    pub fn to_unix(&self) -> u64 {
+        let days = days_from_civil(self.year as i64, self.month as i64, self.day as i64);
+        let secs =
+            days * 86_400 + self.hour as i64 * 3_600 + self.minute as i64 * 60 + self.second as i64;
+        secs.max(0) as u64
+    }
+}
+
+/// Days from 1970-01-01 to the given proleptic Gregorian date.
+///
+/// Counts from a March-based year so the leap day falls at the end of it
+/// (Howard Hinnant's `days_from_civil`).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[inline]
+fn cmos_read(reg: u8) -> u8 {
+    unsafe {
+        CMOS_ADDRESS.write(CMOS_NMI_DISABLE | reg);
+        CMOS_DATA.read()
+    }
+}
+
+fn rtc_wait_update_done() {
+    let mut spins = 0;
+    while cmos_read(RTC_REG_STATUS_A) & RTC_STATUS_A_UIP != 0 && spins < RTC_UIP_MAX_SPINS {
+        core::hint::spin_loop();
+        spins += 1;
+    }
+}
+
+/// Century register index from a raw FADT. A table too short to carry the
+/// field predates it and has no century register either.
+pub fn fadt_century_register(fadt: &[u8]) -> u8 {
+    fadt.get(FADT_CENTURY_OFFSET).copied().unwrap_or(0)
+}
+
+fn rtc_century_register() -> u8 {
+    *CENTURY_REG.call_once(|| {
+        let table = acpi_lookup_table(b"FACP");
+        if table.is_null() {
+            return RTC_REG_CENTURY;
+        }
+        let length = unsafe { read_unaligned(table.add(4) as *const u32) } as usize;
+        fadt_century_register(unsafe { core::slice::from_raw_parts(table, length) })
+    })
+}
+
+fn rtc_read_registers(century_reg: u8) -> RtcRegisters {
+    rtc_wait_update_done();
+    RtcRegisters {
+        seconds: cmos_read(RTC_REG_SECONDS),
+        minutes: cmos_read(RTC_REG_MINUTES),
+        hours: cmos_read(RTC_REG_HOURS),
+        day: cmos_read(RTC_REG_DAY),
+        month: cmos_read(RTC_REG_MONTH),
+        year: cmos_read(RTC_REG_YEAR),
+        century: if century_reg != 0 {
+            cmos_read(century_reg)
+        } else {
+            0
+        },
+    }
+}
+
+/// Read the current wall-clock time from the CMOS RTC. When the FADT says
+/// there is no century register the year is taken to be in the 2000s.
+pub fn rtc_read() -> RtcTime {
+    let century_reg = rtc_century_register();
+    let _guard = CMOS_LOCK.lock();
+
+    let mut regs = rtc_read_registers(century_reg);
+    for _ in 0..RTC_MAX_READ_ATTEMPTS {
+        let again = rtc_read_registers(century_reg);
+        if again == regs {
+            break;
+        }
+        regs = again;
+    }
+    let status_b = cmos_read(RTC_REG_STATUS_B);
+    RtcTime::from_registers(&regs, status_b)
+}
+
+/// Read the current wall-clock time as seconds since the Unix epoch.
+pub fn rtc_read_unix() -> u64 {
+    rtc_read().to_unix()
+}
+
+/// Wall time `now_tsc` in microseconds, interpolated from an RTC reading of
+/// `anchor_secs` taken at `anchor_tsc`. Without a TSC frequency the anchor
+/// is all there is.
+pub fn wall_time_us(anchor_secs: u64, anchor_tsc: u64, now_tsc: u64, tsc_hz: u64) -> u64 {
+    let elapsed_ns = cycles_to_ns(now_tsc.wrapping_sub(anchor_tsc), tsc_hz);
+    anchor_secs
+        .saturating_mul(1_000_000)
+        .saturating_add(elapsed_ns / 1_000)
+}
+
+/// Current wall-clock time as (seconds, microseconds) since the Unix epoch.
+/// Never goes backwards. Without a TSC frequency every call reads the RTC.
+pub fn rtc_gettimeofday() -> (u64, u32) {
+    let tsc_hz = clock_tsc_hz();
+    let anchored = if tsc_hz != 0 {
+        *WALL_ANCHOR.lock()
+    } else {
+        None
+    };
+    let anchor = match anchored {
+        Some(a) => a,
+        None => {
+            // The RTC read spins through update cycles; keep it unlocked.
+            let a = WallAnchor {
+                unix_secs: rtc_read_unix(),
+                tsc: tsc::rdtsc(),
+            };
+            if tsc_hz != 0 {
+                *WALL_ANCHOR.lock().get_or_insert(a)
+            } else {
+                a
+            }
+        }
+    };
+
+    let now = tsc::rdtsc();
+    let us = wall_time_us(anchor.unix_secs, anchor.tsc, now, tsc_hz);
+    let us = LAST_WALL_US.fetch_max(us, Ordering::Relaxed).max(us);
+    (us / 1_000_000, (us % 1_000_000) as u32)
+}
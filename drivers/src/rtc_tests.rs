@@ -0,0 +1,206 @@
+//! RTC tests - BCD conversion and register decoding.
+
+use slopos_lib::clock::{clock_tsc_hz, monotonic_ns};
+use slopos_lib::testing::TestResult;
+use slopos_lib::{assert_eq_test, assert_test};
+
+use crate::rtc::{
+    RTC_STATUS_B_24H, RTC_STATUS_B_BINARY, RtcRegisters, RtcTime, bcd_to_bin,
+    fadt_century_register, rtc_gettimeofday, rtc_read, wall_time_us,
+};
+
+pub fn test_rtc_bcd_to_bin() -> TestResult {
+    assert_eq_test!(bcd_to_bin(0x00), 0);
+    assert_eq_test!(bcd_to_bin(0x09), 9);
+    assert_eq_test!(bcd_to_bin(0x10), 10);
+    assert_eq_test!(bcd_to_bin(0x59), 59);
+    assert_eq_test!(bcd_to_bin(0x99), 99);
+    TestResult::Pass
+}
+
+pub fn test_rtc_bcd_invalid_clamped() -> TestResult {
+    assert_eq_test!(bcd_to_bin(0x5A), 59, "invalid ones digit clamped");
+    assert_eq_test!(bcd_to_bin(0xA5), 95, "invalid tens digit clamped");
+    assert_eq_test!(bcd_to_bin(0xFF), 99, "all-ones stays in range");
+    TestResult::Pass
+}
+
+pub fn test_rtc_decode_bcd_24h() -> TestResult {
+    let regs = RtcRegisters {
+        seconds: 0x45,
+        minutes: 0x30,
+        hours: 0x23,
+        day: 0x31,
+        month: 0x12,
+        year: 0x24,
+        century: 0,
+    };
+    let time = RtcTime::from_registers(&regs, RTC_STATUS_B_24H);
+    assert_eq_test!(
+        time,
+        RtcTime {
+            year: 2024,
+            month: 12,
+            day: 31,
+            hour: 23,
+            minute: 30,
+            second: 45,
+        }
+    );
+    TestResult::Pass
+}
+
+pub fn test_rtc_decode_binary_12h() -> TestResult {
+    let mut regs = RtcRegisters {
+        seconds: 5,
+        minutes: 59,
+        hours: 0x80 | 12,
+        day: 1,
+        month: 2,
+        year: 26,
+        century: 0,
+    };
+    let time = RtcTime::from_registers(&regs, RTC_STATUS_B_BINARY);
+    assert_eq_test!(time.hour, 12, "12 PM is noon");
+    assert_eq_test!(time.year, 2026);
+    assert_eq_test!(time.minute, 59);
+
+    regs.hours = 12;
+    let time = RtcTime::from_registers(&regs, RTC_STATUS_B_BINARY);
+    assert_eq_test!(time.hour, 0, "12 AM is midnight");
+
+    regs.hours = 0x80 | 7;
+    let time = RtcTime::from_registers(&regs, RTC_STATUS_B_BINARY);
+    assert_eq_test!(time.hour, 19, "7 PM");
+    TestResult::Pass
+}
+
+pub fn test_rtc_decode_bcd_to_unix() -> TestResult {
+    let mut regs = RtcRegisters {
+        seconds: 0x45,
+        minutes: 0x30,
+        hours: 0x23,
+        day: 0x31,
+        month: 0x12,
+        year: 0x24,
+        century: 0x20,
+    };
+    let time = RtcTime::from_registers(&regs, RTC_STATUS_B_24H);
+    assert_eq_test!(time.to_unix(), 1_735_687_845, "2024-12-31 23:30:45");
+
+    regs.century = 0x19;
+    regs.year = 0x99;
+    regs.minutes = 0x59;
+    regs.seconds = 0x59;
+    let time = RtcTime::from_registers(&regs, RTC_STATUS_B_24H);
+    assert_eq_test!(time.year, 1999, "century register honoured");
+    assert_eq_test!(time.to_unix(), 946_684_799);
+
+    let leap = RtcRegisters {
+        seconds: 0,
+        minutes: 0,
+        hours: 0,
+        day: 0x29,
+        month: 0x02,
+        year: 0x00,
+        century: 0x20,
+    };
+    let time = RtcTime::from_registers(&leap, RTC_STATUS_B_24H);
+    assert_eq_test!(time.to_unix(), 951_782_400, "2000-02-29");
+    TestResult::Pass
+}
+
+pub fn test_rtc_missing_century_assumes_2000s() -> TestResult {
+    let mut regs = RtcRegisters {
+        seconds: 0,
+        minutes: 0,
+        hours: 0,
+        day: 0x01,
+        month: 0x01,
+        year: 0x26,
+        century: 0,
+    };
+    let time = RtcTime::from_registers(&regs, RTC_STATUS_B_24H);
+    assert_eq_test!(time.year, 2026, "absent register reads as 0");
+
+    regs.century = 0xFF;
+    let time = RtcTime::from_registers(&regs, RTC_STATUS_B_24H);
+    assert_eq_test!(time.year, 2026, "floating bus reads as 0xFF");
+
+    regs.century = 0x05;
+    let time = RtcTime::from_registers(&regs, RTC_STATUS_B_24H);
+    assert_eq_test!(time.year, 2026, "implausible century ignored");
+    TestResult::Pass
+}
+
+pub fn test_rtc_fadt_century_register() -> TestResult {
+    let mut fadt = [0u8; 116];
+    assert_eq_test!(
+        fadt_century_register(&fadt),
+        0,
+        "century 0 means no register"
+    );
+    fadt[108] = 0x32;
+    assert_eq_test!(fadt_century_register(&fadt), 0x32);
+    assert_eq_test!(
+        fadt_century_register(&fadt[..108]),
+        0,
+        "table without the field has no register"
+    );
+    TestResult::Pass
+}
+
+pub fn test_rtc_wall_time_interpolates_tsc() -> TestResult {
+    let hz = 2_000_000_000;
+    assert_eq_test!(wall_time_us(100, 5_000, 5_000, hz), 100_000_000);
+    assert_eq_test!(
+        wall_time_us(100, 5_000, 5_000 + hz / 4, hz),
+        100_250_000,
+        "quarter second after the anchor"
+    );
+    assert_eq_test!(
+        wall_time_us(100, 5_000, 5_000 + hz, 0),
+        100_000_000,
+        "unknown TSC frequency adds nothing"
+    );
+
+    let (first_secs, first_us) = rtc_gettimeofday();
+    let (secs, us) = rtc_gettimeofday();
+    assert_test!(us < 1_000_000, "microseconds out of range");
+    assert_test!(
+        (secs, us) >= (first_secs, first_us),
+        "wall clock went backwards"
+    );
+    TestResult::Pass
+}
+
+pub fn test_rtc_wall_time_keeps_advancing() -> TestResult {
+    const WAIT_NS: u64 = 5_000_000;
+    if clock_tsc_hz() == 0 {
+        return TestResult::Pass;
+    }
+    // Several samples a few ms apart must each move the clock on; a clock
+    // that re-anchors on the RTC would stall right after each anchor.
+    for _ in 0..4 {
+        let (secs, us) = rtc_gettimeofday();
+        let before = secs * 1_000_000 + us as u64;
+        let start = monotonic_ns();
+        while monotonic_ns() - start < WAIT_NS {
+            core::hint::spin_loop();
+        }
+        let (secs, us) = rtc_gettimeofday();
+        let after = secs * 1_000_000 + us as u64;
+        assert_test!(after >= before + WAIT_NS / 2_000, "wall clock stalled");
+    }
+    TestResult::Pass
+}
+
+pub fn test_rtc_read_in_range() -> TestResult {
+    let time = rtc_read();
+    assert_test!((1..=12).contains(&time.month), "month out of range");
+    assert_test!((1..=31).contains(&time.day), "day out of range");
+    assert_test!(time.hour < 24, "hour out of range");
+    assert_test!(time.minute < 60, "minute out of range");
+    assert_test!(time.second < 60, "second out of range");
+    TestResult::Pass
+}
@@ -1,18 +1,23 @@
 use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicU32, Ordering};
 use slopos_lib::IrqMutex;
 use slopos_lib::RingBuffer;
 use slopos_lib::io::Port;
 use slopos_lib::ports::{
     COM1, UART_FCR_14_BYTE_THRESHOLD as FCR_14_BYTE_THRESHOLD, UART_FCR_CLEAR_RX as FCR_CLEAR_RX,
     UART_FCR_CLEAR_TX as FCR_CLEAR_TX, UART_FCR_ENABLE_FIFO as FCR_ENABLE_FIFO,
-    UART_IIR_FIFO_ENABLED as IIR_FIFO_ENABLED, UART_IIR_FIFO_MASK as IIR_FIFO_MASK,
-    UART_LCR_DLAB as LCR_DLAB, UART_LSR_DATA_READY as LSR_DATA_READY,
+    UART_IER_RX_AVAILABLE as IER_RX_AVAILABLE, UART_IIR_FIFO_ENABLED as IIR_FIFO_ENABLED,
+    UART_IIR_FIFO_MASK as IIR_FIFO_MASK, UART_LCR_DLAB as LCR_DLAB, UART_LSR_BREAK as LSR_BREAK,
+    UART_LSR_DATA_READY as LSR_DATA_READY, UART_LSR_FRAMING_ERROR as LSR_FRAMING_ERROR,
+    UART_LSR_OVERRUN as LSR_OVERRUN, UART_LSR_PARITY_ERROR as LSR_PARITY_ERROR,
     UART_LSR_TX_EMPTY as LSR_TX_EMPTY, UART_MCR_AUX2 as MCR_AUX2, UART_MCR_DTR as MCR_DTR,
     UART_MCR_RTS as MCR_RTS, UART_REG_IER as REG_IER, UART_REG_IIR as REG_IIR,
     UART_REG_LCR as REG_LCR, UART_REG_LSR as REG_LSR, UART_REG_MCR as REG_MCR,
     UART_REG_RBR as REG_RBR, UART_REG_SCR as REG_SCR,
 };
 
+use crate::tty;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UartType {
     Uart8250,
@@ -39,6 +44,13 @@ type SerialBuffer = RingBuffer<u8, BUF_SIZE>;
 
 static INPUT_BUFFER: IrqMutex<SerialBuffer> = IrqMutex::new(SerialBuffer::new_with(0));
 
+/// Line status bits that mark the byte being read as untrustworthy. An
+/// overrun is not among them: it means an earlier byte was lost, while the
+/// one in RBR is still good.
+const LSR_RX_ERRORS: u8 = LSR_PARITY_ERROR | LSR_FRAMING_ERROR | LSR_BREAK;
+
+static RX_ERRORS: AtomicU32 = AtomicU32::new(0);
+
 pub fn init() {
     let mut port = SERIAL.lock();
     unsafe { port.init() }
@@ -72,18 +84,85 @@ pub fn serial_putc_com1(ch: u8) {
     SERIAL.lock().write_byte(ch);
 }
 
+/// Write `bytes` to COM1 under a single lock acquisition.
+pub fn serial_write_com1(bytes: &[u8]) {
+    let mut serial = SERIAL.lock();
+    for &b in bytes {
+        serial.write_byte(b);
+    }
+}
+
 pub fn print_args(args: fmt::Arguments<'_>) {
     let _ = SERIAL.lock().write_fmt(args);
 }
 
+/// Turn on the receive-data-available interrupt of COM1.
+///
+/// Call once the IRQ4 handler is registered. Anything that arrived before is
+/// drained here, otherwise the line may stay raised and never edge again.
+pub fn serial_enable_rx_interrupt() {
+    SERIAL.lock().enable_rx_interrupt();
+    serial_poll_receive(COM1.address());
+}
+
+/// Move every byte waiting in the UART into the input buffer.
 pub fn serial_poll_receive(base: u16) {
     let port = Port::<u8>::new(base);
     let lsr = port.offset(REG_LSR);
     let rbr = port.offset(REG_RBR);
-    while unsafe { lsr.read() } & LSR_DATA_READY != 0 {
+    loop {
+        let status = unsafe { lsr.read() };
+        if status & LSR_DATA_READY == 0 {
+            break;
+        }
         let byte = unsafe { rbr.read() };
-        let mut buf = INPUT_BUFFER.lock();
-        let _ = buf.try_push(byte);
+        serial_rx_push(status, byte);
+    }
+}
+
+/// Queue `byte` read with line status `lsr`. A parity, framing or break
+/// error drops the byte; an overrun is counted but the byte is kept.
+/// Returns whether it was queued.
+pub fn serial_rx_push(lsr: u8, byte: u8) -> bool {
+    if lsr & (LSR_RX_ERRORS | LSR_OVERRUN) != 0 {
+        RX_ERRORS.fetch_add(1, Ordering::Relaxed);
+    }
+    if lsr & LSR_RX_ERRORS != 0 {
+        return false;
+    }
+    INPUT_BUFFER.lock().try_push(byte)
+}
+
+/// Number of receive line errors seen, including overruns.
+pub fn serial_rx_errors() -> u32 {
+    RX_ERRORS.load(Ordering::Relaxed)
+}
+
+/// Take the oldest received byte, if any.
+pub fn serial_read_byte() -> Option<u8> {
+    INPUT_BUFFER.lock().try_pop()
+}
+
+/// Block until a full line arrives and copy it into `buf` without the
+/// terminator. Sleeps on the TTY input wait queue between bytes. CR and LF both end a line, backspace and DEL erase the last
+/// byte, and bytes past the end of `buf` are discarded. Returns the length.
+pub fn serial_read_line(buf: &mut [u8]) -> usize {
+    let mut len = 0;
+    loop {
+        let Some(byte) = serial_read_byte() else {
+            tty::tty_wait_serial_input();
+            continue;
+        };
+        match byte {
+            b'\r' | b'\n' => return len,
+            0x08 | 0x7F => len = len.saturating_sub(1),
+            _ => {
+                if len < buf.len() {
+                    buf[len] = byte;
+                    len += 1;
+                }
+            }
+        }
     }
 }
 
@@ -194,6 +273,10 @@ impl SerialPort {
         self.reg(REG_MCR).write(MCR_DTR | MCR_RTS | MCR_AUX2);
     }
 
+    fn enable_rx_interrupt(&mut self) {
+        unsafe { self.reg(REG_IER).write(IER_RX_AVAILABLE) };
+    }
+
     fn write_byte(&mut self, byte: u8) {
         unsafe {
             while (self.reg(REG_LSR).read() & LSR_TX_EMPTY) == 0 {
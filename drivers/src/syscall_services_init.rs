@@ -1,12 +1,20 @@
 use slopos_abi::InputEvent;
 use slopos_abi::fate::FateResult;
+use slopos_abi::input::KeyEvent;
+use slopos_abi::syscall::{SYSCALL_KEYBOARD_POLL_EVENT, SYSCALL_KEYBOARD_SET_LAYOUT};
 
+use slopos_core::syscall::common::syscall_bounded_from_user;
+use slopos_core::syscall::register_syscall;
 use slopos_core::syscall_services::{
     FateServices, InputServices, TtyServices, register_fate_services, register_input_services,
     register_tty_services,
 };
+use slopos_core::{define_syscall, try_or_err};
+use slopos_lib::klog_warn;
+use slopos_mm::user_copy::copy_to_user;
+use slopos_mm::user_ptr::UserPtr;
 
-use crate::{fate, input_event, tty};
+use crate::{fate, input_event, keyboard, tty};
 
 static INPUT_SERVICES: InputServices = InputServices {
     poll: input_poll,
@@ -56,11 +64,34 @@ fn input_get_button_state() -> u32 {
     input_event::input_get_button_state() as u32
 }
 
+define_syscall!(syscall_keyboard_set_layout(ctx, args) requires system {
+    const NAME_MAX: usize = 8;
+    let mut name = [0u8; NAME_MAX];
+    let len = try_or_err!(
+        ctx,
+        syscall_bounded_from_user(&mut name, args.arg0, args.arg1, NAME_MAX)
+    );
+    if !keyboard::keyboard_set_layout_by_name(&name[..len]) {
+        return ctx.err();
+    }
+    ctx.ok(0)
+});
+
+define_syscall!(syscall_keyboard_poll_event(ctx, args) requires system {
+    let ptr = try_or_err!(ctx, UserPtr::<KeyEvent>::try_new(args.arg0));
+    let Some(event) = keyboard::keyboard_poll_event() else {
+        return ctx.ok(0);
+    };
+    try_or_err!(ctx, copy_to_user(ptr, &event));
+    ctx.ok(1)
+});
+
 static TTY_SERVICES: TtyServices = TtyServices {
     read_line: tty_read_line,
     read_char_blocking: tty_read_char_blocking,
     set_focus: tty_set_focus,
     get_focus: tty_get_focus,
+    set_mode: tty_set_mode,
 };
 
 fn tty_read_line(buf: *mut u8, len: usize) -> usize {
@@ -79,6 +110,10 @@ fn tty_get_focus() -> u32 {
     tty::tty_get_focus()
 }
 
+fn tty_set_mode(task_id: u32, canonical: bool) -> i32 {
+    tty::tty_set_mode_for_task(task_id, canonical)
+}
+
 static FATE_SERVICES: FateServices = FateServices {
     notify_outcome: fate_notify_outcome,
 };
@@ -91,4 +126,12 @@ pub fn init_syscall_services() {
     register_input_services(&INPUT_SERVICES);
     register_tty_services(&TTY_SERVICES);
     register_fate_services(&FATE_SERVICES);
+
+    // Keyboard syscalls live with the driver instead of the static table
+    if register_syscall(SYSCALL_KEYBOARD_SET_LAYOUT, syscall_keyboard_set_layout).is_err() {
+        klog_warn!("SYSCALL: keyboard_set_layout already registered");
+    }
+    if register_syscall(SYSCALL_KEYBOARD_POLL_EVENT, syscall_keyboard_poll_event).is_err() {
+        klog_warn!("SYSCALL: keyboard_poll_event already registered");
+    }
 }
@@ -1,6 +1,6 @@
 use core::ffi::c_int;
 use core::ptr;
-use core::sync::atomic::{AtomicU32, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicU32, Ordering};
 
 use slopos_lib::{IrqMutex, cpu, ports::COM1};
 
@@ -39,6 +39,305 @@ static TTY_FOCUS_QUEUE: IrqMutex<TtyWaitQueue> = IrqMutex::new(TtyWaitQueue {
     count: 0,
 });
 static TTY_FOCUSED_TASK_ID: AtomicU32 = AtomicU32::new(0);
+static TTY_CONSOLE: AtomicU8 = AtomicU8::new(TtyConsole::Framebuffer as u8);
+static TTY_CANONICAL: AtomicBool = AtomicBool::new(true);
+
+/// Ctrl-C as typed on either console.
+pub const TTY_CTRL_C: u8 = 0x03;
+
+/// Which devices back the TTY.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TtyConsole {
+    /// PS/2 keyboard and serial input; the shell draws into a window.
+    Framebuffer = 0,
+    /// Input and output both go over COM1; the display, if any, stays up.
+    Serial = 1,
+}
+
+/// Pick the console for this boot. `console=serial` on the cmdline moves the
+/// TTY to COM1 without taking the display down; without a framebuffer there
+/// is nothing else to use.
+pub fn tty_select_console(serial_requested: bool, has_framebuffer: bool) -> TtyConsole {
+    if serial_requested || !has_framebuffer {
+        TtyConsole::Serial
+    } else {
+        TtyConsole::Framebuffer
+    }
+}
+
+pub fn tty_set_console(console: TtyConsole) {
+    TTY_CONSOLE.store(console as u8, Ordering::Release);
+}
+
+pub fn tty_get_console() -> TtyConsole {
+    match TTY_CONSOLE.load(Ordering::Acquire) {
+        1 => TtyConsole::Serial,
+        _ => TtyConsole::Framebuffer,
+    }
+}
+
+/// Canonical mode (the default) edits and echoes a whole line before
+/// `tty_read_line` returns it. Raw mode hands over bytes as they arrive,
+/// without echo.
+pub fn tty_set_mode(canonical: bool) {
+    TTY_CANONICAL.store(canonical, Ordering::Release);
+}
+
+pub fn tty_is_canonical() -> bool {
+    TTY_CANONICAL.load(Ordering::Acquire)
+}
+
+/// Canonical-mode line editing over a caller's buffer.
+pub struct LineDiscipline<'a> {
+    line: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> LineDiscipline<'a> {
+    pub fn new(line: &'a mut [u8]) -> Self {
+        Self { line, len: 0 }
+    }
+
+    /// The line collected so far.
+    pub fn line(&self) -> &[u8] {
+        &self.line[..self.len]
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Apply one input byte, writing whatever should be echoed to `echo`.
+    /// Returns true once the line is complete: on Enter, or on Ctrl-C, which
+    /// throws the pending input away and delivers a lone `TTY_CTRL_C`.
+    pub fn feed(&mut self, c: u8, echo: &mut impl FnMut(u8)) -> bool {
+        match c {
+            b'\n' | b'\r' => {
+                echo(b'\n');
+                true
+            }
+            b'\x08' | 0x7F => {
+                if self.len > 0 {
+                    self.len -= 1;
+                    b"\x08 \x08".iter().for_each(|&b| echo(b));
+                }
+                false
+            }
+            TTY_CTRL_C => {
+                self.len = 0;
+                self.store(c);
+                b"^C\n".iter().for_each(|&b| echo(b));
+                true
+            }
+            _ if is_printable(c) || !is_control_char(c) => {
+                if self.store(c) {
+                    echo(c);
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn store(&mut self, c: u8) -> bool {
+        if self.len >= self.line.len() {
+            return false;
+        }
+        self.line[self.len] = c;
+        self.len += 1;
+        true
+    }
+}
+
+/// Lines of console output kept for scrolling back.
+pub const TTY_SCROLLBACK_LINES: usize = 200;
+/// Widest console line the scrollback can hold.
+pub const TTY_SCROLLBACK_COLS: usize = 160;
+
+/// Console output history and the viewport a renderer draws from.
+///
+/// Lines wrap at the console width. The viewport is `rows` lines tall and
+/// sits `offset` lines above the newest output; offset 0 follows the output.
+pub struct TtyScrollback {
+    text: [[u8; TTY_SCROLLBACK_COLS]; TTY_SCROLLBACK_LINES],
+    lens: [u8; TTY_SCROLLBACK_LINES],
+    /// Slot of the oldest line.
+    first: usize,
+    /// Lines stored, counting the one being written.
+    count: usize,
+    cols: usize,
+    rows: usize,
+    offset: usize,
+    snap_on_output: bool,
+}
+
+const _: () = assert!(TTY_SCROLLBACK_COLS <= u8::MAX as usize);
+
+impl TtyScrollback {
+    pub const fn new() -> Self {
+        Self {
+            text: [[0; TTY_SCROLLBACK_COLS]; TTY_SCROLLBACK_LINES],
+            lens: [0; TTY_SCROLLBACK_LINES],
+            first: 0,
+            count: 1,
+            cols: 80,
+            rows: 25,
+            offset: 0,
+            snap_on_output: true,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lens = [0; TTY_SCROLLBACK_LINES];
+        self.first = 0;
+        self.count = 1;
+        self.offset = 0;
+    }
+
+    /// Console size in characters. Existing lines keep their wrapping.
+    pub fn set_geometry(&mut self, cols: usize, rows: usize) {
+        self.cols = cols.clamp(1, TTY_SCROLLBACK_COLS);
+        self.rows = rows.max(1);
+        self.offset = self.offset.min(self.max_offset());
+    }
+
+    /// Whether new output moves a scrolled-back view to the bottom. When it
+    /// does not, the view stays on the lines it was showing.
+    pub fn set_snap_on_output(&mut self, snap: bool) {
+        self.snap_on_output = snap;
+    }
+
+    /// Lines stored, counting the one being written.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 1 && self.lens[self.first] == 0
+    }
+
+    /// How many lines the view sits above the bottom.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn max_offset(&self) -> usize {
+        self.count.saturating_sub(self.rows)
+    }
+
+    fn slot(&self, index: usize) -> usize {
+        (self.first + index) % TTY_SCROLLBACK_LINES
+    }
+
+    /// Line `index`, counting from the oldest stored line.
+    pub fn line(&self, index: usize) -> &[u8] {
+        if index >= self.count {
+            return &[];
+        }
+        let slot = self.slot(index);
+        &self.text[slot][..self.lens[slot] as usize]
+    }
+
+    /// Text of viewport row `row`, top row first; empty below the output.
+    pub fn visible_row(&self, row: usize) -> &[u8] {
+        if row >= self.rows {
+            return &[];
+        }
+        self.line(self.max_offset() - self.offset + row)
+    }
+
+    /// Move the view `lines` towards older output, or towards newer output
+    /// when negative, stopping at either end.
+    pub fn scroll(&mut self, lines: i32) {
+        let target = self.offset as i64 + lines as i64;
+        self.offset = target.clamp(0, self.max_offset() as i64) as usize;
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        let before = self.max_offset();
+        for &b in bytes {
+            match b {
+                b'\n' => self.new_line(),
+                b'\r' => {}
+                b'\x08' => {
+                    let slot = self.slot(self.count - 1);
+                    self.lens[slot] = self.lens[slot].saturating_sub(1);
+                }
+                _ => {
+                    let mut slot = self.slot(self.count - 1);
+                    if self.lens[slot] as usize >= self.cols {
+                        self.new_line();
+                        slot = self.slot(self.count - 1);
+                    }
+                    self.text[slot][self.lens[slot] as usize] = b;
+                    self.lens[slot] += 1;
+                }
+            }
+        }
+        if self.snap_on_output || self.offset == 0 {
+            self.offset = 0;
+        } else {
+            // Older lines were pushed up; follow them so the view holds still.
+            let grown = self.max_offset().saturating_sub(before);
+            self.offset = (self.offset + grown).min(self.max_offset());
+        }
+    }
+
+    fn new_line(&mut self) {
+        if self.count < TTY_SCROLLBACK_LINES {
+            self.count += 1;
+        } else {
+            self.first = (self.first + 1) % TTY_SCROLLBACK_LINES;
+        }
+        let slot = self.slot(self.count - 1);
+        self.lens[slot] = 0;
+    }
+}
+
+impl Default for TtyScrollback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static TTY_SCROLLBACK: IrqMutex<TtyScrollback> = IrqMutex::new(TtyScrollback::new());
+
+/// Record console output in the scrollback.
+pub fn tty_console_write(bytes: &[u8]) {
+    TTY_SCROLLBACK.lock().write(bytes);
+}
+
+/// Move the console view; positive scrolls back into older output.
+pub fn tty_scroll(lines: i32) {
+    TTY_SCROLLBACK.lock().scroll(lines);
+}
+
+pub fn tty_set_scroll_snap(snap: bool) {
+    TTY_SCROLLBACK.lock().set_snap_on_output(snap);
+}
+
+pub fn tty_set_console_geometry(cols: usize, rows: usize) {
+    TTY_SCROLLBACK.lock().set_geometry(cols, rows);
+}
+
+/// Hand each viewport row to `draw`, top first. A renderer uses this rather
+/// than the framebuffer, which only ever holds the bottom of the output.
+pub fn tty_render_view(draw: &mut dyn FnMut(usize, &[u8])) {
+    let scrollback = TTY_SCROLLBACK.lock();
+    for row in 0..scrollback.rows {
+        draw(row, scrollback.visible_row(row));
+    }
+}
+
+#[inline]
+fn tty_keyboard_enabled() -> bool {
+    tty_get_console() == TtyConsole::Framebuffer
+}
 
 use crate::serial::{serial_buffer_pending, serial_buffer_read, serial_poll_receive};
 
@@ -54,7 +353,7 @@ fn tty_service_serial_input() {
 
 fn tty_input_available() -> c_int {
     tty_service_serial_input();
-    if keyboard::has_input() != 0 {
+    if tty_keyboard_enabled() && keyboard::has_input() != 0 {
         return 1;
     }
     if serial_buffer_pending(COM1.address()) != 0 {
@@ -205,6 +504,18 @@ pub fn tty_set_focus(task_id: u32) -> c_int {
     0
 }
 
+/// Switch the line mode on behalf of `task_id`. Only the focused task may
+/// change it; a task asking while nobody holds focus takes it, as a read
+/// would.
+pub fn tty_set_mode_for_task(task_id: u32, canonical: bool) -> c_int {
+    tty_ensure_focus_for_task(task_id);
+    if !tty_task_has_focus(task_id) {
+        return -1;
+    }
+    tty_set_mode(canonical);
+    0
+}
+
 pub fn tty_get_focus() -> u32 {
     TTY_FOCUSED_TASK_ID.load(Ordering::Relaxed)
 }
@@ -222,7 +533,7 @@ fn is_control_char(c: u8) -> bool {
 fn tty_dequeue_input_char(out_char: &mut u8) -> bool {
     tty_service_serial_input();
 
-    if keyboard::has_input() != 0 {
+    if tty_keyboard_enabled() && keyboard::has_input() != 0 {
         *out_char = keyboard::getchar();
         return true;
     }
@@ -262,9 +573,29 @@ fn tty_block_until_input_ready() {
     }
 }
 
+/// Wait until COM1 has a byte queued. Blocks on the TTY wait queue, which
+/// the serial IRQ handler wakes, and falls back to polling before the
+/// scheduler runs.
+pub(crate) fn tty_wait_serial_input() {
+    if serial_buffer_pending(COM1.address()) != 0 {
+        return;
+    }
+    if scheduler_is_enabled() != 0 {
+        let current = scheduler_get_current_task();
+        if tty_wait_queue_push(current) {
+            block_current_task();
+            return;
+        }
+    }
+    while serial_buffer_pending(COM1.address()) == 0 {
+        tty_cpu_relax();
+    }
+}
+
 #[inline]
-fn serial_putc(c: u8) {
+fn tty_echo(c: u8) {
     serial::serial_putc_com1(c);
+    tty_console_write(&[c]);
 }
 pub fn tty_read_line(buffer: *mut u8, buffer_size: usize) -> usize {
     if buffer.is_null() || buffer_size == 0 {
@@ -283,61 +614,46 @@ pub fn tty_read_line(buffer: *mut u8, buffer_size: usize) -> usize {
         return 0;
     }
 
-    let mut pos = 0usize;
-    let max_pos = buffer_size - 1;
+    // Keep the last byte for the terminating NUL.
+    let line = unsafe { core::slice::from_raw_parts_mut(buffer, buffer_size - 1) };
+    let len = if tty_is_canonical() {
+        tty_read_canonical(task_id, line)
+    } else {
+        tty_read_raw(task_id, line)
+    };
+    unsafe { *buffer.add(len) = 0 };
+    len
+}
 
+/// Next input byte for `task_id`, waiting for focus and input as needed.
+fn tty_next_char(task_id: u32) -> u8 {
     loop {
         if !tty_task_has_focus(task_id) {
             tty_wait_for_focus(task_id);
             continue;
         }
         let mut c = 0u8;
-        if !tty_dequeue_input_char(&mut c) {
-            tty_block_until_input_ready();
-            continue;
-        }
-
-        if c == b'\n' || c == b'\r' {
-            unsafe {
-                *buffer.add(pos) = 0;
-            }
-            serial_putc(b'\n');
-            return pos;
-        }
-
-        if c == b'\x08' {
-            if pos > 0 {
-                pos -= 1;
-                serial_putc(b'\x08');
-                serial_putc(b' ');
-                serial_putc(b'\x08');
-            }
-            continue;
-        }
-
-        if pos >= max_pos {
-            continue;
-        }
-
-        if is_printable(c) {
-            unsafe {
-                *buffer.add(pos) = c;
-            }
-            pos += 1;
-            serial_putc(c);
-            continue;
+        if tty_dequeue_input_char(&mut c) {
+            return c;
         }
+        tty_block_until_input_ready();
+    }
+}
 
-        if is_control_char(c) {
-            continue;
-        }
+fn tty_read_canonical(task_id: u32, line: &mut [u8]) -> usize {
+    let mut discipline = LineDiscipline::new(line);
+    while !discipline.feed(tty_next_char(task_id), &mut tty_echo) {}
+    discipline.len()
+}
 
-        unsafe {
-            *buffer.add(pos) = c;
-        }
-        pos += 1;
-        serial_putc(c);
+/// Wait for one byte, then take whatever else is already queued.
+fn tty_read_raw(task_id: u32, line: &mut [u8]) -> usize {
+    line[0] = tty_next_char(task_id);
+    let mut len = 1;
+    while len < line.len() && tty_dequeue_input_char(&mut line[len]) {
+        len += 1;
     }
+    len
 }
 
 pub fn tty_read_char_blocking(out_char: *mut u8) -> c_int {
@@ -0,0 +1,293 @@
+//! TTY tests - console selection, serial input, line discipline and scrollback.
+
+use slopos_lib::ports::{
+    UART_LSR_BREAK, UART_LSR_DATA_READY, UART_LSR_FRAMING_ERROR, UART_LSR_OVERRUN,
+    UART_LSR_PARITY_ERROR,
+};
+use slopos_lib::testing::TestResult;
+use slopos_lib::{assert_eq_test, assert_test};
+use spin::{Mutex, MutexGuard};
+
+use crate::serial::{serial_read_byte, serial_read_line, serial_rx_errors, serial_rx_push};
+use crate::tty::{
+    LineDiscipline, TTY_CTRL_C, TTY_SCROLLBACK_LINES, TtyConsole, TtyScrollback, tty_get_console,
+    tty_get_focus, tty_is_canonical, tty_select_console, tty_set_console, tty_set_focus,
+    tty_set_mode, tty_set_mode_for_task,
+};
+
+pub fn test_tty_console_default_framebuffer() -> TestResult {
+    assert_eq_test!(tty_select_console(false, true), TtyConsole::Framebuffer);
+    TestResult::Pass
+}
+
+pub fn test_tty_console_cmdline_serial() -> TestResult {
+    assert_eq_test!(
+        tty_select_console(true, true),
+        TtyConsole::Serial,
+        "console=serial must win over an available framebuffer"
+    );
+    TestResult::Pass
+}
+
+pub fn test_tty_console_no_framebuffer() -> TestResult {
+    assert_eq_test!(tty_select_console(false, false), TtyConsole::Serial);
+    assert_eq_test!(tty_select_console(true, false), TtyConsole::Serial);
+    TestResult::Pass
+}
+
+pub fn test_tty_console_set_roundtrip() -> TestResult {
+    let saved = tty_get_console();
+
+    tty_set_console(TtyConsole::Serial);
+    let serial = tty_get_console();
+    tty_set_console(TtyConsole::Framebuffer);
+    let framebuffer = tty_get_console();
+
+    tty_set_console(saved);
+    assert_eq_test!(serial, TtyConsole::Serial);
+    assert_eq_test!(framebuffer, TtyConsole::Framebuffer);
+    TestResult::Pass
+}
+
+fn serial_drain() {
+    while serial_read_byte().is_some() {}
+}
+
+pub fn test_serial_rx_fifo_order() -> TestResult {
+    serial_drain();
+    for &byte in b"abc" {
+        assert_test!(serial_rx_push(UART_LSR_DATA_READY, byte));
+    }
+    assert_eq_test!(serial_read_byte(), Some(b'a'));
+    assert_eq_test!(serial_read_byte(), Some(b'b'));
+    assert_eq_test!(serial_read_byte(), Some(b'c'));
+    assert_eq_test!(serial_read_byte(), None);
+    TestResult::Pass
+}
+
+pub fn test_serial_rx_drops_line_errors() -> TestResult {
+    serial_drain();
+    let errors = serial_rx_errors();
+    assert_test!(serial_rx_push(UART_LSR_DATA_READY, b'x'));
+    for lsr in [
+        UART_LSR_FRAMING_ERROR,
+        UART_LSR_PARITY_ERROR,
+        UART_LSR_BREAK,
+    ] {
+        assert_test!(
+            !serial_rx_push(UART_LSR_DATA_READY | lsr, b'?'),
+            "byte with a line error must be dropped"
+        );
+    }
+    assert_test!(
+        serial_rx_push(UART_LSR_DATA_READY | UART_LSR_OVERRUN, b'o'),
+        "byte read after an overrun is still valid"
+    );
+    assert_test!(serial_rx_push(UART_LSR_DATA_READY, b'y'));
+    assert_eq_test!(serial_rx_errors() - errors, 4);
+    assert_eq_test!(serial_read_byte(), Some(b'x'));
+    assert_eq_test!(serial_read_byte(), Some(b'o'));
+    assert_eq_test!(serial_read_byte(), Some(b'y'));
+    assert_eq_test!(serial_read_byte(), None);
+    TestResult::Pass
+}
+
+pub fn test_serial_read_line_edits_and_truncates() -> TestResult {
+    serial_drain();
+    for &byte in b"lx\x7fs -la\rnext\n" {
+        serial_rx_push(UART_LSR_DATA_READY, byte);
+    }
+    let mut line = [0u8; 16];
+    let len = serial_read_line(&mut line);
+    assert_eq_test!(&line[..len], b"ls -la");
+
+    let mut short = [0u8; 2];
+    let len = serial_read_line(&mut short);
+    assert_eq_test!(&short[..len], b"ne");
+    assert_eq_test!(serial_read_byte(), None);
+    TestResult::Pass
+}
+
+/// Run `input` through a fresh line discipline over a `N`-byte buffer and
+/// return the delivered line and everything echoed, or None if no line
+/// completed.
+fn discipline_run<const N: usize>(input: &[u8]) -> Option<([u8; N], usize, [u8; 64], usize)> {
+    let mut line = [0u8; N];
+    let mut echo = [0u8; 64];
+    let mut echo_len = 0;
+    let mut sink = |b: u8| {
+        echo[echo_len] = b;
+        echo_len += 1;
+    };
+    let mut discipline = LineDiscipline::new(&mut line);
+    let mut done = false;
+    for &c in input {
+        done = discipline.feed(c, &mut sink);
+        if done {
+            break;
+        }
+    }
+    let len = discipline.len();
+    done.then_some((line, len, echo, echo_len))
+}
+
+pub fn test_tty_canonical_backspace_erases() -> TestResult {
+    let Some((line, len, echo, echo_len)) = discipline_run::<16>(b"lx\x08s -la\r") else {
+        return TestResult::Fail;
+    };
+    assert_eq_test!(&line[..len], b"ls -la");
+    assert_eq_test!(&echo[..echo_len], b"lx\x08 \x08s -la\n");
+
+    // Backspace on an empty line echoes nothing; DEL erases like backspace.
+    let Some((line, len, echo, echo_len)) = discipline_run::<16>(b"\x08ab\x7f\n") else {
+        return TestResult::Fail;
+    };
+    assert_eq_test!(&line[..len], b"a");
+    assert_eq_test!(&echo[..echo_len], b"ab\x08 \x08\n");
+    TestResult::Pass
+}
+
+pub fn test_tty_canonical_waits_for_enter() -> TestResult {
+    assert_test!(
+        discipline_run::<16>(b"no newline").is_none(),
+        "a line must not be delivered before Enter"
+    );
+    // Input past the buffer is neither stored nor echoed.
+    let Some((line, len, echo, echo_len)) = discipline_run::<3>(b"abcdef\n") else {
+        return TestResult::Fail;
+    };
+    assert_eq_test!(&line[..len], b"abc");
+    assert_eq_test!(&echo[..echo_len], b"abc\n");
+    TestResult::Pass
+}
+
+pub fn test_tty_canonical_ctrl_c_discards_line() -> TestResult {
+    let Some((line, len, echo, echo_len)) = discipline_run::<16>(b"rm -rf\x03") else {
+        return TestResult::Fail;
+    };
+    assert_eq_test!(&line[..len], &[TTY_CTRL_C]);
+    assert_eq_test!(&echo[..echo_len], b"rm -rf^C\n");
+    TestResult::Pass
+}
+
+pub fn test_tty_mode_roundtrip() -> TestResult {
+    let saved = tty_is_canonical();
+    tty_set_mode(false);
+    let raw = tty_is_canonical();
+    tty_set_mode(true);
+    let canonical = tty_is_canonical();
+    tty_set_mode(saved);
+    assert_test!(!raw, "raw mode must stick");
+    assert_test!(canonical, "canonical mode must stick");
+    TestResult::Pass
+}
+
+pub fn test_tty_mode_needs_focus() -> TestResult {
+    let saved_focus = tty_get_focus();
+    let saved_mode = tty_is_canonical();
+    tty_set_focus(0x7F01);
+    let stranger = tty_set_mode_for_task(0x7F02, false);
+    let after_stranger = tty_is_canonical();
+    let owner = tty_set_mode_for_task(0x7F01, !saved_mode);
+    let after_owner = tty_is_canonical();
+    tty_set_mode(saved_mode);
+    tty_set_focus(saved_focus);
+    assert_eq_test!(stranger, -1, "unfocused task must not switch modes");
+    assert_eq_test!(after_stranger, saved_mode);
+    assert_eq_test!(owner, 0);
+    assert_eq_test!(after_owner, !saved_mode);
+    TestResult::Pass
+}
+
+// Too large for a test stack frame
+static SCROLLBACK: Mutex<TtyScrollback> = Mutex::new(TtyScrollback::new());
+
+fn fresh_scrollback(cols: usize, rows: usize) -> MutexGuard<'static, TtyScrollback> {
+    let mut sb = SCROLLBACK.lock();
+    sb.clear();
+    sb.set_geometry(cols, rows);
+    sb.set_snap_on_output(true);
+    sb
+}
+
+/// Write `count` lines reading "line <n>" starting at `first`.
+fn write_numbered(sb: &mut TtyScrollback, first: usize, count: usize) {
+    for n in first..first + count {
+        let mut digits = [0u8; 20];
+        let mut at = digits.len();
+        let mut v = n;
+        loop {
+            at -= 1;
+            digits[at] = b'0' + (v % 10) as u8;
+            v /= 10;
+            if v == 0 {
+                break;
+            }
+        }
+        sb.write(b"line ");
+        sb.write(&digits[at..]);
+        sb.write(b"\n");
+    }
+}
+
+pub fn test_tty_scrollback_page_up() -> TestResult {
+    let mut sb = fresh_scrollback(80, 10);
+    write_numbered(&mut sb, 0, 30);
+    // 30 full lines plus the empty one the cursor sits on.
+    assert_eq_test!(sb.len(), 31);
+    assert_eq_test!(sb.visible_row(0), b"line 21");
+    assert_eq_test!(sb.visible_row(9), b"");
+
+    sb.scroll(10);
+    assert_eq_test!(sb.offset(), 10);
+    assert_eq_test!(sb.visible_row(0), b"line 11");
+    assert_eq_test!(sb.visible_row(9), b"line 20");
+
+    sb.scroll(100);
+    assert_eq_test!(sb.visible_row(0), b"line 0");
+    sb.scroll(-1000);
+    assert_eq_test!(sb.offset(), 0);
+    assert_eq_test!(sb.visible_row(0), b"line 21");
+    TestResult::Pass
+}
+
+pub fn test_tty_scrollback_snap_on_output() -> TestResult {
+    let mut sb = fresh_scrollback(80, 10);
+    write_numbered(&mut sb, 0, 30);
+
+    sb.scroll(10);
+    sb.write(b"more\n");
+    assert_eq_test!(sb.offset(), 0, "output must snap the view back down");
+
+    sb.set_snap_on_output(false);
+    sb.scroll(10);
+    assert_eq_test!(sb.visible_row(0), b"line 12");
+    sb.write(b"again\n");
+    assert_eq_test!(
+        sb.visible_row(0),
+        b"line 12",
+        "a scrolled view must hold still"
+    );
+    TestResult::Pass
+}
+
+pub fn test_tty_scrollback_not_full_and_wrapped() -> TestResult {
+    let mut sb = fresh_scrollback(4, 10);
+    sb.write(b"a\nbcdefg");
+    // Shorter than the view: nothing to scroll, rows below are blank.
+    sb.scroll(5);
+    assert_eq_test!(sb.offset(), 0);
+    assert_eq_test!(sb.visible_row(0), b"a");
+    assert_eq_test!(sb.visible_row(1), b"bcde");
+    assert_eq_test!(sb.visible_row(2), b"fg");
+    assert_eq_test!(sb.visible_row(3), b"");
+
+    // Overflowing the ring keeps only the newest lines.
+    let mut sb = fresh_scrollback(80, 10);
+    write_numbered(&mut sb, 0, TTY_SCROLLBACK_LINES + 50);
+    assert_eq_test!(sb.len(), TTY_SCROLLBACK_LINES);
+    assert_eq_test!(sb.line(0), b"line 51");
+    sb.scroll(i32::MAX);
+    assert_eq_test!(sb.visible_row(0), b"line 51");
+    TestResult::Pass
+}
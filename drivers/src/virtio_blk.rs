@@ -4,7 +4,7 @@ use core::ffi::c_int;
 use core::mem::size_of;
 use core::ptr;
 
-use slopos_lib::{InitFlag, klog_debug, klog_info};
+use slopos_lib::{InitFlag, klog_debug_mod, klog_info};
 
 use crate::pci::{PciDeviceInfo, PciDriver, pci_register_driver};
 use crate::virtio::{
@@ -194,7 +194,7 @@ fn do_request(
 
 fn virtio_blk_probe(info: *const PciDeviceInfo, _context: *mut core::ffi::c_void) -> c_int {
     if !DEVICE_CLAIMED.claim() {
-        klog_debug!("virtio-blk: already claimed");
+        klog_debug_mod!("drivers", "virtio-blk: already claimed");
         return -1;
     }
 
@@ -212,7 +212,8 @@ fn virtio_blk_probe(info: *const PciDeviceInfo, _context: *mut core::ffi::c_void
 
     let caps = parse_capabilities(info);
 
-    klog_debug!(
+    klog_debug_mod!(
+        "drivers",
         "virtio-blk: caps common={} notify={} isr={} device={}",
         caps.has_common_cfg(),
         caps.has_notify_cfg(),
@@ -281,6 +281,31 @@ pub fn xe_framebuffer_init(boot_fb: Option<FramebufferData>) -> Option<Framebuff
     })
 }
 
+/// Point the primary plane at `phys` by remapping the framebuffer's GGTT
+/// window onto it; the new frames are latched by the next `xe_flush`.
+/// `phys` must be contiguous and at least as large as the framebuffer.
+pub fn xe_scanout(phys: PhysAddr) -> i32 {
+    let (present, ready, ggtt_addr, size) = unsafe {
+        (
+            XE_DEVICE.present,
+            XE_DEVICE.fb.ready,
+            XE_DEVICE.fb.ggtt_addr,
+            XE_DEVICE.fb.size,
+        )
+    };
+    if !present || !ready || phys.is_null() {
+        return -1;
+    }
+    let pages = (align_up_u64(size, PAGE_SIZE_4KB) / PAGE_SIZE_4KB) as u32;
+    let start_entry = (ggtt_addr / PAGE_SIZE_4KB) as u32;
+    let ggtt = unsafe { &*core::ptr::addr_of!(XE_DEVICE.ggtt) };
+    if ggtt::xe_ggtt_map(ggtt, start_entry, phys, pages) {
+        0
+    } else {
+        -1
+    }
+}
+
 pub fn xe_flush() -> i32 {
     let (present, ready, mmio, ggtt_addr) = unsafe {
         (
@@ -4,13 +4,27 @@ use core::slice;
 
 use slopos_lib::{InitFlag, IrqMutex};
 
-use slopos_abi::fs::{FS_TYPE_FILE, USER_FS_OPEN_CREAT, UserFsEntry};
-
-use crate::vfs::{FileSystem, InodeId, vfs_list, vfs_mkdir, vfs_open, vfs_stat, vfs_unlink};
+use slopos_abi::fs::{
+    FS_TYPE_DIRECTORY, FS_TYPE_FILE, SEEK_CUR, SEEK_END, SEEK_SET, USER_FS_OPEN_CREAT, UserDirent,
+    UserFsEntry,
+};
+
+use crate::pipe::{
+    PipeEnd, PipeError, pipe_close_end, pipe_create, pipe_open_end, pipe_read, pipe_write,
+};
+use crate::vfs::{
+    FileSystem, FileType, InodeId, vfs_list, vfs_mkdir, vfs_open, vfs_stat, vfs_unlink,
+};
 
 #[allow(non_camel_case_types)]
 type ssize_t = isize;
 
+/// `file_read_fd`/`file_write_fd` result when a pipe is empty or full and
+/// the call would have to wait for the other end.
+pub const FILEIO_WOULD_BLOCK: ssize_t = -2;
+/// `file_write_fd` result when every read end of the pipe is closed.
+pub const FILEIO_BROKEN_PIPE: ssize_t = -3;
+
 const FILE_OPEN_READ: u32 = 1 << 0;
 const FILE_OPEN_WRITE: u32 = 1 << 1;
 const FILE_OPEN_APPEND: u32 = 1 << 3;
@@ -24,8 +38,12 @@ const MAX_PATH: usize = 256;
 struct FileDescriptor {
     inode: InodeId,
     fs: Option<&'static dyn FileSystem>,
+    /// Byte offset for files, entry index for directories.
     position: usize,
     flags: u32,
+    is_dir: bool,
+    /// Set for pipe ends, which have no filesystem behind them.
+    pipe: Option<(usize, PipeEnd)>,
     valid: bool,
 }
 
@@ -36,6 +54,8 @@ impl FileDescriptor {
             fs: None,
             position: 0,
             flags: 0,
+            is_dir: false,
+            pipe: None,
             valid: false,
         }
     }
@@ -106,10 +126,15 @@ fn with_tables<R>(
 }
 
 fn reset_descriptor(desc: &mut FileDescriptor) {
+    if let (true, Some((id, end))) = (desc.valid, desc.pipe) {
+        let _ = pipe_close_end(id, end);
+    }
     desc.inode = 0;
     desc.fs = None;
     desc.position = 0;
     desc.flags = 0;
+    desc.is_dir = false;
+    desc.pipe = None;
     desc.valid = false;
 }
 
@@ -273,6 +298,9 @@ pub fn fileio_clone_table_for_process(src_process_id: u32, dst_process_id: u32)
 
         for (i, src_desc) in unsafe { (*src_table).descriptors.iter().enumerate() } {
             if src_desc.valid {
+                if let Some((id, end)) = src_desc.pipe {
+                    let _ = pipe_open_end(id, end);
+                }
                 dst_slot.descriptors[i] = *src_desc;
             }
         }
@@ -281,6 +309,31 @@ pub fn fileio_clone_table_for_process(src_process_id: u32, dst_process_id: u32)
     })
 }
 
+/// The table of `process_id`, claiming a free one on first use. Falls back
+/// to the kernel table when every slot is taken.
+fn claim_table(
+    kernel: &mut FileTableSlot,
+    processes: &mut [FileTableSlot; MAX_PROCESSES],
+    process_id: u32,
+) -> *mut FileTableSlot {
+    let kernel_ptr = kernel as *mut FileTableSlot;
+    let table_ptr = if let Some(t) = table_for_pid(kernel, processes, process_id) {
+        t as *mut FileTableSlot
+    } else if let Some(t) = find_free_table(processes) {
+        t as *mut FileTableSlot
+    } else {
+        kernel_ptr
+    };
+    let table: &mut FileTableSlot = unsafe { &mut *table_ptr };
+
+    if !table.in_use {
+        table.in_use = true;
+        table.process_id = process_id;
+        reset_table(table);
+    }
+    table_ptr
+}
+
 pub fn file_open_for_process(process_id: u32, path: *const c_char, flags: u32) -> c_int {
     if path.is_null() || (flags & (FILE_OPEN_READ | FILE_OPEN_WRITE)) == 0 {
         return -1;
@@ -300,26 +353,16 @@ pub fn file_open_for_process(process_id: u32, path: *const c_char, flags: u32) -
         Ok(h) => h,
         Err(_) => return -1,
     };
+    // Directory descriptors are read-only and only serve getdents.
+    let is_dir = handle.file_type == FileType::Directory;
+    if is_dir && (flags & FILE_OPEN_WRITE) != 0 {
+        return -1;
+    }
 
     with_tables(|kernel, processes| {
-        let kernel_ptr = kernel as *mut FileTableSlot;
-        let table_ptr = if let Some(t) = table_for_pid(kernel, processes, process_id) {
-            t as *mut FileTableSlot
-        } else if let Some(t) = find_free_table(processes) {
-            t as *mut FileTableSlot
-        } else {
-            kernel_ptr
-        };
+        let table_ptr = claim_table(kernel, processes, process_id);
         let table: &mut FileTableSlot = unsafe { &mut *table_ptr };
-
-        if !table.in_use {
-            table.in_use = true;
-            table.process_id = process_id;
-            reset_table(table);
-        }
-
-        let table_ptr: *mut FileTableSlot = table;
-        let guard = unsafe { (&(*table_ptr).lock).lock() };
+        let guard = unsafe { (*table_ptr).lock.lock() };
 
         let Some(slot_idx) = find_free_slot(table) else {
             drop(guard);
@@ -344,6 +387,7 @@ pub fn file_open_for_process(process_id: u32, path: *const c_char, flags: u32) -
         desc.fs = Some(handle.fs);
         desc.flags = flags;
         desc.position = position;
+        desc.is_dir = is_dir;
         desc.valid = true;
 
         drop(guard);
@@ -351,6 +395,67 @@ pub fn file_open_for_process(process_id: u32, path: *const c_char, flags: u32) -
     })
 }
 
+/// Create a pipe and install its read end in `fds[0]` and its write end in
+/// `fds[1]`.
+pub fn file_pipe_for_process(process_id: u32, fds: &mut [c_int; 2]) -> c_int {
+    let Some(id) = pipe_create() else {
+        return -1;
+    };
+
+    with_tables(|kernel, processes| {
+        let table_ptr = claim_table(kernel, processes, process_id);
+        let table: &mut FileTableSlot = unsafe { &mut *table_ptr };
+        let guard = unsafe { (*table_ptr).lock.lock() };
+
+        let mut installed = [None; 2];
+        for (i, (end, flags)) in [
+            (PipeEnd::Read, FILE_OPEN_READ),
+            (PipeEnd::Write, FILE_OPEN_WRITE),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let Some(slot_idx) = find_free_slot(table) else {
+                break;
+            };
+            let desc = &mut table.descriptors[slot_idx];
+            reset_descriptor(desc);
+            desc.flags = flags;
+            desc.pipe = Some((id, end));
+            desc.valid = true;
+            installed[i] = Some(slot_idx);
+        }
+
+        let [Some(read_fd), Some(write_fd)] = installed else {
+            // Out of descriptors: closing what was installed drops its end,
+            // and the ends that never got a descriptor are dropped here.
+            for (slot, end) in installed.into_iter().zip([PipeEnd::Read, PipeEnd::Write]) {
+                match slot {
+                    Some(idx) => reset_descriptor(&mut table.descriptors[idx]),
+                    None => {
+                        let _ = pipe_close_end(id, end);
+                    }
+                }
+            }
+            drop(guard);
+            return -1;
+        };
+        drop(guard);
+        fds[0] = read_fd as c_int;
+        fds[1] = write_fd as c_int;
+        0
+    })
+}
+
+fn pipe_result(rc: Result<usize, PipeError>) -> ssize_t {
+    match rc {
+        Ok(n) => n as ssize_t,
+        Err(PipeError::WouldBlock) => FILEIO_WOULD_BLOCK,
+        Err(PipeError::BrokenPipe) => FILEIO_BROKEN_PIPE,
+        Err(PipeError::Invalid) => -1,
+    }
+}
+
 pub fn file_read_fd(process_id: u32, fd: c_int, buffer: *mut c_char, count: usize) -> ssize_t {
     if buffer.is_null() || count == 0 {
         return 0;
@@ -369,10 +474,16 @@ pub fn file_read_fd(process_id: u32, fd: c_int, buffer: *mut c_char, count: usiz
             drop(guard);
             return -1;
         };
-        if (desc.flags & FILE_OPEN_READ) == 0 {
+        if (desc.flags & FILE_OPEN_READ) == 0 || desc.is_dir {
             drop(guard);
             return -1;
         }
+        if let Some((id, _)) = desc.pipe {
+            let buf = unsafe { slice::from_raw_parts_mut(buffer as *mut u8, count) };
+            let rc = pipe_read(id, buf);
+            drop(guard);
+            return pipe_result(rc);
+        }
 
         let fs = match desc.fs {
             Some(fs) => fs,
@@ -415,6 +526,12 @@ pub fn file_write_fd(process_id: u32, fd: c_int, buffer: *const c_char, count: u
             drop(guard);
             return -1;
         }
+        if let Some((id, _)) = desc.pipe {
+            let buf = unsafe { slice::from_raw_parts(buffer as *const u8, count) };
+            let rc = pipe_write(id, buf);
+            drop(guard);
+            return pipe_result(rc);
+        }
 
         let fs = match desc.fs {
             Some(fs) => fs,
@@ -436,6 +553,91 @@ pub fn file_write_fd(process_id: u32, fd: c_int, buffer: *const c_char, count: u
     })
 }
 
+/// Fill `buf` with `UserDirent` records for the directory open on `fd`,
+/// continuing from the entry the previous call stopped at.
+///
+/// Returns the number of bytes written, 0 once every entry has been
+/// returned, or -1 if `fd` is not a directory or the next entry does not
+/// fit in `buf`.
+pub fn file_getdents_fd(process_id: u32, fd: c_int, buf: &mut [u8]) -> ssize_t {
+    with_tables(|kernel, processes| {
+        let Some(table) = table_for_pid(kernel, processes, process_id) else {
+            return -1;
+        };
+        if !table.in_use {
+            return -1;
+        }
+        let table_ptr: *mut FileTableSlot = table;
+        let guard = unsafe { (*table_ptr).lock.lock() };
+        let Some(desc) = (unsafe { get_descriptor(&mut *table_ptr, fd) }) else {
+            drop(guard);
+            return -1;
+        };
+        if !desc.is_dir {
+            drop(guard);
+            return -1;
+        }
+        let fs = match desc.fs {
+            Some(fs) => fs,
+            None => {
+                drop(guard);
+                return -1;
+            }
+        };
+
+        // Count accepted entries here: a filesystem may include the entry
+        // that stopped the walk in its own count.
+        let mut written = 0usize;
+        let mut accepted = 0usize;
+        let rc = fs.readdir(desc.inode, desc.position, &mut |name, inode, file_type| {
+            let name_len = name.len().min(u8::MAX as usize);
+            let reclen = UserDirent::reclen(name_len);
+            if written + reclen > buf.len() {
+                return false;
+            }
+            let header = UserDirent {
+                inode,
+                reclen: reclen as u16,
+                type_: if file_type == FileType::Directory {
+                    FS_TYPE_DIRECTORY
+                } else {
+                    FS_TYPE_FILE
+                },
+                name_len: name_len as u8,
+                _pad: [0; 4],
+            };
+            let record = &mut buf[written..written + reclen];
+            record.fill(0);
+            unsafe {
+                (record.as_mut_ptr() as *mut UserDirent).write_unaligned(header);
+            }
+            let name_start = mem::size_of::<UserDirent>();
+            record[name_start..name_start + name_len].copy_from_slice(&name[..name_len]);
+            written += reclen;
+            accepted += 1;
+            true
+        });
+        if rc.is_err() {
+            drop(guard);
+            return -1;
+        }
+        desc.position = desc.position.saturating_add(accepted);
+
+        if accepted == 0 {
+            // Either the directory is exhausted or the next entry is too big.
+            let mut more = false;
+            let _ = fs.readdir(desc.inode, desc.position, &mut |_, _, _| {
+                more = true;
+                false
+            });
+            drop(guard);
+            return if more { -1 } else { 0 };
+        }
+        drop(guard);
+        written as ssize_t
+    })
+}
+
 pub fn file_close_fd(process_id: u32, fd: c_int) -> c_int {
     with_tables(|kernel, processes| {
         let Some(table) = table_for_pid(kernel, processes, process_id) else {
@@ -456,7 +658,12 @@ pub fn file_close_fd(process_id: u32, fd: c_int) -> c_int {
     })
 }
 
-pub fn file_seek_fd(process_id: u32, fd: c_int, offset: u64, whence: c_int) -> c_int {
+/// Move the offset of `fd` relative to `whence` and return the new offset.
+///
+/// Offsets past the end of the file are allowed; reads there return 0 and
+/// writes extend the file. Returns -1 for directories, an unknown `whence`,
+/// or a result that would be negative.
+pub fn file_seek_fd(process_id: u32, fd: c_int, offset: i64, whence: c_int) -> i64 {
     with_tables(|kernel, processes| {
         let Some(table) = table_for_pid(kernel, processes, process_id) else {
             return -1;
@@ -465,11 +672,15 @@ pub fn file_seek_fd(process_id: u32, fd: c_int, offset: u64, whence: c_int) -> c
             return -1;
         }
         let table_ptr: *mut FileTableSlot = table;
-        let guard = unsafe { (&(*table_ptr).lock).lock() };
+        let guard = unsafe { (*table_ptr).lock.lock() };
         let Some(desc) = (unsafe { get_descriptor(&mut *table_ptr, fd) }) else {
             drop(guard);
             return -1;
         };
+        if desc.is_dir {
+            drop(guard);
+            return -1;
+        }
 
         let fs = match desc.fs {
             Some(fs) => fs,
@@ -479,51 +690,32 @@ pub fn file_seek_fd(process_id: u32, fd: c_int, offset: u64, whence: c_int) -> c
             }
         };
 
-        let size = match fs.stat(desc.inode) {
-            Ok(stat) => stat.size as usize,
-            Err(_) => {
+        let base = match whence {
+            SEEK_SET => 0,
+            SEEK_CUR => desc.position as i64,
+            SEEK_END => match fs.stat(desc.inode) {
+                Ok(stat) => stat.size as i64,
+                Err(_) => {
+                    drop(guard);
+                    return -1;
+                }
+            },
+            _ => {
                 drop(guard);
                 return -1;
             }
         };
 
-        let delta = offset as usize;
-        let new_pos = match whence {
-            0 => {
-                if delta > size {
-                    drop(guard);
-                    return -1;
-                }
-                delta
-            }
-            1 => {
-                if let Some(p) = desc.position.checked_add(delta) {
-                    if p <= size {
-                        p
-                    } else {
-                        drop(guard);
-                        return -1;
-                    }
-                } else {
-                    drop(guard);
-                    return -1;
-                }
-            }
-            2 => {
-                if delta > size {
-                    drop(guard);
-                    return -1;
-                }
-                size - delta
-            }
+        let new_pos = match base.checked_add(offset) {
+            Some(p) if p >= 0 => p,
             _ => {
                 drop(guard);
                 return -1;
             }
         };
-        desc.position = new_pos;
+        desc.position = new_pos as usize;
         drop(guard);
-        0
+        new_pos
     })
 }
 
@@ -555,6 +747,47 @@ pub fn file_get_size_fd(process_id: u32, fd: c_int) -> usize {
     })
 }
 
+/// The pipe and end behind `fd`, or `None` if it is not a pipe descriptor.
+pub fn file_pipe_end(process_id: u32, fd: c_int) -> Option<(usize, PipeEnd)> {
+    with_tables(|kernel, processes| {
+        let table = table_for_pid(kernel, processes, process_id)?;
+        if !table.in_use {
+            return None;
+        }
+        let table_ptr: *mut FileTableSlot = table;
+        let guard = unsafe { (*table_ptr).lock.lock() };
+        let pipe = unsafe { get_descriptor(&mut *table_ptr, fd) }.and_then(|desc| desc.pipe);
+        drop(guard);
+        pipe
+    })
+}
+
+/// Run `f` on the in-place data of an open file, for zero-copy readers
+/// like exec. The data is held stable until `f` returns.
+///
+/// Returns `false` without calling `f` for invalid descriptors and for
+/// files that are empty or not contiguously backed in memory; callers fall
+/// back to `file_read_fd`.
+pub fn fileio_with_backing(process_id: u32, fd: c_int, f: &mut dyn FnMut(&[u8])) -> bool {
+    with_tables(|kernel, processes| {
+        let Some(table) = table_for_pid(kernel, processes, process_id) else {
+            return false;
+        };
+        if !table.in_use {
+            return false;
+        }
+        let table_ptr: *mut FileTableSlot = table;
+        let guard = unsafe { (*table_ptr).lock.lock() };
+        let desc = unsafe { get_descriptor(&mut *table_ptr, fd) };
+        let called = desc.is_some_and(|desc| {
+            desc.fs
+                .is_some_and(|fs| fs.with_backing(desc.inode, &mut *f))
+        });
+        drop(guard);
+        called
+    })
+}
+
 pub fn file_exists_path(path: *const c_char) -> c_int {
     if path.is_null() {
         return 0;
@@ -0,0 +1,179 @@
+//! Anonymous pipes.
+//!
+//! A pipe is a byte ring in a fixed pool, shared by any number of read and
+//! write descriptors. The pool only counts open ends; file descriptors refer
+//! to a pipe by index. Nothing here blocks: an empty or full ring reports
+//! `WouldBlock` and the caller decides whether to wait. Callers that sleep
+//! register a wake hook, run whenever a pipe's state may have let a waiter
+//! through.
+
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use slopos_lib::{IrqMutex, RingBuffer};
+
+/// Bytes buffered per pipe before writers have to wait.
+pub const PIPE_BUF_SIZE: usize = 4096;
+
+pub const MAX_PIPES: usize = 16;
+
+/// Told the index of a pipe that was read, written or had an end closed.
+pub type PipeWakeHook = fn(usize);
+
+/// Registered wake hook (set by the syscall layer, which owns the waiters).
+static WAKE_HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Register the hook that wakes tasks waiting on a pipe. It runs with no
+/// pipe lock held.
+pub fn register_pipe_wake_hook(hook: PipeWakeHook) {
+    WAKE_HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+fn pipe_wake(id: usize) {
+    let hook = WAKE_HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        let hook: PipeWakeHook = unsafe { core::mem::transmute(hook) };
+        hook(id);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipeEnd {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipeError {
+    /// The ring is empty (read) or full (write) and the other end is open.
+    WouldBlock,
+    /// Write with every read end closed.
+    BrokenPipe,
+    /// The index does not name an open pipe.
+    Invalid,
+}
+
+struct Pipe {
+    ring: RingBuffer<u8, PIPE_BUF_SIZE>,
+    readers: u32,
+    writers: u32,
+}
+
+impl Pipe {
+    const fn new() -> Self {
+        Self {
+            ring: RingBuffer::new_with(0),
+            readers: 0,
+            writers: 0,
+        }
+    }
+
+    fn in_use(&self) -> bool {
+        self.readers != 0 || self.writers != 0
+    }
+}
+
+static PIPES: IrqMutex<[Pipe; MAX_PIPES]> = IrqMutex::new([const { Pipe::new() }; MAX_PIPES]);
+
+fn with_pipe<R>(
+    id: usize,
+    f: impl FnOnce(&mut Pipe) -> Result<R, PipeError>,
+) -> Result<R, PipeError> {
+    let mut pipes = PIPES.lock();
+    match pipes.get_mut(id) {
+        Some(pipe) if pipe.in_use() => f(pipe),
+        _ => Err(PipeError::Invalid),
+    }
+}
+
+/// Allocate an empty pipe with one read end and one write end open.
+pub fn pipe_create() -> Option<usize> {
+    let mut pipes = PIPES.lock();
+    let (id, pipe) = pipes.iter_mut().enumerate().find(|(_, p)| !p.in_use())?;
+    pipe.ring.reset();
+    pipe.readers = 1;
+    pipe.writers = 1;
+    Some(id)
+}
+
+/// Count another descriptor referring to `end` of pipe `id`.
+pub fn pipe_open_end(id: usize, end: PipeEnd) -> Result<(), PipeError> {
+    with_pipe(id, |pipe| {
+        match end {
+            PipeEnd::Read => pipe.readers += 1,
+            PipeEnd::Write => pipe.writers += 1,
+        }
+        Ok(())
+    })
+}
+
+/// Drop one descriptor of `end`; the pipe is freed when no end is left.
+/// Waiters on the other end are woken to see EOF or a broken pipe.
+pub fn pipe_close_end(id: usize, end: PipeEnd) -> Result<(), PipeError> {
+    with_pipe(id, |pipe| {
+        let count = match end {
+            PipeEnd::Read => &mut pipe.readers,
+            PipeEnd::Write => &mut pipe.writers,
+        };
+        *count = count.saturating_sub(1);
+        Ok(())
+    })?;
+    pipe_wake(id);
+    Ok(())
+}
+
+/// Whether a read (`PipeEnd::Read`) or write (`PipeEnd::Write`) on pipe
+/// `id` would return without `WouldBlock`. A freed pipe counts as ready,
+/// so a waiter wakes and sees the error.
+pub fn pipe_ready(id: usize, end: PipeEnd) -> bool {
+    let pipes = PIPES.lock();
+    let Some(pipe) = pipes.get(id).filter(|pipe| pipe.in_use()) else {
+        return true;
+    };
+    match end {
+        PipeEnd::Read => !pipe.ring.is_empty() || pipe.writers == 0,
+        PipeEnd::Write => !pipe.ring.is_full() || pipe.readers == 0,
+    }
+}
+
+/// Read buffered bytes. Returns 0 once the ring is drained and every write
+/// end is closed.
+pub fn pipe_read(id: usize, buf: &mut [u8]) -> Result<usize, PipeError> {
+    let read = with_pipe(id, |pipe| {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if pipe.ring.is_empty() {
+            return if pipe.writers == 0 {
+                Ok(0)
+            } else {
+                Err(PipeError::WouldBlock)
+            };
+        }
+        Ok(pipe.ring.read_slice(buf))
+    })?;
+    if read != 0 {
+        pipe_wake(id);
+    }
+    Ok(read)
+}
+
+/// Buffer as much of `buf` as fits and return how much was taken.
+pub fn pipe_write(id: usize, buf: &[u8]) -> Result<usize, PipeError> {
+    let written = with_pipe(id, |pipe| {
+        if pipe.readers == 0 {
+            return Err(PipeError::BrokenPipe);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if pipe.ring.is_full() {
+            return Err(PipeError::WouldBlock);
+        }
+        Ok(pipe.ring.write_slice(buf))
+    })?;
+    if written != 0 {
+        pipe_wake(id);
+    }
+    Ok(written)
+}
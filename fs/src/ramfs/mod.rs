@@ -1,4 +1,5 @@
-use crate::vfs::{FileStat, FileSystem, FileType, InodeId, VfsError, VfsResult};
+use crate::vfs::path::split_path;
+use crate::vfs::{FileStat, FileSystem, FileType, InodeId, VfsError, VfsResult, walk_path};
 use slopos_lib::IrqMutex;
 
 const MAX_INODES: usize = 64;
@@ -53,6 +54,9 @@ impl RamInode {
     }
 
     fn add_dir_entry(&mut self, name: &[u8], inode: InodeId) -> VfsResult<()> {
+        if name.len() > MAX_NAME_LEN {
+            return Err(VfsError::NameTooLong);
+        }
         if self.dir_entry_count >= MAX_DIR_ENTRIES {
             return Err(VfsError::NoSpace);
         }
@@ -65,9 +69,8 @@ impl RamInode {
         }
 
         let entry = &mut self.dir_entries[self.dir_entry_count];
-        let len = name.len().min(MAX_NAME_LEN);
-        entry.name[..len].copy_from_slice(&name[..len]);
-        entry.name_len = len;
+        entry.name[..name.len()].copy_from_slice(name);
+        entry.name_len = name.len();
         entry.inode = inode;
         self.dir_entry_count += 1;
 
@@ -195,6 +198,19 @@ impl RamFs {
         }
     }
 
+    /// Inode at `path`, taken relative to this filesystem's root.
+    pub fn resolve(&self, path: &[u8]) -> VfsResult<InodeId> {
+        walk_path(self, ROOT_INODE, path)
+    }
+
+    /// Create the directory `path`, relative to this filesystem's root.
+    /// Every directory above it must already exist.
+    pub fn mkdir(&self, path: &[u8]) -> VfsResult<InodeId> {
+        let (parent, name) = split_path(path).ok_or(VfsError::InvalidPath)?;
+        let parent = self.resolve(parent)?;
+        self.create(parent, name, FileType::Directory)
+    }
+
     fn with_inner<R>(&self, f: impl FnOnce(&RamFsInner) -> R) -> R {
         let mut inner = self.inner.lock();
         inner.ensure_initialized();
@@ -277,6 +293,20 @@ impl FileSystem for RamFs {
         })
     }
 
+    fn with_backing(&self, inode: InodeId, f: &mut dyn FnMut(&[u8])) -> bool {
+        // Inode data is contiguous; the lock keeps writers out while `f` runs
+        self.with_inner(|inner| {
+            let Ok(ram_inode) = inner.get_inode(inode) else {
+                return false;
+            };
+            if ram_inode.file_type != FileType::Regular || ram_inode.data_len == 0 {
+                return false;
+            }
+            f(&ram_inode.data[..ram_inode.data_len]);
+            true
+        })
+    }
+
     fn write(&self, inode: InodeId, offset: u64, buf: &[u8]) -> VfsResult<usize> {
         self.with_inner_mut(|inner| {
             let ram_inode = inner.get_inode_mut(inode)?;
@@ -286,11 +316,10 @@ impl FileSystem for RamFs {
             }
 
             let offset = offset as usize;
-            let end = offset + buf.len();
-
-            if end > RAMFS_MAX_FILE_SIZE {
-                return Err(VfsError::NoSpace);
-            }
+            let end = match offset.checked_add(buf.len()) {
+                Some(end) if end <= RAMFS_MAX_FILE_SIZE => end,
+                _ => return Err(VfsError::NoSpace),
+            };
 
             ram_inode.data[offset..end].copy_from_slice(buf);
             if end > ram_inode.data_len {
@@ -1,14 +1,25 @@
-use core::ffi::c_int;
+use core::ffi::{c_char, c_int};
 use core::ptr;
 
-use slopos_abi::fs::UserFsEntry;
+use slopos_abi::fs::{
+    SEEK_CUR, SEEK_END, SEEK_SET, USER_FS_OPEN_CREAT, USER_FS_OPEN_READ, USER_FS_OPEN_WRITE,
+    UserDirent, UserFsEntry,
+};
 use slopos_lib::{klog_info, wl_currency};
+use slopos_mm::mm_constants::INVALID_PROCESS_ID;
 
 use crate::blockdev::{BlockDevice, BlockDeviceError, MemoryBlockDevice};
 use crate::ext2::{Ext2Error, Ext2Fs};
+use crate::fileio::{
+    FILEIO_BROKEN_PIPE, FILEIO_WOULD_BLOCK, file_close_fd, file_getdents_fd, file_open_for_process,
+    file_pipe_end, file_pipe_for_process, file_read_fd, file_seek_fd, file_write_fd,
+    fileio_with_backing,
+};
+use crate::pipe::{PipeEnd, pipe_ready};
+use crate::vfs::init::RAMFS_TMP_STATIC;
 use crate::vfs::{
-    vfs_init_builtin_filesystems, vfs_is_initialized, vfs_list, vfs_mkdir, vfs_open, vfs_stat,
-    vfs_unlink,
+    FileType, VfsError, vfs_init_builtin_filesystems, vfs_is_initialized, vfs_list, vfs_mkdir,
+    vfs_open, vfs_readdir, vfs_retry, vfs_stat, vfs_unlink,
 };
 
 pub fn test_vfs_initialized() -> c_int {
@@ -101,6 +112,46 @@ pub fn test_vfs_unlink() -> c_int {
     0
 }
 
+pub fn test_vfs_retry_transient_then_ok() -> c_int {
+    klog_info!("VFS_TEST: retry transient errors");
+    let mut calls = 0u32;
+    let result = vfs_retry(5, || {
+        calls += 1;
+        match calls {
+            1 => Err(VfsError::Busy),
+            2 => Err(VfsError::WouldBlock),
+            _ => Ok(calls),
+        }
+    });
+    if result != Ok(3) || calls != 3 {
+        return -1;
+    }
+
+    // Exhausting the attempt budget surfaces the last transient error
+    let mut calls = 0u32;
+    let result: Result<(), VfsError> = vfs_retry(2, || {
+        calls += 1;
+        Err(VfsError::Busy)
+    });
+    if result != Err(VfsError::Busy) || calls != 2 {
+        return -1;
+    }
+    0
+}
+
+pub fn test_vfs_retry_fatal_no_retry() -> c_int {
+    klog_info!("VFS_TEST: retry stops on fatal error");
+    let mut calls = 0u32;
+    let result: Result<(), VfsError> = vfs_retry(5, || {
+        calls += 1;
+        Err(VfsError::NotFound)
+    });
+    if result != Err(VfsError::NotFound) || calls != 1 {
+        return -1;
+    }
+    0
+}
+
 struct FailingBlockDevice {
     fail_reads: bool,
     fail_writes: bool,
@@ -566,6 +617,67 @@ pub fn test_ext2_wl_currency_on_success() -> c_int {
     if result.is_ok() && balance > 0 { 0 } else { -1 }
 }
 
+pub fn test_fileio_backing_ramfs_matches() -> c_int {
+    klog_info!("VFS_TEST: ramfs file exposes in-place backing");
+    let path = b"/tmp/backing.bin\0";
+    let flags = USER_FS_OPEN_READ | USER_FS_OPEN_WRITE | USER_FS_OPEN_CREAT;
+    let fd = file_open_for_process(INVALID_PROCESS_ID, path.as_ptr() as *const c_char, flags);
+    if fd < 0 {
+        return -1;
+    }
+
+    let content = b"\x7fELF in-place backing";
+    let written = file_write_fd(
+        INVALID_PROCESS_ID,
+        fd,
+        content.as_ptr() as *const c_char,
+        content.len(),
+    );
+    let mut matches = false;
+    let called = fileio_with_backing(INVALID_PROCESS_ID, fd, &mut |data| {
+        matches = data == content;
+    });
+    file_close_fd(INVALID_PROCESS_ID, fd);
+    let _ = vfs_unlink(b"/tmp/backing.bin");
+
+    if written != content.len() as isize {
+        return -1;
+    }
+    if !called {
+        klog_info!("VFS_TEST: ramfs file has no backing");
+        return -1;
+    }
+    if !matches {
+        klog_info!("VFS_TEST: backing contents differ from file");
+        return -1;
+    }
+    0
+}
+
+pub fn test_fileio_backing_device_none() -> c_int {
+    klog_info!("VFS_TEST: device file has no backing");
+    let path = b"/dev/zero\0";
+    let fd = file_open_for_process(
+        INVALID_PROCESS_ID,
+        path.as_ptr() as *const c_char,
+        USER_FS_OPEN_READ,
+    );
+    if fd < 0 {
+        return -1;
+    }
+    let called = fileio_with_backing(INVALID_PROCESS_ID, fd, &mut |_| {});
+    file_close_fd(INVALID_PROCESS_ID, fd);
+    if called {
+        return -1;
+    }
+
+    // Closed descriptors never report backing either
+    if fileio_with_backing(INVALID_PROCESS_ID, fd, &mut |_| {}) {
+        return -1;
+    }
+    0
+}
+
 /// Initialize VFS before running ext2 tests. Returns true if init succeeded.
 pub fn ext2_tests_init() -> bool {
     if let Err(_) = vfs_init_builtin_filesystems() {
@@ -574,3 +686,371 @@ pub fn ext2_tests_init() -> bool {
     }
     true
 }
+
+fn nested_cleanup() {
+    for path in [
+        &b"/tmp/nest/a/b/f.txt"[..],
+        b"/tmp/nest/a/b",
+        b"/tmp/nest/a",
+        b"/tmp/nest",
+    ] {
+        let _ = vfs_unlink(path);
+    }
+}
+
+pub fn test_vfs_nested_directories() -> c_int {
+    klog_info!("VFS_TEST: nested ramfs directories");
+    nested_cleanup();
+    let ramfs = &RAMFS_TMP_STATIC;
+
+    if vfs_mkdir(b"/tmp/nest").is_err() || ramfs.mkdir(b"/nest/a").is_err() {
+        return -1;
+    }
+    if ramfs.mkdir(b"/nest/a/b").is_err() {
+        return -1;
+    }
+    if ramfs.mkdir(b"/nest/missing/c") != Err(VfsError::NotFound) {
+        klog_info!("VFS_TEST: BUG - mkdir under a missing directory succeeded");
+        return -1;
+    }
+    let (Ok(a), Ok(up)) = (ramfs.resolve(b"/nest/a"), ramfs.resolve(b"/nest/a/b/..")) else {
+        return -1;
+    };
+    if a != up {
+        klog_info!("VFS_TEST: BUG - '..' did not lead back to the parent");
+        return -1;
+    }
+
+    let Ok(file) = vfs_open(b"/tmp/nest/a/b/f.txt", true) else {
+        return -1;
+    };
+    if file.write(0, b"deep").is_err() {
+        return -1;
+    }
+    let Ok(again) = vfs_open(b"/tmp/nest/./a/b/../b/f.txt", false) else {
+        klog_info!("VFS_TEST: BUG - could not reopen through '.' and '..'");
+        return -1;
+    };
+    let mut buf = [0u8; 8];
+    if again.read(0, &mut buf) != Ok(4) || &buf[..4] != b"deep" {
+        return -1;
+    }
+    nested_cleanup();
+    0
+}
+
+pub fn test_vfs_open_and_readdir_directory() -> c_int {
+    klog_info!("VFS_TEST: open and list a directory");
+    nested_cleanup();
+    if vfs_mkdir(b"/tmp/nest").is_err() || vfs_mkdir(b"/tmp/nest/a").is_err() {
+        return -1;
+    }
+
+    let Ok(dir) = vfs_open(b"/tmp/nest", false) else {
+        klog_info!("VFS_TEST: BUG - directory could not be opened");
+        return -1;
+    };
+    let mut buf = [0u8; 8];
+    if dir.read(0, &mut buf) != Err(VfsError::IsDirectory) {
+        klog_info!("VFS_TEST: BUG - reading a directory did not fail");
+        return -1;
+    }
+
+    let mut seen = [false; 3];
+    let mut extra = 0;
+    let listed = vfs_readdir(b"/tmp/nest", &mut |name, _, file_type| {
+        match (name, file_type) {
+            (b".", FileType::Directory) => seen[0] = true,
+            (b"..", FileType::Directory) => seen[1] = true,
+            (b"a", FileType::Directory) => seen[2] = true,
+            _ => extra += 1,
+        }
+        true
+    });
+    if listed != Ok(3) || seen != [true; 3] || extra != 0 {
+        klog_info!("VFS_TEST: BUG - unexpected directory listing");
+        return -1;
+    }
+
+    if RAMFS_TMP_STATIC.mkdir(b"/nest/a_name_longer_than_thirty_two_bytes")
+        != Err(VfsError::NameTooLong)
+    {
+        klog_info!("VFS_TEST: BUG - overlong name was not rejected");
+        return -1;
+    }
+    nested_cleanup();
+    0
+}
+
+const GETDENTS_FILES: [&[u8]; 5] = [b"alpha", b"bravo", b"charlie", b"delta", b"echo"];
+
+fn getdents_path<'a>(buf: &'a mut [u8; 32], name: &[u8]) -> &'a [u8] {
+    buf[..8].copy_from_slice(b"/tmp/gd/");
+    buf[8..8 + name.len()].copy_from_slice(name);
+    &buf[..8 + name.len()]
+}
+
+fn getdents_cleanup() {
+    for name in GETDENTS_FILES {
+        let _ = vfs_unlink(getdents_path(&mut [0; 32], name));
+    }
+    let _ = vfs_unlink(b"/tmp/gd");
+}
+
+pub fn test_fileio_getdents_resumes() -> c_int {
+    klog_info!("VFS_TEST: getdents across several calls");
+    getdents_cleanup();
+    if vfs_mkdir(b"/tmp/gd").is_err() {
+        return -1;
+    }
+    for name in GETDENTS_FILES {
+        if vfs_open(getdents_path(&mut [0; 32], name), true).is_err() {
+            getdents_cleanup();
+            return -1;
+        }
+    }
+
+    let fd = file_open_for_process(INVALID_PROCESS_ID, c"/tmp/gd".as_ptr(), USER_FS_OPEN_READ);
+    if fd < 0 {
+        klog_info!("VFS_TEST: BUG - directory descriptor could not be opened");
+        getdents_cleanup();
+        return -1;
+    }
+
+    let result = (|| {
+        let mut byte = [0u8; 1];
+        if file_read_fd(INVALID_PROCESS_ID, fd, byte.as_mut_ptr() as *mut c_char, 1) != -1 {
+            klog_info!("VFS_TEST: BUG - byte read from a directory descriptor");
+            return -1;
+        }
+        let mut tiny = [0u8; 8];
+        if file_getdents_fd(INVALID_PROCESS_ID, fd, &mut tiny) != -1 {
+            klog_info!("VFS_TEST: BUG - undersized getdents buffer was accepted");
+            return -1;
+        }
+
+        // Room for two short records per call, so the walk takes several calls.
+        let mut buf = [0u8; 48];
+        let mut seen = [0u32; 7];
+        let mut calls = 0;
+        loop {
+            let n = file_getdents_fd(INVALID_PROCESS_ID, fd, &mut buf);
+            if n < 0 {
+                klog_info!("VFS_TEST: BUG - getdents failed mid-directory");
+                return -1;
+            }
+            if n == 0 {
+                break;
+            }
+            calls += 1;
+            let mut off = 0;
+            while off < n as usize {
+                let header = unsafe { (buf[off..].as_ptr() as *const UserDirent).read_unaligned() };
+                let name_start = off + size_of::<UserDirent>();
+                let name = &buf[name_start..name_start + header.name_len as usize];
+                match name {
+                    b"." => seen[0] += 1,
+                    b".." => seen[1] += 1,
+                    _ => match GETDENTS_FILES.iter().position(|f| *f == name) {
+                        Some(i) if !header.is_directory() => seen[2 + i] += 1,
+                        _ => return -1,
+                    },
+                }
+                off += header.reclen as usize;
+            }
+        }
+        if calls < 2 || seen != [1; 7] {
+            klog_info!("VFS_TEST: BUG - getdents lost or repeated entries");
+            return -1;
+        }
+        0
+    })();
+
+    file_close_fd(INVALID_PROCESS_ID, fd);
+    getdents_cleanup();
+    result
+}
+
+pub fn test_fileio_seek_and_read_tail() -> c_int {
+    klog_info!("VFS_TEST: lseek repositions reads and writes");
+    let path = c"/tmp/seek.txt";
+    let _ = vfs_unlink(b"/tmp/seek.txt");
+    let flags = USER_FS_OPEN_READ | USER_FS_OPEN_WRITE | USER_FS_OPEN_CREAT;
+    let fd = file_open_for_process(INVALID_PROCESS_ID, path.as_ptr(), flags);
+    if fd < 0 {
+        return -1;
+    }
+
+    let result = (|| {
+        let content = b"0123456789";
+        let written = file_write_fd(
+            INVALID_PROCESS_ID,
+            fd,
+            content.as_ptr() as *const c_char,
+            content.len(),
+        );
+        if written != content.len() as isize {
+            return -1;
+        }
+
+        let mut buf = [0u8; 16];
+        let read = |buf: &mut [u8]| {
+            file_read_fd(
+                INVALID_PROCESS_ID,
+                fd,
+                buf.as_mut_ptr() as *mut c_char,
+                buf.len(),
+            )
+        };
+        if file_seek_fd(INVALID_PROCESS_ID, fd, 5, SEEK_SET) != 5 || read(&mut buf) != 5 {
+            return -1;
+        }
+        if &buf[..5] != b"56789" {
+            klog_info!("VFS_TEST: BUG - read after SEEK_SET returned the wrong tail");
+            return -1;
+        }
+
+        if file_seek_fd(INVALID_PROCESS_ID, fd, -3, SEEK_CUR) != 7
+            || file_seek_fd(INVALID_PROCESS_ID, fd, -4, SEEK_END) != 6
+            || read(&mut buf[..2]) != 2
+            || &buf[..2] != b"67"
+        {
+            klog_info!("VFS_TEST: BUG - relative seeks landed on the wrong offset");
+            return -1;
+        }
+
+        if file_seek_fd(INVALID_PROCESS_ID, fd, -9, SEEK_CUR) != -1
+            || file_seek_fd(INVALID_PROCESS_ID, fd, 0, SEEK_CUR) != 8
+            || file_seek_fd(INVALID_PROCESS_ID, fd, 0, 7) != -1
+        {
+            klog_info!("VFS_TEST: BUG - invalid seek was accepted or moved the offset");
+            return -1;
+        }
+
+        if file_seek_fd(INVALID_PROCESS_ID, fd, 20, SEEK_SET) != 20 || read(&mut buf) != 0 {
+            klog_info!("VFS_TEST: BUG - read past EOF did not return 0");
+            return -1;
+        }
+        0
+    })();
+
+    file_close_fd(INVALID_PROCESS_ID, fd);
+    let _ = vfs_unlink(b"/tmp/seek.txt");
+    result
+}
+
+pub fn test_fileio_pipe_roundtrip() -> c_int {
+    klog_info!("VFS_TEST: pipe carries bytes from the write end to the read end");
+    let mut fds = [-1; 2];
+    if file_pipe_for_process(INVALID_PROCESS_ID, &mut fds) != 0 {
+        return -1;
+    }
+    let [read_fd, write_fd] = fds;
+    let read = |buf: &mut [u8]| {
+        file_read_fd(
+            INVALID_PROCESS_ID,
+            read_fd,
+            buf.as_mut_ptr() as *mut c_char,
+            buf.len(),
+        )
+    };
+    let write = |fd: c_int, data: &[u8]| {
+        file_write_fd(
+            INVALID_PROCESS_ID,
+            fd,
+            data.as_ptr() as *const c_char,
+            data.len(),
+        )
+    };
+
+    let mut buf = [0u8; 16];
+    if read(&mut buf) != FILEIO_WOULD_BLOCK {
+        klog_info!("VFS_TEST: BUG - empty pipe with a writer did not report would-block");
+        file_close_fd(INVALID_PROCESS_ID, read_fd);
+        file_close_fd(INVALID_PROCESS_ID, write_fd);
+        return -1;
+    }
+
+    let message = b"through the pipe";
+    if write(write_fd, message) != message.len() as isize
+        || write(read_fd, b"x") != -1
+        || read(&mut buf) != message.len() as isize
+        || &buf[..] != message
+    {
+        klog_info!("VFS_TEST: BUG - pipe did not return the written bytes");
+        file_close_fd(INVALID_PROCESS_ID, read_fd);
+        file_close_fd(INVALID_PROCESS_ID, write_fd);
+        return -1;
+    }
+
+    // Bytes written before the last writer closes are still delivered, then EOF.
+    let _ = write(write_fd, b"tail");
+    file_close_fd(INVALID_PROCESS_ID, write_fd);
+    if read(&mut buf) != 4 || &buf[..4] != b"tail" || read(&mut buf) != 0 {
+        klog_info!("VFS_TEST: BUG - closed pipe did not drain and then report EOF");
+        file_close_fd(INVALID_PROCESS_ID, read_fd);
+        return -1;
+    }
+    file_close_fd(INVALID_PROCESS_ID, read_fd);
+
+    let mut fds = [-1; 2];
+    if file_pipe_for_process(INVALID_PROCESS_ID, &mut fds) != 0 {
+        return -1;
+    }
+    file_close_fd(INVALID_PROCESS_ID, fds[0]);
+    let broken = write(fds[1], b"nobody listens");
+    file_close_fd(INVALID_PROCESS_ID, fds[1]);
+    if broken != FILEIO_BROKEN_PIPE {
+        klog_info!("VFS_TEST: BUG - write without readers did not report a broken pipe");
+        return -1;
+    }
+    0
+}
+
+pub fn test_fileio_pipe_wait_readiness() -> c_int {
+    klog_info!("VFS_TEST: pipe readiness follows the other end, including close");
+    let mut fds = [-1; 2];
+    if file_pipe_for_process(INVALID_PROCESS_ID, &mut fds) != 0 {
+        return -1;
+    }
+    let [read_fd, write_fd] = fds;
+    let ends = (
+        file_pipe_end(INVALID_PROCESS_ID, read_fd),
+        file_pipe_end(INVALID_PROCESS_ID, write_fd),
+    );
+    let (Some((id, PipeEnd::Read)), Some((write_id, PipeEnd::Write))) = ends else {
+        klog_info!("VFS_TEST: BUG - pipe descriptors did not resolve to their ends");
+        file_close_fd(INVALID_PROCESS_ID, read_fd);
+        file_close_fd(INVALID_PROCESS_ID, write_fd);
+        return -1;
+    };
+
+    let idle = !pipe_ready(id, PipeEnd::Read) && pipe_ready(id, PipeEnd::Write);
+    let _ = file_write_fd(
+        INVALID_PROCESS_ID,
+        write_fd,
+        b"x".as_ptr() as *const c_char,
+        1,
+    );
+    let written = pipe_ready(id, PipeEnd::Read);
+    let mut byte = 0u8;
+    let _ = file_read_fd(
+        INVALID_PROCESS_ID,
+        read_fd,
+        &mut byte as *mut u8 as *mut c_char,
+        1,
+    );
+    let drained = !pipe_ready(id, PipeEnd::Read);
+    file_close_fd(INVALID_PROCESS_ID, write_fd);
+    let eof = pipe_ready(id, PipeEnd::Read);
+    file_close_fd(INVALID_PROCESS_ID, read_fd);
+
+    if write_id != id || !idle || !written || !drained || !eof {
+        klog_info!("VFS_TEST: BUG - pipe readiness did not track reads, writes and close");
+        return -1;
+    }
+    if file_pipe_end(INVALID_PROCESS_ID, read_fd).is_some() {
+        return -1;
+    }
+    0
+}
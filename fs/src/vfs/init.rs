@@ -9,7 +9,7 @@ use crate::vfs::mount::mount;
 static VFS_INIT: InitFlag = InitFlag::new();
 
 static RAMFS_ROOT_STATIC: RamFs = RamFs::new_const();
-static RAMFS_TMP_STATIC: RamFs = RamFs::new_const();
+pub(crate) static RAMFS_TMP_STATIC: RamFs = RamFs::new_const();
 static DEVFS_STATIC: DevFs = DevFs::new();
 
 pub fn vfs_init_builtin_filesystems() -> VfsResult<()> {
@@ -6,6 +6,9 @@ pub mod traits;
 
 pub use init::{vfs_init_builtin_filesystems, vfs_is_initialized};
 pub use mount::{mount, unmount, with_mount_table};
-pub use ops::{VfsHandle, vfs_list, vfs_mkdir, vfs_open, vfs_stat, vfs_unlink};
-pub use path::{ResolvedPath, resolve_parent, resolve_path};
+pub use ops::{
+    VfsHandle, vfs_list, vfs_mkdir, vfs_open, vfs_readdir, vfs_register_yield_callback, vfs_retry,
+    vfs_stat, vfs_unlink,
+};
+pub use path::{ResolvedPath, resolve_parent, resolve_path, walk_path};
 pub use traits::{FileStat, FileSystem, FileType, InodeId, VfsError, VfsResult};
@@ -1,3 +1,6 @@
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
 use crate::vfs::path::{resolve_parent, resolve_path};
 use crate::vfs::traits::{FileType, InodeId, VfsError, VfsResult};
 use slopos_abi::fs::{FS_TYPE_DIRECTORY, FS_TYPE_FILE, FS_TYPE_UNKNOWN, UserFsEntry};
@@ -5,38 +8,101 @@ use slopos_abi::fs::{FS_TYPE_DIRECTORY, FS_TYPE_FILE, FS_TYPE_UNKNOWN, UserFsEnt
 pub struct VfsHandle {
     pub inode: InodeId,
     pub fs: &'static dyn crate::vfs::FileSystem,
+    pub file_type: FileType,
 }
 
 impl VfsHandle {
     pub fn read(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        if self.file_type == FileType::Directory {
+            return Err(VfsError::IsDirectory);
+        }
         self.fs.read(self.inode, offset, buf)
     }
 
     pub fn write(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        if self.file_type == FileType::Directory {
+            return Err(VfsError::IsDirectory);
+        }
         self.fs.write(self.inode, offset, buf)
     }
 
+    /// Visit the entries of an opened directory from entry `offset` on,
+    /// `.` and `..` included. Returns how many entries were visited.
+    pub fn readdir(
+        &self,
+        offset: usize,
+        callback: &mut dyn FnMut(&[u8], InodeId, FileType) -> bool,
+    ) -> VfsResult<usize> {
+        if self.file_type != FileType::Directory {
+            return Err(VfsError::NotDirectory);
+        }
+        self.fs.readdir(self.inode, offset, callback)
+    }
+
     pub fn size(&self) -> VfsResult<u64> {
         let stat = self.fs.stat(self.inode)?;
         Ok(stat.size)
     }
 
+    pub fn with_backing(&self, f: &mut dyn FnMut(&[u8])) -> bool {
+        self.fs.with_backing(self.inode, f)
+    }
+
     pub fn is_directory(&self) -> VfsResult<bool> {
         let stat = self.fs.stat(self.inode)?;
         Ok(stat.file_type == FileType::Directory)
     }
 }
 
+static VFS_YIELD_CALLBACK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Register the function used by `vfs_retry` to give up the CPU between attempts.
+pub fn vfs_register_yield_callback(callback: fn()) {
+    VFS_YIELD_CALLBACK.store(callback as *mut (), Ordering::Release);
+}
+
+fn vfs_yield() {
+    let fn_ptr = VFS_YIELD_CALLBACK.load(Ordering::Acquire);
+    if fn_ptr.is_null() {
+        core::hint::spin_loop();
+        return;
+    }
+    // SAFETY: fn_ptr was set via vfs_register_yield_callback with a valid fn()
+    let callback: fn() = unsafe { core::mem::transmute(fn_ptr) };
+    callback();
+}
+
+/// Run `op` up to `attempts` times, retrying only while it fails with a
+/// retryable error (see [`VfsError::is_retryable`]). Success and fatal errors
+/// are returned immediately; the last retryable error is returned once the
+/// attempts are exhausted.
+pub fn vfs_retry<T>(attempts: u32, mut op: impl FnMut() -> VfsResult<T>) -> VfsResult<T> {
+    let attempts = attempts.max(1);
+    let mut tried = 0u32;
+    loop {
+        match op() {
+            Err(err) if err.is_retryable() => {
+                tried += 1;
+                if tried >= attempts {
+                    return Err(err);
+                }
+                vfs_yield();
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Open a file or directory. Directories can be listed with `readdir` but
+/// fail reads and writes with `IsDirectory`.
 pub fn vfs_open(path: &[u8], create: bool) -> VfsResult<VfsHandle> {
     match resolve_path(path) {
         Ok(resolved) => {
             let stat = resolved.fs.stat(resolved.inode)?;
-            if stat.file_type == FileType::Directory {
-                return Err(VfsError::IsDirectory);
-            }
             Ok(VfsHandle {
                 inode: resolved.inode,
                 fs: resolved.fs,
+                file_type: stat.file_type,
             })
         }
         Err(VfsError::NotFound) if create => {
@@ -45,6 +111,7 @@ pub fn vfs_open(path: &[u8], create: bool) -> VfsResult<VfsHandle> {
             Ok(VfsHandle {
                 inode: new_inode,
                 fs: parent.fs,
+                file_type: FileType::Regular,
             })
         }
         Err(e) => Err(e),
@@ -75,6 +142,14 @@ pub fn vfs_unlink(path: &[u8]) -> VfsResult<()> {
     parent.fs.unlink(parent.inode, name)
 }
 
+/// Enumerate the directory at `path`; see [`VfsHandle::readdir`].
+pub fn vfs_readdir(
+    path: &[u8],
+    callback: &mut dyn FnMut(&[u8], InodeId, FileType) -> bool,
+) -> VfsResult<usize> {
+    vfs_open(path, false)?.readdir(0, callback)
+}
+
 pub fn vfs_list(path: &[u8], entries: &mut [UserFsEntry]) -> VfsResult<usize> {
     let resolved = resolve_path(path)?;
     let stat = resolved.fs.stat(resolved.inode)?;
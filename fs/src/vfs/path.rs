@@ -14,23 +14,7 @@ pub fn resolve_path(path: &[u8]) -> VfsResult<ResolvedPath> {
     with_mount_table(|mount_table| {
         let (fs, relative) = mount_table.resolve(path)?;
 
-        let mut current_inode = fs.root_inode();
-
-        for component in PathComponents::new(relative) {
-            if component == b"." {
-                continue;
-            }
-
-            if component == b".." {
-                match fs.lookup(current_inode, b"..") {
-                    Ok(parent) => current_inode = parent,
-                    Err(_) => {}
-                }
-                continue;
-            }
-
-            current_inode = fs.lookup(current_inode, component)?;
-        }
+        let current_inode = walk_path(fs, fs.root_inode(), relative)?;
 
         let fs_static: &'static dyn FileSystem = unsafe { core::mem::transmute(fs) };
 
@@ -41,6 +25,27 @@ pub fn resolve_path(path: &[u8]) -> VfsResult<ResolvedPath> {
     })
 }
 
+/// Follow `path` one component at a time from the directory `start`.
+///
+/// `.` stays put and `..` goes through the directory's own `..` entry; at a
+/// filesystem root that entry points back at the root.
+pub fn walk_path(fs: &dyn FileSystem, start: InodeId, path: &[u8]) -> VfsResult<InodeId> {
+    let mut current = start;
+    for component in PathComponents::new(path) {
+        if component == b"." {
+            continue;
+        }
+        if component == b".." {
+            if let Ok(parent) = fs.lookup(current, b"..") {
+                current = parent;
+            }
+            continue;
+        }
+        current = fs.lookup(current, component)?;
+    }
+    Ok(current)
+}
+
 pub fn resolve_parent(path: &[u8]) -> VfsResult<(ResolvedPath, &[u8])> {
     if path.is_empty() || path[0] != b'/' {
         return Err(VfsError::InvalidPath);
@@ -58,7 +63,7 @@ pub fn resolve_parent(path: &[u8]) -> VfsResult<(ResolvedPath, &[u8])> {
     Ok((resolved, name))
 }
 
-fn split_path(path: &[u8]) -> Option<(&[u8], &[u8])> {
+pub(crate) fn split_path(path: &[u8]) -> Option<(&[u8], &[u8])> {
     if path.is_empty() || path[0] != b'/' {
         return None;
     }
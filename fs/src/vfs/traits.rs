@@ -155,6 +155,15 @@ pub enum VfsError {
     BadFileDescriptor,
     /// Resource busy (EBUSY)
     Busy,
+    /// Operation would block; try again (EAGAIN)
+    WouldBlock,
+}
+
+impl VfsError {
+    /// Whether the error is transient and the operation may succeed if retried.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, VfsError::Busy | VfsError::WouldBlock)
+    }
 }
 
 /// A filesystem implementation.
@@ -247,6 +256,21 @@ pub trait FileSystem: Send + Sync {
         Err(VfsError::NotSupported)
     }
 
+    /// Run `f` on a file's data in place if it is stored contiguously in
+    /// memory.
+    ///
+    /// Lets callers such as exec read a file without copying it. The
+    /// filesystem keeps the data stable (no writes, truncates or unlinks)
+    /// until `f` returns, so `f` must not call back into this filesystem.
+    ///
+    /// # Returns
+    /// `true` if `f` was called, `false` if the file is empty or not
+    /// contiguously backed (the default).
+    fn with_backing(&self, inode: InodeId, f: &mut dyn FnMut(&[u8])) -> bool {
+        let _ = (inode, f);
+        false
+    }
+
     /// Sync filesystem metadata and data to backing store.
     fn sync(&self) -> VfsResult<()> {
         // Default: no-op for in-memory filesystems
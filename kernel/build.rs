@@ -0,0 +1,24 @@
+//! Stage the kernel symbol table for `src/ksymtab.rs`.
+//!
+//! The Makefile links the kernel once, generates the table from that image
+//! with `scripts/gen_ksymtab.py`, and relinks with `SLOPOS_KSYMTAB` pointing
+//! at it. Without the variable an empty table is embedded.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=SLOPOS_KSYMTAB");
+    let out = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR not set")).join("ksymtab.bin");
+
+    let table = match env::var_os("SLOPOS_KSYMTAB").filter(|path| !path.is_empty()) {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            println!("cargo:rerun-if-changed={}", path.display());
+            fs::read(&path).unwrap_or_else(|e| panic!("reading {}: {}", path.display(), e))
+        }
+        None => Vec::new(),
+    };
+    fs::write(&out, table).expect("writing ksymtab.bin");
+}
@@ -0,0 +1,8 @@
+//! Kernel symbol table, placed in `.ksymtab` for `slopos_lib::stacktrace`.
+
+const KSYMTAB_BLOB: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/ksymtab.bin"));
+
+#[used]
+#[unsafe(link_section = ".ksymtab")]
+static KSYMTAB: [u8; KSYMTAB_BLOB.len()] =
+    *include_bytes!(concat!(env!("OUT_DIR"), "/ksymtab.bin"));
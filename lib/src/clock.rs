@@ -0,0 +1,114 @@
+//! Monotonic clock.
+//!
+//! Time since boot is derived from the TSC when its frequency is known,
+//! either reported by CPUID leaf 0x16 or calibrated against the timer at
+//! boot. Until then, or on CPUs where neither works, the timer tick counter
+//! registered by the timer driver is used at tick resolution.
+
+use core::sync::atomic::{AtomicPtr, AtomicU32, AtomicU64, Ordering};
+
+use crate::tsc;
+
+pub const NS_PER_SEC: u64 = 1_000_000_000;
+
+/// Tick counter callback (e.g. the PIT interrupt count).
+pub type TickSourceFn = fn() -> u64;
+
+static TSC_HZ: AtomicU64 = AtomicU64::new(0);
+static BOOT_TSC: AtomicU64 = AtomicU64::new(0);
+static TICK_SOURCE: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+static TICK_HZ: AtomicU32 = AtomicU32::new(0);
+/// Last value handed out, so switching sources never steps time backwards
+static LAST_NS: AtomicU64 = AtomicU64::new(0);
+
+/// Convert TSC cycles to nanoseconds. Returns 0 if `tsc_hz` is unknown (0).
+#[inline]
+pub fn cycles_to_ns(cycles: u64, tsc_hz: u64) -> u64 {
+    if tsc_hz == 0 {
+        return 0;
+    }
+    let ns = (cycles as u128) * (NS_PER_SEC as u128) / (tsc_hz as u128);
+    ns.min(u64::MAX as u128) as u64
+}
+
+/// Convert timer ticks to nanoseconds. Returns 0 if `tick_hz` is unknown (0).
+#[inline]
+pub fn ticks_to_ns(ticks: u64, tick_hz: u32) -> u64 {
+    cycles_to_ns(ticks, tick_hz as u64)
+}
+
+/// Pick the best available source: TSC cycles if its frequency is known,
+/// otherwise timer ticks.
+#[inline]
+pub fn clock_source_ns(cycles: u64, tsc_hz: u64, ticks: u64, tick_hz: u32) -> u64 {
+    if tsc_hz != 0 {
+        cycles_to_ns(cycles, tsc_hz)
+    } else {
+        ticks_to_ns(ticks, tick_hz)
+    }
+}
+
+/// TSC frequency reported by CPUID leaf 0x16, or 0 if unavailable.
+pub fn cpuid_tsc_hz() -> u64 {
+    let (max_leaf, _, _, _) = crate::cpu::cpuid(0);
+    if max_leaf < 0x16 {
+        return 0;
+    }
+    let (freq_mhz, _, _, _) = crate::cpu::cpuid(0x16);
+    (freq_mhz as u64) * 1_000_000
+}
+
+/// Mark the boot TSC origin and adopt the CPUID-reported frequency.
+pub fn clock_init() {
+    BOOT_TSC.store(tsc::rdtsc(), Ordering::Relaxed);
+    let hz = cpuid_tsc_hz();
+    if hz != 0 {
+        TSC_HZ.store(hz, Ordering::Relaxed);
+    }
+}
+
+/// Register the fallback tick counter and its frequency.
+pub fn clock_set_tick_source(source: TickSourceFn, hz: u32) {
+    TICK_HZ.store(hz, Ordering::Relaxed);
+    TICK_SOURCE.store(source as *mut (), Ordering::Release);
+}
+
+/// Calibrate the TSC from `cycles` elapsed over a known `interval_ns`.
+///
+/// A measured frequency replaces the CPUID-reported one, which is only a
+/// nominal value. Returns the frequency in effect afterwards (0 if still
+/// unknown).
+pub fn clock_calibrate_tsc(cycles: u64, interval_ns: u64) -> u64 {
+    if cycles == 0 || interval_ns == 0 {
+        return TSC_HZ.load(Ordering::Relaxed);
+    }
+    let hz = (cycles as u128) * (NS_PER_SEC as u128) / (interval_ns as u128);
+    let hz = hz.min(u64::MAX as u128) as u64;
+    TSC_HZ.store(hz, Ordering::Relaxed);
+    hz
+}
+
+/// TSC frequency in use (0 = unknown, tick fallback active).
+pub fn clock_tsc_hz() -> u64 {
+    TSC_HZ.load(Ordering::Relaxed)
+}
+
+fn read_ticks() -> u64 {
+    let raw = TICK_SOURCE.load(Ordering::Acquire);
+    if raw.is_null() {
+        return 0;
+    }
+    let source: TickSourceFn = unsafe { core::mem::transmute(raw) };
+    source()
+}
+
+/// Nanoseconds since boot. Never decreases.
+pub fn monotonic_ns() -> u64 {
+    let tsc_hz = TSC_HZ.load(Ordering::Relaxed);
+    let cycles = tsc::rdtsc().wrapping_sub(BOOT_TSC.load(Ordering::Relaxed));
+    // Only call out to the tick source when it will actually be used
+    let ticks = if tsc_hz == 0 { read_ticks() } else { 0 };
+    let now = clock_source_ns(cycles, tsc_hz, ticks, TICK_HZ.load(Ordering::Relaxed));
+    let prev = LAST_NS.fetch_max(now, Ordering::Relaxed);
+    prev.max(now)
+}
@@ -0,0 +1,44 @@
+//! Clock tests - TSC time conversion and source selection.
+
+use crate::clock::{NS_PER_SEC, clock_source_ns, cycles_to_ns, monotonic_ns};
+use crate::testing::TestResult;
+use crate::{assert_eq_test, assert_test};
+
+pub fn test_clock_cycles_to_ns_known_freq() -> TestResult {
+    assert_eq_test!(cycles_to_ns(3_000_000_000, 3_000_000_000), NS_PER_SEC);
+    assert_eq_test!(cycles_to_ns(1, 1_000_000_000), 1);
+    assert_eq_test!(cycles_to_ns(2_400, 2_400_000_000), 1_000);
+    // 10 years at 4 GHz must not overflow the intermediate product
+    let cycles = 4_000_000_000u64 * 86_400 * 3_650;
+    assert_eq_test!(
+        cycles_to_ns(cycles, 4_000_000_000),
+        86_400 * 3_650 * NS_PER_SEC
+    );
+    TestResult::Pass
+}
+
+pub fn test_clock_unknown_freq_falls_back_to_ticks() -> TestResult {
+    assert_eq_test!(cycles_to_ns(123_456, 0), 0, "unknown TSC frequency");
+    assert_eq_test!(
+        clock_source_ns(123_456, 0, 250, 100),
+        2_500_000_000,
+        "ticks used without TSC frequency"
+    );
+    assert_eq_test!(
+        clock_source_ns(1_000, 1_000_000_000, 250, 100),
+        1_000,
+        "TSC preferred when known"
+    );
+    assert_eq_test!(clock_source_ns(0, 0, 250, 0), 0, "no source at all");
+    TestResult::Pass
+}
+
+pub fn test_clock_monotonic_never_decreases() -> TestResult {
+    let mut prev = monotonic_ns();
+    for _ in 0..1000 {
+        let now = monotonic_ns();
+        assert_test!(now >= prev, "monotonic clock went backwards");
+        prev = now;
+    }
+    TestResult::Pass
+}
@@ -0,0 +1,106 @@
+/// Fixed-capacity id allocator that recycles freed ids.
+///
+/// Each id names one of `N` slots; the lowest free slot is handed out first.
+/// The upper 16 bits of an id hold the slot's generation, which is bumped on
+/// every free, so a recycled slot never reproduces the id it had before and
+/// stale ids held elsewhere stop matching. Ids are never 0.
+#[derive(Debug)]
+pub struct IdAllocator<const N: usize> {
+    used: [bool; N],
+    generation: [u16; N],
+    in_use: usize,
+}
+
+const ID_SLOT_BITS: u32 = 16;
+const ID_SLOT_MASK: u32 = (1 << ID_SLOT_BITS) - 1;
+
+impl<const N: usize> Default for IdAllocator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> IdAllocator<N> {
+    /// Slot numbers are stored as `index + 1` in the low bits; keeping them
+    /// below 0xFFFF means no id can equal `u32::MAX` (the usual invalid id).
+    const CAPACITY_OK: () = assert!(N > 0 && N < ID_SLOT_MASK as usize);
+
+    pub const fn new() -> Self {
+        let () = Self::CAPACITY_OK;
+        Self {
+            used: [false; N],
+            generation: [0; N],
+            in_use: 0,
+        }
+    }
+
+    #[inline]
+    fn encode(index: usize, generation: u16) -> u32 {
+        ((generation as u32) << ID_SLOT_BITS) | (index as u32 + 1)
+    }
+
+    /// Slot index an id refers to, if it is the slot's current id.
+    fn decode(&self, id: u32) -> Option<usize> {
+        let slot = (id & ID_SLOT_MASK) as usize;
+        if slot == 0 || slot > N {
+            return None;
+        }
+        let index = slot - 1;
+        let generation = (id >> ID_SLOT_BITS) as u16;
+        (self.generation[index] == generation).then_some(index)
+    }
+
+    /// Allocate the lowest free slot. Returns None when all `N` are in use.
+    pub fn alloc(&mut self) -> Option<u32> {
+        let index = self.used.iter().position(|&used| !used)?;
+        self.used[index] = true;
+        self.in_use += 1;
+        Some(Self::encode(index, self.generation[index]))
+    }
+
+    /// Release `id`. Returns false if it is not currently allocated
+    /// (never issued, already freed, or from an older generation).
+    pub fn free(&mut self, id: u32) -> bool {
+        let Some(index) = self.decode(id) else {
+            return false;
+        };
+        if !self.used[index] {
+            return false;
+        }
+        self.used[index] = false;
+        self.generation[index] = self.generation[index].wrapping_add(1);
+        self.in_use -= 1;
+        true
+    }
+
+    /// Whether `id` is currently allocated.
+    pub fn is_live(&self, id: u32) -> bool {
+        self.decode(id).is_some_and(|index| self.used[index])
+    }
+
+    /// Slot index of a live id, usable for indexing per-id tables.
+    pub fn index_of(&self, id: u32) -> Option<usize> {
+        self.decode(id).filter(|&index| self.used[index])
+    }
+
+    #[inline]
+    pub fn in_use(&self) -> usize {
+        self.in_use
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Free every id. Generations are kept so pre-reset ids stay stale.
+    pub fn reset(&mut self) {
+        for index in 0..N {
+            if self.used[index] {
+                self.used[index] = false;
+                self.generation[index] = self.generation[index].wrapping_add(1);
+            }
+        }
+        self.in_use = 0;
+    }
+}
@@ -1,9 +1,16 @@
 use crate::string::cstr_to_str;
 use core::ffi::{c_char, c_int};
+use core::fmt;
 use core::sync::atomic::{AtomicU64, Ordering};
 
+use slopos_abi::arch::x86_64::idt::{
+    EXCEPTION_ALIGNMENT_CHECK, EXCEPTION_CONTROL_PROTECTION, EXCEPTION_DOUBLE_FAULT,
+    EXCEPTION_GENERAL_PROTECTION, EXCEPTION_INVALID_TSS, EXCEPTION_MACHINE_CHECK, EXCEPTION_NMI,
+    EXCEPTION_PAGE_FAULT, EXCEPTION_SEGMENT_NOT_PRES, EXCEPTION_STACK_FAULT,
+};
+
 use crate::cpu;
-use crate::stacktrace::{self, StacktraceEntry};
+use crate::stacktrace::{self, StacktraceEntry, Symbolized};
 use crate::tsc;
 
 pub const KDIAG_STACK_TRACE_DEPTH: usize = 16;
@@ -58,6 +65,318 @@ fn exception_name(vector: u8) -> &'static [u8] {
     }
 }
 
+/// Descriptor table referenced by a selector error code (bits 1-2).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DescriptorTable {
+    Gdt,
+    Idt,
+    Ldt,
+}
+
+impl DescriptorTable {
+    pub fn name(self) -> &'static str {
+        match self {
+            DescriptorTable::Gdt => "GDT",
+            DescriptorTable::Idt => "IDT",
+            DescriptorTable::Ldt => "LDT",
+        }
+    }
+}
+
+/// Decoded #PF error code bits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PageFaultFlags {
+    pub present: bool,
+    pub write: bool,
+    pub user: bool,
+    pub reserved_bit: bool,
+    pub instruction_fetch: bool,
+    pub protection_key: bool,
+    pub shadow_stack: bool,
+}
+
+/// Decoded selector error code used by #TS, #NP, #SS and #GP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SelectorError {
+    pub external: bool,
+    pub table: DescriptorTable,
+    pub index: u16,
+}
+
+/// Human-readable interpretation of an exception's error code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExceptionDetail {
+    /// Vector pushes no error code, or the code carries no extra meaning.
+    None,
+    PageFault(PageFaultFlags),
+    Selector(SelectorError),
+    /// Vector pushes an error code that is architecturally always zero or opaque.
+    Raw(u64),
+}
+
+pub fn decode_exception(vector: u8, error_code: u64) -> ExceptionDetail {
+    match vector {
+        EXCEPTION_PAGE_FAULT => ExceptionDetail::PageFault(PageFaultFlags {
+            present: error_code & (1 << 0) != 0,
+            write: error_code & (1 << 1) != 0,
+            user: error_code & (1 << 2) != 0,
+            reserved_bit: error_code & (1 << 3) != 0,
+            instruction_fetch: error_code & (1 << 4) != 0,
+            protection_key: error_code & (1 << 5) != 0,
+            shadow_stack: error_code & (1 << 6) != 0,
+        }),
+        EXCEPTION_INVALID_TSS
+        | EXCEPTION_SEGMENT_NOT_PRES
+        | EXCEPTION_STACK_FAULT
+        | EXCEPTION_GENERAL_PROTECTION => {
+            // A zero error code means the fault was not caused by a selector.
+            if error_code == 0 {
+                return ExceptionDetail::None;
+            }
+            let table = if error_code & (1 << 1) != 0 {
+                DescriptorTable::Idt
+            } else if error_code & (1 << 2) != 0 {
+                DescriptorTable::Ldt
+            } else {
+                DescriptorTable::Gdt
+            };
+            ExceptionDetail::Selector(SelectorError {
+                external: error_code & 1 != 0,
+                table,
+                index: ((error_code >> 3) & 0x1FFF) as u16,
+            })
+        }
+        EXCEPTION_DOUBLE_FAULT | EXCEPTION_ALIGNMENT_CHECK | EXCEPTION_CONTROL_PROTECTION => {
+            ExceptionDetail::Raw(error_code)
+        }
+        _ => ExceptionDetail::None,
+    }
+}
+
+fn format_exception_detail(detail: ExceptionDetail, emit: &mut dyn FnMut(fmt::Arguments<'_>)) {
+    match detail {
+        ExceptionDetail::None => {}
+        ExceptionDetail::PageFault(pf) => {
+            emit(format_args!(
+                "Cause: {} {} in {} mode{}{}{}",
+                if pf.present {
+                    "protection violation"
+                } else {
+                    "non-present page"
+                },
+                if pf.instruction_fetch {
+                    "on instruction fetch"
+                } else if pf.write {
+                    "on write"
+                } else {
+                    "on read"
+                },
+                if pf.user { "user" } else { "supervisor" },
+                if pf.reserved_bit {
+                    " [reserved bit set]"
+                } else {
+                    ""
+                },
+                if pf.protection_key {
+                    " [protection key]"
+                } else {
+                    ""
+                },
+                if pf.shadow_stack {
+                    " [shadow stack]"
+                } else {
+                    ""
+                }
+            ));
+        }
+        ExceptionDetail::Selector(sel) => {
+            emit(format_args!(
+                "Cause: selector index {} in {}{}",
+                sel.index,
+                sel.table.name(),
+                if sel.external {
+                    " (external event)"
+                } else {
+                    ""
+                }
+            ));
+        }
+        ExceptionDetail::Raw(code) => {
+            emit(format_args!("Cause: raw error code 0x{:x}", code));
+        }
+    }
+}
+
+fn kdiag_log_exception_detail(detail: ExceptionDetail) {
+    format_exception_detail(detail, &mut |args| crate::klog_info!("{}", args));
+}
+
+/// Words of the interrupted stack included in a fatal exception dump.
+pub const FATAL_STACK_WORDS: usize = 8;
+
+/// Inputs of a fatal exception dump, gathered up front so the report can
+/// be produced (and tested) without taking a real fault.
+pub struct FatalExceptionReport<'a> {
+    pub vector: u8,
+    pub frame: &'a InterruptFrame,
+    /// CR2 for page faults
+    pub fault_addr: Option<u64>,
+    /// Words read upwards from the interrupted RSP (empty if unsafe to read)
+    pub stack: &'a [u64],
+}
+
+/// Emit a fatal exception report one line at a time: decoded cause,
+/// register state, and a hexdump of the top of the interrupted stack.
+pub fn format_fatal_exception(
+    report: &FatalExceptionReport<'_>,
+    emit: &mut dyn FnMut(fmt::Arguments<'_>),
+) {
+    let f = report.frame;
+    let name = unsafe { cstr_to_str(exception_name(report.vector).as_ptr() as *const c_char) };
+    emit(format_args!(
+        "=== FATAL EXCEPTION: vector {} ({}) error 0x{:x} ===",
+        report.vector, name, f.error_code
+    ));
+    if let Some(addr) = report.fault_addr {
+        emit(format_args!("Fault address: 0x{:x}", addr));
+    }
+    format_exception_detail(decode_exception(report.vector, f.error_code), emit);
+    emit(format_args!(
+        "RIP: 0x{:x} {}  CS: 0x{:x}  RFLAGS: 0x{:x}",
+        f.rip,
+        Symbolized::at(f.rip),
+        f.cs,
+        f.rflags
+    ));
+    emit(format_args!("RSP: 0x{:x}  SS: 0x{:x}", f.rsp, f.ss));
+    emit(format_args!(
+        "RAX: 0x{:x}  RBX: 0x{:x}  RCX: 0x{:x}",
+        f.rax, f.rbx, f.rcx
+    ));
+    emit(format_args!(
+        "RDX: 0x{:x}  RSI: 0x{:x}  RDI: 0x{:x}",
+        f.rdx, f.rsi, f.rdi
+    ));
+    emit(format_args!(
+        "RBP: 0x{:x}  R8: 0x{:x}  R9: 0x{:x}",
+        f.rbp, f.r8, f.r9
+    ));
+    emit(format_args!(
+        "R10: 0x{:x}  R11: 0x{:x}  R12: 0x{:x}",
+        f.r10, f.r11, f.r12
+    ));
+    emit(format_args!(
+        "R13: 0x{:x}  R14: 0x{:x}  R15: 0x{:x}",
+        f.r13, f.r14, f.r15
+    ));
+    if report.stack.is_empty() {
+        emit(format_args!("Stack: not readable"));
+    } else {
+        for (i, pair) in report.stack.chunks(2).enumerate() {
+            let addr = f.rsp.wrapping_add((i * 16) as u64);
+            match pair {
+                [a, b] => emit(format_args!("Stack 0x{:016x}: {:016x} {:016x}", addr, a, b)),
+                [a] => emit(format_args!("Stack 0x{:016x}: {:016x}", addr, a)),
+                _ => {}
+            }
+        }
+    }
+    emit(format_args!("=== END FATAL EXCEPTION ==="));
+}
+
+/// Number of stack words that can be read at `rsp` without risking a
+/// nested fault: only aligned kernel stacks, never across a page boundary,
+/// and not when the fault itself is on the stack page (overflow into a guard).
+fn fatal_stack_readable_words(
+    vector: u8,
+    frame: &InterruptFrame,
+    fault_addr: Option<u64>,
+) -> usize {
+    const PAGE: u64 = 4096;
+    let rsp = frame.rsp;
+    if frame.cs & 3 != 0 || rsp & 7 != 0 || rsp < 0xFFFF_8000_0000_0000 {
+        return 0;
+    }
+    if vector == EXCEPTION_DOUBLE_FAULT {
+        return 0;
+    }
+    let stack_page = rsp & !(PAGE - 1);
+    if fault_addr.is_some_and(|addr| addr.wrapping_sub(stack_page).wrapping_add(PAGE) < 2 * PAGE) {
+        return 0;
+    }
+    let to_page_end = (PAGE - (rsp & (PAGE - 1))) / 8;
+    (to_page_end as usize).min(FATAL_STACK_WORDS)
+}
+
+/// Log everything known about an unrecoverable exception in one block.
+pub fn dump_fatal_exception(vector: u8, frame: &InterruptFrame) {
+    let fault_addr = (vector == EXCEPTION_PAGE_FAULT).then(cpu::read_cr2);
+    let mut stack = [0u64; FATAL_STACK_WORDS];
+    let words = fatal_stack_readable_words(vector, frame, fault_addr);
+    for (i, slot) in stack.iter_mut().enumerate().take(words) {
+        *slot = unsafe { core::ptr::read_volatile((frame.rsp as *const u64).add(i)) };
+    }
+    let report = FatalExceptionReport {
+        vector,
+        frame,
+        fault_addr,
+        stack: &stack[..words],
+    };
+    format_fatal_exception(&report, &mut |args| crate::klog_info!("{}", args));
+}
+
+/// Longest line produced by `format_nonrecoverable`.
+pub const NONRECOVERABLE_LINE_MAX: usize = 96;
+
+fn push_bytes(out: &mut [u8], pos: usize, bytes: &[u8]) -> usize {
+    let n = bytes.len().min(out.len().saturating_sub(pos));
+    out[pos..pos + n].copy_from_slice(&bytes[..n]);
+    pos + n
+}
+
+fn push_hex(out: &mut [u8], pos: usize, value: u64) -> usize {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut digits = [0u8; 18];
+    digits[0] = b'0';
+    digits[1] = b'x';
+    for (i, slot) in digits[2..].iter_mut().enumerate() {
+        *slot = DIGITS[((value >> (60 - i * 4)) & 0xF) as usize];
+    }
+    push_bytes(out, pos, &digits)
+}
+
+/// One-line report for an NMI or machine check, returning its length.
+///
+/// Built without `core::fmt`, stack reads or locks, so it is safe to run
+/// while the same exception may fire again.
+pub fn format_nonrecoverable(
+    vector: u8,
+    frame: &InterruptFrame,
+    out: &mut [u8; NONRECOVERABLE_LINE_MAX],
+) -> usize {
+    let name: &[u8] = match vector {
+        EXCEPTION_NMI => b"NMI",
+        EXCEPTION_MACHINE_CHECK => b"#MC",
+        _ => b"exception",
+    };
+    let mut pos = push_bytes(out, 0, b"FATAL ");
+    pos = push_bytes(out, pos, name);
+    pos = push_bytes(out, pos, b" rip=");
+    pos = push_hex(out, pos, frame.rip);
+    pos = push_bytes(out, pos, b" rsp=");
+    pos = push_hex(out, pos, frame.rsp);
+    pos = push_bytes(out, pos, b" err=");
+    push_hex(out, pos, frame.error_code)
+}
+
+/// Write the `format_nonrecoverable` line straight to COM1. It skips the
+/// log history and the deferred ring, so nothing here can take a lock.
+pub fn report_nonrecoverable(vector: u8, frame: &InterruptFrame) {
+    let mut line = [0u8; NONRECOVERABLE_LINE_MAX];
+    let len = format_nonrecoverable(vector, frame, &mut line);
+    crate::klog::klog_emergency_line(&line[..len]);
+}
+
 static MONOTONIC_TIME: AtomicU64 = AtomicU64::new(0);
 static LAST_TSC: AtomicU64 = AtomicU64::new(0);
 pub fn kdiag_timestamp() -> u64 {
@@ -153,9 +472,11 @@ pub fn kdiag_dump_interrupt_frame(frame: *const InterruptFrame) {
             exc_name,
             f.error_code
         );
+        kdiag_log_exception_detail(decode_exception(f.vector as u8, f.error_code));
         crate::klog_info!(
-            "RIP: 0x{:x}  CS: 0x{:x}  RFLAGS: 0x{:x}",
+            "RIP: 0x{:x} {}  CS: 0x{:x}  RFLAGS: 0x{:x}",
             f.rip,
+            Symbolized::at(f.rip),
             f.cs,
             f.rflags
         );
@@ -194,10 +515,11 @@ pub fn kdiag_dump_stack_trace_from_rbp(rbp: u64) {
     for i in 0..frame_count as usize {
         let entry = &entries[i];
         crate::klog_info!(
-            "Frame {}: RBP=0x{:x} RIP=0x{:x}",
+            "Frame {}: RBP=0x{:x} RIP=0x{:x} {}",
             i,
             entry.frame_pointer,
-            entry.return_address
+            entry.return_address,
+            Symbolized::return_to(entry.return_address)
         );
     }
 }
@@ -208,7 +530,11 @@ pub fn kdiag_dump_stack_trace_from_frame(frame: *const InterruptFrame) {
     unsafe {
         let f = &*frame;
         crate::klog_info!("=== STACK TRACE FROM EXCEPTION ===");
-        crate::klog_info!("Exception occurred at RIP: 0x{:x}", f.rip);
+        crate::klog_info!(
+            "Exception occurred at RIP: 0x{:x} {}",
+            f.rip,
+            Symbolized::at(f.rip)
+        );
         kdiag_dump_stack_trace_from_rbp(f.rbp);
         crate::klog_info!("=== END STACK TRACE ===");
     }
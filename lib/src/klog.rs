@@ -1,9 +1,12 @@
 use core::ffi::c_int;
 use core::fmt;
-use core::sync::atomic::{AtomicU8, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
+use crate::cpu;
 use crate::init_flag::InitFlag;
-use crate::ports::COM1;
+use crate::ports::{COM1, UART_LSR_TX_EMPTY, UART_LSR_TX_IDLE, UART_REG_LSR};
+use crate::ring_buffer::RingBuffer;
+use crate::spinlock::IrqMutex;
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -30,6 +33,159 @@ impl KlogLevel {
 static CURRENT_LEVEL: AtomicU8 = AtomicU8::new(KlogLevel::Info as u8);
 static SERIAL_READY: InitFlag = InitFlag::new();
 
+/// Module tags that can carry their own level via `klog_set_module_level`.
+pub const KLOG_MODULES: [&str; 10] = [
+    "boot",
+    "mm",
+    "scheduler",
+    "syscall",
+    "fs",
+    "pci",
+    "drivers",
+    "video",
+    "compositor",
+    "userland",
+];
+
+/// Stored in `MODULE_LEVELS` when a module follows the global level.
+const MODULE_LEVEL_UNSET: u8 = u8::MAX;
+
+static MODULE_LEVELS: [AtomicU8; KLOG_MODULES.len()] =
+    [const { AtomicU8::new(MODULE_LEVEL_UNSET) }; KLOG_MODULES.len()];
+
+fn module_index(module: &str) -> Option<usize> {
+    KLOG_MODULES.iter().position(|&m| m == module)
+}
+
+/// Capacity of the deferred log ring in bytes.
+pub const KLOG_DEFERRED_SIZE: usize = 4096;
+/// LSR polls before `klog_panic_flush` gives up on the UART.
+pub const KLOG_UART_DRAIN_SPINS: u32 = 100_000;
+
+/// Bytes queued by `klog_defer` for contexts that must not spin on the UART.
+static DEFERRED: IrqMutex<RingBuffer<u8, KLOG_DEFERRED_SIZE>> =
+    IrqMutex::new(RingBuffer::new_with(0));
+/// Set while `DEFERRED` may hold bytes; lets the hot logging path skip the
+/// lock (and its per-CPU preempt accounting) when nothing was deferred.
+static DEFERRED_QUEUED: AtomicBool = AtomicBool::new(false);
+
+/// Capacity of the log history ring in bytes.
+pub const KLOG_HISTORY_SIZE: usize = 16 * 1024;
+
+/// Every line that passed the level filter, whether or not serial was up.
+/// A plain spinlock taken with interrupts masked: `IrqMutex`'s preempt
+/// accounting would turn every log call into a reschedule point.
+static HISTORY: spin::Mutex<KlogHistory<KLOG_HISTORY_SIZE>> = spin::Mutex::new(KlogHistory::new());
+
+/// Bounded record of formatted log lines. Once full, the oldest bytes are
+/// overwritten and the line they belonged to is dropped from replays.
+pub struct KlogHistory<const N: usize> {
+    ring: RingBuffer<u8, N>,
+    wrapped: bool,
+}
+
+impl<const N: usize> KlogHistory<N> {
+    pub const fn new() -> Self {
+        Self {
+            ring: RingBuffer::new_overwrite_with(0),
+            wrapped: false,
+        }
+    }
+
+    pub fn record(&mut self, bytes: &[u8]) {
+        if self.ring.len() as usize + bytes.len() > N {
+            self.wrapped = true;
+        }
+        self.ring.write_slice(bytes);
+    }
+
+    pub fn clear(&mut self) {
+        self.ring.reset();
+        self.wrapped = false;
+    }
+
+    /// Hand the recorded bytes to `sink` oldest first, returning the count.
+    /// After a wrap the cut-off remains of the oldest line are skipped.
+    pub fn dump(&self, sink: &mut dyn FnMut(u8)) -> usize {
+        let mut bytes = self.ring.iter().copied();
+        if self.wrapped {
+            for b in bytes.by_ref() {
+                if b == b'\n' {
+                    break;
+                }
+            }
+        }
+        let mut count = 0;
+        for b in bytes {
+            sink(b);
+            count += 1;
+        }
+        count
+    }
+}
+
+impl<const N: usize> Default for KlogHistory<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Longest line kept in the history; the rest of a longer line still goes
+/// to serial but is cut from the replay.
+pub const KLOG_LINE_MAX: usize = 256;
+
+/// A formatted line collected on the stack so the history records it in one
+/// go, never interleaved with another CPU's fragments.
+struct LineBuf {
+    bytes: [u8; KLOG_LINE_MAX],
+    len: usize,
+}
+
+impl LineBuf {
+    const fn new() -> Self {
+        Self {
+            bytes: [0; KLOG_LINE_MAX],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, s: &[u8]) {
+        let n = s.len().min(KLOG_LINE_MAX - self.len);
+        self.bytes[self.len..self.len + n].copy_from_slice(&s[..n]);
+        self.len += n;
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// Record `text` plus its newline under a single history lock.
+fn history_record_line(text: &[u8]) {
+    let flags = cpu::save_flags_cli();
+    {
+        let mut history = HISTORY.lock();
+        history.record(text);
+        history.record(b"\n");
+    }
+    cpu::restore_flags(flags);
+}
+
+/// Replay the log history into `sink`, oldest line first.
+///
+/// Uses `try_lock` so it is safe from the panic path; if the history is
+/// held elsewhere nothing is replayed. `sink` runs under the history lock
+/// and must not log.
+pub fn klog_dump_ring(sink: &mut dyn FnMut(u8)) -> usize {
+    let flags = cpu::save_flags_cli();
+    let count = match HISTORY.try_lock() {
+        Some(history) => history.dump(sink),
+        None => 0,
+    };
+    cpu::restore_flags(flags);
+    count
+}
+
 #[inline(always)]
 fn is_enabled(level: KlogLevel) -> bool {
     level as u8 <= CURRENT_LEVEL.load(Ordering::Relaxed)
@@ -51,30 +207,186 @@ pub(crate) fn log_line(level: KlogLevel, text: &str) {
     if !is_enabled(level) {
         return;
     }
+    klog_drain_deferred();
     write_bytes(text.as_bytes());
     putc(b'\n');
+    history_record_line(text.as_bytes());
+}
+
+/// Write one line straight to COM1, bypassing the level filter and the
+/// deferred ring. Takes no locks, so it is usable from NMI context.
+pub fn klog_emergency_line(bytes: &[u8]) {
+    write_bytes(bytes);
+    putc(b'\n');
 }
 
 pub fn is_enabled_level(level: KlogLevel) -> bool {
     is_enabled(level)
 }
 
+/// Level filter for `module`: its override if set, the global level otherwise.
+/// Unknown tags always use the global level.
+pub fn klog_module_enabled(module: &str, level: KlogLevel) -> bool {
+    let limit = module_index(module)
+        .map(|i| MODULE_LEVELS[i].load(Ordering::Relaxed))
+        .filter(|&raw| raw != MODULE_LEVEL_UNSET)
+        .unwrap_or_else(|| CURRENT_LEVEL.load(Ordering::Relaxed));
+    level as u8 <= limit
+}
+
+/// Like `log_args`, but filtered by `module`'s level before formatting.
+pub fn log_args_module(module: &str, level: KlogLevel, args: fmt::Arguments<'_>) {
+    if !klog_module_enabled(module, level) {
+        return;
+    }
+    // The module filter already passed; format through the global path
+    // without its level check.
+    log_args_unfiltered(args);
+}
+
 pub fn log_args(level: KlogLevel, args: fmt::Arguments<'_>) {
     if !is_enabled(level) {
         return;
     }
-    struct KlogWriter;
+    log_args_unfiltered(args);
+}
+
+fn log_args_unfiltered(args: fmt::Arguments<'_>) {
+    struct KlogWriter(LineBuf);
     impl fmt::Write for KlogWriter {
         fn write_str(&mut self, s: &str) -> fmt::Result {
             write_bytes(s.as_bytes());
+            self.0.push(s.as_bytes());
             Ok(())
         }
     }
-    let _ = fmt::write(&mut KlogWriter, args);
+    klog_drain_deferred();
+    let mut writer = KlogWriter(LineBuf::new());
+    let _ = fmt::write(&mut writer, args);
     putc(b'\n');
+    history_record_line(writer.0.as_bytes());
+}
+
+/// Queue a log line without touching the UART.
+///
+/// The line is written out by the next synchronous log call or by
+/// `klog_panic_flush`. When the ring is full the oldest bytes are dropped.
+pub fn klog_defer(level: KlogLevel, args: fmt::Arguments<'_>) {
+    if !is_enabled(level) {
+        return;
+    }
+    struct DeferWriter<'a>(&'a mut RingBuffer<u8, KLOG_DEFERRED_SIZE>, LineBuf);
+    impl fmt::Write for DeferWriter<'_> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            for &b in s.as_bytes() {
+                self.0.push_overwrite(b);
+            }
+            self.1.push(s.as_bytes());
+            Ok(())
+        }
+    }
+    let mut ring = DEFERRED.lock();
+    let mut writer = DeferWriter(&mut ring, LineBuf::new());
+    let _ = fmt::write(&mut writer, args);
+    writer.0.push_overwrite(b'\n');
+    history_record_line(writer.1.as_bytes());
+    DEFERRED_QUEUED.store(true, Ordering::Release);
+}
+
+/// Number of deferred bytes not yet written out.
+pub fn klog_deferred_pending() -> usize {
+    if !DEFERRED_QUEUED.load(Ordering::Acquire) {
+        return 0;
+    }
+    DEFERRED.lock().len() as usize
+}
+
+/// Hand every deferred byte to `sink`, returning how many were drained.
+///
+/// Uses `try_lock` so a panic raised while the ring is held cannot deadlock;
+/// in that case nothing is drained.
+pub fn klog_drain_deferred_with(sink: &mut dyn FnMut(u8)) -> usize {
+    if !DEFERRED_QUEUED.load(Ordering::Acquire) {
+        return 0;
+    }
+    let Some(mut ring) = DEFERRED.try_lock() else {
+        return 0;
+    };
+    let mut drained = 0;
+    while let Some(b) = ring.try_pop() {
+        sink(b);
+        drained += 1;
+    }
+    DEFERRED_QUEUED.store(false, Ordering::Release);
+    drained
+}
+
+/// Write every deferred byte to the serial port.
+pub fn klog_drain_deferred() -> usize {
+    klog_drain_deferred_with(&mut putc)
+}
+
+/// Source of UART line status register reads.
+pub trait UartLineStatus {
+    fn line_status(&mut self) -> u8;
+}
+
+/// Line status of the COM1 console UART.
+pub struct Com1LineStatus;
+
+impl UartLineStatus for Com1LineStatus {
+    fn line_status(&mut self) -> u8 {
+        unsafe { COM1.offset(UART_REG_LSR).read() }
+    }
+}
+
+/// Spin until the transmit FIFO and shift register are both empty.
+///
+/// Returns false if the UART is still busy after `max_spins` polls.
+pub fn uart_wait_drained(uart: &mut dyn UartLineStatus, max_spins: u32) -> bool {
+    const IDLE: u8 = UART_LSR_TX_EMPTY | UART_LSR_TX_IDLE;
+    for _ in 0..max_spins {
+        if uart.line_status() & IDLE == IDLE {
+            return true;
+        }
+        core::hint::spin_loop();
+    }
+    false
+}
+
+/// Outcome of a panic-time log flush.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KlogFlushReport {
+    /// Deferred bytes written out
+    pub drained: usize,
+    /// The UART reported an empty transmitter before the spin limit
+    pub uart_drained: bool,
+}
+
+/// Drain deferred log bytes into `sink`, then wait for `uart` to go idle.
+pub fn klog_panic_flush_with(
+    sink: &mut dyn FnMut(u8),
+    uart: &mut dyn UartLineStatus,
+    max_spins: u32,
+) -> KlogFlushReport {
+    let drained = klog_drain_deferred_with(sink);
+    let uart_drained = uart_wait_drained(uart, max_spins);
+    KlogFlushReport {
+        drained,
+        uart_drained,
+    }
+}
+
+/// Synchronously push all pending log output to COM1.
+///
+/// Called by the panic handler before halting so the final messages are not
+/// lost in the deferred ring or the UART FIFO.
+pub fn klog_panic_flush() -> KlogFlushReport {
+    klog_panic_flush_with(&mut putc, &mut Com1LineStatus, KLOG_UART_DRAIN_SPINS)
 }
 pub fn klog_init() {
     CURRENT_LEVEL.store(KlogLevel::Info as u8, Ordering::Relaxed);
+    klog_clear_module_levels();
     SERIAL_READY.reset();
 }
 pub fn klog_attach_serial() {
@@ -86,6 +398,30 @@ pub fn klog_set_level(level: KlogLevel) {
 pub fn klog_get_level() -> KlogLevel {
     KlogLevel::from_raw(CURRENT_LEVEL.load(Ordering::Relaxed))
 }
+/// Override the level for one of `KLOG_MODULES`; false if the tag is unknown.
+pub fn klog_set_module_level(module: &str, level: KlogLevel) -> bool {
+    let Some(i) = module_index(module) else {
+        return false;
+    };
+    MODULE_LEVELS[i].store(level as u8, Ordering::Relaxed);
+    true
+}
+/// The override for `module`, or None when it follows the global level.
+pub fn klog_get_module_level(module: &str) -> Option<KlogLevel> {
+    let raw = MODULE_LEVELS[module_index(module)?].load(Ordering::Relaxed);
+    (raw != MODULE_LEVEL_UNSET).then(|| KlogLevel::from_raw(raw))
+}
+/// Drop `module`'s override so it follows the global level again.
+pub fn klog_clear_module_level(module: &str) {
+    if let Some(i) = module_index(module) {
+        MODULE_LEVELS[i].store(MODULE_LEVEL_UNSET, Ordering::Relaxed);
+    }
+}
+pub fn klog_clear_module_levels() {
+    for level in &MODULE_LEVELS {
+        level.store(MODULE_LEVEL_UNSET, Ordering::Relaxed);
+    }
+}
 pub fn klog_is_enabled(level: KlogLevel) -> c_int {
     if is_enabled(level) { 1 } else { 0 }
 }
@@ -100,6 +436,56 @@ macro_rules! klog {
     }};
 }
 
+#[macro_export]
+macro_rules! klog_defer {
+    ($level:expr, $($arg:tt)*) => {{
+        $crate::klog::klog_defer($level, ::core::format_args!($($arg)*));
+    }};
+}
+
+/// `klog!` filtered by a module tag from `KLOG_MODULES`.
+#[macro_export]
+macro_rules! klog_mod {
+    ($module:expr, $level:expr, $($arg:tt)*) => {{
+        $crate::klog::log_args_module($module, $level, ::core::format_args!($($arg)*));
+    }};
+}
+
+#[macro_export]
+macro_rules! klog_error_mod {
+    ($module:expr, $($arg:tt)*) => {
+        $crate::klog_mod!($module, $crate::klog::KlogLevel::Error, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! klog_warn_mod {
+    ($module:expr, $($arg:tt)*) => {
+        $crate::klog_mod!($module, $crate::klog::KlogLevel::Warn, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! klog_info_mod {
+    ($module:expr, $($arg:tt)*) => {
+        $crate::klog_mod!($module, $crate::klog::KlogLevel::Info, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! klog_debug_mod {
+    ($module:expr, $($arg:tt)*) => {
+        $crate::klog_mod!($module, $crate::klog::KlogLevel::Debug, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! klog_trace_mod {
+    ($module:expr, $($arg:tt)*) => {
+        $crate::klog_mod!($module, $crate::klog::KlogLevel::Trace, $($arg)*)
+    };
+}
+
 #[macro_export]
 macro_rules! klog_error {
     ($($arg:tt)*) => {
@@ -418,8 +418,11 @@ pub mod tsc {
 }
 
 pub mod alignment;
+pub mod clock;
+pub mod clock_tests;
 pub mod cpu_local;
 pub mod free_list;
+pub mod id_alloc;
 pub mod init_flag;
 pub mod kdiag;
 pub mod klog;
@@ -444,11 +447,14 @@ pub use paste;
 
 pub use alignment::{align_down_u64, align_down_usize, align_up_u64, align_up_usize};
 pub use alignment::{align_down_usize as align_down, align_up_usize as align_up};
-pub use kdiag::kdiag_dump_interrupt_frame;
 pub use kdiag::{InterruptFrame, KDIAG_STACK_TRACE_DEPTH, kdiag_timestamp};
+pub use kdiag::{
+    dump_fatal_exception, format_nonrecoverable, kdiag_dump_interrupt_frame, report_nonrecoverable,
+};
 pub use klog::{
-    KlogLevel, klog_attach_serial, klog_get_level, klog_init, klog_is_enabled, klog_newline,
-    klog_set_level,
+    KlogFlushReport, KlogLevel, klog_attach_serial, klog_deferred_pending, klog_drain_deferred,
+    klog_emergency_line, klog_get_level, klog_get_module_level, klog_init, klog_is_enabled,
+    klog_newline, klog_panic_flush, klog_set_level, klog_set_module_level,
 };
 pub use math::{abs_i32, max_i32, max_u32, min_i32, min_u32};
 pub use ports::COM1;
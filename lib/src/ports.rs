@@ -45,6 +45,7 @@ pub const UART_REG_LSR: u16 = 5;
 pub const UART_REG_MSR: u16 = 6;
 pub const UART_REG_SCR: u16 = 7;
 
+pub const UART_IER_RX_AVAILABLE: u8 = 0x01;
 pub const UART_LCR_DLAB: u8 = 0x80;
 pub const UART_IIR_FIFO_MASK: u8 = 0xC0;
 pub const UART_IIR_FIFO_ENABLED: u8 = 0xC0;
@@ -53,7 +54,12 @@ pub const UART_FCR_CLEAR_RX: u8 = 0x02;
 pub const UART_FCR_CLEAR_TX: u8 = 0x04;
 pub const UART_FCR_14_BYTE_THRESHOLD: u8 = 0xC0;
 pub const UART_LSR_DATA_READY: u8 = 0x01;
+pub const UART_LSR_OVERRUN: u8 = 0x02;
+pub const UART_LSR_PARITY_ERROR: u8 = 0x04;
+pub const UART_LSR_FRAMING_ERROR: u8 = 0x08;
+pub const UART_LSR_BREAK: u8 = 0x10;
 pub const UART_LSR_TX_EMPTY: u8 = 0x20;
+pub const UART_LSR_TX_IDLE: u8 = 0x40;
 pub const UART_MCR_DTR: u8 = 0x01;
 pub const UART_MCR_RTS: u8 = 0x02;
 pub const UART_MCR_AUX2: u8 = 0x08;
@@ -1,3 +1,12 @@
+/// What `push` and `write_slice` do when the buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowMode {
+    /// Refuse the new element and keep what is stored.
+    Reject,
+    /// Drop the oldest element to make room for the new one.
+    Overwrite,
+}
+
 /// Simple fixed-capacity ring buffer mirroring the old C macros.
 /// Uses a backing array with head/tail/count indices.
 #[derive(Debug)]
@@ -6,37 +15,68 @@ pub struct RingBuffer<T, const N: usize> {
     head: u32,
     tail: u32,
     count: u32,
+    mode: OverflowMode,
 }
 
 impl<T: Copy, const N: usize> RingBuffer<T, N> {
     /// Create a new ring buffer with all elements set to the given value.
     /// This is const-compatible and can be used for static initialization.
+    /// The buffer rejects pushes when full.
     #[inline(always)]
     pub const fn new_with(value: T) -> Self {
+        Self::new_with_mode(value, OverflowMode::Reject)
+    }
+
+    /// Like `new_with`, but a full buffer drops its oldest element on push.
+    #[inline(always)]
+    pub const fn new_overwrite_with(value: T) -> Self {
+        Self::new_with_mode(value, OverflowMode::Overwrite)
+    }
+
+    #[inline(always)]
+    const fn new_with_mode(value: T, mode: OverflowMode) -> Self {
         Self {
             data: [value; N],
             head: 0,
             tail: 0,
             count: 0,
+            mode,
         }
     }
 
+    #[inline(always)]
+    pub const fn mode(&self) -> OverflowMode {
+        self.mode
+    }
+
     /// Returns the current number of elements in the buffer.
     #[inline(always)]
     pub const fn len(&self) -> u32 {
         self.count
     }
+
+    /// Iterate over the stored elements from oldest to newest without popping them.
+    #[inline(always)]
+    pub fn iter(&self) -> RingIter<'_, T, N> {
+        RingIter {
+            buf: self,
+            pos: self.tail,
+            remaining: self.count,
+        }
+    }
 }
 
 impl<T: Copy + Default, const N: usize> RingBuffer<T, N> {
+    /// Empty buffer that rejects pushes when full.
     #[inline(always)]
     pub fn new() -> Self {
-        Self {
-            data: [T::default(); N],
-            head: 0,
-            tail: 0,
-            count: 0,
-        }
+        Self::new_with(T::default())
+    }
+
+    /// Empty buffer that drops its oldest element when pushed while full.
+    #[inline(always)]
+    pub fn new_overwrite() -> Self {
+        Self::new_overwrite_with(T::default())
     }
 
     #[inline(always)]
@@ -85,6 +125,46 @@ impl<T: Copy + Default, const N: usize> RingBuffer<T, N> {
         true
     }
 
+    /// Push according to the buffer's `OverflowMode`; returns false only when
+    /// a `Reject` buffer is full.
+    #[inline(always)]
+    pub fn push(&mut self, value: T) -> bool {
+        match self.mode {
+            OverflowMode::Reject => self.try_push(value),
+            OverflowMode::Overwrite => {
+                self.push_overwrite(value);
+                true
+            }
+        }
+    }
+
+    /// Push as many of `values` as the mode allows, oldest first, and return
+    /// how many were stored. A `Reject` buffer stops when full; an `Overwrite`
+    /// buffer takes them all and keeps the newest `N`.
+    pub fn write_slice(&mut self, values: &[T]) -> usize {
+        let accepted = match self.mode {
+            OverflowMode::Reject => values.len().min((self.capacity() - self.count) as usize),
+            OverflowMode::Overwrite => values.len(),
+        };
+        // Anything older than the last N values would be overwritten anyway.
+        let skip = accepted.saturating_sub(N);
+        for &value in &values[skip..accepted] {
+            self.push_overwrite(value);
+        }
+        accepted
+    }
+
+    /// Pop up to `out.len()` elements, oldest first, and return how many were read.
+    pub fn read_slice(&mut self, out: &mut [T]) -> usize {
+        let n = out.len().min(self.count as usize);
+        for slot in &mut out[..n] {
+            *slot = self.data[self.tail as usize];
+            self.tail = (self.tail + 1) % self.capacity();
+        }
+        self.count -= n as u32;
+        n
+    }
+
     /// Pop oldest element; returns Some(value) or None when empty.
     #[inline(always)]
     pub fn try_pop(&mut self) -> Option<T> {
@@ -102,3 +182,31 @@ impl<T: Copy + Default, const N: usize> RingBuffer<T, N> {
         &self.data
     }
 }
+
+/// Borrowing iterator over a `RingBuffer`, yielding elements in push order.
+pub struct RingIter<'a, T, const N: usize> {
+    buf: &'a RingBuffer<T, N>,
+    pos: u32,
+    remaining: u32,
+}
+
+impl<'a, T, const N: usize> Iterator for RingIter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let item = &self.buf.data[self.pos as usize];
+        self.pos = (self.pos + 1) % N as u32;
+        self.remaining -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.remaining as usize;
+        (n, Some(n))
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for RingIter<'_, T, N> {}
@@ -1,9 +1,146 @@
 use crate::cpu;
 use crate::klog::{self, KlogLevel};
 use core::ffi::c_int;
+use core::fmt;
 
 const STACKTRACE_MAX_LOCAL: usize = 32;
 
+/// First four bytes of a symbol table blob.
+pub const KSYMTAB_MAGIC: [u8; 4] = *b"KSYM";
+const KSYMTAB_HEADER_SIZE: usize = 8;
+const KSYMTAB_ENTRY_SIZE: usize = 16;
+
+/// Linker-provided bounds of `.ksymtab`, filled at build time by
+/// `scripts/gen_ksymtab.py` and empty when the build skipped it.
+mod externs {
+    unsafe extern "C" {
+        pub(super) static __start_ksymtab: u8;
+        pub(super) static __stop_ksymtab: u8;
+    }
+}
+
+/// Read-only view of a symbol table blob.
+///
+/// Layout, little endian: `KSYMTAB_MAGIC`, a u32 entry count, then that many
+/// 16-byte entries `{ addr: u64, size: u32, name_offset: u32 }` sorted by
+/// address, then a pool of NUL-terminated names the offsets point into.
+#[derive(Clone, Copy)]
+pub struct SymbolTable<'a> {
+    entries: &'a [u8],
+    names: &'a [u8],
+}
+
+impl<'a> SymbolTable<'a> {
+    /// Validate the header and split `blob`; None if it is not a table.
+    pub fn parse(blob: &'a [u8]) -> Option<Self> {
+        if blob.len() < KSYMTAB_HEADER_SIZE || blob[..4] != KSYMTAB_MAGIC {
+            return None;
+        }
+        let count = u32::from_le_bytes(blob[4..8].try_into().ok()?) as usize;
+        let end = count
+            .checked_mul(KSYMTAB_ENTRY_SIZE)?
+            .checked_add(KSYMTAB_HEADER_SIZE)?;
+        if end > blob.len() {
+            return None;
+        }
+        Some(Self {
+            entries: &blob[KSYMTAB_HEADER_SIZE..end],
+            names: &blob[end..],
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len() / KSYMTAB_ENTRY_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `(start, size, name_offset)` of entry `index`.
+    fn entry(&self, index: usize) -> (u64, u64, usize) {
+        let raw = &self.entries[index * KSYMTAB_ENTRY_SIZE..][..KSYMTAB_ENTRY_SIZE];
+        let field =
+            |at: usize| u32::from_le_bytes([raw[at], raw[at + 1], raw[at + 2], raw[at + 3]]);
+        let start = field(0) as u64 | (field(4) as u64) << 32;
+        (start, field(8) as u64, field(12) as usize)
+    }
+
+    fn name(&self, offset: usize) -> Option<&'a str> {
+        let tail = self.names.get(offset..)?;
+        let len = tail.iter().position(|&b| b == 0)?;
+        core::str::from_utf8(&tail[..len]).ok()
+    }
+
+    /// The symbol containing `addr` and the offset of `addr` into it.
+    pub fn resolve(&self, addr: u64) -> Option<(&'a str, u64)> {
+        // Binary search for the last entry starting at or below `addr`.
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.entry(mid).0 <= addr {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let (start, size, name_offset) = self.entry(lo.checked_sub(1)?);
+        let offset = addr - start;
+        if offset >= size {
+            return None;
+        }
+        Some((self.name(name_offset)?, offset))
+    }
+}
+
+/// The table embedded in the running kernel, if the build produced one.
+pub fn kernel_symbols() -> Option<SymbolTable<'static>> {
+    let start = &raw const externs::__start_ksymtab;
+    let stop = &raw const externs::__stop_ksymtab;
+    let len = (stop as usize).saturating_sub(start as usize);
+    SymbolTable::parse(unsafe { core::slice::from_raw_parts(start, len) })
+}
+
+/// Resolve a kernel address to `(symbol, offset)` using the embedded table.
+pub fn stacktrace_resolve(addr: u64) -> Option<(&'static str, u64)> {
+    kernel_symbols()?.resolve(addr)
+}
+
+/// Formats an address as `symbol+0xoffset`, or `?` when it cannot be resolved.
+pub struct Symbolized {
+    addr: u64,
+    return_address: bool,
+}
+
+impl Symbolized {
+    /// An address that is itself inside the code, such as a faulting RIP.
+    pub fn at(addr: u64) -> Self {
+        Self {
+            addr,
+            return_address: false,
+        }
+    }
+
+    /// A return address: it points past the call, possibly off the end of a
+    /// function that never returns, so the byte before it is resolved.
+    pub fn return_to(addr: u64) -> Self {
+        Self {
+            addr,
+            return_address: true,
+        }
+    }
+}
+
+impl fmt::Display for Symbolized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bias = self.return_address as u64;
+        match stacktrace_resolve(self.addr.wrapping_sub(bias)) {
+            Some((name, offset)) => write!(f, "{}+0x{:x}", name, offset + bias),
+            None => f.write_str("?"),
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct StacktraceEntry {
@@ -95,10 +232,11 @@ pub fn stacktrace_dump_from(rbp: u64, max_frames: c_int) {
     for i in 0..captured as usize {
         let entry = &local_entries[i];
         crate::klog_info!(
-            "  #{} rbp=0x{:x} rip=0x{:x}",
+            "  #{} rbp=0x{:x} rip=0x{:x} {}",
             i,
             entry.frame_pointer,
-            entry.return_address
+            entry.return_address,
+            Symbolized::return_to(entry.return_address)
         );
     }
 }
@@ -0,0 +1,59 @@
+//! In-memory `DrawTarget` for tests of drawing primitives and text.
+
+use slopos_abi::draw::{DrawTarget, pixel_ops};
+use slopos_abi::pixel::DrawPixelFormat;
+
+/// `W`x`H` target storing one raw color per pixel. Pixels start at 0 and
+/// writes outside the bounds are dropped.
+pub struct TestCanvas<const W: usize, const H: usize> {
+    pub px: [[u32; W]; H],
+}
+
+impl<const W: usize, const H: usize> Default for TestCanvas<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize, const H: usize> TestCanvas<W, H> {
+    pub const fn new() -> Self {
+        Self { px: [[0; W]; H] }
+    }
+
+    pub fn at(&self, x: i32, y: i32) -> u32 {
+        self.px[y as usize][x as usize]
+    }
+
+    /// Whether anything nonzero was drawn at (`x`, `y`).
+    pub fn inked(&self, x: i32, y: i32) -> bool {
+        self.at(x, y) != 0
+    }
+}
+
+impl<const W: usize, const H: usize> DrawTarget for TestCanvas<W, H> {
+    fn width(&self) -> u32 {
+        W as u32
+    }
+
+    fn height(&self) -> u32 {
+        H as u32
+    }
+
+    fn pitch(&self) -> usize {
+        W * 4
+    }
+
+    fn bytes_pp(&self) -> u8 {
+        4
+    }
+
+    fn pixel_format(&self) -> DrawPixelFormat {
+        DrawPixelFormat::Bgra
+    }
+
+    fn draw_pixel(&mut self, x: i32, y: i32, color: u32) {
+        if pixel_ops::in_bounds(x, y, W as u32, H as u32) {
+            self.px[y as usize][x as usize] = color;
+        }
+    }
+}
@@ -27,7 +27,7 @@ use core::ffi::{c_char, c_int};
 use core::ptr;
 
 /// Maximum number of test suites that can be registered.
-pub const HARNESS_MAX_SUITES: usize = 30;
+pub const HARNESS_MAX_SUITES: usize = 48;
 
 /// Default cycles per millisecond estimate (3 GHz).
 const DEFAULT_CYCLES_PER_MS: u64 = 3_000_000;
@@ -182,7 +182,8 @@ fn cached_cycles_per_ms_mut() -> *mut u64 {
     &raw mut CACHED_CYCLES_PER_MS
 }
 
-/// Estimate CPU cycles per millisecond using CPUID if available.
+/// Estimate CPU cycles per millisecond from the calibrated TSC frequency,
+/// falling back to CPUID and then a fixed guess.
 pub fn estimate_cycles_per_ms() -> u64 {
     unsafe {
         if *cached_cycles_per_ms_mut() != 0 {
@@ -190,13 +191,13 @@ pub fn estimate_cycles_per_ms() -> u64 {
         }
     }
 
-    let (max_leaf, _, _, _) = crate::cpu::cpuid(0);
     let mut cycles_per_ms = DEFAULT_CYCLES_PER_MS;
-    if max_leaf >= 0x16 {
-        let (freq_mhz, _, _, _) = crate::cpu::cpuid(0x16);
-        if freq_mhz != 0 {
-            cycles_per_ms = freq_mhz as u64 * 1_000;
-        }
+    let mut tsc_hz = crate::clock::clock_tsc_hz();
+    if tsc_hz == 0 {
+        tsc_hz = crate::clock::cpuid_tsc_hz();
+    }
+    if tsc_hz != 0 {
+        cycles_per_ms = tsc_hz / 1_000;
     }
 
     unsafe {
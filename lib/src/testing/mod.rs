@@ -6,6 +6,8 @@ mod runner;
 pub mod suite_masks;
 
 mod assertions;
+mod canvas;
+pub use canvas::TestCanvas;
 pub use config::{Suite, TestConfig, Verbosity, config_from_cmdline};
 pub use harness::{
     HARNESS_MAX_SUITES, HarnessConfig, TestRunSummary, TestSuiteDesc, TestSuiteResult,
@@ -5,7 +5,9 @@ use slopos_abi::arch::x86_64::paging::PageFlags;
 
 use crate::hhdm::PhysAddrHhdm;
 use crate::mm_constants::PAGE_SIZE_4KB;
-use crate::page_alloc::{ALLOC_FLAG_ZERO, alloc_page_frame, free_page_frame, page_frame_get_ref};
+use crate::page_alloc::{
+    ALLOC_FLAG_ZERO, ALLOC_FLAG_ZERO_ON_FREE, alloc_page_frame, free_page_frame, page_frame_get_ref,
+};
 use crate::paging::{ProcessPageDir, map_page_4kb_in_dir, paging_is_cow, virt_to_phys_in_dir};
 use crate::tlb;
 
@@ -65,7 +67,7 @@ fn resolve_multi_ref(
     aligned_vaddr: VirtAddr,
     old_phys: PhysAddr,
 ) -> Result<(), CowError> {
-    let new_phys = alloc_page_frame(ALLOC_FLAG_ZERO);
+    let new_phys = alloc_page_frame(ALLOC_FLAG_ZERO | ALLOC_FLAG_ZERO_ON_FREE);
     if new_phys.is_null() {
         return Err(CowError::AllocationFailed);
     }
@@ -7,7 +7,9 @@
 use slopos_abi::addr::VirtAddr;
 
 use crate::mm_constants::PAGE_SIZE_4KB;
-use crate::page_alloc::{ALLOC_FLAG_ZERO, alloc_page_frame, free_page_frame};
+use crate::page_alloc::{
+    ALLOC_FLAG_ZERO, ALLOC_FLAG_ZERO_ON_FREE, alloc_page_frame, free_page_frame,
+};
 use crate::paging::{ProcessPageDir, map_page_4kb_in_dir, virt_to_phys_in_dir};
 use crate::process_vm;
 use crate::tlb;
@@ -86,7 +88,7 @@ pub fn handle_demand_fault(
         return Ok(());
     }
 
-    let phys = alloc_page_frame(ALLOC_FLAG_ZERO);
+    let phys = alloc_page_frame(ALLOC_FLAG_ZERO | ALLOC_FLAG_ZERO_ON_FREE);
     if phys.is_null() {
         return Err(DemandError::AllocationFailed);
     }
@@ -0,0 +1,244 @@
+//! First-fit free-list heap over a fixed region.
+//!
+//! Untouched memory past the bump cursor is handed out with `bump_reserve`.
+//! Freed blocks go onto an address-ordered list whose nodes live in-band in
+//! the freed memory itself. Neighbouring free blocks are coalesced on free,
+//! and a free block that ends at the cursor is given back to the bump region.
+
+use core::alloc::Layout;
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::bump_reserve;
+
+/// Granularity of every block. Block offsets and sizes are multiples of
+/// this, so alignment padding is always big enough to hold a `FreeBlock`.
+pub const HEAP_BLOCK_ALIGN: usize = 16;
+
+/// Header written at the start of each free block.
+#[repr(C)]
+struct FreeBlock {
+    size: usize,
+    next: *mut FreeBlock,
+}
+
+const _: () = assert!(size_of::<FreeBlock>() <= HEAP_BLOCK_ALIGN);
+
+pub struct FreeListHeap {
+    base: *mut u8,
+    capacity: usize,
+    /// Offset of the first byte never handed out.
+    next: AtomicUsize,
+    /// Free blocks in ascending address order.
+    free: *mut FreeBlock,
+}
+
+unsafe impl Send for FreeListHeap {}
+
+impl FreeListHeap {
+    /// A heap with no backing memory; every allocation fails until `init`.
+    pub const fn empty() -> Self {
+        Self {
+            base: ptr::null_mut(),
+            capacity: 0,
+            next: AtomicUsize::new(0),
+            free: ptr::null_mut(),
+        }
+    }
+
+    /// Take ownership of `capacity` bytes at `base`.
+    ///
+    /// # Safety
+    /// `base` must be aligned to `HEAP_BLOCK_ALIGN` and the region must stay
+    /// valid and otherwise unused for as long as the heap is.
+    pub unsafe fn init(&mut self, base: *mut u8, capacity: usize) {
+        self.base = base;
+        self.capacity = capacity & !(HEAP_BLOCK_ALIGN - 1);
+        self.next = AtomicUsize::new(0);
+        self.free = ptr::null_mut();
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        !self.base.is_null()
+    }
+
+    /// Offset of the bump cursor; it only grows when no free block fits.
+    pub fn bump_offset(&self) -> usize {
+        self.next.load(Ordering::Relaxed)
+    }
+
+    /// Bytes available to future allocations, free blocks included.
+    pub fn bytes_remaining(&self) -> usize {
+        self.capacity - self.bump_offset() + self.free_bytes()
+    }
+
+    /// Total size of the blocks on the free list.
+    pub fn free_bytes(&self) -> usize {
+        let mut total = 0;
+        let mut block = self.free;
+        while !block.is_null() {
+            unsafe {
+                total += (*block).size;
+                block = (*block).next;
+            }
+        }
+        total
+    }
+
+    /// Number of blocks on the free list.
+    pub fn free_blocks(&self) -> usize {
+        let mut count = 0;
+        let mut block = self.free;
+        while !block.is_null() {
+            count += 1;
+            block = unsafe { (*block).next };
+        }
+        count
+    }
+
+    /// Size of the block backing `layout`, or None if it would overflow.
+    fn block_size(layout: Layout) -> Option<usize> {
+        let size = layout.size().max(HEAP_BLOCK_ALIGN);
+        Some(size.checked_add(HEAP_BLOCK_ALIGN - 1)? & !(HEAP_BLOCK_ALIGN - 1))
+    }
+
+    /// Allocate a block for `layout`, returning null when out of memory.
+    pub fn alloc(&mut self, layout: Layout) -> *mut u8 {
+        if !self.is_initialized() {
+            return ptr::null_mut();
+        }
+        let Some(size) = Self::block_size(layout) else {
+            return ptr::null_mut();
+        };
+        let align = layout.align().max(HEAP_BLOCK_ALIGN);
+
+        if let Some(offset) = self.take_free(size, align) {
+            return unsafe { self.base.add(offset) };
+        }
+
+        // The cursor is always block-aligned, so reserve enough slack to
+        // align the absolute address and give back what is left over. A
+        // trailing remainder ends at the cursor and returns to the bump region.
+        let Some(reserve) = size.checked_add(align - HEAP_BLOCK_ALIGN) else {
+            return ptr::null_mut();
+        };
+        let Ok(bump_layout) = Layout::from_size_align(reserve, HEAP_BLOCK_ALIGN) else {
+            return ptr::null_mut();
+        };
+        let Some(offset) = bump_reserve(&self.next, self.capacity, bump_layout) else {
+            return ptr::null_mut();
+        };
+        let addr = self.base as usize + offset;
+        let start = offset + (addr.next_multiple_of(align) - addr);
+        if start > offset {
+            self.insert_free(offset, start - offset);
+        }
+        let tail = offset + reserve - (start + size);
+        if tail > 0 {
+            self.insert_free(start + size, tail);
+        }
+        unsafe { self.base.add(start) }
+    }
+
+    /// Return a block obtained from `alloc` with the same `layout`.
+    ///
+    /// # Safety
+    /// `ptr` must come from `alloc` on this heap with `layout` and must not be
+    /// freed twice.
+    pub unsafe fn dealloc(&mut self, ptr: *mut u8, layout: Layout) {
+        if ptr.is_null() {
+            return;
+        }
+        let Some(size) = Self::block_size(layout) else {
+            return;
+        };
+        self.insert_free(ptr as usize - self.base as usize, size);
+    }
+
+    /// Unlink the first free block that can hold `size` bytes at `align`,
+    /// putting any leading padding and trailing remainder back on the list.
+    fn take_free(&mut self, size: usize, align: usize) -> Option<usize> {
+        let base = self.base as usize;
+        let mut prev: *mut FreeBlock = ptr::null_mut();
+        let mut block = self.free;
+        while !block.is_null() {
+            let (block_size, next) = unsafe { ((*block).size, (*block).next) };
+            let block_off = block as usize - base;
+            // Align the absolute address; `base` itself is only block-aligned.
+            let start = (block as usize).next_multiple_of(align) - base;
+            let pad = start - block_off;
+            if pad.checked_add(size).is_some_and(|need| need <= block_size) {
+                if prev.is_null() {
+                    self.free = next;
+                } else {
+                    unsafe { (*prev).next = next };
+                }
+                if pad > 0 {
+                    self.insert_free(block_off, pad);
+                }
+                let tail = block_size - pad - size;
+                if tail > 0 {
+                    self.insert_free(start + size, tail);
+                }
+                return Some(start);
+            }
+            prev = block;
+            block = next;
+        }
+        None
+    }
+
+    /// Put `size` bytes at `offset` on the free list, merging with the blocks
+    /// on either side when they touch.
+    fn insert_free(&mut self, offset: usize, size: usize) {
+        let base = self.base as usize;
+        let mut prev: *mut FreeBlock = ptr::null_mut();
+        let mut next = self.free;
+        while !next.is_null() && (next as usize - base) < offset {
+            prev = next;
+            next = unsafe { (*next).next };
+        }
+
+        let mut block = unsafe { self.base.add(offset) } as *mut FreeBlock;
+        unsafe {
+            block.write(FreeBlock { size, next });
+            if !next.is_null() && offset + size == next as usize - base {
+                (*block).size += (*next).size;
+                (*block).next = (*next).next;
+            }
+            if prev.is_null() {
+                self.free = block;
+            } else if (prev as usize - base) + (*prev).size == offset {
+                (*prev).size += (*block).size;
+                (*prev).next = (*block).next;
+                block = prev;
+            } else {
+                (*prev).next = block;
+            }
+
+            // The last block may now end at the cursor: hand it back.
+            let block_off = block as usize - base;
+            if (*block).next.is_null() && block_off + (*block).size == self.bump_offset() {
+                self.unlink_last(block);
+                self.next.store(block_off, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Remove `block`, the tail of the free list, from the list.
+    fn unlink_last(&mut self, block: *mut FreeBlock) {
+        if self.free == block {
+            self.free = ptr::null_mut();
+            return;
+        }
+        let mut cur = self.free;
+        while !cur.is_null() {
+            let next = unsafe { (*cur).next };
+            if next == block {
+                unsafe { (*cur).next = ptr::null_mut() };
+                return;
+            }
+            cur = next;
+        }
+    }
+}
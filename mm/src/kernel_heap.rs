@@ -6,7 +6,7 @@ use slopos_lib::free_list::{
     BlockHeader, FreeList, HEADER_SIZE, MAGIC_FREE, MIN_BLOCK_SIZE, round_up_pow2, size_class,
     try_split_block,
 };
-use slopos_lib::{IrqMutex, klog_debug, klog_info};
+use slopos_lib::{IrqMutex, klog_debug_mod, klog_info};
 
 use crate::memory_layout::{mm_get_kernel_heap_end, mm_get_kernel_heap_start};
 use crate::mm_constants::{PAGE_SIZE_4KB, PageFlags};
@@ -115,7 +115,7 @@ fn expand_heap(heap: &mut KernelHeap, min_size: u32) -> c_int {
         pages_needed = 4;
     }
 
-    klog_debug!("Expanding heap by {} pages", pages_needed);
+    klog_debug_mod!("mm", "Expanding heap by {} pages", pages_needed);
 
     let expansion_start = heap.current_break;
     let total_bytes = (pages_needed as u64) * PAGE_SIZE_4KB;
@@ -276,7 +276,7 @@ pub fn init_kernel_heap() -> c_int {
     }
 
     heap.initialized = true;
-    klog_debug!("Kernel heap initialized at 0x{:x}", heap.start_addr);
+    klog_debug_mod!("mm", "Kernel heap initialized at 0x{:x}", heap.start_addr);
 
     0
 }
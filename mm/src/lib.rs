@@ -5,6 +5,7 @@
 pub mod aslr;
 pub mod cow;
 pub mod demand;
+pub mod early_heap;
 pub mod elf;
 pub mod hhdm;
 pub mod kernel_heap;
@@ -23,6 +24,7 @@ pub mod tests;
 pub mod tests_cow_edge;
 pub mod tests_demand;
 pub mod tests_oom;
+pub mod tests_user_copy;
 pub mod tlb;
 pub mod tlb_tests;
 pub mod user_copy;
@@ -31,9 +33,11 @@ pub mod vma_flags;
 pub mod vma_tree;
 
 use core::alloc::{GlobalAlloc, Layout};
-use core::ptr;
 use core::sync::atomic::{AtomicUsize, Ordering};
-use slopos_lib::align_up;
+
+use slopos_lib::cpu;
+
+use crate::early_heap::FreeListHeap;
 
 const HEAP_SIZE: usize = 2 * 1024 * 1024;
 
@@ -48,32 +52,81 @@ struct AlignedHeap([u8; HEAP_SIZE]);
 #[unsafe(link_section = ".bss.heap")]
 static mut HEAP: AlignedHeap = AlignedHeap([0; HEAP_SIZE]);
 
-pub struct BumpAllocator {
-    next: AtomicUsize,
+/// Free-list heap over HEAP, set up on first use. Always taken with
+/// interrupts disabled, like the klog history lock.
+static EARLY_HEAP: spin::Mutex<FreeListHeap> = spin::Mutex::new(FreeListHeap::empty());
+
+/// Reserve `layout` from a bump region of `capacity` bytes whose cursor is
+/// `next`, returning the offset of the reservation.
+///
+/// Any request whose aligned end would pass `capacity` or overflow `usize`
+/// is treated as exhaustion and leaves the cursor untouched.
+pub(crate) fn bump_reserve(next: &AtomicUsize, capacity: usize, layout: Layout) -> Option<usize> {
+    let align = layout.align().max(8);
+    let mut current = next.load(Ordering::Relaxed);
+    loop {
+        let offset = current.checked_add(align - 1)? & !(align - 1);
+        let end = offset.checked_add(layout.size())?;
+        if end > capacity {
+            return None;
+        }
+        match next.compare_exchange_weak(current, end, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => return Some(offset),
+            Err(actual) => current = actual,
+        }
+    }
 }
 
-impl BumpAllocator {
-    pub const fn new() -> Self {
-        Self {
-            next: AtomicUsize::new(0),
+/// Bytes left in the early heap, counting freed blocks available for reuse.
+pub fn early_heap_bytes_remaining() -> usize {
+    let flags = cpu::save_flags_cli();
+    let remaining = {
+        let heap = EARLY_HEAP.lock();
+        if heap.is_initialized() {
+            heap.bytes_remaining()
+        } else {
+            HEAP_SIZE
         }
+    };
+    cpu::restore_flags(flags);
+    remaining
+}
+
+/// Kernel global allocator backed by the static HEAP.
+///
+/// Freed memory goes back on an in-band free list and is reused by later
+/// allocations; see `early_heap::FreeListHeap`.
+pub struct FreeListAllocator;
+
+impl FreeListAllocator {
+    pub const fn new() -> Self {
+        Self
     }
 }
 
-unsafe impl GlobalAlloc for BumpAllocator {
+impl Default for FreeListAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl GlobalAlloc for FreeListAllocator {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        let align = layout.align().max(8);
-        let size = layout.size();
-        let mut offset = self.next.load(Ordering::Relaxed);
-        offset = align_up(offset, align);
-        if offset + size > HEAP_SIZE {
-            return ptr::null_mut();
-        }
-        self.next.store(offset + size, Ordering::Relaxed);
-        unsafe { HEAP.0.as_mut_ptr().add(offset) }
+        let flags = cpu::save_flags_cli();
+        let ptr = {
+            let mut heap = EARLY_HEAP.lock();
+            if !heap.is_initialized() {
+                unsafe { heap.init(HEAP.0.as_mut_ptr(), HEAP_SIZE) };
+            }
+            heap.alloc(layout)
+        };
+        cpu::restore_flags(flags);
+        ptr
     }
 
-    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
-        // The bump allocator never frees; this is acceptable for early kernel bring-up.
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let flags = cpu::save_flags_cli();
+        unsafe { EARLY_HEAP.lock().dealloc(ptr, layout) };
+        cpu::restore_flags(flags);
     }
 }
@@ -23,7 +23,7 @@ use slopos_lib::string::cstr_to_str;
 
 use slopos_abi::DisplayInfo;
 use slopos_abi::boot::LimineMemmapResponse;
-use slopos_lib::{InitFlag, align_down_u64, align_up_u64, cpu, klog_debug, klog_info};
+use slopos_lib::{InitFlag, align_down_u64, align_up_u64, cpu, klog_debug_mod, klog_info};
 
 const CPUID_FEAT_EDX_APIC: u32 = 1 << 9;
 const MSR_APIC_BASE: u32 = 0x1B;
@@ -304,7 +304,11 @@ fn map_acpi_regions(memmap: *const LimineMemmapResponse, hhdm_offset: u64) {
             }
         }
         if mapped_count > 0 {
-            klog_debug!("MM: Mapped {} ACPI reclaimable pages to HHDM", mapped_count);
+            klog_debug_mod!(
+                "mm",
+                "MM: Mapped {} ACPI reclaimable pages to HHDM",
+                mapped_count
+            );
         }
     }
 }
@@ -561,8 +565,11 @@ pub fn init_memory_system(
     framebuffer: Option<(u64, &DisplayInfo)>,
 ) -> c_int {
     unsafe {
-        klog_debug!("========== SlopOS Memory System Initialization ==========");
-        klog_debug!("Initializing complete memory management system...");
+        klog_debug_mod!(
+            "mm",
+            "========== SlopOS Memory System Initialization =========="
+        );
+        klog_debug_mod!("mm", "Initializing complete memory management system...");
 
         FRAMEBUFFER_RESERVATION = framebuffer.map(|(addr, info)| FramebufferReservation {
             address: addr,
@@ -637,7 +644,10 @@ pub fn init_memory_system(
         display_memory_summary();
 
         klog_info!("MM: Complete memory system initialization successful!");
-        klog_debug!("MM: Ready for scheduler and video subsystem initialization\n");
+        klog_debug_mod!(
+            "mm",
+            "MM: Ready for scheduler and video subsystem initialization\n"
+        );
     }
     0
 }
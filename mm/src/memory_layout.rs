@@ -1,13 +1,13 @@
 use core::ffi::c_void;
 use core::ptr;
 
-use slopos_lib::{InitFlag, klog_debug};
+use slopos_lib::{InitFlag, klog_debug_mod};
 
 use crate::mm_constants::{
     BOOT_STACK_PHYS_ADDR, BOOT_STACK_SIZE, KERNEL_HEAP_SIZE, KERNEL_HEAP_VBASE,
     KERNEL_VIRTUAL_BASE, PAGE_SIZE_1GB, PROCESS_CODE_START_VA, PROCESS_DATA_START_VA,
-    PROCESS_HEAP_MAX_VA, PROCESS_HEAP_START_VA, PROCESS_STACK_SIZE_BYTES, PROCESS_STACK_TOP_VA,
-    USER_SPACE_END_VA, USER_SPACE_START_VA,
+    PROCESS_HEAP_MAX_VA, PROCESS_HEAP_START_VA, PROCESS_MMAP_END_VA, PROCESS_MMAP_START_VA,
+    PROCESS_STACK_SIZE_BYTES, PROCESS_STACK_TOP_VA, USER_SPACE_END_VA, USER_SPACE_START_VA,
 };
 use crate::symbols;
 
@@ -34,6 +34,8 @@ pub struct ProcessMemoryLayout {
     pub data_start: u64,
     pub heap_start: u64,
     pub heap_max: u64,
+    pub mmap_start: u64,
+    pub mmap_end: u64,
     pub stack_top: u64,
     pub stack_size: u64,
     pub user_space_start: u64,
@@ -61,6 +63,8 @@ static PROCESS_LAYOUT: ProcessMemoryLayout = ProcessMemoryLayout {
     data_start: PROCESS_DATA_START_VA,
     heap_start: PROCESS_HEAP_START_VA,
     heap_max: PROCESS_HEAP_MAX_VA,
+    mmap_start: PROCESS_MMAP_START_VA,
+    mmap_end: PROCESS_MMAP_END_VA,
     stack_top: PROCESS_STACK_TOP_VA,
     stack_size: PROCESS_STACK_SIZE_BYTES,
     user_space_start: USER_SPACE_START_VA,
@@ -98,7 +102,7 @@ pub fn init_kernel_memory_layout() {
         KERNEL_LAYOUT.user_space_end = USER_SPACE_END_VA;
     }
 
-    klog_debug!("SlopOS: Kernel memory layout initialized");
+    klog_debug_mod!("mm", "SlopOS: Kernel memory layout initialized");
 }
 pub fn get_kernel_memory_layout() -> *const KernelMemoryLayout {
     if LAYOUT_INIT.is_set() {
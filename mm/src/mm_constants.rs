@@ -11,8 +11,8 @@ pub use slopos_abi::arch::x86_64::memory::{
     EXCEPTION_STACK_TOTAL_SIZE, HHDM_VIRT_BASE, KERNEL_HEAP_SIZE, KERNEL_HEAP_VBASE,
     KERNEL_PDPT_INDEX, KERNEL_PML4_INDEX, KERNEL_VIRTUAL_BASE, MAX_MEMORY_REGIONS, MAX_PROCESSES,
     MMIO_VIRT_BASE, MMIO_VIRT_SIZE, PROCESS_CODE_START_VA, PROCESS_DATA_START_VA,
-    PROCESS_HEAP_MAX_VA, PROCESS_HEAP_START_VA, PROCESS_STACK_SIZE_BYTES, PROCESS_STACK_TOP_VA,
-    USER_SPACE_END_VA, USER_SPACE_START_VA,
+    PROCESS_HEAP_MAX_VA, PROCESS_HEAP_START_VA, PROCESS_MMAP_END_VA, PROCESS_MMAP_START_VA,
+    PROCESS_STACK_SIZE_BYTES, PROCESS_STACK_TOP_VA, USER_SPACE_END_VA, USER_SPACE_START_VA,
 };
 
 // INVALID_PROCESS_ID is canonical in task module
@@ -41,7 +41,7 @@ use core::ptr;
 use core::sync::atomic::{AtomicU32, Ordering};
 
 use slopos_abi::addr::PhysAddr;
-use slopos_lib::{InitFlag, IrqMutex, align_down_u64, align_up_u64, klog_debug, klog_info};
+use slopos_lib::{InitFlag, IrqMutex, align_down_u64, align_up_u64, klog_debug_mod, klog_info};
 
 use crate::hhdm::PhysAddrHhdm;
 use crate::memory_reservations::{
@@ -53,6 +53,9 @@ use crate::mm_constants::PAGE_SIZE_4KB;
 pub const ALLOC_FLAG_ZERO: u32 = 0x01;
 pub const ALLOC_FLAG_DMA: u32 = 0x02;
 pub const ALLOC_FLAG_KERNEL: u32 = 0x04;
+/// Scrub the frame when its last reference is freed, so the contents cannot
+/// leak to whoever allocates it next. Meant for user-visible pages.
+pub const ALLOC_FLAG_ZERO_ON_FREE: u32 = 0x08;
 pub const ALLOC_FLAG_ORDER_SHIFT: u32 = 8;
 pub const ALLOC_FLAG_ORDER_MASK: u32 = 0x1F << ALLOC_FLAG_ORDER_SHIFT;
 pub const ALLOC_FLAG_NO_PCP: u32 = 0x80;
@@ -500,7 +503,7 @@ fn get_current_cpu() -> usize {
     slopos_lib::get_current_cpu()
 }
 
-fn pcp_try_alloc(cpu: usize) -> u32 {
+fn pcp_try_alloc(cpu: usize, flags: u32) -> u32 {
     if cpu >= MAX_CPUS || !PCP_INIT.is_set() {
         return INVALID_PAGE_FRAME;
     }
@@ -536,6 +539,7 @@ fn pcp_try_alloc(cpu: usize) -> u32 {
                 if let Some(desc) = unsafe { alloc.frame_desc_mut(head) } {
                     desc.state = PAGE_FRAME_ALLOCATED;
                     desc.ref_count = 1;
+                    desc.flags = flags as u8;
                     desc.next_free = INVALID_PAGE_FRAME;
                 }
             }
@@ -753,7 +757,8 @@ pub fn init_page_allocator(frame_array: *mut c_void, max_frames: u32) -> c_int {
         }
     }
 
-    klog_debug!(
+    klog_debug_mod!(
+        "mm",
         "Page frame allocator initialized with {} frame descriptors (max order {})",
         max_frames,
         alloc.max_order
@@ -812,11 +817,11 @@ pub fn alloc_page_frames(count: u32, flags: u32) -> PhysAddr {
         let frame_num = if use_pcp {
             let cpu = get_current_cpu();
 
-            let mut frame = pcp_try_alloc(cpu);
+            let mut frame = pcp_try_alloc(cpu, flags);
 
             if frame == INVALID_PAGE_FRAME {
                 pcp_refill(cpu, flags);
-                frame = pcp_try_alloc(cpu);
+                frame = pcp_try_alloc(cpu, flags);
             }
 
             if frame == INVALID_PAGE_FRAME {
@@ -888,7 +893,7 @@ pub fn free_page_frame(phys_addr: PhysAddr) -> c_int {
         alloc.phys_to_frame(phys_addr)
     };
 
-    let (is_valid, is_allocated, order, is_pcp_candidate) = {
+    let (is_valid, is_allocated, order, is_pcp_candidate, scrub) = {
         let alloc = PAGE_ALLOCATOR.lock();
         if !alloc.is_valid_frame(frame_num) {
             klog_info!("free_page_frame: Invalid physical address");
@@ -910,13 +915,21 @@ pub fn free_page_frame(phys_addr: PhysAddr) -> c_int {
             return 0;
         }
 
-        (true, is_alloc, ord, pcp_ok)
+        let scrub = frame.flags as u32 & ALLOC_FLAG_ZERO_ON_FREE != 0;
+        (true, is_alloc, ord, pcp_ok, scrub)
     };
 
     if !is_valid || !is_allocated {
         return 0;
     }
 
+    if scrub {
+        let base = PAGE_ALLOCATOR.lock().frame_to_phys(frame_num);
+        for i in 0..PageAllocator::order_block_pages(order) {
+            let _ = zero_physical_page(base.offset(i as u64 * PAGE_SIZE_4KB));
+        }
+    }
+
     if is_pcp_candidate {
         let cpu = get_current_cpu();
         if pcp_try_free(cpu, frame_num) {
@@ -972,6 +985,76 @@ pub fn get_page_allocator_stats(total: *mut u32, free: *mut u32, allocated: *mut
     }
 }
 
+/// Snapshot of allocator occupancy and buddy fragmentation.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PageAllocStats {
+    pub total_frames: u32,
+    /// Frames available for allocation, including those parked in per-CPU caches.
+    pub free_frames: u32,
+    pub allocated_frames: u32,
+    /// Subset of `free_frames` sitting in per-CPU caches.
+    pub pcp_cached_frames: u32,
+    /// Largest physically contiguous block the buddy lists can hand out, in frames.
+    pub largest_free_run: u32,
+    /// How much of the buddy free memory lies outside the largest block (0..=1000).
+    pub fragmentation_permille: u32,
+}
+
+impl PageAllocator {
+    fn largest_free_order(&self) -> Option<u32> {
+        (0..=self.max_order)
+            .rev()
+            .find(|&order| self.free_lists[order as usize] != INVALID_PAGE_FRAME)
+    }
+}
+
+/// Snapshot the allocator counters. Cheap: the totals are maintained
+/// incrementally and only the buddy list heads are inspected.
+///
+/// Returned by value rather than through an out-pointer; C-side callers get
+/// the same `#[repr(C)]` layout without a pointer to validate.
+pub fn page_alloc_stats() -> PageAllocStats {
+    let mut pcp_cached_frames = 0u32;
+    for cache in PER_CPU_CACHES.iter() {
+        pcp_cached_frames = pcp_cached_frames.saturating_add(cache.count.load(Ordering::Relaxed));
+    }
+
+    let alloc = PAGE_ALLOCATOR.lock();
+    let largest_free_run = alloc
+        .largest_free_order()
+        .map_or(0, PageAllocator::order_block_pages);
+    let fragmentation_permille = if alloc.free_frames == 0 {
+        0
+    } else {
+        let contiguous = (largest_free_run as u64 * 1000) / alloc.free_frames as u64;
+        1000u32.saturating_sub(contiguous as u32)
+    };
+
+    PageAllocStats {
+        total_frames: alloc.total_frames,
+        free_frames: alloc.free_frames.saturating_add(pcp_cached_frames),
+        allocated_frames: alloc.allocated_frames.saturating_sub(pcp_cached_frames),
+        pcp_cached_frames,
+        largest_free_run,
+        fragmentation_permille,
+    }
+}
+
+/// Log a one-line `meminfo` summary of `page_alloc_stats`.
+pub fn page_alloc_log_meminfo() {
+    let stats = page_alloc_stats();
+    klog_info!(
+        "meminfo: total={} free={} allocated={} pcp={} largest_run={} frag={}/1000",
+        stats.total_frames,
+        stats.free_frames,
+        stats.allocated_frames,
+        stats.pcp_cached_frames,
+        stats.largest_free_run,
+        stats.fragmentation_permille
+    );
+}
+
 pub fn get_pcp_stats(cpu: usize, count: *mut u32, allocs: *mut u32, frees: *mut u32) {
     if cpu >= MAX_CPUS {
         return;
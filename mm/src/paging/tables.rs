@@ -7,7 +7,7 @@ use slopos_abi::arch::x86_64::page_table::{
     PAGE_TABLE_ENTRIES, PageTable, PageTableEntry, PageTableLevel,
 };
 use slopos_abi::arch::x86_64::paging::PageFlags;
-use slopos_lib::{cpu, klog_debug, klog_info};
+use slopos_lib::{cpu, klog_debug_mod, klog_info};
 
 use super::walker::{PageTableWalker, WalkAction};
 use crate::hhdm::{self, PhysAddrHhdm};
@@ -649,19 +649,23 @@ pub fn init_paging() {
             panic!("Higher-half kernel mapping not found");
         }
 
-        klog_debug!(
+        klog_debug_mod!(
+            "mm",
             "Higher-half kernel mapping verified at 0x{:x}",
             kernel_phys.as_u64()
         );
 
         let identity_phys = virt_to_phys(VirtAddr::new(0x100000));
         if identity_phys == PhysAddr::new(0x100000) || hhdm::is_available() {
-            klog_debug!("Identity mapping verified");
+            klog_debug_mod!("mm", "Identity mapping verified");
         } else {
-            klog_debug!("Identity mapping not found (may be normal after early boot)");
+            klog_debug_mod!(
+                "mm",
+                "Identity mapping not found (may be normal after early boot)"
+            );
         }
 
-        klog_debug!("Paging system initialized successfully");
+        klog_debug_mod!("mm", "Paging system initialized successfully");
     }
 }
 
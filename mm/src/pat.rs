@@ -25,7 +25,7 @@
 
 use slopos_abi::arch::x86_64::cpuid::CPUID_FEAT_EDX_PAT;
 use slopos_abi::arch::x86_64::msr::Msr;
-use slopos_lib::{InitFlag, cpu, klog_debug, klog_info, klog_warn};
+use slopos_lib::{InitFlag, cpu, klog_debug_mod, klog_info, klog_warn};
 
 // =============================================================================
 // Memory Type Constants
@@ -141,7 +141,7 @@ pub fn pat_supported() -> bool {
 /// 10. Re-enable interrupts
 pub fn pat_init() {
     if !PAT_INIT.init_once() {
-        klog_debug!("PAT: Already initialized, skipping");
+        klog_debug_mod!("mm", "PAT: Already initialized, skipping");
         return;
     }
 
@@ -152,10 +152,10 @@ pub fn pat_init() {
 
     PAT_SUPPORTED.mark_set();
 
-    klog_debug!("PAT: Initializing Page Attribute Table with WC support");
+    klog_debug_mod!("mm", "PAT: Initializing Page Attribute Table with WC support");
 
     let old_pat = cpu::read_msr(Msr::PAT.address());
-    klog_debug!("PAT: Current value: 0x{:016x}", old_pat);
+    klog_debug_mod!("mm", "PAT: Current value: 0x{:016x}", old_pat);
 
     let flags = cpu::save_flags_cli();
 
@@ -183,7 +183,7 @@ pub fn pat_init() {
         );
     } else {
         klog_info!("PAT: Initialized with WC support (PA1=WC, PA5=WC)");
-        klog_debug!("PAT: New value: 0x{:016x}", new_pat);
+        klog_debug_mod!("mm", "PAT: New value: 0x{:016x}", new_pat);
     }
 }
 
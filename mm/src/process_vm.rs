@@ -2,6 +2,7 @@ use core::ffi::c_int;
 use core::ptr;
 
 use slopos_abi::addr::VirtAddr;
+use slopos_lib::id_alloc::IdAllocator;
 use slopos_lib::{IrqMutex, align_down, align_up, klog_info};
 
 use crate::aslr;
@@ -11,7 +12,8 @@ use crate::kernel_heap::{kfree, kmalloc};
 use crate::memory_layout::mm_get_process_layout;
 use crate::mm_constants::{INVALID_PROCESS_ID, MAX_PROCESSES, PAGE_SIZE_4KB, PageFlags};
 use crate::page_alloc::{
-    ALLOC_FLAG_ZERO, alloc_page_frame, free_page_frame, page_frame_can_free, page_frame_inc_ref,
+    ALLOC_FLAG_ZERO, ALLOC_FLAG_ZERO_ON_FREE, alloc_page_frame, free_page_frame,
+    page_frame_can_free, page_frame_inc_ref,
 };
 use crate::paging::{
     PageTable, ProcessPageDir, map_page_4kb_in_dir, paging_copy_kernel_mappings,
@@ -75,7 +77,7 @@ impl ProcessVm {
 struct VmManager {
     processes: [ProcessVm; MAX_PROCESSES],
     num_processes: u32,
-    next_process_id: u32,
+    process_ids: IdAllocator<MAX_PROCESSES>,
     active_process: *mut ProcessVm,
     process_list: *mut ProcessVm,
 }
@@ -87,7 +89,7 @@ impl VmManager {
         Self {
             processes: [ProcessVm::new(); MAX_PROCESSES],
             num_processes: 0,
-            next_process_id: 1,
+            process_ids: IdAllocator::new(),
             active_process: ptr::null_mut(),
             process_list: ptr::null_mut(),
         }
@@ -123,7 +125,7 @@ fn map_user_range(
     let mut mapped: u32 = 0;
 
     while current < end_addr {
-        let phys = alloc_page_frame(ALLOC_FLAG_ZERO);
+        let phys = alloc_page_frame(ALLOC_FLAG_ZERO | ALLOC_FLAG_ZERO_ON_FREE);
         if phys.is_null() {
             klog_info!("map_user_range: Physical allocation failed");
             rollback_range(page_dir, current, start_addr, &mut mapped);
@@ -791,7 +793,7 @@ fn load_segment_pages(
             }
             existing_phys
         } else {
-            let new_phys = alloc_page_frame(ALLOC_FLAG_ZERO);
+            let new_phys = alloc_page_frame(ALLOC_FLAG_ZERO | ALLOC_FLAG_ZERO_ON_FREE);
             if new_phys.is_null() {
                 return Err(ElfError::NullPointer);
             }
@@ -897,13 +899,17 @@ pub fn create_process_vm() -> u32 {
         (*pml4).zero();
     }
 
-    let process_id = manager.next_process_id;
-    manager.next_process_id += 1;
+    let Some(process_id) = manager.process_ids.alloc() else {
+        klog_info!("create_process_vm: Process IDs exhausted");
+        free_page_frame(pml4_phys);
+        return INVALID_PROCESS_ID;
+    };
 
     let page_dir_ptr = kmalloc(core::mem::size_of::<ProcessPageDir>()) as *mut ProcessPageDir;
     if page_dir_ptr.is_null() {
         klog_info!("create_process_vm: Failed to allocate page directory");
         free_page_frame(pml4_phys);
+        manager.process_ids.free(process_id);
         return INVALID_PROCESS_ID;
     }
     unsafe {
@@ -921,6 +927,7 @@ pub fn create_process_vm() -> u32 {
         if map_user_sections(page_dir_ptr) != 0 {
             kfree(page_dir_ptr as *mut _);
             free_page_frame(pml4_phys);
+            manager.process_ids.free(process_id);
             return INVALID_PROCESS_ID;
         }
     }
@@ -964,6 +971,7 @@ pub fn create_process_vm() -> u32 {
             kfree(page_dir_ptr as *mut _);
             proc.page_dir = ptr::null_mut();
             proc.process_id = INVALID_PROCESS_ID;
+            manager.process_ids.free(process_id);
             return INVALID_PROCESS_ID;
         }
 
@@ -984,6 +992,7 @@ pub fn create_process_vm() -> u32 {
             kfree(page_dir_ptr as *mut _);
             proc.page_dir = ptr::null_mut();
             proc.process_id = INVALID_PROCESS_ID;
+            manager.process_ids.free(process_id);
             return INVALID_PROCESS_ID;
         }
         proc.total_pages += stack_pages;
@@ -1062,6 +1071,7 @@ pub fn destroy_process_vm(process_id: u32) -> c_int {
         (*process_ptr).total_pages = 0;
         (*process_ptr).flags = 0;
         manager.num_processes = manager.num_processes.saturating_sub(1);
+        manager.process_ids.free(process_id);
     }
     0
 }
@@ -1169,7 +1179,7 @@ pub fn init_process_vm() -> c_int {
 
     let mut manager = VM_MANAGER.lock();
     manager.num_processes = 0;
-    manager.next_process_id = 1;
+    manager.process_ids.reset();
     manager.active_process = ptr::null_mut();
     manager.process_list = ptr::null_mut();
     for i in 0..MAX_PROCESSES {
@@ -1233,7 +1243,24 @@ pub fn process_vm_get_stack_top(process_id: u32) -> u64 {
     unsafe { (*process_ptr).stack_end }
 }
 
+/// Query the program break (`new_brk == 0`) or move it; see `process_vm_set_brk`.
 pub fn process_vm_brk(process_id: u32, new_brk: u64) -> u64 {
+    if new_brk == 0 {
+        let process_ptr = find_process_vm(process_id);
+        if process_ptr.is_null() {
+            return 0;
+        }
+        return unsafe { (*process_ptr).heap_end };
+    }
+    process_vm_set_brk(process_id, new_brk)
+}
+
+/// Move the heap break to `new_brk` (rounded up to a page) and return the
+/// resulting break. Growth only extends the lazy heap VMA; pages are
+/// faulted in on first touch. Shrinking unmaps and frees the released
+/// pages. Requests below the heap start, past the layout's heap limit, or
+/// reaching the guard page under the stack leave the break unchanged.
+pub fn process_vm_set_brk(process_id: u32, new_brk: u64) -> u64 {
     let process_ptr = find_process_vm(process_id);
     if process_ptr.is_null() {
         return 0;
@@ -1241,10 +1268,6 @@ pub fn process_vm_brk(process_id: u32, new_brk: u64) -> u64 {
     let process = unsafe { &mut *process_ptr };
     let layout = unsafe { &*mm_get_process_layout() };
 
-    if new_brk == 0 {
-        return process.heap_end;
-    }
-
     let aligned_brk = match new_brk.checked_add(PAGE_SIZE_4KB - 1) {
         Some(v) => v & !(PAGE_SIZE_4KB - 1),
         None => return process.heap_end,
@@ -1258,6 +1281,11 @@ pub fn process_vm_brk(process_id: u32, new_brk: u64) -> u64 {
         return process.heap_end;
     }
 
+    // Keep at least one unmapped guard page between heap and stack
+    if process.stack_start != 0 && aligned_brk > process.stack_start - PAGE_SIZE_4KB {
+        return process.heap_end;
+    }
+
     if aligned_brk > process.heap_end {
         let start_addr = process.heap_end;
         let end_addr = aligned_brk;
@@ -1291,6 +1319,93 @@ pub fn process_vm_brk(process_id: u32, new_brk: u64) -> u64 {
     process.heap_end
 }
 
+/// Move the break by `increment` bytes. Returns the previous break, or
+/// `None` if the process is unknown or the new break was rejected.
+pub fn process_vm_sbrk(process_id: u32, increment: i64) -> Option<u64> {
+    let process_ptr = find_process_vm(process_id);
+    if process_ptr.is_null() {
+        return None;
+    }
+    let old_brk = unsafe { (*process_ptr).heap_end };
+    if increment == 0 {
+        return Some(old_brk);
+    }
+    let target = old_brk.checked_add_signed(increment)?;
+    let aligned = target.checked_add(PAGE_SIZE_4KB - 1)? & !(PAGE_SIZE_4KB - 1);
+    if process_vm_set_brk(process_id, target) == aligned {
+        Some(old_brk)
+    } else {
+        None
+    }
+}
+
+/// Lowest `len`-byte gap in [window_start, window_end) not covered by a VMA.
+fn find_free_range(
+    process: *mut ProcessVm,
+    window_start: u64,
+    window_end: u64,
+    len: u64,
+) -> Option<u64> {
+    let tree = unsafe { &(*process).vma_tree };
+    let mut candidate = window_start;
+    loop {
+        let end = candidate.checked_add(len)?;
+        if end > window_end {
+            return None;
+        }
+        let overlap = tree.find_overlapping(candidate, end);
+        if overlap.is_null() {
+            return Some(candidate);
+        }
+        candidate = unsafe { (*overlap).end };
+    }
+}
+
+/// Reserve `len` bytes of zeroed, writable anonymous memory at the lowest
+/// free address in the layout's mmap window and return its base. `len` must
+/// be a nonzero multiple of the page size. Like the heap, the region is a
+/// lazy VMA: frames are allocated one page at a time on first touch, so a
+/// large request costs nothing until used. Returns `None` for a bad length
+/// or when the window has no large enough gap.
+pub fn process_vm_mmap(process_id: u32, len: u64) -> Option<u64> {
+    if len == 0 || (len & (PAGE_SIZE_4KB - 1)) != 0 {
+        return None;
+    }
+    let process_ptr = find_process_vm(process_id);
+    if process_ptr.is_null() {
+        return None;
+    }
+    let layout = unsafe { &*mm_get_process_layout() };
+
+    let start = find_free_range(process_ptr, layout.mmap_start, layout.mmap_end, len)?;
+    let end = start + len;
+
+    let vma_flags =
+        VmaFlags::READ | VmaFlags::WRITE | VmaFlags::USER | VmaFlags::LAZY | VmaFlags::ANON;
+    if add_vma_to_process(process_ptr, start, end, vma_flags) != 0 {
+        return None;
+    }
+    Some(start)
+}
+
+/// Unmap `len` bytes at `addr` from a region returned by `process_vm_mmap`
+/// and free the frames. Both must be page-aligned and nonzero, and the range
+/// must lie inside the mmap window and within a single mapping; unmapping
+/// the middle of a region splits it. Returns 0 on success, -1 otherwise.
+pub fn process_vm_munmap(process_id: u32, addr: u64, len: u64) -> c_int {
+    if len == 0 || ((addr | len) & (PAGE_SIZE_4KB - 1)) != 0 {
+        return -1;
+    }
+    let layout = unsafe { &*mm_get_process_layout() };
+    let Some(end) = addr.checked_add(len) else {
+        return -1;
+    };
+    if addr < layout.mmap_start || end > layout.mmap_end {
+        return -1;
+    }
+    process_vm_free(process_id, addr, len)
+}
+
 /// Clone address space with COW for fork(). Returns child PID or INVALID_PROCESS_ID.
 pub fn process_vm_clone_cow(parent_id: u32) -> u32 {
     let parent_ptr = find_process_vm(parent_id);
@@ -1341,13 +1456,17 @@ pub fn process_vm_clone_cow(parent_id: u32) -> u32 {
         (*pml4).zero();
     }
 
-    let child_id = manager.next_process_id;
-    manager.next_process_id += 1;
+    let Some(child_id) = manager.process_ids.alloc() else {
+        klog_info!("process_vm_clone_cow: Process IDs exhausted");
+        free_page_frame(pml4_phys);
+        return INVALID_PROCESS_ID;
+    };
 
     let child_page_dir = kmalloc(core::mem::size_of::<ProcessPageDir>()) as *mut ProcessPageDir;
     if child_page_dir.is_null() {
         klog_info!("process_vm_clone_cow: Failed to allocate page directory struct");
         free_page_frame(pml4_phys);
+        manager.process_ids.free(child_id);
         return INVALID_PROCESS_ID;
     }
     unsafe {
@@ -1461,6 +1580,7 @@ pub fn process_vm_clone_cow(parent_id: u32) -> u32 {
             kfree(child_page_dir as *mut _);
             (*child_ptr).reset();
         }
+        VM_MANAGER.lock().process_ids.free(child_id);
         return INVALID_PROCESS_ID;
     }
 
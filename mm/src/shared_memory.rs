@@ -4,10 +4,12 @@
 //! Used for client-compositor buffer sharing in the graphics stack.
 
 use core::ffi::c_int;
-use core::sync::atomic::{AtomicU32, Ordering};
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
 
 use slopos_lib::IrqRwLock;
 
+use slopos_abi::ShmError;
 use slopos_abi::addr::{PhysAddr, VirtAddr};
 pub use slopos_abi::pixel::PixelFormat;
 
@@ -15,7 +17,7 @@ use crate::mm_constants::{PAGE_SIZE_4KB, PageFlags};
 use crate::page_alloc::{ALLOC_FLAG_ZERO, alloc_page_frames, free_page_frame};
 use crate::paging::{map_page_4kb_in_dir, unmap_page_in_dir};
 use crate::process_vm::process_vm_get_page_dir;
-use slopos_lib::{align_up, klog_debug, klog_info};
+use slopos_lib::{align_up, klog_debug_mod, klog_info};
 
 pub const SUPPORTED_FORMATS_BITMAP: u32 = (1 << PixelFormat::Argb8888 as u32)
     | (1 << PixelFormat::Xrgb8888 as u32)
@@ -33,6 +35,9 @@ const MAX_MAPPINGS_PER_BUFFER: usize = 8;
 /// Maximum number of entries in the virtual address free list
 const MAX_VADDR_FREE_LIST: usize = 64;
 
+/// Largest buffer `shm_create` and `shm_reallocate` accept
+const SHM_MAX_SIZE: u64 = 64 * 1024 * 1024;
+
 /// Base virtual address for shared memory mappings in userland
 /// This is above the heap region to avoid conflicts
 const SHM_VADDR_BASE: u64 = 0x0000_7000_0000_0000;
@@ -85,6 +90,10 @@ struct ShmMapping {
     task_id: u32,
     /// Virtual address in the task's address space
     virt_addr: VirtAddr,
+    /// Number of 4KB pages mapped at `virt_addr`
+    pages: u32,
+    /// Still shows the frames the buffer had before `shm_reallocate`
+    stale: bool,
     /// Whether this slot is in use
     active: bool,
 }
@@ -94,6 +103,8 @@ impl ShmMapping {
         Self {
             task_id: 0,
             virt_addr: VirtAddr::NULL,
+            pages: 0,
+            stale: false,
             active: false,
         }
     }
@@ -126,8 +137,11 @@ struct SharedBuffer {
     ref_count: u32,
     /// True when compositor has released the buffer (client can reuse)
     released: bool,
-    /// Pixel format of this buffer (for compositor rendering)
-    format: PixelFormat,
+    /// Pixel format declared at creation (for compositor rendering).
+    /// `None` for plain `shm_create` buffers, which match the display format.
+    format: Option<PixelFormat>,
+    /// Frames replaced by `shm_reallocate` that stale mappings still show
+    retired: Option<ShmFrames>,
 }
 
 impl SharedBuffer {
@@ -145,7 +159,22 @@ impl SharedBuffer {
             surface_height: 0,
             ref_count: 0,
             released: false,
-            format: PixelFormat::Argb8888, // Default format
+            format: None,
+            retired: None,
+        }
+    }
+
+    fn has_stale_mappings(&self) -> bool {
+        self.mappings.iter().any(|m| m.active && m.stale)
+    }
+
+    /// Free the retired frames once no stale mapping shows them any more.
+    fn free_retired_if_unmapped(&mut self) {
+        if self.has_stale_mappings() {
+            return;
+        }
+        if let Some(frames) = self.retired.take() {
+            frames.free();
         }
     }
 }
@@ -205,7 +234,8 @@ impl SharedBufferRegistry {
                 // Remove entry from free list (exact fit or discard remainder)
                 // For simplicity, we don't split entries - just use exact match or larger
                 entry.active = false;
-                klog_debug!(
+                klog_debug_mod!(
+                    "mm",
                     "alloc_vaddr: reused free entry vaddr={:#x} size={}",
                     vaddr.as_u64(),
                     aligned_size
@@ -221,6 +251,51 @@ impl SharedBufferRegistry {
         vaddr
     }
 
+    /// Unmap mapping `index` of the buffer in `slot` and reclaim its
+    /// virtual address range. Retired frames are freed along with the last
+    /// stale mapping; the buffer's current frames are left alone.
+    fn drop_mapping(&mut self, slot: usize, index: usize) {
+        let mapping = self.buffers[slot].mappings[index];
+        let page_dir = process_vm_get_page_dir(mapping.task_id);
+        if !page_dir.is_null() {
+            for j in 0..mapping.pages {
+                let page_vaddr = mapping.virt_addr.offset((j as u64) * PAGE_SIZE_4KB);
+                unmap_page_in_dir(page_dir, page_vaddr);
+            }
+        }
+
+        let buffer = &mut self.buffers[slot];
+        buffer.mappings[index] = ShmMapping::empty();
+        buffer.mapping_count = buffer.mapping_count.saturating_sub(1);
+        if mapping.stale {
+            buffer.free_retired_if_unmapped();
+        }
+        self.free_vaddr(
+            mapping.virt_addr,
+            mapping.pages as usize * PAGE_SIZE_4KB as usize,
+        );
+    }
+
+    /// Remove every mapping of the buffer in `slot`, stale ones included.
+    fn unmap_all(&mut self, slot: usize) {
+        for index in 0..MAX_MAPPINGS_PER_BUFFER {
+            if self.buffers[slot].mappings[index].active {
+                self.drop_mapping(slot, index);
+            }
+        }
+    }
+
+    /// Remove the mappings of the buffer in `slot` that still show frames
+    /// from before its last reallocation.
+    fn unmap_stale(&mut self, slot: usize) {
+        for index in 0..MAX_MAPPINGS_PER_BUFFER {
+            let mapping = &self.buffers[slot].mappings[index];
+            if mapping.active && mapping.stale {
+                self.drop_mapping(slot, index);
+            }
+        }
+    }
+
     /// Return a virtual address range to the free list for reuse.
     fn free_vaddr(&mut self, vaddr: VirtAddr, size: usize) {
         let aligned_size = align_up(size, PAGE_SIZE_4KB as usize);
@@ -231,7 +306,8 @@ impl SharedBufferRegistry {
                 entry.vaddr = vaddr;
                 entry.size = aligned_size;
                 entry.active = true;
-                klog_debug!(
+                klog_debug_mod!(
+                    "mm",
                     "free_vaddr: added to free list vaddr={:#x} size={}",
                     vaddr.as_u64(),
                     aligned_size
@@ -242,7 +318,8 @@ impl SharedBufferRegistry {
 
         // Free list is full, can't reclaim this address
         // This is not an error - we just lose this vaddr range
-        klog_debug!(
+        klog_debug_mod!(
+            "mm",
             "free_vaddr: free list full, discarding vaddr={:#x} size={}",
             vaddr.as_u64(),
             aligned_size
@@ -262,7 +339,7 @@ static REGISTRY: IrqRwLock<SharedBufferRegistry> = IrqRwLock::new(SharedBufferRe
 /// # Returns
 /// Buffer token on success, 0 on failure
 pub fn shm_create(owner_process: u32, size: u64, flags: u32) -> u32 {
-    if size == 0 || size > 64 * 1024 * 1024 {
+    if size == 0 || size > SHM_MAX_SIZE {
         klog_info!("shm_create: invalid size {}", size);
         return 0;
     }
@@ -280,12 +357,7 @@ pub fn shm_create(owner_process: u32, size: u64, flags: u32) -> u32 {
     let slot = match registry.find_free_slot() {
         Some(s) => s,
         None => {
-            // Free the allocated pages
-            for i in 0..pages {
-                free_page_frame(PhysAddr::new(
-                    phys_addr.as_u64() + (i as u64) * PAGE_SIZE_4KB,
-                ));
-            }
+            free_frames(phys_addr, pages);
             klog_info!("shm_create: no free slots");
             return 0;
         }
@@ -306,7 +378,8 @@ pub fn shm_create(owner_process: u32, size: u64, flags: u32) -> u32 {
         surface_height: 0,
         ref_count: 1, // Owner holds initial reference
         released: false,
-        format: DEFAULT_PIXEL_FORMAT,
+        format: None,
+        retired: None,
     };
 
     token
@@ -341,10 +414,11 @@ pub fn shm_map(process_id: u32, token: u32, access: ShmAccess) -> u64 {
     {
         let buffer = &registry.buffers[slot];
 
-        // Check if already mapped for this process
+        // Check if already mapped for this process; stale mappings show
+        // the old frames, so the caller gets a fresh one instead
         for mapping in buffer.mappings.iter() {
-            if mapping.active && mapping.task_id == process_id {
-                klog_debug!("shm_map: already mapped for process {}", process_id);
+            if mapping.active && !mapping.stale && mapping.task_id == process_id {
+                klog_debug_mod!("mm", "shm_map: already mapped for process {}", process_id);
                 return mapping.virt_addr.as_u64();
             }
         }
@@ -399,6 +473,8 @@ pub fn shm_map(process_id: u32, token: u32, access: ShmAccess) -> u64 {
     buffer.mappings[mapping_slot] = ShmMapping {
         task_id: process_id,
         virt_addr: vaddr,
+        pages,
+        stale: false,
         active: true,
     };
     buffer.mapping_count += 1;
@@ -424,8 +500,8 @@ pub fn shm_unmap(process_id: u32, virt_addr: u64) -> c_int {
 
     let mut registry = REGISTRY.write();
 
-    // First pass: find the buffer and mapping, capture size for free list
-    let mut found_info: Option<(usize, usize, usize, VirtAddr)> = None; // (buffer_idx, mapping_idx, size, vaddr)
+    // Find the buffer and mapping
+    let mut found_info: Option<(usize, usize)> = None; // (buffer_idx, mapping_idx)
 
     for (buf_idx, buffer) in registry.buffers.iter().enumerate() {
         if !buffer.active {
@@ -437,7 +513,7 @@ pub fn shm_unmap(process_id: u32, virt_addr: u64) -> c_int {
                 && mapping.task_id == process_id
                 && mapping.virt_addr == virt_addr_typed
             {
-                found_info = Some((buf_idx, map_idx, buffer.size, mapping.virt_addr));
+                found_info = Some((buf_idx, map_idx));
                 break;
             }
         }
@@ -446,27 +522,16 @@ pub fn shm_unmap(process_id: u32, virt_addr: u64) -> c_int {
         }
     }
 
-    let (buf_idx, map_idx, buffer_size, mapping_vaddr) = match found_info {
+    let (buf_idx, map_idx) = match found_info {
         Some(info) => info,
         None => return -1,
     };
 
-    // Unmap all pages
-    let pages = registry.buffers[buf_idx].pages;
-    for i in 0..pages {
-        let page_vaddr = mapping_vaddr.offset((i as u64) * PAGE_SIZE_4KB);
-        unmap_page_in_dir(page_dir, page_vaddr);
-    }
-
-    // Clear the mapping
-    registry.buffers[buf_idx].mappings[map_idx] = ShmMapping::empty();
-    registry.buffers[buf_idx].mapping_count =
-        registry.buffers[buf_idx].mapping_count.saturating_sub(1);
-
-    // Return virtual address to free list for reuse
-    registry.free_vaddr(mapping_vaddr, buffer_size);
+    // Unmap the pages and return the virtual address to the free list
+    registry.drop_mapping(buf_idx, map_idx);
 
-    klog_debug!(
+    klog_debug_mod!(
+        "mm",
         "shm_unmap: unmapped vaddr={:#x} for process={}, returned to free list",
         virt_addr,
         process_id
@@ -504,54 +569,161 @@ pub fn shm_destroy(process_id: u32, token: u32) -> c_int {
         return -1;
     }
 
-    let buffer_size = registry.buffers[slot].size;
     let pages = registry.buffers[slot].pages;
     let phys_addr = registry.buffers[slot].phys_addr;
 
-    let mut vaddrs_to_free: [(VirtAddr, u32); MAX_MAPPINGS_PER_BUFFER] =
-        [(VirtAddr::NULL, 0); MAX_MAPPINGS_PER_BUFFER];
-    let mut vaddr_count = 0;
+    registry.unmap_all(slot);
+    free_frames(phys_addr, pages);
+    registry.buffers[slot] = SharedBuffer::empty();
 
-    for mapping in registry.buffers[slot].mappings.iter() {
-        if mapping.active {
-            vaddrs_to_free[vaddr_count] = (mapping.virt_addr, mapping.task_id);
-            vaddr_count += 1;
-        }
+    klog_debug_mod!(
+        "mm",
+        "shm_destroy: destroyed token={} for process={}",
+        token,
+        process_id
+    );
+
+    0
+}
+
+/// Told about shm frames just before they are freed: the physical base and
+/// the length in bytes.
+pub type ShmReleaseHook = fn(PhysAddr, usize);
+
+/// Registered release hook (set by the video subsystem during init).
+static RELEASE_HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Register a hook run before any shm frames go back to the page allocator,
+/// so hardware still reading them (display scanout) can be pointed away.
+/// It runs with the registry locked and must not call back into shm.
+pub fn register_shm_release_hook(hook: ShmReleaseHook) {
+    RELEASE_HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+fn free_frames(phys_addr: PhysAddr, pages: u32) {
+    let hook = RELEASE_HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        let hook: ShmReleaseHook = unsafe { core::mem::transmute(hook) };
+        hook(phys_addr, pages as usize * PAGE_SIZE_4KB as usize);
     }
+    for i in 0..pages {
+        free_page_frame(phys_addr.offset((i as u64) * PAGE_SIZE_4KB));
+    }
+}
 
-    for i in 0..vaddr_count {
-        let (vaddr, map_task_id) = vaddrs_to_free[i];
-        let page_dir = process_vm_get_page_dir(map_task_id);
-        if !page_dir.is_null() {
-            for j in 0..pages {
-                let page_vaddr = vaddr.offset((j as u64) * PAGE_SIZE_4KB);
-                unmap_page_in_dir(page_dir, page_vaddr);
-            }
-        }
+/// Frames taken off a buffer by `shm_reallocate`, or allocated for one by
+/// `shm_alloc_frames`, still allocated.
+pub struct ShmFrames {
+    pub phys_addr: PhysAddr,
+    pub size: usize,
+    pages: u32,
+}
+
+impl ShmFrames {
+    /// Return the frames to the page allocator.
+    pub fn free(self) {
+        free_frames(self.phys_addr, self.pages);
     }
+}
 
-    for mapping in registry.buffers[slot].mappings.iter_mut() {
-        *mapping = ShmMapping::empty();
+/// Allocate zeroed frames for `size` bytes, to be installed on a buffer
+/// with `shm_replace_frames`. Lets a caller fill them before the swap.
+///
+/// # Errors
+/// * `InvalidSize` - zero or above the shm size limit
+/// * `AllocationFailed` - not enough free frames
+pub fn shm_alloc_frames(size: u64) -> Result<ShmFrames, ShmError> {
+    if size == 0 || size > SHM_MAX_SIZE {
+        return Err(ShmError::InvalidSize);
     }
+    let aligned_size = align_up(size as usize, PAGE_SIZE_4KB as usize);
+    let pages = (aligned_size / PAGE_SIZE_4KB as usize) as u32;
 
-    for i in 0..pages {
-        free_page_frame(phys_addr.offset((i as u64) * PAGE_SIZE_4KB));
+    let phys_addr = alloc_page_frames(pages, ALLOC_FLAG_ZERO);
+    if phys_addr.is_null() {
+        klog_info!("shm_alloc_frames: failed to allocate {} pages", pages);
+        return Err(ShmError::AllocationFailed);
     }
+    Ok(ShmFrames {
+        phys_addr,
+        size: aligned_size,
+        pages,
+    })
+}
 
-    registry.buffers[slot] = SharedBuffer::empty();
+/// Back a buffer with `size` bytes of fresh zeroed frames, keeping its
+/// token, owner and format.
+///
+/// Existing mappings go stale: they keep showing the old frames, so a user
+/// still drawing into them does not fault, and `shm_map` hands out a fresh
+/// mapping of the new frames. Stale mappings left from an earlier
+/// reallocation are removed first. The old frames are returned so the
+/// caller can copy out what it wants to keep; it must then pass them to
+/// `shm_retire_frames`.
+///
+/// # Errors
+/// * `InvalidSize` - zero or above the shm size limit
+/// * `AllocationFailed` - not enough free frames; the buffer is unchanged
+/// * `InvalidToken` - no buffer with this token
+/// * `PermissionDenied` - caller does not own the buffer
+pub fn shm_reallocate(process_id: u32, token: u32, size: u64) -> Result<ShmFrames, ShmError> {
+    shm_replace_frames(process_id, token, shm_alloc_frames(size)?)
+}
 
-    for i in 0..vaddr_count {
-        let (vaddr, _) = vaddrs_to_free[i];
-        registry.free_vaddr(vaddr, buffer_size);
+/// Back a buffer with `frames`, as `shm_reallocate` does with fresh ones.
+/// On error the frames are freed and the buffer is unchanged.
+///
+/// # Errors
+/// * `InvalidToken` - no buffer with this token
+/// * `PermissionDenied` - caller does not own the buffer
+pub fn shm_replace_frames(
+    process_id: u32,
+    token: u32,
+    frames: ShmFrames,
+) -> Result<ShmFrames, ShmError> {
+    let mut registry = REGISTRY.write();
+    let Some(slot) = registry.find_by_token(token) else {
+        frames.free();
+        return Err(ShmError::InvalidToken);
+    };
+    if registry.buffers[slot].owner_task != process_id {
+        frames.free();
+        return Err(ShmError::PermissionDenied);
     }
 
-    klog_debug!(
-        "shm_destroy: destroyed token={} for process={}",
-        token,
-        process_id
-    );
+    registry.unmap_stale(slot);
+    let buffer = &mut registry.buffers[slot];
+    for mapping in buffer.mappings.iter_mut() {
+        mapping.stale = mapping.active;
+    }
+    let old = ShmFrames {
+        phys_addr: buffer.phys_addr,
+        size: buffer.size,
+        pages: buffer.pages,
+    };
+    buffer.phys_addr = frames.phys_addr;
+    buffer.size = frames.size;
+    buffer.pages = frames.pages;
+    buffer.surface_width = 0;
+    buffer.surface_height = 0;
+    Ok(old)
+}
 
-    0
+/// Take back the frames `shm_reallocate` returned for `token`. They are
+/// freed at once unless stale mappings still show them, in which case they
+/// go when the last of those is unmapped.
+pub fn shm_retire_frames(token: u32, frames: ShmFrames) {
+    let mut registry = REGISTRY.write();
+    let Some(slot) = registry.find_by_token(token) else {
+        frames.free();
+        return;
+    };
+    let buffer = &mut registry.buffers[slot];
+    if buffer.has_stale_mappings() && buffer.retired.is_none() {
+        buffer.retired = Some(frames);
+    } else {
+        frames.free();
+    }
 }
 
 /// Get information about a shared buffer by token.
@@ -569,6 +741,16 @@ pub fn shm_get_buffer_info(token: u32) -> (PhysAddr, usize, u32) {
     }
 }
 
+/// Run `f` on a buffer's (phys_addr, size) with the buffer held, so it
+/// cannot be destroyed or reallocated until `f` returns. None if the token
+/// is unknown.
+pub fn shm_with_buffer<R>(token: u32, f: impl FnOnce(PhysAddr, usize) -> R) -> Option<R> {
+    let registry = REGISTRY.read();
+    let slot = registry.find_by_token(token)?;
+    let buf = &registry.buffers[slot];
+    Some(f(buf.phys_addr, buf.size))
+}
+
 /// Register a shared buffer as a surface for the compositor.
 ///
 /// # Arguments
@@ -577,38 +759,56 @@ pub fn shm_get_buffer_info(token: u32) -> (PhysAddr, usize, u32) {
 /// * `width` - Surface width in pixels
 /// * `height` - Surface height in pixels
 ///
-/// # Returns
-/// 0 on success, -1 on failure
-pub fn surface_attach(process_id: u32, token: u32, width: u32, height: u32) -> c_int {
+/// # Errors
+/// * `InvalidToken` - no buffer with this token
+/// * `PermissionDenied` - caller does not own the buffer
+/// * `InvalidSize` - the buffer is too small for the dimensions
+pub fn surface_attach(
+    process_id: u32,
+    token: u32,
+    width: u32,
+    height: u32,
+) -> Result<(), ShmError> {
     let mut registry = REGISTRY.write();
 
     let slot = match registry.find_by_token(token) {
         Some(s) => s,
-        None => return -1,
+        None => {
+            klog_info!("surface_attach: unknown token {}", token);
+            return Err(ShmError::InvalidToken);
+        }
     };
 
     let buffer = &mut registry.buffers[slot];
 
     // Only owner can attach (owner_task stores process_id)
     if buffer.owner_task != process_id {
-        return -1;
+        klog_info!(
+            "surface_attach: process {} does not own token {}",
+            process_id,
+            token
+        );
+        return Err(ShmError::PermissionDenied);
     }
 
     // Verify size is sufficient (assume 4 bytes per pixel)
-    let required_size = (width as usize) * (height as usize) * 4;
-    if required_size > buffer.size {
+    let required_size = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(4));
+    if required_size.is_none_or(|size| size > buffer.size) {
         klog_info!(
-            "surface_attach: buffer too small ({}), need {}",
+            "surface_attach: buffer too small ({}) for {}x{}",
             buffer.size,
-            required_size
+            width,
+            height
         );
-        return -1;
+        return Err(ShmError::InvalidSize);
     }
 
     buffer.surface_width = width;
     buffer.surface_height = height;
 
-    0
+    Ok(())
 }
 
 /// Get surface info for a task.
@@ -672,13 +872,17 @@ pub fn shm_cleanup_task(task_id: u32) {
         for mapping in buffer.mappings.iter_mut() {
             if mapping.active && mapping.task_id == task_id {
                 if mapping_vaddr_count < MAX_SHARED_BUFFERS {
-                    vaddrs_from_mappings[mapping_vaddr_count] = (mapping.virt_addr, buffer.size);
+                    vaddrs_from_mappings[mapping_vaddr_count] = (
+                        mapping.virt_addr,
+                        mapping.pages as usize * PAGE_SIZE_4KB as usize,
+                    );
                     mapping_vaddr_count += 1;
                 }
                 *mapping = ShmMapping::empty();
                 buffer.mapping_count = buffer.mapping_count.saturating_sub(1);
             }
         }
+        buffer.free_retired_if_unmapped();
     }
 
     let mut owned_buffer_slots: [usize; MAX_SHARED_BUFFERS] = [0; MAX_SHARED_BUFFERS];
@@ -698,24 +902,19 @@ pub fn shm_cleanup_task(task_id: u32) {
     for i in 0..owned_count {
         let slot = owned_buffer_slots[i];
         let buffer = &mut registry.buffers[slot];
-        let buffer_size = buffer.size;
-        let phys_addr = buffer.phys_addr;
-        let pages = buffer.pages;
-
-        for j in 0..pages {
-            free_page_frame(phys_addr.offset((j as u64) * PAGE_SIZE_4KB));
-        }
+        free_frames(buffer.phys_addr, buffer.pages);
 
         for mapping in buffer.mappings.iter_mut() {
             if mapping.active {
+                let mapped_size = mapping.pages as usize * PAGE_SIZE_4KB as usize;
                 if owned_vaddr_count < MAX_SHARED_BUFFERS {
-                    vaddrs_from_owned[owned_vaddr_count] = (mapping.virt_addr, buffer_size);
+                    vaddrs_from_owned[owned_vaddr_count] = (mapping.virt_addr, mapped_size);
                     owned_vaddr_count += 1;
                 }
 
                 let page_dir = process_vm_get_page_dir(mapping.task_id);
                 if !page_dir.is_null() {
-                    for j in 0..pages {
+                    for j in 0..mapping.pages {
                         let page_vaddr = mapping.virt_addr.offset((j as u64) * PAGE_SIZE_4KB);
                         unmap_page_in_dir(page_dir, page_vaddr);
                     }
@@ -723,7 +922,14 @@ pub fn shm_cleanup_task(task_id: u32) {
             }
         }
 
-        klog_debug!("shm_cleanup_task: destroyed buffer token={}", buffer.token);
+        if let Some(frames) = buffer.retired.take() {
+            frames.free();
+        }
+        klog_debug_mod!(
+            "mm",
+            "shm_cleanup_task: destroyed buffer token={}",
+            buffer.token
+        );
         *buffer = SharedBuffer::empty();
     }
 
@@ -763,7 +969,8 @@ pub fn shm_acquire(token: u32) -> c_int {
     buffer.ref_count = buffer.ref_count.saturating_add(1);
     buffer.released = false;
 
-    klog_debug!(
+    klog_debug_mod!(
+        "mm",
         "shm_acquire: token={} ref_count={}",
         token,
         buffer.ref_count
@@ -795,7 +1002,8 @@ pub fn shm_release(token: u32) -> c_int {
     buffer.ref_count = buffer.ref_count.saturating_sub(1);
     buffer.released = true;
 
-    klog_debug!(
+    klog_debug_mod!(
+        "mm",
         "shm_release: token={} ref_count={} released=true",
         token,
         buffer.ref_count
@@ -920,10 +1128,12 @@ pub fn shm_create_with_format(owner_task: u32, size: u64, format: PixelFormat) -
         surface_height: 0,
         ref_count: 1,
         released: false,
-        format,
+        format: Some(format),
+        retired: None,
     };
 
-    klog_debug!(
+    klog_debug_mod!(
+        "mm",
         "shm_create_with_format: created buffer token={} size={} format={:?} for task={}",
         token,
         aligned_size,
@@ -945,7 +1155,20 @@ pub fn shm_get_format(token: u32) -> u32 {
     let registry = REGISTRY.read();
 
     match registry.find_by_token(token) {
-        Some(slot) => registry.buffers[slot].format as u32,
+        Some(slot) => registry.buffers[slot]
+            .format
+            .unwrap_or(DEFAULT_PIXEL_FORMAT) as u32,
         None => u32::MAX,
     }
 }
+
+/// Get the pixel format a buffer was explicitly created with.
+///
+/// Returns `None` for unknown tokens and for buffers created without a
+/// format, whose pixels are laid out like the display's.
+pub fn shm_get_declared_format(token: u32) -> Option<PixelFormat> {
+    let registry = REGISTRY.read();
+    registry
+        .find_by_token(token)
+        .and_then(|slot| registry.buffers[slot].format)
+}
@@ -11,8 +11,9 @@ use crate::hhdm::PhysAddrHhdm;
 use crate::kernel_heap::{get_heap_stats, kfree, kmalloc, kzalloc};
 use crate::mm_constants::PAGE_SIZE_4KB;
 use crate::page_alloc::{
-    ALLOC_FLAG_ZERO, alloc_page_frame, alloc_page_frames, free_page_frame,
-    get_page_allocator_stats, page_frame_get_ref, page_frame_inc_ref,
+    ALLOC_FLAG_NO_PCP, ALLOC_FLAG_ZERO, ALLOC_FLAG_ZERO_ON_FREE, alloc_page_frame,
+    alloc_page_frames, free_page_frame, get_page_allocator_stats, page_alloc_stats,
+    page_frame_get_ref, page_frame_inc_ref,
 };
 use crate::paging::{
     get_current_page_directory, paging_get_kernel_directory, paging_is_cow,
@@ -266,6 +267,161 @@ pub fn test_page_alloc_fragmentation() -> c_int {
     0
 }
 
+/// Test 9: Scattered alloc/free keeps the free-frame count exact
+pub fn test_page_alloc_stats_scattered_free() -> c_int {
+    const PAGES: usize = 32;
+    let before = page_alloc_stats();
+    if before.total_frames == 0 || before.free_frames < PAGES as u32 * 2 {
+        klog_info!("PAGE_ALLOC_TEST: Not enough free frames for scattered test");
+        return -1;
+    }
+
+    let mut pages = [PhysAddr::NULL; PAGES];
+    for i in 0..PAGES {
+        pages[i] = alloc_page_frame(ALLOC_FLAG_NO_PCP);
+        if pages[i].is_null() {
+            for page in &pages[..i] {
+                free_page_frame(*page);
+            }
+            klog_info!("PAGE_ALLOC_TEST: Failed to allocate page {}", i);
+            return -1;
+        }
+    }
+
+    // Free every other page so nothing can coalesce back
+    for page in pages.iter().step_by(2) {
+        free_page_frame(*page);
+    }
+    let scattered = page_alloc_stats();
+
+    for page in pages.iter().skip(1).step_by(2) {
+        free_page_frame(*page);
+    }
+    let after = page_alloc_stats();
+
+    let expected = before.free_frames - (PAGES as u32 / 2);
+    if scattered.free_frames != expected {
+        klog_info!(
+            "PAGE_ALLOC_TEST: Free count {} after scattered free, expected {}",
+            scattered.free_frames,
+            expected
+        );
+        return -1;
+    }
+    if scattered.largest_free_run > before.largest_free_run {
+        klog_info!("PAGE_ALLOC_TEST: Largest run grew under fragmentation");
+        return -1;
+    }
+    if after.free_frames != before.free_frames {
+        klog_info!(
+            "PAGE_ALLOC_TEST: Free count {} after full release, expected {}",
+            after.free_frames,
+            before.free_frames
+        );
+        return -1;
+    }
+    0
+}
+
+/// Test 9b: Allocating N frames moves the allocated count by exactly N and back
+pub fn test_page_alloc_stats_alloc_free_delta() -> c_int {
+    const PAGES: usize = 16;
+    let before = page_alloc_stats();
+    if before.free_frames < PAGES as u32 {
+        klog_info!("PAGE_ALLOC_TEST: Not enough free frames for delta test");
+        return -1;
+    }
+
+    let mut pages = [PhysAddr::NULL; PAGES];
+    for i in 0..PAGES {
+        pages[i] = alloc_page_frame(ALLOC_FLAG_NO_PCP);
+        if pages[i].is_null() {
+            for page in &pages[..i] {
+                free_page_frame(*page);
+            }
+            klog_info!("PAGE_ALLOC_TEST: Failed to allocate page {}", i);
+            return -1;
+        }
+    }
+    let held = page_alloc_stats();
+
+    for page in &pages {
+        free_page_frame(*page);
+    }
+    let after = page_alloc_stats();
+
+    if held.allocated_frames != before.allocated_frames + PAGES as u32 {
+        klog_info!(
+            "PAGE_ALLOC_TEST: Allocated {} while holding {} pages, expected {}",
+            held.allocated_frames,
+            PAGES,
+            before.allocated_frames + PAGES as u32
+        );
+        return -1;
+    }
+    if after.allocated_frames != before.allocated_frames {
+        klog_info!(
+            "PAGE_ALLOC_TEST: Allocated {} after release, expected {}",
+            after.allocated_frames,
+            before.allocated_frames
+        );
+        return -1;
+    }
+    0
+}
+
+/// Test 10: Consuming the largest block shrinks the largest-run metric
+pub fn test_page_alloc_stats_largest_run() -> c_int {
+    const MAX_BLOCKS: usize = 8;
+    let pristine = page_alloc_stats();
+    if pristine.largest_free_run == 0 || pristine.fragmentation_permille > 1000 {
+        klog_info!("PAGE_ALLOC_TEST: Bogus pristine stats");
+        return -1;
+    }
+
+    let mut blocks = [PhysAddr::NULL; MAX_BLOCKS];
+    let mut taken = 0;
+    let mut shrunk = pristine;
+    while taken < MAX_BLOCKS {
+        blocks[taken] = alloc_page_frames(pristine.largest_free_run, ALLOC_FLAG_NO_PCP);
+        if blocks[taken].is_null() {
+            break;
+        }
+        taken += 1;
+        shrunk = page_alloc_stats();
+        if shrunk.largest_free_run < pristine.largest_free_run {
+            break;
+        }
+    }
+
+    for block in &blocks[..taken] {
+        free_page_frame(*block);
+    }
+    let restored = page_alloc_stats();
+
+    if taken == 0 {
+        klog_info!("PAGE_ALLOC_TEST: Could not allocate the largest free block");
+        return -1;
+    }
+    if shrunk.largest_free_run >= pristine.largest_free_run {
+        klog_info!(
+            "PAGE_ALLOC_TEST: Largest run stayed at {} after taking {} blocks",
+            shrunk.largest_free_run,
+            taken
+        );
+        return -1;
+    }
+    if restored.largest_free_run != pristine.largest_free_run {
+        klog_info!(
+            "PAGE_ALLOC_TEST: Largest run {} not restored to {}",
+            restored.largest_free_run,
+            pristine.largest_free_run
+        );
+        return -1;
+    }
+    0
+}
+
 // ============================================================================
 // KERNEL HEAP TESTS - 10 tests
 // ============================================================================
@@ -384,6 +540,184 @@ pub fn test_heap_alloc_zero() -> c_int {
     0
 }
 
+/// Test 6b: Bump allocator reservations, exact fill, and overflowing sizes
+pub fn test_bump_alloc_bounds() -> c_int {
+    use core::alloc::Layout;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::{bump_reserve, early_heap_bytes_remaining};
+
+    const CAPACITY: usize = 64;
+    let next = AtomicUsize::new(0);
+    let layout = |size: usize, align: usize| Layout::from_size_align(size, align).unwrap();
+
+    if bump_reserve(&next, CAPACITY, layout(10, 1)) != Some(0) {
+        klog_info!("BUMP_TEST: first reservation not at offset 0");
+        return -1;
+    }
+    // Minimum alignment is 8, so the next block starts at 16
+    if bump_reserve(&next, CAPACITY, layout(48, 8)) != Some(16) {
+        klog_info!("BUMP_TEST: aligned reservation misplaced");
+        return -1;
+    }
+    if next.load(Ordering::Relaxed) != CAPACITY {
+        klog_info!("BUMP_TEST: exact fill should leave the cursor at capacity");
+        return -1;
+    }
+    if bump_reserve(&next, CAPACITY, layout(1, 1)).is_some() {
+        klog_info!("BUMP_TEST: full region must refuse further reservations");
+        return -1;
+    }
+
+    // offset + size would wrap to a small value without the checked add
+    let next = AtomicUsize::new(usize::MAX - 15);
+    if bump_reserve(&next, usize::MAX, layout(isize::MAX as usize - 7, 8)).is_some() {
+        klog_info!("BUMP_TEST: overflowing size accepted");
+        return -1;
+    }
+    let next = AtomicUsize::new(usize::MAX - 3);
+    if bump_reserve(&next, usize::MAX, layout(8, 8)).is_some() {
+        klog_info!("BUMP_TEST: overflowing alignment accepted");
+        return -1;
+    }
+    if next.load(Ordering::Relaxed) != usize::MAX - 3 {
+        klog_info!("BUMP_TEST: failed reservation moved the cursor");
+        return -1;
+    }
+
+    if early_heap_bytes_remaining() > 2 * 1024 * 1024 {
+        klog_info!("BUMP_TEST: remaining bytes exceed heap size");
+        return -1;
+    }
+    0
+}
+
+#[repr(C, align(16))]
+struct HeapArena([u8; 1024]);
+
+/// Test 6c: Early heap reuses freed blocks instead of growing the cursor
+pub fn test_early_heap_reuses_freed() -> c_int {
+    use core::alloc::Layout;
+
+    use crate::early_heap::FreeListHeap;
+
+    let mut arena = HeapArena([0; 1024]);
+    let mut heap = FreeListHeap::empty();
+    unsafe { heap.init(arena.0.as_mut_ptr(), arena.0.len()) };
+    let layout = Layout::from_size_align(40, 8).unwrap();
+
+    // Keep a block below so freeing `first` does not just rewind the cursor
+    let first = heap.alloc(layout);
+    let keep = heap.alloc(layout);
+    if first.is_null() || keep.is_null() {
+        klog_info!("EARLY_HEAP_TEST: initial allocations failed");
+        return -1;
+    }
+    let high_water = heap.bump_offset();
+    for _ in 0..100 {
+        unsafe { heap.dealloc(first, layout) };
+        let again = heap.alloc(layout);
+        if again != first {
+            klog_info!("EARLY_HEAP_TEST: freed block not reused");
+            return -1;
+        }
+    }
+    if heap.bump_offset() != high_water {
+        klog_info!("EARLY_HEAP_TEST: alloc/free cycles grew the cursor");
+        return -1;
+    }
+
+    // Freeing the topmost block hands it back to the bump region
+    unsafe { heap.dealloc(keep, layout) };
+    unsafe { heap.dealloc(first, layout) };
+    if heap.bump_offset() != 0 || heap.free_blocks() != 0 {
+        klog_info!("EARLY_HEAP_TEST: empty heap did not rewind to offset 0");
+        return -1;
+    }
+
+    let mut blocks = [core::ptr::null_mut(); 32];
+    for slot in blocks.iter_mut() {
+        *slot = heap.alloc(Layout::from_size_align(32, 16).unwrap());
+    }
+    if blocks.iter().any(|p| p.is_null()) {
+        klog_info!("EARLY_HEAP_TEST: arena should fit 32 blocks of 32 bytes");
+        return -1;
+    }
+    if !heap.alloc(layout).is_null() {
+        klog_info!("EARLY_HEAP_TEST: full arena accepted another allocation");
+        return -1;
+    }
+    0
+}
+
+/// Test 6d: Early heap coalesces neighbours and honours alignment
+pub fn test_early_heap_coalesce_and_align() -> c_int {
+    use core::alloc::Layout;
+
+    use crate::early_heap::{FreeListHeap, HEAP_BLOCK_ALIGN};
+
+    let mut arena = HeapArena([0; 1024]);
+    let mut heap = FreeListHeap::empty();
+    unsafe { heap.init(arena.0.as_mut_ptr(), arena.0.len()) };
+    let small = Layout::from_size_align(64, 8).unwrap();
+
+    let mut blocks = [core::ptr::null_mut(); 5];
+    for slot in blocks.iter_mut() {
+        *slot = heap.alloc(small);
+    }
+    if blocks.iter().any(|p| p.is_null()) {
+        klog_info!("EARLY_HEAP_TEST: setup allocations failed");
+        return -1;
+    }
+
+    // Free out of order; 0..=3 end up as a single block, 4 pins the cursor
+    for i in [2, 0, 3, 1] {
+        unsafe { heap.dealloc(blocks[i], small) };
+    }
+    if heap.free_blocks() != 1 || heap.free_bytes() != 4 * 64 {
+        klog_info!(
+            "EARLY_HEAP_TEST: expected one 256-byte block, got {} blocks / {} bytes",
+            heap.free_blocks(),
+            heap.free_bytes()
+        );
+        return -1;
+    }
+
+    // The coalesced block satisfies a request none of the pieces could
+    let high_water = heap.bump_offset();
+    let big = Layout::from_size_align(200, 8).unwrap();
+    let merged = heap.alloc(big);
+    if merged != blocks[0] || heap.bump_offset() != high_water {
+        klog_info!("EARLY_HEAP_TEST: large request did not reuse coalesced block");
+        return -1;
+    }
+
+    // Over-aligned requests land on their alignment, and the padding they
+    // skip stays usable
+    let aligned = Layout::from_size_align(16, 128).unwrap();
+    let p = heap.alloc(aligned);
+    if p.is_null() || !(p as usize).is_multiple_of(128) {
+        klog_info!("EARLY_HEAP_TEST: over-aligned allocation misaligned");
+        return -1;
+    }
+    for &q in &[merged, blocks[4]] {
+        if !(q as usize).is_multiple_of(HEAP_BLOCK_ALIGN) {
+            klog_info!("EARLY_HEAP_TEST: block not aligned to HEAP_BLOCK_ALIGN");
+            return -1;
+        }
+    }
+    unsafe {
+        heap.dealloc(p, aligned);
+        heap.dealloc(blocks[4], small);
+        heap.dealloc(merged, big);
+    }
+    if heap.bump_offset() != 0 || heap.free_blocks() != 0 {
+        klog_info!("EARLY_HEAP_TEST: freeing everything did not rewind the heap");
+        return -1;
+    }
+    0
+}
+
 /// Test 7: Stats tracking accuracy
 pub fn test_heap_stats() -> c_int {
     let mut stats_before = MaybeUninit::uninit();
@@ -600,6 +934,77 @@ pub fn test_process_vm_counter_reset() -> i32 {
     0
 }
 
+/// Freed ids are recycled lowest-first, but with a new generation
+pub fn test_id_alloc_recycles_lowest() -> c_int {
+    use slopos_lib::id_alloc::IdAllocator;
+
+    let mut ids: IdAllocator<8> = IdAllocator::new();
+    let mut first = [0u32; 4];
+    for slot in first.iter_mut() {
+        *slot = match ids.alloc() {
+            Some(id) => id,
+            None => {
+                klog_info!("ID_ALLOC_TEST: alloc failed with free slots");
+                return -1;
+            }
+        };
+    }
+    if first.contains(&0) || ids.in_use() != 4 {
+        klog_info!("ID_ALLOC_TEST: bad initial ids {:?}", first);
+        return -1;
+    }
+
+    if !ids.free(first[1]) || ids.free(first[1]) {
+        klog_info!("ID_ALLOC_TEST: free/double free result wrong");
+        return -1;
+    }
+    let reused = ids.alloc().unwrap_or(0);
+    if ids.index_of(reused) != Some(1) {
+        klog_info!("ID_ALLOC_TEST: middle slot not reused first");
+        return -1;
+    }
+    if reused == first[1] || ids.is_live(first[1]) || ids.free(first[1]) {
+        klog_info!("ID_ALLOC_TEST: stale id still matches recycled slot");
+        return -1;
+    }
+    if !ids.is_live(first[0]) || !ids.is_live(first[3]) {
+        klog_info!("ID_ALLOC_TEST: unrelated ids disturbed");
+        return -1;
+    }
+    0
+}
+
+/// Exhaustion returns None until an id is freed
+pub fn test_id_alloc_exhaustion() -> c_int {
+    use slopos_lib::id_alloc::IdAllocator;
+
+    let mut ids: IdAllocator<4> = IdAllocator::new();
+    let mut held = [0u32; 4];
+    for slot in held.iter_mut() {
+        *slot = ids.alloc().unwrap_or(0);
+    }
+    if held.contains(&0) || ids.alloc().is_some() {
+        klog_info!("ID_ALLOC_TEST: allocator handed out more than capacity");
+        return -1;
+    }
+    if ids.free(0) || ids.free(u32::MAX) {
+        klog_info!("ID_ALLOC_TEST: freed an id that was never issued");
+        return -1;
+    }
+    ids.free(held[2]);
+    if ids.alloc().and_then(|id| ids.index_of(id)) != Some(2) || ids.alloc().is_some() {
+        klog_info!("ID_ALLOC_TEST: freed slot not reissued after exhaustion");
+        return -1;
+    }
+
+    ids.reset();
+    if ids.in_use() != 0 || ids.is_live(held[0]) || ids.alloc().is_none() {
+        klog_info!("ID_ALLOC_TEST: reset did not release ids");
+        return -1;
+    }
+    0
+}
+
 // ============================================================================
 // PAGING TESTS - 10 tests
 // ============================================================================
@@ -673,7 +1078,7 @@ pub fn test_paging_cow_kernel() -> c_int {
 }
 
 // ============================================================================
-// RING BUFFER TESTS - 8 tests (in lib crate, tested via mm)
+// RING BUFFER TESTS - 14 tests (in lib crate, tested via mm)
 // ============================================================================
 
 /// Test ring buffer basic push/pop
@@ -832,46 +1237,259 @@ pub fn test_ring_buffer_wrap() -> c_int {
     if rb.try_pop() != Some(5) {
         return -1;
     }
-    if rb.try_pop() != Some(6) {
+    if rb.try_pop() != Some(6) {
+        return -1;
+    }
+
+    0
+}
+
+/// Test ring buffer reset
+pub fn test_ring_buffer_reset() -> c_int {
+    use slopos_lib::ring_buffer::RingBuffer;
+
+    let mut rb: RingBuffer<u32, 8> = RingBuffer::new();
+
+    rb.try_push(1);
+    rb.try_push(2);
+    rb.try_push(3);
+
+    rb.reset();
+
+    if !rb.is_empty() {
+        klog_info!("RING_TEST: Buffer should be empty after reset");
+        return -1;
+    }
+
+    if rb.len() != 0 {
+        klog_info!("RING_TEST: Length should be 0 after reset");
+        return -1;
+    }
+
+    0
+}
+
+/// Test ring buffer capacity
+pub fn test_ring_buffer_capacity() -> c_int {
+    use slopos_lib::ring_buffer::RingBuffer;
+
+    let rb: RingBuffer<u32, 16> = RingBuffer::new();
+
+    if rb.capacity() != 16 {
+        klog_info!("RING_TEST: Capacity should be 16");
+        return -1;
+    }
+
+    0
+}
+
+/// Test ring buffer iteration over a partially-filled buffer
+pub fn test_ring_buffer_iter_partial() -> c_int {
+    use slopos_lib::ring_buffer::RingBuffer;
+
+    let mut rb: RingBuffer<u32, 8> = RingBuffer::new();
+
+    if rb.iter().next().is_some() {
+        klog_info!("RING_TEST: Iterator over empty buffer should yield nothing");
+        return -1;
+    }
+
+    rb.try_push(10);
+    rb.try_push(20);
+    rb.try_push(30);
+
+    if rb.iter().len() != 3 {
+        klog_info!("RING_TEST: Iterator length should match buffer length");
+        return -1;
+    }
+
+    let expected = [10u32, 20, 30];
+    let mut seen = 0usize;
+    for (i, &val) in rb.iter().enumerate() {
+        if val != expected[i] {
+            klog_info!(
+                "RING_TEST: Iterator yielded {} at {}, expected {}",
+                val,
+                i,
+                expected[i]
+            );
+            return -1;
+        }
+        seen += 1;
+    }
+
+    if seen != expected.len() {
+        klog_info!("RING_TEST: Iterator yielded {} items, expected 3", seen);
+        return -1;
+    }
+
+    0
+}
+
+/// Test ring buffer iteration across the wrap-around point
+pub fn test_ring_buffer_iter_wrapped() -> c_int {
+    use slopos_lib::ring_buffer::RingBuffer;
+
+    let mut rb: RingBuffer<u32, 4> = RingBuffer::new();
+
+    // Advance tail past the start, then push enough to wrap head
+    rb.try_push(1);
+    rb.try_push(2);
+    rb.try_push(3);
+    rb.try_pop();
+    rb.try_pop();
+    rb.try_push(4);
+    rb.try_push(5);
+    rb.try_push(6);
+
+    let expected = [3u32, 4, 5, 6];
+    let mut seen = 0usize;
+    for (i, &val) in rb.iter().enumerate() {
+        if i >= expected.len() || val != expected[i] {
+            klog_info!("RING_TEST: Wrapped iterator yielded {} at {}", val, i);
+            return -1;
+        }
+        seen += 1;
+    }
+
+    if seen != expected.len() {
+        klog_info!(
+            "RING_TEST: Wrapped iterator yielded {} items, expected 4",
+            seen
+        );
+        return -1;
+    }
+
+    0
+}
+
+/// Test ring buffer iteration leaves contents and push order intact
+pub fn test_ring_buffer_iter_non_destructive() -> c_int {
+    use slopos_lib::ring_buffer::RingBuffer;
+
+    let mut rb: RingBuffer<u32, 4> = RingBuffer::new();
+
+    for i in 0..6u32 {
+        rb.push_overwrite(i);
+    }
+
+    let sum: u32 = rb.iter().sum();
+    if sum != 2 + 3 + 4 + 5 {
+        klog_info!("RING_TEST: Iterator sum mismatch ({})", sum);
+        return -1;
+    }
+
+    if rb.len() != 4 {
+        klog_info!("RING_TEST: Iteration should not change length");
+        return -1;
+    }
+
+    // Popping afterwards must still follow push order
+    for expected in 2..6u32 {
+        if rb.try_pop() != Some(expected) {
+            klog_info!("RING_TEST: Pop after iteration expected {}", expected);
+            return -1;
+        }
+    }
+
+    if !rb.is_empty() {
+        klog_info!("RING_TEST: Buffer should be empty after draining");
+        return -1;
+    }
+
+    0
+}
+
+/// Test bulk write stops at capacity when nearly full
+pub fn test_ring_buffer_write_slice_partial() -> c_int {
+    use slopos_lib::ring_buffer::RingBuffer;
+
+    let mut rb: RingBuffer<u32, 4> = RingBuffer::new();
+    rb.try_push(1);
+    rb.try_push(2);
+    rb.try_push(3);
+
+    let written = rb.write_slice(&[4, 5, 6]);
+    if written != 1 || !rb.is_full() {
+        klog_info!("RING_TEST: Partial write stored {}, expected 1", written);
+        return -1;
+    }
+    if rb.write_slice(&[7]) != 0 {
+        klog_info!("RING_TEST: Write to full reject buffer should store nothing");
+        return -1;
+    }
+
+    let mut out = [0u32; 8];
+    let read = rb.read_slice(&mut out);
+    if read != 4 || out[..4] != [1, 2, 3, 4] || !rb.is_empty() {
+        klog_info!(
+            "RING_TEST: Bulk read returned {} items {:?}",
+            read,
+            &out[..read]
+        );
+        return -1;
+    }
+    if rb.read_slice(&mut out) != 0 {
+        klog_info!("RING_TEST: Bulk read from empty should return 0");
         return -1;
     }
 
     0
 }
 
-/// Test ring buffer reset
-pub fn test_ring_buffer_reset() -> c_int {
+/// Test bulk read/write across the end of the backing array
+pub fn test_ring_buffer_slice_wraparound() -> c_int {
     use slopos_lib::ring_buffer::RingBuffer;
 
-    let mut rb: RingBuffer<u32, 8> = RingBuffer::new();
-
-    rb.try_push(1);
-    rb.try_push(2);
-    rb.try_push(3);
+    let mut rb: RingBuffer<u32, 4> = RingBuffer::new();
 
-    rb.reset();
+    // Move head and tail to index 3 so the next write wraps
+    rb.write_slice(&[0, 0, 0]);
+    let mut drain = [0u32; 3];
+    rb.read_slice(&mut drain);
 
-    if !rb.is_empty() {
-        klog_info!("RING_TEST: Buffer should be empty after reset");
+    if rb.write_slice(&[10, 11, 12]) != 3 {
+        klog_info!("RING_TEST: Wrapping write came up short");
         return -1;
     }
 
-    if rb.len() != 0 {
-        klog_info!("RING_TEST: Length should be 0 after reset");
+    let mut first = [0u32; 2];
+    if rb.read_slice(&mut first) != 2 || first != [10, 11] {
+        klog_info!("RING_TEST: Wrapped read returned {:?}", first);
+        return -1;
+    }
+    if rb.try_pop() != Some(12) || !rb.is_empty() {
+        klog_info!("RING_TEST: Single pop after bulk read out of order");
         return -1;
     }
 
     0
 }
 
-/// Test ring buffer capacity
-pub fn test_ring_buffer_capacity() -> c_int {
-    use slopos_lib::ring_buffer::RingBuffer;
+/// Test overwrite-mode buffer discards the oldest items when full
+pub fn test_ring_buffer_overwrite_mode() -> c_int {
+    use slopos_lib::ring_buffer::{OverflowMode, RingBuffer};
 
-    let rb: RingBuffer<u32, 16> = RingBuffer::new();
+    let mut rb: RingBuffer<u32, 4> = RingBuffer::new_overwrite();
+    if rb.mode() != OverflowMode::Overwrite {
+        return -1;
+    }
 
-    if rb.capacity() != 16 {
-        klog_info!("RING_TEST: Capacity should be 16");
+    for i in 1..=4u32 {
+        rb.push(i);
+    }
+    if !rb.push(5) {
+        klog_info!("RING_TEST: Overwrite push to full buffer should succeed");
+        return -1;
+    }
+    if rb.write_slice(&[6, 7, 8, 9, 10, 11]) != 6 {
+        klog_info!("RING_TEST: Overwrite write should accept every value");
+        return -1;
+    }
+
+    let mut out = [0u32; 4];
+    if rb.read_slice(&mut out) != 4 || out != [8, 9, 10, 11] {
+        klog_info!("RING_TEST: Overwrite kept {:?}, expected newest four", out);
         return -1;
     }
 
@@ -879,7 +1497,7 @@ pub fn test_ring_buffer_capacity() -> c_int {
 }
 
 // ============================================================================
-// IRQMUTEX TESTS - 3 tests
+// IRQMUTEX TESTS - 4 tests
 // ============================================================================
 
 /// Test 1: IrqMutex basic lock/unlock with guard
@@ -954,6 +1572,39 @@ pub fn test_irqmutex_try_lock() -> c_int {
     0
 }
 
+/// Test 4: the guard restores the interrupt flag it found on entry
+pub fn test_irqmutex_restores_interrupt_flag() -> c_int {
+    use slopos_lib::IrqMutex;
+    use slopos_lib::cpu;
+
+    let mutex: IrqMutex<u32> = IrqMutex::new(0);
+    let entry_flags = cpu::save_flags_cli();
+    let mut result = 0;
+
+    cpu::enable_interrupts();
+    {
+        let _guard = mutex.lock();
+        if cpu::are_interrupts_enabled() {
+            klog_info!("IRQMUTEX_TEST: interrupts enabled while lock held");
+            result = -1;
+        }
+    }
+    if !cpu::are_interrupts_enabled() {
+        klog_info!("IRQMUTEX_TEST: interrupts not re-enabled after guard drop");
+        result = -1;
+    }
+
+    cpu::disable_interrupts();
+    drop(mutex.lock());
+    if cpu::are_interrupts_enabled() {
+        klog_info!("IRQMUTEX_TEST: guard enabled interrupts that were off on entry");
+        result = -1;
+    }
+
+    cpu::restore_flags(entry_flags);
+    result
+}
+
 // ============================================================================
 // SHARED MEMORY TESTS - 8 tests
 // ============================================================================
@@ -1094,7 +1745,7 @@ pub fn test_shm_surface_attach() -> c_int {
     }
 
     // Attach surface
-    if surface_attach(owner, token, width, height) != 0 {
+    if surface_attach(owner, token, width, height).is_err() {
         klog_info!("SHM_TEST: surface_attach failed");
         shm_destroy(owner, token);
         return -1;
@@ -1114,7 +1765,7 @@ pub fn test_shm_surface_attach_too_small() -> c_int {
     }
 
     // 1920x1080x4 = 8,294,400 bytes - should fail
-    if surface_attach(owner, token, 1920, 1080) == 0 {
+    if surface_attach(owner, token, 1920, 1080).is_ok() {
         klog_info!("SHM_TEST: surface_attach with too small buffer should fail");
         shm_destroy(owner, token);
         return -1;
@@ -1124,6 +1775,52 @@ pub fn test_shm_surface_attach_too_small() -> c_int {
     0
 }
 
+/// Test 8b: Each surface_attach failure reports its own error
+pub fn test_shm_surface_attach_error_kinds() -> c_int {
+    use slopos_abi::ShmError;
+
+    let owner = 1u32;
+    let token = shm_create(owner, 64 * 64 * 4, 0);
+    if token == 0 {
+        return -1;
+    }
+
+    let checks = [
+        (
+            surface_attach(owner, 0xDEAD_BEEF, 64, 64),
+            ShmError::InvalidToken,
+        ),
+        (
+            surface_attach(owner + 1, token, 64, 64),
+            ShmError::PermissionDenied,
+        ),
+        (
+            surface_attach(owner, token, 128, 128),
+            ShmError::InvalidSize,
+        ),
+    ];
+    let mut result = 0;
+    for (i, (got, expected)) in checks.iter().enumerate() {
+        if *got != Err(*expected) {
+            klog_info!(
+                "SHM_TEST: attach check {} expected {:?}, got {:?}",
+                i,
+                expected,
+                got
+            );
+            result = -1;
+        }
+    }
+
+    if surface_attach(owner, token, 64, 64).is_err() {
+        klog_info!("SHM_TEST: exact-fit attach should succeed");
+        result = -1;
+    }
+
+    shm_destroy(owner, token);
+    result
+}
+
 /// Test 9: Map shared buffer more than MAX_MAPPINGS_PER_BUFFER times
 /// BUG FINDER: shm_map uses unwrap() on mapping slot search - will panic!
 pub fn test_shm_mapping_overflow() -> c_int {
@@ -1160,6 +1857,65 @@ pub fn test_shm_mapping_overflow() -> c_int {
     0
 }
 
+/// Reallocating a mapped buffer must leave the old mapping in place until
+/// its user unmaps it; only then may the old frames be freed.
+pub fn test_shm_reallocate_keeps_old_mapping() -> c_int {
+    use crate::paging::virt_to_phys_in_dir;
+    use crate::shared_memory::{ShmAccess, shm_map, shm_reallocate, shm_retire_frames, shm_unmap};
+
+    let pid = create_process_vm();
+    if pid == crate::mm_constants::INVALID_PROCESS_ID {
+        return -1;
+    }
+    let page_dir = process_vm_get_page_dir(pid);
+    let token = shm_create(pid, 2 * PAGE_SIZE_4KB, 0);
+    if token == 0 || page_dir.is_null() {
+        destroy_process_vm(pid);
+        return -1;
+    }
+
+    let (old_phys, _, _) = shm_get_buffer_info(token);
+    let old_vaddr = shm_map(pid, token, ShmAccess::ReadWrite);
+    let Ok(frames) = shm_reallocate(pid, token, 3 * PAGE_SIZE_4KB) else {
+        klog_info!("SHM_TEST: reallocate failed");
+        shm_destroy(pid, token);
+        destroy_process_vm(pid);
+        return -1;
+    };
+    shm_retire_frames(token, frames);
+
+    let (new_phys, _, _) = shm_get_buffer_info(token);
+    let last_old_page = VirtAddr::new(old_vaddr + PAGE_SIZE_4KB);
+    let mut result = 0;
+    if old_vaddr == 0
+        || virt_to_phys_in_dir(page_dir, last_old_page) != old_phys.offset(PAGE_SIZE_4KB)
+    {
+        klog_info!("SHM_TEST: old mapping dropped by reallocate");
+        result = -1;
+    }
+
+    let new_vaddr = shm_map(pid, token, ShmAccess::ReadWrite);
+    if new_vaddr == old_vaddr || virt_to_phys_in_dir(page_dir, VirtAddr::new(new_vaddr)) != new_phys
+    {
+        klog_info!("SHM_TEST: remap returned the stale mapping");
+        result = -1;
+    }
+
+    let before = page_alloc_stats().free_frames;
+    if shm_unmap(pid, old_vaddr) != 0 || !virt_to_phys_in_dir(page_dir, last_old_page).is_null() {
+        klog_info!("SHM_TEST: stale mapping not unmapped");
+        result = -1;
+    }
+    if page_alloc_stats().free_frames < before + 2 {
+        klog_info!("SHM_TEST: old frames not freed with the stale mapping");
+        result = -1;
+    }
+
+    shm_destroy(pid, token);
+    destroy_process_vm(pid);
+    result
+}
+
 pub fn test_shm_surface_attach_overflow() -> c_int {
     let owner = 1u32;
     let token = shm_create(owner, 64 * 1024 * 1024, 0);
@@ -1170,7 +1926,7 @@ pub fn test_shm_surface_attach_overflow() -> c_int {
 
     let result = surface_attach(owner, token, 0xFFFF, 0xFFFF);
 
-    if result == 0 {
+    if result.is_ok() {
         klog_info!("SHM_TEST: BUG - surface_attach accepted 0xFFFF x 0xFFFF (potential overflow)");
         shm_destroy(owner, token);
         return -1;
@@ -1178,7 +1934,7 @@ pub fn test_shm_surface_attach_overflow() -> c_int {
 
     let result2 = surface_attach(owner, token, 0x8000_0000, 2);
 
-    if result2 == 0 {
+    if result2.is_ok() {
         klog_info!("SHM_TEST: BUG - surface_attach accepted 0x80000000 x 2 (32-bit overflow)");
         shm_destroy(owner, token);
         return -1;
@@ -1312,6 +2068,101 @@ pub fn test_page_alloc_no_stale_data() -> c_int {
     0
 }
 
+/// Fill a page, free it, and report whether the pattern survived the free.
+fn page_pattern_survives_free(flags: u32) -> Option<bool> {
+    let phys = alloc_page_frame(flags);
+    let virt = phys.to_virt_checked()?;
+    let ptr = virt.as_mut_ptr::<u8>();
+    for i in 0..PAGE_SIZE_4KB as usize {
+        unsafe { ptr.add(i).write_volatile(0xA5) };
+    }
+    free_page_frame(phys);
+    // The HHDM mapping stays valid after the frame is returned
+    let intact = (0..PAGE_SIZE_4KB as usize).all(|i| unsafe { ptr.add(i).read_volatile() } == 0xA5);
+    let zeroed = (0..PAGE_SIZE_4KB as usize).all(|i| unsafe { ptr.add(i).read_volatile() } == 0);
+    if intact == zeroed {
+        // Partially overwritten: someone else reused the frame meanwhile
+        return None;
+    }
+    Some(intact)
+}
+
+/// Frames allocated with ALLOC_FLAG_ZERO_ON_FREE are scrubbed on free
+pub fn test_page_alloc_zero_on_free() -> c_int {
+    for flags in [
+        ALLOC_FLAG_ZERO_ON_FREE | ALLOC_FLAG_NO_PCP,
+        ALLOC_FLAG_ZERO_ON_FREE,
+    ] {
+        if page_pattern_survives_free(flags) != Some(false) {
+            klog_info!(
+                "RIGOROUS_TEST: Page not scrubbed on free (flags {:#x})",
+                flags
+            );
+            return -1;
+        }
+    }
+    0
+}
+
+/// A dirtied ZERO_ON_FREE frame comes back clean from an allocation that
+/// does not ask for zeroing, whether it was cached per-CPU or not
+pub fn test_page_alloc_zero_on_free_realloc() -> c_int {
+    for flags in [
+        ALLOC_FLAG_ZERO_ON_FREE | ALLOC_FLAG_NO_PCP,
+        ALLOC_FLAG_ZERO_ON_FREE,
+    ] {
+        let phys = alloc_page_frame(flags);
+        let Some(virt) = phys.to_virt_checked() else {
+            klog_info!(
+                "RIGOROUS_TEST: Failed to allocate frame (flags {:#x})",
+                flags
+            );
+            return -1;
+        };
+        let ptr = virt.as_mut_ptr::<u8>();
+        for i in 0..PAGE_SIZE_4KB as usize {
+            unsafe { ptr.add(i).write_volatile(0x5A) };
+        }
+        free_page_frame(phys);
+
+        // No ALLOC_FLAG_ZERO: any zeroes come from the scrub at free time
+        let again = alloc_page_frame(flags & ALLOC_FLAG_NO_PCP);
+        if again.is_null() {
+            klog_info!("RIGOROUS_TEST: Reallocation failed (flags {:#x})", flags);
+            return -1;
+        }
+        if again != phys {
+            klog_info!("RIGOROUS_TEST: Got a different frame back, checking the freed one");
+        }
+        let dirty =
+            (0..PAGE_SIZE_4KB as usize).find(|&i| unsafe { ptr.add(i).read_volatile() } != 0);
+        free_page_frame(again);
+        if let Some(offset) = dirty {
+            klog_info!(
+                "RIGOROUS_TEST: Freed frame dirty at offset {} (flags {:#x})",
+                offset,
+                flags
+            );
+            return -1;
+        }
+    }
+    0
+}
+
+/// Without the flag, freeing leaves contents alone (the flag is the trigger)
+pub fn test_page_alloc_free_keeps_contents() -> c_int {
+    for flags in [ALLOC_FLAG_NO_PCP, 0] {
+        if page_pattern_survives_free(flags) != Some(true) {
+            klog_info!(
+                "RIGOROUS_TEST: Page scrubbed without flag (flags {:#x})",
+                flags
+            );
+            return -1;
+        }
+    }
+    0
+}
+
 /// Test: Heap allocation boundary - verify we can use full allocated size
 pub fn test_heap_boundary_write() -> c_int {
     let sizes = [16usize, 32, 64, 128, 256, 512, 1024];
@@ -1573,6 +2424,10 @@ pub use crate::tests_cow_edge::{
     test_cow_read_not_cow_fault, test_cow_single_ref_upgrade,
 };
 
+pub use crate::tests_user_copy::{
+    test_user_copy_roundtrip, test_user_copy_spanning_page_boundary, test_user_copy_unmapped_faults,
+};
+
 pub fn test_process_vm_create_destroy_memory() -> c_int {
     init_process_vm();
 
@@ -1694,6 +2549,265 @@ pub fn test_process_vm_brk_expansion() -> c_int {
     0
 }
 
+/// Open a fresh process and return (pid, page_dir, initial break).
+fn brk_test_process() -> Option<(u32, *mut crate::paging::ProcessPageDir, u64)> {
+    init_process_vm();
+    let pid = create_process_vm();
+    if pid == crate::mm_constants::INVALID_PROCESS_ID {
+        return None;
+    }
+    let page_dir = process_vm_get_page_dir(pid);
+    let brk = crate::process_vm::process_vm_brk(pid, 0);
+    if page_dir.is_null() || brk == 0 {
+        destroy_process_vm(pid);
+        return None;
+    }
+    Some((pid, page_dir, brk))
+}
+
+pub fn test_process_vm_set_brk_grow_lazy() -> c_int {
+    use crate::process_vm::{process_vm_get_vma_flags, process_vm_set_brk};
+
+    let Some((pid, page_dir, initial_brk)) = brk_test_process() else {
+        return -1;
+    };
+
+    let target = initial_brk + 3 * PAGE_SIZE_4KB;
+    let new_brk = process_vm_set_brk(pid, target);
+    let lazy = process_vm_get_vma_flags(pid, initial_brk).is_some_and(|f| f.is_demand_paged());
+    let mapped = !virt_to_phys_in_dir(page_dir, VirtAddr::new(initial_brk)).is_null();
+    destroy_process_vm(pid);
+
+    if new_brk != target {
+        klog_info!(
+            "PROCESS_TEST: set_brk returned {:#x}, expected {:#x}",
+            new_brk,
+            target
+        );
+        return -1;
+    }
+    if !lazy || mapped {
+        klog_info!(
+            "PROCESS_TEST: grown heap not lazy (lazy={}, mapped={})",
+            lazy,
+            mapped
+        );
+        return -1;
+    }
+    0
+}
+
+pub fn test_process_vm_brk_touch_maps_only_touched() -> c_int {
+    use crate::demand::handle_demand_fault;
+    use crate::process_vm::process_vm_set_brk;
+
+    const HEAP_PAGES: u64 = 256;
+    let Some((pid, page_dir, initial_brk)) = brk_test_process() else {
+        return -1;
+    };
+
+    let end = initial_brk + HEAP_PAGES * PAGE_SIZE_4KB;
+    if process_vm_set_brk(pid, end) != end {
+        destroy_process_vm(pid);
+        return -1;
+    }
+
+    // User writes to the first and last heap page
+    let last_page = end - PAGE_SIZE_4KB;
+    if handle_demand_fault(page_dir, pid, initial_brk + 8, 0x06).is_err()
+        || handle_demand_fault(page_dir, pid, last_page + 8, 0x06).is_err()
+    {
+        klog_info!("PROCESS_TEST: could not fault in distant heap pages");
+        destroy_process_vm(pid);
+        return -1;
+    }
+
+    let mapped = (0..HEAP_PAGES)
+        .filter(|i| {
+            let va = VirtAddr::new(initial_brk + i * PAGE_SIZE_4KB);
+            !virt_to_phys_in_dir(page_dir, va).is_null()
+        })
+        .count();
+    destroy_process_vm(pid);
+
+    if mapped != 2 {
+        klog_info!(
+            "PROCESS_TEST: {} of {} heap pages backed after two touches",
+            mapped,
+            HEAP_PAGES
+        );
+        return -1;
+    }
+    0
+}
+
+pub fn test_process_vm_set_brk_shrink_unmaps() -> c_int {
+    use crate::demand::handle_demand_fault;
+    use crate::process_vm::{process_vm_get_vma_flags, process_vm_set_brk};
+
+    let Some((pid, page_dir, initial_brk)) = brk_test_process() else {
+        return -1;
+    };
+
+    let second_page = initial_brk + PAGE_SIZE_4KB;
+    if process_vm_set_brk(pid, initial_brk + 2 * PAGE_SIZE_4KB) != initial_brk + 2 * PAGE_SIZE_4KB {
+        destroy_process_vm(pid);
+        return -1;
+    }
+    // User write to a not-present page
+    if handle_demand_fault(page_dir, pid, second_page, 0x06).is_err()
+        || virt_to_phys_in_dir(page_dir, VirtAddr::new(second_page)).is_null()
+    {
+        klog_info!("PROCESS_TEST: could not fault in heap page");
+        destroy_process_vm(pid);
+        return -1;
+    }
+
+    let shrunk = process_vm_set_brk(pid, second_page);
+    let still_mapped = !virt_to_phys_in_dir(page_dir, VirtAddr::new(second_page)).is_null();
+    let still_vma = process_vm_get_vma_flags(pid, second_page).is_some();
+    destroy_process_vm(pid);
+
+    if shrunk != second_page || still_mapped || still_vma {
+        klog_info!(
+            "PROCESS_TEST: shrink left page behind (brk={:#x}, mapped={}, vma={})",
+            shrunk,
+            still_mapped,
+            still_vma
+        );
+        return -1;
+    }
+    0
+}
+
+pub fn test_process_vm_set_brk_rejects_stack() -> c_int {
+    use crate::memory_layout::mm_get_process_layout;
+    use crate::process_vm::{process_vm_get_stack_top, process_vm_sbrk, process_vm_set_brk};
+
+    let Some((pid, _, initial_brk)) = brk_test_process() else {
+        return -1;
+    };
+
+    let stack_size = unsafe { (*mm_get_process_layout()).stack_size };
+    let stack_start = process_vm_get_stack_top(pid) - stack_size;
+
+    let into_stack = process_vm_set_brk(pid, stack_start);
+    let past_stack = process_vm_set_brk(pid, stack_start + PAGE_SIZE_4KB);
+    let sbrk = process_vm_sbrk(pid, (stack_start - initial_brk) as i64);
+    let after = process_vm_set_brk(pid, initial_brk);
+    destroy_process_vm(pid);
+
+    if into_stack != initial_brk || past_stack != initial_brk || sbrk.is_some() {
+        klog_info!("PROCESS_TEST: BUG - heap break allowed into the stack region");
+        return -1;
+    }
+    if after != initial_brk {
+        return -1;
+    }
+    0
+}
+
+pub fn test_process_vm_mmap_munmap() -> c_int {
+    use crate::demand::handle_demand_fault;
+    use crate::memory_layout::mm_get_process_layout;
+    use crate::process_vm::{process_vm_get_vma_flags, process_vm_mmap, process_vm_munmap};
+    use crate::vma_flags::VmaFlags;
+
+    let Some((pid, page_dir, initial_brk)) = brk_test_process() else {
+        return -1;
+    };
+    let layout = unsafe { &*mm_get_process_layout() };
+    let mapped = |addr: u64| !virt_to_phys_in_dir(page_dir, VirtAddr::new(addr)).is_null();
+
+    let bad_len = process_vm_mmap(pid, 0).is_some() || process_vm_mmap(pid, 100).is_some();
+    let Some(base) = process_vm_mmap(pid, 3 * PAGE_SIZE_4KB) else {
+        klog_info!("PROCESS_TEST: mmap of three pages failed");
+        destroy_process_vm(pid);
+        return -1;
+    };
+    let in_window = base >= layout.mmap_start && base + 3 * PAGE_SIZE_4KB <= layout.mmap_end;
+    let writable = process_vm_get_vma_flags(pid, base).is_some_and(|f| f.contains(VmaFlags::WRITE));
+    let lazy = (0..3).all(|i| !mapped(base + i * PAGE_SIZE_4KB));
+    let faulted = (0..3)
+        .all(|i| handle_demand_fault(page_dir, pid, base + i * PAGE_SIZE_4KB, 0x06).is_ok())
+        && (0..3).all(|i| mapped(base + i * PAGE_SIZE_4KB));
+    let next = process_vm_mmap(pid, PAGE_SIZE_4KB);
+
+    let middle = base + PAGE_SIZE_4KB;
+    let unmapped_middle = process_vm_munmap(pid, middle, PAGE_SIZE_4KB) == 0;
+    let split_ok = !mapped(middle) && mapped(base) && mapped(base + 2 * PAGE_SIZE_4KB);
+    let rejected = process_vm_munmap(pid, middle, PAGE_SIZE_4KB) != 0
+        && process_vm_munmap(pid, initial_brk, PAGE_SIZE_4KB) != 0
+        && process_vm_munmap(pid, base + 8, PAGE_SIZE_4KB) != 0
+        && process_vm_munmap(pid, base, 0) != 0;
+    let reused = process_vm_mmap(pid, PAGE_SIZE_4KB);
+    destroy_process_vm(pid);
+
+    if bad_len {
+        klog_info!("PROCESS_TEST: mmap accepted a zero or unaligned length");
+        return -1;
+    }
+    if !in_window || !writable || !lazy || !faulted {
+        klog_info!(
+            "PROCESS_TEST: mmap region wrong (in_window={}, writable={}, lazy={}, faulted={})",
+            in_window,
+            writable,
+            lazy,
+            faulted
+        );
+        return -1;
+    }
+    if next != Some(base + 3 * PAGE_SIZE_4KB) {
+        klog_info!("PROCESS_TEST: second mmap not placed at the lowest free address");
+        return -1;
+    }
+    if !unmapped_middle || !split_ok || !rejected {
+        klog_info!(
+            "PROCESS_TEST: munmap misbehaved (unmapped={}, split={}, rejected={})",
+            unmapped_middle,
+            split_ok,
+            rejected
+        );
+        return -1;
+    }
+    if reused != Some(middle) {
+        klog_info!("PROCESS_TEST: mmap did not reuse the unmapped hole");
+        return -1;
+    }
+    0
+}
+
+pub fn test_process_vm_mmap_large_is_lazy() -> c_int {
+    use crate::process_vm::{process_vm_mmap, process_vm_munmap};
+
+    const LEN: u64 = 1 << 30;
+    let Some((pid, _page_dir, _initial_brk)) = brk_test_process() else {
+        return -1;
+    };
+
+    let mut free_before = 0u32;
+    let mut free_after = 0u32;
+    get_page_allocator_stats(ptr::null_mut(), &mut free_before, ptr::null_mut());
+    let base = process_vm_mmap(pid, LEN);
+    get_page_allocator_stats(ptr::null_mut(), &mut free_after, ptr::null_mut());
+    let unmapped = base.is_some_and(|addr| process_vm_munmap(pid, addr, LEN) == 0);
+    destroy_process_vm(pid);
+
+    if base.is_none() || !unmapped {
+        klog_info!("PROCESS_TEST: 1 GiB mmap/munmap failed");
+        return -1;
+    }
+    // Allow a little slack for allocator bookkeeping on other CPUs
+    if free_before.saturating_sub(free_after) > 8 {
+        klog_info!(
+            "PROCESS_TEST: 1 GiB mmap consumed {} frames up front",
+            free_before - free_after
+        );
+        return -1;
+    }
+    0
+}
+
 pub fn test_cow_page_isolation() -> c_int {
     init_process_vm();
 
@@ -0,0 +1,244 @@
+//! user_copy Tests
+//!
+//! Round-trips data through the user copy helpers against a real process
+//! address space: one mapped user page followed by an unmapped one, with
+//! the process's page tables loaded so the user addresses resolve.
+
+use core::ffi::c_int;
+
+use slopos_abi::addr::{PhysAddr, VirtAddr};
+use slopos_lib::{IrqPreemptGuard, klog_info};
+
+use crate::mm_constants::{INVALID_PROCESS_ID, PAGE_SIZE_4KB, PageFlags};
+use crate::page_alloc::{ALLOC_FLAG_ZERO, alloc_page_frame, free_page_frame};
+use crate::paging::{
+    ProcessPageDir, get_current_page_directory, map_page_4kb_in_dir, switch_page_directory,
+};
+use crate::process_vm::{
+    create_process_vm, destroy_process_vm, init_process_vm, process_vm_alloc,
+    process_vm_get_page_dir,
+};
+use crate::user_copy::{
+    UserCopyFault, copy_bytes_from_user, copy_bytes_from_user_partial, copy_bytes_to_user,
+    copy_bytes_to_user_partial, copy_from_user, copy_to_user, restore_task_provider,
+    set_syscall_process_id,
+};
+use crate::user_ptr::{UserBytes, UserPtr, UserPtrError};
+
+/// A process with one mapped user page at `base` and nothing mapped at
+/// `base + PAGE_SIZE_4KB`, active as the syscall process while it lives.
+///
+/// Interrupts stay off so nothing switches CR3 away mid-test. Other
+/// subsystems' tests use it to exercise their own copies to user memory.
+pub struct UserCopyEnv {
+    pid: u32,
+    base: u64,
+    phys: PhysAddr,
+    saved_dir: *mut ProcessPageDir,
+    saved_provider: Option<fn() -> u32>,
+    _irq: IrqPreemptGuard,
+}
+
+impl UserCopyEnv {
+    pub fn new() -> Option<Self> {
+        init_process_vm();
+        let pid = create_process_vm();
+        if pid == INVALID_PROCESS_ID {
+            klog_info!("USER_COPY_TEST: Failed to create process");
+            return None;
+        }
+        // Reserve two pages of address space; only the first gets mapped
+        let base = process_vm_alloc(pid, 2 * PAGE_SIZE_4KB, PageFlags::WRITABLE.bits() as u32);
+        let dir = process_vm_get_page_dir(pid);
+        let phys = alloc_page_frame(ALLOC_FLAG_ZERO);
+        if base == 0 || dir.is_null() || phys.is_null() {
+            klog_info!("USER_COPY_TEST: Failed to set up user page");
+            if !phys.is_null() {
+                free_page_frame(phys);
+            }
+            destroy_process_vm(pid);
+            return None;
+        }
+        if map_page_4kb_in_dir(dir, VirtAddr::new(base), phys, PageFlags::USER_RW.bits()) != 0 {
+            klog_info!("USER_COPY_TEST: Failed to map user page");
+            free_page_frame(phys);
+            destroy_process_vm(pid);
+            return None;
+        }
+
+        let irq = IrqPreemptGuard::new();
+        let saved_dir = get_current_page_directory();
+        switch_page_directory(dir);
+        let saved_provider = set_syscall_process_id(pid);
+        Some(Self {
+            pid,
+            base,
+            phys,
+            saved_dir,
+            saved_provider,
+            _irq: irq,
+        })
+    }
+
+    /// Start of the mapped user page.
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    pub fn unmapped(&self) -> u64 {
+        self.base + PAGE_SIZE_4KB
+    }
+
+    /// Byte at `offset` into the mapped page, read through the HHDM.
+    fn page_byte(&self, offset: usize) -> u8 {
+        use crate::hhdm::PhysAddrHhdm;
+        let virt = self.phys.to_virt().as_u64() as *const u8;
+        unsafe { virt.add(offset).read_volatile() }
+    }
+}
+
+impl Drop for UserCopyEnv {
+    fn drop(&mut self) {
+        restore_task_provider(self.saved_provider);
+        switch_page_directory(self.saved_dir);
+        destroy_process_vm(self.pid);
+    }
+}
+
+fn pattern(i: usize) -> u8 {
+    (i as u8).wrapping_mul(37).wrapping_add(11)
+}
+
+pub fn test_user_copy_roundtrip() -> c_int {
+    let Some(env) = UserCopyEnv::new() else {
+        return -1;
+    };
+
+    let mut src = [0u8; 256];
+    for (i, b) in src.iter_mut().enumerate() {
+        *b = pattern(i);
+    }
+    let Ok(user) = UserBytes::try_new(env.base + 100, src.len()) else {
+        return -1;
+    };
+    if copy_bytes_to_user(user, &src) != Ok(src.len()) {
+        klog_info!("USER_COPY_TEST: copy_bytes_to_user failed");
+        return -1;
+    }
+    if (0..src.len()).any(|i| env.page_byte(100 + i) != src[i]) {
+        klog_info!("USER_COPY_TEST: BUG - user page contents differ from source");
+        return -1;
+    }
+    let mut back = [0u8; 256];
+    if copy_bytes_from_user(user, &mut back) != Ok(back.len()) || back != src {
+        klog_info!("USER_COPY_TEST: BUG - bytes did not round-trip");
+        return -1;
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    struct Record {
+        a: u64,
+        b: u32,
+        c: u16,
+    }
+    let record = Record {
+        a: 0x0123_4567_89AB_CDEF,
+        b: 0xDEAD_BEEF,
+        c: 0x5A5A,
+    };
+    let Ok(ptr) = UserPtr::<Record>::try_new(env.base + 0x800) else {
+        return -1;
+    };
+    if copy_to_user(ptr, &record).is_err() || copy_from_user(ptr) != Ok(record) {
+        klog_info!("USER_COPY_TEST: BUG - typed value did not round-trip");
+        return -1;
+    }
+    0
+}
+
+pub fn test_user_copy_unmapped_faults() -> c_int {
+    let Some(env) = UserCopyEnv::new() else {
+        return -1;
+    };
+
+    let Ok(ptr) = UserPtr::<u64>::try_new(env.unmapped() + 8) else {
+        return -1;
+    };
+    if copy_from_user(ptr) != Err(UserPtrError::NotMapped) {
+        klog_info!("USER_COPY_TEST: BUG - read of unmapped page not rejected");
+        return -1;
+    }
+    if copy_to_user(ptr, &0x1234) != Err(UserPtrError::NotMapped) {
+        klog_info!("USER_COPY_TEST: BUG - write to unmapped page not rejected");
+        return -1;
+    }
+
+    let Ok(bytes) = UserBytes::try_new(env.unmapped(), 32) else {
+        return -1;
+    };
+    let mut buf = [0u8; 32];
+    let expected = Err(UserCopyFault {
+        copied: 0,
+        error: UserPtrError::NotMapped,
+    });
+    if copy_bytes_from_user_partial(bytes, &mut buf) != expected
+        || copy_bytes_to_user_partial(bytes, &buf) != expected
+    {
+        klog_info!("USER_COPY_TEST: BUG - partial copy of unmapped page copied data");
+        return -1;
+    }
+    0
+}
+
+/// A copy starting 16 bytes before the end of the mapped page and running
+/// into the unmapped one.
+pub fn test_user_copy_spanning_page_boundary() -> c_int {
+    const PREFIX: usize = 16;
+    let Some(env) = UserCopyEnv::new() else {
+        return -1;
+    };
+    let start = env.unmapped() - PREFIX as u64;
+    let Ok(user) = UserBytes::try_new(start, 64) else {
+        return -1;
+    };
+    let page_offset = PAGE_SIZE_4KB as usize - PREFIX;
+
+    let mut src = [0u8; 64];
+    for (i, b) in src.iter_mut().enumerate() {
+        *b = pattern(i);
+    }
+
+    // All-or-nothing: rejected without touching the mapped prefix
+    if copy_bytes_to_user(user, &src) != Err(UserPtrError::NotMapped) {
+        klog_info!("USER_COPY_TEST: BUG - spanning copy_bytes_to_user not rejected");
+        return -1;
+    }
+    if (0..PREFIX).any(|i| env.page_byte(page_offset + i) != 0) {
+        klog_info!("USER_COPY_TEST: BUG - rejected copy wrote to the user page");
+        return -1;
+    }
+
+    let fault = Err(UserCopyFault {
+        copied: PREFIX,
+        error: UserPtrError::NotMapped,
+    });
+    if copy_bytes_to_user_partial(user, &src) != fault {
+        klog_info!("USER_COPY_TEST: BUG - partial write reported the wrong count");
+        return -1;
+    }
+    if (0..PREFIX).any(|i| env.page_byte(page_offset + i) != src[i]) {
+        klog_info!("USER_COPY_TEST: BUG - partial write prefix mismatch");
+        return -1;
+    }
+
+    let mut back = [0xEEu8; 64];
+    if copy_bytes_from_user_partial(user, &mut back) != fault {
+        klog_info!("USER_COPY_TEST: BUG - partial read reported the wrong count");
+        return -1;
+    }
+    if back[..PREFIX] != src[..PREFIX] || back[PREFIX..].iter().any(|&b| b != 0xEE) {
+        klog_info!("USER_COPY_TEST: BUG - partial read copied past the fault");
+        return -1;
+    }
+    0
+}
@@ -23,7 +23,7 @@ use core::ptr;
 use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU64, Ordering};
 
 use slopos_abi::addr::VirtAddr;
-use slopos_lib::{cpu, klog_debug, klog_info};
+use slopos_lib::{cpu, klog_debug_mod, klog_info};
 
 use crate::mm_constants::PAGE_SIZE_4KB;
 
@@ -38,7 +38,7 @@ static IPI_SENDER: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
 /// Must be called from the APIC driver during initialization.
 pub fn register_ipi_sender(sender: SendIpiFn) {
     IPI_SENDER.store(sender as *mut (), Ordering::Release);
-    klog_debug!("TLB: IPI sender registered");
+    klog_debug_mod!("mm", "TLB: IPI sender registered");
 }
 
 // =============================================================================
@@ -108,7 +108,8 @@ fn detect_features() {
         .store(invpcid_supported, Ordering::Release);
     TLB_FEATURES.initialized.store(true, Ordering::Release);
 
-    klog_debug!(
+    klog_debug_mod!(
+        "mm",
         "TLB: Features detected - PCID: {}, INVPCID: {}",
         pcid_supported,
         invpcid_supported
@@ -319,7 +320,8 @@ pub fn register_cpu(apic_id: u32) -> usize {
         if apic_id < MAX_CPUS as u32 {
             APIC_ID_TO_CPU_IDX[apic_id as usize].store(cpu_idx as u32, Ordering::Release);
         }
-        klog_debug!(
+        klog_debug_mod!(
+            "mm",
             "TLB: Registered CPU {} with APIC ID 0x{:x}",
             cpu_idx,
             apic_id
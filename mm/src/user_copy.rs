@@ -88,6 +88,40 @@ fn validate_user_pages(
     Ok(())
 }
 
+/// A byte copy that stopped at an inaccessible user page.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UserCopyFault {
+    /// Bytes transferred before the fault (the accessible prefix)
+    pub copied: usize,
+    pub error: UserPtrError,
+}
+
+/// Walk `len` bytes from `user_addr` page by page, handing each accessible
+/// chunk's (offset, length) to `copy_chunk` until a page is not accessible.
+fn copy_chunks_partial(
+    user_addr: UserVirtAddr,
+    len: usize,
+    mut copy_chunk: impl FnMut(usize, usize),
+) -> Result<usize, UserCopyFault> {
+    let dir = current_process_dir();
+    let start = user_addr.as_u64();
+    let mut copied = 0usize;
+    while copied < len {
+        let addr = start + copied as u64;
+        let page_end = (addr | (crate::mm_constants::PAGE_SIZE_4KB - 1)) + 1;
+        let chunk = ((page_end - addr) as usize).min(len - copied);
+        // Validating a single byte checks the whole page
+        if let Err(error) = UserVirtAddr::try_new(addr, chunk)
+            .and_then(|page_addr| validate_user_pages(page_addr, 1, dir))
+        {
+            return Err(UserCopyFault { copied, error });
+        }
+        copy_chunk(copied, chunk);
+        copied += chunk;
+    }
+    Ok(copied)
+}
+
 pub fn copy_from_user<T: Copy>(src: UserPtr<T>) -> Result<T, UserPtrError> {
     let dir = current_process_dir();
     validate_user_pages(src.addr(), core::mem::size_of::<T>(), dir)?;
@@ -134,3 +168,26 @@ pub fn copy_bytes_to_user(dst: UserBytes, src: &[u8]) -> Result<usize, UserPtrEr
     }
     Ok(copy_len)
 }
+
+/// Like `copy_bytes_from_user`, but copies the accessible prefix of a
+/// buffer that runs into an unmapped page instead of copying nothing.
+pub fn copy_bytes_from_user_partial(
+    src: UserBytes,
+    dst: &mut [u8],
+) -> Result<usize, UserCopyFault> {
+    let copy_len = src.len().min(dst.len());
+    let base = src.base().as_ptr::<u8>();
+    copy_chunks_partial(src.base(), copy_len, |offset, len| unsafe {
+        ptr::copy_nonoverlapping(base.add(offset), dst[offset..].as_mut_ptr(), len);
+    })
+}
+
+/// Like `copy_bytes_to_user`, but fills the accessible prefix of a buffer
+/// that runs into an unmapped page instead of writing nothing.
+pub fn copy_bytes_to_user_partial(dst: UserBytes, src: &[u8]) -> Result<usize, UserCopyFault> {
+    let copy_len = src.len().min(dst.len());
+    let base = dst.base().as_mut_ptr::<u8>();
+    copy_chunks_partial(dst.base(), copy_len, |offset, len| unsafe {
+        ptr::copy_nonoverlapping(src[offset..].as_ptr(), base.add(offset), len);
+    })
+}
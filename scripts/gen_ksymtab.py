@@ -0,0 +1,72 @@
+#!/usr/bin/env python3
+"""Build the kernel symbol table embedded in `.ksymtab`.
+
+Usage:
+    gen_ksymtab.py NM KERNEL_ELF OUT            write the table for KERNEL_ELF
+    gen_ksymtab.py --check NM KERNEL_ELF TABLE  fail if TABLE is stale
+
+The format is parsed by `SymbolTable` in lib/src/stacktrace.rs: magic
+`KSYM`, a u32 count, `count` entries of `<QII` (address, size, name offset)
+sorted by address, then NUL-terminated names. Only sized text symbols are
+kept.
+"""
+
+import struct
+import subprocess
+import sys
+
+MAGIC = b"KSYM"
+
+
+def text_symbols(nm, elf):
+    out = subprocess.run(
+        [nm, "--defined-only", "--demangle", "--numeric-sort", "--print-size", elf],
+        check=True,
+        capture_output=True,
+        text=True,
+    ).stdout
+    seen = set()
+    for line in out.splitlines():
+        fields = line.split(maxsplit=3)
+        if len(fields) != 4 or fields[2] not in ("t", "T"):
+            continue
+        addr, size = int(fields[0], 16), int(fields[1], 16)
+        if size == 0 or addr in seen:
+            continue
+        seen.add(addr)
+        yield addr, size, fields[3]
+
+
+def build_table(nm, elf):
+    entries = bytearray()
+    names = bytearray()
+    count = 0
+    for addr, size, name in text_symbols(nm, elf):
+        entries += struct.pack("<QII", addr, min(size, 0xFFFF_FFFF), len(names))
+        names += name.encode() + b"\0"
+        count += 1
+    return MAGIC + struct.pack("<I", count) + bytes(entries) + bytes(names)
+
+
+def main(argv):
+    check = len(argv) == 5 and argv[1] == "--check"
+    if check:
+        argv = argv[1:]
+    if len(argv) != 4:
+        print(__doc__.strip(), file=sys.stderr)
+        return 2
+    nm, elf, path = argv[1:]
+    table = build_table(nm, elf)
+    if check:
+        with open(path, "rb") as f:
+            if f.read() != table:
+                print(f"{path}: symbol addresses moved after relink", file=sys.stderr)
+                return 1
+        return 0
+    with open(path, "wb") as f:
+        f.write(table)
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main(sys.argv))
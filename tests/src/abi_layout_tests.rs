@@ -0,0 +1,112 @@
+//! ABI layout guard
+//!
+//! Structs in `slopos_abi` cross the kernel/userland boundary as raw bytes,
+//! so a size or alignment change silently breaks every binary built against
+//! the old layout. The expected values are written out by hand: updating one
+//! is a deliberate ABI break and belongs in the same change as the type.
+
+use core::ffi::c_int;
+use core::mem::{align_of, size_of};
+
+use slopos_abi::arch::x86_64::pci::PciDeviceInfo;
+use slopos_abi::damage::DamageRect;
+use slopos_abi::fate::FateResult;
+use slopos_abi::syscall::{UserSysInfo, UserTimeval};
+use slopos_abi::{
+    DisplayInfo, InputEvent, InputEventData, UserDirent, UserFsEntry, UserFsList, UserFsStat,
+    WindowDamageRect, WindowInfo,
+};
+use slopos_lib::klog_info;
+
+/// Expected (size, align) of each guarded struct
+pub const WINDOW_INFO_LAYOUT: (usize, usize) = (192, 4);
+pub const WINDOW_DAMAGE_RECT_LAYOUT: (usize, usize) = (16, 4);
+pub const DAMAGE_RECT_LAYOUT: (usize, usize) = (16, 4);
+pub const INPUT_EVENT_LAYOUT: (usize, usize) = (24, 8);
+pub const INPUT_EVENT_DATA_LAYOUT: (usize, usize) = (8, 4);
+pub const DISPLAY_INFO_LAYOUT: (usize, usize) = (16, 4);
+pub const USER_FS_ENTRY_LAYOUT: (usize, usize) = (72, 4);
+pub const USER_FS_STAT_LAYOUT: (usize, usize) = (8, 4);
+pub const USER_FS_LIST_LAYOUT: (usize, usize) = (16, 8);
+pub const USER_DIRENT_LAYOUT: (usize, usize) = (16, 8);
+pub const USER_SYS_INFO_LAYOUT: (usize, usize) = (56, 8);
+pub const USER_TIMEVAL_LAYOUT: (usize, usize) = (16, 8);
+pub const FATE_RESULT_LAYOUT: (usize, usize) = (8, 4);
+pub const PCI_DEVICE_INFO_LAYOUT: (usize, usize) = (160, 8);
+
+struct AbiLayout {
+    name: &'static str,
+    actual: (usize, usize),
+    expected: (usize, usize),
+}
+
+impl AbiLayout {
+    fn matches(&self) -> bool {
+        self.actual == self.expected
+    }
+}
+
+macro_rules! abi_layout {
+    ($ty:ty, $expected:expr) => {
+        AbiLayout {
+            name: stringify!($ty),
+            actual: (size_of::<$ty>(), align_of::<$ty>()),
+            expected: $expected,
+        }
+    };
+}
+
+const ABI_LAYOUTS: [AbiLayout; 14] = [
+    abi_layout!(WindowInfo, WINDOW_INFO_LAYOUT),
+    abi_layout!(WindowDamageRect, WINDOW_DAMAGE_RECT_LAYOUT),
+    abi_layout!(DamageRect, DAMAGE_RECT_LAYOUT),
+    abi_layout!(InputEvent, INPUT_EVENT_LAYOUT),
+    abi_layout!(InputEventData, INPUT_EVENT_DATA_LAYOUT),
+    abi_layout!(DisplayInfo, DISPLAY_INFO_LAYOUT),
+    abi_layout!(UserFsEntry, USER_FS_ENTRY_LAYOUT),
+    abi_layout!(UserFsStat, USER_FS_STAT_LAYOUT),
+    abi_layout!(UserFsList, USER_FS_LIST_LAYOUT),
+    abi_layout!(UserDirent, USER_DIRENT_LAYOUT),
+    abi_layout!(UserSysInfo, USER_SYS_INFO_LAYOUT),
+    abi_layout!(UserTimeval, USER_TIMEVAL_LAYOUT),
+    abi_layout!(FateResult, FATE_RESULT_LAYOUT),
+    abi_layout!(PciDeviceInfo, PCI_DEVICE_INFO_LAYOUT),
+];
+
+pub fn test_abi_struct_layouts_stable() -> c_int {
+    let mut failures = 0;
+    for layout in ABI_LAYOUTS.iter().filter(|l| !l.matches()) {
+        klog_info!(
+            "ABI_LAYOUT_TEST: BUG - {} is size {} align {}, ABI expects size {} align {}",
+            layout.name,
+            layout.actual.0,
+            layout.actual.1,
+            layout.expected.0,
+            layout.expected.1
+        );
+        failures += 1;
+    }
+    if failures != 0 { -1 } else { 0 }
+}
+
+/// The guard must flag a changed size or alignment and nothing else.
+pub fn test_abi_layout_guard_detects_change() -> c_int {
+    #[repr(C)]
+    struct Grown {
+        _info: WindowInfo,
+        _extra: u32,
+    }
+    #[repr(C, align(8))]
+    struct Realigned {
+        _info: WindowInfo,
+    }
+
+    let grown = abi_layout!(Grown, WINDOW_INFO_LAYOUT);
+    let realigned = abi_layout!(Realigned, WINDOW_INFO_LAYOUT);
+    let same = abi_layout!(WindowInfo, WINDOW_INFO_LAYOUT);
+    if grown.matches() || realigned.matches() || !same.matches() {
+        klog_info!("ABI_LAYOUT_TEST: BUG - layout guard did not detect a changed struct");
+        return -1;
+    }
+    0
+}
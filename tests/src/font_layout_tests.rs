@@ -0,0 +1,237 @@
+use core::ffi::c_int;
+
+use slopos_abi::font::{
+    FONT_CHAR_HEIGHT, FONT_CHAR_WIDTH, FONT_DATA, FONT_FIRST_CHAR, FONT_LAST_CHAR, get_glyph,
+    get_glyph_char, get_glyph_char_or_space, get_glyph_or_space,
+};
+use slopos_abi::font_render::{
+    FontSize, GlyphPlacement, TextRun, baseline_offset, draw_char, draw_str, draw_string,
+    draw_string_scaled, layout_runs,
+};
+use slopos_lib::klog_info;
+use slopos_lib::testing::TestCanvas;
+
+const MAX_GLYPHS: usize = 16;
+
+fn run(text: &[u8], size: FontSize) -> TextRun<'_> {
+    TextRun {
+        text,
+        size,
+        fg: 0xFFFFFF,
+        bg: 0,
+    }
+}
+
+/// Lay out `runs` at (0, 0) and collect the placements.
+fn layout(runs: &[TextRun<'_>], right: i32) -> ([Option<GlyphPlacement>; MAX_GLYPHS], i32) {
+    let mut glyphs = [None; MAX_GLYPHS];
+    let mut count = 0;
+    let end = layout_runs(runs, 0, 0, right, 1000, |g| {
+        if count < MAX_GLYPHS {
+            glyphs[count] = Some(g);
+        }
+        count += 1;
+    });
+    (glyphs, end)
+}
+
+pub fn test_mixed_sizes_share_baseline() -> c_int {
+    let runs = [
+        run(b"ab", FontSize::Normal),
+        run(b"CD", FontSize::Large),
+        run(b"e", FontSize::Normal),
+    ];
+    let (glyphs, _) = layout(&runs, 1000);
+    let Some(first) = glyphs[0] else {
+        return -1;
+    };
+    let baseline = first.y + baseline_offset(first.size);
+    for g in glyphs.iter().take(5) {
+        let Some(g) = g else {
+            klog_info!("FONT_LAYOUT_TEST: BUG - glyph missing from layout");
+            return -1;
+        };
+        if g.y + baseline_offset(g.size) != baseline {
+            klog_info!(
+                "FONT_LAYOUT_TEST: BUG - '{}' baseline {} != {}",
+                g.ch as char,
+                g.y + baseline_offset(g.size),
+                baseline
+            );
+            return -1;
+        }
+    }
+    // Small glyphs hang lower than the top of the line, large ones start at it
+    let large = glyphs[2].unwrap();
+    if large.y != 0
+        || first.y != baseline_offset(FontSize::Large) - baseline_offset(FontSize::Normal)
+    {
+        klog_info!("FONT_LAYOUT_TEST: BUG - line top not set by the largest glyph");
+        return -1;
+    }
+    if glyphs[4].unwrap().x
+        != 2 * FontSize::Normal.glyph_width() + 2 * FontSize::Large.glyph_width()
+    {
+        klog_info!("FONT_LAYOUT_TEST: BUG - glyphs advanced by the wrong width");
+        return -1;
+    }
+    0
+}
+
+pub fn test_line_advance_uses_tallest_glyph() -> c_int {
+    let large = FontSize::Large.glyph_height();
+    let normal = FontSize::Normal.glyph_height();
+
+    // A large glyph anywhere on the first line pushes the second line down
+    let runs = [
+        run(b"a", FontSize::Normal),
+        run(b"B", FontSize::Large),
+        run(b"\nc", FontSize::Normal),
+    ];
+    let (glyphs, end) = layout(&runs, 1000);
+    let Some(c) = glyphs[2] else {
+        return -1;
+    };
+    if c.y != large || end != large + normal {
+        klog_info!(
+            "FONT_LAYOUT_TEST: BUG - line advance {} ignores tallest glyph",
+            c.y
+        );
+        return -1;
+    }
+
+    // All-normal lines keep the plain glyph height
+    let runs = [run(b"a\nb", FontSize::Normal)];
+    let (glyphs, _) = layout(&runs, 1000);
+    if glyphs[1].map(|g| g.y) != Some(normal) {
+        klog_info!("FONT_LAYOUT_TEST: BUG - normal line advance changed");
+        return -1;
+    }
+
+    // Wrapped lines are measured the same way: "aB" fits, "c" wraps
+    let right = FontSize::Normal.glyph_width() + FontSize::Large.glyph_width();
+    let runs = [
+        run(b"a", FontSize::Normal),
+        run(b"B", FontSize::Large),
+        run(b"c", FontSize::Normal),
+    ];
+    let (glyphs, _) = layout(&runs, right);
+    if glyphs[2].map(|g| (g.x, g.y)) != Some((0, large)) {
+        klog_info!("FONT_LAYOUT_TEST: BUG - wrapped line not placed below tallest glyph");
+        return -1;
+    }
+    0
+}
+
+const CANVAS_W: usize = 48;
+const CANVAS_H: usize = 40;
+
+/// Glyphs are drawn with a zero background, so only foreground pixels are
+/// ever inked.
+type Canvas = TestCanvas<CANVAS_W, CANVAS_H>;
+
+pub fn test_scaled_glyph_blocks_match_normal() -> c_int {
+    const FG: u32 = 0xFF80_40FF;
+    let mut normal = Canvas::new();
+    draw_char(&mut normal, 0, 0, b'A', FG, 0);
+    let mut scaled = Canvas::new();
+    draw_string_scaled(&mut scaled, 0, 0, b"A", 2, FG, 0);
+
+    let mut set_bits = 0;
+    for row in 0..FONT_CHAR_HEIGHT {
+        for col in 0..FONT_CHAR_WIDTH {
+            let want = normal.inked(col, row);
+            if want {
+                set_bits += 1;
+            }
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                if scaled.inked(col * 2 + dx, row * 2 + dy) != want {
+                    klog_info!(
+                        "FONT_LAYOUT_TEST: BUG - 2x block ({}, {}) differs from the 1x glyph",
+                        col,
+                        row
+                    );
+                    return -1;
+                }
+            }
+        }
+    }
+    if set_bits == 0 {
+        klog_info!("FONT_LAYOUT_TEST: BUG - glyph drew nothing");
+        return -1;
+    }
+
+    // Nothing lands outside the 16x32 cell, and the next glyph starts after it
+    let outside = (0..CANVAS_H as i32).any(|y| {
+        (0..CANVAS_W as i32)
+            .any(|x| (x >= 2 * FONT_CHAR_WIDTH || y >= 2 * FONT_CHAR_HEIGHT) && scaled.inked(x, y))
+    });
+    if outside {
+        klog_info!("FONT_LAYOUT_TEST: BUG - scaled glyph drew outside its cell");
+        return -1;
+    }
+    let mut pair = Canvas::new();
+    draw_string_scaled(&mut pair, 0, 0, b"AA", 2, FG, 0);
+    let shifted = (0..2 * FONT_CHAR_HEIGHT)
+        .all(|y| (0..CANVAS_W as i32 - 16).all(|x| pair.inked(x + 16, y) == scaled.inked(x, y)));
+    if !shifted {
+        klog_info!("FONT_LAYOUT_TEST: BUG - second glyph not advanced by the scaled width");
+        return -1;
+    }
+    0
+}
+
+pub fn test_latin1_and_box_glyphs() -> c_int {
+    let space = &FONT_DATA[0];
+    for ch in FONT_FIRST_CHAR..=FONT_LAST_CHAR {
+        if get_glyph(ch) != Some(&FONT_DATA[(ch - FONT_FIRST_CHAR) as usize]) {
+            klog_info!("FONT_LAYOUT_TEST: BUG - ASCII glyph {} changed", ch);
+            return -1;
+        }
+    }
+
+    let samples = [
+        'é', 'Ä', 'ß', 'ñ', '©', '¿', '─', '│', '┼', '╔', '╬', '█', '▒',
+    ];
+    for (i, &ch) in samples.iter().enumerate() {
+        let Some(glyph) = get_glyph_char(ch) else {
+            klog_info!("FONT_LAYOUT_TEST: BUG - no glyph for U+{:04X}", ch as u32);
+            return -1;
+        };
+        let duplicate = samples[..i]
+            .iter()
+            .any(|&other| get_glyph_char_or_space(other) == glyph);
+        if glyph == space || duplicate {
+            klog_info!(
+                "FONT_LAYOUT_TEST: BUG - U+{:04X} not a glyph of its own",
+                ch as u32
+            );
+            return -1;
+        }
+    }
+    if get_glyph(0xE9) == get_glyph(b'e') || get_glyph(0xC4) == get_glyph(b'A') {
+        klog_info!("FONT_LAYOUT_TEST: BUG - accented letter drawn without its accent");
+        return -1;
+    }
+
+    // C1 controls and characters outside the tables still fall back
+    if get_glyph(0x85).is_some()
+        || get_glyph_or_space(0x9F) != space
+        || get_glyph_char('\u{2603}').is_some()
+        || get_glyph_char_or_space('\u{2603}') != space
+    {
+        klog_info!("FONT_LAYOUT_TEST: BUG - undefined codepoint did not fall back to space");
+        return -1;
+    }
+
+    // UTF-8 strings draw the same glyph as the Latin-1 byte
+    let mut utf8 = Canvas::new();
+    draw_str(&mut utf8, 0, 0, "é", 0xFFFF_FFFF, 0);
+    let mut latin1 = Canvas::new();
+    draw_string(&mut latin1, 0, 0, &[0xE9], 0xFFFF_FFFF, 0);
+    if utf8.px != latin1.px || (0..FONT_CHAR_WIDTH).any(|x| utf8.inked(x + FONT_CHAR_WIDTH, 6)) {
+        klog_info!("FONT_LAYOUT_TEST: BUG - UTF-8 text not decoded to characters");
+        return -1;
+    }
+    0
+}
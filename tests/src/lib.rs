@@ -14,7 +14,10 @@ use slopos_lib::{StateFlag, define_test_suite, klog_info, register_test_suites};
 pub type InterruptTestConfig = TestConfig;
 pub type InterruptTestVerbosity = Verbosity;
 
+pub mod abi_layout_tests;
 pub mod exception_tests;
+pub mod font_layout_tests;
+pub mod line_history_tests;
 
 pub const TESTS_MAX_SUITES: usize = HARNESS_MAX_SUITES;
 
@@ -54,8 +57,32 @@ pub fn tests_register_suite(desc: &'static TestSuiteDesc) -> i32 {
     0
 }
 
+/// Most crates above this one (e.g. userland) that bring their own suites.
+pub const TESTS_MAX_EXTERNAL_SUITES: usize = 4;
+
+/// Suites from crates that depend on this one and so cannot be listed in
+/// `suites::register_all`. Unlike the registry, kept across resets.
+static EXTERNAL_SUITES: spin::Mutex<[Option<&'static TestSuiteDesc>; TESTS_MAX_EXTERNAL_SUITES]> =
+    spin::Mutex::new([None; TESTS_MAX_EXTERNAL_SUITES]);
+
+/// Add a suite to every later `tests_register_system_suites` run.
+pub fn tests_add_external_suite(desc: &'static TestSuiteDesc) -> i32 {
+    let mut external = EXTERNAL_SUITES.lock();
+    match external.iter_mut().find(|slot| slot.is_none()) {
+        Some(slot) => {
+            *slot = Some(desc);
+            0
+        }
+        None => -1,
+    }
+}
+
 pub fn tests_register_system_suites() {
     suites::register_all();
+    let external = *EXTERNAL_SUITES.lock();
+    for desc in external.into_iter().flatten() {
+        tests_register_suite(desc);
+    }
 }
 
 pub fn tests_run_all(config: *const InterruptTestConfig, summary: *mut TestRunSummary) -> i32 {
@@ -189,8 +216,8 @@ mod suites {
     use slopos_lib::testing::HarnessConfig;
 
     use slopos_mm::tests::{
-        test_alloc_free_cycles_no_leak, test_cow_clone_modify_both, test_cow_fault_handling,
-        test_cow_handle_invalid_address, test_cow_handle_not_cow_page,
+        test_alloc_free_cycles_no_leak, test_bump_alloc_bounds, test_cow_clone_modify_both,
+        test_cow_fault_handling, test_cow_handle_invalid_address, test_cow_handle_not_cow_page,
         test_cow_handle_null_pagedir, test_cow_multi_ref_copy, test_cow_multiple_clones,
         test_cow_no_collateral_damage, test_cow_not_present_not_cow, test_cow_page_boundary,
         test_cow_page_isolation, test_cow_read_not_cow_fault, test_cow_single_ref_upgrade,
@@ -202,45 +229,69 @@ mod suites {
         test_demand_permission_allow_read, test_demand_permission_allow_write,
         test_demand_permission_deny_exec, test_demand_permission_deny_user_kernel,
         test_demand_permission_deny_write_ro, test_dma_allocation_exhaustion,
-        test_heap_alloc_pressure, test_heap_alloc_zero, test_heap_boundary_write,
-        test_heap_double_free_defensive, test_heap_expansion_under_pressure,
-        test_heap_fragmentation_behind_head, test_heap_free_list_search, test_heap_kfree_null,
-        test_heap_kzalloc_zeroed, test_heap_large_alloc, test_heap_large_block_integrity,
-        test_heap_medium_alloc, test_heap_no_overlap, test_heap_small_alloc, test_heap_stats,
-        test_heap_stress_cycles, test_irqmutex_basic, test_irqmutex_mutation,
-        test_irqmutex_try_lock, test_kzalloc_zeroed_under_pressure, test_multiorder_alloc_failure,
+        test_early_heap_coalesce_and_align, test_early_heap_reuses_freed, test_heap_alloc_pressure,
+        test_heap_alloc_zero, test_heap_boundary_write, test_heap_double_free_defensive,
+        test_heap_expansion_under_pressure, test_heap_fragmentation_behind_head,
+        test_heap_free_list_search, test_heap_kfree_null, test_heap_kzalloc_zeroed,
+        test_heap_large_alloc, test_heap_large_block_integrity, test_heap_medium_alloc,
+        test_heap_no_overlap, test_heap_small_alloc, test_heap_stats, test_heap_stress_cycles,
+        test_id_alloc_exhaustion, test_id_alloc_recycles_lowest, test_irqmutex_basic,
+        test_irqmutex_mutation, test_irqmutex_restores_interrupt_flag, test_irqmutex_try_lock,
+        test_kzalloc_zeroed_under_pressure, test_multiorder_alloc_failure,
         test_multiple_process_vms, test_page_alloc_fragmentation,
-        test_page_alloc_fragmentation_oom, test_page_alloc_free_cycle, test_page_alloc_free_null,
+        test_page_alloc_fragmentation_oom, test_page_alloc_free_cycle,
+        test_page_alloc_free_keeps_contents, test_page_alloc_free_null,
         test_page_alloc_multi_order, test_page_alloc_multipage_integrity,
         test_page_alloc_no_stale_data, test_page_alloc_refcount, test_page_alloc_single,
-        test_page_alloc_stats, test_page_alloc_until_oom, test_page_alloc_write_verify,
-        test_page_alloc_zero_full_page, test_page_alloc_zeroed, test_paging_cow_kernel,
-        test_paging_get_kernel_dir, test_paging_user_accessible_kernel, test_paging_virt_to_phys,
-        test_process_heap_expansion_oom, test_process_vm_alloc_and_access,
-        test_process_vm_brk_expansion, test_process_vm_counter_reset,
+        test_page_alloc_stats, test_page_alloc_stats_alloc_free_delta,
+        test_page_alloc_stats_largest_run, test_page_alloc_stats_scattered_free,
+        test_page_alloc_until_oom, test_page_alloc_write_verify, test_page_alloc_zero_full_page,
+        test_page_alloc_zero_on_free, test_page_alloc_zero_on_free_realloc, test_page_alloc_zeroed,
+        test_paging_cow_kernel, test_paging_get_kernel_dir, test_paging_user_accessible_kernel,
+        test_paging_virt_to_phys, test_process_heap_expansion_oom,
+        test_process_vm_alloc_and_access, test_process_vm_brk_expansion,
+        test_process_vm_brk_touch_maps_only_touched, test_process_vm_counter_reset,
         test_process_vm_create_destroy_memory, test_process_vm_creation_pressure,
-        test_process_vm_slot_reuse, test_refcount_during_oom, test_ring_buffer_basic,
-        test_ring_buffer_capacity, test_ring_buffer_empty_pop, test_ring_buffer_fifo,
-        test_ring_buffer_full, test_ring_buffer_overwrite, test_ring_buffer_reset,
-        test_ring_buffer_wrap, test_shm_create_destroy, test_shm_create_excessive_size,
-        test_shm_create_zero_size, test_shm_destroy_non_owner, test_shm_invalid_token,
-        test_shm_mapping_overflow, test_shm_refcount, test_shm_surface_attach,
+        test_process_vm_mmap_large_is_lazy, test_process_vm_mmap_munmap,
+        test_process_vm_set_brk_grow_lazy, test_process_vm_set_brk_rejects_stack,
+        test_process_vm_set_brk_shrink_unmaps, test_process_vm_slot_reuse,
+        test_refcount_during_oom, test_ring_buffer_basic, test_ring_buffer_capacity,
+        test_ring_buffer_empty_pop, test_ring_buffer_fifo, test_ring_buffer_full,
+        test_ring_buffer_iter_non_destructive, test_ring_buffer_iter_partial,
+        test_ring_buffer_iter_wrapped, test_ring_buffer_overwrite, test_ring_buffer_overwrite_mode,
+        test_ring_buffer_reset, test_ring_buffer_slice_wraparound, test_ring_buffer_wrap,
+        test_ring_buffer_write_slice_partial, test_shm_create_destroy,
+        test_shm_create_excessive_size, test_shm_create_zero_size, test_shm_destroy_non_owner,
+        test_shm_invalid_token, test_shm_mapping_overflow, test_shm_reallocate_keeps_old_mapping,
+        test_shm_refcount, test_shm_surface_attach, test_shm_surface_attach_error_kinds,
         test_shm_surface_attach_overflow, test_shm_surface_attach_too_small,
-        test_vma_flags_retrieval, test_zero_flag_under_pressure,
+        test_user_copy_roundtrip, test_user_copy_spanning_page_boundary,
+        test_user_copy_unmapped_faults, test_vma_flags_retrieval, test_zero_flag_under_pressure,
     };
 
     use slopos_core::sched_tests::{
+        test_affinity_excludes_cpu, test_affinity_migrates_queued_task,
+        test_affinity_running_task_leaves_cpu, test_aging_prevents_low_priority_starvation,
+        test_block_unless_pending_returns, test_cancel_block_only_before_wake,
         test_create_conflicting_flags, test_create_max_tasks, test_create_null_entry,
         test_create_null_name, test_create_over_max_tasks, test_double_terminate,
-        test_find_invalid_id, test_get_info_null_output, test_idle_priority_last,
-        test_interleaved_operations, test_many_same_priority_tasks, test_priority_ordering,
-        test_rapid_create_destroy_cycle, test_schedule_duplicate_task, test_schedule_null_task,
-        test_schedule_to_empty_queue, test_schedule_while_disabled, test_scheduler_starts_disabled,
+        test_equal_priority_round_robin, test_exit_code_retrievable_via_wait, test_find_invalid_id,
+        test_get_info_null_output, test_idle_priority_last, test_interleaved_operations,
+        test_kthread_closure_failure_frees_box, test_kthread_closure_runs,
+        test_many_same_priority_tasks, test_priority_ordering, test_rapid_create_destroy_cycle,
+        test_sched_policy_custom_pick_honored, test_sched_policy_edf_and_fair_pick,
+        test_sched_policy_round_robin_default, test_schedule_duplicate_task,
+        test_schedule_null_task, test_schedule_to_empty_queue, test_schedule_while_disabled,
+        test_scheduler_starts_disabled, test_sleep_wakes_after_deadline,
         test_state_transition_invalid_blocked_to_running,
         test_state_transition_invalid_terminated_to_running,
         test_state_transition_ready_to_running, test_state_transition_running_to_blocked,
-        test_terminate_invalid_id, test_terminate_nonexistent_id, test_timer_tick_decrements_slice,
-        test_timer_tick_no_current_task, test_unschedule_not_in_queue,
+        test_task_cpu_time_accumulates, test_terminate_invalid_id, test_terminate_nonexistent_id,
+        test_terminated_task_reports_killed, test_timer_tick_decrements_slice,
+        test_timer_tick_no_current_task, test_transition_running_to_ready,
+        test_transition_same_state_rejected, test_transition_terminated_to_running_rejected,
+        test_unschedule_not_in_queue, test_waitpid_reaps_child_once, test_work_queue_fifo_drain,
+        test_work_queue_overflow_counts_drops, test_yield_hands_off_to_sibling,
     };
 
     use slopos_drivers::ioapic_tests::{
@@ -252,12 +303,30 @@ mod suites {
         test_ioapic_register_constants, test_ioapic_unmask_invalid_gsi,
     };
 
+    use crate::abi_layout_tests::{
+        test_abi_layout_guard_detects_change, test_abi_struct_layouts_stable,
+    };
+
+    use crate::font_layout_tests::{
+        test_latin1_and_box_glyphs, test_line_advance_uses_tallest_glyph,
+        test_mixed_sizes_share_baseline, test_scaled_glyph_blocks_match_normal,
+    };
+
+    use crate::line_history_tests::{
+        test_history_collapses_consecutive_duplicates, test_history_navigate_back_and_forth,
+        test_history_ring_drops_oldest,
+    };
+
     use crate::exception_tests::{
-        test_critical_exception_classification, test_error_code_preservation,
+        test_critical_exception_classification, test_decode_gp_selector,
+        test_decode_page_fault_flags, test_error_code_preservation,
         test_exception_names_all_vectors, test_exception_names_valid,
+        test_fatal_dump_page_fault_report, test_fatal_dump_without_stack,
         test_frame_integrity_patterns, test_frame_invalid_cs, test_frame_mode_detection,
-        test_frame_noncanonical_addresses, test_known_exception_names, test_page_fault_error_codes,
-        test_vector_boundaries,
+        test_frame_noncanonical_addresses, test_known_exception_names,
+        test_nonrecoverable_classification, test_nonrecoverable_report_is_minimal,
+        test_page_fault_error_codes, test_stacktrace_resolves_kernel_function,
+        test_symbol_table_resolve, test_vector_boundaries,
     };
 
     use slopos_mm::tlb_tests::{
@@ -302,9 +371,11 @@ mod suites {
         test_irq_stats_invalid, test_irq_unregister_nonexistent,
         test_operations_on_terminated_task, test_shm_create_boundaries,
         test_syscall_lookup_empty_slot, test_syscall_lookup_invalid_number,
-        test_syscall_lookup_valid, test_task_id_wraparound, test_terminate_already_terminated,
-        test_user_ptr_kernel_address, test_user_ptr_misaligned, test_user_ptr_null,
-        test_user_ptr_overflow_boundary,
+        test_syscall_lookup_valid, test_syscall_register_and_dispatch,
+        test_syscall_register_duplicate, test_syscall_trace_disabled_silent,
+        test_syscall_trace_enabled_logs_call, test_syscall_unregistered_enosys,
+        test_task_id_wraparound, test_terminate_already_terminated, test_user_ptr_kernel_address,
+        test_user_ptr_misaligned, test_user_ptr_null, test_user_ptr_overflow_boundary,
     };
 
     use slopos_core::exec::tests::{
@@ -312,9 +383,99 @@ mod suites {
         test_elf_kernel_address_entry, test_elf_no_load_segments, test_elf_phentsize_mismatch,
         test_elf_segment_filesz_greater_than_memsz, test_elf_segment_offset_overflow,
         test_elf_segment_overflow_vaddr, test_elf_truncated_header, test_elf_wrong_class,
-        test_elf_wrong_endian, test_elf_wrong_machine, test_exec_max_size_boundary,
-        test_path_empty, test_path_too_long, test_process_vm_null_page_dir,
-        test_translate_address_kernel_to_user, test_translate_address_user_passthrough,
+        test_elf_wrong_endian, test_elf_wrong_machine, test_exec_args_too_long,
+        test_exec_args_too_many, test_exec_args_well_formed, test_exec_max_size_boundary,
+        test_exec_shebang_argv, test_exec_shebang_chain, test_exec_shebang_parse,
+        test_exec_stack_auxv, test_path_empty, test_path_too_long, test_process_vm_null_page_dir,
+        test_translate_address_kernel_to_user, test_translate_address_overflow_rejected,
+        test_translate_address_user_passthrough,
+    };
+
+    use slopos_drivers::hpet_tests::{
+        test_hpet_counter_wrap, test_hpet_period_validation, test_hpet_tsc_calibration_plausible,
+        test_hpet_tsc_hz_conversion,
+    };
+    use slopos_drivers::keyboard_tests::{
+        test_keyboard_caps_lock_letters_only, test_keyboard_ctrl_and_prefix,
+        test_keyboard_irq_path_altgr, test_keyboard_layout_de,
+        test_keyboard_layout_syscall_registered, test_keyboard_layout_us_and_uk,
+        test_keyboard_repeat_after_delay, test_keyboard_repeat_cancel_and_modifiers,
+        test_keyboard_repeat_resamples_modifiers, test_keyboard_repeat_timing_from_hz,
+        test_keyboard_set_layout, test_keyboard_shift_uppercases_letter,
+    };
+    use slopos_drivers::mouse_tests::{
+        test_mouse_decode_negative_motion, test_mouse_decode_positive_motion,
+        test_mouse_decoder_resyncs, test_mouse_poll_event_drains_queue,
+    };
+    use slopos_drivers::pci_msi_tests::{
+        test_msi_capability_found_in_chain, test_msi_capability_loop_terminates,
+        test_msi_message_targets_apic, test_msi_no_capability_list, test_msi_program_32bit,
+        test_msi_program_64bit, test_msi_program_rejects_capability_at_end,
+    };
+    use slopos_drivers::pci_tests::{
+        test_pci_bar_sizing_disables_decode, test_pci_scan_multifunction,
+        test_pci_scan_reports_present_devices, test_pci_scan_stops_at_capacity,
+    };
+    use slopos_drivers::random_tests::{
+        test_random_bytes_kib_varies, test_random_constant_pattern_flagged,
+        test_random_hw_rdrand_delivers, test_random_lfsr_output_sane, test_random_next_output_sane,
+        test_random_stats_all_one, test_random_stats_all_zero, test_random_stats_balanced,
+    };
+    use slopos_drivers::rtc_tests::{
+        test_rtc_bcd_invalid_clamped, test_rtc_bcd_to_bin, test_rtc_decode_bcd_24h,
+        test_rtc_decode_bcd_to_unix, test_rtc_decode_binary_12h, test_rtc_fadt_century_register,
+        test_rtc_missing_century_assumes_2000s, test_rtc_read_in_range,
+        test_rtc_wall_time_interpolates_tsc, test_rtc_wall_time_keeps_advancing,
+    };
+    use slopos_drivers::tty_tests::{
+        test_serial_read_line_edits_and_truncates, test_serial_rx_drops_line_errors,
+        test_serial_rx_fifo_order, test_tty_canonical_backspace_erases,
+        test_tty_canonical_ctrl_c_discards_line, test_tty_canonical_waits_for_enter,
+        test_tty_console_cmdline_serial, test_tty_console_default_framebuffer,
+        test_tty_console_no_framebuffer, test_tty_console_set_roundtrip, test_tty_mode_needs_focus,
+        test_tty_mode_roundtrip, test_tty_scrollback_not_full_and_wrapped,
+        test_tty_scrollback_page_up, test_tty_scrollback_snap_on_output,
+    };
+    use slopos_lib::clock_tests::{
+        test_clock_cycles_to_ns_known_freq, test_clock_monotonic_never_decreases,
+        test_clock_unknown_freq_falls_back_to_ticks,
+    };
+
+    use slopos_video::compositor_tests::{
+        test_blend_alpha_over_window, test_blend_mode_occlusion, test_blend_opaque_ignores_alpha,
+        test_buffer_age_first_commit_undefined, test_buffer_age_matches_buffer_count,
+        test_buffer_age_reset_on_reregister, test_buffer_age_unknown_surface,
+        test_capture_converts_to_bgra, test_capture_validates_buffer, test_color_key_16bpp_row,
+        test_color_key_cleared_copies_all, test_color_key_composite, test_commit_copy_retains_back,
+        test_commit_swap_and_copy_mixed, test_commit_swap_exchanges_buffers,
+        test_compose_blit_clips_and_converts, test_compose_into_overlap_top_wins,
+        test_damage_add_coalesces_touching, test_damage_least_waste_merges_adjacent,
+        test_damage_smallest_area_merges_distant, test_focus_routes_keyboard_events,
+        test_format_argb8888_onto_rgb888_keyed, test_format_declared_in_window_info,
+        test_format_rgb888_onto_argb8888, test_frame_batches_damage_into_one_compose,
+        test_frame_callback_fires_while_minimized, test_frame_done_set_by_present,
+        test_frame_done_skips_minimized, test_frame_unmatched_begin_end,
+        test_headless_present_no_framebuffer, test_headless_surfaces_enumerable,
+        test_input_queue_drop_newest, test_input_queue_drop_oldest,
+        test_list_windows_batches_and_respects_max, test_list_windows_copies_to_user,
+        test_overlay_clear_restores_content, test_overlay_cursor_moves_and_damage,
+        test_overlay_move_restores_pixels, test_overlay_on_top_of_windows,
+        test_surface_resize_preserves_content, test_thumbnail_preserves_aspect,
+        test_thumbnail_solid_color, test_thumbnail_uses_declared_format,
+        test_title_embedded_nul_rejected, test_title_long_input_truncated,
+        test_title_unterminated_slot_truncated, test_triple_buffer_detach_returns_spare,
+        test_triple_buffer_quick_commits, test_triple_buffer_swap_rotates,
+    };
+    use slopos_video::roulette_tests::{
+        test_roulette_anim_clamps_past_end, test_roulette_anim_decelerates,
+        test_roulette_anim_lands_on_target, test_roulette_ease_curve,
+    };
+
+    use slopos_video::framebuffer_tests::{
+        test_bezier_flattening, test_fb_clear_16bpp, test_fb_clear_24bpp_and_uniform,
+        test_fb_clear_clipped_to_pitch_and_buffer, test_fb_clear_fills_visible_pixels,
+        test_fill_gradient_interpolates_rows, test_zero_copy_fallback_vs_retarget,
+        test_zero_copy_scanout_released_on_destroy, test_zero_copy_selection,
     };
 
     use slopos_core::scheduler::context_tests::{
@@ -338,14 +499,24 @@ mod suites {
         test_ext2_read_block_out_of_bounds, test_ext2_read_file_data_roundtrip,
         test_ext2_read_file_not_regular, test_ext2_remove_path_not_file,
         test_ext2_unsupported_block_size, test_ext2_wl_currency_on_error,
-        test_ext2_wl_currency_on_success, test_vfs_file_roundtrip, test_vfs_initialized,
-        test_vfs_list, test_vfs_root_stat, test_vfs_unlink,
+        test_ext2_wl_currency_on_success, test_fileio_backing_device_none,
+        test_fileio_backing_ramfs_matches, test_fileio_getdents_resumes,
+        test_fileio_pipe_roundtrip, test_fileio_pipe_wait_readiness,
+        test_fileio_seek_and_read_tail, test_vfs_file_roundtrip, test_vfs_initialized,
+        test_vfs_list, test_vfs_nested_directories, test_vfs_open_and_readdir_directory,
+        test_vfs_retry_fatal_no_retry, test_vfs_retry_transient_then_ok, test_vfs_root_stat,
+        test_vfs_unlink,
     };
 
     define_test_suite!(
         vm,
         SUITE_SCHEDULER,
-        [test_process_vm_slot_reuse, test_process_vm_counter_reset,]
+        [
+            test_process_vm_slot_reuse,
+            test_process_vm_counter_reset,
+            test_id_alloc_recycles_lowest,
+            test_id_alloc_exhaustion,
+        ]
     );
 
     define_test_suite!(
@@ -382,6 +553,16 @@ mod suites {
         slopos_lib::run_test!(passed, total, test_vfs_file_roundtrip);
         slopos_lib::run_test!(passed, total, test_vfs_list);
         slopos_lib::run_test!(passed, total, test_vfs_unlink);
+        slopos_lib::run_test!(passed, total, test_vfs_nested_directories);
+        slopos_lib::run_test!(passed, total, test_vfs_open_and_readdir_directory);
+        slopos_lib::run_test!(passed, total, test_fileio_getdents_resumes);
+        slopos_lib::run_test!(passed, total, test_fileio_seek_and_read_tail);
+        slopos_lib::run_test!(passed, total, test_fileio_pipe_roundtrip);
+        slopos_lib::run_test!(passed, total, test_fileio_pipe_wait_readiness);
+        slopos_lib::run_test!(passed, total, test_vfs_retry_transient_then_ok);
+        slopos_lib::run_test!(passed, total, test_vfs_retry_fatal_no_retry);
+        slopos_lib::run_test!(passed, total, test_fileio_backing_ramfs_matches);
+        slopos_lib::run_test!(passed, total, test_fileio_backing_device_none);
         slopos_lib::run_test!(passed, total, test_ext2_invalid_superblock_magic);
         slopos_lib::run_test!(passed, total, test_ext2_unsupported_block_size);
         slopos_lib::run_test!(passed, total, test_ext2_directory_format_error);
@@ -437,6 +618,9 @@ mod suites {
             test_page_alloc_stats,
             test_page_alloc_free_null,
             test_page_alloc_fragmentation,
+            test_page_alloc_stats_scattered_free,
+            test_page_alloc_stats_alloc_free_delta,
+            test_page_alloc_stats_largest_run,
         ]
     );
 
@@ -450,6 +634,9 @@ mod suites {
             test_heap_kzalloc_zeroed,
             test_heap_kfree_null,
             test_heap_alloc_zero,
+            test_bump_alloc_bounds,
+            test_early_heap_reuses_freed,
+            test_early_heap_coalesce_and_align,
             test_heap_stats,
         ]
     );
@@ -477,6 +664,12 @@ mod suites {
             test_ring_buffer_wrap,
             test_ring_buffer_reset,
             test_ring_buffer_capacity,
+            test_ring_buffer_iter_partial,
+            test_ring_buffer_iter_wrapped,
+            test_ring_buffer_iter_non_destructive,
+            test_ring_buffer_write_slice_partial,
+            test_ring_buffer_slice_wraparound,
+            test_ring_buffer_overwrite_mode,
         ]
     );
 
@@ -487,6 +680,7 @@ mod suites {
             test_irqmutex_basic,
             test_irqmutex_mutation,
             test_irqmutex_try_lock,
+            test_irqmutex_restores_interrupt_flag,
         ]
     );
 
@@ -502,8 +696,10 @@ mod suites {
             test_shm_invalid_token,
             test_shm_surface_attach,
             test_shm_surface_attach_too_small,
+            test_shm_surface_attach_error_kinds,
             test_shm_surface_attach_overflow,
             test_shm_mapping_overflow,
+            test_shm_reallocate_keeps_old_mapping,
         ]
     );
 
@@ -514,6 +710,9 @@ mod suites {
             test_page_alloc_write_verify,
             test_page_alloc_zero_full_page,
             test_page_alloc_no_stale_data,
+            test_page_alloc_zero_on_free,
+            test_page_alloc_free_keeps_contents,
+            test_page_alloc_zero_on_free_realloc,
             test_heap_boundary_write,
             test_heap_no_overlap,
             test_heap_double_free_defensive,
@@ -530,6 +729,12 @@ mod suites {
             test_process_vm_create_destroy_memory,
             test_process_vm_alloc_and_access,
             test_process_vm_brk_expansion,
+            test_process_vm_set_brk_grow_lazy,
+            test_process_vm_brk_touch_maps_only_touched,
+            test_process_vm_set_brk_shrink_unmaps,
+            test_process_vm_set_brk_rejects_stack,
+            test_process_vm_mmap_munmap,
+            test_process_vm_mmap_large_is_lazy,
             test_cow_page_isolation,
             test_cow_fault_handling,
             test_multiple_process_vms,
@@ -568,6 +773,29 @@ mod suites {
             test_schedule_while_disabled,
             test_many_same_priority_tasks,
             test_interleaved_operations,
+            test_kthread_closure_runs,
+            test_kthread_closure_failure_frees_box,
+            test_exit_code_retrievable_via_wait,
+            test_terminated_task_reports_killed,
+            test_waitpid_reaps_child_once,
+            test_sleep_wakes_after_deadline,
+            test_aging_prevents_low_priority_starvation,
+            test_equal_priority_round_robin,
+            test_task_cpu_time_accumulates,
+            test_yield_hands_off_to_sibling,
+            test_affinity_excludes_cpu,
+            test_affinity_migrates_queued_task,
+            test_affinity_running_task_leaves_cpu,
+            test_transition_running_to_ready,
+            test_transition_terminated_to_running_rejected,
+            test_transition_same_state_rejected,
+            test_work_queue_fifo_drain,
+            test_work_queue_overflow_counts_drops,
+            test_cancel_block_only_before_wake,
+            test_block_unless_pending_returns,
+            test_sched_policy_custom_pick_honored,
+            test_sched_policy_round_robin_default,
+            test_sched_policy_edf_and_fair_pick,
         ]
     );
 
@@ -595,6 +823,16 @@ mod suites {
         ]
     );
 
+    define_test_suite!(
+        user_copy,
+        SUITE_SCHEDULER,
+        [
+            test_user_copy_roundtrip,
+            test_user_copy_unmapped_faults,
+            test_user_copy_spanning_page_boundary,
+        ]
+    );
+
     define_test_suite!(
         oom,
         SUITE_SCHEDULER,
@@ -639,6 +877,11 @@ mod suites {
             test_syscall_lookup_invalid_number,
             test_syscall_lookup_empty_slot,
             test_syscall_lookup_valid,
+            test_syscall_register_and_dispatch,
+            test_syscall_register_duplicate,
+            test_syscall_unregistered_enosys,
+            test_syscall_trace_disabled_silent,
+            test_syscall_trace_enabled_logs_call,
             test_fork_null_parent,
             test_fork_kernel_task,
             test_fork_at_task_limit,
@@ -676,6 +919,14 @@ mod suites {
             test_error_code_preservation,
             test_frame_integrity_patterns,
             test_known_exception_names,
+            test_decode_page_fault_flags,
+            test_decode_gp_selector,
+            test_fatal_dump_page_fault_report,
+            test_fatal_dump_without_stack,
+            test_nonrecoverable_classification,
+            test_nonrecoverable_report_is_minimal,
+            test_symbol_table_resolve,
+            test_stacktrace_resolves_kernel_function,
         ]
     );
     define_test_suite!(
@@ -697,10 +948,18 @@ mod suites {
             test_path_empty,
             test_translate_address_kernel_to_user,
             test_translate_address_user_passthrough,
+            test_translate_address_overflow_rejected,
             test_process_vm_null_page_dir,
             test_elf_huge_segment_count,
             test_elf_phentsize_mismatch,
             test_exec_max_size_boundary,
+            test_exec_args_well_formed,
+            test_exec_args_too_long,
+            test_exec_args_too_many,
+            test_exec_stack_auxv,
+            test_exec_shebang_parse,
+            test_exec_shebang_argv,
+            test_exec_shebang_chain,
         ]
     );
     define_test_suite!(
@@ -726,6 +985,22 @@ mod suites {
             test_irq_timer_ticks_accessible,
             test_irq_keyboard_events_accessible,
             test_irq_vector_calculation,
+            test_mouse_decode_positive_motion,
+            test_mouse_decode_negative_motion,
+            test_mouse_decoder_resyncs,
+            test_mouse_poll_event_drains_queue,
+            test_keyboard_shift_uppercases_letter,
+            test_keyboard_caps_lock_letters_only,
+            test_keyboard_ctrl_and_prefix,
+            test_keyboard_repeat_after_delay,
+            test_keyboard_repeat_cancel_and_modifiers,
+            test_keyboard_repeat_resamples_modifiers,
+            test_keyboard_repeat_timing_from_hz,
+            test_keyboard_layout_de,
+            test_keyboard_layout_us_and_uk,
+            test_keyboard_set_layout,
+            test_keyboard_irq_path_altgr,
+            test_keyboard_layout_syscall_registered,
         ]
     );
     define_test_suite!(
@@ -832,6 +1107,212 @@ mod suites {
         ]
     );
 
+    define_test_suite!(
+        compositor,
+        SUITE_SCHEDULER,
+        [
+            test_buffer_age_unknown_surface,
+            test_buffer_age_first_commit_undefined,
+            test_buffer_age_matches_buffer_count,
+            test_buffer_age_reset_on_reregister,
+            test_commit_swap_exchanges_buffers,
+            test_commit_copy_retains_back,
+            test_commit_swap_and_copy_mixed,
+            test_triple_buffer_quick_commits,
+            test_triple_buffer_swap_rotates,
+            test_triple_buffer_detach_returns_spare,
+            test_surface_resize_preserves_content,
+            test_thumbnail_solid_color,
+            test_thumbnail_preserves_aspect,
+            test_thumbnail_uses_declared_format,
+            test_color_key_composite,
+            test_color_key_cleared_copies_all,
+            test_focus_routes_keyboard_events,
+            test_input_queue_drop_oldest,
+            test_input_queue_drop_newest,
+            test_compose_into_overlap_top_wins,
+            test_compose_blit_clips_and_converts,
+            test_list_windows_batches_and_respects_max,
+            test_list_windows_copies_to_user,
+            test_blend_alpha_over_window,
+            test_blend_opaque_ignores_alpha,
+            test_blend_mode_occlusion,
+            test_headless_surfaces_enumerable,
+            test_headless_present_no_framebuffer,
+            test_damage_least_waste_merges_adjacent,
+            test_damage_smallest_area_merges_distant,
+            test_damage_add_coalesces_touching,
+            test_title_unterminated_slot_truncated,
+            test_title_long_input_truncated,
+            test_title_embedded_nul_rejected,
+            test_format_rgb888_onto_argb8888,
+            test_frame_batches_damage_into_one_compose,
+            test_frame_unmatched_begin_end,
+            test_frame_done_set_by_present,
+            test_frame_done_skips_minimized,
+            test_frame_callback_fires_while_minimized,
+            test_format_argb8888_onto_rgb888_keyed,
+            test_format_declared_in_window_info,
+            test_color_key_16bpp_row,
+            test_overlay_on_top_of_windows,
+            test_overlay_move_restores_pixels,
+            test_overlay_cursor_moves_and_damage,
+            test_overlay_clear_restores_content,
+            test_capture_converts_to_bgra,
+            test_capture_validates_buffer,
+        ]
+    );
+
+    define_test_suite!(
+        framebuffer,
+        SUITE_SCHEDULER,
+        [
+            test_zero_copy_selection,
+            test_zero_copy_fallback_vs_retarget,
+            test_zero_copy_scanout_released_on_destroy,
+            test_fb_clear_fills_visible_pixels,
+            test_fb_clear_24bpp_and_uniform,
+            test_fb_clear_clipped_to_pitch_and_buffer,
+            test_fb_clear_16bpp,
+            test_fill_gradient_interpolates_rows,
+            test_bezier_flattening,
+        ]
+    );
+
+    define_test_suite!(
+        roulette,
+        SUITE_SCHEDULER,
+        [
+            test_roulette_ease_curve,
+            test_roulette_anim_decelerates,
+            test_roulette_anim_lands_on_target,
+            test_roulette_anim_clamps_past_end,
+        ]
+    );
+
+    define_test_suite!(
+        tty,
+        SUITE_SCHEDULER,
+        [
+            test_tty_console_default_framebuffer,
+            test_tty_console_cmdline_serial,
+            test_tty_console_no_framebuffer,
+            test_tty_console_set_roundtrip,
+            test_serial_rx_fifo_order,
+            test_serial_rx_drops_line_errors,
+            test_serial_read_line_edits_and_truncates,
+            test_tty_canonical_backspace_erases,
+            test_tty_canonical_waits_for_enter,
+            test_tty_canonical_ctrl_c_discards_line,
+            test_tty_mode_roundtrip,
+            test_tty_mode_needs_focus,
+            test_tty_scrollback_page_up,
+            test_tty_scrollback_snap_on_output,
+            test_tty_scrollback_not_full_and_wrapped,
+        ]
+    );
+
+    define_test_suite!(
+        random,
+        SUITE_SCHEDULER,
+        [
+            test_random_stats_all_zero,
+            test_random_stats_all_one,
+            test_random_stats_balanced,
+            test_random_constant_pattern_flagged,
+            test_random_lfsr_output_sane,
+            test_random_next_output_sane,
+            test_random_bytes_kib_varies,
+            test_random_hw_rdrand_delivers,
+        ]
+    );
+
+    define_test_suite!(
+        line_history,
+        SUITE_SCHEDULER,
+        [
+            test_history_navigate_back_and_forth,
+            test_history_collapses_consecutive_duplicates,
+            test_history_ring_drops_oldest,
+        ]
+    );
+
+    define_test_suite!(
+        abi_layout,
+        SUITE_SCHEDULER,
+        [
+            test_abi_struct_layouts_stable,
+            test_abi_layout_guard_detects_change,
+        ]
+    );
+
+    define_test_suite!(
+        font_layout,
+        SUITE_SCHEDULER,
+        [
+            test_mixed_sizes_share_baseline,
+            test_line_advance_uses_tallest_glyph,
+            test_scaled_glyph_blocks_match_normal,
+            test_latin1_and_box_glyphs,
+        ]
+    );
+
+    define_test_suite!(
+        rtc,
+        SUITE_SCHEDULER,
+        [
+            test_rtc_bcd_to_bin,
+            test_rtc_bcd_invalid_clamped,
+            test_rtc_decode_bcd_24h,
+            test_rtc_decode_binary_12h,
+            test_rtc_decode_bcd_to_unix,
+            test_rtc_missing_century_assumes_2000s,
+            test_rtc_fadt_century_register,
+            test_rtc_wall_time_interpolates_tsc,
+            test_rtc_wall_time_keeps_advancing,
+            test_rtc_read_in_range,
+        ]
+    );
+
+    define_test_suite!(
+        clock,
+        SUITE_SCHEDULER,
+        [
+            test_clock_cycles_to_ns_known_freq,
+            test_clock_unknown_freq_falls_back_to_ticks,
+            test_clock_monotonic_never_decreases,
+        ]
+    );
+
+    define_test_suite!(
+        hpet,
+        SUITE_SCHEDULER,
+        [
+            test_hpet_period_validation,
+            test_hpet_counter_wrap,
+            test_hpet_tsc_hz_conversion,
+            test_hpet_tsc_calibration_plausible,
+        ]
+    );
+
+    define_test_suite!(
+        pci,
+        SUITE_SCHEDULER,
+        [
+            test_pci_scan_reports_present_devices,
+            test_pci_scan_multifunction,
+            test_pci_scan_stops_at_capacity,
+            test_pci_bar_sizing_disables_decode,
+            test_msi_capability_found_in_chain,
+            test_msi_no_capability_list,
+            test_msi_capability_loop_terminates,
+            test_msi_message_targets_apic,
+            test_msi_program_32bit,
+            test_msi_program_64bit,
+            test_msi_program_rejects_capability_at_end,
+        ]
+    );
+
     // FPU/SSE suite requires custom implementation due to inline assembly
     const FPU_NAME: &[u8] = b"fpu_sse\0";
 
@@ -928,6 +1409,7 @@ mod suites {
             PROCESS_VM_SUITE_DESC,
             SCHED_CORE_SUITE_DESC,
             DEMAND_PAGING_SUITE_DESC,
+            USER_COPY_SUITE_DESC,
             OOM_SUITE_DESC,
             COW_EDGE_SUITE_DESC,
             SYSCALL_VALID_SUITE_DESC,
@@ -938,6 +1420,18 @@ mod suites {
             CONTEXT_SUITE_DESC,
             TLB_SUITE_DESC,
             MMIO_SUITE_DESC,
+            COMPOSITOR_SUITE_DESC,
+            FRAMEBUFFER_SUITE_DESC,
+            TTY_SUITE_DESC,
+            RANDOM_SUITE_DESC,
+            RTC_SUITE_DESC,
+            CLOCK_SUITE_DESC,
+            HPET_SUITE_DESC,
+            PCI_SUITE_DESC,
+            LINE_HISTORY_SUITE_DESC,
+            FONT_LAYOUT_SUITE_DESC,
+            ABI_LAYOUT_SUITE_DESC,
+            ROULETTE_SUITE_DESC,
         );
     }
 }
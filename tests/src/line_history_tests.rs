@@ -0,0 +1,95 @@
+use core::ffi::c_int;
+
+use slopos_abi::line_history::{LINE_HISTORY_ENTRIES, LineHistory};
+use slopos_lib::klog_info;
+use spin::Mutex;
+
+// Too large for a test stack frame
+static HISTORY: Mutex<LineHistory> = Mutex::new(LineHistory::new());
+
+fn fresh_history() -> spin::MutexGuard<'static, LineHistory> {
+    let mut h = HISTORY.lock();
+    *h = LineHistory::new();
+    h
+}
+
+pub fn test_history_navigate_back_and_forth() -> c_int {
+    let mut h = fresh_history();
+    for line in [&b"ls"[..], b"cat a", b"echo hi"] {
+        h.push(line);
+    }
+
+    let back: [Option<&[u8]>; 4] = [Some(b"echo hi"), Some(b"cat a"), Some(b"ls"), Some(b"ls")];
+    for (step, want) in back.iter().enumerate() {
+        if h.older() != *want {
+            klog_info!("HISTORY_TEST: BUG - up step {} returned wrong line", step);
+            return -1;
+        }
+    }
+
+    let forward: [Option<&[u8]>; 3] = [Some(b"cat a"), Some(b"echo hi"), None];
+    for (step, want) in forward.iter().enumerate() {
+        if h.newer() != *want {
+            klog_info!("HISTORY_TEST: BUG - down step {} returned wrong line", step);
+            return -1;
+        }
+    }
+
+    // Submitting a line restarts browsing from the newest entry
+    h.older();
+    h.push(b"pwd");
+    if h.older() != Some(&b"pwd"[..]) {
+        klog_info!("HISTORY_TEST: BUG - push did not reset browse position");
+        return -1;
+    }
+    0
+}
+
+pub fn test_history_collapses_consecutive_duplicates() -> c_int {
+    let mut h = fresh_history();
+    let added = [
+        h.push(b"ls"),
+        h.push(b"ls"),
+        h.push(b"cd /"),
+        h.push(b"ls"),
+        h.push(b"  "),
+    ];
+    if added != [true, false, true, true, false] || h.len() != 3 {
+        klog_info!(
+            "HISTORY_TEST: BUG - duplicate/blank lines stored (len {})",
+            h.len()
+        );
+        return -1;
+    }
+    if h.older() != Some(&b"ls"[..]) || h.older() != Some(&b"cd /"[..]) {
+        klog_info!("HISTORY_TEST: BUG - non-consecutive duplicate lost");
+        return -1;
+    }
+    0
+}
+
+pub fn test_history_ring_drops_oldest() -> c_int {
+    let mut h = fresh_history();
+    if h.older().is_some() || h.newer().is_some() {
+        klog_info!("HISTORY_TEST: BUG - empty history returned a line");
+        return -1;
+    }
+    for i in 0..(LINE_HISTORY_ENTRIES + 2) {
+        let line = [b'a' + (i % 26) as u8, b'0' + (i / 26) as u8];
+        h.push(&line);
+    }
+    if h.len() != LINE_HISTORY_ENTRIES {
+        klog_info!("HISTORY_TEST: BUG - ring holds {} lines", h.len());
+        return -1;
+    }
+    let mut oldest = None;
+    for _ in 0..(LINE_HISTORY_ENTRIES + 4) {
+        oldest = h.older().map(|l| [l[0], l[1]]);
+    }
+    // Lines 0 and 1 were overwritten, so line 2 is the oldest left
+    if oldest != Some([b'c', b'0']) {
+        klog_info!("HISTORY_TEST: BUG - oldest entry not evicted");
+        return -1;
+    }
+    0
+}
@@ -4,18 +4,26 @@ use core::ptr;
 use slopos_boot::early_init::{BootInitStep, boot_init_priority};
 use slopos_core::syscall::register_spawn_task_callback;
 use slopos_core::{
-    INVALID_TASK_ID, TASK_FLAG_COMPOSITOR, TASK_FLAG_DISPLAY_EXCLUSIVE, TASK_STATE_BLOCKED,
-    TASK_STATE_RUNNING, Task, TaskEntry, schedule_task, task_get_info, task_set_state,
-    task_terminate,
+    INVALID_TASK_ID, TASK_FLAG_COMPOSITOR, TASK_FLAG_DISPLAY_EXCLUSIVE, TASK_FLAG_SYSTEM,
+    TASK_STATE_BLOCKED, TASK_STATE_RUNNING, Task, TaskEntry, schedule_task, task_get_info,
+    task_set_state, task_terminate,
 };
-use slopos_lib::klog_info;
+use slopos_lib::testing::suite_masks::SUITE_SCHEDULER;
+use slopos_lib::{define_test_suite, klog_info_mod};
 use slopos_mm::process_vm::process_vm_load_elf;
+use slopos_tests::tests_add_external_suite;
 
+use crate::gfx::draw_buffer_tests::{
+    test_blit_clips_both_buffers, test_draw_buffer_blit_from_clips_and_converts,
+    test_draw_buffer_draw_line_clips_and_damages, test_draw_buffer_fill_rect_clips_and_damages,
+    test_draw_buffer_rgb565_pixels, test_fill_rect_clips_to_bounds,
+    test_line_points_slopes_and_offscreen, test_rgb565_pack_and_decode,
+};
 use crate::loader::user_spawn_program_with_flags;
 
 #[unsafe(link_section = ".user_text")]
 fn log_info(msg: &str) {
-    klog_info!("{msg}");
+    klog_info_mod!("userland", "{msg}");
 }
 
 #[unsafe(link_section = ".user_text")]
@@ -134,7 +142,7 @@ fn block_task_on(task_id: u32, task_info: *mut Task, wait_on: u32) -> i32 {
 fn boot_step_userland_preinit() -> i32 {
     register_spawn_task_callback(spawn_task_by_name);
 
-    let shell_id = userland_spawn_with_flags(b"shell\0", 5, 0);
+    let shell_id = userland_spawn_with_flags(b"shell\0", 5, TASK_FLAG_SYSTEM);
     if shell_id <= 0 {
         log_info("USERLAND: Failed to create shell init task\n");
         return -1;
@@ -209,6 +217,34 @@ fn boot_step_userland_preinit() -> i32 {
     0
 }
 
+define_test_suite!(
+    draw_buffer,
+    SUITE_SCHEDULER,
+    [
+        test_rgb565_pack_and_decode,
+        test_draw_buffer_rgb565_pixels,
+        test_fill_rect_clips_to_bounds,
+        test_draw_buffer_fill_rect_clips_and_damages,
+        test_blit_clips_both_buffers,
+        test_draw_buffer_blit_from_clips_and_converts,
+        test_line_points_slopes_and_offscreen,
+        test_draw_buffer_draw_line_clips_and_damages,
+    ]
+);
+
+/// Hand the userland suites to the harness before it runs.
+fn boot_step_userland_tests() -> i32 {
+    tests_add_external_suite(&DRAW_BUFFER_SUITE_DESC)
+}
+
+#[used]
+#[unsafe(link_section = ".boot_init_drivers")]
+static BOOT_STEP_USERLAND_TESTS: BootInitStep = BootInitStep::new(
+    b"userland tests\0",
+    boot_step_userland_tests,
+    boot_init_priority(85),
+);
+
 #[used]
 #[unsafe(link_section = ".boot_init_services")]
 static BOOT_STEP_USERLAND_HOOK: BootInitStep = BootInitStep::new(
@@ -13,6 +13,8 @@
 
 use core::ffi::c_void;
 
+use slopos_abi::{CompositeSource, CompositeTarget, LayerTarget, OverlayLayer};
+
 use crate::gfx::{self, DamageRect, DamageTracker, DrawBuffer, DrawTarget, PixelFormat, rgb};
 use crate::syscall::{
     CachedShmMapping, DisplayInfo, ShmBuffer, UserWindowInfo, sys_drain_queue,
@@ -299,8 +301,8 @@ struct WindowManager {
     // Client surface cache for shared memory mappings
     surface_cache: ClientSurfaceCache,
     // Output buffer info for compositing
-    output_bytes_pp: u8,
     output_pitch: usize,
+    output_format: slopos_abi::PixelFormat,
     // Output damage accumulator for partial redraw
     output_damage: DamageTracker,
     // Previous frame's window bounds (for expose damage calculation)
@@ -308,6 +310,8 @@ struct WindowManager {
     // Cursor positions visited this frame (for trail-free damage)
     cursor_trail: [(i32, i32); MAX_CURSOR_TRAIL],
     cursor_trail_count: usize,
+    // Topmost layer; currently carries the mouse cursor
+    overlay: OverlayLayer,
 }
 
 impl WindowManager {
@@ -345,18 +349,19 @@ impl WindowManager {
             taskbar_needs_redraw: true,
             needs_full_redraw: true,
             surface_cache: ClientSurfaceCache::new(),
-            output_bytes_pp: 4,
             output_pitch: 0,
+            output_format: slopos_abi::PixelFormat::Argb8888,
             output_damage: DamageTracker::new(),
             prev_window_bounds: [WindowBounds::default(); MAX_WINDOWS],
             cursor_trail: [(0, 0); MAX_CURSOR_TRAIL],
             cursor_trail_count: 0,
+            overlay: OverlayLayer::new(),
         }
     }
 
-    fn set_output_info(&mut self, bytes_pp: u8, pitch: usize) {
-        self.output_bytes_pp = bytes_pp;
+    fn set_output_info(&mut self, pitch: usize, format: slopos_abi::PixelFormat) {
         self.output_pitch = pitch;
+        self.output_format = format;
     }
 
     /// Update mouse state from kernel.
@@ -922,24 +927,95 @@ impl WindowManager {
         }
     }
 
-    /// Draw mouse cursor to the output buffer
-    fn draw_cursor(&self, buf: &mut DrawBuffer) {
-        // Simple crosshair cursor
-        let mx = self.mouse_x;
-        let my = self.mouse_y;
+    /// Whether this frame only needs the cursor moved: nothing else changed
+    /// and neither the old nor the new cursor spot touches the taskbar, whose
+    /// buttons highlight under the pointer.
+    fn cursor_only_frame(&self, fb_height: i32) -> bool {
+        if !self.mouse_moved()
+            || !self.overlay.is_active()
+            || self.first_frame
+            || self.needs_full_redraw
+            || self.taskbar_needs_redraw
+            || self.dragging
+            || self.output_damage.is_dirty()
+            || self.any_window_dirty()
+        {
+            return false;
+        }
+        let taskbar_y = fb_height - TASKBAR_HEIGHT;
+        let trail = &self.cursor_trail[..self.cursor_trail_count];
+        trail
+            .iter()
+            .chain(core::iter::once(&(self.mouse_x, self.mouse_y)))
+            .all(|&(_, y)| y + CURSOR_SIZE / 2 < taskbar_y)
+    }
 
-        // Horizontal line
-        gfx::fill_rect(buf, mx - 4, my, CURSOR_SIZE, 1, COLOR_CURSOR);
+    /// Move the cursor without repainting: the overlay puts back the pixels
+    /// it covered and draws itself at the new spot. Both spots are damaged.
+    fn move_cursor(&mut self, buf: &mut DrawBuffer) {
+        let x = self.mouse_x - CURSOR_SIZE / 2;
+        let y = self.mouse_y - CURSOR_SIZE / 2;
+        let damage = self.overlay.move_and_damage(&mut layer_target(buf), x, y);
+        for rect in damage.into_iter().flatten() {
+            buf.add_damage(rect.x0, rect.y0, rect.x1, rect.y1);
+        }
+    }
 
-        // Vertical line
-        gfx::fill_rect(buf, mx, my - 4, 1, CURSOR_SIZE, COLOR_CURSOR);
+    /// Draw mouse cursor to the output buffer via the overlay layer
+    fn draw_cursor(&mut self, buf: &mut DrawBuffer) {
+        let x = self.mouse_x - CURSOR_SIZE / 2;
+        let y = self.mouse_y - CURSOR_SIZE / 2;
+        let cursor = buf.pixel_format().convert_color(COLOR_CURSOR);
+
+        // Where the cursor was this frame needs repairing too
+        for &(old_x, old_y) in &self.cursor_trail[..self.cursor_trail_count] {
+            let (old_x, old_y) = (old_x - CURSOR_SIZE / 2, old_y - CURSOR_SIZE / 2);
+            buf.add_damage(
+                old_x,
+                old_y,
+                old_x + CURSOR_SIZE - 1,
+                old_y + CURSOR_SIZE - 1,
+            );
+        }
+
+        let mut target = layer_target(buf);
+
+        if self.overlay.is_active() {
+            self.overlay.composite_at(&mut target, x, y);
+        } else {
+            // Simple crosshair cursor; everything else is keyed out
+            const SIZE: usize = CURSOR_SIZE as usize;
+            let transparent = !cursor;
+            let mut pixels = [transparent; SIZE * SIZE];
+            for i in 0..SIZE {
+                pixels[(SIZE / 2) * SIZE + i] = cursor;
+                pixels[i * SIZE + SIZE / 2] = cursor;
+            }
+            self.overlay.set_color_key(Some(transparent));
+            self.overlay.set_overlay(
+                &mut target,
+                &pixels,
+                x,
+                y,
+                CURSOR_SIZE as u32,
+                CURSOR_SIZE as u32,
+            );
+        }
+
+        buf.add_damage(x, y, x + CURSOR_SIZE - 1, y + CURSOR_SIZE - 1);
     }
 
     /// Draw window content from client's shared memory surface (100% safe)
     fn draw_window_content(&mut self, buf: &mut DrawBuffer, window: &UserWindowInfo) {
+        // Surfaces in a format we can't convert are skipped, not the whole frame
+        let Some(src_format) = window.pixel_format(self.output_format) else {
+            self.draw_window_placeholder(buf, window);
+            return;
+        };
+
         // Calculate buffer size for this surface
-        let bytes_pp = self.output_bytes_pp as usize;
-        let src_pitch = (window.width as usize) * bytes_pp;
+        let src_bpp = src_format.bytes_per_pixel() as usize;
+        let src_pitch = (window.width as usize) * src_bpp;
         let buffer_size = src_pitch * (window.height as usize);
 
         // Try to get or create a cached mapping for the client's surface
@@ -965,44 +1041,25 @@ impl WindowManager {
             }
         };
 
-        let dst_pitch = self.output_pitch;
-        let buf_width = buf.width() as i32;
-        let buf_height = buf.height() as i32;
-
-        // Clip to buffer bounds
-        let x0 = window.x.max(0);
-        let y0 = window.y.max(0);
-        let x1 = (window.x + window.width as i32).min(buf_width);
-        let y1 = (window.y + window.height as i32).min(buf_height);
-
-        if x0 >= x1 || y0 >= y1 {
-            return;
-        }
-
-        // Calculate offsets into source buffer
-        let src_start_x = (x0 - window.x) as usize;
-        let src_start_y = (y0 - window.y) as usize;
-
-        // Get destination buffer data
-        let dst_data = buf.data_mut();
-
-        // Copy each row from client surface to output buffer (100% safe - slice ops only)
-        for row in 0..(y1 - y0) as usize {
-            let src_row = src_start_y + row;
-            let dst_row = (y0 as usize) + row;
-
-            let src_off = src_row * src_pitch + src_start_x * bytes_pp;
-            let dst_off = dst_row * dst_pitch + (x0 as usize) * bytes_pp;
-            let copy_width = ((x1 - x0) as usize) * bytes_pp;
-
-            // Safe slice operations with bounds checking
-            let src_end = src_off + copy_width;
-            let dst_end = dst_off + copy_width;
-
-            if src_end <= src_data.len() && dst_end <= dst_data.len() {
-                dst_data[dst_off..dst_end].copy_from_slice(&src_data[src_off..src_end]);
-            }
-        }
+        let (width, height) = (buf.width(), buf.height());
+        let mut target = CompositeTarget {
+            data: buf.data_mut(),
+            width,
+            height,
+            pitch: self.output_pitch,
+            format: self.output_format,
+        };
+        // Clipped and bounds-checked per row (100% safe - slice ops only)
+        target.blit(&CompositeSource {
+            data: src_data,
+            format: src_format,
+            x: window.x,
+            y: window.y,
+            width: window.width,
+            height: window.height,
+            color_key: window.color_key(),
+            blend_mode: window.blend_mode(),
+        });
     }
 
     /// Render a simple placeholder for a window's content area when the client's surface is not available.
@@ -1137,6 +1194,19 @@ impl WindowManager {
     }
 }
 
+/// View a draw buffer as an overlay target
+fn layer_target<'b>(buf: &'b mut DrawBuffer) -> LayerTarget<'b> {
+    let (width, height, pitch) = (buf.width(), buf.height(), buf.pitch());
+    let bytes_pp = buf.bytes_pp() as usize;
+    LayerTarget {
+        data: buf.data_mut(),
+        width,
+        height,
+        pitch,
+        bytes_pp,
+    }
+}
+
 /// Convert UTF-8 title array to &str (now 100% safe - no unsafe needed)
 ///
 /// With the ABI change to use `[u8; 32]` instead of `[c_char; 32]`,
@@ -1171,7 +1241,7 @@ pub fn compositor_user_main(_arg: *mut c_void) {
         },
     };
 
-    wm.set_output_info(output.bytes_pp, output.pitch);
+    wm.set_output_info(output.pitch, fb_info.format);
 
     let pixel_format = if fb_info.format.is_bgr_order() {
         PixelFormat::Bgra
@@ -1193,7 +1263,11 @@ pub fn compositor_user_main(_arg: *mut c_void) {
         if wm.needs_redraw() {
             if let Some(mut buf) = output.draw_buffer() {
                 buf.set_pixel_format(pixel_format);
-                wm.render(&mut buf);
+                if wm.cursor_only_frame(fb_info.height as i32) {
+                    wm.move_cursor(&mut buf);
+                } else {
+                    wm.render(&mut buf);
+                }
             }
 
             output.present();
@@ -0,0 +1,352 @@
+//! DrawBuffer tests - pixel formats, clipping and damage.
+
+use slopos_abi::damage::DamageRect;
+use slopos_abi::pixel::DrawPixelFormat;
+use slopos_abi::{draw_primitives, pixel_ops};
+use slopos_lib::testing::TestResult;
+use slopos_lib::{assert_eq_test, assert_test};
+
+use super::{DrawBuffer, DrawTarget};
+
+const GUARD: u8 = 0xA5;
+
+pub fn test_rgb565_pack_and_decode() -> TestResult {
+    let fmt = DrawPixelFormat::from_bpp(16);
+    assert_eq_test!(fmt, DrawPixelFormat::Rgb565);
+    assert_eq_test!(fmt.convert_color(0xFFFF_0000), 0xF800, "red");
+    assert_eq_test!(fmt.convert_color(0xFF00_FF00), 0x07E0, "green");
+    assert_eq_test!(fmt.convert_color(0xFF00_00FF), 0x001F, "blue");
+    assert_eq_test!(fmt.convert_color(0x00FF_FFFF), 0xFFFF, "alpha dropped");
+    assert_eq_test!(fmt.convert_color(0xFF07_0307), 0, "low bits truncated");
+
+    // Values representable in 5-6-5 survive a round trip unchanged
+    for color in [0xFFFF_FFFF, 0xFF00_0000, 0xFF84_8284, 0xFF21_0C29] {
+        assert_eq_test!(fmt.decode_color(fmt.convert_color(color)), color);
+    }
+    TestResult::Pass
+}
+
+pub fn test_draw_buffer_rgb565_pixels() -> TestResult {
+    // 3x2 pixels of 2 bytes in 8-byte rows; the last 2 bytes are padding
+    let mut data = [GUARD; 8 * 2];
+    let Some(mut buf) = DrawBuffer::new(&mut data, 3, 2, 8, 2) else {
+        return TestResult::Fail;
+    };
+    assert_eq_test!(buf.pixel_format(), DrawPixelFormat::Rgb565);
+
+    buf.set_pixel(1, 1, 0xFFFF_0000);
+    assert_eq_test!(buf.get_pixel(1, 1), 0xFFFF_0000, "red reads back");
+    buf.set_pixel(3, 0, 0xFFFF_FFFF);
+    buf.set_pixel(-1, 1, 0xFFFF_FFFF);
+    assert_test!(
+        buf.data()[6..8] == [GUARD; 2] && buf.data()[8..10] == [GUARD; 2],
+        "off-buffer pixels dropped"
+    );
+    assert_eq_test!(
+        &buf.data()[8 + 2..8 + 4],
+        &[0x00, 0xF8][..],
+        "two bytes per pixel"
+    );
+
+    buf.clear(0xFF00_00FF);
+    assert_eq_test!(
+        buf.get_pixel(2, 1),
+        0xFF00_00FF,
+        "clear reaches the last pixel"
+    );
+    for row in buf.data().chunks_exact(8) {
+        assert_test!(
+            row[..6].chunks_exact(2).all(|px| px == [0x1F, 0x00]),
+            "cleared to blue"
+        );
+    }
+    assert_test!(
+        DrawBuffer::new(&mut [0u8; 16], 3, 2, 8, 1).is_none(),
+        "8bpp rejected"
+    );
+    TestResult::Pass
+}
+
+pub fn test_fill_rect_clips_to_bounds() -> TestResult {
+    let clip = |x, y, w, h| pixel_ops::clip_rect(x, y, w, h, 10, 8);
+    assert_eq_test!(clip(2, 3, 4, 2), Some((2, 3, 5, 4)), "fully inside");
+    assert_eq_test!(clip(-3, -2, 5, 4), Some((0, 0, 1, 1)), "negative origin");
+    assert_eq_test!(clip(7, 6, 10, 10), Some((7, 6, 9, 7)), "off right/bottom");
+    assert_eq_test!(clip(-5, -5, 30, 30), Some((0, 0, 9, 7)), "covers buffer");
+    assert_eq_test!(clip(0, 0, i32::MAX, 1), Some((0, 0, 9, 0)), "huge width");
+    assert_eq_test!(clip(10, 0, 4, 4), None, "right of buffer");
+    assert_eq_test!(clip(-4, 0, 4, 4), None, "ends left of buffer");
+    assert_eq_test!(clip(3, 3, 0, 4), None, "zero width");
+    assert_eq_test!(clip(3, 3, 4, -1), None, "negative height");
+    TestResult::Pass
+}
+
+/// ARGB pixel at (x, y) of a 4-byte-per-pixel buffer.
+fn argb_at(data: &[u8], pitch: usize, x: usize, y: usize) -> u32 {
+    let off = y * pitch + x * 4;
+    u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]])
+}
+
+pub fn test_draw_buffer_fill_rect_clips_and_damages() -> TestResult {
+    const W: usize = 6;
+    const H: usize = 5;
+    const FILL: u32 = 0xFF12_3456;
+    let mut data = [0u8; W * 4 * H];
+    let Some(mut buf) = DrawBuffer::new(&mut data, W as u32, H as u32, W * 4, 4) else {
+        return TestResult::Fail;
+    };
+    buf.set_pixel_format(DrawPixelFormat::Bgra);
+
+    // Hangs off the top-left corner: only (0..2, 0..3) is visible
+    buf.fill_rect(-3, -1, 5, 4, FILL);
+    let regions = buf.damage().regions();
+    assert_eq_test!(regions.len(), 1, "one damage rect");
+    assert_eq_test!(
+        regions[0],
+        DamageRect {
+            x0: 0,
+            y0: 0,
+            x1: 1,
+            y1: 2
+        },
+        "damage is the clipped area"
+    );
+    for y in 0..H {
+        for x in 0..W {
+            let expected = if x < 2 && y < 3 { FILL } else { 0 };
+            assert_eq_test!(argb_at(buf.data(), W * 4, x, y), expected, "filled area");
+        }
+    }
+
+    buf.clear_damage();
+    buf.fill_rect(W as i32, 0, 4, 4, FILL);
+    buf.fill_rect(1, 1, 0, 3, FILL);
+    assert_test!(!buf.damage().is_dirty(), "invisible fills add no damage");
+    TestResult::Pass
+}
+
+pub fn test_blit_clips_both_buffers() -> TestResult {
+    use pixel_ops::{BlitRect, clip_blit};
+    let blit = |src_x, src_y, dst_x, dst_y, width, height| BlitRect {
+        src_x,
+        src_y,
+        dst_x,
+        dst_y,
+        width,
+        height,
+    };
+    // 8x8 source into a 4x6 destination
+    let clip = |r| clip_blit(r, 8, 8, 4, 6);
+
+    assert_eq_test!(clip(blit(1, 1, 0, 0, 3, 3)), Some(blit(1, 1, 0, 0, 3, 3)));
+    assert_eq_test!(
+        clip(blit(0, 0, -2, -1, 4, 4)),
+        Some(blit(2, 1, 0, 0, 2, 3)),
+        "negative destination trims source too"
+    );
+    assert_eq_test!(
+        clip(blit(-3, 2, 0, 1, 5, 2)),
+        Some(blit(0, 2, 3, 1, 1, 2)),
+        "negative source moves destination"
+    );
+    assert_eq_test!(
+        clip(blit(6, 0, 0, 0, 4, 8)),
+        Some(blit(6, 0, 0, 0, 2, 6)),
+        "limited by source width and destination height"
+    );
+    assert_eq_test!(clip(blit(0, 0, 4, 0, 2, 2)), None, "off destination");
+    assert_eq_test!(clip(blit(8, 0, 0, 0, 2, 2)), None, "off source");
+    assert_eq_test!(clip(blit(0, 0, 0, 0, 0, 5)), None, "zero width");
+    assert_eq_test!(
+        clip(blit(0, 0, i32::MIN, 0, i32::MAX, 1)),
+        None,
+        "no overflow"
+    );
+    TestResult::Pass
+}
+
+pub fn test_draw_buffer_blit_from_clips_and_converts() -> TestResult {
+    const RED: u32 = 0xFFFF_0000;
+    const GREY: u32 = 0xFF84_8284;
+    let mut src_data = [0u8; 4 * 4 * 4];
+    let Some(mut src) = DrawBuffer::new(&mut src_data, 4, 4, 16, 4) else {
+        return TestResult::Fail;
+    };
+    src.set_pixel_format(DrawPixelFormat::Bgra);
+    for y in 0..4 {
+        for x in 0..4 {
+            src.set_pixel(x, y, 0xFF00_0000 | ((y as u32) << 8) | x as u32);
+        }
+    }
+
+    // Same format: rows copied, clipped on the left of the destination
+    let mut dst_data = [0u8; 3 * 4 * 3];
+    let Some(mut dst) = DrawBuffer::new(&mut dst_data, 3, 3, 12, 4) else {
+        return TestResult::Fail;
+    };
+    dst.set_pixel_format(DrawPixelFormat::Bgra);
+    dst.blit_from(&src, 1, 1, -1, 0, 3, 3);
+    for y in 0..3 {
+        for x in 0..3 {
+            let expected = if x < 2 {
+                0xFF00_0000 | ((y as u32 + 1) << 8) | (x as u32 + 2)
+            } else {
+                0
+            };
+            assert_eq_test!(dst.get_pixel(x, y), expected, "copied pixels");
+        }
+    }
+    assert_eq_test!(
+        dst.damage().regions(),
+        &[DamageRect {
+            x0: 0,
+            y0: 0,
+            x1: 1,
+            y1: 2
+        }][..],
+        "damage is the clipped destination"
+    );
+
+    // Different format: each pixel converted to 5-6-5
+    src.fill_rect(0, 0, 4, 4, GREY);
+    src.set_pixel(1, 0, RED);
+    let mut small = [0u8; 2 * 2 * 2];
+    let Some(mut dst) = DrawBuffer::new(&mut small, 2, 2, 4, 2) else {
+        return TestResult::Fail;
+    };
+    dst.blit_from(&src, 0, 0, 0, 0, 4, 4);
+    assert_eq_test!(dst.get_pixel(1, 0), RED, "red converted");
+    assert_eq_test!(dst.get_pixel(0, 1), GREY, "grey converted");
+    assert_eq_test!(dst.damage().regions().len(), 1);
+
+    dst.clear_damage();
+    dst.blit_from(&src, 4, 0, 0, 0, 2, 2);
+    assert_test!(!dst.damage().is_dirty(), "empty blit adds no damage");
+    TestResult::Pass
+}
+
+#[derive(Debug, PartialEq)]
+struct LineTrace {
+    first: (i32, i32),
+    last: (i32, i32),
+    count: i32,
+    visible: i32,
+    unit_steps: bool,
+}
+
+/// Walk a line and summarize it, counting points inside a 10x8 buffer.
+fn trace_line(x0: i32, y0: i32, x1: i32, y1: i32) -> LineTrace {
+    let mut trace = LineTrace {
+        first: (x0, y0),
+        last: (x0, y0),
+        count: 0,
+        visible: 0,
+        unit_steps: true,
+    };
+    draw_primitives::line_points(x0, y0, x1, y1, |x, y| {
+        if trace.count > 0 {
+            let (px, py) = trace.last;
+            let (sx, sy) = ((x - px).abs(), (y - py).abs());
+            if sx > 1 || sy > 1 || sx + sy == 0 {
+                trace.unit_steps = false;
+            }
+        } else {
+            trace.first = (x, y);
+        }
+        trace.last = (x, y);
+        trace.count += 1;
+        if (0..10).contains(&x) && (0..8).contains(&y) {
+            trace.visible += 1;
+        }
+    });
+    trace
+}
+
+pub fn test_line_points_slopes_and_offscreen() -> TestResult {
+    let line = |first, last, count, visible| LineTrace {
+        first,
+        last,
+        count,
+        visible,
+        unit_steps: true,
+    };
+    assert_eq_test!(
+        trace_line(0, 0, 4, 2),
+        line((0, 0), (4, 2), 5, 5),
+        "shallow"
+    );
+    assert_eq_test!(trace_line(1, 0, 3, 6), line((1, 0), (3, 6), 7, 7), "steep");
+    assert_eq_test!(
+        trace_line(6, 7, 2, 1),
+        line((6, 7), (2, 1), 7, 7),
+        "steep, drawn backwards"
+    );
+    assert_eq_test!(trace_line(3, 3, 3, 3), line((3, 3), (3, 3), 1, 1), "point");
+    assert_eq_test!(
+        trace_line(-5, 2, 14, 2),
+        line((-5, 2), (14, 2), 20, 10),
+        "horizontal past both edges"
+    );
+    assert_eq_test!(
+        trace_line(4, -6, 4, 20),
+        line((4, -6), (4, 20), 27, 8),
+        "vertical past both edges"
+    );
+    assert_eq_test!(
+        trace_line(i32::MAX - 2, 0, i32::MAX, 1),
+        line((i32::MAX - 2, 0), (i32::MAX, 1), 3, 0),
+        "no overflow near i32::MAX"
+    );
+    TestResult::Pass
+}
+
+pub fn test_draw_buffer_draw_line_clips_and_damages() -> TestResult {
+    const W: usize = 8;
+    const H: usize = 6;
+    const INK: u32 = 0xFF00_FF00;
+    let mut data = [0u8; W * 4 * H];
+    let Some(mut buf) = DrawBuffer::new(&mut data, W as u32, H as u32, W * 4, 4) else {
+        return TestResult::Fail;
+    };
+    buf.set_pixel_format(DrawPixelFormat::Bgra);
+
+    // Diagonal starting off the top-left corner: only (0, 0)..(5, 5) land
+    buf.draw_line(-2, -2, 5, 5, INK);
+    for y in 0..H as i32 {
+        for x in 0..W as i32 {
+            let expected = if x == y { INK } else { 0 };
+            assert_eq_test!(buf.get_pixel(x, y), expected, "diagonal pixels");
+        }
+    }
+    assert_eq_test!(
+        buf.damage().regions(),
+        &[DamageRect {
+            x0: 0,
+            y0: 0,
+            x1: 5,
+            y1: 5
+        }][..],
+        "bounding box clipped to the buffer"
+    );
+
+    // Backwards horizontal line past the right edge
+    buf.clear_damage();
+    buf.draw_line(20, 1, 6, 1, INK);
+    assert_eq_test!(buf.get_pixel(6, 1), INK);
+    assert_eq_test!(buf.get_pixel(7, 1), INK);
+    assert_eq_test!(buf.get_pixel(5, 1), 0, "stops at the start point");
+    assert_eq_test!(
+        buf.damage().regions(),
+        &[DamageRect {
+            x0: 6,
+            y0: 1,
+            x1: 7,
+            y1: 1
+        }][..]
+    );
+
+    buf.clear_damage();
+    buf.draw_line(-5, -1, -1, -9, INK);
+    assert_test!(!buf.damage().is_dirty(), "off-buffer line adds no damage");
+    TestResult::Pass
+}
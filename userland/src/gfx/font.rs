@@ -28,6 +28,35 @@ pub fn draw_string(buf: &mut DrawBuffer, x: i32, y: i32, text: &str, fg: u32, bg
     }
 }
 
+/// `draw_string` with each glyph pixel drawn as a `scale`x`scale` block.
+/// Damage covers the scaled text bounds.
+pub fn draw_string_scaled(
+    buf: &mut DrawBuffer,
+    x: i32,
+    y: i32,
+    text: &str,
+    scale: i32,
+    fg: u32,
+    bg: u32,
+) {
+    let scale = scale.max(1);
+    let width = buf.width() as i32;
+    let height = buf.height() as i32;
+
+    font_render::draw_str_scaled(buf, x, y, text, scale, fg, bg);
+
+    let text_w = string_width(text) * scale;
+    let text_h = string_height(text) * scale;
+    let x1 = x.max(0);
+    let y1 = y.max(0);
+    let x2 = (x + text_w - 1).min(width - 1);
+    let y2 = (y + text_h - 1).min(height - 1);
+
+    if x1 <= x2 && y1 <= y2 {
+        buf.add_damage(x1, y1, x2, y2);
+    }
+}
+
 pub fn string_width(text: &str) -> i32 {
     font_render::str_width(text)
 }
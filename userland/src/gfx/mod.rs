@@ -1,10 +1,12 @@
+#[cfg(not(feature = "standalone-bin"))]
+pub mod draw_buffer_tests;
 pub mod font;
 pub mod primitives;
 
 pub use slopos_abi::DrawTarget;
 pub use slopos_abi::damage::{DamageRect, DamageTracker, MAX_DAMAGE_REGIONS};
 pub use slopos_abi::pixel::DrawPixelFormat;
-use slopos_abi::{PixelBuffer, pixel_ops};
+use slopos_abi::{PixelBuffer, draw_primitives, pixel_ops};
 
 pub type PixelFormat = DrawPixelFormat;
 
@@ -30,7 +32,7 @@ impl<'a> DrawBuffer<'a> {
         if data.len() < required_size {
             return None;
         }
-        if bytes_pp != 3 && bytes_pp != 4 {
+        if !matches!(bytes_pp, 2..=4) {
             return None;
         }
 
@@ -115,41 +117,188 @@ impl<'a> DrawBuffer<'a> {
         self.write_pixel_at_offset(offset, converted);
     }
 
+    /// Draw a line from (x0, y0) to (x1, y1) with a standard ARGB color.
+    ///
+    /// Points are plotted with `set_pixel`, so endpoints outside the buffer
+    /// only lose their off-screen pixels. The line's bounding box, clipped to
+    /// the buffer, is recorded as a single damage rect.
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: u32) {
+        draw_primitives::line_points(x0, y0, x1, y1, |x, y| self.set_pixel(x, y, color));
+        self.add_damage(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1));
+    }
+
+    /// Stroke a quadratic Bezier curve from `p0` to `p2` bent towards `p1`.
+    ///
+    /// The curve is flattened into line segments within a quarter pixel of
+    /// the true curve. It never leaves its control points' bounding box, so
+    /// that box, clipped to the buffer, is recorded as one damage rect.
+    pub fn draw_quad_bezier(&mut self, p0: (i32, i32), p1: (i32, i32), p2: (i32, i32), color: u32) {
+        draw_primitives::quad_bezier(self, p0, p1, p2, color);
+        self.add_control_damage(&[p0, p1, p2]);
+    }
+
+    /// Stroke a cubic Bezier curve from `p0` to `p3` with control points `p1`
+    /// and `p2`. Flattening and damage work as in `draw_quad_bezier`.
+    pub fn draw_cubic_bezier(
+        &mut self,
+        p0: (i32, i32),
+        p1: (i32, i32),
+        p2: (i32, i32),
+        p3: (i32, i32),
+        color: u32,
+    ) {
+        draw_primitives::cubic_bezier(self, p0, p1, p2, p3, color);
+        self.add_control_damage(&[p0, p1, p2, p3]);
+    }
+
+    fn add_control_damage(&mut self, points: &[(i32, i32)]) {
+        let (mut x0, mut y0) = (i32::MAX, i32::MAX);
+        let (mut x1, mut y1) = (i32::MIN, i32::MIN);
+        for &(x, y) in points {
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+        }
+        self.add_damage(x0, y0, x1, y1);
+    }
+
+    /// Fill a rectangle with a standard ARGB color.
+    ///
+    /// The rectangle is clipped to the buffer, so negative origins and
+    /// rectangles hanging off an edge draw only their visible part. Rows are
+    /// written as whole spans and the clipped area is recorded as a single
+    /// damage rect. Nothing happens if no part is visible.
+    pub fn fill_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: u32) {
+        let Some((x0, y0, x1, y1)) = pixel_ops::clip_rect(x, y, w, h, self.width, self.height)
+        else {
+            return;
+        };
+        let raw = self.pixel_format.convert_color(color);
+        for row in y0..=y1 {
+            self.fill_row_span(row, x0, x1, raw);
+        }
+        self.add_damage(x0, y0, x1, y1);
+    }
+
+    /// Fill a rectangle with a vertical gradient from `top` to `bottom`
+    /// (standard ARGB colors).
+    ///
+    /// Clipped like `fill_rect`; each row gets its own blended color and is
+    /// written as one span. The clipped area is recorded as a single damage
+    /// rect.
+    pub fn fill_gradient(&mut self, x: i32, y: i32, w: i32, h: i32, top: u32, bottom: u32) {
+        let Some((x0, y0, x1, y1)) = pixel_ops::clip_rect(x, y, w, h, self.width, self.height)
+        else {
+            return;
+        };
+        draw_primitives::fill_gradient(self, x, y, w, h, top, bottom);
+        self.add_damage(x0, y0, x1, y1);
+    }
+
+    /// `fill_gradient` running from `left` to `right` instead.
+    pub fn fill_gradient_horizontal(
+        &mut self,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        left: u32,
+        right: u32,
+    ) {
+        let Some((x0, y0, x1, y1)) = pixel_ops::clip_rect(x, y, w, h, self.width, self.height)
+        else {
+            return;
+        };
+        draw_primitives::fill_gradient_horizontal(self, x, y, w, h, left, right);
+        self.add_damage(x0, y0, x1, y1);
+    }
+
     pub fn get_pixel(&self, x: i32, y: i32) -> u32 {
         if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
             return 0;
         }
 
         let offset = self.pixel_offset(x as u32, y as u32);
-        let raw = match self.bytes_pp {
-            4 => {
-                if offset + 4 <= self.data.len() {
-                    u32::from_le_bytes([
-                        self.data[offset],
-                        self.data[offset + 1],
-                        self.data[offset + 2],
-                        self.data[offset + 3],
-                    ])
-                } else {
-                    0
-                }
+        match self.data.get(offset..offset + self.bytes_pp as usize) {
+            Some(bytes) => self.pixel_format.decode_color(raw_pixel(bytes)),
+            None => 0,
+        }
+    }
+
+    /// Copy a `w` x `h` region of `src` at (src_x, src_y) to (dst_x, dst_y).
+    ///
+    /// The region is clipped against both buffers. Pixels are converted when
+    /// the formats differ; otherwise whole rows are copied. The copied area
+    /// is recorded as a single damage rect.
+    #[allow(clippy::too_many_arguments)]
+    pub fn blit_from(
+        &mut self,
+        src: &DrawBuffer<'_>,
+        src_x: i32,
+        src_y: i32,
+        dst_x: i32,
+        dst_y: i32,
+        w: i32,
+        h: i32,
+    ) {
+        let rect = pixel_ops::BlitRect {
+            src_x,
+            src_y,
+            dst_x,
+            dst_y,
+            width: w,
+            height: h,
+        };
+        let Some(r) = pixel_ops::clip_blit(rect, src.width, src.height, self.width, self.height)
+        else {
+            return;
+        };
+
+        let src_bpp = src.bytes_pp as usize;
+        let dst_bpp = self.bytes_pp as usize;
+        let same_format = src_bpp == dst_bpp && src.pixel_format == self.pixel_format;
+        for row in 0..r.height {
+            let src_off = src.pixel_offset(r.src_x as u32, (r.src_y + row) as u32);
+            let dst_off = self.pixel_offset(r.dst_x as u32, (r.dst_y + row) as u32);
+            let src_end = src_off + r.width as usize * src_bpp;
+            let dst_end = dst_off + r.width as usize * dst_bpp;
+            if src_end > src.data.len() || dst_end > self.data.len() {
+                continue;
             }
-            3 => {
-                if offset + 3 <= self.data.len() {
-                    u32::from_le_bytes([
-                        self.data[offset],
-                        self.data[offset + 1],
-                        self.data[offset + 2],
-                        0xFF,
-                    ])
-                } else {
-                    0
-                }
+            let src_row = &src.data[src_off..src_end];
+            let dst_row = &mut self.data[dst_off..dst_end];
+            if same_format {
+                dst_row.copy_from_slice(src_row);
+                continue;
             }
-            _ => 0,
-        };
+            for (d, s) in dst_row
+                .chunks_exact_mut(dst_bpp)
+                .zip(src_row.chunks_exact(src_bpp))
+            {
+                let color = src.pixel_format.decode_color(raw_pixel(s));
+                let raw = self.pixel_format.convert_color(color).to_le_bytes();
+                d.copy_from_slice(&raw[..dst_bpp]);
+            }
+        }
+        self.add_damage(
+            r.dst_x,
+            r.dst_y,
+            r.dst_x + r.width - 1,
+            r.dst_y + r.height - 1,
+        );
+    }
+}
 
-        self.pixel_format.convert_color(raw)
+/// Raw little-endian pixel value from 2, 3 or 4 bytes. 24bpp pixels have
+/// no alpha byte and read as opaque.
+#[inline]
+fn raw_pixel(bytes: &[u8]) -> u32 {
+    match *bytes {
+        [b0, b1] => u16::from_le_bytes([b0, b1]) as u32,
+        [b0, b1, b2] => u32::from_le_bytes([b0, b1, b2, 0xFF]),
+        [b0, b1, b2, b3] => u32::from_le_bytes([b0, b1, b2, b3]),
+        _ => 0,
     }
 }
 
@@ -183,6 +332,11 @@ impl PixelBuffer for DrawBuffer<'_> {
     fn write_pixel_at_offset(&mut self, byte_offset: usize, color: u32) {
         let bytes = color.to_le_bytes();
         match self.bytes_pp {
+            2 => {
+                if byte_offset + 2 <= self.data.len() {
+                    self.data[byte_offset..byte_offset + 2].copy_from_slice(&bytes[..2]);
+                }
+            }
             4 => {
                 if byte_offset + 4 <= self.data.len() {
                     self.data[byte_offset..byte_offset + 4].copy_from_slice(&bytes);
@@ -217,6 +371,15 @@ impl PixelBuffer for DrawBuffer<'_> {
         let row_off = (row as usize) * pitch + (x0 as usize) * bytes_pp;
 
         match bytes_pp {
+            2 => {
+                let end = row_off + span_w * 2;
+                if end <= self.data.len() {
+                    let bytes = color.to_le_bytes();
+                    for chunk in self.data[row_off..end].chunks_exact_mut(2) {
+                        chunk.copy_from_slice(&bytes[..2]);
+                    }
+                }
+            }
             4 => {
                 let end = row_off + span_w * 4;
                 if end <= self.data.len() {
@@ -255,6 +418,11 @@ impl PixelBuffer for DrawBuffer<'_> {
         } else {
             let bytes = color.to_le_bytes();
             match bytes_pp {
+                2 => {
+                    for chunk in self.data.chunks_exact_mut(2) {
+                        chunk.copy_from_slice(&bytes[..2]);
+                    }
+                }
                 4 => {
                     for chunk in self.data.chunks_exact_mut(4) {
                         chunk.copy_from_slice(&bytes);
@@ -2,23 +2,11 @@ use super::{DrawBuffer, DrawTarget};
 use slopos_abi::draw_primitives;
 
 pub fn fill_rect(buf: &mut DrawBuffer, x: i32, y: i32, w: i32, h: i32, color: u32) {
-    draw_primitives::fill_rect(buf, x, y, w, h, color);
-    let x1 = x.max(0);
-    let y1 = y.max(0);
-    let x2 = (x + w - 1).min(buf.width() as i32 - 1);
-    let y2 = (y + h - 1).min(buf.height() as i32 - 1);
-    if x1 <= x2 && y1 <= y2 {
-        buf.add_damage(x1, y1, x2, y2);
-    }
+    buf.fill_rect(x, y, w, h, color);
 }
 
 pub fn draw_line(buf: &mut DrawBuffer, x0: i32, y0: i32, x1: i32, y1: i32, color: u32) {
-    draw_primitives::line(buf, x0, y0, x1, y1, color);
-    let min_x = x0.min(x1).max(0);
-    let min_y = y0.min(y1).max(0);
-    let max_x = x0.max(x1).min(buf.width() as i32 - 1);
-    let max_y = y0.max(y1).min(buf.height() as i32 - 1);
-    buf.add_damage(min_x, min_y, max_x, max_y);
+    buf.draw_line(x0, y0, x1, y1, color);
 }
 
 pub fn draw_circle(buf: &mut DrawBuffer, cx: i32, cy: i32, radius: i32, color: u32) {
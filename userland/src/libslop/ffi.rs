@@ -39,6 +39,11 @@ pub extern "C" fn _exit(status: c_int) -> ! {
     sys::sys_exit(status)
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn sched_yield() -> c_int {
+    sys::sys_yield()
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn brk(addr: *mut c_void) -> *mut c_void {
     sys::sys_brk(addr)
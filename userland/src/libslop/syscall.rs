@@ -2,7 +2,7 @@
 
 use core::ffi::{c_char, c_int, c_void};
 
-use crate::syscall_raw::{syscall1, syscall2, syscall3};
+use crate::syscall_raw::{syscall0, syscall1, syscall2, syscall3};
 use slopos_abi::syscall::*;
 
 pub fn sys_read(fd: c_int, buf: *mut c_void, count: usize) -> isize {
@@ -17,10 +17,31 @@ pub fn sys_open(path: *const c_char, flags: c_int) -> c_int {
     unsafe { syscall2(SYSCALL_FS_OPEN, path as u64, flags as u64) as c_int }
 }
 
+/// Move the file offset; `whence` is one of `SEEK_SET`, `SEEK_CUR`, `SEEK_END`.
+pub fn sys_lseek(fd: c_int, offset: i64, whence: c_int) -> i64 {
+    unsafe { syscall3(SYSCALL_FS_LSEEK, fd as u64, offset as u64, whence as u64) as i64 }
+}
+
+/// Create a pipe; `fds[0]` becomes the read end and `fds[1]` the write end.
+pub fn sys_pipe(fds: *mut [i32; 2]) -> c_int {
+    unsafe { syscall1(SYSCALL_FS_PIPE, fds as u64) as c_int }
+}
+
 pub fn sys_close(fd: c_int) -> c_int {
     unsafe { syscall1(SYSCALL_FS_CLOSE, fd as u64) as c_int }
 }
 
+/// Read packed `UserDirent` records from a directory opened with `sys_open`.
+pub fn sys_getdents(fd: c_int, buf: *mut c_void, len: usize) -> isize {
+    unsafe { syscall3(SYSCALL_FS_GETDENTS, fd as u64, buf as u64, len as u64) as isize }
+}
+
+/// Give up the CPU to another ready task of equal or higher priority.
+/// Returns at once when nothing else is ready.
+pub fn sys_yield() -> c_int {
+    unsafe { syscall0(SYSCALL_YIELD) as c_int }
+}
+
 pub fn sys_exit(status: c_int) -> ! {
     unsafe {
         syscall1(SYSCALL_EXIT, status as u64);
@@ -35,21 +56,13 @@ pub fn sys_brk(addr: *mut c_void) -> *mut c_void {
 }
 
 pub fn sys_sbrk(increment: isize) -> *mut c_void {
-    unsafe {
-        let current = syscall1(SYSCALL_BRK, 0) as usize;
-        if increment == 0 {
-            return current as *mut c_void;
-        }
-        let new_brk = if increment > 0 {
-            current.wrapping_add(increment as usize)
-        } else {
-            current.wrapping_sub((-increment) as usize)
-        };
-        let result = syscall1(SYSCALL_BRK, new_brk as u64) as usize;
-        if result == new_brk {
-            current as *mut c_void
-        } else {
-            usize::MAX as *mut c_void
-        }
-    }
+    unsafe { syscall1(SYSCALL_SBRK, increment as u64) as *mut c_void }
+}
+
+pub fn sys_mmap(len: usize) -> *mut c_void {
+    unsafe { syscall1(SYSCALL_MMAP, len as u64) as *mut c_void }
+}
+
+pub fn sys_munmap(addr: *mut c_void, len: usize) -> c_int {
+    unsafe { syscall2(SYSCALL_MUNMAP, addr as u64, len as u64) as c_int }
 }
@@ -37,16 +37,18 @@ unsafe impl<T> Sync for SyncUnsafeCell<T> {}
 use crate::gfx::{self, DrawBuffer};
 use crate::runtime;
 use crate::syscall::{
-    DisplayInfo, ShmBuffer, USER_FS_OPEN_CREAT, USER_FS_OPEN_READ, USER_FS_OPEN_WRITE, UserFsEntry,
-    UserFsList, UserSysInfo, sys_fb_info, sys_fs_close, sys_fs_list, sys_fs_mkdir, sys_fs_open,
-    sys_fs_read, sys_fs_unlink, sys_fs_write, sys_halt, sys_read_char, sys_spawn_task,
-    sys_surface_commit, sys_surface_set_title, sys_sys_info, sys_write,
+    DisplayInfo, PciDeviceInfo, ShmBuffer, USER_FS_OPEN_CREAT, USER_FS_OPEN_READ,
+    USER_FS_OPEN_WRITE, UserFsEntry, UserFsList, UserSysInfo, sys_fb_info, sys_fs_close,
+    sys_fs_list, sys_fs_mkdir, sys_fs_open, sys_fs_read, sys_fs_unlink, sys_fs_write, sys_halt,
+    sys_keyboard_set_layout, sys_pci_enumerate, sys_read_char, sys_spawn_task, sys_surface_commit,
+    sys_surface_set_title, sys_sys_info, sys_tty_set_mode, sys_write,
 };
 
 const SHELL_MAX_TOKENS: usize = 16;
 const SHELL_MAX_TOKEN_LENGTH: usize = 64;
 const SHELL_PATH_BUF: usize = 128;
 const SHELL_IO_MAX: usize = 512;
+const SHELL_PCI_MAX: usize = 32;
 
 #[unsafe(link_section = ".user_rodata")]
 static PROMPT: &[u8] = b"$ ";
@@ -85,6 +87,8 @@ const SHELL_SCROLLBACK_LINES: usize = 256;
 const SHELL_SCROLLBACK_COLS: usize = 160;
 const KEY_PAGE_UP: u8 = 0x80;
 const KEY_PAGE_DOWN: u8 = 0x81;
+const KEY_ARROW_UP: u8 = 0x82;
+const KEY_ARROW_DOWN: u8 = 0x83;
 
 // =============================================================================
 // DisplayState: Cell-based state (no borrow conflicts)
@@ -371,6 +375,10 @@ mod buffers {
     static LIST_ENTRIES: SyncUnsafeCell<[UserFsEntry; 32]> =
         SyncUnsafeCell::new([UserFsEntry::new(); 32]);
 
+    #[unsafe(link_section = ".user_bss")]
+    static PCI_DEVICES: SyncUnsafeCell<[PciDeviceInfo; SHELL_PCI_MAX]> =
+        SyncUnsafeCell::new([PciDeviceInfo::zeroed(); SHELL_PCI_MAX]);
+
     pub fn with_line_buf<R, F: FnOnce(&mut [u8; 256]) -> R>(f: F) -> R {
         f(unsafe { &mut *LINE_BUF.get() })
     }
@@ -392,11 +400,42 @@ mod buffers {
         f(unsafe { &mut *LIST_ENTRIES.get() })
     }
 
+    pub fn with_pci_devices<R, F: FnOnce(&mut [PciDeviceInfo; SHELL_PCI_MAX]) -> R>(f: F) -> R {
+        f(unsafe { &mut *PCI_DEVICES.get() })
+    }
+
     pub fn token_ptr(idx: usize) -> *const u8 {
         unsafe { (*TOKEN_STORAGE.get())[idx].as_ptr() }
     }
 }
 
+// =============================================================================
+// Command history (up/down-arrow recall)
+// =============================================================================
+
+mod history {
+    use super::*;
+    use slopos_abi::line_history::LineHistory;
+
+    #[unsafe(link_section = ".user_bss")]
+    static HISTORY: SyncUnsafeCell<LineHistory> = SyncUnsafeCell::new(LineHistory::new());
+
+    /// Record a submitted line; consecutive duplicates are collapsed.
+    pub fn history_push(line: &[u8]) {
+        unsafe { (*HISTORY.get()).push(line) };
+    }
+
+    /// Older line, or None if the history is empty
+    pub fn history_prev() -> Option<&'static [u8]> {
+        unsafe { (*HISTORY.get()).older() }
+    }
+
+    /// Newer line, or None when back at the empty input line
+    pub fn history_next() -> Option<&'static [u8]> {
+        unsafe { (*HISTORY.get()).newer() }
+    }
+}
+
 // =============================================================================
 // Free drawing functions (no &mut self, explicit parameters)
 // =============================================================================
@@ -953,6 +992,21 @@ static BUILTINS: &[BuiltinEntry] = &[
         func: cmd_sysinfo,
         desc: b"Launch the sysinfo program",
     },
+    BuiltinEntry {
+        name: b"lspci",
+        func: cmd_lspci,
+        desc: b"List PCI devices",
+    },
+    BuiltinEntry {
+        name: b"kbd",
+        func: cmd_kbd,
+        desc: b"Select the keyboard layout (us, uk, de)",
+    },
+    BuiltinEntry {
+        name: b"stty",
+        func: cmd_stty,
+        desc: b"Set the terminal line mode (cooked, raw)",
+    },
     BuiltinEntry {
         name: b"ls",
         func: cmd_ls,
@@ -1216,6 +1270,94 @@ fn cmd_sysinfo(_argc: i32, _argv: &[*const u8]) -> i32 {
     0
 }
 
+/// Write the low `width` nibbles of `value` as lowercase hex.
+#[unsafe(link_section = ".user_text")]
+fn write_hex(value: u32, width: usize) {
+    let mut tmp = [0u8; 8];
+    let width = width.min(tmp.len());
+    for (i, slot) in tmp[..width].iter_mut().enumerate() {
+        let nibble = ((value >> ((width - 1 - i) * 4)) & 0xF) as u8;
+        *slot = if nibble < 10 {
+            b'0' + nibble
+        } else {
+            b'a' + nibble - 10
+        };
+    }
+    shell_write(&tmp[..width]);
+}
+
+#[unsafe(link_section = ".user_text")]
+fn cmd_lspci(_argc: i32, _argv: &[*const u8]) -> i32 {
+    buffers::with_pci_devices(|devices| {
+        let count = sys_pci_enumerate(devices);
+        if count < 0 {
+            shell_write(b"lspci: failed\n");
+            return 1;
+        }
+        for dev in &devices[..count as usize] {
+            write_hex(dev.bus as u32, 2);
+            shell_write(b":");
+            write_hex(dev.device as u32, 2);
+            shell_write(b".");
+            write_hex(dev.function as u32, 1);
+            shell_write(b" class ");
+            write_hex(dev.class_code as u32, 2);
+            write_hex(dev.subclass as u32, 2);
+            shell_write(b": ");
+            write_hex(dev.vendor_id as u32, 4);
+            shell_write(b":");
+            write_hex(dev.device_id as u32, 4);
+            shell_write(NL);
+        }
+        0
+    })
+}
+
+#[unsafe(link_section = ".user_text")]
+fn cmd_kbd(argc: i32, argv: &[*const u8]) -> i32 {
+    if argc < 2 {
+        shell_write(ERR_MISSING_OPERAND);
+        return 1;
+    }
+    if argc > 2 {
+        shell_write(ERR_TOO_MANY_ARGS);
+        return 1;
+    }
+    let len = runtime::u_strlen(argv[1]);
+    let name = unsafe { core::slice::from_raw_parts(argv[1], len) };
+    if sys_keyboard_set_layout(name) != 0 {
+        shell_write(b"kbd: unknown layout\n");
+        return 1;
+    }
+    0
+}
+
+#[unsafe(link_section = ".user_text")]
+fn cmd_stty(argc: i32, argv: &[*const u8]) -> i32 {
+    if argc < 2 {
+        shell_write(ERR_MISSING_OPERAND);
+        return 1;
+    }
+    if argc > 2 {
+        shell_write(ERR_TOO_MANY_ARGS);
+        return 1;
+    }
+    let len = runtime::u_strlen(argv[1]);
+    let canonical = match unsafe { core::slice::from_raw_parts(argv[1], len) } {
+        b"cooked" => true,
+        b"raw" => false,
+        _ => {
+            shell_write(b"stty: expected cooked or raw\n");
+            return 1;
+        }
+    };
+    if sys_tty_set_mode(canonical) != 0 {
+        shell_write(b"stty: terminal is focused elsewhere\n");
+        return 1;
+    }
+    0
+}
+
 #[unsafe(link_section = ".user_text")]
 fn cmd_ls(argc: i32, argv: &[*const u8]) -> i32 {
     if argc > 2 {
@@ -1453,6 +1595,23 @@ pub fn shell_user_main(_arg: *mut c_void) {
                 shell_console_page_down();
                 continue;
             }
+            if c == KEY_ARROW_UP || c == KEY_ARROW_DOWN {
+                let recalled = if c == KEY_ARROW_UP {
+                    history::history_prev()
+                } else {
+                    history::history_next()
+                };
+                if c == KEY_ARROW_UP && recalled.is_none() {
+                    continue;
+                }
+                let line = recalled.unwrap_or(&[]);
+                buffers::with_line_buf(|buf| {
+                    len = cmp::min(line.len(), buf.len() - 1);
+                    buf[..len].copy_from_slice(&line[..len]);
+                    shell_redraw_input(line_row, &buf[..len]);
+                });
+                continue;
+            }
 
             // Return to follow mode if we were scrolled up
             if DISPLAY.enabled.get() && !DISPLAY.follow.get() {
@@ -1493,6 +1652,8 @@ pub fn shell_user_main(_arg: *mut c_void) {
             });
         }
 
+        buffers::with_line_buf(|buf| history::history_push(&buf[..len]));
+
         // Null-terminate
         buffers::with_line_buf(|buf| {
             let capped = cmp::min(len, buf.len() - 1);
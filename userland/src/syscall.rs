@@ -2,15 +2,16 @@ use core::ffi::{c_char, c_void};
 use core::num::NonZeroU32;
 use core::ptr::NonNull;
 
-use crate::syscall_raw::{syscall0, syscall1, syscall2, syscall3, syscall4};
+use crate::syscall_raw::{syscall0, syscall1, syscall2, syscall3, syscall4, syscall5};
 
 pub use slopos_abi::{
     DisplayInfo, INPUT_FOCUS_KEYBOARD, INPUT_FOCUS_POINTER, InputEvent, InputEventData,
-    InputEventType, MAX_WINDOW_DAMAGE_REGIONS, PixelFormat, SHM_ACCESS_RO, SHM_ACCESS_RW,
-    SurfaceRole, USER_FS_OPEN_APPEND, USER_FS_OPEN_CREAT, USER_FS_OPEN_READ, USER_FS_OPEN_WRITE,
-    UserFsEntry, UserFsList, UserFsStat, WindowDamageRect, WindowInfo,
+    InputEventType, KeyEvent, MAX_WINDOW_DAMAGE_REGIONS, PixelFormat, SHM_ACCESS_RO, SHM_ACCESS_RW,
+    SurfaceBlendMode, SurfaceRole, USER_FS_OPEN_APPEND, USER_FS_OPEN_CREAT, USER_FS_OPEN_READ,
+    USER_FS_OPEN_WRITE, UserFsEntry, UserFsList, UserFsStat, WindowDamageRect, WindowInfo,
 };
 
+pub use slopos_abi::arch::x86_64::pci::PciDeviceInfo;
 pub use slopos_abi::syscall::*;
 
 pub type UserWindowDamageRect = WindowDamageRect;
@@ -50,6 +51,20 @@ pub fn sys_sleep_ms(ms: u32) {
     }
 }
 
+/// Block for at least `ns` nanoseconds without spinning.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_nanosleep(ns: u64) -> i64 {
+    unsafe { syscall1(SYSCALL_NANOSLEEP, ns) as i64 }
+}
+
+/// Wall-clock time since the Unix epoch.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_gettimeofday(tv: &mut UserTimeval) -> i64 {
+    unsafe { syscall1(SYSCALL_GETTIMEOFDAY, tv as *mut UserTimeval as u64) as i64 }
+}
+
 #[inline(always)]
 #[unsafe(link_section = ".user_text")]
 pub fn sys_get_time_ms() -> u64 {
@@ -92,7 +107,7 @@ pub fn sys_roulette_draw(fate: u32) -> i64 {
 #[unsafe(link_section = ".user_text")]
 pub fn sys_exit() -> ! {
     unsafe {
-        syscall0(SYSCALL_EXIT);
+        syscall1(SYSCALL_EXIT, 0);
     }
     loop {}
 }
@@ -109,6 +124,12 @@ pub fn sys_tty_set_focus(task_id: u32) -> i64 {
     unsafe { syscall1(SYSCALL_TTY_SET_FOCUS, task_id as u64) as i64 }
 }
 
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_tty_set_mode(canonical: bool) -> i64 {
+    unsafe { syscall1(SYSCALL_TTY_SET_MODE, canonical as u64) as i64 }
+}
+
 #[inline(always)]
 #[unsafe(link_section = ".user_text")]
 pub fn sys_random_next() -> u32 {
@@ -169,6 +190,19 @@ pub fn sys_sys_info(info: &mut UserSysInfo) -> i64 {
     unsafe { syscall1(SYSCALL_SYS_INFO, info as *mut _ as u64) as i64 }
 }
 
+/// List the PCI devices found at boot. Returns the number of entries filled.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_pci_enumerate(devices: &mut [PciDeviceInfo]) -> i64 {
+    unsafe {
+        syscall2(
+            SYSCALL_PCI_ENUMERATE,
+            devices.as_mut_ptr() as u64,
+            devices.len() as u64,
+        ) as i64
+    }
+}
+
 #[inline(always)]
 #[unsafe(link_section = ".user_text")]
 pub fn sys_enumerate_windows(windows: &mut [UserWindowInfo]) -> u64 {
@@ -181,6 +215,21 @@ pub fn sys_enumerate_windows(windows: &mut [UserWindowInfo]) -> u64 {
     }
 }
 
+/// List visible windows for a taskbar or window switcher. Unlike
+/// `sys_enumerate_windows` this works for any task; `shm_token` and damage
+/// are always zero. Returns the number of entries filled.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_list_windows(windows: &mut [UserWindowInfo]) -> i64 {
+    unsafe {
+        syscall2(
+            SYSCALL_LIST_WINDOWS,
+            windows.as_mut_ptr() as u64,
+            windows.len() as u64,
+        ) as i64
+    }
+}
+
 #[inline(always)]
 #[unsafe(link_section = ".user_text")]
 pub fn sys_set_window_position(task_id: u32, x: i32, y: i32) -> i64 {
@@ -233,12 +282,59 @@ pub fn sys_fork() -> i32 {
     unsafe { syscall0(SYSCALL_FORK) as i32 }
 }
 
+/// Wait for child `pid` and store its exit code in `status`; pass `WNOHANG`
+/// in `options` to poll instead of blocking.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_waitpid(pid: u32, status: *mut i32, options: u64) -> i64 {
+    unsafe { syscall3(SYSCALL_WAITPID, pid as u64, status as u64, options) as i64 }
+}
+
 #[inline(always)]
 #[unsafe(link_section = ".user_text")]
 pub fn sys_surface_commit() -> i64 {
     unsafe { syscall0(SYSCALL_SURFACE_COMMIT) as i64 }
 }
 
+/// Commit by swapping front and back buffers; the back buffer then holds
+/// the previous frame.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_surface_commit_swap() -> i64 {
+    unsafe { syscall0(SYSCALL_SURFACE_COMMIT_SWAP) as i64 }
+}
+
+/// Downscale window `task_id` into `out`, tightly packed in its pixel format.
+/// Returns `width << 16 | height`, or a negative error.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_surface_thumbnail(task_id: u32, max_w: u32, max_h: u32, out: &mut [u8]) -> i64 {
+    unsafe {
+        syscall5(
+            SYSCALL_SURFACE_THUMBNAIL,
+            task_id as u64,
+            max_w as u64,
+            max_h as u64,
+            out.as_mut_ptr() as u64,
+            out.len() as u64,
+        ) as i64
+    }
+}
+
+/// Start batching damage and commits until `sys_surface_end_frame`.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_surface_begin_frame() -> i64 {
+    unsafe { syscall0(SYSCALL_SURFACE_BEGIN_FRAME) as i64 }
+}
+
+/// Commit everything batched since `sys_surface_begin_frame` at once.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_surface_end_frame() -> i64 {
+    unsafe { syscall0(SYSCALL_SURFACE_END_FRAME) as i64 }
+}
+
 #[inline(always)]
 #[unsafe(link_section = ".user_text")]
 pub fn sys_shm_create(size: u64, flags: u32) -> u32 {
@@ -276,6 +372,75 @@ pub fn sys_surface_attach(token: u32, width: u32, height: u32) -> i64 {
     }
 }
 
+/// Attach a back buffer to the caller's surface (token 0 detaches it).
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_surface_attach_back(token: u32, width: u32, height: u32) -> i64 {
+    unsafe {
+        syscall3(
+            SYSCALL_SURFACE_ATTACH_BACK,
+            token as u64,
+            width as u64,
+            height as u64,
+        ) as i64
+    }
+}
+
+/// Token of the buffer to draw the next frame into (0 without a back
+/// buffer). Ask again after each swap commit.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_surface_back_buffer() -> u32 {
+    unsafe { syscall0(SYSCALL_SURFACE_BACK_BUFFER) as u32 }
+}
+
+/// Attach a spare buffer to the caller's surface for triple buffering.
+/// Only takes effect alongside a back buffer; see `sys_surface_detach_spare`.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_surface_attach_spare(token: u32, width: u32, height: u32) -> i64 {
+    unsafe {
+        syscall3(
+            SYSCALL_SURFACE_ATTACH_SPARE,
+            token as u64,
+            width as u64,
+            height as u64,
+        ) as i64
+    }
+}
+
+/// Detach the spare buffer. Returns the detached token, which is then safe
+/// to free, or a negative error (`Busy` while commits are still queued).
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_surface_detach_spare() -> i64 {
+    unsafe { syscall3(SYSCALL_SURFACE_ATTACH_SPARE, 0, 0, 0) as i64 }
+}
+
+/// Resize the caller's surface. Its buffers keep their tokens; old mappings
+/// keep the old pixels until unmapped, so unmap and map them again once the
+/// `SurfaceResize` input event arrives.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_surface_resize(width: u32, height: u32) -> i64 {
+    unsafe { syscall2(SYSCALL_SURFACE_RESIZE, width as u64, height as u64) as i64 }
+}
+
+/// Capture the screen into `out` as BGRA8888, rows `stride` bytes apart
+/// (0 packs them tightly). `out` must hold the whole frame.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_compositor_capture(out: &mut [u8], stride: u32) -> i64 {
+    unsafe {
+        syscall3(
+            SYSCALL_COMPOSITOR_CAPTURE,
+            out.as_mut_ptr() as u64,
+            out.len() as u64,
+            stride as u64,
+        ) as i64
+    }
+}
+
 #[inline(always)]
 #[unsafe(link_section = ".user_text")]
 pub fn sys_fb_flip(token: u32) -> i64 {
@@ -320,6 +485,13 @@ pub fn sys_poll_frame_done() -> u64 {
     unsafe { syscall0(SYSCALL_POLL_FRAME_DONE) }
 }
 
+/// True once per presented frame that included this task's surface.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_surface_frame_done() -> bool {
+    unsafe { syscall0(SYSCALL_SURFACE_FRAME_DONE) == 1 }
+}
+
 #[inline(always)]
 #[unsafe(link_section = ".user_text")]
 pub fn sys_mark_frames_done(present_time_ms: u64) {
@@ -383,6 +555,18 @@ pub fn sys_surface_set_title(title: &str) -> i64 {
     }
 }
 
+pub fn sys_surface_set_color_key(key: Option<u32>) -> i64 {
+    let (enabled, color) = match key {
+        Some(color) => (1, color),
+        None => (0, 0),
+    };
+    unsafe { syscall2(SYSCALL_SURFACE_SET_COLOR_KEY, enabled, color as u64) as i64 }
+}
+
+pub fn sys_surface_set_blend_mode(mode: SurfaceBlendMode) -> i64 {
+    unsafe { syscall1(SYSCALL_SURFACE_SET_BLEND_MODE, mode as u64) as i64 }
+}
+
 pub fn sys_input_poll(event_out: &mut InputEvent) -> Option<InputEvent> {
     let result = unsafe { syscall1(SYSCALL_INPUT_POLL, event_out as *mut InputEvent as u64) };
     if result == 1 { Some(*event_out) } else { None }
@@ -448,6 +632,28 @@ pub fn sys_input_get_button_state() -> u8 {
     unsafe { syscall0(SYSCALL_INPUT_GET_BUTTON_STATE) as u8 }
 }
 
+/// Select the keyboard layout ("us", "uk" or "de"). Needs a system task.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_keyboard_set_layout(name: &[u8]) -> i64 {
+    unsafe {
+        syscall2(
+            SYSCALL_KEYBOARD_SET_LAYOUT,
+            name.as_ptr() as u64,
+            name.len() as u64,
+        ) as i64
+    }
+}
+
+/// Pop the oldest raw keyboard event, if any. Needs a system task.
+#[inline(always)]
+#[unsafe(link_section = ".user_text")]
+pub fn sys_keyboard_poll_event() -> Option<KeyEvent> {
+    let mut event = KeyEvent::default();
+    let result = unsafe { syscall1(SYSCALL_KEYBOARD_POLL_EVENT, &mut event as *mut _ as u64) };
+    (result as i64 == 1).then_some(event)
+}
+
 pub use slopos_abi::ShmError;
 
 /// Safe wrapper for an owned shared memory buffer (read-write access).
@@ -542,12 +748,14 @@ impl ShmBuffer {
     /// This registers the buffer as a drawable surface.
     ///
     /// # Errors
-    /// Returns error if the kernel rejects the attach operation.
+    /// - `ShmError::InvalidToken` if the kernel no longer knows the buffer
+    /// - `ShmError::PermissionDenied` if this process does not own it
+    /// - `ShmError::InvalidSize` if the buffer is too small for the surface
     #[unsafe(link_section = ".user_text")]
     pub fn attach_surface(&self, width: u32, height: u32) -> Result<(), ShmError> {
         let result = sys_surface_attach(self.token.get(), width, height);
         if result < 0 {
-            Err(ShmError::PermissionDenied)
+            Err(ShmError::from_c_int(result as i32))
         } else {
             Ok(())
         }
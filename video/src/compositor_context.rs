@@ -12,16 +12,35 @@
 //! - Client calls damage() to mark changed regions
 //! - Client calls commit() to make changes visible
 //! - Compositor reads directly from client buffer via shm_token
-//! - NO kernel-side buffer copies
+//! - NO kernel-side buffer copies, except a plain commit on a surface with an
+//!   attached back buffer, which copies back to front so the back buffer keeps
+//!   its contents (`surface_commit_swap` exchanges the two instead); with a
+//!   third (spare) buffer attached the copy goes into the spare, so the
+//!   buffer being presented is never written. Copies run at the end of
+//!   `drain_queue`, after the context lock is released
 
 use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec::Vec;
 
 use slopos_abi::damage::{DamageRect, InternalDamageTracker};
 use slopos_abi::{
-    CompositorError, MAX_CHILDREN, MAX_WINDOW_DAMAGE_REGIONS, SurfaceRole, WINDOW_STATE_NORMAL,
-    WindowDamageRect, WindowInfo,
+    CompositeSource, CompositeTarget, CompositorError, MAX_BUFFER_AGE, MAX_CHILDREN,
+    MAX_WINDOW_DAMAGE_REGIONS, SurfaceBlendMode, SurfaceRole, WINDOW_FLAG_ALPHA_BLEND,
+    WINDOW_FLAG_COLOR_KEY, WINDOW_FORMAT_NATIVE, WINDOW_STATE_MINIMIZED, WINDOW_STATE_NORMAL,
+    WINDOW_TITLE_LEN, WindowDamageRect, WindowInfo, copy_window_title, window_title_text,
+};
+use slopos_drivers::input_event::{
+    InputEvent, InputOverflowPolicy, get_timestamp_ms, input_poll, input_push,
+    input_set_keyboard_focus, input_set_overflow_policy,
 };
 use slopos_lib::IrqMutex;
+use slopos_mm::hhdm::PhysAddrHhdm;
+use slopos_mm::shared_memory::{
+    ShmFrames, shm_alloc_frames, shm_get_buffer_info, shm_get_declared_format, shm_replace_frames,
+    shm_retire_frames, shm_with_buffer, surface_attach,
+};
+
+use crate::framebuffer;
 
 type DamageTracker = InternalDamageTracker;
 
@@ -41,6 +60,26 @@ enum ClientOp {
     Commit {
         task_id: u32,
     },
+    /// Commit by exchanging front and back buffers instead of copying
+    CommitSwap {
+        task_id: u32,
+    },
+    /// Attach (or with token 0, detach) a second buffer to draw into
+    AttachBackBuffer {
+        task_id: u32,
+        shm_token: u32,
+    },
+    /// Attach a third buffer for triple buffering
+    AttachSpareBuffer {
+        task_id: u32,
+        shm_token: u32,
+    },
+    /// Reallocate the surface's buffers at a new size
+    Resize {
+        task_id: u32,
+        width: u32,
+        height: u32,
+    },
     Register {
         task_id: u32,
         width: u32,
@@ -81,10 +120,44 @@ enum ClientOp {
     /// Set window title (UTF-8, max 31 chars + null terminator)
     SetTitle {
         task_id: u32,
-        title: [u8; 32],
+        title: [u8; WINDOW_TITLE_LEN],
+    },
+    /// Set or clear the transparent color key
+    SetColorKey {
+        task_id: u32,
+        key: Option<u32>,
+    },
+    /// Choose opaque copy or alpha blending
+    SetBlendMode {
+        task_id: u32,
+        mode: SurfaceBlendMode,
+    },
+    /// Open a damage batch: damage and commits are held until EndFrame
+    BeginFrame {
+        task_id: u32,
+    },
+    /// Close a damage batch and commit everything accumulated in it
+    EndFrame {
+        task_id: u32,
     },
 }
 
+impl ClientOp {
+    /// Whether applying this op can change which token holds which buffer
+    /// role on `task`'s surface.
+    fn moves_buffers_of(&self, task: u32) -> bool {
+        match *self {
+            Self::Commit { task_id }
+            | Self::CommitSwap { task_id }
+            | Self::EndFrame { task_id }
+            | Self::AttachBackBuffer { task_id, .. }
+            | Self::AttachSpareBuffer { task_id, .. }
+            | Self::Resize { task_id, .. } => task_id == task,
+            _ => false,
+        }
+    }
+}
+
 // =============================================================================
 // Surface State (Wayland-aligned - no kernel buffers)
 // =============================================================================
@@ -95,8 +168,14 @@ enum ClientOp {
 /// The compositor reads directly from the client's buffer.
 /// This struct only tracks metadata and damage regions.
 struct SurfaceState {
-    /// Token referencing client's shared memory buffer
+    /// Token referencing client's shared memory buffer (the front buffer)
     shm_token: u32,
+    /// Buffer the client draws into when double-buffered (0 = none)
+    back_token: u32,
+    /// Third buffer, neither presented nor drawn into (0 = double-buffered)
+    spare_token: u32,
+    /// The pending commit exchanges front and back instead of copying
+    swap_pending: bool,
     /// Surface dimensions (from client's buffer)
     width: u32,
     height: u32,
@@ -119,6 +198,8 @@ struct SurfaceState {
     frame_callback_pending: bool,
     /// Timestamp (ms) when the frame was presented, 0 if not yet presented
     last_present_time_ms: u64,
+    /// Set when a presented frame included this surface, cleared when read
+    frame_presented: bool,
     /// Role of this surface (toplevel, popup, subsurface)
     role: SurfaceRole,
     /// Parent task ID for subsurfaces (None for toplevel/popup)
@@ -131,14 +212,36 @@ struct SurfaceState {
     relative_x: i32,
     relative_y: i32,
     /// Window title (UTF-8, null-terminated)
-    title: [u8; 32],
+    title: [u8; WINDOW_TITLE_LEN],
+    /// Monotonic commit counter for this surface
+    commit_seq: u64,
+    /// Commit sequence at which each buffer slot was last front (0 = never)
+    buffer_last_front: [u64; SURFACE_BUFFER_COUNT],
+    /// Slot index of the buffer currently presented
+    front_buffer: usize,
+    /// Slot index of the buffer the client draws into next
+    back_buffer: usize,
+    /// Pixels matching this color are skipped during composite
+    color_key: Option<u32>,
+    /// Opaque surfaces are copied; alpha-blended ones are mixed by alpha
+    blend_mode: SurfaceBlendMode,
+    /// Inside a BeginFrame/EndFrame batch; commits are deferred to EndFrame
+    in_frame: bool,
 }
 
+/// Buffer age slots. A surface has one, two or three buffers: a lone front
+/// buffer is drawn over in place, a back buffer is swapped or copied with
+/// the front, and a spare rotates through as a third slot.
+const SURFACE_BUFFER_COUNT: usize = 3;
+
 impl SurfaceState {
     /// Create a new surface state. No kernel buffer allocation - just metadata.
     fn new(width: u32, height: u32, shm_token: u32) -> Self {
         Self {
             shm_token,
+            back_token: 0,
+            spare_token: 0,
+            swap_pending: false,
             width,
             height,
             pending_damage: DamageTracker::new(),
@@ -151,13 +254,21 @@ impl SurfaceState {
             window_state: WINDOW_STATE_NORMAL,
             frame_callback_pending: false,
             last_present_time_ms: 0,
+            frame_presented: false,
             role: SurfaceRole::None,
             parent_task: None,
             children: [None; MAX_CHILDREN],
             child_count: 0,
             relative_x: 0,
             relative_y: 0,
-            title: [0; 32],
+            title: [0; WINDOW_TITLE_LEN],
+            commit_seq: 0,
+            buffer_last_front: [0; SURFACE_BUFFER_COUNT],
+            front_buffer: 1,
+            back_buffer: 0,
+            color_key: None,
+            blend_mode: SurfaceBlendMode::Opaque,
+            in_frame: false,
         }
     }
 
@@ -165,7 +276,11 @@ impl SurfaceState {
     ///
     /// This is now a zero-copy operation - we just swap damage trackers.
     /// The compositor reads directly from the client's buffer via shm_token.
-    fn commit(&mut self) {
+    /// Surfaces with a back buffer either copy it to the front or, for a
+    /// swap commit, exchange the two tokens. Triple-buffered surfaces
+    /// rotate the spare in instead (see `commit_triple`). A copy is returned
+    /// rather than done, so it can run outside the context lock.
+    fn commit(&mut self) -> Option<BufferCopy> {
         // If client didn't explicitly add damage, assume full surface damage
         // This maintains backwards compatibility with simple clients that don't call damage()
         if self.pending_damage.is_empty() {
@@ -176,6 +291,154 @@ impl SurfaceState {
         core::mem::swap(&mut self.committed_damage, &mut self.pending_damage);
         self.pending_damage.clear();
         self.dirty = true;
+
+        self.commit_seq += 1;
+        let swap = core::mem::take(&mut self.swap_pending);
+        if self.is_triple_buffered() {
+            return self.commit_triple(swap);
+        }
+        if self.back_token == 0 || !swap {
+            // A lone buffer is drawn over in place, and a copy commit leaves
+            // the back buffer holding this frame too: the client's next
+            // buffer always has the frame just committed
+            self.buffer_last_front[self.back_buffer] = self.commit_seq;
+            self.buffer_last_front[self.front_buffer] = self.commit_seq;
+            return (self.back_token != 0).then(|| self.copy_back_to(self.shm_token));
+        }
+
+        // The back buffer the client just drew into becomes the front buffer
+        core::mem::swap(&mut self.shm_token, &mut self.back_token);
+        core::mem::swap(&mut self.front_buffer, &mut self.back_buffer);
+        self.buffer_last_front[self.front_buffer] = self.commit_seq;
+        None
+    }
+
+    fn copy_back_to(&self, dst: u32) -> BufferCopy {
+        BufferCopy {
+            src: self.back_token,
+            dst,
+            bytes: self.buffer_bytes(),
+        }
+    }
+
+    /// Attach a back buffer, or detach it with token 0. A new buffer's
+    /// contents are unknown; once detached the client draws over the front
+    /// buffer, which holds the last frame.
+    fn attach_back(&mut self, token: u32) {
+        self.back_token = token;
+        self.swap_pending = false;
+        self.buffer_last_front[self.back_buffer] = if token == 0 {
+            self.buffer_last_front[self.front_buffer]
+        } else {
+            0
+        };
+    }
+
+    fn attach_spare(&mut self, token: u32) {
+        self.spare_token = token;
+        self.buffer_last_front[self.spare_buffer()] = 0;
+    }
+
+    fn is_triple_buffered(&self) -> bool {
+        self.back_token != 0 && self.spare_token != 0
+    }
+
+    /// Slot index of the spare buffer; the three slots are 0, 1 and 2.
+    fn spare_buffer(&self) -> usize {
+        SURFACE_BUFFER_COUNT - self.front_buffer - self.back_buffer
+    }
+
+    /// Triple-buffered commit: the new front is always a buffer the
+    /// compositor was not reading, and the old front retires to spare.
+    ///
+    /// A swap commit presents the back buffer and hands the spare to the
+    /// client. A copy commit copies the back buffer into the spare and
+    /// presents that, so the client keeps drawing into the same buffer.
+    fn commit_triple(&mut self, swap: bool) -> Option<BufferCopy> {
+        let spare = self.spare_buffer();
+        let mut copy = None;
+        if swap {
+            let ready = self.back_token;
+            self.back_token = self.spare_token;
+            self.spare_token = self.shm_token;
+            self.shm_token = ready;
+            self.front_buffer = self.back_buffer;
+            self.back_buffer = spare;
+        } else {
+            copy = Some(self.copy_back_to(self.spare_token));
+            core::mem::swap(&mut self.shm_token, &mut self.spare_token);
+            self.front_buffer = spare;
+            self.buffer_last_front[self.back_buffer] = self.commit_seq;
+        }
+        self.buffer_last_front[self.front_buffer] = self.commit_seq;
+        copy
+    }
+
+    /// Buffers to reallocate for a resize to `width`x`height`, or None if
+    /// the size is unchanged.
+    fn resize_job(&self, width: u32, height: u32) -> Option<ResizeJob> {
+        if width == self.width && height == self.height {
+            return None;
+        }
+        Some(ResizeJob {
+            old: (self.width, self.height),
+            new: (width, height),
+            tokens: [self.shm_token, self.back_token, self.spare_token],
+            frames: [None, None, None],
+        })
+    }
+
+    /// Swap in the buffers `job` reallocated, keeping the overlapping
+    /// top-left region, and damage the whole surface.
+    ///
+    /// If the surface changed while the job ran or its front buffer could
+    /// not be reallocated, it is left as it was and this returns false; a
+    /// back or spare buffer that could not be is detached instead.
+    fn resize(&mut self, job: &mut ResizeJob) -> bool {
+        let tokens = [self.shm_token, self.back_token, self.spare_token];
+        if (self.width, self.height) != job.old || tokens != job.tokens {
+            return false;
+        }
+        let [front, back, spare] = &mut job.frames;
+        let new = job.new;
+        if self.shm_token != 0 && !install_shm_buffer(self.shm_token, front.take(), new) {
+            return false;
+        }
+        if self.back_token != 0 && !install_shm_buffer(self.back_token, back.take(), new) {
+            self.back_token = 0;
+            self.swap_pending = false;
+        }
+        if self.spare_token != 0 && !install_shm_buffer(self.spare_token, spare.take(), new) {
+            self.spare_token = 0;
+        }
+
+        (self.width, self.height) = new;
+        self.pending_damage.clear();
+        self.committed_damage.set_full_damage();
+        self.dirty = true;
+        // Contents outside the old size are undefined: redraw in full
+        self.buffer_last_front = [0; SURFACE_BUFFER_COUNT];
+        true
+    }
+
+    /// Bytes a buffer needs to hold the whole surface.
+    fn buffer_bytes(&self) -> usize {
+        self.width as usize * self.height as usize * SURFACE_BYTES_PER_PIXEL
+    }
+
+    /// Age of the buffer the client will draw into next, in commits.
+    /// 0 means its contents are undefined and a full redraw is required.
+    fn buffer_age(&self) -> u8 {
+        let last_front = self.buffer_last_front[self.back_buffer];
+        if last_front == 0 {
+            return 0;
+        }
+        let age = self.commit_seq - last_front + 1;
+        if age > MAX_BUFFER_AGE as u64 {
+            0
+        } else {
+            age as u8
+        }
     }
 
     fn add_damage(&mut self, x: i32, y: i32, width: i32, height: i32) {
@@ -187,6 +450,23 @@ impl SurfaceState {
         });
     }
 
+    /// Whether the compositor draws this surface at all.
+    fn is_presented(&self) -> bool {
+        self.visible && self.window_state != WINDOW_STATE_MINIMIZED
+    }
+
+    /// `WindowInfo::flags` describing how the surface composites.
+    fn window_flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.color_key.is_some() {
+            flags |= WINDOW_FLAG_COLOR_KEY;
+        }
+        if self.blend_mode == SurfaceBlendMode::AlphaBlend {
+            flags |= WINDOW_FLAG_ALPHA_BLEND;
+        }
+        flags
+    }
+
     fn export_damage(&self) -> ([DamageRect; MAX_WINDOW_DAMAGE_REGIONS], u8) {
         export_damage_to_window_format(&self.committed_damage)
     }
@@ -200,6 +480,12 @@ struct CompositorContext {
     surfaces: BTreeMap<u32, SurfaceState>,
     queue: VecDeque<ClientOp>,
     next_z_order: u32,
+    /// Task whose surface receives keyboard input (0 = none)
+    focused_task: u32,
+    /// Commits since the compositor last checked; nonzero means recompose
+    compose_requests: u32,
+    /// Back-buffer copies from commits, done once the lock is dropped
+    pending_copies: Vec<BufferCopy>,
 }
 
 impl CompositorContext {
@@ -208,14 +494,37 @@ impl CompositorContext {
             surfaces: BTreeMap::new(),
             queue: VecDeque::new(),
             next_z_order: 1,
+            focused_task: 0,
+            compose_requests: 0,
+            pending_copies: Vec::new(),
+        }
+    }
+
+    /// Commit a surface and flag that the screen needs recomposing.
+    fn commit_surface(&mut self, task_id: u32) {
+        if let Some(surface) = self.surfaces.get_mut(&task_id) {
+            surface.in_frame = false;
+            self.pending_copies.extend(surface.commit());
+            self.compose_requests = self.compose_requests.saturating_add(1);
+        }
+    }
+
+    /// Close batches left open by a previous frame that never sent EndFrame.
+    fn close_stale_frames(&mut self) {
+        let mut compose = 0u32;
+        for surface in self.surfaces.values_mut() {
+            if surface.in_frame {
+                surface.in_frame = false;
+                self.pending_copies.extend(surface.commit());
+                compose += 1;
+            }
         }
+        self.compose_requests = self.compose_requests.saturating_add(compose);
     }
 
     /// Normalize z-order values to prevent overflow.
     /// Called automatically when z-order gets too high.
     fn normalize_z_order(&mut self) {
-        use alloc::vec::Vec;
-
         // Collect (task_id, z_order) pairs
         let mut ordered: Vec<(u32, u32)> = self
             .surfaces
@@ -237,6 +546,99 @@ impl CompositorContext {
         self.next_z_order = (ordered.len() + 1) as u32;
     }
 
+    /// Screen position of a surface.
+    ///
+    /// For subsurfaces: parent position + relative offset.
+    /// For toplevel/popup: window_x/window_y directly.
+    fn absolute_position(&self, surface: &SurfaceState) -> (i32, i32) {
+        if surface.role != SurfaceRole::Subsurface {
+            return (surface.window_x, surface.window_y);
+        }
+        match surface.parent_task.and_then(|id| self.surfaces.get(&id)) {
+            Some(parent) => (
+                parent.window_x + surface.relative_x,
+                parent.window_y + surface.relative_y,
+            ),
+            // No parent (or it is gone): use relative as absolute
+            None => (surface.relative_x, surface.relative_y),
+        }
+    }
+
+    /// Snapshot of a surface as reported to window enumeration.
+    fn window_info(&self, task_id: u32, surface: &SurfaceState) -> WindowInfo {
+        let (abs_x, abs_y) = self.absolute_position(surface);
+
+        // Export damage from committed state
+        let (damage_rects, dmg_count) = surface.export_damage();
+        let mut regions = [WindowDamageRect::default(); MAX_WINDOW_DAMAGE_REGIONS];
+        for i in 0..MAX_WINDOW_DAMAGE_REGIONS {
+            regions[i] = WindowDamageRect {
+                x0: damage_rects[i].x0,
+                y0: damage_rects[i].y0,
+                x1: damage_rects[i].x1,
+                y1: damage_rects[i].y1,
+            };
+        }
+
+        let mut info = WindowInfo {
+            task_id,
+            x: abs_x,
+            y: abs_y,
+            width: surface.width,
+            height: surface.height,
+            state: surface.window_state,
+            damage_count: dmg_count,
+            flags: surface.window_flags(),
+            format: shm_get_declared_format(surface.shm_token)
+                .map_or(WINDOW_FORMAT_NATIVE, |f| f as u8),
+            shm_token: surface.shm_token,
+            color_key: surface.color_key.unwrap_or(0),
+            damage_regions: regions,
+            ..WindowInfo::default()
+        };
+        info.set_title(&surface.title);
+        info
+    }
+
+    /// Composite every presented surface onto `target`, bottom to top.
+    ///
+    /// Surfaces without a declared pixel format are taken to be in the
+    /// target's format; surfaces whose buffer is missing are skipped.
+    fn compose_into(&self, target: &mut CompositeTarget<'_>) {
+        let mut order: alloc::vec::Vec<&SurfaceState> = self
+            .surfaces
+            .values()
+            .filter(|surface| surface.is_presented())
+            .collect();
+        order.sort_by_key(|surface| surface.z_order);
+
+        for surface in order {
+            let format = shm_get_declared_format(surface.shm_token).unwrap_or(target.format);
+            let bytes = surface.width as usize
+                * surface.height as usize
+                * format.bytes_per_pixel() as usize;
+            let (phys, size, _owner) = shm_get_buffer_info(surface.shm_token);
+            if phys.is_null() || size < bytes {
+                continue;
+            }
+            let Some(virt) = phys.to_virt_checked() else {
+                continue;
+            };
+            let data = unsafe { core::slice::from_raw_parts(virt.as_u64() as *const u8, bytes) };
+            let (x, y) = self.absolute_position(surface);
+            target.blit(&CompositeSource {
+                data,
+                format,
+                x,
+                y,
+                width: surface.width,
+                height: surface.height,
+                color_key: surface.color_key,
+                blend_mode: surface.blend_mode,
+            });
+        }
+    }
+
     /// Check if z-order normalization is needed (approaching u32 overflow)
     fn needs_z_order_normalization(&self) -> bool {
         self.next_z_order > 0xFFFF_0000
@@ -260,6 +662,126 @@ pub fn surface_commit(task_id: u32) -> Result<(), CompositorError> {
     Ok(())
 }
 
+/// Commit by swapping front and back buffers. Called by CLIENT tasks.
+///
+/// The buffer the client just drew becomes the front buffer and the old
+/// front becomes the new back buffer, so nothing is copied but the back
+/// buffer's contents are one frame older afterwards (see buffer age). For
+/// clients that redraw the whole surface each frame. Without an attached
+/// back buffer this is a plain commit.
+pub fn surface_commit_swap(task_id: u32) -> Result<(), CompositorError> {
+    let mut ctx = CONTEXT.lock();
+    ctx.queue.push_back(ClientOp::CommitSwap { task_id });
+    Ok(())
+}
+
+/// Attach a spare buffer to a double-buffered surface, making it triple
+/// buffered. Called by CLIENT tasks.
+///
+/// Commits then never write to or present the buffer the compositor read
+/// last: the client's frame lands in the buffer that was neither presented
+/// nor drawn into, and the old front becomes the spare. Surfaces short on
+/// memory simply never attach one. A surface that already has a spare, or
+/// has one queued, rejects a second.
+pub fn surface_attach_spare_buffer(task_id: u32, shm_token: u32) -> Result<(), CompositorError> {
+    if shm_token == 0 {
+        return Err(CompositorError::InvalidToken);
+    }
+    let mut ctx = CONTEXT.lock();
+    let queued = ctx
+        .queue
+        .iter()
+        .any(|op| matches!(*op, ClientOp::AttachSpareBuffer { task_id: t, .. } if t == task_id));
+    if queued
+        || ctx
+            .surfaces
+            .get(&task_id)
+            .is_some_and(|s| s.spare_token != 0)
+    {
+        return Err(CompositorError::InvalidArgument);
+    }
+    ctx.queue
+        .push_back(ClientOp::AttachSpareBuffer { task_id, shm_token });
+    Ok(())
+}
+
+/// Detach the spare buffer and return its token. Called by CLIENT tasks.
+///
+/// Commits rotate tokens through the three roles, so the client cannot
+/// know which of its buffers is spare; the returned token is the one the
+/// surface no longer uses and the only one that is safe to free. The
+/// detach applies at once, so it fails with `Busy` while commits or other
+/// buffer changes for the surface are still queued; retry once they have
+/// been drained (for example after the next frame callback).
+pub fn surface_detach_spare_buffer(task_id: u32) -> Result<u32, CompositorError> {
+    let mut ctx = CONTEXT.lock();
+    if ctx.queue.iter().any(|op| op.moves_buffers_of(task_id)) {
+        return Err(CompositorError::Busy);
+    }
+    let surface = ctx
+        .surfaces
+        .get_mut(&task_id)
+        .ok_or(CompositorError::SurfaceNotFound)?;
+    match core::mem::take(&mut surface.spare_token) {
+        0 => Err(CompositorError::BufferNotFound),
+        token => Ok(token),
+    }
+}
+
+/// Resize a surface. Called by CLIENT tasks.
+///
+/// When the compositor next drains its queue the surface's buffers are
+/// reallocated at the new size, clamped to the framebuffer. Content in the
+/// overlapping top-left region is kept and the whole surface is damaged.
+/// Buffer tokens stay the same. Existing mappings keep showing the old
+/// frames, so a frame being drawn is not cut short; the task gets a
+/// `SurfaceResize` input event with the final size and must then unmap and
+/// map the buffers again. Zero dimensions are rejected.
+pub fn surface_resize(task_id: u32, width: u32, height: u32) -> Result<(), CompositorError> {
+    if width == 0 || height == 0 {
+        return Err(CompositorError::InvalidArgument);
+    }
+    let mut ctx = CONTEXT.lock();
+    ctx.queue.push_back(ClientOp::Resize {
+        task_id,
+        width,
+        height,
+    });
+    Ok(())
+}
+
+/// Attach a back buffer to a surface. Called by CLIENT tasks.
+///
+/// The client then draws into the back buffer (see `surface_back_buffer`)
+/// and each commit either copies it to the front buffer or, with
+/// `surface_commit_swap`, exchanges the two. Token 0 detaches it again.
+pub fn surface_attach_back_buffer(task_id: u32, shm_token: u32) -> Result<(), CompositorError> {
+    let mut ctx = CONTEXT.lock();
+    ctx.queue
+        .push_back(ClientOp::AttachBackBuffer { task_id, shm_token });
+    Ok(())
+}
+
+/// Open a damage batch. Called by CLIENT tasks.
+///
+/// Damage and commits queued until the matching `surface_end_frame` are
+/// accumulated and committed once, so the compositor recomposes once per
+/// client frame instead of once per update. A batch still open when the
+/// compositor starts its next frame is closed implicitly.
+pub fn surface_begin_frame(task_id: u32) -> Result<(), CompositorError> {
+    let mut ctx = CONTEXT.lock();
+    ctx.queue.push_back(ClientOp::BeginFrame { task_id });
+    Ok(())
+}
+
+/// Close a damage batch and commit it. Called by CLIENT tasks.
+/// Ignored when no batch is open, including one already closed as stale.
+pub fn surface_end_frame(task_id: u32) -> Result<(), CompositorError> {
+    let mut ctx = CONTEXT.lock();
+    ctx.queue.push_back(ClientOp::EndFrame { task_id });
+    Ok(())
+}
+
 /// Register a surface for a task when it calls surface_attach.
 /// Called by CLIENT tasks. Enqueues the registration for processing by compositor.
 pub fn register_surface_for_task(
@@ -302,6 +824,9 @@ pub fn drain_queue() {
     let mut ctx = CONTEXT.lock();
     let mut processed = 0;
 
+    // A BeginFrame without EndFrame only holds its damage for one frame
+    ctx.close_stale_frames();
+
     while processed < MAX_OPS_PER_DRAIN {
         let op = match ctx.queue.pop_front() {
             Some(op) => op,
@@ -310,8 +835,85 @@ pub fn drain_queue() {
 
         match op {
             ClientOp::Commit { task_id } => {
+                // Inside a batch the commit is folded into EndFrame
+                let in_frame = ctx.surfaces.get(&task_id).is_some_and(|s| s.in_frame);
+                if !in_frame {
+                    ctx.commit_surface(task_id);
+                }
+            }
+            ClientOp::CommitSwap { task_id } => {
+                // Inside a batch the swap is applied by EndFrame
+                let in_frame = match ctx.surfaces.get_mut(&task_id) {
+                    Some(surface) => {
+                        surface.swap_pending = true;
+                        surface.in_frame
+                    }
+                    None => false,
+                };
+                if !in_frame {
+                    ctx.commit_surface(task_id);
+                }
+            }
+            ClientOp::AttachBackBuffer { task_id, shm_token } => {
+                if let Some(surface) = ctx.surfaces.get_mut(&task_id) {
+                    surface.attach_back(shm_token);
+                }
+            }
+            ClientOp::AttachSpareBuffer { task_id, shm_token } => {
                 if let Some(surface) = ctx.surfaces.get_mut(&task_id) {
-                    surface.commit();
+                    surface.attach_spare(shm_token);
+                }
+            }
+            ClientOp::Resize {
+                task_id,
+                width,
+                height,
+            } => {
+                let (width, height) = clamp_to_display(width, height);
+                let job = ctx
+                    .surfaces
+                    .get(&task_id)
+                    .and_then(|surface| surface.resize_job(width, height));
+                let Some(mut job) = job else {
+                    processed += 1;
+                    continue;
+                };
+                // Allocating and filling the new buffers touches whole
+                // buffers; do it unlocked, after the copies queued so far
+                let copies = core::mem::take(&mut ctx.pending_copies);
+                drop(ctx);
+                run_buffer_copies(copies);
+                job.prepare();
+                ctx = CONTEXT.lock();
+
+                let resized = ctx
+                    .surfaces
+                    .get_mut(&task_id)
+                    .is_some_and(|surface| surface.resize(&mut job));
+                if resized {
+                    ctx.compose_requests = ctx.compose_requests.saturating_add(1);
+                    input_push(
+                        task_id,
+                        InputEvent::surface_resize(width, height, get_timestamp_ms()),
+                    );
+                }
+            }
+            ClientOp::BeginFrame { task_id } => {
+                // Nested BeginFrame implicitly closes the open batch
+                let in_frame = ctx.surfaces.get(&task_id).is_some_and(|s| s.in_frame);
+                if in_frame {
+                    ctx.commit_surface(task_id);
+                }
+                if let Some(surface) = ctx.surfaces.get_mut(&task_id) {
+                    surface.in_frame = true;
+                }
+            }
+            ClientOp::EndFrame { task_id } => {
+                // An EndFrame whose batch was never opened, or was already
+                // closed as stale, has nothing left to commit
+                let in_frame = ctx.surfaces.get(&task_id).is_some_and(|s| s.in_frame);
+                if in_frame {
+                    ctx.commit_surface(task_id);
                 }
             }
             ClientOp::Register {
@@ -342,6 +944,10 @@ pub fn drain_queue() {
             }
             ClientOp::Unregister { task_id } => {
                 ctx.surfaces.remove(&task_id);
+                if ctx.focused_task == task_id {
+                    ctx.focused_task = 0;
+                    input_set_keyboard_focus(0);
+                }
             }
             ClientOp::RequestFrameCallback { task_id } => {
                 if let Some(surface) = ctx.surfaces.get_mut(&task_id) {
@@ -419,10 +1025,40 @@ pub fn drain_queue() {
                     surface.dirty = true;
                 }
             }
+            ClientOp::SetColorKey { task_id, key } => {
+                if let Some(surface) = ctx.surfaces.get_mut(&task_id) {
+                    surface.color_key = key;
+                    surface.dirty = true;
+                }
+            }
+            ClientOp::SetBlendMode { task_id, mode } => {
+                if let Some(surface) = ctx.surfaces.get_mut(&task_id) {
+                    surface.blend_mode = mode;
+                    surface.dirty = true;
+                }
+            }
         }
         processed += 1;
     }
     // Any remaining ops are processed next frame
+
+    // Copy commits touch whole buffers; do them with interrupts back on
+    let copies = core::mem::take(&mut ctx.pending_copies);
+    drop(ctx);
+    run_buffer_copies(copies);
+}
+
+fn run_buffer_copies(copies: Vec<BufferCopy>) {
+    for copy in copies {
+        copy_shm_buffer(copy.src, copy.dst, copy.bytes);
+    }
+}
+
+/// Number of commits since the last call, clearing the count.
+/// IMMEDIATE - called by COMPOSITOR only; nonzero means the screen needs
+/// recomposing.
+pub fn compositor_take_compose_requests() -> u32 {
+    core::mem::take(&mut CONTEXT.lock().compose_requests)
 }
 
 /// Set window position. IMMEDIATE - called by COMPOSITOR only.
@@ -467,9 +1103,80 @@ pub fn surface_raise_window(task_id: u32) -> Result<(), CompositorError> {
     if let Some(surface) = ctx.surfaces.get_mut(&task_id) {
         surface.z_order = new_z;
     }
+
+    // The raised window is the one the user is interacting with
+    ctx.focused_task = task_id;
+    drop(ctx);
+    input_set_keyboard_focus(task_id);
     Ok(())
 }
 
+/// Give keyboard focus to a task's surface, or clear it with 0.
+/// IMMEDIATE - called by COMPOSITOR only.
+///
+/// Decoded keyboard events are routed to the focused task's input queue only;
+/// unfocused windows receive nothing.
+pub fn surface_set_focus(task_id: u32) -> Result<(), CompositorError> {
+    let mut ctx = CONTEXT.lock();
+    if task_id != 0 && !ctx.surfaces.contains_key(&task_id) {
+        return Err(CompositorError::SurfaceNotFound);
+    }
+    ctx.focused_task = task_id;
+    drop(ctx);
+    input_set_keyboard_focus(task_id);
+    Ok(())
+}
+
+/// Render all visible windows into `target` in stacking order.
+/// IMMEDIATE - called by COMPOSITOR only.
+///
+/// Works on any in-memory buffer, e.g. for screenshots or headless
+/// rendering; the desktop itself is drawn by the userland compositor.
+pub fn compositor_compose_into(target: &mut CompositeTarget<'_>) {
+    CONTEXT.lock().compose_into(target);
+}
+
+/// Task whose surface currently has keyboard focus (0 = none).
+pub fn surface_get_focus() -> u32 {
+    CONTEXT.lock().focused_task
+}
+
+fn require_surface(task_id: u32) -> Result<(), CompositorError> {
+    if CONTEXT.lock().surfaces.contains_key(&task_id) {
+        Ok(())
+    } else {
+        Err(CompositorError::SurfaceNotFound)
+    }
+}
+
+/// Deliver an input event to a window's queue. IMMEDIATE - called by COMPOSITOR only.
+///
+/// Returns `Ok(false)` if the event was discarded because the queue is full
+/// and its policy is `DropNewest` (or no queue could be created).
+pub fn surface_push_input(task_id: u32, event: InputEvent) -> Result<bool, CompositorError> {
+    require_surface(task_id)?;
+    Ok(input_push(task_id, event))
+}
+
+/// Take the oldest queued input event for a window.
+pub fn surface_poll_input(task_id: u32) -> Option<InputEvent> {
+    input_poll(task_id)
+}
+
+/// Choose whether a window's full input queue discards its oldest events
+/// (the default) or new ones. IMMEDIATE - called by COMPOSITOR only.
+pub fn surface_set_input_overflow(
+    task_id: u32,
+    policy: InputOverflowPolicy,
+) -> Result<(), CompositorError> {
+    require_surface(task_id)?;
+    if input_set_overflow_policy(task_id, policy) {
+        Ok(())
+    } else {
+        Err(CompositorError::OutOfMemory)
+    }
+}
+
 /// Enumerate all visible windows. IMMEDIATE - called by COMPOSITOR only.
 ///
 /// Note: Damage is NOT cleared here. It persists until the next commit replaces it.
@@ -496,54 +1203,7 @@ pub fn surface_enumerate_windows(out_buffer: *mut WindowInfo, max_count: u32) ->
             continue;
         }
 
-        // Calculate absolute position
-        // For subsurfaces: parent position + relative offset
-        // For toplevel/popup: use window_x/window_y directly
-        let (abs_x, abs_y) = if surface.role == SurfaceRole::Subsurface {
-            if let Some(parent_id) = surface.parent_task {
-                if let Some(parent) = ctx.surfaces.get(&parent_id) {
-                    (
-                        parent.window_x + surface.relative_x,
-                        parent.window_y + surface.relative_y,
-                    )
-                } else {
-                    // Parent not found, fall back to relative as absolute
-                    (surface.relative_x, surface.relative_y)
-                }
-            } else {
-                // No parent set, use relative as absolute
-                (surface.relative_x, surface.relative_y)
-            }
-        } else {
-            (surface.window_x, surface.window_y)
-        };
-
-        // Export damage from committed state
-        let (damage_rects, dmg_count) = surface.export_damage();
-        let mut regions = [WindowDamageRect::default(); MAX_WINDOW_DAMAGE_REGIONS];
-        for i in 0..MAX_WINDOW_DAMAGE_REGIONS {
-            regions[i] = WindowDamageRect {
-                x0: damage_rects[i].x0,
-                y0: damage_rects[i].y0,
-                x1: damage_rects[i].x1,
-                y1: damage_rects[i].y1,
-            };
-        }
-
-        unsafe {
-            let info = &mut *out_buffer.add(count as usize);
-            info.task_id = task_id;
-            info.x = abs_x;
-            info.y = abs_y;
-            info.width = surface.width;
-            info.height = surface.height;
-            info.state = surface.window_state;
-            info.damage_count = dmg_count;
-            info._padding = [0; 2];
-            info.shm_token = surface.shm_token;
-            info.damage_regions = regions;
-            info.title = surface.title;
-        }
+        unsafe { *out_buffer.add(count as usize) = ctx.window_info(task_id, surface) };
 
         // Note: We no longer clear damage here. The next commit will replace it.
         // This ensures damage isn't lost if the compositor fails to render.
@@ -557,6 +1217,116 @@ pub fn surface_enumerate_windows(out_buffer: *mut WindowInfo, max_count: u32) ->
     count
 }
 
+/// List visible windows for any client (taskbars, window switchers).
+///
+/// Fills `out` with the visible windows starting at the `start`-th one, in
+/// the same order as `surface_enumerate_windows`, and returns how many were
+/// written. Buffer tokens and damage are compositor-private and reported as
+/// empty.
+pub fn surface_list_windows(start: u32, out: &mut [WindowInfo]) -> u32 {
+    let ctx = CONTEXT.lock();
+    let visible = ctx.surfaces.iter().filter(|(_, surface)| surface.visible);
+    let mut count = 0u32;
+    for ((&task_id, surface), slot) in visible.skip(start as usize).zip(out.iter_mut()) {
+        *slot = WindowInfo {
+            shm_token: 0,
+            damage_count: 0,
+            damage_regions: Default::default(),
+            ..ctx.window_info(task_id, surface)
+        };
+        count += 1;
+    }
+    count
+}
+
+// =============================================================================
+// Presentation
+// =============================================================================
+
+/// Whether there is a framebuffer to present to.
+///
+/// Without one the compositor runs headless: surfaces can still be created,
+/// committed and enumerated, but presenting fails with `NoFramebuffer`.
+pub fn compositor_has_framebuffer() -> bool {
+    framebuffer::get_display_info().is_some()
+}
+
+/// Present a client buffer to the screen. IMMEDIATE - called by COMPOSITOR only.
+///
+/// The buffer is held across the flip, so it cannot be freed between the
+/// lookup and scanout moving onto it; once it is, freeing it moves scanout
+/// back to the framebuffer (see `framebuffer_release_scanout`).
+pub fn compositor_present(shm_token: u32) -> Result<(), CompositorError> {
+    if !compositor_has_framebuffer() {
+        return Err(CompositorError::NoFramebuffer);
+    }
+    let rc = shm_with_buffer(shm_token, |phys, size| {
+        if phys.is_null() || size == 0 {
+            return Err(CompositorError::InvalidToken);
+        }
+        Ok(framebuffer::fb_flip_zero_copy(phys, size))
+    })
+    .ok_or(CompositorError::InvalidToken)??;
+    if rc != 0 {
+        return Err(CompositorError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Copy the screen into `out` as BGRA8888, rows `out_stride` bytes apart.
+///
+/// `out` must hold the whole frame (see `DisplayInfo::capture_size`).
+/// Returns the captured width and height.
+pub fn compositor_capture(
+    out: &mut [u8],
+    out_stride: usize,
+) -> Result<(u32, u32), CompositorError> {
+    let info = framebuffer::get_display_info().ok_or(CompositorError::NoFramebuffer)?;
+    let needed = info
+        .capture_size(out_stride)
+        .ok_or(CompositorError::InvalidArgument)?;
+    if out.len() < needed {
+        return Err(CompositorError::InvalidArgument);
+    }
+    compositor_capture_rows(0, out, out_stride)?;
+    Ok((info.width, info.height))
+}
+
+/// Capture as many whole rows from `first_row` down as fit in `out`, as
+/// BGRA8888 with rows `out_stride` bytes apart. Returns the rows copied.
+///
+/// Lets callers stage a capture through a small buffer, one batch of rows
+/// at a time.
+pub fn compositor_capture_rows(
+    first_row: u32,
+    out: &mut [u8],
+    out_stride: usize,
+) -> Result<u32, CompositorError> {
+    let fb = framebuffer::snapshot().ok_or(CompositorError::NoFramebuffer)?;
+    if out_stride < fb.width() as usize * 4 {
+        return Err(CompositorError::InvalidArgument);
+    }
+    if first_row >= fb.height() {
+        return Ok(0);
+    }
+
+    let pitch = fb.pitch() as usize;
+    let rows = (fb.height() - first_row) as usize;
+    let src = unsafe {
+        core::slice::from_raw_parts(fb.base_ptr().add(first_row as usize * pitch), rows * pitch)
+    };
+    let copied = framebuffer::capture_pixels(
+        src,
+        fb.width() as usize,
+        rows,
+        pitch,
+        fb.info.format,
+        out,
+        out_stride,
+    );
+    Ok(copied as u32)
+}
+
 // =============================================================================
 // Frame Callback Protocol (Wayland wl_surface.frame)
 // =============================================================================
@@ -572,11 +1342,17 @@ pub fn surface_request_frame_callback(task_id: u32) -> Result<(), CompositorErro
 
 /// Mark frame as done for all surfaces with pending callbacks.
 /// Called by COMPOSITOR after presenting a frame.
-/// Sets last_present_time_ms for surfaces that had frame_callback_pending.
+///
+/// Frame callbacks fire for every surface, shown or not, so a minimized
+/// client is still paced. Only surfaces that were composited get the
+/// `surface_frame_done` flag.
 pub fn surface_mark_frames_done(present_time_ms: u64) {
     let mut ctx = CONTEXT.lock();
 
     for surface in ctx.surfaces.values_mut() {
+        if surface.is_presented() {
+            surface.frame_presented = true;
+        }
         if surface.frame_callback_pending {
             surface.last_present_time_ms = present_time_ms;
             surface.frame_callback_pending = false;
@@ -584,6 +1360,18 @@ pub fn surface_mark_frames_done(present_time_ms: u64) {
     }
 }
 
+/// Whether the surface made it to the screen since the last call.
+/// Called by CLIENT tasks; reading consumes the flag (one-shot).
+///
+/// Clients can wait for this before drawing the next frame so they never
+/// render faster than the compositor presents.
+pub fn surface_frame_done(task_id: u32) -> bool {
+    let mut ctx = CONTEXT.lock();
+    ctx.surfaces
+        .get_mut(&task_id)
+        .is_some_and(|surface| core::mem::take(&mut surface.frame_presented))
+}
+
 /// Poll for frame completion. Called by CLIENT tasks.
 /// Returns the presentation timestamp if frame was done, 0 if still pending.
 /// Clears last_present_time_ms after returning it (one-shot).
@@ -628,15 +1416,221 @@ pub fn surface_add_damage(
 
 /// Get the buffer age for a surface. Called by CLIENT tasks.
 ///
-/// NOTE: With the Wayland-aligned buffer ownership model, the kernel does not
-/// track buffer content. This always returns 0 (undefined content).
+/// The kernel never sees buffer contents, but it does see every commit and
+/// every buffer the surface has attached. The age is how many commits ago
+/// the buffer the client draws into next was last the front buffer, so a
+/// client only needs to redraw the damage of that many frames: 1 for a lone
+/// buffer or after a copy commit, and the buffer count once swap commits
+/// settle in.
 ///
-/// For client-side double-buffering with proper buffer age, clients would need
-/// to manage multiple buffers themselves. This is a potential future enhancement.
-pub fn surface_get_buffer_age(_task_id: u32) -> u8 {
-    // Buffer age is not tracked by kernel - client manages buffer content
-    // Return 0 = undefined content (client must redraw everything)
-    0
+/// Returns 0 (undefined content, full redraw) for unknown surfaces, before the
+/// next buffer has ever been presented, and once the age exceeds
+/// `MAX_BUFFER_AGE`.
+pub fn surface_get_buffer_age(task_id: u32) -> u8 {
+    let ctx = CONTEXT.lock();
+    ctx.surfaces
+        .get(&task_id)
+        .map_or(0, |surface| surface.buffer_age())
+}
+
+/// Token of the buffer a double-buffered client should draw into next,
+/// or 0 if the surface is unknown or has no back buffer.
+pub fn surface_back_buffer(task_id: u32) -> u32 {
+    let ctx = CONTEXT.lock();
+    ctx.surfaces
+        .get(&task_id)
+        .map_or(0, |surface| surface.back_token)
+}
+
+// =============================================================================
+// Thumbnails (taskbar previews of minimized windows)
+// =============================================================================
+
+/// Bytes per pixel of client surface buffers (see `surface_attach`).
+const SURFACE_BYTES_PER_PIXEL: usize = 4;
+
+/// Fit `width`x`height` inside `max_w`x`max_h`, preserving aspect ratio.
+/// Surfaces that already fit are not upscaled.
+fn thumbnail_dimensions(width: u32, height: u32, max_w: u32, max_h: u32) -> (u32, u32) {
+    if width <= max_w && height <= max_h {
+        return (width, height);
+    }
+    // Compare width/max_w against height/max_h without dividing
+    if (width as u64) * (max_h as u64) > (height as u64) * (max_w as u64) {
+        let h = ((height as u64) * (max_w as u64) / (width as u64)).max(1);
+        (max_w, h as u32)
+    } else {
+        let w = ((width as u64) * (max_h as u64) / (height as u64)).max(1);
+        (w as u32, max_h)
+    }
+}
+
+/// Downscale a surface's buffer into `out` with nearest-neighbor sampling.
+///
+/// The thumbnail is written tightly packed (pitch = width * bytes per pixel)
+/// in the surface's declared pixel format, 4 bytes per pixel when none was
+/// declared. The buffer is sampled with the compositor lock held, so it
+/// cannot be resized or detached mid-copy. Returns the thumbnail dimensions,
+/// or `None` if the surface or its buffer is gone, the bounds are zero, or
+/// `out` is too small.
+pub fn surface_generate_thumbnail(
+    task_id: u32,
+    max_w: u32,
+    max_h: u32,
+    out: &mut [u8],
+) -> Option<(u32, u32)> {
+    if max_w == 0 || max_h == 0 {
+        return None;
+    }
+
+    let ctx = CONTEXT.lock();
+    let surface = ctx.surfaces.get(&task_id)?;
+    let (width, height) = (surface.width, surface.height);
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let bytes_pp = shm_get_declared_format(surface.shm_token)
+        .map_or(SURFACE_BYTES_PER_PIXEL, |f| f.bytes_per_pixel() as usize);
+    let (phys, size, _owner) = shm_get_buffer_info(surface.shm_token);
+    let src_pitch = width as usize * bytes_pp;
+    let src_len = src_pitch * height as usize;
+    if phys.is_null() || size < src_len {
+        return None;
+    }
+    let virt = phys.to_virt_checked()?;
+    let src = unsafe { core::slice::from_raw_parts(virt.as_u64() as *const u8, src_len) };
+
+    let (thumb_w, thumb_h) = thumbnail_dimensions(width, height, max_w, max_h);
+    let dst_pitch = thumb_w as usize * bytes_pp;
+    if out.len() < dst_pitch * thumb_h as usize {
+        return None;
+    }
+
+    for ty in 0..thumb_h as usize {
+        let sy = ty * height as usize / thumb_h as usize;
+        let src_row = &src[sy * src_pitch..(sy + 1) * src_pitch];
+        let dst_row = &mut out[ty * dst_pitch..(ty + 1) * dst_pitch];
+        for (tx, dst) in dst_row.chunks_exact_mut(bytes_pp).enumerate() {
+            let sx = tx * width as usize / thumb_w as usize;
+            dst.copy_from_slice(&src_row[sx * bytes_pp..(sx + 1) * bytes_pp]);
+        }
+    }
+
+    Some((thumb_w, thumb_h))
+}
+
+/// Clamp surface dimensions to the framebuffer; unchanged when headless.
+fn clamp_to_display(width: u32, height: u32) -> (u32, u32) {
+    match framebuffer::get_display_info() {
+        Some(info) => (width.min(info.width), height.min(info.height)),
+        None => (width, height),
+    }
+}
+
+/// Surface buffers reallocated for a resize. Built under the context
+/// lock, filled without it, then swapped in by `SurfaceState::resize`.
+struct ResizeJob {
+    old: (u32, u32),
+    new: (u32, u32),
+    /// Front, back and spare tokens when the job was built; 0 = none
+    tokens: [u32; SURFACE_BUFFER_COUNT],
+    frames: [Option<ShmFrames>; SURFACE_BUFFER_COUNT],
+}
+
+impl ResizeJob {
+    /// Allocate the new frames and copy the old contents into them.
+    /// A buffer that cannot be allocated is left without frames.
+    fn prepare(&mut self) {
+        for (token, frames) in self.tokens.iter().zip(self.frames.iter_mut()) {
+            if *token != 0 {
+                *frames = prepare_shm_buffer(*token, self.old, self.new);
+            }
+        }
+    }
+}
+
+impl Drop for ResizeJob {
+    fn drop(&mut self) {
+        for frames in self.frames.iter_mut().filter_map(Option::take) {
+            frames.free();
+        }
+    }
+}
+
+/// Fresh frames for a surface buffer at `new` (width, height), holding the
+/// rows and columns it shares with its current `old` contents.
+fn prepare_shm_buffer(token: u32, old: (u32, u32), new: (u32, u32)) -> Option<ShmFrames> {
+    let bytes = new.0 as usize * new.1 as usize * SURFACE_BYTES_PER_PIXEL;
+    let frames = shm_alloc_frames(bytes as u64).ok()?;
+
+    let (old_phys, old_size, _) = shm_get_buffer_info(token);
+    let src_pitch = old.0 as usize * SURFACE_BYTES_PER_PIXEL;
+    let dst_pitch = new.0 as usize * SURFACE_BYTES_PER_PIXEL;
+    let row_bytes = src_pitch.min(dst_pitch);
+    // The old buffer may have been smaller than its surface claimed
+    let rows = (old.1.min(new.1) as usize).min(old_size.checked_div(src_pitch).unwrap_or(0));
+    if let (Some(src), Some(dst)) = (
+        old_phys.to_virt_checked(),
+        frames.phys_addr.to_virt_checked(),
+    ) {
+        let src = src.as_u64() as *const u8;
+        let dst = dst.as_u64() as *mut u8;
+        for y in 0..rows {
+            unsafe {
+                core::ptr::copy_nonoverlapping(
+                    src.add(y * src_pitch),
+                    dst.add(y * dst_pitch),
+                    row_bytes,
+                );
+            }
+        }
+    }
+    Some(frames)
+}
+
+/// Put `frames` behind a surface buffer sized `new`. The old frames stay
+/// behind existing mappings until they are unmapped. Returns false if there
+/// are no frames or the buffer is gone.
+fn install_shm_buffer(token: u32, frames: Option<ShmFrames>, new: (u32, u32)) -> bool {
+    let Some(frames) = frames else {
+        return false;
+    };
+    let (_, _, owner) = shm_get_buffer_info(token);
+    let Ok(old_frames) = shm_replace_frames(owner, token, frames) else {
+        return false;
+    };
+    shm_retire_frames(token, old_frames);
+    let _ = surface_attach(owner, token, new.0, new.1);
+    true
+}
+
+/// Back-buffer copy requested by a commit.
+struct BufferCopy {
+    src: u32,
+    dst: u32,
+    bytes: usize,
+}
+
+/// Copy the first `bytes` of one shm buffer into another.
+/// Skipped if either buffer is missing or too small, or they are the same.
+fn copy_shm_buffer(src_token: u32, dst_token: u32, bytes: usize) {
+    let (src_phys, src_size, _) = shm_get_buffer_info(src_token);
+    let (dst_phys, dst_size, _) = shm_get_buffer_info(dst_token);
+    if src_token == dst_token
+        || src_phys.is_null()
+        || dst_phys.is_null()
+        || src_size < bytes
+        || dst_size < bytes
+    {
+        return;
+    }
+    let (Some(src), Some(dst)) = (src_phys.to_virt_checked(), dst_phys.to_virt_checked()) else {
+        return;
+    };
+    unsafe {
+        core::ptr::copy_nonoverlapping(src.as_u64() as *const u8, dst.as_u64() as *mut u8, bytes);
+    }
 }
 
 // =============================================================================
@@ -687,17 +1681,40 @@ pub fn surface_set_relative_position(
 
 /// Set the window title. Called by CLIENT tasks.
 /// Title is UTF-8, max 31 characters (null-terminated in 32-byte buffer).
+/// Longer titles are truncated; trailing NUL padding is ignored, but a NUL
+/// inside the text is rejected rather than cutting the title short.
 pub fn surface_set_title(task_id: u32, title: &[u8]) -> Result<(), CompositorError> {
-    let mut title_buf = [0u8; 32];
-    let copy_len = title.len().min(31); // Leave room for null terminator
-    title_buf[..copy_len].copy_from_slice(&title[..copy_len]);
-    // Ensure null termination
-    title_buf[copy_len] = 0;
+    let title = window_title_text(title).ok_or(CompositorError::InvalidArgument)?;
+    let mut title_buf = [0u8; WINDOW_TITLE_LEN];
+    copy_window_title(&mut title_buf, title);
+    queue_title(task_id, title_buf);
+    Ok(())
+}
 
+/// Queue a raw title buffer as-is; enumeration re-terminates it on the way out.
+pub(crate) fn queue_title(task_id: u32, title: [u8; WINDOW_TITLE_LEN]) {
     let mut ctx = CONTEXT.lock();
-    ctx.queue.push_back(ClientOp::SetTitle {
-        task_id,
-        title: title_buf,
-    });
+    ctx.queue.push_back(ClientOp::SetTitle { task_id, title });
+}
+
+/// Set or clear the surface's transparent color key. Called by CLIENT tasks.
+/// Pixels whose RGB bits match the key are skipped when compositing, letting
+/// whatever is underneath show through.
+pub fn surface_set_color_key(task_id: u32, key: Option<u32>) -> Result<(), CompositorError> {
+    let mut ctx = CONTEXT.lock();
+    ctx.queue.push_back(ClientOp::SetColorKey { task_id, key });
+    Ok(())
+}
+
+/// Choose how the surface is combined with windows beneath it (a
+/// `SurfaceBlendMode` value). Called by CLIENT tasks.
+///
+/// Alpha-blended surfaces are mixed by their per-pixel alpha and never
+/// count as covering what lies beneath them; opaque ones are copied as-is.
+pub fn surface_set_blend_mode(task_id: u32, mode: u8) -> Result<(), CompositorError> {
+    let mode = SurfaceBlendMode::from_u8(mode).ok_or(CompositorError::InvalidArgument)?;
+    let mut ctx = CONTEXT.lock();
+    ctx.queue
+        .push_back(ClientOp::SetBlendMode { task_id, mode });
     Ok(())
 }
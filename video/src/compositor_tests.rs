@@ -0,0 +1,2049 @@
+//! Compositor context tests - surface lifecycle and commit bookkeeping.
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use slopos_abi::addr::{PhysAddr, VirtAddr};
+use slopos_abi::damage::{DamageRect, DamageTracker, MergeStrategy};
+use slopos_abi::syscall::SYSCALL_SURFACE_BACK_BUFFER;
+use slopos_abi::{
+    CompositeSource, CompositeTarget, CompositorError, DisplayInfo, InputEvent, InputEventType,
+    InputOverflowPolicy, LayerTarget, MAX_EVENTS_PER_TASK, OverlayLayer, PixelFormat,
+    SurfaceBlendMode, WINDOW_FORMAT_NATIVE, WINDOW_STATE_MINIMIZED, WINDOW_STATE_NORMAL,
+    WINDOW_TITLE_LEN, WindowInfo, pixel_ops,
+};
+use slopos_core::syscall::handlers::{capture_to_user, list_windows_to_user, syscall_name};
+use slopos_drivers::input_event::{
+    input_cleanup_task, input_get_keyboard_focus, input_poll, input_route_key_event,
+    input_set_keyboard_focus,
+};
+use slopos_lib::testing::TestResult;
+use slopos_lib::{assert_eq_test, assert_test, klog_info};
+use slopos_mm::hhdm::PhysAddrHhdm;
+use slopos_mm::mm_constants::PAGE_SIZE_4KB;
+use slopos_mm::page_alloc::page_alloc_stats;
+use slopos_mm::shared_memory::{
+    shm_create, shm_create_with_format, shm_destroy, shm_get_buffer_info,
+};
+use slopos_mm::tests_user_copy::UserCopyEnv;
+use slopos_mm::user_copy::{copy_bytes_from_user, copy_bytes_to_user, copy_from_user};
+use slopos_mm::user_ptr::{UserBytes, UserPtr, UserPtrError};
+
+use crate::compositor_context::{
+    compositor_capture, compositor_compose_into, compositor_has_framebuffer, compositor_present,
+    compositor_take_compose_requests, drain_queue, queue_title, register_surface_for_task,
+    surface_add_damage, surface_attach_back_buffer, surface_attach_spare_buffer,
+    surface_back_buffer, surface_begin_frame, surface_commit, surface_commit_swap,
+    surface_detach_spare_buffer, surface_end_frame, surface_enumerate_windows, surface_frame_done,
+    surface_generate_thumbnail, surface_get_buffer_age, surface_get_focus, surface_list_windows,
+    surface_mark_frames_done, surface_poll_frame_done, surface_poll_input, surface_push_input,
+    surface_raise_window, surface_request_frame_callback, surface_resize, surface_set_blend_mode,
+    surface_set_color_key, surface_set_focus, surface_set_input_overflow, surface_set_title,
+    surface_set_window_position, surface_set_window_state, unregister_surface_for_task,
+};
+
+use crate::framebuffer::{FbState, get_display_info, replace_state};
+
+/// Task IDs far above MAX_TASKS so tests never collide with live surfaces.
+const TEST_TASK_BASE: u32 = 0x7E57_0000;
+
+struct SurfaceFixture {
+    task_id: u32,
+    /// Attached shm buffers in front, back, spare order; freed after unregistering.
+    buffers: Vec<ShmPixels>,
+}
+
+impl SurfaceFixture {
+    fn new(task_id: u32, width: u32, height: u32) -> Self {
+        Self::with_token(task_id, width, height, 0)
+    }
+
+    fn with_token(task_id: u32, width: u32, height: u32, shm_token: u32) -> Self {
+        let _ = register_surface_for_task(task_id, width, height, shm_token);
+        drain_queue();
+        Self {
+            task_id,
+            buffers: Vec::new(),
+        }
+    }
+
+    fn commit(&self) {
+        let _ = surface_commit(self.task_id);
+        drain_queue();
+    }
+}
+
+impl Drop for SurfaceFixture {
+    fn drop(&mut self) {
+        unregister_surface_for_task(self.task_id);
+        drain_queue();
+    }
+}
+
+pub fn test_buffer_age_unknown_surface() -> TestResult {
+    assert_eq_test!(surface_get_buffer_age(TEST_TASK_BASE + 0xFFF), 0);
+    TestResult::Pass
+}
+
+pub fn test_buffer_age_first_commit_undefined() -> TestResult {
+    let Some(surface) = SurfaceFixture::buffered(TEST_TASK_BASE + 1, 2) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    assert_eq_test!(
+        surface_get_buffer_age(surface.task_id),
+        0,
+        "age before any commit"
+    );
+
+    surface.swap();
+    let age = surface_get_buffer_age(surface.task_id);
+    if age != 0 {
+        klog_info!(
+            "COMPOSITOR_TEST: back buffer never presented but age={}",
+            age
+        );
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+pub fn test_buffer_age_matches_buffer_count() -> TestResult {
+    for count in 1..=3 {
+        let Some(surface) = SurfaceFixture::buffered(TEST_TASK_BASE + 2, count) else {
+            klog_info!("COMPOSITOR_TEST: shm_create failed");
+            return TestResult::Fail;
+        };
+        for _ in 0..5 {
+            surface.swap();
+        }
+        assert_eq_test!(
+            surface_get_buffer_age(surface.task_id),
+            count as u8,
+            "steady-state age is the buffer count"
+        );
+    }
+
+    // A freshly attached back buffer has never been shown
+    let Some(surface) = SurfaceFixture::buffered(TEST_TASK_BASE + 2, 1) else {
+        return TestResult::Fail;
+    };
+    surface.swap();
+    assert_eq_test!(surface_get_buffer_age(surface.task_id), 1);
+    let Some(back) = ShmPixels::new(BUF_W, BUF_H, |_, _| 0) else {
+        return TestResult::Fail;
+    };
+    let _ = surface_attach_back_buffer(surface.task_id, back.token);
+    drain_queue();
+    assert_eq_test!(surface_get_buffer_age(surface.task_id), 0);
+    let _ = surface_attach_back_buffer(surface.task_id, 0);
+    drain_queue();
+    assert_eq_test!(
+        surface_get_buffer_age(surface.task_id),
+        1,
+        "detached: drawing goes over the front again"
+    );
+    TestResult::Pass
+}
+
+pub fn test_buffer_age_reset_on_reregister() -> TestResult {
+    let task_id = TEST_TASK_BASE + 3;
+    {
+        let Some(surface) = SurfaceFixture::buffered(task_id, 2) else {
+            klog_info!("COMPOSITOR_TEST: shm_create failed");
+            return TestResult::Fail;
+        };
+        surface.swap();
+        surface.swap();
+        assert_eq_test!(surface_get_buffer_age(task_id), 2);
+    }
+
+    let _surface = SurfaceFixture::new(task_id, 32, 32);
+    assert_eq_test!(
+        surface_get_buffer_age(task_id),
+        0,
+        "fresh surface must redraw fully"
+    );
+    TestResult::Pass
+}
+
+/// Process ID used as owner of test shm buffers.
+const TEST_SHM_OWNER: u32 = 0x7E57;
+
+/// Shared buffer filled by a per-pixel function, destroyed on drop.
+struct ShmPixels {
+    token: u32,
+}
+
+impl ShmPixels {
+    fn new(width: u32, height: u32, pixel: impl Fn(u32, u32) -> u32) -> Option<Self> {
+        let token = shm_create(TEST_SHM_OWNER, (width * height * 4) as u64, 0);
+        if token == 0 {
+            return None;
+        }
+        let (phys, _, _) = shm_get_buffer_info(token);
+        let base = phys.to_virt().as_u64() as *mut u32;
+        for y in 0..height {
+            for x in 0..width {
+                unsafe { base.add((y * width + x) as usize).write(pixel(x, y)) };
+            }
+        }
+        Some(Self { token })
+    }
+}
+
+impl Drop for ShmPixels {
+    fn drop(&mut self) {
+        shm_destroy(TEST_SHM_OWNER, self.token);
+    }
+}
+
+fn read_pixel(buf: &[u8], width: u32, x: u32, y: u32) -> u32 {
+    let off = ((y * width + x) * 4) as usize;
+    u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+}
+
+pub fn test_thumbnail_solid_color() -> TestResult {
+    const COLOR: u32 = 0xFF33_66CC;
+    let Some(pixels) = ShmPixels::new(64, 48, |_, _| COLOR) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let surface = SurfaceFixture::with_token(TEST_TASK_BASE + 10, 64, 48, pixels.token);
+
+    let mut out = [0u8; 16 * 16 * 4];
+    let Some((w, h)) = surface_generate_thumbnail(surface.task_id, 16, 16, &mut out) else {
+        klog_info!("COMPOSITOR_TEST: thumbnail generation failed");
+        return TestResult::Fail;
+    };
+
+    for y in 0..h {
+        for x in 0..w {
+            let px = read_pixel(&out, w, x, y);
+            if px != COLOR {
+                klog_info!(
+                    "COMPOSITOR_TEST: thumbnail pixel ({}, {}) = {:#x}, expected {:#x}",
+                    x,
+                    y,
+                    px,
+                    COLOR
+                );
+                return TestResult::Fail;
+            }
+        }
+    }
+    TestResult::Pass
+}
+
+pub fn test_thumbnail_preserves_aspect() -> TestResult {
+    // Left half red, right half blue so sampling can be checked too
+    let Some(pixels) = ShmPixels::new(
+        200,
+        100,
+        |x, _| if x < 100 { 0xFFFF_0000 } else { 0xFF00_00FF },
+    ) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let surface = SurfaceFixture::with_token(TEST_TASK_BASE + 11, 200, 100, pixels.token);
+
+    let mut out = [0u8; 50 * 50 * 4];
+    let dims = surface_generate_thumbnail(surface.task_id, 50, 50, &mut out);
+    assert_eq_test!(dims, Some((50, 25)), "2:1 surface into 50x50 box");
+    assert_eq_test!(read_pixel(&out, 50, 0, 0), 0xFFFF_0000);
+    assert_eq_test!(read_pixel(&out, 50, 49, 24), 0xFF00_00FF);
+
+    let mut tall = [0u8; 50 * 50 * 4];
+    let dims = surface_generate_thumbnail(surface.task_id, 50, 10, &mut tall);
+    assert_eq_test!(dims, Some((20, 10)), "height-limited box");
+
+    let mut small = [0u8; 8];
+    assert_test!(
+        surface_generate_thumbnail(surface.task_id, 50, 50, &mut small).is_none(),
+        "undersized output buffer must be rejected"
+    );
+    TestResult::Pass
+}
+
+pub fn test_thumbnail_uses_declared_format() -> TestResult {
+    const W: u32 = 30;
+    const H: u32 = 10;
+    let token = shm_create_with_format(TEST_SHM_OWNER, (W * H * 3) as u64, PixelFormat::Rgb888);
+    if token == 0 {
+        klog_info!("COMPOSITOR_TEST: shm_create_with_format failed");
+        return TestResult::Fail;
+    }
+    // Each 3-byte pixel encodes its own column so sampling is checkable
+    let (phys, _, _) = shm_get_buffer_info(token);
+    let base = phys.to_virt().as_u64() as *mut u8;
+    for i in 0..(W * H) as usize {
+        let x = (i % W as usize) as u8;
+        unsafe {
+            base.add(i * 3).write(x);
+            base.add(i * 3 + 1).write(0x40);
+            base.add(i * 3 + 2).write(0x80);
+        }
+    }
+    let surface = SurfaceFixture::with_token(TEST_TASK_BASE + 125, W, H, token);
+
+    let mut out = [0u8; 15 * 5 * 3 + 1];
+    let dims = surface_generate_thumbnail(surface.task_id, 15, 15, &mut out);
+    drop(surface);
+    shm_destroy(TEST_SHM_OWNER, token);
+
+    assert_eq_test!(dims, Some((15, 5)));
+    assert_eq_test!(&out[..3], &[0, 0x40, 0x80][..], "first pixel");
+    assert_eq_test!(&out[3..6], &[2, 0x40, 0x80][..], "packed at 3 bytes");
+    assert_eq_test!(&out[14 * 3..15 * 3], &[28, 0x40, 0x80][..], "last column");
+    assert_eq_test!(out[15 * 5 * 3], 0, "wrote past the thumbnail");
+    TestResult::Pass
+}
+
+fn find_window(task_id: u32) -> Option<WindowInfo> {
+    let mut windows = vec![WindowInfo::default(); 64];
+    let count = surface_enumerate_windows(windows.as_mut_ptr(), windows.len() as u32);
+    windows
+        .into_iter()
+        .take(count as usize)
+        .find(|w| w.task_id == task_id)
+}
+
+/// Composite `window`'s buffer onto a `fb_w`-wide framebuffer at (0, 0)
+/// through `CompositeTarget::blit`, as the userland compositor does.
+fn composite_window(fb: &mut [u8], fb_w: u32, window: &WindowInfo) -> bool {
+    let (phys, size, _) = shm_get_buffer_info(window.shm_token);
+    let pitch = window.width as usize * 4;
+    if phys.is_null() || size < pitch * window.height as usize {
+        return false;
+    }
+    let src = unsafe {
+        core::slice::from_raw_parts(
+            phys.to_virt().as_u64() as *const u8,
+            pitch * window.height as usize,
+        )
+    };
+    let height = (fb.len() / (fb_w as usize * 4)) as u32;
+    let mut target = CompositeTarget {
+        data: fb,
+        width: fb_w,
+        height,
+        pitch: fb_w as usize * 4,
+        format: PixelFormat::Argb8888,
+    };
+    target.blit(&CompositeSource {
+        data: src,
+        format: PixelFormat::Argb8888,
+        x: 0,
+        y: 0,
+        width: window.width,
+        height: window.height,
+        color_key: window.color_key(),
+        blend_mode: window.blend_mode(),
+    });
+    true
+}
+
+pub fn test_color_key_composite() -> TestResult {
+    const KEY: u32 = 0xFFFF_00FF;
+    const FG: u32 = 0xFF20_4060;
+    const FB_BG: u32 = 0xFF0A_0B0C;
+    const W: u32 = 16;
+    const H: u32 = 8;
+
+    // Keyed background with a foreground square in the middle
+    let Some(pixels) = ShmPixels::new(W, H, |x, y| {
+        if (4..12).contains(&x) && (2..6).contains(&y) {
+            FG
+        } else {
+            KEY
+        }
+    }) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let surface = SurfaceFixture::with_token(TEST_TASK_BASE + 20, W, H, pixels.token);
+    // Alpha/X bits are ignored when matching the key
+    assert_test!(surface_set_color_key(surface.task_id, Some(KEY & 0x00FF_FFFF)).is_ok());
+    drain_queue();
+
+    let Some(window) = find_window(surface.task_id) else {
+        klog_info!("COMPOSITOR_TEST: keyed surface not enumerated");
+        return TestResult::Fail;
+    };
+    assert_eq_test!(window.color_key(), Some(KEY & 0x00FF_FFFF));
+
+    let mut fb = vec![0u8; (W * H * 4) as usize];
+    for px in fb.chunks_exact_mut(4) {
+        px.copy_from_slice(&FB_BG.to_le_bytes());
+    }
+    assert_test!(composite_window(&mut fb, W, &window), "composite failed");
+
+    for y in 0..H {
+        for x in 0..W {
+            let inside = (4..12).contains(&x) && (2..6).contains(&y);
+            let expected = if inside { FG } else { FB_BG };
+            let px = read_pixel(&fb, W, x, y);
+            if px != expected {
+                klog_info!(
+                    "COMPOSITOR_TEST: composited ({}, {}) = {:#x}, expected {:#x}",
+                    x,
+                    y,
+                    px,
+                    expected
+                );
+                return TestResult::Fail;
+            }
+        }
+    }
+    TestResult::Pass
+}
+
+pub fn test_color_key_cleared_copies_all() -> TestResult {
+    const KEY: u32 = 0xFF00_FF00;
+    let Some(pixels) = ShmPixels::new(8, 4, |_, _| KEY) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let surface = SurfaceFixture::with_token(TEST_TASK_BASE + 21, 8, 4, pixels.token);
+    let _ = surface_set_color_key(surface.task_id, Some(KEY));
+    let _ = surface_set_color_key(surface.task_id, None);
+    drain_queue();
+
+    let Some(window) = find_window(surface.task_id) else {
+        klog_info!("COMPOSITOR_TEST: surface not enumerated");
+        return TestResult::Fail;
+    };
+    assert_eq_test!(window.color_key(), None, "key should be cleared");
+
+    let mut fb = vec![0u8; 8 * 4 * 4];
+    assert_test!(composite_window(&mut fb, 8, &window), "composite failed");
+    assert_test!(
+        fb.chunks_exact(4).all(|px| px == KEY.to_le_bytes()),
+        "unkeyed surface must be copied verbatim"
+    );
+    TestResult::Pass
+}
+
+fn poll_key(task_id: u32) -> Option<u8> {
+    input_poll(task_id)
+        .filter(|e| e.event_type == InputEventType::KeyPress)
+        .map(|e| (e.data.data0 & 0xFF) as u8)
+}
+
+fn check_focus_routing(a: u32, b: u32) -> TestResult {
+    let a_surface = SurfaceFixture::new(a, 32, 32);
+    let b_surface = SurfaceFixture::new(b, 32, 32);
+
+    assert_test!(surface_set_focus(a_surface.task_id).is_ok());
+    assert_eq_test!(surface_get_focus(), a);
+    input_route_key_event(0x1E, b'a', true, 0);
+    assert_eq_test!(poll_key(a), Some(0x1E), "focused window gets the key");
+    assert_eq_test!(poll_key(b), None, "unfocused window gets nothing");
+
+    assert_test!(surface_raise_window(b_surface.task_id).is_ok());
+    assert_eq_test!(surface_get_focus(), b, "raise moves focus");
+    assert_eq_test!(input_get_keyboard_focus(), b);
+    input_route_key_event(0x30, b'b', true, 0);
+    assert_eq_test!(poll_key(b), Some(0x30));
+    assert_eq_test!(poll_key(a), None);
+
+    assert_test!(
+        surface_set_focus(TEST_TASK_BASE + 0xFFF).is_err(),
+        "focus on unknown surface must fail"
+    );
+    TestResult::Pass
+}
+
+pub fn test_focus_routes_keyboard_events() -> TestResult {
+    let (a, b) = (TEST_TASK_BASE + 30, TEST_TASK_BASE + 31);
+    let prev_focus = input_get_keyboard_focus();
+
+    let result = check_focus_routing(a, b);
+
+    input_cleanup_task(a);
+    input_cleanup_task(b);
+    input_set_keyboard_focus(prev_focus);
+    result
+}
+
+/// Detaches the framebuffer to simulate a headless boot, restored on drop.
+struct HeadlessGuard {
+    saved: Option<FbState>,
+}
+
+impl HeadlessGuard {
+    fn new() -> Self {
+        Self {
+            saved: replace_state(None),
+        }
+    }
+}
+
+impl Drop for HeadlessGuard {
+    fn drop(&mut self) {
+        replace_state(self.saved.take());
+    }
+}
+
+/// Scans out of `pixels` instead of the real framebuffer, restored on drop.
+struct MockFramebuffer<'a> {
+    saved: Option<FbState>,
+    _pixels: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> MockFramebuffer<'a> {
+    fn new(pixels: &'a mut [u8], info: DisplayInfo) -> Self {
+        let fb = FbState {
+            base: VirtAddr::new(pixels.as_mut_ptr() as u64),
+            phys: PhysAddr::NULL,
+            scanout: PhysAddr::NULL,
+            info,
+        };
+        Self {
+            saved: replace_state(Some(fb)),
+            _pixels: PhantomData,
+        }
+    }
+}
+
+impl Drop for MockFramebuffer<'_> {
+    fn drop(&mut self) {
+        replace_state(self.saved.take());
+    }
+}
+
+pub fn test_headless_surfaces_enumerable() -> TestResult {
+    let _headless = HeadlessGuard::new();
+    assert_test!(!compositor_has_framebuffer());
+
+    let surface = SurfaceFixture::new(TEST_TASK_BASE + 40, 64, 32);
+    surface.commit();
+    let Some(window) = find_window(surface.task_id) else {
+        klog_info!("COMPOSITOR_TEST: headless surface not enumerated");
+        return TestResult::Fail;
+    };
+    assert_eq_test!((window.width, window.height), (64, 32));
+    TestResult::Pass
+}
+
+pub fn test_headless_present_no_framebuffer() -> TestResult {
+    let Some(pixels) = ShmPixels::new(16, 16, |_, _| 0) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+
+    let _headless = HeadlessGuard::new();
+    assert_eq_test!(
+        compositor_present(pixels.token),
+        Err(CompositorError::NoFramebuffer)
+    );
+    assert_eq_test!(
+        compositor_present(0),
+        Err(CompositorError::NoFramebuffer),
+        "missing framebuffer is reported before token validation"
+    );
+    TestResult::Pass
+}
+
+/// Two 20x20 rects one column apart plus two tiny rects far away from each
+/// other's neighbours, fed into a tracker that only has room for three. The
+/// gap keeps `add` from coalescing the large pair before capacity is hit.
+fn merge_with_strategy(strategy: MergeStrategy) -> DamageTracker<3> {
+    let mut tracker = DamageTracker::<3>::with_strategy(strategy);
+    tracker.add_rect(0, 0, 19, 19);
+    tracker.add_rect(21, 0, 40, 19);
+    tracker.add_rect(100, 100, 101, 101);
+    tracker.add_rect(110, 110, 111, 111);
+    tracker
+}
+
+fn has_region(tracker: &DamageTracker<3>, rect: DamageRect) -> bool {
+    tracker.regions().contains(&rect)
+}
+
+pub fn test_damage_least_waste_merges_adjacent() -> TestResult {
+    let tracker = merge_with_strategy(MergeStrategy::LeastWaste);
+    assert_eq_test!(tracker.count(), 3);
+    assert_test!(
+        has_region(
+            &tracker,
+            DamageRect {
+                x0: 0,
+                y0: 0,
+                x1: 40,
+                y1: 19
+            }
+        ),
+        "nearby rects merged with little waste"
+    );
+    assert_test!(has_region(
+        &tracker,
+        DamageRect {
+            x0: 100,
+            y0: 100,
+            x1: 101,
+            y1: 101
+        }
+    ));
+    assert_test!(has_region(
+        &tracker,
+        DamageRect {
+            x0: 110,
+            y0: 110,
+            x1: 111,
+            y1: 111
+        }
+    ));
+    TestResult::Pass
+}
+
+pub fn test_damage_smallest_area_merges_distant() -> TestResult {
+    let tracker = merge_with_strategy(MergeStrategy::SmallestArea);
+    assert_eq_test!(tracker.count(), 3);
+    assert_test!(
+        has_region(
+            &tracker,
+            DamageRect {
+                x0: 100,
+                y0: 100,
+                x1: 111,
+                y1: 111
+            }
+        ),
+        "smallest bounding box wins even though it spans empty space"
+    );
+    assert_eq_test!(
+        DamageRect {
+            x0: 100,
+            y0: 100,
+            x1: 101,
+            y1: 101
+        }
+        .wasted_area(&DamageRect {
+            x0: 110,
+            y0: 110,
+            x1: 111,
+            y1: 111
+        }),
+        136
+    );
+    TestResult::Pass
+}
+
+pub fn test_damage_add_coalesces_touching() -> TestResult {
+    let rect = |x0, y0, x1, y1| DamageRect { x0, y0, x1, y1 };
+    let mut tracker = DamageTracker::<4>::new();
+
+    tracker.add_rect(0, 0, 9, 9);
+    tracker.add_rect(10, 0, 19, 9);
+    assert_eq_test!(tracker.regions(), &[rect(0, 0, 19, 9)][..], "shared edge");
+    tracker.add_rect(5, 5, 12, 12);
+    assert_eq_test!(tracker.regions(), &[rect(0, 0, 19, 12)][..], "overlap");
+
+    tracker.add_rect(20, 13, 24, 17);
+    assert_eq_test!(tracker.count(), 2, "corner contact stays separate");
+    assert_eq_test!(tracker.total_area(), 20 * 13 + 5 * 5);
+
+    tracker.add_rect(40, 0, 49, 9);
+    assert_eq_test!(tracker.count(), 3);
+    // The bridge grows the first region under the corner rect's top edge, so
+    // everything collapses into one region.
+    tracker.add_rect(20, 0, 39, 4);
+    assert_eq_test!(
+        tracker.regions(),
+        &[rect(0, 0, 49, 17)][..],
+        "grown region folds in every neighbour it now touches"
+    );
+    assert_eq_test!(tracker.total_area(), 50 * 18);
+    assert_test!(!tracker.is_full_damage());
+
+    tracker.clear();
+    assert_eq_test!(tracker.total_area(), 0);
+    TestResult::Pass
+}
+
+pub fn test_title_unterminated_slot_truncated() -> TestResult {
+    let surface = SurfaceFixture::new(TEST_TASK_BASE + 50, 16, 16);
+    queue_title(surface.task_id, [b'A'; WINDOW_TITLE_LEN]);
+    surface.commit();
+
+    let Some(window) = find_window(surface.task_id) else {
+        klog_info!("COMPOSITOR_TEST: titled surface not enumerated");
+        return TestResult::Fail;
+    };
+    assert_eq_test!(
+        window.title[WINDOW_TITLE_LEN - 1],
+        0,
+        "title force-terminated"
+    );
+    assert_eq_test!(window.title_str().len(), WINDOW_TITLE_LEN - 1);
+    assert_test!(
+        window.title[..WINDOW_TITLE_LEN - 1]
+            .iter()
+            .all(|&b| b == b'A')
+    );
+    TestResult::Pass
+}
+
+pub fn test_title_long_input_truncated() -> TestResult {
+    let surface = SurfaceFixture::new(TEST_TASK_BASE + 51, 16, 16);
+    let _ = surface_set_title(surface.task_id, &[b'B'; 40]);
+    surface.commit();
+
+    let Some(window) = find_window(surface.task_id) else {
+        klog_info!("COMPOSITOR_TEST: titled surface not enumerated");
+        return TestResult::Fail;
+    };
+    assert_eq_test!(window.title[WINDOW_TITLE_LEN - 1], 0);
+    assert_eq_test!(window.title_str().len(), WINDOW_TITLE_LEN - 1);
+    TestResult::Pass
+}
+
+pub fn test_title_embedded_nul_rejected() -> TestResult {
+    let surface = SurfaceFixture::new(TEST_TASK_BASE + 52, 16, 16);
+    assert_eq_test!(surface_set_title(surface.task_id, b"Terminal"), Ok(()));
+    surface.commit();
+
+    assert_eq_test!(
+        surface_set_title(surface.task_id, b"Term\0inal"),
+        Err(CompositorError::InvalidArgument),
+        "NUL inside the title"
+    );
+    surface.commit();
+    let title = find_window(surface.task_id).map(|w| w.title);
+    assert_test!(
+        title.is_some_and(|t| t.starts_with(b"Terminal\0")),
+        "rejected title left the old one in place"
+    );
+
+    // Trailing NUL padding, as from a fixed C buffer, is accepted
+    assert_eq_test!(surface_set_title(surface.task_id, b"Files\0\0\0"), Ok(()));
+    surface.commit();
+    let Some(window) = find_window(surface.task_id) else {
+        klog_info!("COMPOSITOR_TEST: titled surface not enumerated");
+        return TestResult::Fail;
+    };
+    assert_eq_test!(window.title_str(), "Files");
+    TestResult::Pass
+}
+
+pub fn test_format_rgb888_onto_argb8888() -> TestResult {
+    // Two 24-bit pixels, memory order [B, G, R]
+    let src = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+    let mut dst = [0u8; 8];
+    pixel_ops::convert_row_keyed(
+        &mut dst,
+        PixelFormat::Argb8888,
+        &src,
+        PixelFormat::Rgb888,
+        None,
+    );
+    assert_eq_test!(dst, [0x11, 0x22, 0x33, 0xFF, 0x44, 0x55, 0x66, 0xFF]);
+
+    // BGR surfaces swap channels on the way in
+    let src = [0x33, 0x22, 0x11];
+    let mut dst = [0u8; 4];
+    pixel_ops::convert_row_keyed(
+        &mut dst,
+        PixelFormat::Argb8888,
+        &src,
+        PixelFormat::Bgr888,
+        None,
+    );
+    assert_eq_test!(dst, [0x11, 0x22, 0x33, 0xFF]);
+    TestResult::Pass
+}
+
+pub fn test_format_argb8888_onto_rgb888_keyed() -> TestResult {
+    let src = [0x11, 0x22, 0x33, 0x80, 0x00, 0xFF, 0x00, 0xFF];
+    let mut dst = [0xAAu8; 6];
+    pixel_ops::convert_row_keyed(
+        &mut dst,
+        PixelFormat::Rgb888,
+        &src,
+        PixelFormat::Argb8888,
+        Some(0x00_FF00),
+    );
+    assert_eq_test!(
+        dst,
+        [0x11, 0x22, 0x33, 0xAA, 0xAA, 0xAA],
+        "alpha dropped, keyed pixel left untouched"
+    );
+    TestResult::Pass
+}
+
+pub fn test_color_key_16bpp_row() -> TestResult {
+    // Two RGB565 pixels; the second one is the key
+    let src = [0x1F, 0xF8, 0xE0, 0x07];
+    let mut dst = [0xAAu8; 4];
+    pixel_ops::copy_row_keyed(&mut dst, &src, 2, Some(0x07E0));
+    assert_eq_test!(dst, [0x1F, 0xF8, 0xAA, 0xAA], "keyed 16-bit pixel kept");
+
+    // Too shallow to key: plain copy instead of a panic
+    let mut dst = [0u8; 4];
+    pixel_ops::copy_row_keyed(&mut dst, &src, 1, Some(0x1F));
+    assert_eq_test!(dst, src, "1-byte pixels are copied");
+    TestResult::Pass
+}
+
+pub fn test_format_declared_in_window_info() -> TestResult {
+    let declared = shm_create_with_format(TEST_SHM_OWNER, 16 * 16 * 3, PixelFormat::Rgb888);
+    let plain = shm_create(TEST_SHM_OWNER, 16 * 16 * 4, 0);
+    if declared == 0 || plain == 0 {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    }
+
+    let rgb = SurfaceFixture::with_token(TEST_TASK_BASE + 60, 16, 16, declared);
+    let native = SurfaceFixture::with_token(TEST_TASK_BASE + 61, 16, 16, plain);
+    rgb.commit();
+    native.commit();
+
+    let result = match (find_window(rgb.task_id), find_window(native.task_id)) {
+        (Some(rgb_win), Some(native_win)) => {
+            rgb_win.pixel_format(PixelFormat::Argb8888) == Some(PixelFormat::Rgb888)
+                && native_win.format == WINDOW_FORMAT_NATIVE
+                && native_win.pixel_format(PixelFormat::Argb8888) == Some(PixelFormat::Argb8888)
+        }
+        _ => false,
+    };
+    drop(rgb);
+    drop(native);
+    shm_destroy(TEST_SHM_OWNER, declared);
+    shm_destroy(TEST_SHM_OWNER, plain);
+
+    if !result {
+        klog_info!("COMPOSITOR_TEST: surface format not exposed through WindowInfo");
+        return TestResult::Fail;
+    }
+    TestResult::Pass
+}
+
+const OVERLAY_W: u32 = 16;
+const OVERLAY_H: u32 = 16;
+const OVERLAY_PITCH: usize = OVERLAY_W as usize * 4;
+
+fn overlay_pixel(data: &[u8], x: usize, y: usize) -> u32 {
+    let off = y * OVERLAY_PITCH + x * 4;
+    u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]])
+}
+
+/// Run `test` over two overlapping windows. `compose` renders them into a
+/// fresh frame through the compositor, as the desktop is drawn before the
+/// overlay goes on top. Window pixels are unique per position.
+fn with_overlay_desktop(
+    test: impl FnOnce(&mut dyn FnMut() -> alloc::vec::Vec<u8>) -> TestResult,
+) -> TestResult {
+    let (Some(back), Some(front)) = (
+        ShmPixels::new(12, 12, |x, y| 0xFF10_0000 | (y << 8) | x),
+        ShmPixels::new(10, 10, |x, y| 0xFF20_0000 | (y << 8) | x),
+    ) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let bottom = SurfaceFixture::with_token(TEST_TASK_BASE + 127, 12, 12, back.token);
+    let top = SurfaceFixture::with_token(TEST_TASK_BASE + 128, 10, 10, front.token);
+    let _ = surface_set_window_position(bottom.task_id, 0, 0);
+    let _ = surface_set_window_position(top.task_id, 6, 6);
+
+    test(&mut || {
+        let mut data = vec![0u8; OVERLAY_PITCH * OVERLAY_H as usize];
+        compositor_compose_into(&mut CompositeTarget {
+            data: &mut data,
+            width: OVERLAY_W,
+            height: OVERLAY_H,
+            pitch: OVERLAY_PITCH,
+            format: PixelFormat::Argb8888,
+        });
+        data
+    })
+}
+
+fn overlay_target(data: &mut [u8]) -> LayerTarget<'_> {
+    LayerTarget {
+        data,
+        width: OVERLAY_W,
+        height: OVERLAY_H,
+        pitch: OVERLAY_PITCH,
+        bytes_pp: 4,
+    }
+}
+
+pub fn test_overlay_on_top_of_windows() -> TestResult {
+    with_overlay_desktop(|compose| {
+        let reference = compose();
+        assert_eq_test!(
+            overlay_pixel(&reference, 3, 3),
+            0xFF10_0303,
+            "bottom window"
+        );
+        assert_eq_test!(overlay_pixel(&reference, 7, 7), 0xFF20_0101, "top window");
+
+        let mut data = reference.clone();
+        let mut overlay = Box::new(OverlayLayer::new());
+        let pixels = [0xFFAA_BBCCu32; 4 * 4];
+        let ok = overlay.set_overlay(&mut overlay_target(&mut data), &pixels, 4, 5, 4, 4);
+        assert_test!(ok, "overlay accepted");
+        assert_eq_test!(overlay.bounds(), Some((4, 5, 4, 4)), "overlay bounds");
+
+        // A repainted frame gets the overlay back on top of both windows
+        let mut data = compose();
+        overlay.composite_at(&mut overlay_target(&mut data), 4, 5);
+        for y in 0..OVERLAY_H as usize {
+            for x in 0..OVERLAY_W as usize {
+                let inside = (4..8).contains(&x) && (5..9).contains(&y);
+                let expected = if inside {
+                    0xFFAA_BBCC
+                } else {
+                    overlay_pixel(&reference, x, y)
+                };
+                assert_eq_test!(
+                    overlay_pixel(&data, x, y),
+                    expected,
+                    "overlay covers windows"
+                );
+            }
+        }
+
+        // Moving off the repainted frame uncovers the composited windows
+        overlay.move_overlay(&mut overlay_target(&mut data), 12, 12);
+        assert_test!(
+            data[..12 * OVERLAY_PITCH] == reference[..12 * OVERLAY_PITCH],
+            "old spot shows the windows again"
+        );
+
+        // Oversized content is refused without disturbing the current overlay
+        let big = [0u32; 33 * 33];
+        let ok = overlay.set_overlay(&mut overlay_target(&mut data), &big, 0, 0, 33, 33);
+        assert_test!(!ok, "oversized overlay rejected");
+        assert_eq_test!(overlay.bounds(), Some((12, 12, 4, 4)), "bounds unchanged");
+        TestResult::Pass
+    })
+}
+
+pub fn test_overlay_move_restores_pixels() -> TestResult {
+    with_overlay_desktop(|compose| {
+        let reference = compose();
+        let mut data = reference.clone();
+        let mut overlay = Box::new(OverlayLayer::new());
+        let pixels = [0xFF11_2233u32; 3 * 3];
+
+        overlay.set_overlay(&mut overlay_target(&mut data), &pixels, 1, 1, 3, 3);
+        overlay.move_overlay(&mut overlay_target(&mut data), 10, 10);
+
+        for y in 0..OVERLAY_H as usize {
+            for x in 0..OVERLAY_W as usize {
+                let inside = (10..13).contains(&x) && (10..13).contains(&y);
+                let expected = if inside {
+                    0xFF11_2233
+                } else {
+                    overlay_pixel(&reference, x, y)
+                };
+                assert_eq_test!(overlay_pixel(&data, x, y), expected, "pixels after move");
+            }
+        }
+
+        // Partially off-screen positions clip instead of faulting
+        overlay.move_overlay(&mut overlay_target(&mut data), 14, -1);
+        assert_eq_test!(overlay_pixel(&data, 15, 1), 0xFF11_2233, "clipped overlay");
+        assert_eq_test!(
+            overlay_pixel(&data, 11, 11),
+            overlay_pixel(&reference, 11, 11),
+            "old spot restored"
+        );
+        TestResult::Pass
+    })
+}
+
+pub fn test_overlay_cursor_moves_and_damage() -> TestResult {
+    with_overlay_desktop(|compose| {
+        let reference = compose();
+        let mut data = reference.clone();
+        let mut overlay = Box::new(OverlayLayer::new());
+        let cursor = [0xFFFF_FFFFu32; 2 * 2];
+        let rect = |x0, y0| DamageRect {
+            x0,
+            y0,
+            x1: x0 + 1,
+            y1: y0 + 1,
+        };
+
+        assert_eq_test!(overlay.damage_rect(), None, "no cursor yet");
+        overlay.set_overlay(&mut overlay_target(&mut data), &cursor, 2, 2, 2, 2);
+        assert_eq_test!(overlay.damage_rect(), Some(rect(2, 2)));
+
+        let mut damage: DamageTracker = DamageTracker::new();
+        for (x, y) in [(6, 4), (9, 12)] {
+            let spots = overlay.move_and_damage(&mut overlay_target(&mut data), x, y);
+            for rect in spots.into_iter().flatten() {
+                damage.add(rect);
+            }
+        }
+        assert_eq_test!(overlay.damage_rect(), Some(rect(9, 12)));
+
+        // Both spots the cursor left are back to the windows underneath
+        for (x, y) in [(2, 2), (3, 3), (6, 4), (7, 5)] {
+            assert_eq_test!(
+                overlay_pixel(&data, x, y),
+                overlay_pixel(&reference, x, y),
+                "pixels under an old cursor position restored"
+            );
+        }
+        assert_eq_test!(overlay_pixel(&data, 10, 13), 0xFFFF_FFFF);
+
+        // And every spot it touched is damaged so the screen is repaired
+        for (x, y) in [(2, 2), (7, 5), (10, 13)] {
+            let spot = DamageRect {
+                x0: x,
+                y0: y,
+                x1: x,
+                y1: y,
+            };
+            assert_test!(
+                damage.regions().iter().any(|r| r.intersects(&spot)),
+                "cursor spot damaged"
+            );
+        }
+        TestResult::Pass
+    })
+}
+
+pub fn test_overlay_clear_restores_content() -> TestResult {
+    with_overlay_desktop(|compose| {
+        let reference = compose();
+        let mut data = reference.clone();
+        let mut overlay = Box::new(OverlayLayer::new());
+
+        // Checkerboard with a color key: keyed pixels must leave the window visible
+        let mut pixels = [0u32; 4 * 4];
+        for (i, px) in pixels.iter_mut().enumerate() {
+            *px = if i % 2 == 0 { 0xFFFF_FFFF } else { 0 };
+        }
+        overlay.set_color_key(Some(0));
+        overlay.set_overlay(&mut overlay_target(&mut data), &pixels, 4, 4, 4, 4);
+        assert_eq_test!(
+            overlay_pixel(&data, 4, 4),
+            0xFFFF_FFFF,
+            "opaque pixel drawn"
+        );
+        assert_eq_test!(
+            overlay_pixel(&data, 5, 4),
+            overlay_pixel(&reference, 5, 4),
+            "keyed pixel transparent"
+        );
+
+        overlay.clear_overlay(&mut overlay_target(&mut data));
+        assert_test!(!overlay.is_active(), "overlay inactive after clear");
+        assert_test!(data == reference, "frame restored after clear");
+
+        // Clearing twice must not write stale save-under data
+        data[0] = 0x5A;
+        overlay.clear_overlay(&mut overlay_target(&mut data));
+        assert_eq_test!(data[0], 0x5A, "second clear is a no-op");
+        TestResult::Pass
+    })
+}
+
+fn has_damage(window: &WindowInfo, x0: i32, y0: i32, x1: i32, y1: i32) -> bool {
+    let count = (window.damage_count as usize).min(window.damage_regions.len());
+    window.damage_regions[..count]
+        .iter()
+        .any(|r| r.x0 == x0 && r.y0 == y0 && r.x1 == x1 && r.y1 == y1)
+}
+
+pub fn test_frame_batches_damage_into_one_compose() -> TestResult {
+    let surface = SurfaceFixture::new(TEST_TASK_BASE + 70, 64, 64);
+    compositor_take_compose_requests();
+
+    let task_id = surface.task_id;
+    let _ = surface_begin_frame(task_id);
+    let _ = surface_add_damage(task_id, 0, 0, 10, 10);
+    let _ = surface_add_damage(task_id, 5, 5, 10, 10);
+    let _ = surface_commit(task_id);
+    let _ = surface_add_damage(task_id, 40, 40, 4, 4);
+    let _ = surface_end_frame(task_id);
+    drain_queue();
+
+    assert_eq_test!(
+        compositor_take_compose_requests(),
+        1,
+        "one compose per frame"
+    );
+    assert_eq_test!(compositor_take_compose_requests(), 0);
+
+    let Some(window) = find_window(task_id) else {
+        return TestResult::Fail;
+    };
+    assert_eq_test!(window.damage_count, 2, "overlapping damage merged");
+    assert_test!(
+        has_damage(&window, 0, 0, 14, 14),
+        "union of overlapping rects"
+    );
+    assert_test!(has_damage(&window, 40, 40, 43, 43), "disjoint rect kept");
+    TestResult::Pass
+}
+
+pub fn test_frame_unmatched_begin_end() -> TestResult {
+    let surface = SurfaceFixture::new(TEST_TASK_BASE + 71, 64, 64);
+    let task_id = surface.task_id;
+    compositor_take_compose_requests();
+
+    let _ = surface_end_frame(task_id);
+    drain_queue();
+    assert_eq_test!(
+        compositor_take_compose_requests(),
+        0,
+        "lone EndFrame ignored"
+    );
+
+    let _ = surface_begin_frame(task_id);
+    let _ = surface_add_damage(task_id, 8, 8, 2, 2);
+    drain_queue();
+    assert_eq_test!(
+        compositor_take_compose_requests(),
+        0,
+        "open frame holds its damage"
+    );
+
+    drain_queue();
+    assert_eq_test!(
+        compositor_take_compose_requests(),
+        1,
+        "lone BeginFrame closed next frame"
+    );
+    let Some(window) = find_window(task_id) else {
+        return TestResult::Fail;
+    };
+    assert_test!(has_damage(&window, 8, 8, 9, 9), "held damage committed");
+
+    let _ = surface_end_frame(task_id);
+    drain_queue();
+    assert_eq_test!(
+        compositor_take_compose_requests(),
+        0,
+        "late EndFrame after a stale close does not commit again"
+    );
+    TestResult::Pass
+}
+
+pub fn test_frame_done_set_by_present() -> TestResult {
+    let surface = SurfaceFixture::new(TEST_TASK_BASE + 80, 32, 32);
+    surface.commit();
+    assert_test!(
+        !surface_frame_done(surface.task_id),
+        "not presented before the compositor draws"
+    );
+
+    surface_mark_frames_done(1234);
+    assert_test!(surface_frame_done(surface.task_id), "set after present");
+    assert_test!(
+        !surface_frame_done(surface.task_id),
+        "cleared after being read"
+    );
+    assert_test!(
+        !surface_frame_done(TEST_TASK_BASE + 0xFFF),
+        "unknown surface"
+    );
+    TestResult::Pass
+}
+
+pub fn test_frame_done_skips_minimized() -> TestResult {
+    let surface = SurfaceFixture::new(TEST_TASK_BASE + 81, 32, 32);
+    assert_eq_test!(
+        surface_set_window_state(surface.task_id, WINDOW_STATE_MINIMIZED),
+        Ok(())
+    );
+    surface_mark_frames_done(1234);
+    assert_test!(
+        !surface_frame_done(surface.task_id),
+        "minimized surface was not presented"
+    );
+
+    assert_eq_test!(
+        surface_set_window_state(surface.task_id, WINDOW_STATE_NORMAL),
+        Ok(())
+    );
+    surface_mark_frames_done(1250);
+    assert_test!(
+        surface_frame_done(surface.task_id),
+        "presented again once restored"
+    );
+    TestResult::Pass
+}
+
+pub fn test_frame_callback_fires_while_minimized() -> TestResult {
+    let surface = SurfaceFixture::new(TEST_TASK_BASE + 126, 32, 32);
+    assert_eq_test!(
+        surface_set_window_state(surface.task_id, WINDOW_STATE_MINIMIZED),
+        Ok(())
+    );
+    assert_eq_test!(surface_request_frame_callback(surface.task_id), Ok(()));
+    drain_queue();
+    surface_mark_frames_done(4321);
+    assert_eq_test!(
+        surface_poll_frame_done(surface.task_id),
+        4321,
+        "callback must not wait for the window to be restored"
+    );
+    assert_test!(!surface_frame_done(surface.task_id), "not presented");
+    TestResult::Pass
+}
+
+fn shm_pixel(token: u32, width: u32, x: u32, y: u32) -> u32 {
+    let (phys, _, _) = shm_get_buffer_info(token);
+    let base = phys.to_virt().as_u64() as *const u32;
+    unsafe { base.add((y * width + x) as usize).read() }
+}
+
+fn fill_shm(token: u32, width: u32, height: u32, color: u32) {
+    let (phys, _, _) = shm_get_buffer_info(token);
+    let base = phys.to_virt().as_u64() as *mut u32;
+    for i in 0..(width * height) as usize {
+        unsafe { base.add(i).write(color) };
+    }
+}
+
+/// Size of the buffers attached by `SurfaceFixture::buffered`.
+const BUF_W: u32 = 16;
+const BUF_H: u32 = 8;
+
+impl SurfaceFixture {
+    /// Surface with `count` plain buffers: front, then back, then spare.
+    fn buffered(task_id: u32, count: usize) -> Option<Self> {
+        let mut buffers = Vec::new();
+        for _ in 0..count {
+            buffers.push(ShmPixels::new(BUF_W, BUF_H, |_, _| 0xFF00_0000)?);
+        }
+        let mut surface = Self::with_token(task_id, BUF_W, BUF_H, buffers[0].token);
+        if let Some(back) = buffers.get(1) {
+            let _ = surface_attach_back_buffer(task_id, back.token);
+            drain_queue();
+        }
+        if let Some(spare) = buffers.get(2) {
+            let _ = surface_attach_spare_buffer(task_id, spare.token);
+            drain_queue();
+        }
+        surface.buffers = buffers;
+        Some(surface)
+    }
+
+    fn front(&self) -> u32 {
+        self.buffers[0].token
+    }
+
+    fn back(&self) -> u32 {
+        self.buffers[1].token
+    }
+
+    fn spare(&self) -> u32 {
+        self.buffers[2].token
+    }
+
+    /// Draw a frame into whichever buffer the client should draw into.
+    fn draw(&self, color: u32) {
+        fill_shm(surface_back_buffer(self.task_id), BUF_W, BUF_H, color);
+    }
+
+    fn swap(&self) {
+        let _ = surface_commit_swap(self.task_id);
+        drain_queue();
+    }
+
+    /// Token and corner pixel of the buffer the compositor reads.
+    fn presented(&self) -> Option<(u32, u32)> {
+        let window = find_window(self.task_id)?;
+        let token = window.shm_token;
+        Some((token, shm_pixel(token, BUF_W, BUF_W - 1, BUF_H - 1)))
+    }
+}
+
+pub fn test_commit_swap_exchanges_buffers() -> TestResult {
+    let Some(surface) = SurfaceFixture::buffered(TEST_TASK_BASE + 90, 2) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let task_id = surface.task_id;
+    assert_eq_test!(surface_back_buffer(task_id), surface.back());
+    assert_eq_test!(
+        syscall_name(SYSCALL_SURFACE_BACK_BUFFER),
+        Some("surface_back_buffer"),
+        "clients can ask which buffer to draw into"
+    );
+
+    surface.draw(0xFF11_2233);
+    surface.swap();
+    assert_eq_test!(
+        surface.presented(),
+        Some((surface.back(), 0xFF11_2233)),
+        "compositor reads the buffer just drawn"
+    );
+    assert_eq_test!(
+        surface_back_buffer(task_id),
+        surface.front(),
+        "old front becomes the back buffer"
+    );
+    assert_eq_test!(surface_get_buffer_age(task_id), 0, "old front never shown");
+
+    surface.draw(0xFF44_5566);
+    surface.swap();
+    assert_eq_test!(surface.presented(), Some((surface.front(), 0xFF44_5566)));
+    assert_eq_test!(surface_back_buffer(task_id), surface.back());
+    assert_eq_test!(
+        surface_get_buffer_age(task_id),
+        2,
+        "back buffer holds the previous frame"
+    );
+    assert_eq_test!(shm_pixel(surface.back(), BUF_W, 0, 0), 0xFF11_2233);
+    TestResult::Pass
+}
+
+pub fn test_commit_copy_retains_back() -> TestResult {
+    let Some(surface) = SurfaceFixture::buffered(TEST_TASK_BASE + 91, 2) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let task_id = surface.task_id;
+
+    surface.draw(0xFF77_8899);
+    surface.commit();
+    assert_eq_test!(
+        surface.presented(),
+        Some((surface.front(), 0xFF77_8899)),
+        "copy commit fills the front buffer"
+    );
+    assert_eq_test!(surface_back_buffer(task_id), surface.back());
+    assert_eq_test!(shm_pixel(surface.back(), BUF_W, 0, 0), 0xFF77_8899);
+    assert_eq_test!(
+        surface_get_buffer_age(task_id),
+        1,
+        "back buffer matches the front"
+    );
+    TestResult::Pass
+}
+
+pub fn test_commit_swap_and_copy_mixed() -> TestResult {
+    let Some(surface) = SurfaceFixture::buffered(TEST_TASK_BASE + 92, 2) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let task_id = surface.task_id;
+
+    surface.draw(0xFF00_00A1);
+    surface.swap();
+    surface.draw(0xFF00_00A2);
+    surface.commit();
+    assert_eq_test!(
+        surface.presented(),
+        Some((surface.back(), 0xFF00_00A2)),
+        "copy after swap updates the current front"
+    );
+    assert_eq_test!(surface_get_buffer_age(task_id), 1);
+
+    surface.draw(0xFF00_00A3);
+    surface.swap();
+    assert_eq_test!(surface.presented(), Some((surface.front(), 0xFF00_00A3)));
+    assert_eq_test!(
+        shm_pixel(surface_back_buffer(task_id), BUF_W, 0, 0),
+        0xFF00_00A2,
+        "back buffer holds the copied frame"
+    );
+    assert_eq_test!(surface_get_buffer_age(task_id), 2);
+
+    // Detaching falls back to committing the front buffer in place
+    let _ = surface_attach_back_buffer(task_id, 0);
+    drain_queue();
+    surface.swap();
+    assert_eq_test!(surface_back_buffer(task_id), 0);
+    assert_eq_test!(surface.presented(), Some((surface.front(), 0xFF00_00A3)));
+    TestResult::Pass
+}
+
+pub fn test_triple_buffer_quick_commits() -> TestResult {
+    let Some(surface) = SurfaceFixture::buffered(TEST_TASK_BASE + 93, 3) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let task_id = surface.task_id;
+
+    // Two copy commits land before the compositor drains either
+    surface.draw(0xFF00_00B1);
+    let _ = surface_commit(task_id);
+    surface.draw(0xFF00_00B2);
+    let _ = surface_commit(task_id);
+    drain_queue();
+
+    let Some((presented, pixel)) = surface.presented() else {
+        return TestResult::Fail;
+    };
+    assert_eq_test!(pixel, 0xFF00_00B2, "compositor sees the latest commit");
+    assert_test!(
+        presented != surface.back(),
+        "never presents the buffer being drawn"
+    );
+    assert_eq_test!(surface_back_buffer(task_id), surface.back());
+
+    // The client starts on its next frame straight away
+    surface.draw(0xFF00_00B3);
+    assert_eq_test!(
+        surface.presented(),
+        Some((presented, 0xFF00_00B2)),
+        "presented frame is untouched by drawing"
+    );
+    assert_eq_test!(surface_get_buffer_age(task_id), 1);
+    TestResult::Pass
+}
+
+pub fn test_triple_buffer_swap_rotates() -> TestResult {
+    let Some(surface) = SurfaceFixture::buffered(TEST_TASK_BASE + 94, 3) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let task_id = surface.task_id;
+
+    surface.draw(0xFF00_00C1);
+    surface.swap();
+    assert_eq_test!(surface.presented(), Some((surface.back(), 0xFF00_00C1)));
+    assert_eq_test!(
+        surface_back_buffer(task_id),
+        surface.spare(),
+        "client moves on to the spare, not the old front"
+    );
+    assert_eq_test!(surface_get_buffer_age(task_id), 0);
+
+    surface.draw(0xFF00_00C2);
+    surface.swap();
+    assert_eq_test!(surface.presented(), Some((surface.spare(), 0xFF00_00C2)));
+    assert_eq_test!(surface_back_buffer(task_id), surface.front());
+
+    surface.draw(0xFF00_00C3);
+    surface.swap();
+    assert_eq_test!(surface.presented(), Some((surface.front(), 0xFF00_00C3)));
+    assert_eq_test!(surface_back_buffer(task_id), surface.back());
+    assert_eq_test!(
+        surface_get_buffer_age(task_id),
+        3,
+        "back buffer was presented three commits ago"
+    );
+
+    // Detaching the spare drops back to double buffering
+    assert_eq_test!(surface_detach_spare_buffer(task_id), Ok(surface.spare()));
+    surface.draw(0xFF00_00C4);
+    surface.swap();
+    assert_eq_test!(surface.presented(), Some((surface.back(), 0xFF00_00C4)));
+    assert_eq_test!(surface_back_buffer(task_id), surface.front());
+    TestResult::Pass
+}
+
+pub fn test_triple_buffer_detach_returns_spare() -> TestResult {
+    let Some(surface) = SurfaceFixture::buffered(TEST_TASK_BASE + 95, 3) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let task_id = surface.task_id;
+
+    assert_eq_test!(
+        surface_attach_spare_buffer(task_id, surface.front()),
+        Err(CompositorError::InvalidArgument),
+        "a second spare is rejected"
+    );
+
+    surface.draw(0xFF00_00D1);
+    let _ = surface_commit_swap(task_id);
+    assert_eq_test!(
+        surface_detach_spare_buffer(task_id),
+        Err(CompositorError::Busy),
+        "detach waits for queued swaps"
+    );
+    drain_queue();
+
+    // The swap rotated the old front into the spare slot
+    assert_eq_test!(surface_detach_spare_buffer(task_id), Ok(surface.front()));
+    assert_eq_test!(
+        surface_detach_spare_buffer(task_id),
+        Err(CompositorError::BufferNotFound)
+    );
+    assert_eq_test!(surface.presented(), Some((surface.back(), 0xFF00_00D1)));
+    TestResult::Pass
+}
+
+/// Events pushed past capacity; timestamps number them in push order.
+const INPUT_OVERFILL: usize = MAX_EVENTS_PER_TASK + 6;
+
+fn numbered_key(n: usize) -> InputEvent {
+    InputEvent::key(InputEventType::KeyPress, 0x1E, b'a', n as u64)
+}
+
+/// Overfill a fresh window's queue under `policy` and return the
+/// timestamps of the surviving events in the order they drain.
+fn overfill_input(task_id: u32, policy: InputOverflowPolicy) -> Option<alloc::vec::Vec<u64>> {
+    let surface = SurfaceFixture::new(task_id, 16, 16);
+    surface_set_input_overflow(surface.task_id, policy).ok()?;
+    for n in 0..INPUT_OVERFILL {
+        let queued = surface_push_input(surface.task_id, numbered_key(n)).ok()?;
+        let expect_queued = n < MAX_EVENTS_PER_TASK || policy == InputOverflowPolicy::DropOldest;
+        if queued != expect_queued {
+            klog_info!("COMPOSITOR_TEST: push {} queued={}", n, queued);
+            input_cleanup_task(task_id);
+            return None;
+        }
+    }
+    let mut drained = alloc::vec::Vec::new();
+    while let Some(event) = surface_poll_input(surface.task_id) {
+        drained.push(event.timestamp_ms);
+    }
+    input_cleanup_task(task_id);
+    Some(drained)
+}
+
+pub fn test_input_queue_drop_oldest() -> TestResult {
+    let Some(drained) = overfill_input(TEST_TASK_BASE + 100, InputOverflowPolicy::DropOldest)
+    else {
+        return TestResult::Fail;
+    };
+    let expected: alloc::vec::Vec<u64> = (6..INPUT_OVERFILL as u64).collect();
+    assert_eq_test!(drained, expected, "newest events survive, in order");
+    TestResult::Pass
+}
+
+pub fn test_input_queue_drop_newest() -> TestResult {
+    let Some(drained) = overfill_input(TEST_TASK_BASE + 101, InputOverflowPolicy::DropNewest)
+    else {
+        return TestResult::Fail;
+    };
+    let expected: alloc::vec::Vec<u64> = (0..MAX_EVENTS_PER_TASK as u64).collect();
+    assert_eq_test!(drained, expected, "oldest events survive, in order");
+
+    assert_eq_test!(
+        surface_push_input(TEST_TASK_BASE + 0xFFF, numbered_key(0)),
+        Err(CompositorError::SurfaceNotFound)
+    );
+    TestResult::Pass
+}
+
+pub fn test_compose_into_overlap_top_wins() -> TestResult {
+    const RED: u32 = 0xFFFF_0000;
+    const BLUE: u32 = 0xFF00_00FF;
+    const W: u32 = 24;
+    let (Some(red), Some(blue)) = (
+        ShmPixels::new(8, 8, |_, _| RED),
+        ShmPixels::new(8, 8, |_, _| BLUE),
+    ) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    // Registered later, so stacked above the red one
+    let bottom = SurfaceFixture::with_token(TEST_TASK_BASE + 110, 8, 8, red.token);
+    let top = SurfaceFixture::with_token(TEST_TASK_BASE + 111, 8, 8, blue.token);
+    assert_eq_test!(surface_set_window_position(bottom.task_id, 2, 2), Ok(()));
+    assert_eq_test!(surface_set_window_position(top.task_id, 6, 6), Ok(()));
+
+    let mut data = vec![0u8; (W * W * 4) as usize];
+    let compose = |data: &mut [u8]| {
+        data.fill(0);
+        compositor_compose_into(&mut CompositeTarget {
+            data,
+            width: W,
+            height: W,
+            pitch: (W * 4) as usize,
+            format: PixelFormat::Argb8888,
+        });
+    };
+
+    compose(&mut data);
+    assert_eq_test!(read_pixel(&data, W, 3, 3), RED, "bottom-only area");
+    assert_eq_test!(read_pixel(&data, W, 7, 7), BLUE, "overlap shows top");
+    assert_eq_test!(read_pixel(&data, W, 13, 13), BLUE, "top-only area");
+    assert_eq_test!(read_pixel(&data, W, 20, 2), 0, "background untouched");
+
+    assert_eq_test!(surface_raise_window(bottom.task_id), Ok(()));
+    compose(&mut data);
+    assert_eq_test!(
+        read_pixel(&data, W, 7, 7),
+        RED,
+        "raised window wins overlap"
+    );
+    assert_eq_test!(read_pixel(&data, W, 13, 13), BLUE);
+
+    assert_eq_test!(
+        surface_set_window_state(bottom.task_id, WINDOW_STATE_MINIMIZED),
+        Ok(())
+    );
+    compose(&mut data);
+    assert_eq_test!(read_pixel(&data, W, 3, 3), 0, "minimized window not drawn");
+    assert_eq_test!(read_pixel(&data, W, 7, 7), BLUE);
+    TestResult::Pass
+}
+
+pub fn test_compose_blit_clips_and_converts() -> TestResult {
+    // 4x4 ARGB source, half off the left/top edge of a 6x6 RGB888 target
+    let src: alloc::vec::Vec<u8> = (0..16u32)
+        .flat_map(|i| (0xFF00_0000 | (i * 0x0001_0101)).to_le_bytes())
+        .collect();
+    let mut data = vec![0u8; 6 * 6 * 3];
+    let mut target = CompositeTarget {
+        data: &mut data,
+        width: 6,
+        height: 6,
+        pitch: 6 * 3,
+        format: PixelFormat::Rgb888,
+    };
+    target.blit(&CompositeSource {
+        data: &src,
+        format: PixelFormat::Argb8888,
+        x: -2,
+        y: -2,
+        width: 4,
+        height: 4,
+        color_key: None,
+        blend_mode: SurfaceBlendMode::Opaque,
+    });
+
+    let px = |x: usize, y: usize| {
+        let off = y * 18 + x * 3;
+        u32::from_le_bytes([data[off], data[off + 1], data[off + 2], 0])
+    };
+    // Source pixel (2, 2) = index 10 lands at target (0, 0)
+    assert_eq_test!(px(0, 0), 10 * 0x0001_0101);
+    assert_eq_test!(px(1, 1), 15 * 0x0001_0101);
+    assert_eq_test!(px(2, 0), 0, "clipped source ends at column 2");
+    assert_eq_test!(px(0, 2), 0, "clipped source ends at row 2");
+    TestResult::Pass
+}
+
+/// Half-transparent red over an opaque blue window, composed into ARGB.
+fn compose_translucent_over_blue(mode: u8) -> Option<(u32, u32)> {
+    const W: u32 = 8;
+    let red = ShmPixels::new(4, 4, |x, _| if x < 2 { 0x80FF_0000 } else { 0 })?;
+    let blue = ShmPixels::new(W, W, |_, _| 0xFF00_00FF)?;
+    let bottom = SurfaceFixture::with_token(TEST_TASK_BASE + 120, W, W, blue.token);
+    let top = SurfaceFixture::with_token(TEST_TASK_BASE + 121, 4, 4, red.token);
+    let _ = surface_set_blend_mode(top.task_id, mode);
+    top.commit();
+    bottom.commit();
+
+    let mut data = vec![0u8; (W * W * 4) as usize];
+    compositor_compose_into(&mut CompositeTarget {
+        data: &mut data,
+        width: W,
+        height: W,
+        pitch: (W * 4) as usize,
+        format: PixelFormat::Argb8888,
+    });
+    // Half-alpha pixel and fully transparent pixel of the top window
+    Some((read_pixel(&data, W, 0, 0), read_pixel(&data, W, 3, 0)))
+}
+
+pub fn test_blend_alpha_over_window() -> TestResult {
+    let Some((half, clear)) = compose_translucent_over_blue(SurfaceBlendMode::AlphaBlend as u8)
+    else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    // 0x80/0xFF of red over blue; alpha stays opaque
+    assert_eq_test!(half, 0xFF80_007F, "half-alpha red mixed with blue");
+    assert_eq_test!(clear, 0xFF00_00FF, "transparent pixel shows window below");
+    TestResult::Pass
+}
+
+pub fn test_blend_opaque_ignores_alpha() -> TestResult {
+    let Some((half, clear)) = compose_translucent_over_blue(SurfaceBlendMode::Opaque as u8) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    assert_eq_test!(half, 0x80FF_0000, "opaque surface copied as-is");
+    assert_eq_test!(clear, 0, "zero alpha still overwrites");
+
+    assert_eq_test!(
+        surface_set_blend_mode(TEST_TASK_BASE + 122, 7),
+        Err(CompositorError::InvalidArgument)
+    );
+    TestResult::Pass
+}
+
+pub fn test_blend_mode_occlusion() -> TestResult {
+    let pixels = [0u8; 4];
+    let source = |format, color_key, blend_mode| CompositeSource {
+        data: &pixels,
+        format,
+        x: 0,
+        y: 0,
+        width: 1,
+        height: 1,
+        color_key,
+        blend_mode,
+    };
+    let argb = PixelFormat::Argb8888;
+    assert_test!(source(argb, None, SurfaceBlendMode::Opaque).is_opaque());
+    assert_test!(
+        !source(argb, None, SurfaceBlendMode::AlphaBlend).is_opaque(),
+        "translucent window must not occlude"
+    );
+    assert_test!(
+        source(PixelFormat::Xrgb8888, None, SurfaceBlendMode::AlphaBlend).is_opaque(),
+        "no alpha channel to blend with"
+    );
+    assert_test!(!source(argb, Some(0), SurfaceBlendMode::Opaque).is_opaque());
+
+    let surface = SurfaceFixture::new(TEST_TASK_BASE + 123, 4, 4);
+    let _ = surface_set_blend_mode(surface.task_id, SurfaceBlendMode::AlphaBlend as u8);
+    surface.commit();
+    let mode = find_window(surface.task_id).map(|w| w.blend_mode());
+    assert_eq_test!(
+        mode,
+        Some(SurfaceBlendMode::AlphaBlend),
+        "exposed via WindowInfo"
+    );
+    TestResult::Pass
+}
+
+/// A surface created by the window-list tests.
+struct ListedWindow {
+    task_offset: u32,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    title: &'static [u8],
+}
+
+const LISTED_WINDOWS: [ListedWindow; 3] = [
+    ListedWindow {
+        task_offset: 80,
+        x: 10,
+        y: 20,
+        width: 64,
+        height: 48,
+        title: b"editor",
+    },
+    ListedWindow {
+        task_offset: 81,
+        x: 100,
+        y: 40,
+        width: 32,
+        height: 32,
+        title: b"clock",
+    },
+    ListedWindow {
+        task_offset: 82,
+        x: -5,
+        y: 300,
+        width: 120,
+        height: 16,
+        title: b"taskbar",
+    },
+];
+
+fn listed_surfaces() -> alloc::vec::Vec<SurfaceFixture> {
+    LISTED_WINDOWS
+        .iter()
+        .map(|w| {
+            let surface =
+                SurfaceFixture::with_token(TEST_TASK_BASE + w.task_offset, w.width, w.height, 0);
+            let _ = surface_set_window_position(surface.task_id, w.x, w.y);
+            let _ = surface_set_title(surface.task_id, w.title);
+            surface.commit();
+            surface
+        })
+        .collect()
+}
+
+fn listed_window_matches(info: &WindowInfo) -> bool {
+    LISTED_WINDOWS
+        .iter()
+        .find(|w| TEST_TASK_BASE + w.task_offset == info.task_id)
+        .is_some_and(|w| {
+            (info.x, info.y, info.width, info.height) == (w.x, w.y, w.width, w.height)
+                && info.title_str().as_bytes() == w.title
+                && info.shm_token == 0
+                && info.damage_count == 0
+        })
+}
+
+pub fn test_list_windows_batches_and_respects_max() -> TestResult {
+    let _surfaces = listed_surfaces();
+    let mut all = vec![WindowInfo::default(); 64];
+    let visible = surface_enumerate_windows(all.as_mut_ptr(), all.len() as u32);
+
+    let mut out = vec![WindowInfo::default(); 64];
+    assert_eq_test!(surface_list_windows(0, &mut out), visible);
+    assert_eq_test!(
+        surface_list_windows(0, &mut out[..2]),
+        2,
+        "capped by buffer"
+    );
+    assert_eq_test!(
+        surface_list_windows(visible - 1, &mut out[..4]),
+        1,
+        "resumes from start"
+    );
+    assert_eq_test!(surface_list_windows(visible, &mut out[..4]), 0);
+
+    // Listing in pieces yields the same windows as one enumeration
+    let mut pieced = 0usize;
+    while pieced < visible as usize {
+        let got = surface_list_windows(pieced as u32, &mut out[pieced..pieced + 2]);
+        assert_test!(got > 0);
+        pieced += got as usize;
+    }
+    for i in 0..visible as usize {
+        assert_eq_test!(out[i].task_id, all[i].task_id, "batched order");
+    }
+    let ours = out[..visible as usize]
+        .iter()
+        .filter(|w| listed_window_matches(w))
+        .count();
+    assert_eq_test!(ours, LISTED_WINDOWS.len(), "listed fields match");
+    TestResult::Pass
+}
+
+pub fn test_list_windows_copies_to_user() -> TestResult {
+    let _surfaces = listed_surfaces();
+    let mut all = vec![WindowInfo::default(); 64];
+    let visible = surface_enumerate_windows(all.as_mut_ptr(), all.len() as u32);
+    let entry = core::mem::size_of::<WindowInfo>() as u64;
+    let capacity = (PAGE_SIZE_4KB / entry) as u32;
+    if visible > capacity {
+        klog_info!("COMPOSITOR_TEST: too many live windows for one user page");
+        return TestResult::Fail;
+    }
+
+    let Some(env) = UserCopyEnv::new() else {
+        return TestResult::Fail;
+    };
+    let read_back = |i: u32| {
+        UserPtr::<WindowInfo>::try_new(env.base() + i as u64 * entry)
+            .ok()
+            .and_then(|ptr| copy_from_user(ptr).ok())
+    };
+
+    // `max` is honoured: nothing is written past the second entry
+    assert_test!(list_windows_to_user(env.base(), 2) == Ok(2));
+    assert_test!(
+        read_back(2).is_some_and(|w| w.task_id == 0),
+        "wrote past max"
+    );
+
+    assert_test!(list_windows_to_user(env.base(), capacity) == Ok(visible));
+    let mut ours = 0;
+    for i in 0..visible {
+        let Some(info) = read_back(i) else {
+            return TestResult::Fail;
+        };
+        assert_eq_test!(info.task_id, all[i as usize].task_id);
+        if listed_window_matches(&info) {
+            ours += 1;
+        }
+    }
+    assert_eq_test!(ours, LISTED_WINDOWS.len(), "copied fields match");
+
+    // Bad buffers are rejected
+    assert_test!(list_windows_to_user(0, 4) == Err(UserPtrError::Null));
+    assert_test!(list_windows_to_user(0xFFFF_8000_0000_1000, 4).is_err());
+    assert_test!(list_windows_to_user(env.unmapped(), 4) == Err(UserPtrError::NotMapped));
+    assert_test!(list_windows_to_user(env.base(), 0) == Ok(0));
+    TestResult::Pass
+}
+
+fn resize_pattern(x: u32, y: u32) -> u32 {
+    0xFF00_0000 | (y << 8) | x
+}
+
+pub fn test_surface_resize_preserves_content() -> TestResult {
+    let Some(front) = ShmPixels::new(16, 8, resize_pattern) else {
+        klog_info!("COMPOSITOR_TEST: shm_create failed");
+        return TestResult::Fail;
+    };
+    let surface = SurfaceFixture::with_token(TEST_TASK_BASE + 124, 16, 8, front.token);
+    let task_id = surface.task_id;
+
+    assert_eq_test!(
+        surface_resize(task_id, 0, 8),
+        Err(CompositorError::InvalidArgument)
+    );
+    assert_eq_test!(surface_resize(task_id, 32, 24), Ok(()));
+    drain_queue();
+
+    let Some(window) = find_window(task_id) else {
+        return TestResult::Fail;
+    };
+    assert_eq_test!((window.width, window.height), (32, 24));
+    assert_eq_test!(window.shm_token, front.token, "token survives the resize");
+    assert_eq_test!(window.damage_count, u8::MAX, "whole surface damaged");
+    assert_eq_test!(shm_pixel(front.token, 32, 5, 3), resize_pattern(5, 3));
+    assert_eq_test!(shm_pixel(front.token, 32, 15, 7), resize_pattern(15, 7));
+    assert_eq_test!(shm_pixel(front.token, 32, 20, 10), 0, "new area is blank");
+    assert_eq_test!(surface_get_buffer_age(task_id), 0);
+
+    let event = surface_poll_input(task_id);
+    assert_test!(event.is_some_and(
+        |e| e.event_type == InputEventType::SurfaceResize && e.resize_dimensions() == (32, 24)
+    ));
+
+    // Growing from 4 to 5 pages must hand the old 4 back
+    let _ = surface_resize(task_id, 64, 64);
+    drain_queue();
+    let before = page_alloc_stats().free_frames;
+    let _ = surface_resize(task_id, 64, 80);
+    drain_queue();
+    let used = before.saturating_sub(page_alloc_stats().free_frames);
+    assert_test!(used < 4, "old frames are freed");
+
+    if let Some(info) = get_display_info() {
+        let _ = surface_resize(task_id, info.width + 100, 4);
+        drain_queue();
+        let width = find_window(task_id).map(|w| w.width);
+        assert_eq_test!(width, Some(info.width), "clamped to the framebuffer");
+    }
+    input_cleanup_task(task_id);
+    TestResult::Pass
+}
+
+const GUARD: u8 = 0xAA;
+/// Offset into the user page of the tightly packed capture.
+const PACKED_OFFSET: usize = 2048;
+
+/// Distinct 0xRRGGBBAA color per pixel for capture tests.
+fn capture_color(x: usize, y: usize) -> u32 {
+    u32::from_be_bytes([
+        0x10 * x as u8 + y as u8,
+        0x80 + x as u8,
+        0x40 + y as u8,
+        0x7F,
+    ])
+}
+
+/// Capture a 3x2 mock framebuffer in `format` and compare each output pixel
+/// with its BGRA8888 bytes; output row padding must stay untouched.
+fn check_capture_format(format: PixelFormat) -> bool {
+    const W: usize = 3;
+    const H: usize = 2;
+    const OUT_STRIDE: usize = W * 4 + 8;
+    let bpp = format.bytes_per_pixel() as usize;
+    let pitch = W * bpp + 4;
+    let mut pixels = vec![GUARD; pitch * H];
+    for y in 0..H {
+        for x in 0..W {
+            let at = y * pitch + x * bpp;
+            format.encode_pixel(capture_color(x, y), &mut pixels[at..at + bpp]);
+        }
+    }
+
+    let mut out = [GUARD; OUT_STRIDE * H];
+    let info = DisplayInfo::new(W as u32, H as u32, pitch as u32, format);
+    let captured = {
+        let _fb = MockFramebuffer::new(&mut pixels, info);
+        compositor_capture(&mut out, OUT_STRIDE)
+    };
+    if captured != Ok((W as u32, H as u32)) {
+        return false;
+    }
+
+    let alpha = |rgba: u32| if format.has_alpha() { rgba as u8 } else { 0xFF };
+    (0..H).all(|y| {
+        let row = &out[y * OUT_STRIDE..(y + 1) * OUT_STRIDE];
+        let pixels_match = (0..W).all(|x| {
+            let [r, g, b, _] = capture_color(x, y).to_be_bytes();
+            row[x * 4..x * 4 + 4] == [b, g, r, alpha(capture_color(x, y))]
+        });
+        pixels_match && row[W * 4..].iter().all(|&b| b == GUARD)
+    })
+}
+
+pub fn test_capture_converts_to_bgra() -> TestResult {
+    for format in [
+        PixelFormat::Argb8888,
+        PixelFormat::Xrgb8888,
+        PixelFormat::Rgb888,
+        PixelFormat::Bgr888,
+        PixelFormat::Rgba8888,
+        PixelFormat::Bgra8888,
+    ] {
+        if !check_capture_format(format) {
+            klog_info!("COMPOSITOR_TEST: capture of {:?} mismatched", format);
+            return TestResult::Fail;
+        }
+    }
+
+    // Raw bytes: 24bpp [B, G, R] and XRGB's ignored X both come out opaque
+    let mut pixels = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x00];
+    let mut out = [0u8; 8];
+    let info = DisplayInfo::new(2, 1, 8, PixelFormat::Rgb888);
+    let rgb = {
+        let _fb = MockFramebuffer::new(&mut pixels, info);
+        compositor_capture(&mut out, 8).map(|_| out)
+    };
+    assert_eq_test!(rgb, Ok([0x11, 0x22, 0x33, 0xFF, 0x44, 0x55, 0x66, 0xFF]));
+
+    let info = DisplayInfo::new(2, 1, 8, PixelFormat::Xrgb8888);
+    let xrgb = {
+        let _fb = MockFramebuffer::new(&mut pixels, info);
+        compositor_capture(&mut out, 8).map(|_| out)
+    };
+    assert_eq_test!(xrgb, Ok([0x11, 0x22, 0x33, 0xFF, 0x55, 0x66, 0x77, 0xFF]));
+    TestResult::Pass
+}
+
+pub fn test_capture_validates_buffer() -> TestResult {
+    const W: u32 = 4;
+    const H: u32 = 3;
+    const STRIDE: usize = 20;
+    let mut pixels = [0x5Au8; (W * H * 4) as usize];
+    let mut out = [0u8; STRIDE * 3];
+    let info = DisplayInfo::new(W, H, W * 4, PixelFormat::Argb8888);
+    assert_eq_test!(info.capture_size(STRIDE), Some(STRIDE * 2 + 16));
+    assert_eq_test!(info.capture_size(12), None, "stride shorter than a row");
+
+    let Some(env) = UserCopyEnv::new() else {
+        return TestResult::Fail;
+    };
+    let packed_at = env.base() + PACKED_OFFSET as u64;
+    let (Ok(user), Ok(packed_user)) = (
+        UserBytes::try_new(env.base(), STRIDE * 3),
+        UserBytes::try_new(packed_at, 49),
+    ) else {
+        return TestResult::Fail;
+    };
+    let _ = copy_bytes_to_user(user, &[GUARD; STRIDE * 3]);
+    let _ = copy_bytes_to_user(packed_user, &[GUARD; 49]);
+
+    let (short, narrow, packed, strided) = {
+        let _fb = MockFramebuffer::new(&mut pixels, info);
+        (
+            compositor_capture(&mut out[..STRIDE * 2 + 15], STRIDE),
+            compositor_capture(&mut out, 12),
+            capture_to_user(packed_at, 48, 0),
+            capture_to_user(env.base(), (STRIDE * 3) as u64, STRIDE as u64),
+        )
+    };
+    assert_eq_test!(short, Err(CompositorError::InvalidArgument));
+    assert_eq_test!(narrow, Err(CompositorError::InvalidArgument));
+    assert_eq_test!(packed, Ok(()));
+    assert_eq_test!(strided, Ok(()));
+
+    let mut read = [0u8; STRIDE * 3];
+    assert_test!(copy_bytes_from_user(user, &mut read).is_ok());
+    for row in read.chunks_exact(STRIDE) {
+        assert_test!(row[..16].iter().all(|&b| b == 0x5A), "row captured");
+        assert_test!(
+            row[16..].iter().all(|&b| b == GUARD),
+            "stride padding untouched"
+        );
+    }
+    let mut read = [0u8; 49];
+    assert_test!(copy_bytes_from_user(packed_user, &mut read).is_ok());
+    assert_test!(read[..48].iter().all(|&b| b == 0x5A), "stride 0 packs rows");
+    assert_eq_test!(read[48], GUARD, "nothing past the frame");
+
+    let _headless = HeadlessGuard::new();
+    assert_eq_test!(
+        compositor_capture(&mut out, STRIDE),
+        Err(CompositorError::NoFramebuffer)
+    );
+    assert_eq_test!(
+        capture_to_user(packed_at, 48, 0),
+        Err(CompositorError::NoFramebuffer)
+    );
+    TestResult::Pass
+}
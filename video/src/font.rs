@@ -36,6 +36,19 @@ pub fn draw_string(ctx: &mut GraphicsContext, x: i32, y: i32, text: &[u8], fg: u
     font_render::draw_string(ctx, x, y, text, fg, bg);
 }
 
+/// `draw_string` scaled by a whole factor, for banners and titles.
+pub fn draw_string_scaled(
+    ctx: &mut GraphicsContext,
+    x: i32,
+    y: i32,
+    text: &[u8],
+    scale: i32,
+    fg: u32,
+    bg: u32,
+) {
+    font_render::draw_string_scaled(ctx, x, y, text, scale, fg, bg);
+}
+
 pub fn draw_str(ctx: &mut GraphicsContext, x: i32, y: i32, text: &str, fg: u32, bg: u32) {
     font_render::draw_str(ctx, x, y, text, fg, bg);
 }
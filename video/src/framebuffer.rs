@@ -2,18 +2,24 @@ use core::ffi::c_int;
 use core::ptr;
 
 use slopos_abi::addr::{PhysAddr, VirtAddr};
+use slopos_abi::arch::x86_64::paging::PAGE_SIZE_4KB;
 use slopos_abi::pixel::DrawPixelFormat;
 use slopos_abi::{DisplayInfo, PixelFormat};
-use slopos_lib::{IrqMutex, klog_debug, klog_warn};
-use slopos_mm::hhdm::PhysAddrHhdm;
+use slopos_lib::{IrqMutex, klog_debug_mod, klog_warn};
+use slopos_mm::hhdm::{PhysAddrHhdm, VirtAddrHhdm};
 
 const MIN_FRAMEBUFFER_WIDTH: u32 = 320;
 const MIN_FRAMEBUFFER_HEIGHT: u32 = 240;
 const MAX_BUFFER_SIZE: u32 = 64 * 1024 * 1024;
+/// Scanout base registers take page-aligned surface addresses.
+const SCANOUT_BASE_ALIGN: u64 = PAGE_SIZE_4KB;
 
 #[derive(Copy, Clone)]
 pub(crate) struct FbState {
     pub(crate) base: VirtAddr,
+    pub(crate) phys: PhysAddr,
+    /// Buffer the display is reading; differs from `phys` after a zero-copy flip.
+    pub(crate) scanout: PhysAddr,
     pub(crate) info: DisplayInfo,
 }
 
@@ -61,6 +67,10 @@ impl FramebufferState {
 
 static FRAMEBUFFER: IrqMutex<FramebufferState> = IrqMutex::new(FramebufferState::new());
 static FRAMEBUFFER_FLUSH: IrqMutex<Option<fn() -> c_int>> = IrqMutex::new(None);
+static FRAMEBUFFER_SCANOUT: IrqMutex<Option<ScanoutCallback>> = IrqMutex::new(None);
+
+/// Backend hook that retargets display scanout; returns 0 on success.
+pub type ScanoutCallback = fn(PhysAddr) -> c_int;
 
 fn init_state_from_raw(addr: u64, width: u32, height: u32, pitch: u32, bpp: u8) -> i32 {
     if addr == 0 || width < MIN_FRAMEBUFFER_WIDTH || width > DisplayInfo::MAX_DIMENSION {
@@ -97,8 +107,11 @@ fn init_state_from_raw(addr: u64, width: u32, height: u32, pitch: u32, bpp: u8)
 
     let display_info = DisplayInfo::new(width, height, pitch, PixelFormat::from_bpp(bpp));
 
+    let phys = mapped_base.to_phys_hhdm();
     let fb_state = FbState {
         base: mapped_base,
+        phys,
+        scanout: phys,
         info: display_info,
     };
 
@@ -118,7 +131,8 @@ pub fn init_with_display_info(address: *mut u8, info: &DisplayInfo) -> i32 {
 
     if rc == 0 {
         if let Some(fb) = FRAMEBUFFER.lock().fb {
-            klog_debug!(
+            klog_debug_mod!(
+                "video",
                 "Framebuffer init: phys=0x{:x} virt=0x{:x} {}x{} pitch={} bpp={}",
                 address as u64,
                 fb.base.as_u64(),
@@ -151,72 +165,119 @@ pub fn framebuffer_is_initialized() -> i32 {
     FRAMEBUFFER.lock().fb.is_some() as i32
 }
 
-pub fn framebuffer_clear(color: u32) {
-    let fb = match FRAMEBUFFER.lock().fb {
-        Some(fb) => fb,
-        None => return,
+/// Fill `width`x`height` pixels of a `pitch`-strided buffer with `pixel`
+/// (already in the buffer's format).
+///
+/// Rows are clipped to the pitch and to `buf`, so a bogus mode never writes
+/// past a row end or the end of the buffer. `buf` is usually framebuffer
+/// MMIO, so every store is volatile. Returns the number of rows filled.
+pub(crate) fn fill_pixels(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    pitch: usize,
+    bytes_pp: usize,
+    pixel: u32,
+) -> usize {
+    let bytes: [u8; 4] = match bytes_pp {
+        2 => {
+            let [b0, b1] = (pixel as u16).to_le_bytes();
+            [b0, b1, 0, 0]
+        }
+        // 24bpp is stored high byte first, matching framebuffer_set_pixel
+        3 => [(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8, 0],
+        4 => pixel.to_le_bytes(),
+        _ => return 0,
     };
-
-    let bytes_pp = fb.info.bytes_per_pixel() as usize;
-    let converted = fb.draw_pixel_format().convert_color(color);
-    let base = fb.base_ptr();
-    let pitch = fb.pitch() as usize;
-    let width = fb.width() as usize;
-    let height = fb.height() as usize;
-
-    if bytes_pp == 4 {
-        let b0 = (converted & 0xFF) as u8;
-        let b1 = ((converted >> 8) & 0xFF) as u8;
-        let b2 = ((converted >> 16) & 0xFF) as u8;
-        let b3 = ((converted >> 24) & 0xFF) as u8;
-
-        if b0 == b1 && b1 == b2 && b2 == b3 {
-            // Fast path: all bytes identical (black, white, grey) - bulk memset per row
-            let row_bytes = width * 4;
-            for y in 0..height {
-                let row_ptr = unsafe { base.add(y * pitch) };
-                unsafe { ptr::write_bytes(row_ptr, b0, row_bytes) };
-            }
-        } else {
-            // 64-bit writes (2 pixels at a time) for non-uniform colors
-            let color64 = (converted as u64) | ((converted as u64) << 32);
-            let pairs = width / 2;
-            let remainder = width % 2;
-
-            for y in 0..height {
-                let row_ptr = unsafe { base.add(y * pitch) };
-                unsafe {
-                    let mut ptr64 = row_ptr as *mut u64;
-                    for _ in 0..pairs {
-                        ptr64.write_volatile(color64);
-                        ptr64 = ptr64.add(1);
-                    }
-                    if remainder > 0 {
-                        (ptr64 as *mut u32).write_volatile(converted);
+    if pitch == 0 {
+        return 0;
+    }
+    let row_bytes = width.saturating_mul(bytes_pp).min(pitch) / bytes_pp * bytes_pp;
+
+    let mut rows = 0;
+    for row in buf.chunks_mut(pitch).take(height) {
+        let Some(row) = row.get_mut(..row_bytes) else {
+            break;
+        };
+        for px in row.chunks_exact_mut(bytes_pp) {
+            let p = px.as_mut_ptr();
+            unsafe {
+                if bytes_pp == 4 && (p as *mut u32).is_aligned() {
+                    ptr::write_volatile(p as *mut u32, pixel);
+                } else {
+                    for (i, &b) in bytes[..bytes_pp].iter().enumerate() {
+                        ptr::write_volatile(p.add(i), b);
                     }
                 }
             }
         }
-    } else {
-        // Fallback for 2bpp/3bpp
-        for y in 0..height {
-            let row_ptr = unsafe { base.add(y * pitch) };
-            for x in 0..width {
-                let pixel_ptr = unsafe { row_ptr.add(x * bytes_pp) };
-                unsafe {
-                    match bytes_pp {
-                        2 => ptr::write_volatile(pixel_ptr as *mut u16, converted as u16),
-                        3 => {
-                            ptr::write_volatile(pixel_ptr, ((converted >> 16) & 0xFF) as u8);
-                            ptr::write_volatile(pixel_ptr.add(1), ((converted >> 8) & 0xFF) as u8);
-                            ptr::write_volatile(pixel_ptr.add(2), (converted & 0xFF) as u8);
-                        }
-                        _ => {}
-                    }
-                }
-            }
+        rows += 1;
+    }
+    rows
+}
+
+/// Convert `height` rows of `width` pixels in `format` to BGRA8888, i.e. the
+/// bytes B, G, R, A in memory, whatever the source depth.
+///
+/// Source rows are `pitch` bytes apart and output rows `out_stride` apart;
+/// output padding is left alone. Stops at the first row that does not fit
+/// in `src` or `out` and returns the number of rows converted.
+pub(crate) fn capture_pixels(
+    src: &[u8],
+    width: usize,
+    height: usize,
+    pitch: usize,
+    format: PixelFormat,
+    out: &mut [u8],
+    out_stride: usize,
+) -> usize {
+    let bytes_pp = format.bytes_per_pixel() as usize;
+    let src_row = width.saturating_mul(bytes_pp);
+    let out_row = width.saturating_mul(4);
+    if pitch < src_row || out_stride < out_row {
+        return 0;
+    }
+
+    for y in 0..height {
+        let Some(src) = src.get(y * pitch..y * pitch + src_row) else {
+            return y;
+        };
+        let Some(out) = out.get_mut(y * out_stride..y * out_stride + out_row) else {
+            return y;
+        };
+        for (px, dst) in src.chunks_exact(bytes_pp).zip(out.chunks_exact_mut(4)) {
+            let rgba = format.decode_pixel(px);
+            dst.copy_from_slice(&[
+                (rgba >> 8) as u8,
+                (rgba >> 16) as u8,
+                (rgba >> 24) as u8,
+                rgba as u8,
+            ]);
         }
     }
+    height
+}
+
+/// Fill the whole visible framebuffer with `color`.
+pub fn framebuffer_clear(color: u32) {
+    let fb = match FRAMEBUFFER.lock().fb {
+        Some(fb) => fb,
+        None => return,
+    };
+
+    let Some(len) = (fb.pitch() as usize).checked_mul(fb.height() as usize) else {
+        return;
+    };
+    let converted = fb.draw_pixel_format().convert_color(color);
+    let buf = unsafe { core::slice::from_raw_parts_mut(fb.base_ptr(), len) };
+    fill_pixels(
+        buf,
+        fb.width() as usize,
+        fb.height() as usize,
+        fb.pitch() as usize,
+        fb.info.bytes_per_pixel() as usize,
+        converted,
+    );
 }
 
 pub fn framebuffer_set_pixel(x: u32, y: u32, color: u32) {
@@ -368,16 +429,122 @@ pub(crate) fn snapshot() -> Option<FbState> {
     FRAMEBUFFER.lock().fb
 }
 
+/// Swap the active framebuffer, returning the previous one.
+pub(crate) fn replace_state(fb: Option<FbState>) -> Option<FbState> {
+    core::mem::replace(&mut FRAMEBUFFER.lock().fb, fb)
+}
+
 pub fn register_flush_callback(callback: fn() -> c_int) {
     let mut guard = FRAMEBUFFER_FLUSH.lock();
     *guard = Some(callback);
 }
 
-pub fn framebuffer_flush() -> c_int {
+fn flush_backend() -> c_int {
     let guard = FRAMEBUFFER_FLUSH.lock();
     if let Some(cb) = *guard { cb() } else { 0 }
 }
 
+/// Record that the display now reads `scanout` for the framebuffer at `phys`.
+fn set_scanout(phys: PhysAddr, scanout: PhysAddr) {
+    if let Some(fb) = FRAMEBUFFER.lock().fb.as_mut().filter(|fb| fb.phys == phys) {
+        fb.scanout = scanout;
+    }
+}
+
+/// Publish what was drawn into the framebuffer.
+///
+/// Kernel drawing always targets `base`, so if a zero-copy flip left scanout
+/// on a client buffer it is pointed back at the framebuffer first.
+pub fn framebuffer_flush() -> c_int {
+    let fb = FRAMEBUFFER.lock().fb;
+    if let Some(fb) = fb.filter(|fb| fb.scanout != fb.phys) {
+        let scanout = *FRAMEBUFFER_SCANOUT.lock();
+        if scanout.is_some_and(|retarget| retarget(fb.phys) == 0) {
+            set_scanout(fb.phys, fb.phys);
+        }
+    }
+    flush_backend()
+}
+
+/// Point scanout back at the framebuffer if a zero-copy flip left it inside
+/// the `size` bytes at `phys`, which are about to be freed. Runs as the shm
+/// release hook, so the display never reads frames after they are reused.
+pub fn framebuffer_release_scanout(phys: PhysAddr, size: usize) {
+    let Some(fb) = FRAMEBUFFER.lock().fb else {
+        return;
+    };
+    let start = phys.as_u64();
+    let end = start.saturating_add(size as u64);
+    if fb.scanout != fb.phys && (start..end).contains(&fb.scanout.as_u64()) {
+        framebuffer_flush();
+    }
+}
+
+/// Register a backend hook that points display scanout at a physical address.
+/// Only backends whose hardware can retarget the plane base should register;
+/// without one every flip copies.
+pub fn register_scanout_callback(callback: ScanoutCallback) {
+    replace_scanout_callback(Some(callback));
+}
+
+pub(crate) fn replace_scanout_callback(
+    callback: Option<ScanoutCallback>,
+) -> Option<ScanoutCallback> {
+    core::mem::replace(&mut *FRAMEBUFFER_SCANOUT.lock(), callback)
+}
+
+/// Whether `size` bytes at `shm_phys` can be scanned out in place of the
+/// framebuffer at `fb_phys`.
+///
+/// The buffer must be page-aligned (scanout base granularity) and must either
+/// be the framebuffer itself or not overlap it at all; a partially
+/// overlapping buffer would tear the live image.
+pub fn can_zero_copy(shm_phys: PhysAddr, fb_phys: PhysAddr, size: usize) -> bool {
+    if shm_phys.is_null() || fb_phys.is_null() || size == 0 {
+        return false;
+    }
+    if !shm_phys.is_aligned(SCANOUT_BASE_ALIGN) {
+        return false;
+    }
+    if shm_phys == fb_phys {
+        return true;
+    }
+    let (shm, fb, len) = (shm_phys.as_u64(), fb_phys.as_u64(), size as u64);
+    let (Some(shm_end), Some(fb_end)) = (shm.checked_add(len), fb.checked_add(len)) else {
+        return false;
+    };
+    shm_end <= fb || fb_end <= shm
+}
+
+/// Present a client buffer by retargeting scanout at it instead of copying.
+///
+/// Falls back to `fb_flip_from_shm` when no scanout hook is registered, the
+/// buffer is smaller than the framebuffer, `can_zero_copy` rejects it, or the
+/// backend fails to retarget. The buffer stays on screen until the next flip
+/// or `framebuffer_flush`; an shm buffer freed before then is taken off
+/// screen by `framebuffer_release_scanout`.
+pub fn fb_flip_zero_copy(shm_phys: PhysAddr, size: usize) -> c_int {
+    let fb = match FRAMEBUFFER.lock().fb {
+        Some(fb) => fb,
+        None => return -1,
+    };
+    let scanout = *FRAMEBUFFER_SCANOUT.lock();
+
+    let fb_size = fb.info.buffer_size();
+    if size >= fb_size && can_zero_copy(shm_phys, fb.phys, fb_size) {
+        // Already scanning out of this buffer: nothing to move
+        if shm_phys == fb.scanout {
+            return flush_backend();
+        }
+        if scanout.is_some_and(|retarget| retarget(shm_phys) == 0) {
+            set_scanout(fb.phys, shm_phys);
+            return flush_backend();
+        }
+    }
+
+    fb_flip_from_shm(shm_phys, size)
+}
+
 pub fn fb_flip_from_shm(shm_phys: PhysAddr, size: usize) -> c_int {
     let fb = match FRAMEBUFFER.lock().fb {
         Some(fb) => fb,
@@ -396,8 +563,31 @@ pub fn fb_flip_from_shm(shm_phys: PhysAddr, size: usize) -> c_int {
     };
 
     unsafe {
-        ptr::copy_nonoverlapping(shm_virt as *const u8, fb.base_ptr(), copy_size);
+        copy_to_framebuffer(fb.base_ptr(), shm_virt as *const u8, copy_size);
     }
 
     framebuffer_flush()
 }
+
+/// Copy `len` bytes from RAM at `src` into framebuffer MMIO at `dst` with
+/// volatile stores, a word at a time where `dst` allows it.
+///
+/// # Safety
+/// `src` must be readable and `dst` writable for `len` bytes.
+unsafe fn copy_to_framebuffer(dst: *mut u8, src: *const u8, len: usize) {
+    const WORD: usize = core::mem::size_of::<u64>();
+    let mut off = 0;
+    unsafe {
+        if (dst as *mut u64).is_aligned() {
+            while off + WORD <= len {
+                let word = ptr::read_unaligned(src.add(off) as *const u64);
+                ptr::write_volatile(dst.add(off) as *mut u64, word);
+                off += WORD;
+            }
+        }
+        while off < len {
+            ptr::write_volatile(dst.add(off), *src.add(off));
+            off += 1;
+        }
+    }
+}
@@ -0,0 +1,363 @@
+//! Framebuffer tests - flip path selection and clearing.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use slopos_abi::addr::PhysAddr;
+use slopos_abi::draw_primitives;
+use slopos_abi::pixel::DrawPixelFormat;
+use slopos_lib::testing::{TestCanvas, TestResult};
+use slopos_lib::{assert_eq_test, assert_test, klog_info};
+use slopos_mm::hhdm::PhysAddrHhdm;
+use slopos_mm::shared_memory::{
+    register_shm_release_hook, shm_create, shm_destroy, shm_get_buffer_info,
+};
+
+use crate::framebuffer::{
+    FbState, can_zero_copy, fb_flip_zero_copy, fill_pixels, framebuffer_release_scanout,
+    replace_scanout_callback, snapshot,
+};
+
+/// Process ID used as owner of test shm buffers.
+const TEST_SHM_OWNER: u32 = 0x7E58;
+
+pub fn test_zero_copy_selection() -> TestResult {
+    let fb = PhysAddr::new(0x8000_0000);
+    let size = 0x30_0000;
+
+    assert_test!(
+        can_zero_copy(PhysAddr::new(0x1000_0000), fb, size),
+        "disjoint aligned buffer"
+    );
+    assert_test!(can_zero_copy(fb, fb, size), "buffer is the framebuffer");
+    assert_test!(
+        !can_zero_copy(PhysAddr::new(0x1000_0040), fb, size),
+        "unaligned base"
+    );
+    assert_test!(
+        !can_zero_copy(PhysAddr::new(0x8000_0000 + 0x1000), fb, size),
+        "partial overlap"
+    );
+    assert_test!(
+        !can_zero_copy(PhysAddr::new(0x8000_0000 - 0x1000), fb, size),
+        "partial overlap from below"
+    );
+    assert_test!(!can_zero_copy(PhysAddr::NULL, fb, size), "null buffer");
+    assert_test!(
+        !can_zero_copy(PhysAddr::new(0x1000_0000), fb, 0),
+        "empty flip"
+    );
+    TestResult::Pass
+}
+
+/// Raw bytes of the top-left framebuffer pixel.
+fn first_pixel(fb: &FbState) -> u32 {
+    let mut bytes = [0u8; 4];
+    for (i, b) in bytes
+        .iter_mut()
+        .enumerate()
+        .take(fb.info.bytes_per_pixel() as usize)
+    {
+        *b = unsafe { fb.base_ptr().add(i).read_volatile() };
+    }
+    u32::from_le_bytes(bytes)
+}
+
+static RETARGETED_TO: AtomicU64 = AtomicU64::new(0);
+
+fn fake_scanout(phys: PhysAddr) -> core::ffi::c_int {
+    RETARGETED_TO.store(phys.as_u64(), Ordering::SeqCst);
+    0
+}
+
+pub fn test_zero_copy_fallback_vs_retarget() -> TestResult {
+    let Some(fb) = snapshot() else {
+        klog_info!("FB_TEST: no framebuffer, skipping");
+        return TestResult::Pass;
+    };
+    let fb_size = fb.info.buffer_size();
+    let before = first_pixel(&fb);
+
+    let token = shm_create(TEST_SHM_OWNER, fb_size as u64, 0);
+    if token == 0 {
+        klog_info!("FB_TEST: shm_create failed");
+        return TestResult::Fail;
+    }
+    let (phys, _, _) = shm_get_buffer_info(token);
+    // Every bit differs from what is on screen, so a copy is always visible
+    unsafe {
+        let px = phys.to_virt().as_u64() as *mut u8;
+        let bytes = (!before).to_le_bytes();
+        for (i, &b) in bytes
+            .iter()
+            .enumerate()
+            .take(fb.info.bytes_per_pixel() as usize)
+        {
+            px.add(i).write(b);
+        }
+    }
+
+    let prev = replace_scanout_callback(Some(fake_scanout));
+    RETARGETED_TO.store(0, Ordering::SeqCst);
+    let rc = fb_flip_zero_copy(phys, fb_size);
+    let retargeted = RETARGETED_TO.load(Ordering::SeqCst);
+    let after_retarget = first_pixel(&fb);
+    let scanout_after_retarget = snapshot().map(|s| s.scanout);
+
+    // Too short to scan out, so this copies and must bring scanout home
+    RETARGETED_TO.store(0, Ordering::SeqCst);
+    let rc_copy = fb_flip_zero_copy(phys, fb_size - 1);
+    let restored_to = RETARGETED_TO.load(Ordering::SeqCst);
+    let after_copy = first_pixel(&fb);
+    let scanout_after_copy = snapshot().map(|s| s.scanout);
+    replace_scanout_callback(prev);
+    shm_destroy(TEST_SHM_OWNER, token);
+
+    assert_eq_test!(rc, 0);
+    assert_eq_test!(retargeted, phys.as_u64(), "scanout hook not used");
+    assert_eq_test!(after_retarget, before, "zero-copy flip must not copy");
+    assert_eq_test!(scanout_after_retarget, Some(phys), "scanout not tracked");
+    assert_eq_test!(rc_copy, 0);
+    assert_test!(after_copy != before, "fallback flip must copy");
+    assert_eq_test!(restored_to, fb.phys.as_u64(), "scanout not restored");
+    assert_eq_test!(scanout_after_copy, Some(fb.phys), "scanout not tracked");
+    TestResult::Pass
+}
+
+pub fn test_zero_copy_scanout_released_on_destroy() -> TestResult {
+    let Some(fb) = snapshot() else {
+        klog_info!("FB_TEST: no framebuffer, skipping");
+        return TestResult::Pass;
+    };
+    let fb_size = fb.info.buffer_size();
+    let token = shm_create(TEST_SHM_OWNER, fb_size as u64, 0);
+    if token == 0 {
+        klog_info!("FB_TEST: shm_create failed");
+        return TestResult::Fail;
+    }
+    let (phys, _, _) = shm_get_buffer_info(token);
+
+    register_shm_release_hook(framebuffer_release_scanout);
+    let prev = replace_scanout_callback(Some(fake_scanout));
+    let rc = fb_flip_zero_copy(phys, fb_size);
+    let scanout_on_buffer = snapshot().map(|s| s.scanout);
+    RETARGETED_TO.store(0, Ordering::SeqCst);
+    let destroyed = shm_destroy(TEST_SHM_OWNER, token);
+    let restored_to = RETARGETED_TO.load(Ordering::SeqCst);
+    let scanout_after_destroy = snapshot().map(|s| s.scanout);
+    replace_scanout_callback(prev);
+
+    assert_eq_test!(rc, 0);
+    assert_eq_test!(scanout_on_buffer, Some(phys));
+    assert_eq_test!(destroyed, 0);
+    assert_eq_test!(
+        restored_to,
+        fb.phys.as_u64(),
+        "scanout must leave the buffer before its frames are freed"
+    );
+    assert_eq_test!(scanout_after_destroy, Some(fb.phys));
+    TestResult::Pass
+}
+
+const GUARD: u8 = 0xAA;
+
+pub fn test_fb_clear_fills_visible_pixels() -> TestResult {
+    const W: usize = 5;
+    const H: usize = 3;
+    const PITCH: usize = W * 4 + 12;
+    let color = DrawPixelFormat::Rgb.convert_color(0x0012_3456);
+    assert_eq_test!(color, 0x0056_3412, "color converted to RGB order");
+
+    // One extra row of guard bytes past pitch * height
+    let mut buf = [GUARD; PITCH * (H + 1)];
+    let rows = fill_pixels(&mut buf[..PITCH * H], W, H, PITCH, 4, color);
+    assert_eq_test!(rows, H);
+
+    for y in 0..H {
+        let row = &buf[y * PITCH..(y + 1) * PITCH];
+        for x in 0..W {
+            let px = u32::from_le_bytes(row[x * 4..x * 4 + 4].try_into().unwrap());
+            if px != color {
+                klog_info!("FB_TEST: pixel ({}, {}) = {:#x}", x, y, px);
+                return TestResult::Fail;
+            }
+        }
+        assert_test!(
+            row[W * 4..].iter().all(|&b| b == GUARD),
+            "pitch padding untouched"
+        );
+    }
+    assert_test!(
+        buf[PITCH * H..].iter().all(|&b| b == GUARD),
+        "nothing written past pitch * height"
+    );
+    TestResult::Pass
+}
+
+pub fn test_fb_clear_24bpp_and_uniform() -> TestResult {
+    const W: usize = 4;
+    const H: usize = 2;
+    const PITCH: usize = 16;
+    let mut buf = [GUARD; PITCH * H];
+    assert_eq_test!(fill_pixels(&mut buf, W, H, PITCH, 3, 0x0011_2233), H);
+    for y in 0..H {
+        let row = &buf[y * PITCH..(y + 1) * PITCH];
+        assert_test!(
+            row[..W * 3]
+                .chunks_exact(3)
+                .all(|px| px == [0x11, 0x22, 0x33]),
+            "24bpp pixel bytes"
+        );
+        assert_test!(row[W * 3..].iter().all(|&b| b == GUARD), "24bpp padding");
+    }
+
+    // Uniform colors take the memset path and must clip the same way
+    let mut buf = [GUARD; PITCH * H];
+    assert_eq_test!(fill_pixels(&mut buf, 3, H, PITCH, 4, 0), H);
+    for y in 0..H {
+        let row = &buf[y * PITCH..(y + 1) * PITCH];
+        assert_test!(row[..12].iter().all(|&b| b == 0), "black row");
+        assert_test!(row[12..].iter().all(|&b| b == GUARD), "black padding");
+    }
+    TestResult::Pass
+}
+
+pub fn test_fill_gradient_interpolates_rows() -> TestResult {
+    const TOP: u32 = 0xFF00_3090;
+    const BOTTOM: u32 = 0xFFF0_9030;
+    let mut strip = TestCanvas::<1, 4>::new();
+    draw_primitives::fill_gradient(&mut strip, 0, 0, 1, 4, TOP, BOTTOM);
+    let rows = strip.px.map(|[px]| px);
+    assert_eq_test!(rows[0], TOP);
+    assert_eq_test!(rows[3], BOTTOM);
+    assert_eq_test!(rows[1], 0xFF50_5070, "one third of the way");
+    assert_eq_test!(rows[2], 0xFFA0_7050, "two thirds of the way");
+
+    // Clipping keeps each row's color; a horizontal gradient runs per column
+    let mut strip = TestCanvas::<1, 4>::new();
+    draw_primitives::fill_gradient(&mut strip, 0, -2, 1, 4, TOP, BOTTOM);
+    assert_eq_test!(strip.px.map(|[px]| px), [rows[2], rows[3], 0, 0]);
+    let mut strip = TestCanvas::<4, 1>::new();
+    draw_primitives::fill_gradient_horizontal(&mut strip, 0, 0, 4, 1, TOP, BOTTOM);
+    assert_eq_test!(strip.px[0], rows);
+
+    assert_eq_test!(
+        draw_primitives::lerp_color(TOP, BOTTOM, 0, 0),
+        TOP,
+        "single row"
+    );
+    assert_eq_test!(
+        draw_primitives::lerp_color(TOP, BOTTOM, u32::MAX / 3, u32::MAX),
+        rows[1],
+        "huge step counts do not overflow"
+    );
+    TestResult::Pass
+}
+
+/// Flattened curve summary: end points, vertex count and how close a
+/// vertex came to `target`.
+struct CurveTrace {
+    first: (i32, i32),
+    last: (i32, i32),
+    count: u32,
+    nearest: i32,
+    in_hull: bool,
+}
+
+fn trace_curve(
+    flatten: impl FnOnce(&mut dyn FnMut(i32, i32)),
+    target: (i32, i32),
+    hull: (i32, i32, i32, i32),
+) -> CurveTrace {
+    let mut trace = CurveTrace {
+        first: (i32::MIN, i32::MIN),
+        last: (i32::MIN, i32::MIN),
+        count: 0,
+        nearest: i32::MAX,
+        in_hull: true,
+    };
+    flatten(&mut |x, y| {
+        if trace.count == 0 {
+            trace.first = (x, y);
+        }
+        trace.last = (x, y);
+        trace.count += 1;
+        let distance = (x - target.0).abs().max((y - target.1).abs());
+        trace.nearest = trace.nearest.min(distance);
+        let (x0, y0, x1, y1) = hull;
+        trace.in_hull &= (x0..=x1).contains(&x) && (y0..=y1).contains(&y);
+    });
+    trace
+}
+
+pub fn test_bezier_flattening() -> TestResult {
+    // B(1/2) = (p0 + 3 p1 + 3 p2 + p3) / 8 = (50, 75)
+    let (p0, p1, p2, p3) = ((0, 0), (0, 100), (100, 100), (100, 0));
+    let cubic = trace_curve(
+        |plot| draw_primitives::cubic_bezier_points(p0, p1, p2, p3, plot),
+        (50, 75),
+        (0, 0, 100, 100),
+    );
+    assert_eq_test!(cubic.first, p0);
+    assert_eq_test!(cubic.last, p3);
+    assert_test!(cubic.nearest <= 1, "passes through the analytic midpoint");
+    assert_test!(cubic.in_hull, "stays inside the control hull");
+    assert_test!(
+        (8..=128).contains(&cubic.count),
+        "smooth without excessive segments"
+    );
+
+    // B(1/2) = (p0 + 2 p1 + p2) / 4 = (50, 50)
+    let quad = trace_curve(
+        |plot| draw_primitives::quad_bezier_points((0, 0), (50, 100), (100, 0), plot),
+        (50, 50),
+        (0, 0, 100, 100),
+    );
+    assert_eq_test!((quad.first, quad.last), ((0, 0), (100, 0)));
+    assert_test!(quad.nearest <= 1, "quadratic midpoint");
+
+    // Collinear control points need no subdivision at all
+    let straight = trace_curve(
+        |plot| draw_primitives::cubic_bezier_points((0, 0), (10, 5), (20, 10), (30, 15), plot),
+        (15, 7),
+        (0, 0, 30, 15),
+    );
+    assert_eq_test!(straight.count, 2, "a straight cubic is one segment");
+    TestResult::Pass
+}
+
+pub fn test_fb_clear_16bpp() -> TestResult {
+    const W: usize = 3;
+    const PITCH: usize = 8;
+    let mut buf = [GUARD; PITCH * 2];
+    assert_eq_test!(fill_pixels(&mut buf, W, 2, PITCH, 2, 0xF81F), 2);
+    for row in buf.chunks_exact(PITCH) {
+        assert_test!(
+            row[..W * 2].chunks_exact(2).all(|px| px == [0x1F, 0xF8]),
+            "16bpp pixel bytes"
+        );
+        assert_test!(row[W * 2..].iter().all(|&b| b == GUARD), "16bpp padding");
+    }
+    TestResult::Pass
+}
+
+pub fn test_fb_clear_clipped_to_pitch_and_buffer() -> TestResult {
+    // Width claims more bytes than the pitch holds: clip at the row end
+    let mut buf = [GUARD; 16 * 2];
+    assert_eq_test!(fill_pixels(&mut buf, 10, 2, 16, 4, 0x0102_0304), 2);
+    assert_test!(
+        buf.chunks_exact(4).all(|px| px == [4, 3, 2, 1]),
+        "each row filled up to its pitch only"
+    );
+
+    // Buffer shorter than pitch * height: stop at the last whole row
+    let mut buf = [GUARD; 16 * 2 + 8];
+    assert_eq_test!(fill_pixels(&mut buf, 4, 3, 16, 4, 0x0102_0304), 2);
+    assert_test!(
+        buf[32..].iter().all(|&b| b == GUARD),
+        "partial row untouched"
+    );
+
+    assert_eq_test!(fill_pixels(&mut buf, 4, 3, 0, 4, 0), 0, "zero pitch");
+    assert_eq_test!(fill_pixels(&mut buf, 4, 3, 16, 1, 0), 0, "bad depth");
+    TestResult::Pass
+}
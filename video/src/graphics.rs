@@ -196,6 +196,19 @@ pub fn fill_rect(ctx: &mut GraphicsContext, x: i32, y: i32, w: i32, h: i32, colo
     draw_primitives::fill_rect(ctx, x, y, w, h, color);
 }
 
+#[inline]
+pub fn fill_gradient(
+    ctx: &mut GraphicsContext,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+    top: u32,
+    bottom: u32,
+) {
+    draw_primitives::fill_gradient(ctx, x, y, w, h, top, bottom);
+}
+
 #[inline]
 pub fn draw_rect(ctx: &mut GraphicsContext, x: i32, y: i32, w: i32, h: i32, color: u32) {
     draw_primitives::rect(ctx, x, y, w, h, color);
@@ -206,6 +219,29 @@ pub fn draw_line(ctx: &mut GraphicsContext, x0: i32, y0: i32, x1: i32, y1: i32,
     draw_primitives::line(ctx, x0, y0, x1, y1, color);
 }
 
+#[inline]
+pub fn draw_quad_bezier(
+    ctx: &mut GraphicsContext,
+    p0: (i32, i32),
+    p1: (i32, i32),
+    p2: (i32, i32),
+    color: u32,
+) {
+    draw_primitives::quad_bezier(ctx, p0, p1, p2, color);
+}
+
+#[inline]
+pub fn draw_cubic_bezier(
+    ctx: &mut GraphicsContext,
+    p0: (i32, i32),
+    p1: (i32, i32),
+    p2: (i32, i32),
+    p3: (i32, i32),
+    color: u32,
+) {
+    draw_primitives::cubic_bezier(ctx, p0, p1, p2, p3, color);
+}
+
 #[inline]
 pub fn draw_circle(ctx: &mut GraphicsContext, cx: i32, cy: i32, radius: i32, color: u32) {
     draw_primitives::circle(ctx, cx, cy, radius, color);
@@ -3,22 +3,25 @@
 
 extern crate alloc;
 
-use core::ffi::c_int;
 use slopos_abi::CompositorError;
 use slopos_abi::FramebufferData;
-use slopos_abi::addr::PhysAddr;
 use slopos_abi::video_traits::VideoResult;
 use slopos_core::syscall_services::{VideoServices, register_video_services};
 use slopos_core::task::register_video_cleanup_hook;
+use slopos_drivers::tty::{self, TtyConsole};
 use slopos_drivers::xe;
 use slopos_lib::{klog_info, klog_warn};
+use slopos_mm::shared_memory::register_shm_release_hook;
 
 pub mod compositor_context;
+pub mod compositor_tests;
 pub mod font;
 pub mod framebuffer;
+pub mod framebuffer_tests;
 pub mod graphics;
 pub mod panic_screen;
 pub mod roulette_core;
+pub mod roulette_tests;
 pub mod splash;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -27,10 +30,6 @@ pub enum VideoBackend {
     Xe,
 }
 
-fn video_fb_flip(shm_phys: PhysAddr, size: usize) -> c_int {
-    framebuffer::fb_flip_from_shm(shm_phys, size)
-}
-
 fn video_roulette_draw(fate: u32) -> VideoResult {
     roulette_core::roulette_draw_kernel(fate)
 }
@@ -44,15 +43,8 @@ fn video_surface_set_title(
         return Err(CompositorError::InvalidArgument);
     }
 
-    let ptr_addr = title_ptr as u64;
-    let len = title_len.min(31);
-    let end_addr = ptr_addr.saturating_add(len as u64);
-    use slopos_mm::mm_constants::USER_SPACE_END_VA;
-    if ptr_addr >= USER_SPACE_END_VA || end_addr > USER_SPACE_END_VA {
-        return Err(CompositorError::InvalidArgument);
-    }
-
-    let title = unsafe { core::slice::from_raw_parts(title_ptr, len) };
+    // Callers pass kernel memory; the syscall copies the title in first
+    let title = unsafe { core::slice::from_raw_parts(title_ptr, title_len) };
     compositor_context::surface_set_title(task_id, title)
 }
 
@@ -60,22 +52,36 @@ static VIDEO_SERVICES: VideoServices = VideoServices {
     get_display_info: framebuffer::get_display_info,
     roulette_draw: video_roulette_draw,
     surface_enumerate_windows: compositor_context::surface_enumerate_windows,
+    surface_list_windows: compositor_context::surface_list_windows,
     surface_set_window_position: compositor_context::surface_set_window_position,
     surface_set_window_state: compositor_context::surface_set_window_state,
     surface_raise_window: compositor_context::surface_raise_window,
     surface_commit: compositor_context::surface_commit,
+    surface_commit_swap: compositor_context::surface_commit_swap,
+    surface_begin_frame: compositor_context::surface_begin_frame,
+    surface_end_frame: compositor_context::surface_end_frame,
+    surface_generate_thumbnail: compositor_context::surface_generate_thumbnail,
+    surface_attach_back_buffer: compositor_context::surface_attach_back_buffer,
+    surface_attach_spare_buffer: compositor_context::surface_attach_spare_buffer,
+    surface_detach_spare_buffer: compositor_context::surface_detach_spare_buffer,
+    surface_back_buffer: compositor_context::surface_back_buffer,
+    surface_resize: compositor_context::surface_resize,
     register_surface: compositor_context::register_surface_for_task,
     drain_queue: compositor_context::drain_queue,
-    fb_flip: video_fb_flip,
+    fb_flip: compositor_context::compositor_present,
+    compositor_capture_rows: compositor_context::compositor_capture_rows,
     surface_request_frame_callback: compositor_context::surface_request_frame_callback,
     surface_mark_frames_done: compositor_context::surface_mark_frames_done,
     surface_poll_frame_done: compositor_context::surface_poll_frame_done,
+    surface_frame_done: compositor_context::surface_frame_done,
     surface_add_damage: compositor_context::surface_add_damage,
     surface_get_buffer_age: compositor_context::surface_get_buffer_age,
     surface_set_role: compositor_context::surface_set_role,
     surface_set_parent: compositor_context::surface_set_parent,
     surface_set_relative_position: compositor_context::surface_set_relative_position,
     surface_set_title: video_surface_set_title,
+    surface_set_color_key: compositor_context::surface_set_color_key,
+    surface_set_blend_mode: compositor_context::surface_set_blend_mode,
 };
 
 fn task_cleanup_callback(task_id: u32) {
@@ -88,9 +94,11 @@ fn task_cleanup_callback(task_id: u32) {
 
 pub fn init(framebuffer: Option<FramebufferData>, backend: VideoBackend) {
     register_video_cleanup_hook(task_cleanup_callback);
+    register_shm_release_hook(framebuffer::framebuffer_release_scanout);
 
     if backend == VideoBackend::Xe {
         framebuffer::register_flush_callback(xe::xe_flush);
+        framebuffer::register_scanout_callback(xe::xe_scanout);
     }
 
     let fb_to_use = framebuffer;
@@ -105,10 +113,14 @@ pub fn init(framebuffer: Option<FramebufferData>, backend: VideoBackend) {
         );
 
         if framebuffer::init_with_display_info(fb.address, &fb.info) != 0 {
-            klog_warn!("Framebuffer init failed; skipping banner paint.");
+            klog_warn!("Framebuffer init failed; skipping banner paint, console on serial.");
+            tty::tty_set_console(TtyConsole::Serial);
             return;
         }
 
+        // Don't show whatever the firmware left behind while the splash loads
+        framebuffer::framebuffer_clear(0);
+
         register_video_services(&VIDEO_SERVICES);
 
         if let Err(err) = splash::splash_show_boot_screen() {
@@ -120,7 +132,8 @@ pub fn init(framebuffer: Option<FramebufferData>, backend: VideoBackend) {
         }
         framebuffer::framebuffer_flush();
     } else {
-        klog_warn!("No framebuffer provided; skipping video init.");
+        klog_warn!("No framebuffer provided; skipping video init, console on serial.");
+        tty::tty_set_console(TtyConsole::Serial);
     }
 }
 
@@ -6,13 +6,63 @@
 
 use core::ffi::c_char;
 
-use crate::graphics::GraphicsContext;
+use slopos_drivers::tty;
+use slopos_lib::IrqMutex;
+
+use crate::graphics::{self, GraphicsContext};
 use crate::{font, framebuffer};
 
 // Colors (ARGB format)
 const PANIC_BG_COLOR: u32 = 0xFF8B0000; // Dark red
 const PANIC_FG_COLOR: u32 = 0xFFFFFFFF; // White
 const PANIC_HEADER_COLOR: u32 = 0xFFFF4444; // Bright red for header
+const PANIC_LOG_COLOR: u32 = 0xFFDDDDDD; // Light gray for console lines
+
+/// Screen area the console scrollback is drawn into, in characters.
+#[derive(Clone, Copy)]
+struct LogPane {
+    x: i32,
+    y: i32,
+    cols: usize,
+    rows: usize,
+}
+
+static LOG_PANE: IrqMutex<Option<LogPane>> = IrqMutex::new(None);
+
+/// Draw the scrollback viewport into `pane`, clearing what was there.
+fn draw_log_pane(pane: &LogPane) {
+    let Ok(mut ctx) = GraphicsContext::new() else {
+        return;
+    };
+    let char_width = font::FONT_CHAR_WIDTH;
+    let char_height = font::FONT_CHAR_HEIGHT;
+    graphics::fill_rect(
+        &mut ctx,
+        pane.x,
+        pane.y,
+        pane.cols as i32 * char_width,
+        pane.rows as i32 * char_height,
+        PANIC_BG_COLOR,
+    );
+    tty::tty_render_view(&mut |row, text| {
+        let y = pane.y + row as i32 * char_height;
+        for (col, &byte) in text.iter().take(pane.cols).enumerate() {
+            let x = pane.x + col as i32 * char_width;
+            font::font_draw_char_ctx(&ctx, x, y, byte as c_char, PANIC_LOG_COLOR, PANIC_BG_COLOR);
+        }
+    });
+}
+
+/// Page the console pane of the panic screen; positive pages go back into
+/// older output. Does nothing before `display_panic_screen` has drawn it.
+pub fn panic_screen_scroll_log(pages: i32) {
+    let Some(pane) = *LOG_PANE.lock() else {
+        return;
+    };
+    tty::tty_scroll(pages.saturating_mul(pane.rows as i32));
+    draw_log_pane(&pane);
+    framebuffer::framebuffer_flush();
+}
 
 /// Format a u64 value as a hex string into the provided buffer.
 /// Returns a slice to the formatted string (null-terminated).
@@ -196,6 +246,32 @@ pub fn display_panic_screen(
     y += char_height + 4;
 
     draw_register_line(&ctx, 60, y, b"CR4: \0", cr4);
+    y += char_height * 2;
+
+    // Draw the tail of the console output, scrollable with PgUp/PgDn
+    let log_header = b"Console (PgUp/PgDn to scroll):\0";
+    font::font_draw_string_ctx(
+        &ctx,
+        40,
+        y,
+        log_header.as_ptr() as *const c_char,
+        PANIC_HEADER_COLOR,
+        PANIC_BG_COLOR,
+    );
+    y += char_height + 8;
+    let rows = (height - 80 - y) / char_height;
+    let cols = (width - 100) / char_width;
+    if rows > 0 && cols > 0 {
+        let pane = LogPane {
+            x: 60,
+            y,
+            cols: cols as usize,
+            rows: rows as usize,
+        };
+        tty::tty_set_console_geometry(pane.cols, pane.rows);
+        draw_log_pane(&pane);
+        *LOG_PANE.lock() = Some(pane);
+    }
 
     // Draw prompt at bottom
     let prompt = b"Press ENTER to shutdown\0";
@@ -657,6 +657,95 @@ fn choose_segment_for_parity(fate_number: u32, need_colored: bool) -> i32 {
     start
 }
 
+/// Ease-out curve `p * (2 - p)` in Q16: full speed at the start, zero
+/// velocity at the end.
+pub fn ease_out_q16(p_q16: u32) -> u32 {
+    let p = p_q16.min(1 << 16) as u64;
+    ((p * (131072 - p)) >> 16) as u32
+}
+
+/// Pointer angle `elapsed_ms` into a spin of `total_rotation` degrees.
+pub fn spin_angle(start_angle: i32, total_rotation: i32, elapsed_ms: u32, duration_ms: u32) -> i32 {
+    if elapsed_ms >= duration_ms {
+        return start_angle + total_rotation;
+    }
+    let p_q16 = (((elapsed_ms as u64) << 16) / duration_ms as u64) as u32;
+    start_angle + ((total_rotation as i64 * ease_out_q16(p_q16) as i64) >> 16) as i32
+}
+
+/// Segment the pointer at `angle_deg` rests on.
+pub fn segment_at_angle(angle_deg: i32) -> i32 {
+    normalize_angle(angle_deg) / ROULETTE_SEGMENT_DEGREES
+}
+
+/// One frame of the spin animation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RouletteFrame {
+    pub pointer_angle: i32,
+    /// Segment under the pointer
+    pub segment: i32,
+    /// Final frame: the pointer rests on the target segment
+    pub done: bool,
+}
+
+/// Spin animation: the pointer starts on a segment away from the outcome,
+/// loops the wheel a few times and decelerates onto the chosen segment.
+pub struct RouletteAnim {
+    start_angle: i32,
+    total_rotation: i32,
+    target_segment: i32,
+    duration_ms: u32,
+    elapsed_ms: u32,
+}
+
+impl RouletteAnim {
+    pub fn new(fate_number: u32, duration_ms: u32) -> Self {
+        let want_colored = (fate_number & 1) != 0;
+        let mut start_segment = (fate_number % ROULETTE_SEGMENT_COUNT as u32) as i32;
+        let target_segment = choose_segment_for_parity(fate_number, want_colored);
+        if start_segment == target_segment {
+            start_segment = (start_segment + 3) % ROULETTE_SEGMENT_COUNT;
+        }
+
+        let start_angle = segment_center_angle(start_segment);
+        let rotation_to_target =
+            normalize_angle(segment_center_angle(target_segment) - start_angle);
+        Self {
+            start_angle,
+            total_rotation: ROULETTE_SPIN_LOOPS * ROULETTE_DEGREE_STEPS + rotation_to_target,
+            target_segment,
+            duration_ms,
+            elapsed_ms: 0,
+        }
+    }
+
+    #[inline]
+    pub fn start_angle(&self) -> i32 {
+        self.start_angle
+    }
+
+    #[inline]
+    pub fn target_segment(&self) -> i32 {
+        self.target_segment
+    }
+
+    /// Advance by `dt_ms`; steps past the end keep returning the final frame.
+    pub fn step(&mut self, dt_ms: u32) -> RouletteFrame {
+        self.elapsed_ms = self.elapsed_ms.saturating_add(dt_ms).min(self.duration_ms);
+        let pointer_angle = spin_angle(
+            self.start_angle,
+            self.total_rotation,
+            self.elapsed_ms,
+            self.duration_ms,
+        );
+        RouletteFrame {
+            pointer_angle,
+            segment: segment_at_angle(pointer_angle),
+            done: self.elapsed_ms >= self.duration_ms,
+        }
+    }
+}
+
 fn text_width_px(text: &[u8]) -> i32 {
     let mut len = 0usize;
     for &b in text {
@@ -722,12 +811,7 @@ pub fn roulette_run(backend: *const RouletteBackend, fate_number: u32) -> i32 {
         pointer_base_radius,
     };
 
-    let want_colored = (fate_number & 1) != 0;
-    let mut start_segment = (fate_number % ROULETTE_SEGMENT_COUNT as u32) as i32;
-    let target_segment = choose_segment_for_parity(fate_number, want_colored);
-    if start_segment == target_segment {
-        start_segment = (start_segment + 3) % ROULETTE_SEGMENT_COUNT;
-    }
+    let mut anim = RouletteAnim::new(fate_number, ROULETTE_SPIN_DURATION_MS as u32);
 
     unsafe {
         backend_sleep_ms(backend, 300);
@@ -735,14 +819,6 @@ pub fn roulette_run(backend: *const RouletteBackend, fate_number: u32) -> i32 {
 
     let center_x = width / 2;
     let center_y = height / 2;
-    let start_angle = segment_center_angle(start_segment);
-    let target_angle = segment_center_angle(target_segment);
-    let rotation_to_target = normalize_angle(target_angle - start_angle);
-    let mut total_rotation = ROULETTE_SPIN_LOOPS * ROULETTE_DEGREE_STEPS + rotation_to_target;
-    if total_rotation <= 0 {
-        total_rotation += ROULETTE_DEGREE_STEPS;
-    }
-
     let mut last_pointer_angle = -1;
     let _ = render_wheel_frame(
         backend,
@@ -756,7 +832,7 @@ pub fn roulette_run(backend: *const RouletteBackend, fate_number: u32) -> i32 {
         layout.pointer_tip_radius,
         layout.pointer_base_radius,
         -1,
-        start_angle,
+        anim.start_angle(),
         &mut last_pointer_angle,
         fate_number,
         false,
@@ -764,16 +840,11 @@ pub fn roulette_run(backend: *const RouletteBackend, fate_number: u32) -> i32 {
         true,
     );
 
-    let mut total_frames = ROULETTE_SPIN_DURATION_MS / ROULETTE_SPIN_FRAME_DELAY_MS;
-    if total_frames < 1 {
-        total_frames = 1;
-    }
-
-    for frame in 1..=total_frames {
-        let p_q16 = ((frame as u32) << 16) / (total_frames as u32);
-        let eased_q16 = (((p_q16 as u64) * (131072u64 - p_q16 as u64)) >> 16) as u32; // p * (2 - p)
-        let pointer_angle_frame =
-            start_angle + ((total_rotation as i64 * eased_q16 as i64) >> 16) as i32;
+    let final_frame = loop {
+        let frame = anim.step(ROULETTE_SPIN_FRAME_DELAY_MS as u32);
+        if frame.done {
+            break frame;
+        }
         let _ = render_wheel_frame(
             backend,
             width,
@@ -786,7 +857,7 @@ pub fn roulette_run(backend: *const RouletteBackend, fate_number: u32) -> i32 {
             layout.pointer_tip_radius,
             layout.pointer_base_radius,
             -1,
-            pointer_angle_frame,
+            frame.pointer_angle,
             &mut last_pointer_angle,
             fate_number,
             false,
@@ -796,10 +867,10 @@ pub fn roulette_run(backend: *const RouletteBackend, fate_number: u32) -> i32 {
         unsafe {
             backend_sleep_ms(backend, ROULETTE_SPIN_FRAME_DELAY_MS as u32);
         }
-    }
+    };
 
-    let pointer_angle = start_angle + total_rotation;
-    let landing_segment = target_segment;
+    let pointer_angle = final_frame.pointer_angle;
+    let landing_segment = final_frame.segment;
     let _ = render_wheel_frame(
         backend,
         width,
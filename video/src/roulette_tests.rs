@@ -0,0 +1,102 @@
+//! Roulette tests - spin animation easing and landing.
+
+use slopos_lib::testing::TestResult;
+use slopos_lib::{assert_eq_test, assert_test};
+
+use crate::roulette_core::{RouletteAnim, ease_out_q16, segment_at_angle, spin_angle};
+
+const DURATION_MS: u32 = 2000;
+const STEP_MS: u32 = 100;
+
+pub fn test_roulette_ease_curve() -> TestResult {
+    assert_eq_test!(ease_out_q16(0), 0, "ease starts at 0");
+    assert_eq_test!(ease_out_q16(1 << 16), 1 << 16, "ease ends at 1");
+    assert_eq_test!(ease_out_q16(u32::MAX), 1 << 16, "ease clamps past 1");
+    assert_test!(ease_out_q16(1 << 15) > 1 << 15, "ease is ahead of linear");
+
+    assert_eq_test!(
+        spin_angle(30, 720, 0, 1000),
+        30,
+        "spin starts at start angle"
+    );
+    assert_eq_test!(
+        spin_angle(30, 720, 1000, 1000),
+        750,
+        "spin ends after full rotation"
+    );
+    assert_eq_test!(
+        spin_angle(30, 720, 5, 0),
+        750,
+        "zero duration jumps to the end"
+    );
+    TestResult::Pass
+}
+
+pub fn test_roulette_anim_decelerates() -> TestResult {
+    const STEPS: usize = (DURATION_MS / STEP_MS) as usize;
+    let mut anim = RouletteAnim::new(7, DURATION_MS);
+    let mut deltas = [0i32; STEPS];
+    let mut prev = anim.start_angle();
+    for delta in deltas.iter_mut() {
+        let angle = anim.step(STEP_MS).pointer_angle;
+        *delta = angle - prev;
+        prev = angle;
+    }
+
+    assert_test!(deltas[0] > 0, "wheel moves on the first frame");
+    assert_test!(
+        deltas.windows(2).all(|w| w[1] <= w[0] && w[1] >= 0),
+        "pointer only slows down and never reverses"
+    );
+    assert_test!(
+        deltas[0] > deltas[STEPS - 1] * 4,
+        "spin is much slower at the end than at the start"
+    );
+    TestResult::Pass
+}
+
+pub fn test_roulette_anim_lands_on_target() -> TestResult {
+    for fate in [0u32, 1, 2, 5, 11, 12, 0xDEAD_BEEF] {
+        let mut anim = RouletteAnim::new(fate, DURATION_MS);
+        let first = anim.step(0);
+        assert_test!(!first.done, "zero step does not finish");
+        assert_test!(
+            first.segment != anim.target_segment(),
+            "spin does not start on the outcome"
+        );
+
+        let mut frame = first;
+        for _ in 0..(DURATION_MS / STEP_MS) {
+            frame = anim.step(STEP_MS);
+        }
+        assert_test!(frame.done, "animation finished after its duration");
+        assert_eq_test!(
+            frame.segment,
+            anim.target_segment(),
+            "pointer lands on chosen segment"
+        );
+        assert_eq_test!(
+            segment_at_angle(frame.pointer_angle),
+            anim.target_segment(),
+            "final angle is inside the target segment"
+        );
+    }
+    TestResult::Pass
+}
+
+pub fn test_roulette_anim_clamps_past_end() -> TestResult {
+    let mut anim = RouletteAnim::new(4, DURATION_MS);
+    let end = anim.step(DURATION_MS + 500);
+    assert_test!(end.done, "overshooting step finishes");
+    assert_eq_test!(
+        anim.step(STEP_MS),
+        end,
+        "later steps repeat the final frame"
+    );
+    assert_eq_test!(
+        anim.step(u32::MAX),
+        end,
+        "huge step repeats the final frame"
+    );
+    TestResult::Pass
+}